@@ -3,6 +3,8 @@ use criterion::criterion_main;
 mod field;
 mod hash;
 mod fft;
+mod fri;
+mod merkle;
 mod polynom;
 
-criterion_main!(field::group, hash::group, fft::group, polynom::group);
\ No newline at end of file
+criterion_main!(field::group, hash::group, fft::group, fri::group, merkle::group, polynom::group);
\ No newline at end of file
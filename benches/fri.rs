@@ -0,0 +1,18 @@
+use criterion::{ black_box, criterion_group, Criterion };
+use distaff::{ math::field, testing, ProofOptions };
+
+pub fn commit_layers(c: &mut Criterion) {
+    let size: usize = 1 << 24;
+    let options = ProofOptions::default();
+    let evaluations = field::rand_vector(size);
+    let domain = field::get_power_series(field::get_root_of_unity(size), size);
+
+    let mut group = c.benchmark_group("FRI");
+    group.sample_size(10);
+    group.bench_function("Commit phase (2^24 points)", |bench| {
+        bench.iter(|| testing::commit_fri_layers(black_box(&evaluations), black_box(&domain), black_box(&options)))
+    });
+    group.finish();
+}
+
+criterion_group!(group, commit_layers);
@@ -1,5 +1,5 @@
 use criterion::{ black_box, criterion_group, Criterion };
-use distaff::crypto::hash;
+use distaff::{ crypto::hash, utils::hasher };
 
 pub fn poseidon(c: &mut Criterion) {
     let v: [u8; 64] = [
@@ -66,4 +66,11 @@ pub fn sha3(c: &mut Criterion) {
     });
 }
 
-criterion_group!(group, poseidon, rescue, gmimc, blake3, sha3);
+pub fn rescue_round(c: &mut Criterion) {
+    let mut state: [u128; 6] = [1, 2, 3, 4, 5, 6];
+    c.bench_function("Rescue round", |bench| {
+        bench.iter(|| hasher::apply_round(black_box(&mut state), black_box(0)))
+    });
+}
+
+criterion_group!(group, poseidon, rescue, rescue_round, gmimc, blake3, sha3);
@@ -0,0 +1,21 @@
+use criterion::{ black_box, criterion_group, Criterion };
+use distaff::{ math::field, crypto::{ hash, hash_columns } };
+
+pub fn hash_leaves(c: &mut Criterion) {
+    let size: usize = 1 << 22;
+    let columns = (0..4).map(|_| field::rand_vector(size)).collect::<Vec<Vec<u128>>>();
+    let columns = columns.iter().map(|column| &column[..]).collect::<Vec<&[u128]>>();
+    let available = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+
+    let mut group = c.benchmark_group("Merkle leaves");
+    group.sample_size(10);
+    group.bench_function("Hash 4 columns (2^22 rows, 1 thread)", |bench| {
+        bench.iter(|| hash_columns(black_box(&columns), hash::blake3, 1))
+    });
+    group.bench_function(&format!("Hash 4 columns (2^22 rows, {} threads)", available), |bench| {
+        bench.iter(|| hash_columns(black_box(&columns), hash::blake3, available))
+    });
+    group.finish();
+}
+
+criterion_group!(group, hash_leaves);
@@ -0,0 +1,177 @@
+use std::time::{ Duration, Instant };
+use serde::Serialize;
+use crate::{
+    Program, ProgramInputs, ProofOptions, assembly, builders, execute_with_stats, verify,
+    math::field, utils::hasher,
+};
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// A program together with the inputs on which it is benchmarked and the outputs it is expected
+/// to produce. Workloads are generated deterministically, and so, the same workload can be used
+/// to compare performance of different versions of the VM.
+pub struct Workload {
+    pub name            : String,
+    pub program         : Program,
+    pub inputs          : ProgramInputs,
+    pub num_outputs     : usize,
+    pub expected_outputs: Vec<u128>,
+}
+
+/// Performance metrics of proving and verifying a single workload.
+#[derive(Clone, Debug, Serialize)]
+pub struct Metrics {
+    pub name            : String,
+    pub trace_length    : usize,    // length of the execution trace, in steps
+    pub proving_time    : Duration, // time to execute the program and to generate the proof
+    pub verifying_time  : Duration, // time to verify the proof
+    pub proof_size      : usize,    // size of the serialized proof, in bytes
+    pub prover_memory   : usize,    // peak memory used by the prover, in bytes
+}
+
+// WORKLOADS
+// ================================================================================================
+impl Workload {
+
+    /// Returns a workload which computes the 2^`log_length`-th term of Fibonacci sequence.
+    pub fn fibonacci(log_length: u32) -> Workload {
+        assert!(log_length >= 2, "log of sequence length must be at least 2, but was {}", log_length);
+        let length = 1usize << log_length;
+        let (program, inputs) = builders::fibonacci(length);
+
+        let mut n1 = field::ZERO;
+        let mut n2 = field::ONE;
+        for _ in 0..(length - 1) {
+            let n3 = field::add(n1, n2);
+            n1 = n2;
+            n2 = n3;
+        }
+
+        return Workload {
+            name            : format!("fibonacci_2^{}", log_length),
+            program         : program,
+            inputs          : inputs,
+            num_outputs     : 1,
+            expected_outputs: vec![n2],
+        };
+    }
+
+    /// Returns a workload which verifies a pseudo-random Merkle authentication path for a tree
+    /// of the specified `depth`; the nodes and the index bits of the path are read from secret
+    /// input tapes, and the root of the tree is the output.
+    pub fn merkle_path(depth: usize) -> Workload {
+        assert!(depth >= 2, "tree depth must be at least 2, but was {}", depth);
+        let source = format!("begin read.ab smpath.{} end", depth);
+
+        let mut s1 = [0u8; 32];
+        s1[0] = 1; s1[1] = depth as u8;
+        let mut s2 = [0u8; 32];
+        s2[0] = 2; s2[1] = depth as u8;
+        let path = [field::prng_vector(s1, depth), field::prng_vector(s2, depth)];
+        let index = (field::prng(s1) % (1u128 << (depth - 1))) as usize;
+
+        // the leaf is followed by pairs of values on tapes A and B: the first pair contains the
+        // next bit of the position index, and the second pair contains the next node
+        let mut a = vec![path[0][0]];
+        let mut b = vec![path[1][0]];
+        let mut position = index + (1 << (depth - 1));
+        for i in 1..depth {
+            a.push(field::ZERO);
+            b.push((position & 1) as u128);
+            position >>= 1;
+            a.push(path[0][i]);
+            b.push(path[1][i]);
+        }
+
+        // compute the root of the tree
+        let mut node = vec![path[0][0], path[1][0]];
+        let mut position = index + (1 << (depth - 1));
+        for i in 1..depth {
+            node = if position & 1 == 0 {
+                hasher::digest(&[node[0], node[1], path[0][i], path[1][i]])
+            }
+            else {
+                hasher::digest(&[path[0][i], path[1][i], node[0], node[1]])
+            };
+            position >>= 1;
+        }
+
+        return Workload {
+            name            : format!("merkle_path_{}", depth),
+            program         : assembly::compile(&source).unwrap(),
+            inputs          : ProgramInputs::new(&[], &a, &b),
+            num_outputs     : 2,
+            expected_outputs: vec![node[1], node[0]],
+        };
+    }
+
+    /// Returns a workload which hashes a pair of values `length` times, each time hashing the
+    /// result of the previous hash.
+    pub fn hash_chain(length: usize) -> Workload {
+        let (program, inputs) = builders::hash_chain(length);
+
+        // values are hashed in the reverse of their order on the stack, and the digest is put
+        // onto the stack in reverse order as well
+        let mut value = vec![field::ONE, field::ZERO];
+        for _ in 0..length {
+            value = hasher::digest(&[value[1], value[0]]);
+            value.reverse();
+        }
+
+        return Workload {
+            name            : format!("hash_chain_{}", length),
+            program         : program,
+            inputs          : inputs,
+            num_outputs     : 2,
+            expected_outputs: value,
+        };
+    }
+}
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
+/// Returns the standard set of workloads: Fibonacci sequences of 2^8 and 2^10 terms, Merkle
+/// paths for trees of depth 16 and 32, and hash chains of 64 and 256 hashes.
+pub fn standard_workloads() -> Vec<Workload> {
+    return vec![
+        Workload::fibonacci(8),
+        Workload::fibonacci(10),
+        Workload::merkle_path(16),
+        Workload::merkle_path(32),
+        Workload::hash_chain(64),
+        Workload::hash_chain(256),
+    ];
+}
+
+/// Proves and verifies the `workload` with the specified `options`, and returns the collected
+/// metrics. Panics if the workload does not produce the expected outputs, or if the proof
+/// cannot be verified.
+pub fn measure(workload: &Workload, options: &ProofOptions) -> Metrics {
+    let now = Instant::now();
+    let (outputs, proof, stats) = execute_with_stats(&workload.program, &workload.inputs, workload.num_outputs, options);
+    let proving_time = now.elapsed();
+    assert!(outputs == workload.expected_outputs,
+        "workload {} produced outputs {:?}, but {:?} were expected", workload.name, outputs, workload.expected_outputs);
+
+    let public_inputs = workload.inputs.get_public_inputs();
+    let now = Instant::now();
+    let result = verify(workload.program.hash(), public_inputs, &outputs, &proof);
+    let verifying_time = now.elapsed();
+    assert!(result == Ok(true), "proof of workload {} failed to verify: {:?}", workload.name, result);
+
+    return Metrics {
+        name            : workload.name.clone(),
+        trace_length    : proof.trace_length(),
+        proving_time    : proving_time,
+        verifying_time  : verifying_time,
+        proof_size      : proof.to_bytes().len(),
+        prover_memory   : stats.peak_memory,
+    };
+}
+
+/// Measures all workloads returned by `standard_workloads()` with the specified `options`.
+pub fn measure_all(options: &ProofOptions) -> Vec<Metrics> {
+    return standard_workloads().iter().map(|workload| measure(workload, options)).collect();
+}
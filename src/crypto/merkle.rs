@@ -1,7 +1,9 @@
 use std::slice;
 use std::collections::{ HashMap, BTreeSet };
 use serde::{ Serialize, Deserialize };
+use crossbeam_utils::thread;
 use crate::crypto::{ HashFunction };
+use crate::utils::{ uninit_vector, as_bytes };
 
 // TYPES AND INTERFACES
 // ================================================================================================
@@ -14,6 +16,7 @@ pub struct MerkleTree {
 pub struct BatchMerkleProof {
     pub values  : Vec<[u8; 32]>,
     pub nodes   : Vec<Vec<[u8; 32]>>,
+    pub cap     : Vec<[u8; 32]>,    // empty when paths go all the way up to the root
     pub depth   : u8
 }
 
@@ -33,11 +36,26 @@ impl MerkleTree {
         };
     }
 
+    /// Returns the number of bytes occupied by the nodes and the leaves of the tree.
+    pub fn memory_size(&self) -> usize {
+        return (self.nodes.len() + self.values.len()) * 32;
+    }
+
     /// Returns the root of the tree
     pub fn root(&self) -> &[u8; 32] {
         return &self.nodes[1];
     }
 
+    /// Returns the cap of the tree at the specified `height`: the 2^`height` nodes which are
+    /// `height` levels below the root, ordered from left to right; the cap at height 0 is the
+    /// root itself.
+    pub fn cap(&self, height: u32) -> &[[u8; 32]] {
+        let depth = self.values.len().trailing_zeros();
+        assert!(height < depth, "cap height must be smaller than {}, but was {}", depth, height);
+        let size = 1 << height;
+        return &self.nodes[size..(2 * size)];
+    }
+
     /// Returns leaf nodes of the tree
     pub fn leaves(&self) -> &[[u8; 32]] {
         return &self.values;
@@ -62,7 +80,17 @@ impl MerkleTree {
 
     /// Computes merkle paths for the provided indexes and compresses the paths into a single proof.
     pub fn prove_batch(&self, indexes: &[usize]) -> BatchMerkleProof {
+        return self.prove_batch_with_cap(indexes, 0);
+    }
+
+    /// Computes merkle paths for the provided indexes up to the cap of the tree at `cap_height`,
+    /// and compresses the paths into a single proof; the cap is included into the proof, and so,
+    /// every path is shorter by `cap_height` nodes at the cost of 2^`cap_height` cap nodes. If the
+    /// tree is not tall enough, the cap is made up of the parents of the leaves.
+    pub fn prove_batch_with_cap(&self, indexes: &[usize], cap_height: u32) -> BatchMerkleProof {
         let n = self.values.len();
+        let depth = n.trailing_zeros() as u8;
+        let cap_height = get_cap_height(depth, cap_height);
 
         let index_map = map_indexes(indexes, n);
         let indexes = normalize_indexes(indexes);
@@ -98,8 +126,7 @@ impl MerkleTree {
         }
 
         // add required internal nodes to the proof, skipping redundancies
-        let depth = self.values.len().trailing_zeros() as u8;
-        for _ in 1..depth {
+        for _ in 1..(depth - cap_height as u8) {
             let indexes = next_indexes.clone();
             next_indexes.truncate(0);
 
@@ -120,7 +147,8 @@ impl MerkleTree {
             }
         }
 
-        return BatchMerkleProof { values, nodes, depth };
+        let cap = if cap_height == 0 { Vec::new() } else { self.cap(cap_height).to_vec() };
+        return BatchMerkleProof { values, nodes, cap, depth };
     }
 
     /// Checks whether the path for the specified index is valid.
@@ -151,10 +179,34 @@ impl MerkleTree {
     }
 
     /// Checks whether the batch proof contains merkle paths for the of the specified indexes.
+    /// Indexes must be distinct and sorted in ascending order, and the proof must contain
+    /// exactly one value for every index and no unused authentication nodes; thus, a set of
+    /// indexes has exactly one valid batch proof.
     pub fn verify_batch(root: &[u8; 32], indexes: &[usize], proof: &BatchMerkleProof, hash: HashFunction) -> bool {
+        return MerkleTree::verify_batch_with_cap(root, indexes, proof, 0, hash);
+    }
+
+    /// Checks whether the batch proof contains merkle paths for the specified indexes up to the
+    /// cap at `cap_height`, and whether the cap included into the proof hashes into the root; the
+    /// cap must be of the size `prove_batch_with_cap()` would produce for the same `cap_height`.
+    pub fn verify_batch_with_cap(root: &[u8; 32], indexes: &[usize], proof: &BatchMerkleProof, cap_height: u32, hash: HashFunction) -> bool {
         let mut buf = [0u8; 64];
         let mut v: HashMap<usize, [u8; 32]> = HashMap::new();
 
+        if indexes.windows(2).any(|w| w[0] >= w[1]) { return false; }
+        if indexes.is_empty() || indexes.len() != proof.values.len() { return false; }
+        if proof.depth == 0 || proof.depth as u32 >= usize::BITS { return false; }
+
+        // the cap must be of the expected size and must hash into the root
+        let cap_height = get_cap_height(proof.depth, cap_height);
+        let cap_size = 1usize << cap_height;
+        if cap_height == 0 {
+            if !proof.cap.is_empty() { return false; }
+        }
+        else if proof.cap.len() != cap_size || build_merkle_nodes(&proof.cap, hash)[1] != *root {
+            return false;
+        }
+
         // replace odd indexes, offset, and sort in ascending order
         let offset = usize::pow(2, proof.depth as u32);
         let index_map = map_indexes(indexes, offset - 1);
@@ -206,8 +258,8 @@ impl MerkleTree {
             next_indexes.push(parent_index);
         }
 
-        // iteratively move up, until we get to the root
-        for _ in 1..proof.depth {
+        // iteratively move up, until we get to the cap
+        for _ in 1..(proof.depth - cap_height as u8) {
             let indexes = next_indexes.clone();
             next_indexes.truncate(0);
 
@@ -258,8 +310,17 @@ impl MerkleTree {
                 i += 1;
             }
         }
-     
-        return *root == *v.get(&1).unwrap();
+
+        // all authentication nodes must have been used
+        if proof_pointers.iter().zip(proof.nodes.iter()).any(|(&pointer, path)| pointer != path.len()) {
+            return false;
+        }
+
+        // all computed nodes at the top must match the cap
+        return next_indexes.iter().all(|index| {
+            let expected = if cap_height == 0 { root } else { &proof.cap[index - cap_size] };
+            v.get(index) == Some(expected)
+        });
     }
 }
 
@@ -293,6 +354,37 @@ pub fn build_merkle_nodes(leaves: &[[u8; 32]], hash: HashFunction) -> Vec<[u8; 3
     return nodes;
 }
 
+/// Hashes rows of the specified `columns` into leaves of a Merkle tree: leaf i is a hash of the
+/// values of all columns at row i, taken in the order of the columns. Rows are split into
+/// `num_threads` batches which are hashed in separate threads; the result does not depend on
+/// the number of threads.
+pub fn hash_columns(columns: &[&[u128]], hash: HashFunction, num_threads: usize) -> Vec<[u8; 32]> {
+    assert!(columns.len() > 0, "at least one column must be provided");
+    assert!(num_threads > 0, "number of threads must be greater than 0");
+    let n = columns[0].len();
+    assert!(columns.iter().all(|column| column.len() == n), "all columns must have the same length");
+    if n == 0 { return Vec::new(); }
+    let batch_size = (n + num_threads - 1) / num_threads;
+
+    let mut leaves: Vec<[u8; 32]> = uninit_vector(n);
+    thread::scope(|s| {
+        for (i, batch) in leaves.chunks_mut(batch_size).enumerate() {
+            s.spawn(move |_| {
+                let mut row = vec![0u128; columns.len()];
+                for (j, leaf) in batch.iter_mut().enumerate() {
+                    let step = i * batch_size + j;
+                    for (value, column) in row.iter_mut().zip(columns) {
+                        *value = column[step];
+                    }
+                    hash(as_bytes(&row), leaf);
+                }
+            });
+        }
+    }).unwrap();
+
+    return leaves;
+}
+
 fn map_indexes(indexes: &[usize], max_valid: usize) -> HashMap<usize, usize> {
     let mut map = HashMap::new();
     for (i, index) in indexes.iter().cloned().enumerate() {
@@ -311,12 +403,20 @@ fn normalize_indexes(indexes: &[usize]) -> Vec<usize> {
     return set.into_iter().collect();
 }
 
+/// Returns the height of the cap of a tree of the specified `depth`; caps of trees which are not
+/// tall enough are made up of the parents of the leaves.
+fn get_cap_height(depth: u8, cap_height: u32) -> u32 {
+    return std::cmp::min(cap_height, depth as u32 - 1);
+}
+
 // TESTS
 // ================================================================================================
 #[cfg(test)]
 mod tests {
 
     use crate::crypto::hash;
+    use crate::math::field;
+    use crate::utils::as_bytes;
 
     static LEAVES4: [[u8; 32]; 4] = [
         [166, 168,  47, 140, 153, 86, 156,  86, 226, 229, 149,  76,  70, 132, 209, 109, 166, 193, 113, 197,  42, 116, 170, 144,  74, 104,  29, 110, 220, 49, 224, 123],
@@ -517,6 +617,84 @@ mod tests {
         assert_eq!(true, super::MerkleTree::verify_batch(tree.root(), &[0, 1, 2, 3, 4, 5, 6, 7], &proof, hash::poseidon));
     }
 
+    #[test]
+    fn verify_batch_non_canonical() {
+        let leaves = LEAVES8.to_vec();
+        let tree = super::MerkleTree::new(leaves, hash::poseidon);
+        let proof = tree.prove_batch(&[1, 6]);
+
+        // indexes must be sorted
+        assert_eq!(false, super::MerkleTree::verify_batch(tree.root(), &[6, 1], &proof, hash::poseidon));
+
+        // trailing values are rejected
+        let mut modified = proof.clone();
+        modified.values.push(modified.values[0]);
+        assert_eq!(false, super::MerkleTree::verify_batch(tree.root(), &[1, 6], &modified, hash::poseidon));
+
+        // trailing authentication nodes are rejected
+        let mut modified = proof.clone();
+        let node = modified.nodes[0][0];
+        modified.nodes[0].push(node);
+        assert_eq!(false, super::MerkleTree::verify_batch(tree.root(), &[1, 6], &modified, hash::poseidon));
+    }
+
+    #[test]
+    fn verify_batch_with_cap() {
+        let leaves = LEAVES8.to_vec();
+        let tree = super::MerkleTree::new(leaves, hash::poseidon);
+        let cap = vec![hash_2x1(&LEAVES8[0], &LEAVES8[1]), hash_2x1(&LEAVES8[2], &LEAVES8[3]),
+            hash_2x1(&LEAVES8[4], &LEAVES8[5]), hash_2x1(&LEAVES8[6], &LEAVES8[7])];
+        assert_eq!(&cap[..], tree.cap(2));
+        assert_eq!(&[*tree.root()], tree.cap(0));
+
+        // paths end at the cap, and the cap is included into the proof
+        let proof = tree.prove_batch_with_cap(&[1, 6], 1);
+        assert_eq!(vec![vec![LEAVES8[0], cap[1]], vec![LEAVES8[7], cap[2]]], proof.nodes);
+        assert_eq!(tree.cap(1), &proof.cap[..]);
+        assert_eq!(true, super::MerkleTree::verify_batch_with_cap(tree.root(), &[1, 6], &proof, 1, hash::poseidon));
+
+        for &cap_height in [0, 1, 2].iter() {
+            let proof = tree.prove_batch_with_cap(&[1, 3, 6], cap_height);
+            assert_eq!(true, super::MerkleTree::verify_batch_with_cap(tree.root(), &[1, 3, 6], &proof, cap_height, hash::poseidon));
+        }
+
+        // caps higher than the tree allows are made up of the parents of the leaves
+        let proof = tree.prove_batch_with_cap(&[1, 6], 5);
+        assert_eq!(cap, proof.cap);
+        assert_eq!(true, super::MerkleTree::verify_batch_with_cap(tree.root(), &[1, 6], &proof, 5, hash::poseidon));
+
+        // the cap must be of the expected size and must match the root
+        let proof = tree.prove_batch_with_cap(&[1, 6], 1);
+        assert_eq!(false, super::MerkleTree::verify_batch_with_cap(tree.root(), &[1, 6], &proof, 2, hash::poseidon));
+        assert_eq!(false, super::MerkleTree::verify_batch(tree.root(), &[1, 6], &proof, hash::poseidon));
+
+        let mut modified = proof.clone();
+        modified.cap[0] = modified.cap[1];
+        assert_eq!(false, super::MerkleTree::verify_batch_with_cap(tree.root(), &[1, 6], &modified, 1, hash::poseidon));
+
+        // a cap which matches the root must still match the paths
+        let mut modified = proof.clone();
+        modified.nodes[0][0] = LEAVES8[2];
+        assert_eq!(false, super::MerkleTree::verify_batch_with_cap(tree.root(), &[1, 6], &modified, 1, hash::poseidon));
+    }
+
+    #[test]
+    fn hash_columns() {
+        let columns = [field::rand_vector(1000), field::rand_vector(1000), field::rand_vector(1000)];
+        let columns = columns.iter().map(|column| &column[..]).collect::<Vec<&[u128]>>();
+
+        // every leaf is a hash of a single row, regardless of how rows are split among threads
+        let leaves = super::hash_columns(&columns, hash::blake3, 1);
+        for &i in [0, 1, 999].iter() {
+            let mut expected = [0u8; 32];
+            hash::blake3(as_bytes(&[columns[0][i], columns[1][i], columns[2][i]]), &mut expected);
+            assert_eq!(expected, leaves[i]);
+        }
+        for &num_threads in [2, 3, 8].iter() {
+            assert_eq!(leaves, super::hash_columns(&columns, hash::blake3, num_threads));
+        }
+    }
+
     // HELPER FUNCTIONS
     // --------------------------------------------------------------------------------------------
     fn hash_2x1(v1: &[u8; 32], v2: &[u8; 32]) -> [u8; 32] {
@@ -1,6 +1,9 @@
 pub mod hash;
 
 mod merkle;
-pub use merkle::{ MerkleTree, BatchMerkleProof, build_merkle_nodes };
+pub use merkle::{ MerkleTree, BatchMerkleProof, build_merkle_nodes, hash_columns };
+
+mod strobe;
+pub use strobe::Strobe128;
 
 pub type HashFunction = fn(&[u8], &mut [u8]);
\ No newline at end of file
@@ -0,0 +1,128 @@
+use std::convert::TryInto;
+
+// CONSTANTS
+// ================================================================================================
+const STROBE_R: u8 = 166;
+
+const FLAG_I: u8 = 1;
+const FLAG_A: u8 = 1 << 1;
+const FLAG_C: u8 = 1 << 2;
+const FLAG_T: u8 = 1 << 3;
+const FLAG_M: u8 = 1 << 4;
+const FLAG_K: u8 = 1 << 5;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// A minimal implementation of STROBE-128 protocol framework over Keccak-f[1600] permutation; only
+/// the operations needed for Merlin transcripts (AD, meta-AD, and PRF) are supported. The
+/// implementation follows STROBE v1.0.2 specification.
+#[derive(Clone)]
+pub struct Strobe128 {
+    state       : [u8; 200],
+    pos         : u8,
+    pos_begin   : u8,
+    cur_flags   : u8,
+}
+
+// STROBE IMPLEMENTATION
+// ================================================================================================
+impl Strobe128 {
+
+    pub fn new(protocol_label: &[u8]) -> Strobe128 {
+        let mut state = [0u8; 200];
+        state[0..6].copy_from_slice(&[1, STROBE_R + 2, 1, 0, 1, 96]);
+        state[6..18].copy_from_slice(b"STROBEv1.0.2");
+        keccak_f1600(&mut state);
+
+        let mut strobe = Strobe128 { state, pos: 0, pos_begin: 0, cur_flags: 0 };
+        strobe.meta_ad(protocol_label, false);
+        return strobe;
+    }
+
+    pub fn meta_ad(&mut self, data: &[u8], more: bool) {
+        self.begin_op(FLAG_M | FLAG_A, more);
+        self.absorb(data);
+    }
+
+    pub fn ad(&mut self, data: &[u8], more: bool) {
+        self.begin_op(FLAG_A, more);
+        self.absorb(data);
+    }
+
+    pub fn prf(&mut self, data: &mut [u8], more: bool) {
+        self.begin_op(FLAG_I | FLAG_A | FLAG_C, more);
+        self.squeeze(data);
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+    fn run_f(&mut self) {
+        self.state[self.pos as usize] ^= self.pos_begin;
+        self.state[(self.pos + 1) as usize] ^= 0x04;
+        self.state[(STROBE_R + 1) as usize] ^= 0x80;
+        keccak_f1600(&mut self.state);
+        self.pos = 0;
+        self.pos_begin = 0;
+    }
+
+    fn absorb(&mut self, data: &[u8]) {
+        for &byte in data.iter() {
+            self.state[self.pos as usize] ^= byte;
+            self.advance();
+        }
+    }
+
+    fn squeeze(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            *byte = self.state[self.pos as usize];
+            self.state[self.pos as usize] = 0;
+            self.advance();
+        }
+    }
+
+    fn advance(&mut self) {
+        self.pos += 1;
+        if self.pos == STROBE_R {
+            self.run_f();
+        }
+    }
+
+    fn begin_op(&mut self, flags: u8, more: bool) {
+        // continuation of the previous operation
+        if more {
+            assert!(self.cur_flags == flags,
+                "tried to continue operation {:#b}, but begun operation {:#b}", self.cur_flags, flags);
+            return;
+        }
+
+        // transport operations are not supported
+        assert!(flags & FLAG_T == 0, "transport operations are not supported");
+
+        let old_begin = self.pos_begin;
+        self.pos_begin = self.pos + 1;
+        self.cur_flags = flags;
+        self.absorb(&[old_begin, flags]);
+
+        // cipher operations must start at the beginning of a block
+        let force_f = 0 != (flags & (FLAG_C | FLAG_K));
+        if force_f && self.pos != 0 {
+            self.run_f();
+        }
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Applies Keccak-f[1600] permutation to the state interpreted as 25 little-endian 64-bit lanes.
+fn keccak_f1600(state: &mut [u8; 200]) {
+    let mut lanes = [0u64; 25];
+    for (lane, bytes) in lanes.iter_mut().zip(state.chunks(8)) {
+        *lane = u64::from_le_bytes(bytes.try_into().unwrap());
+    }
+    keccak::f1600(&mut lanes);
+    for (lane, bytes) in lanes.iter().zip(state.chunks_mut(8)) {
+        bytes.copy_from_slice(&lane.to_le_bytes());
+    }
+}
@@ -0,0 +1,132 @@
+use std::convert::TryFrom;
+use std::fmt::Write;
+use std::ops::Range;
+use crate::{
+    testing::{ ExecutionTrace, find_failing_transition },
+    processor::opcodes::{ FlowOps, UserOps },
+    SPONGE_RANGE, CF_OP_BITS_RANGE, LD_OP_BITS_RANGE, HD_OP_BITS_RANGE,
+};
+
+// CONSTANTS
+// ================================================================================================
+
+/// Values with more digits than this are abbreviated in the table; the full value is available as
+/// the title of the cell.
+const MAX_VALUE_DIGITS: usize = 8;
+
+const STYLE: &str = "
+table { border-collapse: collapse; font-family: monospace; font-size: 12px; }
+th, td { border: 1px solid #ccc; padding: 2px 6px; text-align: right; }
+th { background: #eee; }
+td.op { text-align: left; }
+tr.highlighted td { border-top: 2px solid #d00; border-bottom: 2px solid #d00; font-weight: bold; }
+caption { text-align: left; padding: 4px 0; font-family: sans-serif; }
+";
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
+/// Renders the specified `steps` of the `trace` as a standalone HTML page. The page contains a
+/// table with a row for every step and columns for the decoder sponge, op bits, and the user
+/// stack; every row is colored according to the user operation executed at that step, and rows
+/// of `highlighted` steps are outlined.
+pub fn render_html(trace: &ExecutionTrace, steps: Range<usize>, highlighted: &[usize]) -> String {
+    return render_page(trace, steps, highlighted, None);
+}
+
+/// Renders the steps around the first transition of the `trace` which does not satisfy
+/// transition constraints in the same way as `render_html()` does; the two steps of the
+/// transition are highlighted, and `context` steps are rendered before and after them. Returns
+/// None if all transition constraints are satisfied.
+pub fn render_failure_html(trace: &ExecutionTrace, context: usize) -> Option<String> {
+    let (step, constraint) = find_failing_transition(trace)?;
+    let start = step.saturating_sub(context);
+    let end = std::cmp::min(step + 2 + context, trace.length());
+    let mut caption = format!("transition constraint {} is not satisfied between steps {} and {}", constraint, step, step + 1);
+    if let Some(location) = trace.source(step) {
+        caption = format!("{} (instruction at {})", caption, location);
+    }
+    return Some(render_page(trace, start..end, &[step, step + 1], Some(&caption)));
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+fn render_page(trace: &ExecutionTrace, steps: Range<usize>, highlighted: &[usize], caption: Option<&str>) -> String {
+    assert!(steps.start < steps.end && steps.end <= trace.length(),
+        "steps {:?} are outside of the trace of {} steps", steps, trace.length());
+    let stack = trace.user_stack_registers();
+
+    let mut html = String::new();
+    write!(html, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Execution trace</title>\n\
+        <style>{}</style>\n</head>\n<body>\n<table>\n", STYLE).unwrap();
+    if let Some(caption) = caption {
+        write!(html, "<caption>{}</caption>\n", caption).unwrap();
+    }
+
+    // column groups followed by column names
+    write!(html, "<tr><th rowspan=\"2\">step</th><th rowspan=\"2\">op</th><th colspan=\"{}\">sponge</th>\
+        <th colspan=\"{}\">cf bits</th><th colspan=\"{}\">ld bits</th><th colspan=\"{}\">hd bits</th>\
+        <th colspan=\"{}\">stack</th></tr>\n<tr>",
+        SPONGE_RANGE.len(), CF_OP_BITS_RANGE.len(), LD_OP_BITS_RANGE.len(), HD_OP_BITS_RANGE.len(), stack.len()).unwrap();
+    for range in [SPONGE_RANGE, CF_OP_BITS_RANGE, LD_OP_BITS_RANGE, HD_OP_BITS_RANGE].iter() {
+        for i in 0..range.len() {
+            write!(html, "<th>{}</th>", i).unwrap();
+        }
+    }
+    for i in 0..stack.len() {
+        write!(html, "<th>s{}</th>", i).unwrap();
+    }
+    html.push_str("</tr>\n");
+
+    for step in steps {
+        let op_code = trace.op_code(step);
+        let class = if highlighted.contains(&step) { " class=\"highlighted\"" } else { "" };
+        write!(html, "<tr{} style=\"background: {}\"><td>{}</td><td class=\"op\">{}</td>",
+            class, op_color(op_code), step, op_name(trace, step)).unwrap();
+
+        for register in SPONGE_RANGE.chain(CF_OP_BITS_RANGE).chain(LD_OP_BITS_RANGE).chain(HD_OP_BITS_RANGE).chain(stack.clone()) {
+            html.push_str(&render_value(trace.get(register, step)));
+        }
+        html.push_str("</tr>\n");
+    }
+
+    html.push_str("</table>\n</body>\n</html>\n");
+    return html;
+}
+
+/// Returns the name of the flow control operation and of the user operation executed at the
+/// specified step; invalid op codes are rendered as numbers.
+fn op_name(trace: &ExecutionTrace, step: usize) -> String {
+    let mut flow_op_code = 0u8;
+    for (i, register) in CF_OP_BITS_RANGE.enumerate() {
+        flow_op_code |= (trace.get(register, step) as u8) << i;
+    }
+    let flow_op = match FlowOps::try_from(flow_op_code) {
+        Ok(op) => op.to_string(),
+        Err(_) => format!("?{}", flow_op_code),
+    };
+
+    let op_code = trace.op_code(step);
+    let user_op = match UserOps::try_from(op_code) {
+        Ok(op) => op.to_string(),
+        Err(_) => format!("?{}", op_code),
+    };
+
+    return format!("{} / {}", flow_op, user_op);
+}
+
+/// Maps every op code onto a distinct light color; consecutive op codes are far apart on the
+/// color wheel so that related operations are still easy to tell apart.
+fn op_color(op_code: u8) -> String {
+    let hue = (op_code as usize * 137) % 360;
+    return format!("hsl({}, 70%, 90%)", hue);
+}
+
+fn render_value(value: u128) -> String {
+    let digits = value.to_string();
+    if digits.len() <= MAX_VALUE_DIGITS {
+        return format!("<td>{}</td>", digits);
+    }
+    return format!("<td title=\"{}\">{}&hellip;</td>", digits, &digits[..MAX_VALUE_DIGITS - 1]);
+}
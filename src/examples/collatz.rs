@@ -1,4 +1,4 @@
-use distaff::{ math::field, ProgramInputs, assembly };
+use distaff::{ math::field, builders };
 use super::{ Example, utils::parse_args };
 
 pub fn get_example(args: &[String]) -> Example  {
@@ -10,29 +10,14 @@ pub fn get_example(args: &[String]) -> Example  {
     let expected_result: u128 = compute_collatz_steps(value as u128);
     
     // construct the program which executes an unbounded loop to compute a Collatz sequence
-    // which starts with the provided value; the output of the program is the number of steps
-    // needed to reach the end of the sequence
-    let program = assembly::compile("
-    begin
-        pad read dup push.1 ne
-        while.true
-            swap push.1 add swap dup isodd.128
-            if.true
-                push.3 mul push.1 add
-            else
-                push.2 div
-            end
-            dup push.1 ne
-        end
-        swap
-    end").unwrap();
+    // which starts with the provided value; the starting value is the only secret input for
+    // tape A, and the output of the program is the number of steps needed to reach the end
+    // of the sequence
+    let (program, inputs) = builders::collatz(value as u128);
 
     println!("Generated a program to compute Collatz sequence; expected result: {}", 
         expected_result);
 
-    // put the starting value as the only secret input for tape A
-    let inputs = ProgramInputs::new(&[], &[value as u128], &[]);
-
     // a single element from the top of the stack will be the output
     let num_outputs = 1;
 
@@ -8,6 +8,8 @@ pub mod conditional;
 pub mod fibonacci;
 pub mod merkle;
 pub mod range;
+pub mod schnorr;
+pub mod smt;
 
 pub struct Example {
     pub program         : Program,
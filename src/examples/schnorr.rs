@@ -0,0 +1,112 @@
+use distaff::{ Program, ProgramInputs, assembly, math::field, utils::hasher };
+use super::{ Example, utils::parse_args };
+
+// CONSTANTS
+// ================================================================================================
+
+/// Generator of the group in which signatures are computed; the group is the multiplicative group
+/// of the native field. This is not a secure choice for production use: discrete logarithms in
+/// a 128-bit prime field are much easier to compute than in elliptic curve groups of same size.
+const GENERATOR: u128 = 7;
+
+/// Order of the multiplicative group of the field; exponents are reduced by this value.
+const GROUP_ORDER: u128 = field::MODULUS - 1;
+
+// EXAMPLE
+// ================================================================================================
+
+pub fn get_example(args: &[String]) -> Example  {
+
+    // get the message and proof options from the arguments
+    let (message, options) = parse_args(args);
+    let message = message as u128;
+
+    // generate a key pair and sign the message
+    let mut seed = [0u8; 32];
+    seed[0] = 1; seed[1] = 2; seed[2] = 3;
+    let secret_key = field::prng(seed);
+    let public_key = field::exp(GENERATOR, secret_key);
+    let (r, s) = sign(message, secret_key);
+    println!("Signed message {} with public key {}", message, public_key);
+
+    // generate the program to verify the signature
+    let program = generate_verification_program();
+    println!("Generated a program to verify a Schnorr signature");
+
+    // message and public key are public inputs, while the signature is kept secret
+    let inputs = ProgramInputs::new(&[message, public_key], &[r, s], &[]);
+
+    // the program leaves the message and the public key on the stack
+    let num_outputs = 2;
+    let expected_result = vec![message, public_key];
+
+    return Example {
+        program,
+        inputs,
+        options,
+        expected_result,
+        num_outputs
+    };
+}
+
+/// Returns a program which verifies a Schnorr signature (r, s) against a message and a public
+/// key; the program expects the stack to be [message, public key] and secret tape A to contain
+/// [r, s]. The program checks that g^s = r * pk^e, where e = hash(r, message), and fails if this
+/// is not the case.
+fn generate_verification_program() -> Program {
+
+    let source = format!("
+    begin
+        read dup.2 hash.2 drop
+        pick.3 swap exp.128 mul
+        read push.{} swap exp.128
+        assert.eq
+    end", GENERATOR);
+
+    return assembly::compile(&source).unwrap();
+}
+
+// SIGNING
+// ================================================================================================
+
+/// Signs the `message` with the `secret_key` and returns the signature as a tuple (r, s).
+fn sign(message: u128, secret_key: u128) -> (u128, u128) {
+    let mut seed = [0u8; 32];
+    seed[0] = 4; seed[1] = 5; seed[2] = 6;
+    let k = field::prng(seed);
+
+    let r = field::exp(GENERATOR, k);
+    let e = compute_challenge(r, message);
+    let s = add_mod(k, mul_mod(secret_key, e));
+
+    return (r, s);
+}
+
+/// Computes e = hash(r, message) in the same way as the verification program does.
+fn compute_challenge(r: u128, message: u128) -> u128 {
+    return hasher::digest(&[message, r])[0];
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Computes (a + b) % GROUP_ORDER; a and b are assumed to be smaller than GROUP_ORDER.
+fn add_mod(a: u128, b: u128) -> u128 {
+    let (result, overflow) = a.overflowing_add(b);
+    return if overflow || result >= GROUP_ORDER { result.wrapping_sub(GROUP_ORDER) } else { result };
+}
+
+/// Computes (a * b) % GROUP_ORDER using double-and-add to avoid 256-bit arithmetic.
+fn mul_mod(a: u128, b: u128) -> u128 {
+    let mut result = 0;
+    let mut a = a % GROUP_ORDER;
+    let mut b = b;
+    while b > 0 {
+        if b & 1 == 1 {
+            result = add_mod(result, a);
+        }
+        a = add_mod(a, a);
+        b = b >> 1;
+    }
+    return result;
+}
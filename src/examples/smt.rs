@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use distaff::{ Program, ProgramInputs, assembly, math::field, utils::hasher };
+use super::{ Example, utils::parse_args };
+
+pub fn get_example(args: &[String]) -> Example  {
+
+    // get the depth of the tree and proof options from the arguments
+    let (depth, options) = parse_args(args);
+    assert!(depth >= 2 && depth <= 128, "tree depth must be between 2 and 128, but received {}", depth);
+
+    // populate a sparse Merkle tree with a few pseudo-random key-value pairs
+    let mut tree = SparseMerkleTree::new(depth);
+    let keys = generate_keys(depth, 4);
+    for (i, &key) in keys.iter().enumerate() {
+        tree.insert(key, [i as u128 + 1, i as u128 + 2]);
+    }
+
+    // update the value stored under the first key
+    let key = keys[0];
+    let old_value = tree.get(key);
+    let new_value = [field::rand(), field::rand()];
+
+    let old_root = tree.root();
+    let path = tree.get_path(key);
+    tree.insert(key, new_value);
+    let new_root = tree.root();
+    println!("Updated value at key {}; new tree root: {:?}", key, new_root);
+
+    // generate the program to prove the update
+    let program = generate_update_program(depth);
+    println!("Generated a program to update a sparse Merkle tree of depth {}", depth);
+
+    // transform the update into a set of inputs for the program
+    let inputs = generate_program_inputs(key, old_value, new_value, &path);
+
+    // the program leaves the new and the old roots of the tree on the stack
+    let num_outputs = 4;
+    let expected_result = vec![new_root[1], new_root[0], old_root[1], old_root[0]];
+
+    return Example {
+        program,
+        inputs,
+        options,
+        expected_result,
+        num_outputs
+    };
+}
+
+/// Returns a program which updates a leaf in a sparse Merkle tree of depth `n`; the program
+/// expects the stack to be [new value, old value, key], and leaves [new root, old root] on the
+/// stack. The caller is expected to check that the old root matches the current tree root.
+fn generate_update_program(n: usize) -> Program {
+
+    let source = format!("
+    begin
+        mrupdate.{}
+    end", n);
+
+    return assembly::compile(&source).unwrap();
+}
+
+/// Converts an update of the value stored under the `key` into a set of inputs which can be
+/// consumed by the program created by the function above. Values are put onto the stack in
+/// reverse order, while nodes of the authentication path are split across tapes A and B.
+fn generate_program_inputs(key: u128, old_value: [u128; 2], new_value: [u128; 2], path: &[[u128; 2]]) -> ProgramInputs {
+
+    let public_inputs = [new_value[1], new_value[0], old_value[1], old_value[0], key];
+
+    let mut a = Vec::new();
+    let mut b = Vec::new();
+    for node in path.iter() {
+        a.push(node[0]);
+        b.push(node[1]);
+    }
+
+    return ProgramInputs::new(&public_inputs, &a, &b);
+}
+
+/// Pseudo-randomly generates `count` keys for a tree of depth `n`.
+fn generate_keys(n: usize, count: usize) -> Vec<u128> {
+    let mut seed = [0u8; 32];
+    seed[0] = 1; seed[1] = 2; seed[2] = 3;
+
+    let num_leaves = 1u128 << (n - 1);
+    return field::prng_vector(seed, count).iter().map(|&v| v % num_leaves).collect();
+}
+
+// SPARSE MERKLE TREE
+// ================================================================================================
+
+/// A sparse Merkle tree which keeps only non-empty nodes in memory; all other nodes are assumed
+/// to be roots of empty subtrees. Leaves of an empty tree are set to [0, 0].
+struct SparseMerkleTree {
+    depth       : usize,
+    nodes       : HashMap<(usize, u128), [u128; 2]>,
+    empty_nodes : Vec<[u128; 2]>,
+}
+
+impl SparseMerkleTree {
+
+    /// Returns an empty tree of the specified depth; depth includes the leaf and the root levels.
+    pub fn new(depth: usize) -> SparseMerkleTree {
+        let mut empty_nodes = vec![[field::ZERO; 2]];
+        for i in 1..depth {
+            let node = empty_nodes[i - 1];
+            empty_nodes.push(hash_nodes(node, node));
+        }
+        return SparseMerkleTree { depth, nodes: HashMap::new(), empty_nodes };
+    }
+
+    pub fn root(&self) -> [u128; 2] {
+        return self.get_node(self.depth - 1, 0);
+    }
+
+    /// Returns the value stored under the specified key.
+    pub fn get(&self, key: u128) -> [u128; 2] {
+        return self.get_node(0, key);
+    }
+
+    /// Returns the authentication path for the leaf at the specified key, starting with the
+    /// sibling of the leaf and ending with a child of the root.
+    pub fn get_path(&self, key: u128) -> Vec<[u128; 2]> {
+        let mut path = Vec::with_capacity(self.depth - 1);
+        let mut index = key;
+        for level in 0..(self.depth - 1) {
+            path.push(self.get_node(level, index ^ 1));
+            index = index >> 1;
+        }
+        return path;
+    }
+
+    /// Sets the value stored under the specified key, and updates all nodes on the path from
+    /// the leaf to the root.
+    pub fn insert(&mut self, key: u128, value: [u128; 2]) {
+        let mut node = value;
+        let mut index = key;
+        self.nodes.insert((0, index), node);
+        for level in 1..self.depth {
+            let sibling = self.get_node(level - 1, index ^ 1);
+            node = if index & 1 == 0 { hash_nodes(node, sibling) } else { hash_nodes(sibling, node) };
+            index = index >> 1;
+            self.nodes.insert((level, index), node);
+        }
+    }
+
+    fn get_node(&self, level: usize, index: u128) -> [u128; 2] {
+        return match self.nodes.get(&(level, index)) {
+            Some(node) => *node,
+            None => self.empty_nodes[level]
+        };
+    }
+}
+
+fn hash_nodes(left: [u128; 2], right: [u128; 2]) -> [u128; 2] {
+    let hash = hasher::digest(&[left[0], left[1], right[0], right[1]]);
+    return [hash[0], hash[1]];
+}
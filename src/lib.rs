@@ -1,4 +1,5 @@
 use log::debug;
+use std::io::Read;
 use std::ops::Range;
 use std::time::Instant;
 
@@ -10,15 +11,27 @@ mod tests;
 pub mod crypto;
 pub mod math;
 pub mod utils;
+pub mod testing;
+pub mod bench;
+pub mod diagnostics;
 
 mod stark;
-pub use stark::{ StarkProof, ProofOptions };
+pub use stark::{ StarkProof, BatchProof, BoundaryValues, FormatHeader, FORMAT_VERSION, ProofShape, ProofSizeStats, ProofOptions, ProofStats, ProverStats, MinimumAcceptableOptions, Transcript, HashTranscript, MerlinTranscript };
 
 mod processor;
-pub use processor::{ OpCode, OpHint };
+pub use processor::{ OpCode, OpHint, HostInterface, AdviceLog, AdviceStream, ExecutionError, Checkpoint, AuditReport, CheckFailure, CheckKind };
 
 mod programs;
-pub use programs::{ Program, ProgramInputs, assembly, blocks };
+pub use programs::{ Program, ProgramAbi, ProgramInputs, SourceLocation, hash_public_inputs, environment_inputs, hash_event_log, hash_output_tape, hash_many, ExecutionDigest, OutputCommitment, OutputOpening, commit_outputs, MAX_COMMITTED_OUTPUTS, derive_nullifier, hash_nullifier, NULLIFIER_TAG, assembly, builders, blocks };
+
+mod prover;
+pub use prover::{ Prover, TuningTarget };
+
+mod verifier;
+pub use verifier::{ Verifier };
+
+#[cfg(feature = "service")]
+pub mod service;
 
 // EXECUTOR
 // ================================================================================================
@@ -27,15 +40,324 @@ pub use programs::{ Program, ProgramInputs, assembly, blocks };
 /// 
 /// * `inputs` specifies the initial stack state and provides secret input tapes;
 /// * `num_outputs` specifies the number of elements from the top of the stack to be returned;
+/// 
+/// Panics if the program does not complete within `DEFAULT_MAX_CYCLES` cycles, or if it overflows
+/// the stack.
 pub fn execute(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &ProofOptions) -> (Vec<u128>, StarkProof)
+{
+    return match execute_with_limit(program, inputs, num_outputs, options, DEFAULT_MAX_CYCLES) {
+        Ok(result) => result,
+        Err(err) => panic!("{}", err)
+    };
+}
+
+/// Executes the specified `program` and returns the result together with a STARK-based proof of execution.
+/// Unlike `execute()`, returns `ExecutionError::ResourceExceeded` error if the program does not complete
+/// within `max_cycles` cycles, and `ExecutionError::StackOverflow` error if the program tries to grow
+/// the stack beyond `MAX_STACK_DEPTH` items.
+/// 
+/// * `inputs` specifies the initial stack state and provides secret input tapes;
+/// * `num_outputs` specifies the number of elements from the top of the stack to be returned;
+pub fn execute_with_limit(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &ProofOptions, max_cycles: usize)
+    -> Result<(Vec<u128>, StarkProof), ExecutionError>
 {
     assert!(num_outputs <= MAX_OUTPUTS, 
         "cannot produce more than {} outputs, but requested {}", MAX_OUTPUTS, num_outputs);
+    check_abi(program, inputs, num_outputs);
 
     // execute the program to create an execution trace
     let now = Instant::now();
-    let (trace, ctx_depth, loop_depth) = processor::execute(program, inputs);
-    let mut trace = stark::TraceTable::new(trace, ctx_depth, loop_depth, options.extension_factor());
+    let (trace, ctx_depth, loop_depth, call_depth) = processor::execute(program, inputs, max_cycles)?;
+    let mut transcript = HashTranscript::new(options.hash_fn());
+    return Ok(prove_execution(program, inputs, num_outputs, options, &mut transcript, trace, ctx_depth, loop_depth, call_depth, &mut None, now));
+}
+
+/// Executes the specified `program` and returns the result together with a STARK-based proof of execution.
+/// Unlike `execute()`, verifier challenges are derived using the provided `transcript` rather than the
+/// default `HashTranscript`; the transcript may already contain messages of a larger protocol, in which
+/// case the proof can be verified only against a transcript in the same state.
+/// 
+/// * `inputs` specifies the initial stack state and provides secret input tapes;
+/// * `num_outputs` specifies the number of elements from the top of the stack to be returned;
+pub fn execute_with_transcript(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &ProofOptions, transcript: &mut dyn Transcript) -> (Vec<u128>, StarkProof)
+{
+    assert!(num_outputs <= MAX_OUTPUTS, 
+        "cannot produce more than {} outputs, but requested {}", MAX_OUTPUTS, num_outputs);
+    check_abi(program, inputs, num_outputs);
+
+    let now = Instant::now();
+    let (trace, ctx_depth, loop_depth, call_depth) = processor::execute(program, inputs, DEFAULT_MAX_CYCLES)
+        .unwrap_or_else(|err| panic!("{}", err));
+    return prove_execution(program, inputs, num_outputs, options, transcript, trace, ctx_depth, loop_depth, call_depth, &mut None, now);
+}
+
+/// Executes the specified `program` and returns the result together with a STARK-based proof of execution
+/// and with the memory used by the prover in each phase of proof generation; panics in the same cases
+/// as `execute()`.
+/// 
+/// * `inputs` specifies the initial stack state and provides secret input tapes;
+/// * `num_outputs` specifies the number of elements from the top of the stack to be returned;
+pub fn execute_with_stats(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &ProofOptions) -> (Vec<u128>, StarkProof, ProverStats)
+{
+    assert!(num_outputs <= MAX_OUTPUTS, 
+        "cannot produce more than {} outputs, but requested {}", MAX_OUTPUTS, num_outputs);
+    check_abi(program, inputs, num_outputs);
+
+    let now = Instant::now();
+    let (trace, ctx_depth, loop_depth, call_depth) = processor::execute(program, inputs, DEFAULT_MAX_CYCLES)
+        .unwrap_or_else(|err| panic!("{}", err));
+    let mut transcript = HashTranscript::new(options.hash_fn());
+    return prove_execution_with_stats(program, inputs, num_outputs, options, &mut transcript, trace, ctx_depth, loop_depth, call_depth, &mut None, now);
+}
+
+/// Executes the specified `program` once and returns the result together with a STARK-based proof of
+/// execution for every set of proof options in `options`; every proof is the same as the one generated by
+/// `execute()` for the same options. The execution trace, constraint evaluations, and commitments are
+/// computed only once and are shared by all proofs; thus, all options must define the same extension factor,
+/// domain offset, and hash function, but may differ in the number of queries, grinding factor, and the
+/// length of FRI remainder. Panics in the same cases as `execute()`.
+/// 
+/// * `inputs` specifies the initial stack state and provides secret input tapes;
+/// * `num_outputs` specifies the number of elements from the top of the stack to be returned;
+pub fn execute_with_options(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &[ProofOptions]) -> (Vec<u128>, Vec<StarkProof>)
+{
+    assert!(!options.is_empty(), "at least one set of proof options must be provided");
+    assert!(num_outputs <= MAX_OUTPUTS, 
+        "cannot produce more than {} outputs, but requested {}", MAX_OUTPUTS, num_outputs);
+    check_abi(program, inputs, num_outputs);
+
+    let now = Instant::now();
+    let (trace, ctx_depth, loop_depth, call_depth) = processor::execute(program, inputs, DEFAULT_MAX_CYCLES)
+        .unwrap_or_else(|err| panic!("{}", err));
+    let (mut trace, outputs) = build_trace_table(program, num_outputs, &options[0], trace, ctx_depth, loop_depth, call_depth, now);
+
+    // commit to the trace once, and complete a proof for every set of options from a copy of the
+    // transcript taken right after the commitments
+    let mut setup = stark::ProverSetup::new(&trace);
+    let mut transcript = HashTranscript::new(options[0].hash_fn());
+    let mut proofs = Vec::with_capacity(options.len());
+    {
+        let mut context = stark::ProverContext::new(&mut trace, inputs.get_public_inputs(), &outputs, &options[0], &mut transcript, &mut setup);
+        for options in options.iter() {
+            assert!(context.matches(options),
+                "all proof options must define the same extension factor, domain offset, and hash function");
+            let (proof, _) = context.prove(options, &mut transcript.clone());
+            proofs.push(proof);
+        }
+    }
+    setup.release(trace);
+
+    return (outputs, proofs);
+}
+
+/// Executes the specified `program` without generating a proof of execution, and returns the top
+/// `num_outputs` elements of the stack. Returns `ExecutionError::ResourceExceeded` error if the
+/// program does not complete within `max_cycles` cycles, and `ExecutionError::StackOverflow` error
+/// if the program tries to grow the stack beyond `MAX_STACK_DEPTH` items.
+pub fn run(program: &Program, inputs: &ProgramInputs, num_outputs: usize, max_cycles: usize) -> Result<Vec<u128>, ExecutionError>
+{
+    assert!(num_outputs <= MAX_OUTPUTS, 
+        "cannot produce more than {} outputs, but requested {}", MAX_OUTPUTS, num_outputs);
+    check_abi(program, inputs, num_outputs);
+
+    // the program is executed by the interpreter because execution trace is not needed; stack
+    // slots beyond the depth of the stack hold zeros, same as in the execution trace
+    let mut outputs = processor::interpret(program, inputs, max_cycles)?;
+    outputs.resize(num_outputs, math::field::ZERO);
+
+    return Ok(outputs);
+}
+
+/// Executes the specified `program` without generating a proof of execution in audit mode: every
+/// runtime check which fails during execution (stack underflows, non-binary values passed to
+/// operations which expect binary values, reads from empty input tapes, failed assertions etc.)
+/// is recorded in the returned report, and execution continues as if the check passed. Thus,
+/// all issues of a program can be found in a single execution. Execution is still aborted if the
+/// program does not complete within `max_cycles` cycles, or if the stack overflows.
+pub fn audit(program: &Program, inputs: &ProgramInputs, max_cycles: usize) -> AuditReport {
+    return processor::audit(program, inputs, max_cycles);
+}
+
+/// Executes the specified `program` without generating a proof of execution, and returns a source
+/// map of the execution: for every assembly instruction executed, in the order of execution, the
+/// location of the instruction in the assembly source together with the range of trace steps at
+/// which operations compiled from the instruction were executed. An instruction which is executed
+/// several times (e.g. in the body of a loop) appears in the map once for every execution. Returns
+/// `ExecutionError::ResourceExceeded` error if the program does not complete within `max_cycles`
+/// cycles.
+pub fn source_map(program: &Program, inputs: &ProgramInputs, max_cycles: usize) -> Result<Vec<(SourceLocation, Range<usize>)>, ExecutionError>
+{
+    let locations = processor::locate_steps(program, inputs, max_cycles)?;
+
+    let mut map: Vec<(SourceLocation, Range<usize>)> = Vec::new();
+    for (step, location) in locations.into_iter().enumerate() {
+        if let Some(location) = location {
+            match map.last_mut() {
+                Some((last, steps)) if *last == location && steps.end == step => steps.end = step + 1,
+                _ => map.push((location, step..(step + 1))),
+            }
+        }
+    }
+
+    return Ok(map);
+}
+
+/// Executes the specified `program` without generating a proof of execution until the first step at
+/// or after `step` at which a checkpoint can be captured (checkpoints are captured at steps which are
+/// multiples of 16), and returns the checkpoint; advice requested by the program is supplied by the
+/// `host`, if one is provided. Returns None if the program completes before such a step is reached.
+pub fn checkpoint(program: &Program, inputs: &ProgramInputs, host: Option<&mut dyn HostInterface>, step: usize, max_cycles: usize)
+    -> Result<Option<Checkpoint>, ExecutionError>
+{
+    return processor::execute_until(program, inputs, host, step, max_cycles);
+}
+
+/// Resumes execution of the specified `program` from the `checkpoint` without generating a proof of
+/// execution, and returns the top `num_outputs` elements of the stack at the end of execution. Returns
+/// `ExecutionError::ResourceExceeded` error if the program does not complete within `max_cycles`
+/// cycles after the checkpoint.
+pub fn resume(program: &Program, checkpoint: &Checkpoint, host: Option<&mut dyn HostInterface>, num_outputs: usize, max_cycles: usize)
+    -> Result<Vec<u128>, ExecutionError>
+{
+    assert!(num_outputs <= MAX_OUTPUTS, 
+        "cannot produce more than {} outputs, but requested {}", MAX_OUTPUTS, num_outputs);
+
+    // stack registers follow decoder registers in the execution trace; stack slots beyond the
+    // depth of the stack hold zeros
+    let (trace, ctx_depth, loop_depth, call_depth) = processor::resume(program, checkpoint, host, max_cycles)?;
+    let stack_start = stark::TraceState::compute_decoder_width(ctx_depth, loop_depth, call_depth);
+    let stack_end = trace.len() - HASHER_WIDTH;
+    let outputs = (stack_start..(stack_start + num_outputs))
+        .map(|i| if i < stack_end { trace[i][trace[i].len() - 1] } else { math::field::ZERO })
+        .collect();
+
+    return Ok(outputs);
+}
+
+/// Resumes execution of the specified `program` from the `checkpoint`, and captures a new checkpoint
+/// in the same way as `checkpoint()` function does; this can be used to split execution of a
+/// long-running program into several segments.
+pub fn resume_until(program: &Program, checkpoint: &Checkpoint, host: Option<&mut dyn HostInterface>, step: usize, max_cycles: usize)
+    -> Result<Option<Checkpoint>, ExecutionError>
+{
+    return processor::resume_until(program, checkpoint, host, step, max_cycles);
+}
+
+/// Executes the specified `program` and returns the result together with a STARK-based proof of execution.
+/// Unlike `execute()`, advice requested by the program via host calls is supplied by the `host`; all
+/// supplied values are recorded into an advice log which is returned as the third element of the result.
+/// 
+/// * `inputs` specifies the initial stack state and provides secret input tapes;
+/// * `num_outputs` specifies the number of elements from the top of the stack to be returned;
+pub fn execute_with_host(program: &Program, inputs: &ProgramInputs, host: &mut dyn HostInterface, num_outputs: usize, options: &ProofOptions) -> (Vec<u128>, StarkProof, AdviceLog)
+{
+    assert!(num_outputs <= MAX_OUTPUTS, 
+        "cannot produce more than {} outputs, but requested {}", MAX_OUTPUTS, num_outputs);
+    check_abi(program, inputs, num_outputs);
+
+    // execute the program to create an execution trace, and record all advice supplied by the host
+    let now = Instant::now();
+    let mut recorder = processor::AdviceRecorder::new(host);
+    let (trace, ctx_depth, loop_depth, call_depth) = processor::execute_with_host(program, inputs, &mut recorder, DEFAULT_MAX_CYCLES)
+        .unwrap_or_else(|err| panic!("{}", err));
+    let advice_log = recorder.into_log();
+
+    let mut transcript = HashTranscript::new(options.hash_fn());
+    let (outputs, proof) = prove_execution(program, inputs, num_outputs, options, &mut transcript, trace, ctx_depth, loop_depth, call_depth, &mut None, now);
+    return (outputs, proof, advice_log);
+}
+
+/// Executes the specified `program` in the same way as `execute_with_host()` does, but advice supplied
+/// by the `host` is not recorded. Together with a host which streams advice, such as `AdviceStream`,
+/// this makes it possible to prove programs which consume more advice than could be held in memory
+/// at once; only the execution trace is kept in memory.
+/// 
+/// * `inputs` specifies the initial stack state and provides secret input tapes;
+/// * `num_outputs` specifies the number of elements from the top of the stack to be returned;
+pub fn execute_without_log(program: &Program, inputs: &ProgramInputs, host: &mut dyn HostInterface, num_outputs: usize, options: &ProofOptions) -> (Vec<u128>, StarkProof)
+{
+    assert!(num_outputs <= MAX_OUTPUTS, 
+        "cannot produce more than {} outputs, but requested {}", MAX_OUTPUTS, num_outputs);
+    check_abi(program, inputs, num_outputs);
+
+    let now = Instant::now();
+    let (trace, ctx_depth, loop_depth, call_depth) = processor::execute_with_host(program, inputs, host, DEFAULT_MAX_CYCLES)
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    let mut transcript = HashTranscript::new(options.hash_fn());
+    return prove_execution(program, inputs, num_outputs, options, &mut transcript, trace, ctx_depth, loop_depth, call_depth, &mut None, now);
+}
+
+/// Re-executes the specified `program` using advice recorded in the `advice_log` instead of making
+/// calls to the original host, and returns the result together with a STARK-based proof of execution.
+/// 
+/// * `inputs` must be the same inputs as those used when the advice log was recorded;
+/// * `num_outputs` specifies the number of elements from the top of the stack to be returned;
+pub fn execute_from_log(program: &Program, inputs: &ProgramInputs, advice_log: &AdviceLog, num_outputs: usize, options: &ProofOptions) -> (Vec<u128>, StarkProof)
+{
+    assert!(num_outputs <= MAX_OUTPUTS, 
+        "cannot produce more than {} outputs, but requested {}", MAX_OUTPUTS, num_outputs);
+    check_abi(program, inputs, num_outputs);
+
+    // execute the program to create an execution trace, and make sure the entire log was consumed
+    let now = Instant::now();
+    let mut replay = processor::AdviceReplay::new(advice_log);
+    let (trace, ctx_depth, loop_depth, call_depth) = processor::execute_with_host(program, inputs, &mut replay, DEFAULT_MAX_CYCLES)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert!(replay.is_complete(), "program execution did not consume all entries of the advice log");
+
+    let mut transcript = HashTranscript::new(options.hash_fn());
+    return prove_execution(program, inputs, num_outputs, options, &mut transcript, trace, ctx_depth, loop_depth, call_depth, &mut None, now);
+}
+
+/// Makes sure that `inputs` and `num_outputs` match the ABI of the `program`, if the program
+/// has one.
+fn check_abi(program: &Program, inputs: &ProgramInputs, num_outputs: usize) {
+    if let Some(abi) = program.abi() {
+        if let Err(err) = abi.check_execution(inputs, num_outputs) {
+            panic!("inputs do not match program ABI: {}", err);
+        }
+    }
+}
+
+/// Builds a trace table from the execution trace and generates a STARK proof for it; the prover
+/// `setup` is re-used if it matches the shape of the trace, and is replaced otherwise.
+fn prove_execution(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &ProofOptions, transcript: &mut dyn Transcript,
+    trace: Vec<Vec<u128>>, ctx_depth: usize, loop_depth: usize, call_depth: usize, setup: &mut Option<stark::ProverSetup>, now: Instant) -> (Vec<u128>, StarkProof)
+{
+    let (outputs, proof, _) = prove_execution_with_stats(program, inputs, num_outputs, options, transcript,
+        trace, ctx_depth, loop_depth, call_depth, setup, now);
+    return (outputs, proof);
+}
+
+/// Same as `prove_execution()`, but also returns the memory used by the prover.
+fn prove_execution_with_stats(program: &Program, inputs: &ProgramInputs, num_outputs: usize, options: &ProofOptions, transcript: &mut dyn Transcript,
+    trace: Vec<Vec<u128>>, ctx_depth: usize, loop_depth: usize, call_depth: usize, setup: &mut Option<stark::ProverSetup>, now: Instant) -> (Vec<u128>, StarkProof, ProverStats)
+{
+    let (mut trace, outputs) = build_trace_table(program, num_outputs, options, trace, ctx_depth, loop_depth, call_depth, now);
+
+    // build prover setup (this also allocates all buffers needed by the prover up front) unless
+    // the existing setup can be re-used, and generate STARK proof
+    if !setup.as_ref().map_or(false, |setup| setup.matches(&trace)) {
+        *setup = Some(stark::ProverSetup::new(&trace));
+    }
+    let setup = setup.as_mut().unwrap();
+    let (proof, stats) = stark::prove(&mut trace, inputs.get_public_inputs(), &outputs, options, transcript, setup);
+    debug!("Allocated {} buffers of {} elements to generate the proof; peak prover memory was {} bytes",
+        setup.pool().num_allocated(),
+        setup.pool().buffer_size(),
+        stats.peak_memory);
+    setup.release(trace);
+
+    return (outputs, proof, stats);
+}
+
+/// Builds a trace table from the execution trace of the `program` and returns it together with
+/// `num_outputs` values from the top of the stack at the last step of the trace.
+fn build_trace_table(program: &Program, num_outputs: usize, options: &ProofOptions,
+    trace: Vec<Vec<u128>>, ctx_depth: usize, loop_depth: usize, call_depth: usize, now: Instant) -> (stark::TraceTable, Vec<u128>)
+{
+    let trace = stark::TraceTable::new(trace, ctx_depth, loop_depth, call_depth, options.extension_factor(), options.domain_offset());
     debug!("Generated execution trace of {} registers and {} steps in {} ms",
         trace.register_count(),
         trace.unextended_length(),
@@ -58,12 +380,72 @@ pub fn execute(program: &Program, inputs: &ProgramInputs, num_outputs: usize, op
         hex::encode(program.hash()),
         hex::encode(program_hash));
 
-    // generate STARK proof
-    let proof = stark::prove(&mut trace, inputs.get_public_inputs(), &outputs, options);
+    return (trace, outputs);
+}
+
+// BATCH EXECUTOR
+// ================================================================================================
+
+/// Executes every program in `programs` with the corresponding `inputs`, and returns the results
+/// together with a single STARK-based proof of all executions. Execution traces of all programs
+/// are padded to the length of the longest trace and are committed to together; thus, the cost of
+/// commitments and of the low-degree proof is shared by all programs in the batch.
+///
+/// * `inputs` specifies the initial stack state and secret input tapes of every program;
+/// * `num_outputs` specifies the number of elements from the top of the stack to be returned
+///   for every program;
+///
+/// Panics if any of the programs does not complete within `DEFAULT_MAX_CYCLES` cycles.
+pub fn execute_batch(programs: &[Program], inputs: &[ProgramInputs], num_outputs: usize, options: &ProofOptions) -> (Vec<Vec<u128>>, BatchProof)
+{
+    assert!(!programs.is_empty(), "at least one program must be provided");
+    assert!(programs.len() == inputs.len(), "expected inputs for {} programs, but received {}",
+        programs.len(), inputs.len());
+    assert!(num_outputs <= MAX_OUTPUTS, 
+        "cannot produce more than {} outputs, but requested {}", MAX_OUTPUTS, num_outputs);
+
+    // execute all programs to determine the longest trace, and then re-execute programs with
+    // shorter traces so that their traces are padded to the same length
+    let now = Instant::now();
+    let mut executions = Vec::with_capacity(programs.len());
+    for (program, inputs) in programs.iter().zip(inputs) {
+        check_abi(program, inputs, num_outputs);
+        let execution = processor::execute(program, inputs, DEFAULT_MAX_CYCLES)
+            .unwrap_or_else(|err| panic!("{}", err));
+        executions.push(execution);
+    }
+    let trace_length = executions.iter().map(|(trace, _, _, _)| trace[0].len()).max().unwrap();
 
+    let mut traces = Vec::with_capacity(programs.len());
+    let mut outputs = Vec::with_capacity(programs.len());
+    for (i, (trace, ctx_depth, loop_depth, call_depth)) in executions.into_iter().enumerate() {
+        let (trace, ctx_depth, loop_depth, call_depth) = if trace[0].len() < trace_length {
+            processor::execute_padded(&programs[i], &inputs[i], trace_length, DEFAULT_MAX_CYCLES)
+                .unwrap_or_else(|err| panic!("{}", err))
+        }
+        else {
+            (trace, ctx_depth, loop_depth, call_depth)
+        };
+        let (trace, program_outputs) = build_trace_table(&programs[i], num_outputs, options, trace, ctx_depth, loop_depth, call_depth, now);
+        traces.push(trace);
+        outputs.push(program_outputs);
+    }
+
+    let public_inputs = inputs.iter().map(|inputs| inputs.get_public_inputs().to_vec()).collect::<Vec<Vec<u128>>>();
+    let mut transcript = HashTranscript::new(options.hash_fn());
+    let proof = stark::prove_batch(&mut traces, &public_inputs, &outputs, options, &mut transcript);
     return (outputs, proof);
 }
 
+/// Verifies that if every program with a hash in `program_hashes` is executed with the
+/// corresponding `public_inputs` and some secret inputs, the result is equal to the corresponding
+/// `outputs`; the programs must be listed in the same order as they were passed to `execute_batch()`.
+pub fn verify_batch(program_hashes: &[[u8; 32]], public_inputs: &[Vec<u128>], outputs: &[Vec<u128>], proof: &BatchProof) -> Result<bool, String>
+{
+    let mut transcript = HashTranscript::new(proof.options().hash_fn());
+    return stark::verify_batch(program_hashes, public_inputs, outputs, proof, &mut transcript);
+}
+
 // VERIFIER
 // ================================================================================================
 
@@ -71,14 +453,141 @@ pub fn execute(program: &Program, inputs: &ProgramInputs, num_outputs: usize, op
 /// provided `public_inputs` and some secret inputs, the result is equal to the `outputs`.
 pub fn verify(program_hash: &[u8; 32], public_inputs: &[u128], outputs: &[u128], proof: &StarkProof) -> Result<bool, String>
 {
-    return stark::verify(program_hash, public_inputs, outputs, proof);
+    let mut transcript = HashTranscript::new(proof.options().hash_fn());
+    return verify_proof(program_hash, public_inputs, outputs, proof, &mut transcript, &mut None);
+}
+
+/// Verifies execution of a program in the same way as `verify()`, but derives verifier challenges
+/// using the provided `transcript`; the transcript must be in the same state as the transcript
+/// passed to `execute_with_transcript()` when the proof was generated.
+pub fn verify_with_transcript(program_hash: &[u8; 32], public_inputs: &[u128], outputs: &[u128], proof: &StarkProof, transcript: &mut dyn Transcript) -> Result<bool, String>
+{
+    return verify_proof(program_hash, public_inputs, outputs, proof, transcript, &mut None);
+}
+
+/// Verifies execution of a program in the same way as `verify()`, but also rejects proofs which
+/// were generated with options providing less than `min_security` bits of conjectured security
+/// (see `ProofOptions::conjectured_security()`); this prevents a prover from choosing weak
+/// proof options.
+pub fn verify_with_security(program_hash: &[u8; 32], public_inputs: &[u128], outputs: &[u128], proof: &StarkProof, min_security: u32) -> Result<bool, String>
+{
+    let security = proof.options().conjectured_security(proof.trace_length());
+    if security < min_security {
+        return Err(format!("proof provides {} bits of security, but at least {} bits are required",
+            security, min_security));
+    }
+    return verify(program_hash, public_inputs, outputs, proof);
+}
+
+/// Verifies execution of a program in the same way as `verify()`, but also rejects proofs which
+/// were generated with options weaker than those specified by the `policy`.
+pub fn verify_with_policy(program_hash: &[u8; 32], public_inputs: &[u128], outputs: &[u128], proof: &StarkProof, policy: &MinimumAcceptableOptions) -> Result<bool, String>
+{
+    policy.check(proof.options())?;
+    return verify(program_hash, public_inputs, outputs, proof);
+}
+
+/// Verifies that if a program with the specified `program_hash` is executed in the specified
+/// `environment` with the provided `public_inputs` and some secret inputs, the result is equal
+/// to the `outputs`; the program must have been executed with inputs constructed via
+/// `ProgramInputs::with_environment()` function.
+pub fn verify_with_environment(program_hash: &[u8; 32], environment: &[u128; ENVIRONMENT_WIDTH], public_inputs: &[u128], outputs: &[u128], proof: &StarkProof) -> Result<bool, String>
+{
+    if public_inputs.len() > MAX_PUBLIC_INPUTS - ENVIRONMENT_WIDTH {
+        return Err(format!("expected no more than {} public inputs together with an environment, but received {}",
+            MAX_PUBLIC_INPUTS - ENVIRONMENT_WIDTH, public_inputs.len()));
+    }
+    return verify(program_hash, &environment_inputs(environment, public_inputs), outputs, proof);
+}
+
+/// Verifies that if a program with the specified `program_hash` is executed with the stack
+/// initialized with the digest of `public_inputs` (see `hash_public_inputs()`) and some secret
+/// inputs, the result is equal to the `outputs`; the program must have been executed with
+/// inputs constructed via `ProgramInputs::with_public_digest()` function. The proof commits only
+/// to the digest, and so, the raw inputs are needed only at verification time, and there is no
+/// limit on their number; the digest binds the number of inputs, and so, inputs padded with
+/// trailing ZEROs are rejected.
+pub fn verify_with_committed_inputs(program_hash: &[u8; 32], public_inputs: &[u128], outputs: &[u128], proof: &StarkProof) -> Result<bool, String>
+{
+    return verify(program_hash, &hash_public_inputs(public_inputs), outputs, proof);
+}
+
+/// Verifies execution of a program in the same way as `verify()`, but also checks that the
+/// digest of the output tape committed to by the proof is equal to the `event_log_hash`; the
+/// expected digest of an event log can be computed via `hash_event_log()` function.
+pub fn verify_with_events(program_hash: &[u8; 32], public_inputs: &[u128], outputs: &[u128], proof: &StarkProof, event_log_hash: &[u128; HASH_DIGEST_SIZE]) -> Result<bool, String>
+{
+    let tape_hash = hash_output_tape(proof.output_tape());
+    if tape_hash != *event_log_hash {
+        return Err(format!("event log hash {:?} does not match expected hash {:?}",
+            tape_hash, event_log_hash));
+    }
+    return verify(program_hash, public_inputs, outputs, proof);
+}
+
+/// Verifies execution of a program in the same way as `verify()`, but also checks that the
+/// first 2 `outputs` are equal to the `nullifier` published together with the proof; the
+/// program must derive the nullifier via instructions returned from `derive_nullifier()`, and
+/// the prover can compute it beforehand via `hash_nullifier()` function.
+pub fn verify_with_nullifier(program_hash: &[u8; 32], public_inputs: &[u128], outputs: &[u128], nullifier: &[u128; HASH_DIGEST_SIZE], proof: &StarkProof) -> Result<bool, String>
+{
+    if outputs.len() < HASH_DIGEST_SIZE || outputs[..HASH_DIGEST_SIZE] != nullifier[..] {
+        return Err(format!("outputs {:?} do not start with nullifier {:?}", outputs, nullifier));
+    }
+    return verify(program_hash, public_inputs, outputs, proof);
+}
+
+/// Verifies that a program with the specified `program_hash`, executed with the provided
+/// `public_inputs` and some secret inputs, committed to its `num_outputs` outputs with the
+/// specified `root` (see `commit_outputs()`), and that the `openings` reveal some of these
+/// outputs; outputs which are not opened remain hidden from the verifier.
+pub fn verify_with_disclosure(program_hash: &[u8; 32], public_inputs: &[u128], root: &[u128; HASH_DIGEST_SIZE], num_outputs: usize, openings: &[OutputOpening], proof: &StarkProof) -> Result<bool, String>
+{
+    for opening in openings.iter() {
+        opening.verify(root, num_outputs)?;
+    }
+    return verify(program_hash, public_inputs, root, proof);
+}
+
+/// Verifies execution of a program in the same way as `verify()`, but first checks that
+/// `public_inputs` and `outputs` match the `abi` of the program.
+pub fn verify_with_abi(program_hash: &[u8; 32], abi: &ProgramAbi, public_inputs: &[u128], outputs: &[u128], proof: &StarkProof) -> Result<bool, String>
+{
+    abi.check_verification(public_inputs, outputs)?;
+    return verify(program_hash, public_inputs, outputs, proof);
+}
+
+/// Verifies execution of a program in the same way as `verify()`, but reads the proof (in the
+/// encoding produced by `StarkProof::to_bytes()`) from the `reader` via size-bounded parsing
+/// (see `StarkProof::from_reader()`): proofs of more than `max_proof_size` bytes are rejected as
+/// soon as the limit is reached, and so, an untrusted source cannot make the verifier allocate
+/// more than roughly `max_proof_size` bytes. The proof is parsed in full before it is verified;
+/// nothing is verified while the bytes are being read. Bytes following the proof are left in
+/// the reader.
+pub fn verify_from_reader<R: Read>(program_hash: &[u8; 32], public_inputs: &[u128], outputs: &[u128], reader: R, max_proof_size: usize) -> Result<bool, String>
+{
+    let proof = StarkProof::from_reader(reader, max_proof_size)?;
+    return verify(program_hash, public_inputs, outputs, &proof);
+}
+
+/// Verifies the STARK `proof`; the verifier `setup` is re-used if it matches the shape of the
+/// proof, and is replaced otherwise.
+fn verify_proof(program_hash: &[u8; 32], public_inputs: &[u128], outputs: &[u128], proof: &StarkProof, transcript: &mut dyn Transcript,
+    setup: &mut Option<stark::VerifierSetup>) -> Result<bool, String>
+{
+    if !setup.as_ref().map_or(false, |setup| setup.matches(proof)) {
+        *setup = Some(stark::VerifierSetup::new(proof));
+    }
+    return stark::verify(program_hash, public_inputs, outputs, proof, transcript, setup.as_ref().unwrap());
 }
 
 // GLOBAL CONSTANTS
 // ================================================================================================
 
 pub const MAX_CONTEXT_DEPTH : usize = 16;
+pub const DEFAULT_MAX_CYCLES: usize = 1 << 30;
 pub const MAX_LOOP_DEPTH    : usize = 8;
+pub const MAX_CALL_DEPTH    : usize = 8;
 const MIN_TRACE_LENGTH      : usize = 16;
 const MAX_REGISTER_COUNT    : usize = 128;
 const MIN_EXTENSION_FACTOR  : usize = 16;
@@ -90,7 +599,10 @@ const MIN_LOOP_DEPTH        : usize = 1;
 
 // PUSH OPERATION
 // ------------------------------------------------------------------------------------------------
+// a sequence of up to MAX_PUSH_SEQUENCE consecutive PUSH operations can start on any step which
+// is a multiple of PUSH_OP_ALIGNMENT
 const PUSH_OP_ALIGNMENT     : usize = 8;
+const MAX_PUSH_SEQUENCE     : usize = 4;
 
 // HASH OPERATION
 // ------------------------------------------------------------------------------------------------
@@ -112,6 +624,15 @@ const HACC_NUM_ROUNDS       : usize = 14;
 //  ctr ╒═════ sponge ══════╕╒═══ cf_ops ══╕╒═══════ ld_ops ═══════╕╒═ hd_ops ╕╒═ ctx ══╕╒═ loop ═╕
 //   0    1    2    3    4    5    6    7    8    9    10   11   12   13   14   15   ..   ..   ..
 // ├────┴────┴────┴────┴────┴────┴────┴────┴────┴────┴────┴────┴────┴────┴────┴────┴────┴────┴────┤
+//
+//  ╒═ call ╕╒═ dyn ═╕╒═ call stack ═╕
+//    ..      ..     ..    ..    ..
+// ┴───────┴───────┴────┴────┴────┤
+//
+// call registers are present only in traces of programs which call procedures; the call register
+// is set to 1 on BEGIN and TEND operations which start and end a procedure call, the dyn register
+// is additionally set to 1 when the call is dynamic, and the call stack holds hashes of blocks
+// from which procedures were called (as well as hashes of dynamically called procedures)
 
 const NUM_CF_OP_BITS        : usize = 3;
 const NUM_LD_OP_BITS        : usize = 5;
@@ -136,4 +657,33 @@ const HD_OP_BITS_RANGE      : Range<usize> = Range { start: 13, end: 15 };
 
 pub const MAX_PUBLIC_INPUTS : usize = 8;
 pub const MAX_OUTPUTS       : usize = MAX_PUBLIC_INPUTS;
-pub const MAX_STACK_DEPTH   : usize = 32;
\ No newline at end of file
+pub const ENVIRONMENT_WIDTH : usize = 4;
+pub const MAX_STACK_DEPTH   : usize = 32;
+
+// HASHER LAYOUT
+// ------------------------------------------------------------------------------------------------
+//
+//  act ╒═══════════ hasher state ══════════╕╒═════════ permutation input ═════════╕
+//   0     1     2     3     4     5     6     7     8     9     10    11    12
+// ├─────┴─────┴─────┴─────┴─────┴─────┴─────┴─────┴─────┴─────┴─────┴─────┴─────┤
+//
+// hasher registers are located in the trace after the last register of the user stack; the first
+// register is set to 1 for cycles in which the hasher computes a permutation requested by the stack,
+// and the last 6 registers hold the input of the permutation for the entire cycle
+
+const HASHER_WIDTH          : usize = 2 * HASH_STATE_WIDTH + 1;
+
+// AUXILIARY LAYOUT
+// ------------------------------------------------------------------------------------------------
+//
+// ╒═ bus ═╕╒═ out ═╕
+//     0        1
+// ├───────┴───────┤
+//
+// auxiliary registers are built after the main trace has been committed to, and are committed to
+// separately; the bus register holds the running product of the bus which connects co-processors
+// to the stack, and the output register accumulates values written to the output tape
+
+const AUX_WIDTH             : usize = 2;
+const BUS_REGISTER_IDX      : usize = 0;
+const OUTPUT_REGISTER_IDX   : usize = 1;
\ No newline at end of file
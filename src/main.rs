@@ -4,6 +4,8 @@ use distaff::{ self, StarkProof };
 mod examples;
 use examples::{ Example };
 
+mod repl;
+
 fn main() {
 
     // configure logging
@@ -17,6 +19,10 @@ fn main() {
     if args.len() < 2 {
         ex = examples::fibonacci::get_example(&args);
     }
+    else if args[1] == "repl" {
+        repl::run();
+        return;
+    }
     else {
         ex = match args[1].as_str() {
             "collatz"       => examples::collatz::get_example(&args[1..]),
@@ -25,6 +31,8 @@ fn main() {
             "fibonacci"     => examples::fibonacci::get_example(&args[1..]),
             "merkle"        => examples::merkle::get_example(&args[1..]),
             "rangecheck"    => examples::range::get_example(&args[1..]),
+            "schnorr"       => examples::schnorr::get_example(&args[1..]),
+            "smt"           => examples::smt::get_example(&args[1..]),
             _ => panic!("Could not find example program for '{}'", args[1])
         }
     }
@@ -42,14 +50,17 @@ fn main() {
     assert_eq!(expected_result, outputs, "Program result was computed incorrectly");
 
     // serialize the proof to see how big it is
-    let proof_bytes = bincode::serialize(&proof).unwrap();
+    let proof_bytes = proof.to_bytes();
     println!("Execution proof size: {} KB", proof_bytes.len() / 1024);
-    println!("Execution proof security: {} bits", options.security_level(true));
+    print!("{}", proof.stats());
+    println!("Execution proof security: {} bits conjectured, {} bits proven",
+        options.conjectured_security(proof.trace_length()),
+        options.proven_security(proof.trace_length()));
     println!("--------------------------------");
 
     // verify that executing a program with a given hash and given inputs
     // results in the expected output
-    let proof = bincode::deserialize::<StarkProof>(&proof_bytes).unwrap();
+    let proof = StarkProof::from_bytes(&proof_bytes).unwrap();
     let now = Instant::now();
     match distaff::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof) {
         Ok(_) => println!("Execution verified in {} ms", now.elapsed().as_millis()),
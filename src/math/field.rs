@@ -3,6 +3,7 @@ use std::convert::TryInto;
 use rand::prelude::*;
 use rand::distributions::{ Distribution, Uniform };
 use crate::utils::{ uninit_vector };
+use crate::crypto::HashFunction;
 
 // CONSTANTS
 // ================================================================================================
@@ -15,11 +16,17 @@ pub const G: u128 = 23953097886125630542083529559205016746;
 
 // public constants
 pub const MODULUS: u128 = M;
+
+// largest power of 2 which divides MODULUS - 1; G generates the subgroup of this order
+pub const TWO_ADICITY: u32 = 40;
 pub const RANGE: Range<u128> = Range { start: 0, end: M };
 
 pub const ZERO: u128 = 0;
 pub const ONE: u128 = 1;
 
+// number of bits of the power processed at once by exp_many()
+const EXP_WINDOW: usize = 4;
+
 // BASIC ARITHMETIC
 // --------------------------------------------------------------------------------------------
 
@@ -38,36 +45,24 @@ pub fn sub(a: u128, b: u128) -> u128 {
 pub fn mul(a: u128, b: u128) -> u128 {
 
     let (x0, x1, x2) = mul_128x64(a, (b >> 64) as u64);         // x = a * b_hi
-    let (mut x0, mut x1, x2) = mul_reduce(x0, x1, x2);          // x = x - (x >> 128) * m
-    if x2 == 1 {
-        // if there was an overflow beyond 128 bits, subtract
-        // modulus from the result to make sure it fits into 
-        // 128 bits; this can potentially be removed in favor
-        // of checking overflow later
-        let (t0, t1) = sub_modulus(x0, x1);                     // x = x - m
-        x0 = t0; x1 = t1;
-    }
+    let (x0, x1, x2) = mul_reduce(x0, x1, x2);                  // x = x - (x >> 128) * m
+    // if there was an overflow beyond 128 bits, subtract modulus from the result to make sure
+    // it fits into 128 bits; this can potentially be removed in favor of checking overflow later
+    let (x0, x1) = sub_modulus_if(x0, x1, x2 == 1);             // x = x - m
 
     let (y0, y1, y2) = mul_128x64(a, b as u64);                 // y = a * b_lo
 
-    let (mut y1, carry) = add64_with_carry(y1, x0, 0);          // y = y + (x << 64)
-    let (mut y2, y3) = add64_with_carry(y2, x1, carry);
-    if y3 == 1 {
-        // if there was an overflow beyond 192 bits, subtract
-        // modulus * 2^64 from the result to make sure it fits
-        // into 192 bits; this can potentially replace the
-        // previous overflow check (but needs to be proven)
-        let (t0, t1) = sub_modulus(y1, y2);                     // y = y - (m << 64)
-        y1 = t0; y2 = t1;
-    }
-    
-    let (mut z0, mut z1, z2) = mul_reduce(y0, y1, y2);          // z = y - (y >> 128) * m
+    let (y1, carry) = add64_with_carry(y1, x0, 0);              // y = y + (x << 64)
+    let (y2, y3) = add64_with_carry(y2, x1, carry);
+    // if there was an overflow beyond 192 bits, subtract modulus * 2^64 from the result to make
+    // sure it fits into 192 bits; this can potentially replace the previous overflow check (but
+    // needs to be proven)
+    let (y1, y2) = sub_modulus_if(y1, y2, y3 == 1);             // y = y - (m << 64)
 
-    // make sure z is smaller than m
-    if z2 == 1 || (z1 == (M >> 64) as u64 && z0 >= (M as u64)) {
-        let (t0, t1) = sub_modulus(z0, z1);                     // z = z - m
-        z0 = t0; z1 = t1;
-    }
+    let (z0, z1, z2) = mul_reduce(y0, y1, y2);                  // z = y - (y >> 128) * m
+
+    // make sure z is smaller than m; & is used instead of && so that both sides are evaluated
+    let (z0, z1) = sub_modulus_if(z0, z1, (z2 == 1) | ((z1 == (M >> 64) as u64) & (z0 >= (M as u64))));
 
     return ((z1 as u128) << 64) + (z0 as u128);
 }
@@ -80,6 +75,7 @@ pub fn mul_acc(a: &mut [u128], b: &[u128], c: u128) {
 }
 
 /// Computes y such that (x * y) % m = 1; x is assumed to be a valid field element.
+#[cfg(not(feature = "constant-time"))]
 pub fn inv(x: u128) -> u128 {
     if x == 0 { return 0 };
 
@@ -161,6 +157,13 @@ pub fn inv(x: u128) -> u128 {
     return a;
 }
 
+/// Computes y such that (x * y) % m = 1; x is assumed to be a valid field element. The inverse
+/// is computed as x^(m - 2), and so, the time it takes does not depend on the value of x.
+#[cfg(feature = "constant-time")]
+pub fn inv(x: u128) -> u128 {
+    return exp(x, M - 2);
+}
+
 /// Computes multiplicative inverses of all slice elements using batch inversion method.
 pub fn inv_many(values: &[u128]) -> Vec<u128> {
     let mut result = uninit_vector(values.len());
@@ -198,6 +201,7 @@ pub fn div(a: u128, b: u128) -> u128 {
 }
 
 /// Computes (b^p) % m; b and p are assumed to be valid field elements.
+#[cfg(not(feature = "constant-time"))]
 pub fn exp(b: u128, p: u128) -> u128 {
     if b == 0 { return 0; }
     else if p == 0 { return 1; }
@@ -218,6 +222,66 @@ pub fn exp(b: u128, p: u128) -> u128 {
     return r;
 }
 
+/// Computes (b^p) % m; b and p are assumed to be valid field elements. The power is computed
+/// with a Montgomery ladder which performs the same sequence of multiplications for all 128
+/// bits of p, and so, the time it takes does not depend on the values of b and p.
+#[cfg(feature = "constant-time")]
+pub fn exp(b: u128, p: u128) -> u128 {
+    // r0 = b^(bits of p seen so far), r1 = r0 * b
+    let mut r0 = ONE;
+    let mut r1 = b;
+    for i in (0..128).rev() {
+        let bit = (p >> i) & 1 == 1;
+        let t = mul(r0, r1);
+        let s = select(bit, r1, r0);
+        let s = mul(s, s);
+        r0 = select(bit, t, s);
+        r1 = select(bit, s, t);
+    }
+
+    // 0^p = 0 for all p, including p = 0
+    return select(b == 0, ZERO, r0);
+}
+
+/// Computes (b^p) % m for all values b in place; the values and p are assumed to be valid field
+/// elements. The values are raised to the power together using a fixed 4-bit window over the
+/// bits of p, and so, every step performs N independent multiplications which the CPU can
+/// execute in parallel. The sequence of multiplications depends only on p.
+pub fn exp_many<const N: usize>(values: &mut [u128; N], p: u128) {
+    if p == 0 {
+        values.iter_mut().for_each(|v| *v = if *v == ZERO { ZERO } else { ONE });
+        return;
+    }
+
+    // powers[k][j] = values[j]^k for all k < 2^EXP_WINDOW
+    let mut powers = [[ONE; N]; 1 << EXP_WINDOW];
+    powers[1] = *values;
+    for k in 2..powers.len() {
+        for j in 0..N {
+            powers[k][j] = mul(powers[k - 1][j], powers[1][j]);
+        }
+    }
+
+    // process windows of p starting with the most significant non-zero one
+    let mask = (1 << EXP_WINDOW) - 1;
+    let num_windows = (128 - p.leading_zeros() as usize + EXP_WINDOW - 1) / EXP_WINDOW;
+    let mut result = powers[((p >> ((num_windows - 1) * EXP_WINDOW)) & mask) as usize];
+    for i in (0..(num_windows - 1)).rev() {
+        for _ in 0..EXP_WINDOW {
+            for j in 0..N {
+                result[j] = mul(result[j], result[j]);
+            }
+        }
+        let window = ((p >> (i * EXP_WINDOW)) & mask) as usize;
+        if window != 0 {
+            for j in 0..N {
+                result[j] = mul(result[j], powers[window][j]);
+            }
+        }
+    }
+    *values = result;
+}
+
 /// Computes (0 - x) % m; x is assumed to be a valid field element.
 pub fn neg(x: u128) -> u128 {
     return sub(ZERO, x);
@@ -228,8 +292,8 @@ pub fn neg(x: u128) -> u128 {
 pub fn get_root_of_unity(order: usize) -> u128 {
     assert!(order != 0, "cannot get root of unity for order 0");
     assert!(order.is_power_of_two(), "order must be a power of 2");
-    assert!(order.trailing_zeros() <= 40, "order cannot exceed 2^40");
-    let p = 1u128 << (40 - order.trailing_zeros());
+    assert!(order.trailing_zeros() <= TWO_ADICITY, "order cannot exceed 2^{}", TWO_ADICITY);
+    let p = 1u128 << (TWO_ADICITY - order.trailing_zeros());
     return exp(G, p);
 }
 
@@ -274,12 +338,51 @@ pub fn prng_vector(seed: [u8; 32], length: usize) -> Vec<u128> {
     return g.sample_iter(range).take(length).collect();
 }
 
+/// Samples a field element uniformly from `bytes` by rejection: the bytes are read as a sequence
+/// of 16-byte little-endian integers, and the first integer which is smaller than the modulus
+/// is returned. Returns None if there is no such integer; for uniformly random bytes, a single
+/// integer is rejected with probability smaller than 2^-81.
+pub fn sample(bytes: &[u8]) -> Option<u128> {
+    return bytes.chunks_exact(16)
+        .map(|chunk| u128::from_le_bytes(chunk.try_into().unwrap()))
+        .find(|&value| value < M);
+}
+
+/// Hashes `data` into a field element using `hash_fn`: the data is hashed together with an 8-byte
+/// counter, and the counter is incremented until an element can be sampled from the digest.
+pub fn hash_to_field(data: &[u8], hash_fn: HashFunction) -> u128 {
+    let mut buffer = data.to_vec();
+    buffer.extend_from_slice(&[0u8; 8]);
+    let mut digest = [0u8; 32];
+    for counter in 0u64.. {
+        buffer[data.len()..].copy_from_slice(&counter.to_le_bytes());
+        hash_fn(&buffer, &mut digest);
+        if let Some(value) = sample(&digest) {
+            return value;
+        }
+    }
+    unreachable!();
+}
+
 // TYPE CONVERSIONS
 // --------------------------------------------------------------------------------------------
 pub fn from_bytes(bytes: &[u8]) -> u128 { 
     return u128::from_le_bytes(bytes.try_into().unwrap());
 }
 
+/// Returns the canonical 16-byte little-endian encoding of a field element.
+pub fn to_le_bytes(value: u128) -> [u8; 16] {
+    debug_assert!(value < M, "{} is not a valid field element", value);
+    return value.to_le_bytes();
+}
+
+/// Decodes a field element from its canonical 16-byte little-endian encoding; returns None if the
+/// encoded integer is not smaller than the modulus.
+pub fn from_le_bytes(bytes: [u8; 16]) -> Option<u128> {
+    let value = u128::from_le_bytes(bytes);
+    return if value < M { Some(value) } else { None };
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
 
@@ -301,7 +404,7 @@ fn mul_reduce(z0: u64, z1: u64, z2: u64) -> (u64, u64, u64) {
 #[inline(always)]
 fn mul_by_modulus(a: u64) -> (u64, u64, u64) {
     let a_lo = (a as u128).wrapping_mul(M);
-    let a_hi = if a == 0 { 0 } else { a - 1 };
+    let a_hi = a.wrapping_sub((a != 0) as u64);
     return (a_lo as u64, (a_lo >> 64) as u64, a_hi);
 }
 
@@ -313,6 +416,31 @@ fn sub_modulus(a_lo: u64, a_hi: u64) -> (u64, u64) {
     return (z as u64, (z >> 64) as u64);
 }
 
+/// Subtracts modulus from a if the condition is true; otherwise, returns a unchanged.
+#[inline(always)]
+#[cfg(not(feature = "constant-time"))]
+fn sub_modulus_if(a_lo: u64, a_hi: u64, condition: bool) -> (u64, u64) {
+    return if condition { sub_modulus(a_lo, a_hi) } else { (a_lo, a_hi) };
+}
+
+/// Subtracts modulus from a if the condition is true; otherwise, returns a unchanged. The
+/// subtraction is always performed, and the result is selected without branching.
+#[inline(always)]
+#[cfg(feature = "constant-time")]
+fn sub_modulus_if(a_lo: u64, a_hi: u64, condition: bool) -> (u64, u64) {
+    let (t_lo, t_hi) = sub_modulus(a_lo, a_hi);
+    let a = select(condition, ((t_hi as u128) << 64) | (t_lo as u128), ((a_hi as u128) << 64) | (a_lo as u128));
+    return (a as u64, (a >> 64) as u64);
+}
+
+/// Returns a if the condition is true, and b otherwise, without branching on the condition.
+#[inline(always)]
+#[cfg(feature = "constant-time")]
+fn select(condition: bool, a: u128, b: u128) -> u128 {
+    let mask = 0u128.wrapping_sub(condition as u128);
+    return (a & mask) | (b & !mask);
+}
+
 #[inline(always)]
 fn sub_192x192(a0: u64, a1: u64, a2: u64, b0: u64, b1: u64, b2: u64) -> (u64, u64, u64) {
     let z0 = (a0 as u128).wrapping_sub(b0 as u128);
@@ -322,6 +450,7 @@ fn sub_192x192(a0: u64, a1: u64, a2: u64, b0: u64, b1: u64, b2: u64) -> (u64, u6
 }
 
 #[inline(always)]
+#[cfg(not(feature = "constant-time"))]
 fn add_192x192(a0: u64, a1: u64, a2: u64, b0: u64, b1: u64, b2: u64) -> (u64, u64, u64) {
     let z0 = (a0 as u128) + (b0 as u128);
     let z1 = (a1 as u128) + (b1 as u128) + (z0 >> 64);
@@ -435,6 +564,67 @@ mod tests {
         }
     }
 
+    #[test]
+    fn exp() {
+        let m = BigUint::from(super::MODULUS);
+
+        assert_eq!(1, super::exp(1, 0));
+        assert_eq!(0, super::exp(0, 0));
+        assert_eq!(0, super::exp(0, 5));
+        assert_eq!(1, super::exp(super::G, 1 << 40));
+        assert_eq!(super::MODULUS - 1, super::exp(super::G, 1 << 39));
+
+        // compare random values against big integer exponentiation
+        let b: Vec<u128> = super::rand_vector(100);
+        let p: Vec<u128> = super::rand_vector(100);
+        for i in 0..b.len() {
+            let expected = BigUint::from(b[i]).modpow(&BigUint::from(p[i]), &m);
+            assert_eq!(expected, BigUint::from(super::exp(b[i], p[i])));
+        }
+    }
+
+    #[test]
+    fn exp_many() {
+        let powers = [0, 1, 2, 3, 15, 16, 17, 1 << 40, super::MODULUS - 2, super::rand()];
+        for &p in powers.iter() {
+            let mut values: [u128; 6] = super::rand_vector(6).try_into().unwrap();
+            values[0] = super::ZERO;
+            values[1] = super::ONE;
+            let expected: Vec<u128> = values.iter().map(|&b| super::exp(b, p)).collect();
+            super::exp_many(&mut values, p);
+            assert_eq!(expected, values.to_vec());
+        }
+    }
+
+    #[test]
+    fn sample() {
+        assert_eq!(Some(5), super::sample(&5u128.to_le_bytes()));
+        assert_eq!(None, super::sample(&[5; 15]));
+
+        // integers which are not smaller than the modulus are rejected
+        let mut bytes = super::MODULUS.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&7u128.to_le_bytes());
+        assert_eq!(Some(7), super::sample(&bytes));
+        assert_eq!(None, super::sample(&u128::MAX.to_le_bytes()));
+    }
+
+    #[test]
+    fn hash_to_field() {
+        let a = super::hash_to_field(b"distaff", crate::crypto::hash::blake3);
+        assert!(a < super::MODULUS);
+        assert_eq!(a, super::hash_to_field(b"distaff", crate::crypto::hash::blake3));
+        assert_ne!(a, super::hash_to_field(b"distaff", crate::crypto::hash::sha3));
+        assert_ne!(a, super::hash_to_field(b"distafff", crate::crypto::hash::blake3));
+    }
+
+    #[test]
+    fn le_bytes() {
+        let x = super::rand();
+        assert_eq!(Some(x), super::from_le_bytes(super::to_le_bytes(x)));
+        assert_eq!(None, super::from_le_bytes(super::MODULUS.to_le_bytes()));
+        assert_eq!(Some(super::MODULUS - 1), super::from_le_bytes((super::MODULUS - 1).to_le_bytes()));
+    }
+
     #[test]
     fn get_root_of_unity() {
         let root_40: u128 = super::get_root_of_unity(usize::pow(2, 40));
@@ -151,6 +151,18 @@ pub fn mul_by_const(p: &[u128], k: u128) -> Vec<u128> {
     return result;
 }
 
+/// Multiplies every coefficient p_i of polynomial `p` by offset^i; evaluating the resulting
+/// polynomial over a domain D yields evaluations of `p` over the coset offset * D. Shifting by
+/// an offset and then by its inverse returns the original polynomial.
+pub fn shift_in_place(p: &mut [u128], offset: u128) {
+    if offset == field::ONE { return; }
+    let mut power_of_offset = field::ONE;
+    for coefficient in p.iter_mut() {
+        *coefficient = field::mul(*coefficient, power_of_offset);
+        power_of_offset = field::mul(power_of_offset, offset);
+    }
+}
+
 /// Divides polynomial `a` by polynomial `b`; if the polynomials don't divide evenly,
 /// the remainder is ignored.
 pub fn div(a: &[u128], b: &[u128]) -> Vec<u128> {
@@ -330,6 +342,28 @@ mod tests {
         assert_eq!(y1, y2);
     }
 
+    #[test]
+    fn shift_in_place() {
+        let n: usize = 256;
+        let offset = field::rand();
+
+        // evaluations of a shifted polynomial over the subgroup are evaluations of the original
+        // polynomial over the coset
+        let poly = field::rand_vector(n);
+        let mut y1 = poly.clone();
+        super::shift_in_place(&mut y1, offset);
+        super::eval_fft(&mut y1, true);
+
+        let roots = field::get_power_series(field::get_root_of_unity(n), n);
+        let y2 = roots.iter().map(|&x| super::eval(&poly, field::mul(x, offset))).collect::<Vec<u128>>();
+        assert_eq!(y1, y2);
+
+        // shifting by the inverse of the offset undoes the shift
+        super::interpolate_fft(&mut y1, true);
+        super::shift_in_place(&mut y1, field::inv(offset));
+        assert_eq!(poly, y1);
+    }
+
     #[test]
     fn add() {
         let poly1: [u128; 3] = [384863712573444386, 7682273369345308472, 13294661765012277990];
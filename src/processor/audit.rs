@@ -0,0 +1,61 @@
+use serde::{ Serialize };
+use crate::programs::SourceLocation;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Results of executing a program in audit mode. In audit mode, runtime checks which would
+/// otherwise abort execution (e.g. stack underflows, non-binary values passed to operations which
+/// expect binary values, reads from an empty input tape, or failed assertions) are recorded, and
+/// execution continues; thus, all issues of a program can be found in a single execution.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct AuditReport {
+    pub failures    : Vec<CheckFailure>,
+    pub stack       : Vec<u128>,        // state of the stack at the end of execution, top first
+    pub cycles      : usize,            // number of cycles executed
+    pub error       : Option<String>,   // execution error which aborted execution, if any
+}
+
+/// A runtime check which failed at a given step of execution, together with the location of the
+/// assembly instruction which was being executed, if it is known.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CheckFailure {
+    pub step        : usize,
+    pub kind        : CheckKind,
+    pub message     : String,
+    pub location    : Option<SourceLocation>,
+}
+
+/// Kinds of runtime checks performed during execution of a program.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckKind {
+    /// an operation needed more values than there were on the stack; missing values are
+    /// assumed to be ZEROs
+    Underflow,
+    /// an operation or a control flow block expected a binary value; non-binary operation
+    /// inputs are used as is, and non-binary conditions are assumed to be ZEROs
+    NonBinary,
+    /// an input tape was empty or provided invalid advice; missing values are assumed to be
+    /// ZEROs
+    Advice,
+    /// an ASSERT, ASSERTEQ, or ASSERTEQW operation failed
+    Assertion,
+    /// an operation received an operand for which it is not defined (e.g. division by ZERO)
+    InvalidOperand,
+}
+
+// AUDIT REPORT IMPLEMENTATION
+// ================================================================================================
+impl AuditReport {
+
+    /// Returns true if all runtime checks passed and execution was not aborted.
+    pub fn is_clean(&self) -> bool {
+        return self.failures.is_empty() && self.error.is_none();
+    }
+
+    /// Serializes the report into JSON.
+    pub fn to_json(&self) -> String {
+        return serde_json::to_string(self).unwrap();
+    }
+}
@@ -0,0 +1,107 @@
+use serde::{ Serialize, Deserialize };
+use crate::SPONGE_WIDTH;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// A snapshot of the full state of the VM captured right before the operation at a given step
+/// is executed. Execution of a program can be resumed from a checkpoint (possibly after the
+/// checkpoint was serialized and de-serialized), and the resumed execution goes through exactly
+/// the same states as the original execution would have gone from that step onward.
+///
+/// Checkpoints can be captured only at steps which are multiples of 16 and at which a user
+/// operation of a Span block is about to be executed. Every hash permutation is requested by a
+/// single RESCR operation, and thus, the state of the hash co-processor does not need to be
+/// captured: a resumed execution computes permutations requested after the checkpoint in its
+/// own hasher trace.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    program_hash    : [u8; 32],
+    step            : usize,
+    position        : Vec<usize>,
+    decoder         : DecoderState,
+    stack           : StackState,
+}
+
+/// State of the decoder at the step at which a checkpoint was captured. Stack registers which
+/// were allocated before this step are captured even if they are currently empty because the
+/// width of the execution trace depends on them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DecoderState {
+    pub op_count    : u128,
+    pub sponge      : [u128; SPONGE_WIDTH],
+    pub ctx_stack   : Vec<u128>,
+    pub ctx_depth   : usize,
+    pub loop_stack  : Vec<u128>,
+    pub loop_depth  : usize,
+    #[serde(default)]
+    pub call_stack  : Vec<u128>,
+    #[serde(default)]
+    pub call_depth  : usize,
+}
+
+/// State of the user stack at the step at which a checkpoint was captured, together with the
+/// values which remain on the secret input tapes; tape A also holds advice which was supplied
+/// by the host but has not been consumed yet.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StackState {
+    pub registers   : Vec<u128>,
+    pub depth       : usize,
+    pub max_depth   : usize,
+    pub tape_a      : Vec<u128>,
+    pub tape_b      : Vec<u128>,
+}
+
+// CHECKPOINT IMPLEMENTATION
+// ================================================================================================
+impl Checkpoint {
+
+    /// Returns a checkpoint for a program with the specified hash captured at the specified
+    /// `step`; the position of the checkpoint within the program is built up separately via
+    /// `enclose()` method.
+    pub fn new(program_hash: [u8; 32], step: usize, decoder: DecoderState, stack: StackState) -> Checkpoint {
+        return Checkpoint { program_hash, step, position: Vec::new(), decoder, stack };
+    }
+
+    /// Returns hash of the program for which the checkpoint was captured.
+    pub fn program_hash(&self) -> &[u8; 32] {
+        return &self.program_hash;
+    }
+
+    /// Returns the step at which the checkpoint was captured; execution resumes from this step.
+    pub fn step(&self) -> usize {
+        return self.step;
+    }
+
+    /// Returns the number of user operations executed before the checkpoint was captured.
+    pub fn op_count(&self) -> u128 {
+        return self.decoder.op_count;
+    }
+
+    /// Returns the values on the user stack at the checkpoint (top of the stack first).
+    pub fn user_stack(&self) -> &[u128] {
+        return &self.stack.registers[..self.stack.depth];
+    }
+
+    /// Returns the position of the checkpoint within the program: for each nested block, the
+    /// index of the block within a sequence of blocks (and for Switch and Loop blocks, the index
+    /// of the branch being executed), and the index of the next operation within a Span block.
+    pub fn position(&self) -> &[usize] {
+        return &self.position;
+    }
+
+    pub fn decoder_state(&self) -> &DecoderState {
+        return &self.decoder;
+    }
+
+    pub fn stack_state(&self) -> &StackState {
+        return &self.stack;
+    }
+
+    /// Prepends `index` to the position of the checkpoint; this is called by every block which
+    /// encloses the operation at which the checkpoint was captured, starting with the inner-most
+    /// block.
+    pub fn enclose(&mut self, index: usize) {
+        self.position.insert(0, index);
+    }
+}
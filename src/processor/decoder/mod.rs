@@ -1,11 +1,11 @@
 use crate::{
     math::field,
     utils::sponge,
-    MAX_CONTEXT_DEPTH, MAX_LOOP_DEPTH,
+    MAX_CONTEXT_DEPTH, MAX_LOOP_DEPTH, MAX_CALL_DEPTH,
     NUM_CF_OP_BITS, NUM_LD_OP_BITS, NUM_HD_OP_BITS,
-    SPONGE_WIDTH, BASE_CYCLE_LENGTH, PUSH_OP_ALIGNMENT,
+    SPONGE_WIDTH, BASE_CYCLE_LENGTH, PUSH_OP_ALIGNMENT, MAX_PUSH_SEQUENCE,
 };
-use super::opcodes::{ FlowOps, UserOps };
+use super::{ opcodes::{ FlowOps, UserOps }, checkpoint::DecoderState };
 
 #[cfg(test)]
 mod tests;
@@ -29,6 +29,11 @@ pub struct Decoder {
 
     loop_stack  : Vec<Vec<u128>>,
     loop_depth  : usize,
+
+    call_flag   : Vec<u128>,
+    dyn_flag    : Vec<u128>,
+    call_stack  : Vec<Vec<u128>>,
+    call_depth  : usize,
 }
 
 // DECODER IMPLEMENTATION
@@ -69,12 +74,57 @@ impl Decoder {
         let loop_stack = Vec::new();
         let loop_depth = loop_stack.len();
 
+        // call flag registers are always tracked, but they become a part of the execution trace
+        // only if the program calls procedures
+        let call_flag = vec![field::ZERO; init_trace_length];
+        let dyn_flag = vec![field::ZERO; init_trace_length];
+        let call_stack = Vec::new();
+        let call_depth = call_stack.len();
+
         // create and return decoder
         return Decoder {
             step: 0, 
             op_counter, sponge, sponge_trace,
             cf_op_bits, ld_op_bits, hd_op_bits,
             ctx_stack, ctx_depth, loop_stack, loop_depth,
+            call_flag, dyn_flag, call_stack, call_depth,
+        };
+    }
+
+    /// Creates a new instance of instruction decoder which starts in the specified `state`; the
+    /// first step of register traces holds the values of the state.
+    pub fn from_state(state: &DecoderState, init_trace_length: usize) -> Decoder {
+        let mut decoder = Decoder::new(init_trace_length);
+
+        decoder.op_counter[0] = state.op_count;
+        decoder.sponge = state.sponge;
+        for (register, &value) in decoder.sponge_trace.iter_mut().zip(state.sponge.iter()) {
+            register[0] = value;
+        }
+
+        decoder.ctx_stack = state.ctx_stack.iter().map(|&value| init_register(value, init_trace_length)).collect();
+        decoder.ctx_depth = state.ctx_depth;
+
+        decoder.loop_stack = state.loop_stack.iter().map(|&value| init_register(value, init_trace_length)).collect();
+        decoder.loop_depth = state.loop_depth;
+
+        decoder.call_stack = state.call_stack.iter().map(|&value| init_register(value, init_trace_length)).collect();
+        decoder.call_depth = state.call_depth;
+
+        return decoder;
+    }
+
+    /// Returns the state of the decoder at the current step.
+    pub fn save_state(&self) -> DecoderState {
+        return DecoderState {
+            op_count    : self.op_counter[self.step],
+            sponge      : self.sponge,
+            ctx_stack   : self.ctx_stack.iter().map(|register| register[self.step]).collect(),
+            ctx_depth   : self.ctx_depth,
+            loop_stack  : self.loop_stack.iter().map(|register| register[self.step]).collect(),
+            loop_depth  : self.loop_depth,
+            call_stack  : self.call_stack.iter().map(|register| register[self.step]).collect(),
+            call_depth  : self.call_depth,
         };
     }
 
@@ -84,11 +134,15 @@ impl Decoder {
     }
 
     /// Returns value of the current step pointer.
-    #[cfg(test)]
     pub fn current_step(&self) -> usize {
         return self.step;
     }
 
+    /// Returns the number of user operations executed before the current step.
+    pub fn op_count(&self) -> u128 {
+        return self.op_counter[self.step];
+    }
+
     /// Returns the max value of the context stack reached during program execution.
     pub fn max_ctx_stack_depth(&self) -> usize {
         // outer-most context doesn't count because it is always just 0
@@ -100,6 +154,11 @@ impl Decoder {
         return self.loop_stack.len();
     }
 
+    /// Returns the max value of the call stack reached during program execution.
+    pub fn max_call_stack_depth(&self) -> usize {
+        return self.call_stack.len();
+    }
+
     /// Returns the state of the stack at the specified `step`.
     #[cfg(test)]
     pub fn get_state(&self, step: usize) -> Vec<u128> {
@@ -112,6 +171,11 @@ impl Decoder {
         for register in self.hd_op_bits.iter()   { state.push(register[step]); }
         for register in self.ctx_stack.iter()    { state.push(register[step]); }
         for register in self.loop_stack.iter()   { state.push(register[step]); }
+        if self.call_stack.len() > 0 {
+            state.push(self.call_flag[step]);
+            state.push(self.dyn_flag[step]);
+            for register in self.call_stack.iter() { state.push(register[step]); }
+        }
 
         return state;
     }
@@ -150,6 +214,13 @@ impl Decoder {
 
         registers.append(&mut self.loop_stack);
 
+        // call flag registers are included only if the program called at least one procedure
+        if self.call_stack.len() > 0 {
+            registers.push(self.call_flag);
+            registers.push(self.dyn_flag);
+            registers.append(&mut self.call_stack);
+        }
+
         return registers;
     }
 
@@ -164,6 +235,7 @@ impl Decoder {
         self.advance_step(false);
         self.save_context();
         self.copy_loop_stack();
+        self.copy_call_stack();
         self.set_op_bits(FlowOps::Begin, UserOps::Noop);
         self.set_sponge([0, 0, 0, 0]);
     }
@@ -176,6 +248,7 @@ impl Decoder {
         self.advance_step(false);
         let context_hash = self.pop_context();
         self.copy_loop_stack();
+        self.copy_call_stack();
 
         let block_hash = self.sponge[0];
         if true_branch {
@@ -198,6 +271,7 @@ impl Decoder {
         self.advance_step(false);
         self.save_context();
         self.save_loop_image(loop_image);
+        self.copy_call_stack();
         self.set_op_bits(FlowOps::Loop, UserOps::Noop);
         self.set_sponge([0, 0, 0, 0]);
     }
@@ -209,6 +283,7 @@ impl Decoder {
 
         self.advance_step(false);
         self.copy_context_stack();
+        self.copy_call_stack();
         assert!(self.sponge[0] == self.peek_loop_image(),
             "cannot wrap a loop at step {}: hash of the last iteration doesn't match loop image", self.step);
         self.set_op_bits(FlowOps::Wrap, UserOps::Noop);
@@ -222,32 +297,108 @@ impl Decoder {
 
         self.advance_step(false);
         self.copy_context_stack();
+        self.copy_call_stack();
         assert!(self.sponge[0] == self.pop_loop_image(),
             "cannot break a loop at step {}: hash of the last iteration doesn't match loop image", self.step);
         self.set_op_bits(FlowOps::Break, UserOps::Noop);
         self.set_sponge(self.sponge);
     }
 
+    /// Initiates a procedure call; this is the same as starting a Group block, but the hash of the
+    /// calling block is pushed onto the call stack rather than onto the context stack.
+    pub fn start_call(&mut self) {
+        assert!(self.step % BASE_CYCLE_LENGTH == BASE_CYCLE_LENGTH - 1,
+            "cannot start a procedure call at step {}: operation alignment is not valid", self.step);
+
+        self.advance_step(false);
+        self.copy_context_stack();
+        self.copy_loop_stack();
+        self.save_call_context(&[self.sponge[0]]);
+        self.set_op_bits(FlowOps::Begin, UserOps::Noop);
+        self.set_call_flag(false);
+        self.set_sponge([0, 0, 0, 0]);
+    }
+
+    /// Terminates a procedure call; the hash of the calling block is popped from the call stack.
+    pub fn end_call(&mut self) {
+        assert!(self.step % BASE_CYCLE_LENGTH == 0,
+            "cannot return from a procedure at step {}: operation alignment is not valid", self.step);
+
+        self.advance_step(false);
+        self.copy_context_stack();
+        self.copy_loop_stack();
+        let context_hash = self.pop_call_context(1)[0];
+
+        let block_hash = self.sponge[0];
+        self.set_op_bits(FlowOps::Tend, UserOps::Noop);
+        self.set_call_flag(false);
+        self.set_sponge([context_hash, block_hash, 0, 0]);
+    }
+
+    /// Initiates a dynamic procedure call; this is the same as starting a procedure call, but
+    /// `callee_hash` is pushed onto the call stack together with the hash of the calling block
+    /// so that the body of the called procedure can be checked against it on return.
+    pub fn start_dyncall(&mut self, callee_hash: u128) {
+        assert!(self.step % BASE_CYCLE_LENGTH == BASE_CYCLE_LENGTH - 1,
+            "cannot start a dynamic call at step {}: operation alignment is not valid", self.step);
+
+        self.advance_step(false);
+        self.copy_context_stack();
+        self.copy_loop_stack();
+        self.save_call_context(&[callee_hash, self.sponge[0]]);
+        self.set_op_bits(FlowOps::Begin, UserOps::Noop);
+        self.set_call_flag(true);
+        self.set_sponge([0, 0, 0, 0]);
+    }
+
+    /// Terminates a dynamic procedure call; hash of the executed procedure must match the hash
+    /// saved on the call stack, and it is not merged into the hash of the calling block.
+    pub fn end_dyncall(&mut self) {
+        assert!(self.step % BASE_CYCLE_LENGTH == 0,
+            "cannot return from a dynamic call at step {}: operation alignment is not valid", self.step);
+
+        self.advance_step(false);
+        self.copy_context_stack();
+        self.copy_loop_stack();
+        let context = self.pop_call_context(2);
+        assert!(self.sponge[0] == context[0],
+            "cannot return from a dynamic call at step {}: hash of the procedure doesn't match", self.step);
+
+        self.set_op_bits(FlowOps::Tend, UserOps::Noop);
+        self.set_call_flag(true);
+        self.set_sponge([context[1], 0, 0, 0]);
+    }
+
     /// Updates the decoder with the value of the specified operation.
     pub fn decode_op(&mut self, op_code: UserOps, op_value: u128) {
         
-        // op_value can be provided only for a PUSH operation and only
-        // at steps which are multiples of 8
+        // op_value can be provided only for operations with immediate values, and only at
+        // steps which are multiples of 8 or which continue a sequence of such operations
         if op_value != field::ZERO {
-            match op_code {
-                UserOps::Push => assert!(self.step % PUSH_OP_ALIGNMENT == 0,
-                        "invalid PUSH operation alignment at step {}", self.step),
-                _ => panic!("invalid {:?} operation at step {}: op_value is non-zero", op_code, self.step),
-            }
+            assert!(op_code.has_immediate(),
+                "invalid {:?} operation at step {}: op_value is non-zero", op_code, self.step);
+
+            let offset = self.step % PUSH_OP_ALIGNMENT;
+            assert!(offset == 0 || (offset < MAX_PUSH_SEQUENCE && self.is_push_at(self.step - 1)),
+                "invalid {:?} operation alignment at step {}", op_code, self.step);
         }
 
         self.advance_step(true);
         self.copy_context_stack();
         self.copy_loop_stack();
+        self.copy_call_stack();
         self.set_op_bits(FlowOps::Hacc, op_code);
         self.apply_hacc_round(op_code, op_value);
     }
 
+    /// Makes sure register traces can hold at least `trace_length` steps; this must be called
+    /// before the trace is finalized.
+    pub fn extend_trace(&mut self, trace_length: usize) {
+        if trace_length > self.trace_length() {
+            self.resize_trace(trace_length);
+        }
+    }
+
     /// Populate all register traces with values for steps between the current step
     /// and the end of the trace.
     pub fn finalize_trace(&mut self) {
@@ -264,6 +415,11 @@ impl Decoder {
         for register in self.sponge_trace.iter_mut() { fill_register(register, self.step + 1, register[self.step]); }
         for register in self.ctx_stack.iter_mut()    { fill_register(register, self.step + 1, register[self.step]); }
         for register in self.loop_stack.iter_mut()   { fill_register(register, self.step + 1, register[self.step]); }
+        for register in self.call_stack.iter_mut()   { fill_register(register, self.step + 1, register[self.step]); }
+
+        // call flags are not set for VOID operations
+        fill_register(&mut self.call_flag, self.step, field::ZERO);
+        fill_register(&mut self.dyn_flag, self.step, field::ZERO);
 
         // update the step pointer to point to the last step
         self.step = self.trace_length() - 1;
@@ -279,15 +435,7 @@ impl Decoder {
 
         // make sure there is enough memory allocated for register traces
         if self.step >= self.trace_length() {
-            let new_length = self.trace_length() * 2;
-
-            self.op_counter.resize(new_length, field::ZERO);
-            for register in self.sponge_trace.iter_mut() { register.resize(new_length, field::ZERO); }
-            for register in self.cf_op_bits.iter_mut()   { register.resize(new_length, field::ZERO); }
-            for register in self.ld_op_bits.iter_mut()   { register.resize(new_length, field::ZERO); }
-            for register in self.hd_op_bits.iter_mut()   { register.resize(new_length, field::ZERO); }
-            for register in self.ctx_stack.iter_mut()    { register.resize(new_length, field::ZERO); }
-            for register in self.loop_stack.iter_mut()   { register.resize(new_length, field::ZERO); }
+            self.resize_trace(self.trace_length() * 2);
         }
 
         // for user ops, increment counter by 1; otherwise, copy counter from thee previous step
@@ -298,6 +446,19 @@ impl Decoder {
             self.op_counter[self.step] = self.op_counter[self.step - 1];
         }
     }
+
+    fn resize_trace(&mut self, new_length: usize) {
+        self.op_counter.resize(new_length, field::ZERO);
+        for register in self.sponge_trace.iter_mut() { register.resize(new_length, field::ZERO); }
+        for register in self.cf_op_bits.iter_mut()   { register.resize(new_length, field::ZERO); }
+        for register in self.ld_op_bits.iter_mut()   { register.resize(new_length, field::ZERO); }
+        for register in self.hd_op_bits.iter_mut()   { register.resize(new_length, field::ZERO); }
+        for register in self.ctx_stack.iter_mut()    { register.resize(new_length, field::ZERO); }
+        for register in self.loop_stack.iter_mut()   { register.resize(new_length, field::ZERO); }
+        for register in self.call_stack.iter_mut()   { register.resize(new_length, field::ZERO); }
+        self.call_flag.resize(new_length, field::ZERO);
+        self.dyn_flag.resize(new_length, field::ZERO);
+    }
     
     /// Populates all bits registers based on the opcodes for control flow and user operations.
     fn set_op_bits(&mut self, flow_op: FlowOps, user_op: UserOps) {
@@ -320,6 +481,18 @@ impl Decoder {
         }
     }
 
+    /// Returns true if the user operation decoded at the specified step was PUSH.
+    fn is_push_at(&self, step: usize) -> bool {
+        let mut op_code = 0;
+        for i in 0..NUM_LD_OP_BITS {
+            op_code |= (self.ld_op_bits[i][step] as u8) << i;
+        }
+        for i in 0..NUM_HD_OP_BITS {
+            op_code |= (self.hd_op_bits[i][step] as u8) << (i + NUM_LD_OP_BITS);
+        }
+        return op_code == UserOps::Push as u8;
+    }
+
     // CONTEXT STACK HELPERS
     // --------------------------------------------------------------------------------------------
 
@@ -430,6 +603,71 @@ impl Decoder {
         }
     }
 
+    // CALL STACK HELPERS
+    // --------------------------------------------------------------------------------------------
+
+    /// Pushes the specified `values` onto the call stack; the first value ends up at the top
+    /// of the stack.
+    fn save_call_context(&mut self, values: &[u128]) {
+        // increment call depth and make sure it doesn't overflow the stack
+        let num_values = values.len();
+        self.call_depth += num_values;
+        assert!(self.call_depth <= MAX_CALL_DEPTH, "call stack overflow at step {}", self.step);
+
+        // if the depth exceeds current number of registers allocated for the call stack,
+        // add new register traces to the stack
+        while self.call_depth > self.call_stack.len() {
+            self.call_stack.push(vec![field::ZERO; self.trace_length()]);
+        }
+
+        // shift all stack values to the right by the number of pushed values
+        for i in num_values..self.call_stack.len() {
+            self.call_stack[i][self.step] = self.call_stack[i - num_values][self.step - 1];
+        }
+
+        // set the top of the stack to the pushed values
+        for (i, &value) in values.iter().enumerate() {
+            self.call_stack[i][self.step] = value;
+        }
+    }
+
+    /// Removes `num_values` values from the top of the call stack and returns them.
+    fn pop_call_context(&mut self, num_values: usize) -> Vec<u128> {
+        // make sure the stack has enough values
+        assert!(self.call_depth >= num_values, "call stack underflow at step {}", self.step);
+
+        // shift all stack values to the left by the number of popped values, and fill the
+        // slots freed at the bottom of the stack with zeros
+        let stack_length = self.call_stack.len();
+        for i in num_values..stack_length {
+            self.call_stack[i - num_values][self.step] = self.call_stack[i][self.step - 1];
+        }
+        for i in (stack_length - num_values)..stack_length {
+            self.call_stack[i][self.step] = field::ZERO;
+        }
+
+        // update the stack depth and return the values that were at the top of the stack
+        // before it was shifted to the left
+        self.call_depth -= num_values;
+        return self.call_stack[..num_values].iter().map(|register| register[self.step - 1]).collect();
+    }
+
+    /// Copies contents of the call stack from the previous to the current step.
+    fn copy_call_stack(&mut self) {
+        for i in 0..self.call_stack.len() {
+            self.call_stack[i][self.step] = self.call_stack[i][self.step - 1];
+        }
+    }
+
+    /// Sets the call flag for the operation decoded at the previous step; dynamic flag is set
+    /// only for operations which start and end dynamic calls.
+    fn set_call_flag(&mut self, is_dynamic: bool) {
+        self.call_flag[self.step - 1] = field::ONE;
+        if is_dynamic {
+            self.dyn_flag[self.step - 1] = field::ONE;
+        }
+    }
+
     // HASH ACCUMULATOR HELPERS
     // --------------------------------------------------------------------------------------------
 
@@ -463,4 +701,10 @@ fn fill_register(register: &mut Vec<u128>, from: usize, value: u128) {
     let to = register.len();
     register.resize(from, field::ZERO);
     register.resize(to, value);
-}
\ No newline at end of file
+}
+
+fn init_register(value: u128, length: usize) -> Vec<u128> {
+    let mut register = vec![field::ZERO; length];
+    register[0] = value;
+    return register;
+}
@@ -0,0 +1,60 @@
+use crate::{ OpCode, MAX_STACK_DEPTH };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+#[derive(Clone, PartialEq)]
+pub enum ExecutionError {
+    /// Program execution was aborted because the number of executed cycles exceeded the limit;
+    /// contains the limit and the number of cycles executed before execution was aborted.
+    ResourceExceeded(usize, usize),
+
+    /// Program execution was aborted because an operation would have grown the stack beyond
+    /// MAX_STACK_DEPTH items; contains the step at which the operation was executed, the
+    /// operation itself, and the high-water marks of the stack depth reached before this step
+    /// as (step, depth) pairs - the first pair describes the initial state of the stack.
+    StackOverflow(usize, OpCode, Vec<(usize, usize)>),
+
+    /// Program execution was aborted because the host supplied a value which is not a valid
+    /// field element (either as advice for a host call, or as a value of a secret input tape);
+    /// contains the step at which the value was requested and the value itself.
+    InvalidAdvice(usize, u128),
+}
+
+// EXECUTION ERROR IMPLEMENTATION
+// ================================================================================================
+impl ExecutionError {
+
+    /// Returns the number of cycles executed before execution was aborted.
+    pub fn cycles(&self) -> usize {
+        return match self {
+            ExecutionError::ResourceExceeded(_, cycles) => *cycles,
+            ExecutionError::StackOverflow(step, _, _) => *step,
+            ExecutionError::InvalidAdvice(step, _) => *step,
+        };
+    }
+}
+
+impl std::fmt::Debug for ExecutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return match self {
+            ExecutionError::ResourceExceeded(max_cycles, cycles) => write!(f,
+                "execution error: limit of {} cycles exceeded after executing {} cycles", max_cycles, cycles),
+            ExecutionError::StackOverflow(step, op_code, history) => {
+                let history: Vec<String> = history.iter()
+                    .map(|(step, depth)| format!("{} at step {}", depth, step))
+                    .collect();
+                write!(f, "execution error: stack overflow at step {}: {} operation would grow the stack beyond {} items; \
+                    stack depth reached {}; consider dropping values which are no longer needed before the stack gets this deep",
+                    step, op_code, MAX_STACK_DEPTH, history.join(", "))
+            },
+            ExecutionError::InvalidAdvice(step, value) => write!(f,
+                "execution error: value {} supplied by the host at step {} is not a valid field element", value, step),
+        };
+    }
+}
+
+impl std::fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return std::fmt::Debug::fmt(self, f);
+    }
+}
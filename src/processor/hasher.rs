@@ -0,0 +1,152 @@
+use crate::{
+    math::field,
+    utils::hasher,
+    HASH_STATE_WIDTH, HASH_NUM_ROUNDS, BASE_CYCLE_LENGTH, HASHER_WIDTH,
+};
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Hash co-processor which computes Rescue permutations requested by the stack in a dedicated set
+/// of registers. Each permutation occupies a single 16-step cycle of the trace: the first 10 steps
+/// of a cycle apply Rescue rounds to the state, and the remaining steps hold the result of the
+/// permutation. The input of the permutation is kept in a separate set of registers for the entire
+/// cycle so that the input and the result can be received from the bus as a single message.
+/// Cycles which follow the last requested permutation compute a permutation of an all-zero state
+/// and have their activity flag (the first register) set to 0.
+pub struct Hasher {
+    registers   : Vec<Vec<u128>>,
+    step        : usize,
+}
+
+// HASHER IMPLEMENTATION
+// ================================================================================================
+impl Hasher {
+
+    /// Returns a new Hasher with enough memory allocated for each register to hold trace lengths
+    /// of `init_trace_length` steps.
+    pub fn new(init_trace_length: usize) -> Hasher {
+        let registers = vec![Vec::with_capacity(init_trace_length); HASHER_WIDTH];
+        return Hasher { registers, step: 0 };
+    }
+
+    /// Computes a permutation of the `state` in the cycle which follows the cycle of the
+    /// previously requested permutation.
+    pub fn permute(&mut self, state: [u128; HASH_STATE_WIDTH]) {
+        let cycle = build_cycle(field::ONE, state);
+        self.append_cycle(&cycle);
+    }
+
+    /// Returns the number of steps occupied by the permutations requested so far.
+    pub fn trace_length(&self) -> usize {
+        return self.step;
+    }
+
+    /// Fills all cycles between the last permutation and the end of the trace with permutations
+    /// of an all-zero state.
+    pub fn finalize_trace(&mut self, trace_length: usize) {
+        assert!(trace_length % BASE_CYCLE_LENGTH == 0,
+            "trace length must be a multiple of {}", BASE_CYCLE_LENGTH);
+        assert!(trace_length >= self.step,
+            "trace length {} is too short for {} steps of permutations", trace_length, self.step);
+
+        let empty_cycle = build_cycle(field::ZERO, [field::ZERO; HASH_STATE_WIDTH]);
+        while self.step < trace_length {
+            self.append_cycle(&empty_cycle);
+        }
+    }
+
+    /// Merges all register traces into a single vector of traces.
+    pub fn into_register_traces(self) -> Vec<Vec<u128>> {
+        return self.registers;
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+    fn append_cycle(&mut self, cycle: &[[u128; HASHER_WIDTH]]) {
+        for state in cycle.iter() {
+            for (register, &value) in self.registers.iter_mut().zip(state.iter()) {
+                register.push(value);
+            }
+        }
+        self.step += cycle.len();
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Returns states of the hasher for a full cycle of a permutation of the specified `input`; the
+/// activity `flag` is prepended and the `input` is appended to every state of the cycle.
+fn build_cycle(flag: u128, input: [u128; HASH_STATE_WIDTH]) -> Vec<[u128; HASHER_WIDTH]> {
+    let mut cycle = Vec::with_capacity(BASE_CYCLE_LENGTH);
+    let mut state = input;
+    cycle.push(build_row(flag, &state, &input));
+    for i in 0..HASH_NUM_ROUNDS {
+        hasher::apply_round(&mut state, i);
+        cycle.push(build_row(flag, &state, &input));
+    }
+    cycle.resize(BASE_CYCLE_LENGTH, build_row(flag, &state, &input));
+    return cycle;
+}
+
+fn build_row(flag: u128, state: &[u128; HASH_STATE_WIDTH], input: &[u128; HASH_STATE_WIDTH]) -> [u128; HASHER_WIDTH] {
+    let mut result = [flag; HASHER_WIDTH];
+    result[1..(HASH_STATE_WIDTH + 1)].copy_from_slice(state);
+    result[(HASH_STATE_WIDTH + 1)..].copy_from_slice(input);
+    return result;
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ utils::hasher, HASH_STATE_WIDTH, HASHER_WIDTH };
+    use super::Hasher;
+
+    #[test]
+    fn permute() {
+        let mut hasher = Hasher::new(64);
+        let state1 = [1, 2, 3, 4, 5, 6];
+        let state2 = [7, 8, 9, 10, 11, 12];
+        hasher.permute(state1);
+        hasher.permute(state2);
+        assert_eq!(32, hasher.trace_length());
+        hasher.finalize_trace(64);
+        assert_eq!(64, hasher.trace_length());
+
+        let mut expected1 = state1;
+        hasher::permute(&mut expected1);
+        let mut expected2 = state2;
+        hasher::permute(&mut expected2);
+
+        let registers = hasher.into_register_traces();
+        assert_eq!(HASHER_WIDTH, registers.len());
+        assert_eq!(vec![1; 32], &registers[0][..32]);
+        assert_eq!(vec![0; 32], &registers[0][32..]);
+        for i in 0..HASH_STATE_WIDTH {
+            let state = &registers[i + 1];
+            let input = &registers[i + 1 + HASH_STATE_WIDTH];
+            assert_eq!(64, state.len());
+            assert_eq!(state1[i], state[0]);
+            assert_eq!(expected1[i], state[10]);
+            assert_eq!(expected1[i], state[15]);
+            assert_eq!(vec![state1[i]; 16], &input[..16]);
+            assert_eq!(state2[i], state[16]);
+            assert_eq!(expected2[i], state[26]);
+            assert_eq!(vec![state2[i]; 16], &input[16..32]);
+            assert_eq!(0, state[32]);
+            assert_eq!(vec![0; 32], &input[32..]);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn finalize_short_trace() {
+        let mut hasher = Hasher::new(64);
+        hasher.permute([0; HASH_STATE_WIDTH]);
+        hasher.permute([0; HASH_STATE_WIDTH]);
+        hasher.finalize_trace(16);
+    }
+}
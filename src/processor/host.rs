@@ -0,0 +1,322 @@
+use std::io::{ self, Read, Write };
+use serde::{ Serialize, Deserialize };
+use crate::{ math::field, programs::blocks::{ ProgramBlock } };
+
+// HOST INTERFACE
+// ================================================================================================
+
+/// Defines an interface through which the VM can request non-deterministic advice from the host
+/// while a program is being executed. Advice is requested by executing a READ operation with
+/// `HostCall` execution hint; values supplied by the host are put at the front of tape A, and
+/// thus, are consumed by this READ operation and by the operations which follow it.
+///
+/// Values supplied by the host are not constrained by the VM in any way. It is up to the program
+/// to verify that these values are correct (e.g. by squaring a value which is claimed to be a
+/// square root of another value).
+///
+/// All values supplied by the host (including values of secret input tapes returned from
+/// `read_tape()`) must be valid field elements; if the host supplies a value which is not smaller
+/// than the field modulus, execution is aborted with `ExecutionError::InvalidAdvice` error.
+pub trait HostInterface {
+
+    /// Returns a list of values which should be put at the front of tape A in response to a host
+    /// call with the specified `id`. `stack` contains the current state of the stack with the top
+    /// of the stack in the first position. The returned list must contain at least one value.
+    fn get_advice(&mut self, id: u32, stack: &[u128]) -> Vec<u128>;
+
+    /// Returns the body of a procedure with the specified body `hash` in response to a dynamic
+    /// call; returns None if the host does not know such a procedure. The VM checks that the
+    /// hash of the returned body matches the requested hash, and thus, unlike other advice,
+    /// procedures supplied by the host are authenticated.
+    fn get_procedure(&mut self, _hash: u128) -> Option<Vec<ProgramBlock>> {
+        return None;
+    }
+
+    /// Notifies the host that an event with the specified `id` and `payload` was emitted by the
+    /// program. The event is also recorded on the output tape, and thus, is committed to by the
+    /// proof regardless of what the host does with the notification.
+    fn on_event(&mut self, _id: u32, _payload: u128) {
+    }
+
+    /// Returns the next value of secret input tape A (when `tape` is 0) or tape B (when `tape`
+    /// is 1) once all values of the tape supplied via program inputs have been consumed; returns
+    /// None if the host has no more values for the tape. This makes it possible to stream tape
+    /// values into the VM instead of materializing them as program inputs. Values are requested
+    /// only when READ operations (and the `pmpath` and `mrupdate` macros) need them.
+    fn read_tape(&mut self, _tape: usize) -> Option<u128> {
+        return None;
+    }
+}
+
+impl<F> HostInterface for F where F: FnMut(u32, &[u128]) -> Vec<u128> {
+    fn get_advice(&mut self, id: u32, stack: &[u128]) -> Vec<u128> {
+        return self(id, stack);
+    }
+}
+
+// ADVICE LOG
+// ================================================================================================
+
+/// A record of all advice supplied by a host during a single program execution, including the
+/// values of secret input tapes read from the host. The log can be used to re-execute the
+/// program without access to the original host.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AdviceLog {
+    entries : Vec<(u32, Vec<u128>)>,
+    #[serde(default)]
+    tapes   : [Vec<u128>; 2],
+}
+
+impl AdviceLog {
+
+    /// Returns an empty advice log.
+    pub fn new() -> AdviceLog {
+        return AdviceLog { entries: Vec::new(), tapes: [Vec::new(), Vec::new()] };
+    }
+
+    /// Returns the number of host calls recorded in the log.
+    pub fn len(&self) -> usize {
+        return self.entries.len();
+    }
+
+    /// Returns id of the host call and values supplied in response to it for the call at the
+    /// specified position in the log.
+    pub fn get_entry(&self, index: usize) -> (u32, &[u128]) {
+        let (id, values) = &self.entries[index];
+        return (*id, values);
+    }
+
+    /// Returns values of secret input tape A (when `tape` is 0) or tape B (when `tape` is 1)
+    /// which were read from the host.
+    pub fn get_tape(&self, tape: usize) -> &[u128] {
+        return &self.tapes[tape];
+    }
+
+    /// Writes all entries of the log to the `writer` as a stream of records which can be
+    /// replayed via `AdviceStream`. Every record consists of the id of the host call and the
+    /// number of supplied values (both as 4-byte little-endian integers), followed by the values
+    /// (as 16-byte little-endian integers). Tape values are not included; they are written
+    /// separately by `write_tape_to()`.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for (id, values) in self.entries.iter() {
+            writer.write_all(&id.to_le_bytes())?;
+            writer.write_all(&(values.len() as u32).to_le_bytes())?;
+            for value in values.iter() {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+        return Ok(());
+    }
+
+    /// Writes values of the specified secret input `tape` which were read from the host to the
+    /// `writer` as a stream of 16-byte little-endian integers which can be read by `AdviceStream`.
+    pub fn write_tape_to<W: Write>(&self, tape: usize, mut writer: W) -> io::Result<()> {
+        for value in self.tapes[tape].iter() {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        return Ok(());
+    }
+
+    fn record(&mut self, id: u32, values: &[u128]) {
+        self.entries.push((id, values.to_vec()));
+    }
+
+    fn record_tape(&mut self, tape: usize, value: u128) {
+        self.tapes[tape].push(value);
+    }
+}
+
+// RECORDING AND REPLAYING HOSTS
+// ================================================================================================
+
+/// Forwards host calls to the wrapped host and records all supplied values into an advice log.
+pub struct AdviceRecorder<'a> {
+    host    : &'a mut dyn HostInterface,
+    log     : AdviceLog,
+}
+
+impl<'a> AdviceRecorder<'a> {
+
+    pub fn new(host: &'a mut dyn HostInterface) -> AdviceRecorder<'a> {
+        return AdviceRecorder { host, log: AdviceLog::new() };
+    }
+
+    pub fn into_log(self) -> AdviceLog {
+        return self.log;
+    }
+}
+
+impl<'a> HostInterface for AdviceRecorder<'a> {
+    fn get_advice(&mut self, id: u32, stack: &[u128]) -> Vec<u128> {
+        let advice = self.host.get_advice(id, stack);
+        self.log.record(id, &advice);
+        return advice;
+    }
+
+    fn get_procedure(&mut self, hash: u128) -> Option<Vec<ProgramBlock>> {
+        return self.host.get_procedure(hash);
+    }
+
+    fn on_event(&mut self, id: u32, payload: u128) {
+        self.host.on_event(id, payload);
+    }
+
+    fn read_tape(&mut self, tape: usize) -> Option<u128> {
+        let value = self.host.read_tape(tape)?;
+        self.log.record_tape(tape, value);
+        return Some(value);
+    }
+}
+
+/// Supplies advice from a previously recorded advice log; host calls must be made in exactly
+/// the same order as they were made when the log was recorded. Procedures supplied for dynamic
+/// calls are not recorded in the log, and thus, programs which make dynamic calls cannot be
+/// replayed.
+pub struct AdviceReplay<'a> {
+    log     : &'a AdviceLog,
+    next    : usize,
+    tapes   : [usize; 2],
+}
+
+impl<'a> AdviceReplay<'a> {
+
+    pub fn new(log: &'a AdviceLog) -> AdviceReplay<'a> {
+        return AdviceReplay { log, next: 0, tapes: [0, 0] };
+    }
+
+    /// Returns true if all entries and tape values of the log have been replayed.
+    pub fn is_complete(&self) -> bool {
+        return self.next == self.log.len()
+            && self.tapes[0] == self.log.tapes[0].len()
+            && self.tapes[1] == self.log.tapes[1].len();
+    }
+}
+
+impl<'a> HostInterface for AdviceReplay<'a> {
+    fn get_advice(&mut self, id: u32, _stack: &[u128]) -> Vec<u128> {
+        assert!(self.next < self.log.len(),
+            "host call {} was not recorded in the advice log; the log contains only {} calls",
+            id, self.log.len());
+
+        let (expected_id, values) = self.log.get_entry(self.next);
+        assert!(id == expected_id,
+            "host call {} does not match host call {} recorded in the advice log at position {}",
+            id, expected_id, self.next);
+
+        self.next += 1;
+        return values.to_vec();
+    }
+
+    fn read_tape(&mut self, tape: usize) -> Option<u128> {
+        let value = self.log.tapes[tape].get(self.tapes[tape]).cloned()?;
+        self.tapes[tape] += 1;
+        return Some(value);
+    }
+}
+
+// ADVICE STREAMS
+// ================================================================================================
+
+/// Supplies advice read from a stream of records written by `AdviceLog::write_to()`; a record is
+/// read only when the host call it answers is made, and so, advice does not need to be loaded
+/// into memory before execution starts (e.g. when a program consumes millions of Merkle nodes).
+/// As with `AdviceReplay`, host calls must be made in the same order as the records appear in
+/// the stream; the stream is expected to be well-formed, and any issue with it causes a panic.
+///
+/// A stream created via `with_tapes()` also supplies values of secret input tapes A and B once
+/// the values supplied via program inputs have been consumed; every tape is read from its own
+/// reader as a sequence of 16-byte little-endian integers (as written by
+/// `AdviceLog::write_tape_to()`), and the tape ends where its reader does.
+pub struct AdviceStream<R: Read> {
+    reader  : R,
+    tapes   : [Option<R>; 2],
+    next    : usize,
+}
+
+impl<R: Read> AdviceStream<R> {
+
+    pub fn new(reader: R) -> AdviceStream<R> {
+        return AdviceStream { reader, tapes: [None, None], next: 0 };
+    }
+
+    /// Returns a stream which reads host call records from the `reader`, and values of secret
+    /// input tapes A and B from `tape_a` and `tape_b` readers respectively.
+    pub fn with_tapes(reader: R, tape_a: R, tape_b: R) -> AdviceStream<R> {
+        return AdviceStream { reader, tapes: [Some(tape_a), Some(tape_b)], next: 0 };
+    }
+
+    /// Returns the number of records read from the stream so far.
+    pub fn records_read(&self) -> usize {
+        return self.next;
+    }
+
+    fn read_bytes(&mut self, buf: &mut [u8]) {
+        if let Err(err) = self.reader.read_exact(buf) {
+            panic!("could not read record {} from the advice stream: {}", self.next, err);
+        }
+    }
+}
+
+impl<R: Read> HostInterface for AdviceStream<R> {
+    fn get_advice(&mut self, id: u32, _stack: &[u128]) -> Vec<u128> {
+        let mut header = [0u8; 8];
+        self.read_bytes(&mut header);
+        let expected_id = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let num_values = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        assert!(id == expected_id,
+            "host call {} does not match host call {} recorded in the advice stream at position {}",
+            id, expected_id, self.next);
+
+        // the number of values comes from the stream, and so, the vector grows only as values
+        // are actually read
+        let mut values = Vec::new();
+        let mut buf = [0u8; 16];
+        for _ in 0..num_values {
+            self.read_bytes(&mut buf);
+            let value = u128::from_le_bytes(buf);
+            assert!(value < field::MODULUS,
+                "record {} of the advice stream contains a value which is not a valid field element", self.next);
+            values.push(value);
+        }
+
+        self.next += 1;
+        return values;
+    }
+
+    fn read_tape(&mut self, tape: usize) -> Option<u128> {
+        let reader = self.tapes[tape].as_mut()?;
+        let value = match read_value(reader) {
+            Ok(value) => value?,
+            Err(err) => panic!("could not read a value of tape {} from the advice stream: {}", tape, err),
+        };
+        assert!(value < field::MODULUS,
+            "tape {} of the advice stream contains a value which is not a valid field element", tape);
+        return Some(value);
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Reads a single 16-byte little-endian value from the `reader`; returns None if the reader
+/// ends before the value starts.
+fn read_value<R: Read>(reader: &mut R) -> io::Result<Option<u128>> {
+    let mut buf = [0u8; 16];
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+            Err(err) => return Err(err),
+        }
+    }
+
+    if filled == 0 {
+        return Ok(None);
+    }
+    else if filled < buf.len() {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "stream ended in the middle of a value"));
+    }
+    return Ok(Some(u128::from_le_bytes(buf)));
+}
@@ -0,0 +1,669 @@
+use crate::{
+    math::field,
+    utils::{ self, hasher, zeroize },
+    programs::{ Program, ProgramInputs, SourceLocation, blocks::{ ProgramBlock, Span, Loop } },
+    HACC_NUM_ROUNDS, HASH_STATE_WIDTH, MAX_STACK_DEPTH,
+};
+use super::{ OpCode, OpHint, ExecutionError, AuditReport, CheckFailure, CheckKind };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Executes programs directly against a vector of stack values without building register
+/// traces. The interpreter goes through exactly the same sequence of steps as the trace builder
+/// (including NOOPs executed at block boundaries), but is implemented independently of it; thus,
+/// the two can be used to cross-check each other.
+struct Interpreter {
+    stack       : Vec<u128>,                // top of the stack is at the end of the vector
+    tape_a      : Vec<u128>,
+    tape_b      : Vec<u128>,
+    step        : usize,
+    op_count    : u128,
+    history     : Vec<(usize, usize)>,      // high-water marks of stack depth as (step, depth)
+    states      : Option<Vec<Vec<u128>>>,
+    failures    : Option<Vec<CheckFailure>>,    // recorded only in audit mode
+    location    : Option<SourceLocation>,       // source of the operation being executed
+    sources     : Option<Vec<Option<SourceLocation>>>,
+}
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
+/// Executes the `program` against the specified inputs and returns the state of the stack at
+/// the end of execution (top of the stack first). Returns an error if the program does not
+/// complete within `max_cycles` cycles.
+pub fn interpret(program: &Program, inputs: &ProgramInputs, max_cycles: usize) -> Result<Vec<u128>, ExecutionError> {
+    let mut interpreter = Interpreter::new(inputs, false);
+    interpreter.execute_program(program, max_cycles)?;
+    return Ok(interpreter.stack.iter().rev().cloned().collect());
+}
+
+/// Executes the `program` against the specified inputs and returns the state of the stack at
+/// every step of execution (top of the stack first). Returns an error if the program does not
+/// complete within `max_cycles` cycles.
+pub fn interpret_steps(program: &Program, inputs: &ProgramInputs, max_cycles: usize) -> Result<Vec<Vec<u128>>, ExecutionError> {
+    let mut interpreter = Interpreter::new(inputs, true);
+    interpreter.execute_program(program, max_cycles)?;
+    return Ok(interpreter.states.take().unwrap());
+}
+
+/// Executes the `program` against the specified inputs and returns the location of the assembly
+/// instruction executed at every step of execution; steps at which control flow operations or
+/// operations which were not compiled from an instruction are executed have no location.
+/// Returns an error if the program does not complete within `max_cycles` cycles.
+pub fn locate_steps(program: &Program, inputs: &ProgramInputs, max_cycles: usize) -> Result<Vec<Option<SourceLocation>>, ExecutionError> {
+    let mut interpreter = Interpreter::new(inputs, false);
+    interpreter.sources = Some(Vec::new());
+    interpreter.execute_program(program, max_cycles)?;
+    return Ok(interpreter.sources.take().unwrap());
+}
+
+/// Executes the `program` against the specified inputs in audit mode: runtime checks which fail
+/// are recorded in the returned report instead of aborting execution (see `AuditReport`).
+/// Execution is still aborted if the program does not complete within `max_cycles` cycles, or
+/// if the stack overflows.
+pub fn audit(program: &Program, inputs: &ProgramInputs, max_cycles: usize) -> AuditReport {
+    let mut interpreter = Interpreter::new(inputs, false);
+    interpreter.failures = Some(Vec::new());
+    let result = interpreter.execute_program(program, max_cycles);
+    return AuditReport {
+        failures: interpreter.failures.take().unwrap(),
+        stack   : interpreter.stack.iter().rev().cloned().collect(),
+        cycles  : interpreter.step,
+        error   : result.err().map(|err| format!("{:?}", err)),
+    };
+}
+
+// INTERPRETER IMPLEMENTATION
+// ================================================================================================
+impl Interpreter {
+
+    fn new(inputs: &ProgramInputs, record_states: bool) -> Interpreter {
+        let mut stack = inputs.get_public_inputs().to_vec();
+        stack.reverse();
+
+        // reverse secret inputs so that they are consumed in FIFO order
+        let [secret_inputs_a, secret_inputs_b] = inputs.get_secret_inputs();
+        let mut tape_a = secret_inputs_a.clone();
+        tape_a.reverse();
+        let mut tape_b = secret_inputs_b.clone();
+        tape_b.reverse();
+
+        let states = if record_states { Some(vec![stack.iter().rev().cloned().collect()]) } else { None };
+        let history = vec![(0, stack.len())];
+        return Interpreter {
+            stack, tape_a, tape_b, step: 0, op_count: 0, history, states,
+            failures: None, location: None, sources: None,
+        };
+    }
+
+    // PROGRAM STRUCTURE
+    // --------------------------------------------------------------------------------------------
+    fn execute_program(&mut self, program: &Program, max_cycles: usize) -> Result<(), ExecutionError> {
+        self.execute_blocks(program.root().body(), max_cycles)?;
+        self.close_block();
+        return Ok(());
+    }
+
+    fn execute_blocks(&mut self, blocks: &[ProgramBlock], max_cycles: usize) -> Result<(), ExecutionError> {
+        match &blocks[0] {
+            ProgramBlock::Span(block) => self.execute_span(block, true, max_cycles)?,
+            _ => panic!("first block in a sequence must be a Span block"),
+        }
+
+        for block in blocks.iter().skip(1) {
+            match block {
+                ProgramBlock::Span(block) => self.execute_span(block, false, max_cycles)?,
+                ProgramBlock::Group(block) => {
+                    self.execute_flow_op();
+                    self.execute_blocks(block.body(), max_cycles)?;
+                    self.close_block();
+                },
+                ProgramBlock::Call(block) => {
+                    self.execute_flow_op();
+                    self.execute_blocks(block.body(), max_cycles)?;
+                    self.close_block();
+                },
+                ProgramBlock::DynCall(_) => panic!("dynamic calls require a host interface"),
+                ProgramBlock::Switch(block) => {
+                    self.execute_flow_op();
+                    let condition = self.peek(0);
+                    if !is_binary(condition) {
+                        self.fail(CheckKind::NonBinary, format!("cannot select a branch based on a non-binary condition {}", condition));
+                    }
+                    match condition {
+                        1 => self.execute_blocks(block.true_branch(), max_cycles)?,
+                        _ => self.execute_blocks(block.false_branch(), max_cycles)?,
+                    };
+                    self.close_block();
+                },
+                ProgramBlock::Loop(block) => {
+                    let condition = self.peek(0);
+                    if !is_binary(condition) {
+                        self.fail(CheckKind::NonBinary, format!("cannot enter loop based on a non-binary condition {}", condition));
+                    }
+                    match condition {
+                        1 => self.execute_loop(block, max_cycles)?,
+                        _ => {
+                            self.execute_flow_op();
+                            self.execute_blocks(block.skip(), max_cycles)?;
+                            self.close_block();
+                        },
+                    }
+                },
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn execute_span(&mut self, block: &Span, is_first: bool, max_cycles: usize) -> Result<(), ExecutionError> {
+        if !is_first {
+            self.execute_user_op(OpCode::Noop, OpHint::None);
+        }
+
+        for i in 0..block.length() {
+            if self.step >= max_cycles {
+                return Err(ExecutionError::ResourceExceeded(max_cycles, self.step));
+            }
+            let (op_code, op_hint) = block.get_op(i);
+            self.location = block.get_source(i);
+            self.execute_user_op(op_code, op_hint);
+            self.location = None;
+
+            // the stack is allowed to grow beyond its limit while an operation is executed so
+            // that the overflow can be reported as an error of the operation
+            if self.stack.len() > MAX_STACK_DEPTH {
+                return Err(ExecutionError::StackOverflow(self.step - 1, op_code, self.history.clone()));
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn execute_loop(&mut self, block: &Loop, max_cycles: usize) -> Result<(), ExecutionError> {
+        self.execute_flow_op();
+        loop {
+            self.execute_blocks(block.body(), max_cycles)?;
+            let condition = self.peek(0);
+            self.execute_flow_op();
+            if !is_binary(condition) {
+                self.fail(CheckKind::NonBinary, format!("cannot exit loop based on a non-binary condition {}", condition));
+            }
+            if condition != 1 {
+                break;
+            }
+        }
+
+        match &block.skip()[0] {
+            ProgramBlock::Span(block) => self.execute_span(block, true, max_cycles)?,
+            _ => panic!("invalid skip block content: content must be a Span block"),
+        }
+
+        self.close_block();
+        return Ok(());
+    }
+
+    fn close_block(&mut self) {
+        self.execute_user_op(OpCode::Noop, OpHint::None);
+        self.execute_flow_op();
+        for _ in 0..HACC_NUM_ROUNDS {
+            self.execute_user_op(OpCode::Noop, OpHint::None);
+        }
+    }
+
+    /// Executes a control flow operation; such operations do not change the stack and are not
+    /// counted by the operation counter.
+    fn execute_flow_op(&mut self) {
+        if let Some(sources) = self.sources.as_mut() {
+            sources.push(None);
+        }
+        self.step += 1;
+        self.record_state();
+    }
+
+    // USER OPERATIONS
+    // --------------------------------------------------------------------------------------------
+    fn execute_user_op(&mut self, op_code: OpCode, op_hint: OpHint) {
+        match op_code {
+            OpCode::Begin | OpCode::Noop => (),
+
+            OpCode::Assert      => {
+                let x = self.pop(1);
+                if x != field::ONE {
+                    self.fail(CheckKind::Assertion, format!("ASSERT failed at step {}", self.step));
+                }
+            },
+            OpCode::AssertEq    => {
+                let (x, y) = (self.pop(2), self.pop(1));
+                if x != y {
+                    self.fail(CheckKind::Assertion, format!("ASSERTEQ failed at step {}", self.step));
+                }
+            },
+            OpCode::AssertEqW   => {
+                let values = self.pop_n(8);
+                if values[..4] != values[4..] {
+                    self.fail(CheckKind::Assertion, format!("ASSERTEQW failed at step {}", self.step));
+                }
+            },
+
+            OpCode::Push        => match op_hint {
+                OpHint::PushValue(value) => self.push(value),
+                _ => panic!("invalid value for PUSH operation at step {}", self.step),
+            },
+            OpCode::Clk         => self.push(self.op_count),
+            OpCode::Read        => {
+                match op_hint {
+                    OpHint::EqStart => {
+                        let (x, y) = (self.peek(0), self.peek(1));
+                        self.tape_a.push(if x == y { field::ONE } else { field::inv(field::sub(x, y)) });
+                    },
+                    OpHint::DivStart => {
+                        let (x, y) = (self.peek(0), self.peek(1));
+                        if x == field::ZERO {
+                            self.fail(CheckKind::InvalidOperand, format!("division by zero at step {}", self.step));
+                            self.tape_a.push(field::ZERO);
+                            self.tape_a.push(field::ZERO);
+                        }
+                        else {
+                            self.tape_a.push(y % x);
+                            self.tape_a.push(y / x);
+                        }
+                    },
+                    OpHint::DivWStart => {
+                        let x: Vec<u128> = (0..4).map(|i| self.peek(i)).collect();
+                        let y: Vec<u128> = (4..8).map(|i| self.peek(i)).collect();
+                        if x.iter().chain(y.iter()).any(|&limb| limb > u64::MAX as u128) {
+                            self.fail(CheckKind::InvalidOperand, format!("limb does not fit into 64 bits at step {}", self.step));
+                            self.tape_a.extend_from_slice(&[field::ZERO; 4]);
+                        }
+                        else if x.iter().all(|&limb| limb == field::ZERO) {
+                            self.fail(CheckKind::InvalidOperand, format!("division by zero at step {}", self.step));
+                            self.tape_a.extend_from_slice(&[field::ZERO; 4]);
+                        }
+                        else {
+                            let x = [x[0] as u64, x[1] as u64, x[2] as u64, x[3] as u64];
+                            let y = [y[0] as u64, y[1] as u64, y[2] as u64, y[3] as u64];
+                            let quotient = utils::div_u256(&y, &x);
+                            self.tape_a.extend(quotient.iter().map(|&limb| limb as u128));
+                        }
+                    },
+                    OpHint::HostCall(id) => panic!("host call {} at step {} is not supported by the interpreter", id, self.step),
+                    OpHint::None => (),
+                    _ => panic!("execution hint {:?} is not valid for READ operation", op_hint)
+                }
+                let value = self.read_tape_a();
+                self.push(value);
+            },
+            OpCode::Read2       => {
+                if let OpHint::PmpathStart(n) = op_hint {
+                    let idx = self.peek(2);
+                    let n = (n - 1) as usize;
+                    if self.tape_a.len() < n {
+                        self.fail(CheckKind::Advice, String::from("too few items on tape A for pmpath macro"));
+                        self.tape_a.splice(0..0, vec![field::ZERO; n - self.tape_a.len()]);
+                    }
+                    let nodes = self.tape_a.split_off(self.tape_a.len() - n);
+                    for i in 0..n {
+                        self.tape_a.push((idx >> (n - i - 1)) & 1);
+                        self.tape_a.push(nodes[i]);
+                    }
+                }
+                let value_a = self.read_tape_a();
+                let value_b = self.read_tape_b();
+                self.push(value_a);
+                self.push(value_b);
+            },
+            OpCode::Write       => { self.pop(1); },
+
+            OpCode::Dup         => { let x = self.peek(0); self.push(x); },
+            OpCode::Dup2        => for _ in 0..2 { let x = self.peek(1); self.push(x); },
+            OpCode::Dup4        => for _ in 0..4 { let x = self.peek(3); self.push(x); },
+            OpCode::Pad2        => { self.push(field::ZERO); self.push(field::ZERO); },
+
+            OpCode::Drop        => { self.pop(1); },
+            OpCode::Drop4       => { self.pop_n(4); },
+
+            OpCode::Swap        => self.swap(1),
+            OpCode::Swap2       => self.swap(2),
+            OpCode::Swap4       => self.swap(4),
+
+            OpCode::Roll4       => self.roll(4),
+            OpCode::Roll8       => self.roll(8),
+
+            OpCode::Choose      => {
+                let v = self.pop_n(3);
+                let x = self.select(v[2], v[0], v[1], "CHOOSE");
+                self.push(x);
+            },
+            OpCode::Choose2     => {
+                let v = self.pop_n(6);
+                let (x, y) = (self.select(v[4], v[1], v[3], "CHOOSE2"), self.select(v[4], v[0], v[2], "CHOOSE2"));
+                self.push(x);
+                self.push(y);
+            },
+            OpCode::CSwap2      => {
+                let v = self.pop_n(6);
+                let order = if self.select(v[4], 1, 0, "CSWAP2") == 1 { [1, 0, 3, 2] } else { [3, 2, 1, 0] };
+                for &i in order.iter() { self.push(v[i]); }
+            },
+
+            OpCode::Add         => { let (x, y) = (self.pop(2), self.pop(1)); self.push(field::add(x, y)); },
+            OpCode::Mul         => { let (x, y) = (self.pop(2), self.pop(1)); self.push(field::mul(x, y)); },
+            OpCode::Inv         => {
+                let x = self.pop(1);
+                if x == field::ZERO {
+                    self.fail(CheckKind::InvalidOperand, format!("cannot compute INV of {} at step {}", field::ZERO, self.step));
+                }
+                self.push(field::inv(x));
+            },
+            OpCode::Neg         => { let x = self.pop(1); self.push(field::neg(x)); },
+            OpCode::Not         => {
+                let x = self.pop(1);
+                let x = self.select(x, field::ZERO, field::ONE, "NOT");
+                self.push(x);
+            },
+            OpCode::And         => {
+                let (x, y) = (self.pop(2), self.pop(1));
+                if !is_binary(x) || !is_binary(y) {
+                    self.fail(CheckKind::NonBinary, format!("cannot compute AND for a non-binary value at step {}", self.step));
+                }
+                self.push(field::mul(x, y));
+            },
+            OpCode::Or          => {
+                let (x, y) = (self.pop(2), self.pop(1));
+                if !is_binary(x) || !is_binary(y) {
+                    self.fail(CheckKind::NonBinary, format!("cannot compute OR for a non-binary value at step {}", self.step));
+                }
+                self.push(if x == field::ONE || y == field::ONE { field::ONE } else { field::ZERO });
+            },
+            OpCode::ExpAcc      => self.op_expacc(op_hint),
+
+            OpCode::Eq          => {
+                let (aux, x, y) = (self.pop(3), self.pop(2), self.pop(1));
+                if x == y {
+                    self.push(field::ONE);
+                }
+                else {
+                    if aux != field::inv(field::sub(x, y)) {
+                        self.fail(CheckKind::Advice, format!("invalid AUX value for EQ operation at step {}", self.step));
+                    }
+                    self.push(field::ZERO);
+                }
+            },
+            OpCode::Cmp         => self.op_cmp(op_hint),
+            OpCode::BinAcc      => self.op_binacc(op_hint),
+
+            OpCode::RescR       => {
+                let mut state = [field::ZERO; HASH_STATE_WIDTH];
+                state.copy_from_slice(&self.pop_n(HASH_STATE_WIDTH));
+                hasher::permute(&mut state);
+                for &value in state.iter().rev() { self.push(value); }
+            },
+        }
+
+        let depth = self.stack.len();
+        if depth <= MAX_STACK_DEPTH && depth > self.history[self.history.len() - 1].1 {
+            self.history.push((self.step, depth));
+        }
+
+        if let Some(sources) = self.sources.as_mut() {
+            sources.push(self.location);
+        }
+        self.step += 1;
+        self.op_count += 1;
+        self.record_state();
+    }
+
+    fn op_expacc(&mut self, hint: OpHint) {
+        if let OpHint::ExpStart(n) = hint {
+            let exponent = self.peek(6);
+            for i in 0..n {
+                self.tape_a.push((exponent >> (n - i - 1)) & 1);
+            }
+        }
+
+        let bit = self.read_bit_a();
+        let v = self.pop_n(6);
+        let (multiplier, power_of_two, result, acc, base_power) = (v[1], v[2], v[3], v[4], v[5]);
+
+        self.push(field::mul(base_power, base_power));
+        self.push(field::add(acc, field::mul(bit, power_of_two)));
+        self.push(field::mul(result, multiplier));
+        self.push(field::mul(power_of_two, 2));
+        self.push(if bit == field::ONE { base_power } else { field::ONE });
+        self.push(bit);
+    }
+
+    fn op_cmp(&mut self, hint: OpHint) {
+        if let OpHint::CmpStart(n) = hint {
+            let (a_val, b_val) = (self.peek(8), self.peek(9));
+            for i in 0..n {
+                self.tape_a.push((a_val >> i) & 1);
+                self.tape_b.push((b_val >> i) & 1);
+            }
+        }
+
+        let a_bit = self.read_bit_a();
+        let b_bit = self.read_tape_b();
+        if !is_binary(b_bit) {
+            self.fail(CheckKind::NonBinary, format!("expected binary input at step {} but received: {}", self.step, b_bit));
+        }
+
+        let v = self.pop_n(8);
+        let power_of_two = v[0];
+        if !power_of_two.is_power_of_two() {
+            self.fail(CheckKind::InvalidOperand,
+                format!("expected top of the stack at step {} to be a power of 2, but received {}", self.step, power_of_two));
+        }
+        let next_power_of_two = if power_of_two == 1 { field::div(1, 2) } else { power_of_two >> 1 };
+
+        // the result of comparison is determined by the most significant differing bit
+        let (gt, lt) = (v[4], v[5]);
+        let not_set = field::mul(field::sub(field::ONE, gt), field::sub(field::ONE, lt));
+        let (gt, lt) = match (a_bit, b_bit) {
+            (1, 0) => (field::add(gt, not_set), lt),
+            (0, 1) => (gt, field::add(lt, not_set)),
+            _      => (gt, lt),
+        };
+
+        self.push(field::add(v[7], field::mul(a_bit, power_of_two)));
+        self.push(field::add(v[6], field::mul(b_bit, power_of_two)));
+        self.push(lt);
+        self.push(gt);
+        self.push(not_set);
+        self.push(b_bit);
+        self.push(a_bit);
+        self.push(next_power_of_two);
+    }
+
+    fn op_binacc(&mut self, hint: OpHint) {
+        match hint {
+            OpHint::RcStart(n) => {
+                let value = self.peek(4);
+                for i in 0..n {
+                    self.tape_a.push((value >> (n - i - 1)) & 1);
+                }
+            },
+            OpHint::MrupdateStart(n) => {
+                let idx = self.peek(8);
+                let n = (n - 1) as usize;
+                if self.tape_a.len() < n {
+                    self.fail(CheckKind::Advice, String::from("too few items on tape A for mrupdate macro"));
+                    self.tape_a.splice(0..0, vec![field::ZERO; n - self.tape_a.len()]);
+                }
+                let nodes = self.tape_a.split_off(self.tape_a.len() - n);
+                for i in 0..n {
+                    self.tape_a.push(nodes[i]);
+                    self.tape_a.push((idx >> (n - i - 1)) & 1);
+                }
+            },
+            _ => (),
+        }
+
+        let bit = self.read_bit_a();
+        let v = self.pop_n(4);
+        let power_of_two = v[2];
+        if !power_of_two.is_power_of_two() {
+            self.fail(CheckKind::InvalidOperand,
+                format!("expected 3rd value from the top of the stack at step {} to be a power of 2, but received {}",
+                self.step, power_of_two));
+        }
+
+        self.push(field::add(v[3], field::mul(bit, power_of_two)));
+        self.push(field::mul(power_of_two, 2));
+        self.push(field::ZERO);
+        self.push(bit);
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+
+    /// Reports a failed runtime check: in audit mode, the failure is recorded and execution
+    /// continues; otherwise, execution is aborted with the specified message followed by the
+    /// location of the instruction being executed, if it is known.
+    fn fail(&mut self, kind: CheckKind, message: String) {
+        let location = self.location;
+        match self.failures.as_mut() {
+            Some(failures) => failures.push(CheckFailure { step: self.step, kind, message, location }),
+            None => match location {
+                Some(location) => panic!("{} ({})", message, location),
+                None => panic!("{}", message),
+            },
+        }
+    }
+
+    /// Makes sure the stack contains at least `n` values; missing values are assumed to be ZEROs
+    /// at the bottom of the stack.
+    fn require(&mut self, n: usize) {
+        if self.stack.len() < n {
+            self.fail(CheckKind::Underflow, format!("stack underflow at step {}", self.step));
+            self.stack.splice(0..0, vec![field::ZERO; n - self.stack.len()]);
+        }
+    }
+
+    /// Returns the value at the specified `depth` from the top of the stack.
+    fn peek(&mut self, depth: usize) -> u128 {
+        self.require(depth + 1);
+        return self.stack[self.stack.len() - depth - 1];
+    }
+
+    /// Removes the top value from the stack; the stack must contain at least `depth` values.
+    fn pop(&mut self, depth: usize) -> u128 {
+        self.require(depth);
+        return self.stack.pop().unwrap();
+    }
+
+    /// Removes `n` values from the top of the stack and returns them top first.
+    fn pop_n(&mut self, n: usize) -> Vec<u128> {
+        self.require(n);
+        let mut values = self.stack.split_off(self.stack.len() - n);
+        values.reverse();
+        return values;
+    }
+
+    fn push(&mut self, value: u128) {
+        self.stack.push(value);
+    }
+
+    /// Swaps the top `n` values of the stack with the `n` values below them.
+    fn swap(&mut self, n: usize) {
+        let v = self.pop_n(2 * n);
+        for &value in v[..n].iter().rev() { self.push(value); }
+        for &value in v[n..].iter().rev() { self.push(value); }
+    }
+
+    /// Moves the `n`th value from the top of the stack to the top of the stack.
+    fn roll(&mut self, n: usize) {
+        self.require(n);
+        let value = self.stack.remove(self.stack.len() - n);
+        self.stack.push(value);
+    }
+
+    fn read_tape_a(&mut self) -> u128 {
+        return match self.tape_a.pop() {
+            Some(value) => value,
+            None => {
+                self.fail(CheckKind::Advice, format!("attempt to read from empty tape A at step {}", self.step));
+                field::ZERO
+            }
+        };
+    }
+
+    fn read_tape_b(&mut self) -> u128 {
+        return match self.tape_b.pop() {
+            Some(value) => value,
+            None => {
+                self.fail(CheckKind::Advice, format!("attempt to read from empty tape B at step {}", self.step));
+                field::ZERO
+            }
+        };
+    }
+
+    fn read_bit_a(&mut self) -> u128 {
+        let bit = self.read_tape_a();
+        if !is_binary(bit) {
+            self.fail(CheckKind::NonBinary, format!("expected binary input at step {} but received: {}", self.step, bit));
+        }
+        return bit;
+    }
+
+    /// Returns `if_one` if the `condition` is 1, and `if_zero` if the condition is 0; a
+    /// non-binary condition is reported as a failed check and is treated as 0.
+    fn select(&mut self, condition: u128, if_one: u128, if_zero: u128, op_name: &str) -> u128 {
+        if !is_binary(condition) {
+            self.fail(CheckKind::NonBinary, format!("{} on a non-binary condition at step {}", op_name, self.step));
+        }
+        return if condition == field::ONE { if_one } else { if_zero };
+    }
+
+    fn record_state(&mut self) {
+        if let Some(states) = self.states.as_mut() {
+            states.push(self.stack.iter().rev().cloned().collect());
+        }
+    }
+}
+
+/// Values on the stack and remaining values of the input tapes are zeroized when the
+/// interpreter is dropped.
+impl Drop for Interpreter {
+    fn drop(&mut self) {
+        zeroize(&mut self.stack);
+        zeroize(&mut self.tape_a);
+        zeroize(&mut self.tape_b);
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+fn is_binary(value: u128) -> bool {
+    return value == field::ZERO || value == field::ONE;
+}
+
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ programs::assembly, ProgramInputs };
+
+    #[test]
+    fn interpret_program() {
+        let program = assembly::compile(
+            "begin mul read while.true dup mul read end end").unwrap();
+        let inputs = ProgramInputs::new(&[5, 3], &[1, 0], &[]);
+        assert_eq!(vec![225], super::interpret(&program, &inputs, usize::MAX).unwrap());
+
+        // states are recorded at every step of execution
+        let (trace, _, _, _) = crate::processor::execute(&program, &inputs, usize::MAX).unwrap();
+        let states = super::interpret_steps(&program, &inputs, usize::MAX).unwrap();
+        assert!(states.len() <= trace[0].len());
+        assert_eq!(vec![5, 3], states[0]);
+        assert_eq!(vec![225], states[states.len() - 1]);
+
+        // execution is aborted once the limit of cycles is exceeded
+        let inputs = ProgramInputs::new(&[5, 3], &[1, 1, 1, 1, 1, 0], &[]);
+        assert!(super::interpret(&program, &inputs, 32).is_err());
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     math::field,
     programs::{ Program, ProgramInputs, blocks::{ ProgramBlock, Span, Loop } },
-    MIN_TRACE_LENGTH, HACC_NUM_ROUNDS,
+    MIN_TRACE_LENGTH, HACC_NUM_ROUNDS, BASE_CYCLE_LENGTH,
 };
 
 // RE-EXPORTS
@@ -13,105 +13,328 @@ pub use decoder::{ Decoder };
 mod stack;
 pub use stack::{ Stack };
 
+mod hasher;
+pub use hasher::{ Hasher };
+
+// the range checker is not yet included in the execution trace (see stark/constraints/README.md)
+#[cfg(test)]
+mod range;
+#[cfg(test)]
+pub use range::{ RangeChecker, RANGE_TABLE_SIZE };
+
 pub mod opcodes;
 pub use opcodes::{ UserOps as OpCode, OpHint };
 
+mod errors;
+pub use errors::{ ExecutionError };
+
+mod host;
+pub use host::{ HostInterface, AdviceLog, AdviceRecorder, AdviceReplay, AdviceStream };
+
+mod interpreter;
+pub use interpreter::{ interpret, interpret_steps, locate_steps, audit };
+
+mod audit;
+pub use audit::{ AuditReport, CheckFailure, CheckKind };
+
+mod checkpoint;
+pub use checkpoint::{ Checkpoint };
+
+// CONSTANTS
+// ================================================================================================
+
+/// positions of a checkpoint within a Loop block: in the skip block executed instead of the loop,
+/// in the loop body, and in the skip block executed after the loop was exited
+const LOOP_SKIP: usize = 0;
+const LOOP_BODY: usize = 1;
+const LOOP_EXIT: usize = 2;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Keeps track of the position from which execution is resumed, and of the step at which
+/// execution should be suspended.
+struct Cursor {
+    program_hash    : [u8; 32],
+    resume_path     : Vec<usize>,       // next position is at the end of the vector
+    base_step       : usize,
+    suspend_step    : usize,
+    dyncall_depth   : usize,            // number of dynamic calls which are being executed
+}
+
+/// Reasons for which execution of a program can stop before the program completes.
+enum Interrupt {
+    Failed(ExecutionError),
+    Suspended(Checkpoint),
+}
+
 // PUBLIC FUNCTIONS
 // ================================================================================================
 
 /// Returns register traces resulting from executing the `program` against the specified inputs.
-pub fn execute(program: &Program, inputs: &ProgramInputs) -> (Vec<Vec<u128>>, usize, usize)
+/// Returns an error if the program does not complete within `max_cycles` cycles, or if it
+/// overflows the stack.
+pub fn execute(program: &Program, inputs: &ProgramInputs, max_cycles: usize) -> Result<(Vec<Vec<u128>>, usize, usize, usize), ExecutionError>
 {
-    // initialize decoder and stack components
-    let mut decoder = Decoder::new(MIN_TRACE_LENGTH);
-    let mut stack = Stack::new(inputs, MIN_TRACE_LENGTH);
+    let stack = Stack::new(inputs, MIN_TRACE_LENGTH);
+    return execute_program(program, Decoder::new(MIN_TRACE_LENGTH), stack, Cursor::new(program), max_cycles)
+        .map_err(|interrupt| interrupt.into_error());
+}
+
+/// Returns register traces resulting from executing the `program` in the same way as `execute()`
+/// does, but the traces are padded with NOOP operations to at least `trace_length` steps; this is
+/// used to align execution traces of programs which are proven together.
+pub fn execute_padded(program: &Program, inputs: &ProgramInputs, trace_length: usize, max_cycles: usize)
+    -> Result<(Vec<Vec<u128>>, usize, usize, usize), ExecutionError>
+{
+    assert!(trace_length.is_power_of_two(), "trace length must be a power of 2");
+    let trace_length = std::cmp::max(trace_length, MIN_TRACE_LENGTH);
+    let stack = Stack::new(inputs, trace_length);
+    return execute_program(program, Decoder::new(trace_length), stack, Cursor::new(program), max_cycles)
+        .map_err(|interrupt| interrupt.into_error());
+}
+
+/// Returns register traces resulting from executing the `program` against the specified inputs;
+/// advice requested by the program is supplied by the `host`. Returns an error if the program
+/// does not complete within `max_cycles` cycles.
+pub fn execute_with_host(program: &Program, inputs: &ProgramInputs, host: &mut dyn HostInterface, max_cycles: usize)
+    -> Result<(Vec<Vec<u128>>, usize, usize, usize), ExecutionError>
+{
+    let stack = Stack::with_host(inputs, MIN_TRACE_LENGTH, host);
+    return execute_program(program, Decoder::new(MIN_TRACE_LENGTH), stack, Cursor::new(program), max_cycles)
+        .map_err(|interrupt| interrupt.into_error());
+}
+
+/// Executes the `program` against the specified inputs until the first step at or after the
+/// specified `step` at which a checkpoint can be captured, and returns the checkpoint. Returns
+/// None if the program completes before such a step is reached.
+pub fn execute_until(program: &Program, inputs: &ProgramInputs, host: Option<&mut dyn HostInterface>, step: usize, max_cycles: usize)
+    -> Result<Option<Checkpoint>, ExecutionError>
+{
+    let stack = match host {
+        Some(host) => Stack::with_host(inputs, MIN_TRACE_LENGTH, host),
+        None => Stack::new(inputs, MIN_TRACE_LENGTH),
+    };
+    let cursor = Cursor::new(program).suspend_at(step);
+    return into_checkpoint(execute_program(program, Decoder::new(MIN_TRACE_LENGTH), stack, cursor, max_cycles));
+}
+
+/// Resumes execution of the `program` from the specified `checkpoint`, and returns register
+/// traces for all steps starting with the step of the checkpoint. Returns an error if the
+/// program does not complete within `max_cycles` cycles after the checkpoint.
+pub fn resume(program: &Program, checkpoint: &Checkpoint, host: Option<&mut dyn HostInterface>, max_cycles: usize)
+    -> Result<(Vec<Vec<u128>>, usize, usize, usize), ExecutionError>
+{
+    let decoder = Decoder::from_state(checkpoint.decoder_state(), MIN_TRACE_LENGTH);
+    let stack = Stack::from_state(checkpoint.stack_state(), MIN_TRACE_LENGTH, host);
+    let cursor = Cursor::resume_from(program, checkpoint);
+    return execute_program(program, decoder, stack, cursor, max_cycles)
+        .map_err(|interrupt| interrupt.into_error());
+}
+
+/// Resumes execution of the `program` from the specified `checkpoint` and captures a new
+/// checkpoint in the same way as `execute_until()` function does.
+pub fn resume_until(program: &Program, checkpoint: &Checkpoint, host: Option<&mut dyn HostInterface>, step: usize, max_cycles: usize)
+    -> Result<Option<Checkpoint>, ExecutionError>
+{
+    let decoder = Decoder::from_state(checkpoint.decoder_state(), MIN_TRACE_LENGTH);
+    let stack = Stack::from_state(checkpoint.stack_state(), MIN_TRACE_LENGTH, host);
+    let cursor = Cursor::resume_from(program, checkpoint).suspend_at(step);
+    return into_checkpoint(execute_program(program, decoder, stack, cursor, max_cycles));
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+fn execute_program(program: &Program, mut decoder: Decoder, mut stack: Stack, mut cursor: Cursor, max_cycles: usize)
+    -> Result<(Vec<Vec<u128>>, usize, usize, usize), Interrupt>
+{
+    // initialize hasher component
+    let mut hasher = Hasher::new(MIN_TRACE_LENGTH);
 
     // execute body of the program
-    execute_blocks(program.root().body(), &mut decoder, &mut stack);
+    execute_blocks(program.root().body(), &mut decoder, &mut stack, &mut hasher, &mut cursor, max_cycles)?;
     close_block(&mut decoder, &mut stack, field::ZERO, true);
 
-    // fill in remaining steps to make sure the length of the trace is a power of 2
+    // fill in remaining steps to make sure the length of the trace is a power of 2; the hasher
+    // may need more steps for the requested permutations than the decoder and the stack
+    let trace_length = hasher.trace_length().next_power_of_two();
+    decoder.extend_trace(trace_length);
+    stack.extend_trace(trace_length);
     decoder.finalize_trace();
     stack.finalize_trace();
+    hasher.finalize_trace(stack.trace_length());
 
-    // save context and loop depths into variables before decoder is consumed
+    // save context, loop, and call depths into variables before decoder is consumed
     let context_depth = decoder.max_ctx_stack_depth();
     let loop_depth = decoder.max_loop_stack_depth();
+    let call_depth = decoder.max_call_stack_depth();
 
-    // merge decoder and stack register traces into a single vector
+    // merge decoder, stack, and hasher register traces into a single vector
     let mut register_traces = decoder.into_register_traces();
     register_traces.append(&mut stack.into_register_traces());
+    register_traces.append(&mut hasher.into_register_traces());
 
-    return (register_traces, context_depth, loop_depth);
+    return Ok((register_traces, context_depth, loop_depth, call_depth));
 }
 
-// HELPER FUNCTIONS
-// ================================================================================================
-fn execute_blocks(blocks: &[ProgramBlock], decoder: &mut Decoder, stack: &mut Stack)
+fn execute_blocks(blocks: &[ProgramBlock], decoder: &mut Decoder, stack: &mut Stack, hasher: &mut Hasher, cursor: &mut Cursor, max_cycles: usize)
+    -> Result<(), Interrupt>
 {
-    // execute first block in the sequence, which mast be a Span block
-    match &blocks[0] {
-        ProgramBlock::Span(block) => execute_span(block, decoder, stack, true),
-        _ => panic!("first block in a sequence must be a Span block"),
+    // when execution is resumed from a checkpoint, all blocks preceding the block which contains
+    // the checkpoint have already been executed
+    let start = cursor.next_position().unwrap_or(0);
+    assert!(start < blocks.len(), "checkpoint position is not valid for the program");
+
+    for (i, block) in blocks.iter().enumerate().skip(start) {
+        execute_block(block, i == 0, decoder, stack, hasher, cursor, max_cycles)
+            .map_err(|interrupt| interrupt.enclose(i))?;
     }
 
-    // execute all other blocks in the sequence one after another
-    for block in blocks.iter().skip(1) {
+    return Ok(());
+}
+
+fn execute_block(block: &ProgramBlock, is_first: bool, decoder: &mut Decoder, stack: &mut Stack, hasher: &mut Hasher, cursor: &mut Cursor, max_cycles: usize)
+    -> Result<(), Interrupt>
+{
+    // first block in a sequence must be a Span block
+    if is_first {
         match block {
-            ProgramBlock::Span(block) => execute_span(block, decoder, stack, false),
-            ProgramBlock::Group(block) => {
-                start_block(decoder, stack);
-                execute_blocks(block.body(), decoder, stack);
-                close_block(decoder, stack, field::ZERO, true);
-            },
-            ProgramBlock::Switch(block) => {
-                start_block(decoder, stack);
-                let condition = stack.get_stack_top();
-                match condition {
-                    0 => {
-                        execute_blocks(block.false_branch(), decoder, stack);
-                        close_block(decoder, stack, block.true_branch_hash(), false);
-                    },
-                    1 => {
-                        execute_blocks(block.true_branch(), decoder, stack);
-                        close_block(decoder, stack, block.false_branch_hash(), true);
-                    },
-                    _ => panic!("cannot select a branch based on a non-binary condition {}", condition)
-                };
-            },
-            ProgramBlock::Loop(block) => {
-                let condition = stack.get_stack_top();
-                match condition {
+            ProgramBlock::Span(block) => return execute_span(block, decoder, stack, hasher, true, cursor, max_cycles),
+            _ => panic!("first block in a sequence must be a Span block"),
+        }
+    }
+
+    // if execution is being resumed into this block, the block has already been started
+    let is_resumed = cursor.is_resuming();
+    match block {
+        ProgramBlock::Span(block) => execute_span(block, decoder, stack, hasher, false, cursor, max_cycles)?,
+        ProgramBlock::Group(block) => {
+            if !is_resumed { start_block(decoder, stack); }
+            execute_blocks(block.body(), decoder, stack, hasher, cursor, max_cycles)?;
+            close_block(decoder, stack, field::ZERO, true);
+        },
+        ProgramBlock::Call(block) => {
+            if !is_resumed { start_call(decoder, stack); }
+            execute_blocks(block.body(), decoder, stack, hasher, cursor, max_cycles)?;
+            close_call(decoder, stack, false);
+        },
+        ProgramBlock::DynCall(_) => {
+            assert!(!is_resumed, "checkpoint position is not valid for the program");
+            let callee_hash = stack.get_stack_top();
+            let body = stack.get_procedure(callee_hash);
+
+            start_dyncall(decoder, stack, callee_hash);
+            cursor.dyncall_depth += 1;
+            execute_blocks(&body, decoder, stack, hasher, cursor, max_cycles)?;
+            cursor.dyncall_depth -= 1;
+
+            close_call(decoder, stack, true);
+        },
+        ProgramBlock::Switch(block) => {
+            let condition = match cursor.next_position() {
+                Some(branch) => branch as u128,
+                None => {
+                    start_block(decoder, stack);
+                    stack.get_stack_top()
+                }
+            };
+            match condition {
+                0 => {
+                    execute_blocks(block.false_branch(), decoder, stack, hasher, cursor, max_cycles)
+                        .map_err(|interrupt| interrupt.enclose(0))?;
+                    close_block(decoder, stack, block.true_branch_hash(), false);
+                },
+                1 => {
+                    execute_blocks(block.true_branch(), decoder, stack, hasher, cursor, max_cycles)
+                        .map_err(|interrupt| interrupt.enclose(1))?;
+                    close_block(decoder, stack, block.false_branch_hash(), true);
+                },
+                _ => panic!("cannot select a branch based on a non-binary condition {}", condition)
+            };
+        },
+        ProgramBlock::Loop(block) => {
+            let position = match cursor.next_position() {
+                Some(position) => position,
+                None => match stack.get_stack_top() {
                     0 => {
                         start_block(decoder, stack);
-                        execute_blocks(block.skip(), decoder, stack);
-                        close_block(decoder, stack, block.body_hash(), false);
+                        LOOP_SKIP
                     },
-                    1 => execute_loop(block, decoder, stack),
-                    _ => panic!("cannot enter loop based on a non-binary condition {}", condition)
+                    1 => LOOP_BODY,
+                    condition => panic!("cannot enter loop based on a non-binary condition {}", condition)
                 }
-            },
-        }
+            };
+            if position == LOOP_SKIP {
+                execute_blocks(block.skip(), decoder, stack, hasher, cursor, max_cycles)
+                    .map_err(|interrupt| interrupt.enclose(LOOP_SKIP))?;
+                close_block(decoder, stack, block.body_hash(), false);
+            }
+            else {
+                execute_loop(block, decoder, stack, hasher, is_resumed.then(|| position), cursor, max_cycles)?;
+            }
+        },
     }
+
+    return Ok(());
 }
 
-/// Executes all instructions in a Span block.
-fn execute_span(block: &Span, decoder: &mut Decoder, stack: &mut Stack, is_first: bool)
+/// Executes all instructions in a Span block; returns an error if the number of executed cycles
+/// exceeds `max_cycles`, or if an instruction overflows the stack.
+fn execute_span(block: &Span, decoder: &mut Decoder, stack: &mut Stack, hasher: &mut Hasher, is_first: bool, cursor: &mut Cursor, max_cycles: usize)
+    -> Result<(), Interrupt>
 {
-    // if this is the first Span block in a sequence of blocks, it needs to be
-    // pre-padded with a NOOP to make sure the first instruction in the block
-    // starts executing on a step which is a multiple of 16
-    if !is_first {
-        decoder.decode_op(OpCode::Noop, field::ZERO);
-        stack.execute(OpCode::Noop, OpHint::None);
-    }
+    // when execution is resumed from a checkpoint, it starts with the instruction at which the
+    // checkpoint was captured
+    let start = match cursor.next_position() {
+        Some(start) => start,
+        None => {
+            // if this is the first Span block in a sequence of blocks, it needs to be
+            // pre-padded with a NOOP to make sure the first instruction in the block
+            // starts executing on a step which is a multiple of 16
+            if !is_first {
+                decoder.decode_op(OpCode::Noop, field::ZERO);
+                stack.execute(OpCode::Noop, OpHint::None);
+            }
+            0
+        }
+    };
+    assert!(start < block.length(), "checkpoint position is not valid for the program");
 
     // execute all other instructions in the block
-    for i in 0..block.length() {
+    for i in start..block.length() {
+        // steps taken by the hash co-processor count towards the cycle limit as well because
+        // the trace must be long enough to hold all of them
+        let cycles = std::cmp::max(decoder.current_step(), hasher.trace_length());
+        if cycles >= max_cycles {
+            return Err(Interrupt::Failed(ExecutionError::ResourceExceeded(max_cycles, cycles)));
+        }
+
+        // capture a checkpoint if execution should be suspended at this step
+        if cursor.should_suspend(decoder.current_step()) {
+            return Err(Interrupt::Suspended(cursor.capture(decoder, stack, i)));
+        }
+
         let (op_code, op_hint) = block.get_op(i);
+
+        // every RESCR operation is delegated to the hash co-processor, which computes the entire
+        // permutation in a cycle of its own; the stack and the hasher exchange the input and the
+        // result of the permutation over the bus
+        if op_code == OpCode::RescR {
+            hasher.permute(stack.get_hash_state());
+        }
+
+        // CLK operation pushes the value of the operation counter onto the stack; the counter
+        // must be read before the operation is decoded
+        let op_hint = if op_code == OpCode::Clk { OpHint::OpCount(decoder.op_count()) } else { op_hint };
+
         decoder.decode_op(op_code, op_hint.value());
         stack.execute(op_code, op_hint);
+        if let Some(err) = stack.error() {
+            return Err(Interrupt::Failed(err.clone()));
+        }
     }
+
+    return Ok(());
 }
 
 /// Starts executing a new program block.
@@ -142,40 +365,166 @@ fn close_block(decoder: &mut Decoder, stack: &mut Stack, sibling_hash: u128, is_
     }
 }
 
-/// Executes the specified loop.
-fn execute_loop(block: &Loop, decoder: &mut Decoder, stack: &mut Stack)
+/// Starts executing a procedure call.
+fn start_call(decoder: &mut Decoder, stack: &mut Stack)
 {
-    // mark the beginning of the loop block
-    decoder.start_loop(block.image());
+    decoder.start_call();
+    stack.execute(OpCode::Noop, OpHint::None);
+}
+
+/// Starts executing a dynamic call of a procedure with the specified body hash.
+fn start_dyncall(decoder: &mut Decoder, stack: &mut Stack, callee_hash: u128)
+{
+    decoder.start_dyncall(callee_hash);
     stack.execute(OpCode::Noop, OpHint::None);
+}
 
-    // execute blocks in loop body until top of the stack becomes 0
-    loop {
-        execute_blocks(block.body(), decoder, stack);
+/// Returns from the currently executing procedure call; the body of the procedure is padded in
+/// the same way as the body of a block closed via close_block() function.
+fn close_call(decoder: &mut Decoder, stack: &mut Stack, is_dynamic: bool)
+{
+    decoder.decode_op(OpCode::Noop, field::ZERO);
+    stack.execute(OpCode::Noop, OpHint::None);
 
-        let condition = stack.get_stack_top();
-        match condition {
-            0 => {
-                decoder.break_loop();
-                stack.execute(OpCode::Noop, OpHint::None);
-                break;
-            },
-            1 => {
-                decoder.wrap_loop();
-                stack.execute(OpCode::Noop, OpHint::None);
-            },
-            _ => panic!("cannot exit loop based on a non-binary condition {}", condition)
-        };
+    if is_dynamic { decoder.end_dyncall(); } else { decoder.end_call(); }
+    stack.execute(OpCode::Noop, OpHint::None);
+
+    for _ in 0..HACC_NUM_ROUNDS {
+        decoder.decode_op(OpCode::Noop, field::ZERO);
+        stack.execute(OpCode::Noop, OpHint::None);
+    }
+}
+
+/// Executes the specified loop; if `resume_position` is provided, execution is resumed either
+/// in the loop body or in the skip block which follows the loop.
+fn execute_loop(block: &Loop, decoder: &mut Decoder, stack: &mut Stack, hasher: &mut Hasher, resume_position: Option<usize>, cursor: &mut Cursor, max_cycles: usize)
+    -> Result<(), Interrupt>
+{
+    // mark the beginning of the loop block
+    if resume_position.is_none() {
+        decoder.start_loop(block.image());
+        stack.execute(OpCode::Noop, OpHint::None);
+    }
+
+    // execute blocks in loop body until top of the stack becomes 0
+    if resume_position != Some(LOOP_EXIT) {
+        loop {
+            execute_blocks(block.body(), decoder, stack, hasher, cursor, max_cycles)
+                .map_err(|interrupt| interrupt.enclose(LOOP_BODY))?;
+
+            let condition = stack.get_stack_top();
+            match condition {
+                0 => {
+                    decoder.break_loop();
+                    stack.execute(OpCode::Noop, OpHint::None);
+                    break;
+                },
+                1 => {
+                    decoder.wrap_loop();
+                    stack.execute(OpCode::Noop, OpHint::None);
+                },
+                _ => panic!("cannot exit loop based on a non-binary condition {}", condition)
+            };
+        }
     }
 
     // execute the contents of the skip block to make sure the loop was exited correctly
     match &block.skip()[0] {
-        ProgramBlock::Span(block) => execute_span(block, decoder, stack, true),
+        ProgramBlock::Span(block) => execute_span(block, decoder, stack, hasher, true, cursor, max_cycles)
+            .map_err(|interrupt| interrupt.enclose(LOOP_EXIT))?,
         _ => panic!("invalid skip block content: content must be a Span block"),
     }
 
     // close block
     close_block(decoder, stack, block.skip_hash(), true);
+    return Ok(());
+}
+
+/// Converts the result of an execution which was expected to be suspended into a checkpoint.
+fn into_checkpoint(result: Result<(Vec<Vec<u128>>, usize, usize, usize), Interrupt>) -> Result<Option<Checkpoint>, ExecutionError> {
+    return match result {
+        Ok(_) => Ok(None),
+        Err(Interrupt::Suspended(checkpoint)) => Ok(Some(checkpoint)),
+        Err(Interrupt::Failed(err)) => Err(err),
+    };
+}
+
+// CURSOR IMPLEMENTATION
+// ================================================================================================
+impl Cursor {
+
+    fn new(program: &Program) -> Cursor {
+        return Cursor {
+            program_hash    : *program.hash(),
+            resume_path     : Vec::new(),
+            base_step       : 0,
+            suspend_step    : usize::MAX,
+            dyncall_depth   : 0,
+        };
+    }
+
+    fn resume_from(program: &Program, checkpoint: &Checkpoint) -> Cursor {
+        assert!(program.hash() == checkpoint.program_hash(),
+            "checkpoint was not captured for program {}", hex::encode(program.hash()));
+        let mut cursor = Cursor::new(program);
+        cursor.resume_path = checkpoint.position().iter().rev().cloned().collect();
+        cursor.base_step = checkpoint.step();
+        return cursor;
+    }
+
+    fn suspend_at(mut self, step: usize) -> Cursor {
+        self.suspend_step = step;
+        return self;
+    }
+
+    /// Returns true if execution is being resumed into the block which is about to be executed.
+    fn is_resuming(&self) -> bool {
+        return !self.resume_path.is_empty();
+    }
+
+    /// Returns the next position along the path to the resumed checkpoint, if any.
+    fn next_position(&mut self) -> Option<usize> {
+        return self.resume_path.pop();
+    }
+
+    /// Returns true if a checkpoint should be captured at the specified local step; checkpoints
+    /// are not captured inside dynamic calls because bodies of dynamically called procedures are
+    /// not a part of the program, and thus, execution could not be resumed into them.
+    fn should_suspend(&self, step: usize) -> bool {
+        let step = self.base_step + step;
+        return step >= self.suspend_step && step % BASE_CYCLE_LENGTH == 0 && self.dyncall_depth == 0;
+    }
+
+    /// Captures a checkpoint before the operation at the specified position of a Span block.
+    fn capture(&self, decoder: &Decoder, stack: &Stack, op_index: usize) -> Checkpoint {
+        let step = self.base_step + decoder.current_step();
+        let mut checkpoint = Checkpoint::new(self.program_hash, step, decoder.save_state(), stack.save_state());
+        checkpoint.enclose(op_index);
+        return checkpoint;
+    }
+}
+
+// INTERRUPT IMPLEMENTATION
+// ================================================================================================
+impl Interrupt {
+
+    /// Records the `index` of the block which encloses a suspended operation into the checkpoint.
+    fn enclose(self, index: usize) -> Interrupt {
+        return match self {
+            Interrupt::Suspended(mut checkpoint) => {
+                checkpoint.enclose(index);
+                Interrupt::Suspended(checkpoint)
+            },
+            interrupt => interrupt,
+        };
+    }
+
+    fn into_error(self) -> ExecutionError {
+        return match self {
+            Interrupt::Failed(err) => err,
+            Interrupt::Suspended(_) => unreachable!("execution cannot be suspended without a suspension step"),
+        };
+    }
 }
 
 // TESTS
@@ -185,19 +534,19 @@ fn execute_loop(block: &Loop, decoder: &mut Decoder, stack: &mut Stack)
 mod tests {
 
     use crate::{ programs::assembly, stark::TraceState, utils::as_bytes };
-    use super::{ ProgramInputs };
+    use super::{ ProgramInputs, Checkpoint, BASE_CYCLE_LENGTH };
 
     #[test]
     fn execute_span() {
         let program = assembly::compile("begin add push.5 mul push.7 end").unwrap();
         let inputs = ProgramInputs::from_public(&[1, 2]);
 
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth, call_depth) = super::execute(&program, &inputs, usize::MAX).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(64, trace_length);
-        assert_eq!(17, trace.len());
-        let mut state = build_trace_state(trace.len(), ctx_depth, loop_depth) ;
+        assert_eq!(30, trace.len());
+        let mut state = build_trace_state(trace.len(), ctx_depth, loop_depth, call_depth);
         state.update_from_trace(&trace, trace_length - 1);
 
         assert_eq!(46, state.op_counter());
@@ -209,18 +558,42 @@ mod tests {
         assert_eq!([7, 15, 0, 0, 0, 0, 0, 0], state.user_stack());
     }
 
+    #[test]
+    fn execute_hash_permutations() {
+        let program = assembly::compile("begin hashr.5 end").unwrap();
+        let inputs = ProgramInputs::from_public(&[1, 2, 3, 4, 5, 6]);
+
+        // the hasher needs 80 steps for 5 permutations, and so, the trace is extended beyond the
+        // steps taken by the decoder and the stack
+        let (trace, ctx_depth, loop_depth, call_depth) = super::execute(&program, &inputs, usize::MAX).unwrap();
+        let trace_length = trace[0].len();
+        assert_eq!(128, trace_length);
+        assert!(trace.iter().all(|register| register.len() == trace_length));
+
+        let mut state = build_trace_state(trace.len(), ctx_depth, loop_depth, call_depth);
+        state.update_from_trace(&trace, 79);
+        assert_eq!(1, state.hasher_flag());
+        assert_eq!(&state.user_stack()[..6], state.hasher_state());
+        state.update_from_trace(&trace, 80);
+        assert_eq!(0, state.hasher_flag());
+
+        // steps taken by the hasher count towards the cycle limit
+        let error = super::execute(&program, &inputs, 64).err().unwrap();
+        assert!(matches!(error, super::ExecutionError::ResourceExceeded(64, 64)));
+    }
+
     #[test]
     fn execute_block() {
         let program = assembly::compile("begin add block push.5 mul push.7 end end").unwrap();
         let inputs = ProgramInputs::from_public(&[1, 2]);
 
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth, call_depth) = super::execute(&program, &inputs, usize::MAX).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(64, trace_length);
-        assert_eq!(18, trace.len());
+        assert_eq!(31, trace.len());
 
-        let mut state = build_trace_state(trace.len(), ctx_depth, loop_depth) ;
+        let mut state = build_trace_state(trace.len(), ctx_depth, loop_depth, call_depth);
         state.update_from_trace(&trace, trace_length - 1);
         
         assert_eq!(60, state.op_counter());
@@ -240,13 +613,13 @@ mod tests {
         
         // execute true branch
         let inputs = ProgramInputs::new(&[5, 3], &[1], &[]);
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth, call_depth) = super::execute(&program, &inputs, usize::MAX).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(128, trace_length);
-        assert_eq!(19, trace.len());
+        assert_eq!(32, trace.len());
 
-        let mut state = build_trace_state(trace.len(), ctx_depth, loop_depth) ;
+        let mut state = build_trace_state(trace.len(), ctx_depth, loop_depth, call_depth);
         state.update_from_trace(&trace, trace_length - 1);
 
         assert_eq!(76, state.op_counter());
@@ -260,13 +633,13 @@ mod tests {
 
         // execute false branch
         let inputs = ProgramInputs::new(&[5, 3], &[0], &[]);
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth, call_depth) = super::execute(&program, &inputs, usize::MAX).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(128, trace_length);
-        assert_eq!(19, trace.len());
+        assert_eq!(32, trace.len());
 
-        let mut state = build_trace_state(trace.len(), ctx_depth, loop_depth) ;
+        let mut state = build_trace_state(trace.len(), ctx_depth, loop_depth, call_depth);
         state.update_from_trace(&trace, trace_length - 1);
 
         assert_eq!(92, state.op_counter());
@@ -286,13 +659,13 @@ mod tests {
 
         // don't enter the loop
         let inputs = ProgramInputs::new(&[5, 3], &[0], &[]);
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth, call_depth) = super::execute(&program, &inputs, usize::MAX).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(64, trace_length);
-        assert_eq!(18, trace.len());
+        assert_eq!(31, trace.len());
 
-        let mut state = build_trace_state(trace.len(), ctx_depth, loop_depth) ;
+        let mut state = build_trace_state(trace.len(), ctx_depth, loop_depth, call_depth);
         state.update_from_trace(&trace, trace_length - 1);
 
         assert_eq!(60, state.op_counter());
@@ -306,13 +679,13 @@ mod tests {
 
         // execute one iteration
         let inputs = ProgramInputs::new(&[5, 3], &[1, 0], &[]);
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth, call_depth) = super::execute(&program, &inputs, usize::MAX).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(128, trace_length);
-        assert_eq!(19, trace.len());
+        assert_eq!(32, trace.len());
 
-        let mut state = build_trace_state(trace.len(), ctx_depth, loop_depth) ;
+        let mut state = build_trace_state(trace.len(), ctx_depth, loop_depth, call_depth);
         state.update_from_trace(&trace, trace_length - 1);
 
         assert_eq!(75, state.op_counter());
@@ -326,13 +699,13 @@ mod tests {
 
         // execute five iteration
         let inputs = ProgramInputs::new(&[5, 3], &[1, 1, 1, 1, 1, 0], &[]);
-        let (trace, ctx_depth, loop_depth) = super::execute(&program, &inputs);
+        let (trace, ctx_depth, loop_depth, call_depth) = super::execute(&program, &inputs, usize::MAX).unwrap();
         let trace_length = trace[0].len();
 
         assert_eq!(256, trace_length);
-        assert_eq!(19, trace.len());
+        assert_eq!(32, trace.len());
 
-        let mut state = build_trace_state(trace.len(), ctx_depth, loop_depth) ;
+        let mut state = build_trace_state(trace.len(), ctx_depth, loop_depth, call_depth);
         state.update_from_trace(&trace, trace_length - 1);
 
         assert_eq!(135, state.op_counter());
@@ -345,9 +718,67 @@ mod tests {
         assert_eq!([43143988327398919500410556793212890625, 0, 0, 0, 0, 0, 0, 0], state.user_stack());
     }
 
-    fn build_trace_state(num_registers: usize, ctx_depth: usize, loop_depth: usize) -> TraceState {
-        let decoder_width = TraceState::compute_decoder_width(ctx_depth, loop_depth);
-        let stack_depth = num_registers - decoder_width;
-        return TraceState::new(ctx_depth, loop_depth, stack_depth);
+    #[test]
+    fn resume_from_checkpoint() {
+        let program = assembly::compile("
+            begin
+                mul read if.true add push.3 else push.7 add push.8 end
+                read while.true dup mul block push.1 add end read end
+                push.5 hash.2
+            end").unwrap();
+
+        for secret_a in [vec![1, 1, 1, 1, 0], vec![0, 0]].iter() {
+            let inputs = ProgramInputs::new(&[5, 3, 2], secret_a, &[]);
+            let (trace, ctx_depth, loop_depth, call_depth) = super::execute(&program, &inputs, usize::MAX).unwrap();
+
+            // resumed execution must produce the same decoder and stack register traces as the
+            // original execution from the step of the checkpoint onward; the hasher computes
+            // permutations in cycles of its own, and so, its trace is not aligned with the steps
+            // of the checkpoint
+            let mut num_checkpoints = 0;
+            for step in (0..trace[0].len()).step_by(BASE_CYCLE_LENGTH) {
+                let checkpoint = match super::execute_until(&program, &inputs, None, step, usize::MAX).unwrap() {
+                    Some(checkpoint) => checkpoint,
+                    None => break,
+                };
+                assert!(checkpoint.step() >= step);
+                num_checkpoints += 1;
+
+                let (resumed, resumed_ctx_depth, resumed_loop_depth, resumed_call_depth) = super::resume(&program, &checkpoint, None, usize::MAX).unwrap();
+                assert_eq!((ctx_depth, loop_depth, call_depth), (resumed_ctx_depth, resumed_loop_depth, resumed_call_depth));
+                assert_eq!(trace.len(), resumed.len());
+
+                let length = std::cmp::min(resumed[0].len(), trace[0].len() - checkpoint.step());
+                let num_registers = trace.len() - crate::HASHER_WIDTH;
+                for (register, resumed_register) in trace.iter().zip(resumed.iter()).take(num_registers) {
+                    assert_eq!(register[checkpoint.step()..(checkpoint.step() + length)], resumed_register[..length]);
+                }
+            }
+            assert!(num_checkpoints > 4);
+        }
+    }
+
+    #[test]
+    fn resume_until_checkpoint() {
+        let program = assembly::compile("begin read while.true push.3 mul read end end").unwrap();
+        let inputs = ProgramInputs::new(&[1], &[1, 1, 1, 1, 1, 1, 1, 0], &[]);
+        let direct = super::execute_until(&program, &inputs, None, 100, usize::MAX).unwrap().unwrap();
+
+        // a checkpoint captured from a resumed execution is the same as the checkpoint captured
+        // directly, including after a serialization round trip
+        let first = super::execute_until(&program, &inputs, None, 40, usize::MAX).unwrap().unwrap();
+        let first: Checkpoint = bincode::deserialize(&bincode::serialize(&first).unwrap()).unwrap();
+        let second = super::resume_until(&program, &first, None, 100, usize::MAX).unwrap().unwrap();
+        assert_eq!(direct, second);
+        assert_eq!(112, second.step());
+
+        // the program completes before the requested step
+        assert_eq!(None, super::resume_until(&program, &second, None, 1000, usize::MAX).unwrap());
+    }
+
+    fn build_trace_state(num_registers: usize, ctx_depth: usize, loop_depth: usize, call_depth: usize) -> TraceState {
+        let decoder_width = TraceState::compute_decoder_width(ctx_depth, loop_depth, call_depth);
+        let stack_depth = num_registers - decoder_width - crate::HASHER_WIDTH;
+        return TraceState::new(ctx_depth, loop_depth, call_depth, stack_depth);
     }
 }
\ No newline at end of file
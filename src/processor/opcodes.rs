@@ -1,3 +1,5 @@
+use std::convert::TryFrom;
+
 // FLOW CONTROL OPERATIONS
 // ================================================================================================
 #[repr(u8)]
@@ -19,6 +21,37 @@ impl FlowOps {
     }
 }
 
+impl TryFrom<u8> for FlowOps {
+    type Error = String;
+
+    /// Converts a raw op code into a flow control operation; fails if the value does not encode
+    /// any of the operations.
+    fn try_from(value: u8) -> Result<FlowOps, String> {
+        return match value {
+            0b000 => Ok(FlowOps::Hacc),
+            0b001 => Ok(FlowOps::Begin),
+            0b010 => Ok(FlowOps::Tend),
+            0b011 => Ok(FlowOps::Fend),
+            0b100 => Ok(FlowOps::Loop),
+            0b101 => Ok(FlowOps::Wrap),
+            0b110 => Ok(FlowOps::Break),
+            0b111 => Ok(FlowOps::Void),
+            _ => Err(format!("value {} is not a valid flow control op code", value)),
+        };
+    }
+}
+
+impl TryFrom<u128> for FlowOps {
+    type Error = String;
+
+    fn try_from(value: u128) -> Result<FlowOps, String> {
+        return match u8::try_from(value) {
+            Ok(value) => FlowOps::try_from(value),
+            Err(_) => Err(format!("value {} is not a valid flow control op code", value)),
+        };
+    }
+}
+
 impl std::fmt::Display for FlowOps {
 
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -62,7 +95,7 @@ pub enum UserOps {
     Inv         = 0b0_11_01100,         // no shift
     Neg         = 0b0_11_01101,         // no shift
     Not         = 0b0_11_01110,         // no shift
-    //???       = 0b0_11_01111,
+    AssertEqW   = 0b0_11_01111,         // left shift: 8
 
     Read        = 0b0_11_10000,         // right shift: 1
     Read2       = 0b0_11_10001,         // right shift: 2
@@ -70,8 +103,8 @@ pub enum UserOps {
     Dup2        = 0b0_11_10011,         // right shift: 2
     Dup4        = 0b0_11_10100,         // right shift: 4
     Pad2        = 0b0_11_10101,         // right shift: 2
-    //???       = 0b0_11_10110,
-    //???       = 0b0_11_10111,
+    Clk         = 0b0_11_10110,         // right shift: 1
+    Write       = 0b0_11_10111,         // left shift: 1
 
     Swap        = 0b0_11_11000,         // no shift
     Swap2       = 0b0_11_11001,         // no shift
@@ -79,7 +112,7 @@ pub enum UserOps {
     Roll4       = 0b0_11_11011,         // no shift
     Roll8       = 0b0_11_11100,         // no shift
     BinAcc      = 0b0_11_11101,         // no shift
-    //???       = 0b0_11_11110,
+    ExpAcc      = 0b0_11_11110,         // no shift
 
     // high-degree operations
     Push        = 0b0_00_11111,         // right shift: 1
@@ -104,6 +137,16 @@ impl UserOps {
         };
     }
 
+    /// Returns true if the operation carries an immediate value which is injected into the
+    /// program hash; this is possible only for high-degree operations with flags of degree 3
+    /// or lower because op_value injection must not push sponge constraints over degree 8.
+    pub fn has_immediate(&self) -> bool {
+        return match self {
+            UserOps::Push => true,
+            _ => false,
+        };
+    }
+
     pub fn hd_index(&self) -> usize {
         return match self {
             UserOps::Push | UserOps::Cmp | UserOps::RescR | UserOps::Noop | UserOps::Begin => {
@@ -116,6 +159,70 @@ impl UserOps {
     }
 }
 
+impl TryFrom<u8> for UserOps {
+    type Error = String;
+
+    /// Converts a raw op code into a user operation; fails if the value does not encode any of
+    /// the operations.
+    fn try_from(value: u8) -> Result<UserOps, String> {
+        return match value {
+            0b0_11_00000 => Ok(UserOps::Assert),
+            0b0_11_00001 => Ok(UserOps::AssertEq),
+            0b0_11_00010 => Ok(UserOps::Eq),
+            0b0_11_00011 => Ok(UserOps::Drop),
+            0b0_11_00100 => Ok(UserOps::Drop4),
+            0b0_11_00101 => Ok(UserOps::Choose),
+            0b0_11_00110 => Ok(UserOps::Choose2),
+            0b0_11_00111 => Ok(UserOps::CSwap2),
+
+            0b0_11_01000 => Ok(UserOps::Add),
+            0b0_11_01001 => Ok(UserOps::Mul),
+            0b0_11_01010 => Ok(UserOps::And),
+            0b0_11_01011 => Ok(UserOps::Or),
+            0b0_11_01100 => Ok(UserOps::Inv),
+            0b0_11_01101 => Ok(UserOps::Neg),
+            0b0_11_01110 => Ok(UserOps::Not),
+            0b0_11_01111 => Ok(UserOps::AssertEqW),
+
+            0b0_11_10000 => Ok(UserOps::Read),
+            0b0_11_10001 => Ok(UserOps::Read2),
+            0b0_11_10010 => Ok(UserOps::Dup),
+            0b0_11_10011 => Ok(UserOps::Dup2),
+            0b0_11_10100 => Ok(UserOps::Dup4),
+            0b0_11_10101 => Ok(UserOps::Pad2),
+            0b0_11_10110 => Ok(UserOps::Clk),
+            0b0_11_10111 => Ok(UserOps::Write),
+
+            0b0_11_11000 => Ok(UserOps::Swap),
+            0b0_11_11001 => Ok(UserOps::Swap2),
+            0b0_11_11010 => Ok(UserOps::Swap4),
+            0b0_11_11011 => Ok(UserOps::Roll4),
+            0b0_11_11100 => Ok(UserOps::Roll8),
+            0b0_11_11101 => Ok(UserOps::BinAcc),
+            0b0_11_11110 => Ok(UserOps::ExpAcc),
+
+            0b0_00_11111 => Ok(UserOps::Push),
+            0b0_01_11111 => Ok(UserOps::Cmp),
+            0b0_10_11111 => Ok(UserOps::RescR),
+
+            0b0_00_00000 => Ok(UserOps::Begin),
+            0b0_11_11111 => Ok(UserOps::Noop),
+            _ => Err(format!("value {} is not a valid user op code", value)),
+        };
+    }
+}
+
+impl TryFrom<u128> for UserOps {
+    type Error = String;
+
+    fn try_from(value: u128) -> Result<UserOps, String> {
+        return match u8::try_from(value) {
+            Ok(value) => UserOps::try_from(value),
+            Err(_) => Err(format!("value {} is not a valid user op code", value)),
+        };
+    }
+}
+
 impl std::fmt::Display for UserOps {
 
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -126,10 +233,13 @@ impl std::fmt::Display for UserOps {
 
             UserOps::Assert     => write!(f, "assert"),
             UserOps::AssertEq   => write!(f, "asserteq"),
+            UserOps::AssertEqW  => write!(f, "asserteqw"),
     
             UserOps::Push       => write!(f, "push"),
+            UserOps::Clk        => write!(f, "clk"),
             UserOps::Read       => write!(f, "read"),
             UserOps::Read2      => write!(f, "read2"),
+            UserOps::Write      => write!(f, "write"),
     
             UserOps::Dup        => write!(f, "dup"),
             UserOps::Dup2       => write!(f, "dup2"),
@@ -161,6 +271,7 @@ impl std::fmt::Display for UserOps {
             UserOps::Eq         => write!(f, "eq"),
             UserOps::Cmp        => write!(f, "cmp"),
             UserOps::BinAcc     => write!(f, "binacc"),
+            UserOps::ExpAcc     => write!(f, "expacc"),
     
             UserOps::RescR      => write!(f, "rescr")
         };
@@ -169,13 +280,20 @@ impl std::fmt::Display for UserOps {
 
 // OPERATION HINTS
 // ================================================================================================
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum OpHint {
     EqStart,
+    DivStart,
+    DivWStart,
     RcStart(u32),
+    ExpStart(u32),
     CmpStart(u32),
     PmpathStart(u32),
+    MrupdateStart(u32),
+    HostCall(u32),
+    Event(u32),
     PushValue(u128),
+    OpCount(u128),
     None,
 }
 
@@ -192,11 +310,48 @@ impl std::fmt::Display for OpHint {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         return match self {
             OpHint::EqStart          => write!(f, "::eq"),
+            OpHint::DivStart         => write!(f, ".div"),
+            OpHint::DivWStart        => write!(f, ".divw"),
             OpHint::RcStart(value)   => write!(f, ".{}", value),
+            OpHint::ExpStart(value)  => write!(f, ".{}", value),
             OpHint::CmpStart(value)     => write!(f, ".{}", value),
             OpHint::PmpathStart(value)  => write!(f, ".{}", value),
+            OpHint::MrupdateStart(value) => write!(f, ".{}", value),
+            OpHint::HostCall(value)     => write!(f, ".host.{}", value),
+            OpHint::Event(value)        => write!(f, ".event.{}", value),
             OpHint::PushValue(value)    => write!(f, "({})", value),
+            OpHint::OpCount(_)          => Ok(()),
             OpHint::None             => Ok(()),
         };
     }
-}
\ No newline at end of file
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use std::convert::TryFrom;
+    use super::{ FlowOps, UserOps };
+
+    #[test]
+    fn op_code_conversion() {
+        // every valid op code converts into the operation it encodes
+        let mut num_user_ops = 0;
+        for value in 0..=255u8 {
+            if let Ok(op_code) = UserOps::try_from(value) {
+                assert_eq!(value, op_code as u8);
+                num_user_ops += 1;
+            }
+        }
+        assert_eq!(36, num_user_ops);
+
+        for value in 0..8u8 {
+            assert_eq!(value, FlowOps::try_from(value as u128).unwrap() as u8);
+        }
+
+        assert!(UserOps::try_from(0b1_11_00000u8).is_err());
+        assert!(UserOps::try_from(0b0_11_00000u128 + 256).is_err());
+        assert!(FlowOps::try_from(8u8).is_err());
+    }
+}
@@ -0,0 +1,133 @@
+use crate::math::field;
+
+// CONSTANTS
+// ================================================================================================
+pub const RANGE_TABLE_SIZE: usize = 1 << 16;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Range checker co-processor which builds a lookup table for 16-bit range checks. The table
+/// consists of two registers:
+/// * a value register which starts at 0, ends at 2^16 - 1, and at every step either stays the
+///   same or is incremented by 1 - thus, every 16-bit value appears in it at least once;
+/// * a lookup flag register which is set to 1 for rows which satisfy a range check; every
+///   checked value appears in the table once per range check performed on it.
+///
+/// 8-bit range checks are reduced to 16-bit range checks: x < 2^8 if and only if both x and
+/// x * 2^8 are 16-bit values.
+pub struct RangeChecker {
+    lookups     : Vec<usize>,
+    num_lookups : usize,
+}
+
+// RANGE CHECKER IMPLEMENTATION
+// ================================================================================================
+impl RangeChecker {
+
+    pub fn new() -> RangeChecker {
+        return RangeChecker { lookups: vec![0; RANGE_TABLE_SIZE], num_lookups: 0 };
+    }
+
+    /// Records a 16-bit range check for the specified `value`.
+    pub fn check_u16(&mut self, value: u16) {
+        self.lookups[value as usize] += 1;
+        self.num_lookups += 1;
+    }
+
+    /// Records an 8-bit range check for the specified `value`; this results in two 16-bit range
+    /// checks: for the value itself and for the value multiplied by 2^8.
+    pub fn check_u8(&mut self, value: u8) {
+        self.check_u16(value as u16);
+        self.check_u16((value as u16) << 8);
+    }
+
+    /// Returns the number of 16-bit range checks recorded so far.
+    pub fn lookup_count(&self) -> usize {
+        return self.num_lookups;
+    }
+
+    /// Returns the minimum number of steps needed to hold the lookup table; this is always a
+    /// power of 2. The last step of the table is always a padding row because bus messages are
+    /// not exchanged at the last step of the trace.
+    pub fn trace_length(&self) -> usize {
+        return (self.row_count() + 1).next_power_of_two();
+    }
+
+    /// Builds value and lookup flag registers of the table; the table is padded with rows
+    /// holding the largest 16-bit value to fill the specified `trace_length`.
+    pub fn into_register_traces(self, trace_length: usize) -> Vec<Vec<u128>> {
+        assert!(trace_length.is_power_of_two(), "trace length must be a power of 2");
+        assert!(trace_length >= self.trace_length(),
+            "trace length must be at least {}, but was {}", self.trace_length(), trace_length);
+
+        let mut values = Vec::with_capacity(trace_length);
+        let mut flags = Vec::with_capacity(trace_length);
+        for (value, &count) in self.lookups.iter().enumerate() {
+            if count == 0 {
+                values.push(value as u128);
+                flags.push(field::ZERO);
+            }
+            else {
+                values.resize(values.len() + count, value as u128);
+                flags.resize(flags.len() + count, field::ONE);
+            }
+        }
+
+        values.resize(trace_length, (RANGE_TABLE_SIZE - 1) as u128);
+        flags.resize(trace_length, field::ZERO);
+
+        return vec![values, flags];
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+    fn row_count(&self) -> usize {
+        return self.lookups.iter().map(|&count| std::cmp::max(count, 1)).sum::<usize>();
+    }
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use super::{ RangeChecker, RANGE_TABLE_SIZE };
+
+    #[test]
+    fn build_table() {
+        let mut checker = RangeChecker::new();
+        assert_eq!(RANGE_TABLE_SIZE * 2, checker.trace_length());
+
+        checker.check_u16(7);
+        checker.check_u16(7);
+        checker.check_u8(3);
+        assert_eq!(4, checker.lookup_count());
+        assert_eq!(RANGE_TABLE_SIZE * 2, checker.trace_length());
+
+        let registers = checker.into_register_traces(RANGE_TABLE_SIZE * 2);
+        let (values, flags) = (&registers[0], &registers[1]);
+        assert_eq!(RANGE_TABLE_SIZE * 2, values.len());
+        assert_eq!(4, flags.iter().sum::<u128>());
+
+        // value 3 is checked once, value 7 is checked twice
+        assert_eq!([0, 1, 2, 3, 4, 5, 6, 7, 7, 8], values[..10]);
+        assert_eq!([0, 0, 0, 1, 0, 0, 0, 1, 1, 0], flags[..10]);
+
+        // 3 * 2^8 is checked once
+        assert_eq!(768, values[769]);
+        assert_eq!(1, flags[769]);
+
+        // the table is padded with the largest value
+        assert_eq!((RANGE_TABLE_SIZE - 1) as u128, values[values.len() - 1]);
+        assert_eq!(0, flags[flags.len() - 1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn build_table_too_short() {
+        let mut checker = RangeChecker::new();
+        checker.check_u16(1);
+        checker.into_register_traces(RANGE_TABLE_SIZE);
+    }
+}
@@ -1,8 +1,10 @@
 use crate::{
     math::field,
-    utils::hasher,
+    utils::{ self, hasher, zeroize },
     ProgramInputs, OpCode, OpHint,
-    HASH_STATE_WIDTH, MIN_STACK_DEPTH, MAX_STACK_DEPTH,
+    programs::blocks::{ ProgramBlock, Call },
+    processor::{ HostInterface, ExecutionError, checkpoint::StackState },
+    HASH_STATE_WIDTH, MAX_STACK_DEPTH,
 };
 
 #[cfg(test)]
@@ -10,35 +12,40 @@ mod tests;
 
 // TYPES AND INTERFACES
 // ================================================================================================
-pub struct Stack {
-    registers   : Vec<Vec<u128>>,
+pub struct Stack<'a> {
+    state       : Vec<u128>,
+    next        : Vec<u128>,
+    trace       : Vec<u128>,
     tape_a      : Vec<u128>,
     tape_b      : Vec<u128>,
+    host        : Option<&'a mut dyn HostInterface>,
     max_depth   : usize,
     depth       : usize,
     step        : usize,
+    history     : Vec<(usize, usize)>,
+    overflowed  : bool,
+    error       : Option<ExecutionError>,
 }
 
 // STACK IMPLEMENTATION
 // ================================================================================================
-impl Stack {
-
-    /// Returns a new Stack with enough memory allocated for each register to hold trace lengths
-    /// of `init_trace_length` steps. Register traces will be expanded dynamically if the number
-    /// of actual steps exceeds this initial setting.
-    pub fn new(inputs: &ProgramInputs, init_trace_length: usize) -> Stack {
-
-        // allocate space for register traces and initialize the first state with public inputs
+impl<'a> Stack<'a> {
+
+    /// Returns a new Stack with enough memory allocated to hold a trace of `init_trace_length`
+    /// steps. The trace will be expanded dynamically if the number of actual steps exceeds this
+    /// initial setting.
+    ///
+    /// Operations are executed against a compact buffer which holds the state of the stack at
+    /// the current step, and every resulting state is written as a row into a row-major trace
+    /// table; the table is converted into register traces only once execution is finished.
+    /// The table is only as wide as the stack has been deep so far, and so, registers which a
+    /// program never uses do not take up memory.
+    pub fn new(inputs: &ProgramInputs, init_trace_length: usize) -> Stack<'a> {
+
+        // initialize the first state with public inputs
         let public_inputs = inputs.get_public_inputs();
-        let init_stack_depth = std::cmp::max(public_inputs.len(), MIN_STACK_DEPTH);
-        let mut registers: Vec<Vec<u128>> = Vec::with_capacity(init_stack_depth);
-        for i in 0..init_stack_depth {
-            let mut register = vec![field::ZERO; init_trace_length];
-            if i < public_inputs.len() { 
-                register[0] = public_inputs[i];
-            }
-            registers.push(register);
-        }
+        let mut state = vec![field::ZERO; std::cmp::max(public_inputs.len(), 1)];
+        state[..public_inputs.len()].copy_from_slice(public_inputs);
 
         // reverse secret inputs so that they are consumed in FIFO order
         let [secret_inputs_a, secret_inputs_b] = inputs.get_secret_inputs();
@@ -47,23 +54,72 @@ impl Stack {
         let mut tape_b = secret_inputs_b.clone();
         tape_b.reverse();
 
+        return Stack::from_parts(state, init_trace_length, tape_a, tape_b, None, public_inputs.len(), public_inputs.len());
+    }
+
+    /// Returns a new Stack which requests advice from the specified `host` whenever a READ
+    /// operation with `HostCall` execution hint is executed.
+    pub fn with_host(inputs: &ProgramInputs, init_trace_length: usize, host: &'a mut dyn HostInterface) -> Stack<'a> {
+        let mut stack = Stack::new(inputs, init_trace_length);
+        stack.host = Some(host);
+        return stack;
+    }
+
+    /// Returns a new Stack which starts in the specified `state`; advice is requested from the
+    /// `host`, if one is provided.
+    pub fn from_state(state: &StackState, init_trace_length: usize, host: Option<&'a mut dyn HostInterface>) -> Stack<'a> {
+        // tapes are stored in reverse order so that they are consumed in FIFO order
+        let tape_a = state.tape_a.iter().rev().cloned().collect();
+        let tape_b = state.tape_b.iter().rev().cloned().collect();
+
+        return Stack::from_parts(state.registers.clone(), init_trace_length, tape_a, tape_b, host, state.depth, state.max_depth);
+    }
+
+    fn from_parts(state: Vec<u128>, init_trace_length: usize, tape_a: Vec<u128>, tape_b: Vec<u128>,
+        host: Option<&'a mut dyn HostInterface>, depth: usize, max_depth: usize) -> Stack<'a>
+    {
+        // allocate the trace table up front and write the initial state into its first row
+        let mut trace = vec![field::ZERO; state.len() * init_trace_length];
+        trace[..state.len()].copy_from_slice(&state);
+
         return Stack {
-            registers,
-            tape_a,
-            tape_b,
-            max_depth: public_inputs.len(),
-            depth: public_inputs.len(),
-            step: 0,
+            next        : vec![field::ZERO; state.len()],
+            state       : state,
+            trace       : trace,
+            tape_a      : tape_a,
+            tape_b      : tape_b,
+            host        : host,
+            max_depth   : max_depth,
+            depth       : depth,
+            step        : 0,
+            history     : vec![(0, depth)],
+            overflowed  : false,
+            error       : None,
         };
     }
 
-    /// Executes `opcode` against the current state of the stack.
+    /// Returns the state of the stack and of the secret input tapes at the current step.
+    pub fn save_state(&self) -> StackState {
+        return StackState {
+            registers   : self.state.clone(),
+            depth       : self.depth,
+            max_depth   : self.max_depth,
+            tape_a      : self.tape_a.iter().rev().cloned().collect(),
+            tape_b      : self.tape_b.iter().rev().cloned().collect(),
+        };
+    }
+
+    /// Executes `opcode` against the current state of the stack. If the operation would grow
+    /// the stack beyond MAX_STACK_DEPTH items, or if the host supplies a value which is not a
+    /// valid field element, the stack is left unchanged, and the resulting error can be
+    /// retrieved via `error()` method; the stack must not be used after that.
     pub fn execute(&mut self, op_code: OpCode, op_hint: OpHint) {
 
         // increment step pointer and make sure there is enough memory allocated to hold the trace
         self.advance_step();
 
-        // execute the appropriate action against the current state of the stack
+        // execute the appropriate action against the current state of the stack; the new state
+        // is built in the `next` buffer
         match op_code {
 
             OpCode::Begin       => self.op_noop(),
@@ -71,10 +127,13 @@ impl Stack {
 
             OpCode::Assert      => self.op_assert(),
             OpCode::AssertEq    => self.op_asserteq(),
+            OpCode::AssertEqW   => self.op_asserteqw(),
 
             OpCode::Push        => self.op_push(op_hint),
+            OpCode::Clk         => self.op_clk(op_hint),
             OpCode::Read        => self.op_read(op_hint),
             OpCode::Read2       => self.op_read2(op_hint),
+            OpCode::Write       => self.op_write(op_hint),
 
             OpCode::Dup         => self.op_dup(),
             OpCode::Dup2        => self.op_dup2(),
@@ -102,6 +161,7 @@ impl Stack {
             OpCode::Not         => self.op_not(),
             OpCode::And         => self.op_and(),
             OpCode::Or          => self.op_or(),
+            OpCode::ExpAcc      => self.op_expacc(op_hint),
 
             OpCode::Eq          => self.op_eq(),
             OpCode::Cmp         => self.op_cmp(op_hint),
@@ -109,11 +169,34 @@ impl Stack {
 
             OpCode::RescR       => self.op_rescr(),
         }
+
+        // the step of an operation is the step at which it starts executing
+        if self.error.is_some() {
+            return;
+        }
+        else if self.overflowed {
+            let history = self.history.clone();
+            self.error = Some(ExecutionError::StackOverflow(self.step - 1, op_code, history));
+            return;
+        }
+        else if self.depth > self.history[self.history.len() - 1].1 {
+            self.history.push((self.step - 1, self.depth));
+        }
+
+        // make the new state current, and record it in the trace table
+        std::mem::swap(&mut self.state, &mut self.next);
+        let width = self.state.len();
+        self.trace[(self.step * width)..((self.step + 1) * width)].copy_from_slice(&self.state);
+    }
+
+    /// Returns the error which aborted execution of the last operation, if any.
+    pub fn error(&self) -> Option<&ExecutionError> {
+        return self.error.as_ref();
     }
 
     /// Returns trace length of register traces in the decoder.
     pub fn trace_length(&self) -> usize {
-        return self.registers[0].len();
+        return self.trace.len() / self.state.len();
     }
 
     /// Returns value of the current step pointer.
@@ -124,26 +207,68 @@ impl Stack {
 
     /// Returns the value at the top of the stack at the current step.
     pub fn get_stack_top(&self) -> u128 {
-        return self.registers[0][self.step];
+        return self.state[0];
     }
 
-    /// Populate all register traces with values for steps between the current step
-    /// and the end of the trace.
+    /// Requests the body of a procedure with the specified body `hash` from the host, and makes
+    /// sure that the hash of the supplied body matches the requested hash.
+    pub fn get_procedure(&mut self, hash: u128) -> Vec<ProgramBlock> {
+        let body = match self.host.as_mut() {
+            Some(host) => host.get_procedure(hash),
+            None => panic!("dynamic call at step {} requires a host interface", self.step)
+        };
+        let body = body.unwrap_or_else(||
+            panic!("procedure {} called at step {} was not supplied by the host", hash, self.step));
+
+        let procedure = Call::new(body);
+        assert!(procedure.body_hash() == hash,
+            "procedure supplied by the host for dynamic call at step {} does not match hash {}", self.step, hash);
+        return procedure.body().to_vec();
+    }
+
+    /// Returns the values of the top 6 registers of the stack at the current step; these
+    /// registers hold the state of the hash function during RESCR operations. Registers which
+    /// have not been used yet are returned as zeros.
+    pub fn get_hash_state(&self) -> [u128; HASH_STATE_WIDTH] {
+        let mut state = [field::ZERO; HASH_STATE_WIDTH];
+        for (i, &value) in self.state.iter().take(HASH_STATE_WIDTH).enumerate() {
+            state[i] = value;
+        }
+        return state;
+    }
+
+    /// Makes sure the trace table can hold at least `trace_length` steps; this must be called
+    /// before the trace is finalized.
+    pub fn extend_trace(&mut self, trace_length: usize) {
+        if trace_length > self.trace_length() {
+            self.trace.resize(trace_length * self.state.len(), field::ZERO);
+        }
+    }
+
+    /// Populate all rows of the trace table for steps between the current step and the end
+    /// of the trace with the current state.
     pub fn finalize_trace(&mut self) {
-        let trace_length = self.trace_length();
-        for register in self.registers.iter_mut() {
-            register.resize(self.step + 1, field::ZERO);
-            register.resize(trace_length, register[self.step]);
+        let width = self.state.len();
+        for row in self.trace[((self.step + 1) * width)..].chunks_mut(width) {
+            row.copy_from_slice(&self.state);
         }
 
         // update the step pointer to point to the last step
         self.step = self.trace_length() - 1;
     }
 
-    /// Merges all register traces into a single vector of traces.
-    pub fn into_register_traces(mut self) -> Vec<Vec<u128>> {
-        self.registers.truncate(self.max_depth);
-        return self.registers;
+    /// Converts the trace table into a vector of register traces.
+    pub fn into_register_traces(self) -> Vec<Vec<u128>> {
+        let width = self.state.len();
+        let mut registers: Vec<Vec<u128>> = (0..self.max_depth)
+            .map(|_| Vec::with_capacity(self.trace_length()))
+            .collect();
+        for row in self.trace.chunks(width) {
+            for (register, &value) in registers.iter_mut().zip(row) {
+                register.push(value);
+            }
+        }
+        return registers;
     }
 
     // FLOW CONTROL OPERATIONS
@@ -154,19 +279,29 @@ impl Stack {
 
     fn op_assert(&mut self) {
         assert!(self.depth >= 1, "stack underflow at step {}", self.step);
-        let value = self.registers[0][self.step - 1];
+        let value = self.state[0];
         assert!(value == field::ONE, "ASSERT failed at step {}", self.step);
         self.shift_left(1, 1);
     }
 
     fn op_asserteq(&mut self) {
         assert!(self.depth >= 2, "stack underflow at step {}", self.step);
-        let x = self.registers[0][self.step - 1];
-        let y = self.registers[1][self.step - 1];
+        let x = self.state[0];
+        let y = self.state[1];
         assert!(x == y, "ASSERTEQ failed at step {}", self.step);
         self.shift_left(2, 2);
     }
 
+    fn op_asserteqw(&mut self) {
+        assert!(self.depth >= 8, "stack underflow at step {}", self.step);
+        for i in 0..4 {
+            let x = self.state[i];
+            let y = self.state[i + 4];
+            assert!(x == y, "ASSERTEQW failed at step {}", self.step);
+        }
+        self.shift_left(8, 8);
+    }
+
     // INPUT OPERATIONS
     // --------------------------------------------------------------------------------------------
     fn op_push(&mut self, hint: OpHint) {
@@ -175,7 +310,16 @@ impl Stack {
             OpHint::PushValue(value) => value,
             _ => panic!("invalid value for PUSH operation at step {}", self.step),
         };
-        self.registers[0][self.step] = op_value;
+        self.next[0] = op_value;
+    }
+
+    fn op_clk(&mut self, hint: OpHint) {
+        self.shift_right(0, 1);
+        let op_count = match hint {
+            OpHint::OpCount(value) => value,
+            _ => panic!("operation counter for CLK operation at step {} is missing", self.step),
+        };
+        self.next[0] = op_count;
     }
 
     fn op_read(&mut self, hint: OpHint) {
@@ -186,8 +330,8 @@ impl Stack {
                 // between top two stack values onto secret tape A, if they are equal; otherwise
                 // push value 1
                 assert!(self.depth >= 2, "stack underflow at step {}", self.step);
-                let x = self.registers[0][self.step - 1];
-                let y = self.registers[1][self.step - 1];
+                let x = self.state[0];
+                let y = self.state[1];
                 if x == y {
                     self.tape_a.push(field::ONE);
                 }
@@ -195,7 +339,46 @@ impl Stack {
                     self.tape_a.push(field::inv(field::sub(x, y)));
                 }
             },
+            OpHint::DivStart => {
+                // if we are about to read the result of integer division, put the quotient and
+                // the remainder of dividing the second stack value by the top stack value at the
+                // front of tape A so that the quotient is consumed by this operation
+                assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+                let x = self.state[0];
+                let y = self.state[1];
+                assert!(x != field::ZERO, "division by zero at step {}", self.step);
+                self.tape_a.push(y % x);
+                self.tape_a.push(y / x);
+            },
+            OpHint::DivWStart => {
+                // if we are about to read the result of 256-bit integer division, put the limbs
+                // of the quotient of dividing the second stack word by the top stack word at the
+                // front of tape A so that the most significant limb is consumed by this operation
+                assert!(self.depth >= 8, "stack underflow at step {}", self.step);
+                let x = to_u256(&self.state[0..4], self.step);
+                let y = to_u256(&self.state[4..8], self.step);
+                assert!(x != [0; 4], "division by zero at step {}", self.step);
+                let quotient = utils::div_u256(&y, &x);
+                self.tape_a.extend(quotient.iter().map(|&limb| limb as u128));
+            },
+            OpHint::HostCall(id) => {
+                // request advice from the host and put it at the front of tape A so that the
+                // first value is consumed by this operation
+                let stack_state: Vec<u128> = self.state[..self.depth].to_vec();
+                let advice = match self.host.as_mut() {
+                    Some(host) => host.get_advice(id, &stack_state),
+                    None => panic!("host call {} at step {} requires a host interface", id, self.step)
+                };
+                assert!(advice.len() > 0, "host call {} at step {} returned no values", id, self.step);
+                if let Some(&value) = advice.iter().find(|&&value| value >= field::MODULUS) {
+                    self.error = Some(ExecutionError::InvalidAdvice(self.step - 1, value));
+                    return;
+                }
+                self.tape_a.extend(advice.iter().rev());
+            },
             OpHint::None => {
+                self.fill_tape(0, 1);
+                if self.error.is_some() { return; }
                 assert!(self.tape_a.len() > 0, "attempt to read from empty tape A at step {}", self.step);
             },
             _ => panic!("execution hint {:?} is not valid for READ operation", hint)
@@ -203,7 +386,7 @@ impl Stack {
 
         self.shift_right(0, 1);
         let value = self.tape_a.pop().unwrap();
-        self.registers[0][self.step] = value;
+        self.next[0] = value;
     }
 
     fn op_read2(&mut self, hint: OpHint) {
@@ -213,10 +396,13 @@ impl Stack {
                 assert!(self.depth >= 3, "stack underflow at step {}", self.step);
 
                 let n = (n - 1) as usize;
+                self.fill_tape(0, n);
+                self.fill_tape(1, n);
+                if self.error.is_some() { return; }
                 assert!(self.tape_a.len() >= n, "too few items on tape A for pmpath macro");
                 assert!(self.tape_b.len() >= n, "too few items on tape B for pmpath macro");
 
-                let idx = self.registers[2][self.step - 1];
+                let idx = self.state[2];
 
                 // we need to insert binary decomposition of index into tape A, but we need to make
                 // sure it is interlaced with node values already present there. To do this,
@@ -231,6 +417,9 @@ impl Stack {
                 }
             },
             OpHint::None => {
+                self.fill_tape(0, 1);
+                self.fill_tape(1, 1);
+                if self.error.is_some() { return; }
                 assert!(self.tape_a.len() > 0, "attempt to read from empty tape A at step {}", self.step);
                 assert!(self.tape_b.len() > 0, "attempt to read from empty tape B at step {}", self.step);
             },
@@ -240,8 +429,48 @@ impl Stack {
         self.shift_right(0, 2);
         let value_a = self.tape_a.pop().unwrap();
         let value_b = self.tape_b.pop().unwrap();
-        self.registers[0][self.step] = value_b;
-        self.registers[1][self.step] = value_a;
+        self.next[0] = value_b;
+        self.next[1] = value_a;
+    }
+
+    /// Makes sure that the specified secret input `tape` (0 for tape A, 1 for tape B) holds at
+    /// least `n` values by reading values from the host, if the host has any; values read from
+    /// the host are consumed after all values already on the tape. If the host supplies a value
+    /// which is not a valid field element, execution of the current operation is aborted.
+    fn fill_tape(&mut self, tape: usize, n: usize) {
+        let host = match self.host.as_mut() {
+            Some(host) => host,
+            None => return,
+        };
+        let values = if tape == 0 { &mut self.tape_a } else { &mut self.tape_b };
+        while values.len() < n {
+            match host.read_tape(tape) {
+                Some(value) if value >= field::MODULUS => {
+                    self.error = Some(ExecutionError::InvalidAdvice(self.step - 1, value));
+                    return;
+                },
+                Some(value) => values.insert(0, value),
+                None => break,
+            }
+        }
+    }
+
+    // OUTPUT OPERATIONS
+    // --------------------------------------------------------------------------------------------
+    /// Removes the top item from the stack; the value of the item is appended to the output tape
+    /// which is built from the execution trace after the program has been executed. If the
+    /// operation starts an event record, the host is notified about the event; the id of the
+    /// event is at the top of the stack, and the payload of the event is right below it.
+    fn op_write(&mut self, hint: OpHint) {
+        assert!(self.depth >= 1, "stack underflow at step {}", self.step);
+        if let OpHint::Event(id) = hint {
+            assert!(self.depth >= 2, "stack underflow at step {}", self.step);
+            let payload = self.state[1];
+            if let Some(host) = self.host.as_mut() {
+                host.on_event(id, payload);
+            }
+        }
+        self.shift_left(1, 1);
     }
 
     // STACK MANIPULATION OPERATIONS
@@ -249,29 +478,29 @@ impl Stack {
     fn op_dup(&mut self) {
         assert!(self.depth >= 1, "stack underflow at step {}", self.step);
         self.shift_right(0, 1);
-        self.registers[0][self.step] = self.registers[0][self.step - 1];
+        self.next[0] = self.state[0];
     }
 
     fn op_dup2(&mut self) {
         assert!(self.depth >= 2, "stack underflow at step {}", self.step);
         self.shift_right(0, 2);
-        self.registers[0][self.step] = self.registers[0][self.step - 1];
-        self.registers[1][self.step] = self.registers[1][self.step - 1];
+        self.next[0] = self.state[0];
+        self.next[1] = self.state[1];
     }
 
     fn op_dup4(&mut self) {
         assert!(self.depth >= 4, "stack underflow at step {}", self.step);
         self.shift_right(0, 4);
-        self.registers[0][self.step] = self.registers[0][self.step - 1];
-        self.registers[1][self.step] = self.registers[1][self.step - 1];
-        self.registers[2][self.step] = self.registers[2][self.step - 1];
-        self.registers[3][self.step] = self.registers[3][self.step - 1];
+        self.next[0] = self.state[0];
+        self.next[1] = self.state[1];
+        self.next[2] = self.state[2];
+        self.next[3] = self.state[3];
     }
 
     fn op_pad2(&mut self) {
         self.shift_right(0, 2);
-        self.registers[0][self.step] = field::ZERO;
-        self.registers[1][self.step] = field::ZERO;
+        self.next[0] = field::ZERO;
+        self.next[1] = field::ZERO;
     }
 
     fn op_drop(&mut self) {
@@ -286,52 +515,52 @@ impl Stack {
 
     fn op_swap(&mut self) {
         assert!(self.depth >= 2, "stack underflow at step {}", self.step);
-        self.registers[0][self.step] = self.registers[1][self.step - 1];
-        self.registers[1][self.step] = self.registers[0][self.step - 1];
+        self.next[0] = self.state[1];
+        self.next[1] = self.state[0];
         self.copy_state(2);
     }
 
     fn op_swap2(&mut self) {
         assert!(self.depth >= 4, "stack underflow at step {}", self.step);
-        self.registers[0][self.step] = self.registers[2][self.step - 1];
-        self.registers[1][self.step] = self.registers[3][self.step - 1];
-        self.registers[2][self.step] = self.registers[0][self.step - 1];
-        self.registers[3][self.step] = self.registers[1][self.step - 1];
+        self.next[0] = self.state[2];
+        self.next[1] = self.state[3];
+        self.next[2] = self.state[0];
+        self.next[3] = self.state[1];
         self.copy_state(4);
     }
 
     fn op_swap4(&mut self) {
         assert!(self.depth >= 8, "stack underflow at step {}", self.step);
-        self.registers[0][self.step] = self.registers[4][self.step - 1];
-        self.registers[1][self.step] = self.registers[5][self.step - 1];
-        self.registers[2][self.step] = self.registers[6][self.step - 1];
-        self.registers[3][self.step] = self.registers[7][self.step - 1];
-        self.registers[4][self.step] = self.registers[0][self.step - 1];
-        self.registers[5][self.step] = self.registers[1][self.step - 1];
-        self.registers[6][self.step] = self.registers[2][self.step - 1];
-        self.registers[7][self.step] = self.registers[3][self.step - 1];
+        self.next[0] = self.state[4];
+        self.next[1] = self.state[5];
+        self.next[2] = self.state[6];
+        self.next[3] = self.state[7];
+        self.next[4] = self.state[0];
+        self.next[5] = self.state[1];
+        self.next[6] = self.state[2];
+        self.next[7] = self.state[3];
         self.copy_state(8);
     }
 
     fn op_roll4(&mut self) {
         assert!(self.depth >= 4, "stack underflow at step {}", self.step);
-        self.registers[0][self.step] = self.registers[3][self.step - 1];
-        self.registers[1][self.step] = self.registers[0][self.step - 1];
-        self.registers[2][self.step] = self.registers[1][self.step - 1];
-        self.registers[3][self.step] = self.registers[2][self.step - 1];
+        self.next[0] = self.state[3];
+        self.next[1] = self.state[0];
+        self.next[2] = self.state[1];
+        self.next[3] = self.state[2];
         self.copy_state(4);
     }
 
     fn op_roll8(&mut self) {
         assert!(self.depth >= 8, "stack underflow at step {}", self.step);
-        self.registers[0][self.step] = self.registers[7][self.step - 1];
-        self.registers[1][self.step] = self.registers[0][self.step - 1];
-        self.registers[2][self.step] = self.registers[1][self.step - 1];
-        self.registers[3][self.step] = self.registers[2][self.step - 1];
-        self.registers[4][self.step] = self.registers[3][self.step - 1];
-        self.registers[5][self.step] = self.registers[4][self.step - 1];
-        self.registers[6][self.step] = self.registers[5][self.step - 1];
-        self.registers[7][self.step] = self.registers[6][self.step - 1];
+        self.next[0] = self.state[7];
+        self.next[1] = self.state[0];
+        self.next[2] = self.state[1];
+        self.next[3] = self.state[2];
+        self.next[4] = self.state[3];
+        self.next[5] = self.state[4];
+        self.next[6] = self.state[5];
+        self.next[7] = self.state[6];
         self.copy_state(8);
     }
 
@@ -339,12 +568,12 @@ impl Stack {
     // --------------------------------------------------------------------------------------------
     fn op_choose(&mut self) {
         assert!(self.depth >= 3, "stack underflow at step {}", self.step);
-        let condition = self.registers[2][self.step - 1];
+        let condition = self.state[2];
         if condition == field::ONE {
-            self.registers[0][self.step] = self.registers[0][self.step - 1];
+            self.next[0] = self.state[0];
         }
         else if condition == field::ZERO {
-            self.registers[0][self.step] = self.registers[1][self.step - 1];
+            self.next[0] = self.state[1];
         }
         else {
             assert!(false, "CHOOSE on a non-binary condition at step {}", self.step);
@@ -354,14 +583,14 @@ impl Stack {
 
     fn op_choose2(&mut self) {
         assert!(self.depth >= 6, "stack underflow at step {}", self.step);
-        let condition = self.registers[4][self.step - 1];
+        let condition = self.state[4];
         if condition == field::ONE {
-            self.registers[0][self.step] = self.registers[0][self.step - 1];
-            self.registers[1][self.step] = self.registers[1][self.step - 1];
+            self.next[0] = self.state[0];
+            self.next[1] = self.state[1];
         }
         else if condition == field::ZERO {
-            self.registers[0][self.step] = self.registers[2][self.step - 1];
-            self.registers[1][self.step] = self.registers[3][self.step - 1];
+            self.next[0] = self.state[2];
+            self.next[1] = self.state[3];
         }
         else {
             assert!(false, "CHOOSE2 on a non-binary condition at step {}", self.step);
@@ -371,18 +600,18 @@ impl Stack {
 
     fn op_cswap2(&mut self) {
         assert!(self.depth >= 6, "stack underflow at step {}", self.step);
-        let condition = self.registers[4][self.step - 1];
+        let condition = self.state[4];
         if condition == field::ZERO {
-            self.registers[0][self.step] = self.registers[0][self.step - 1];
-            self.registers[1][self.step] = self.registers[1][self.step - 1];
-            self.registers[2][self.step] = self.registers[2][self.step - 1];
-            self.registers[3][self.step] = self.registers[3][self.step - 1];
+            self.next[0] = self.state[0];
+            self.next[1] = self.state[1];
+            self.next[2] = self.state[2];
+            self.next[3] = self.state[3];
         }
         else if condition == field::ONE {
-            self.registers[0][self.step] = self.registers[2][self.step - 1];
-            self.registers[1][self.step] = self.registers[3][self.step - 1];
-            self.registers[2][self.step] = self.registers[0][self.step - 1];
-            self.registers[3][self.step] = self.registers[1][self.step - 1];
+            self.next[0] = self.state[2];
+            self.next[1] = self.state[3];
+            self.next[2] = self.state[0];
+            self.next[3] = self.state[1];
         }
         else {
             assert!(false, "CSWAP2 on a non-binary condition at step {}", self.step);
@@ -394,79 +623,122 @@ impl Stack {
     // --------------------------------------------------------------------------------------------
     fn op_add(&mut self) {
         assert!(self.depth >= 2, "stack underflow at step {}", self.step);
-        let x = self.registers[0][self.step - 1];
-        let y = self.registers[1][self.step - 1];
-        self.registers[0][self.step] = field::add(x, y);
+        let x = self.state[0];
+        let y = self.state[1];
+        self.next[0] = field::add(x, y);
         self.shift_left(2, 1);
     }
 
     fn op_mul(&mut self) {
         assert!(self.depth >= 2, "stack underflow at step {}", self.step);
-        let x = self.registers[0][self.step - 1];
-        let y = self.registers[1][self.step - 1];
-        self.registers[0][self.step] = field::mul(x, y);
+        let x = self.state[0];
+        let y = self.state[1];
+        self.next[0] = field::mul(x, y);
         self.shift_left(2, 1);
     }
 
     fn op_inv(&mut self) {
         assert!(self.depth >= 1, "stack underflow at step {}", self.step);
-        let x = self.registers[0][self.step - 1];
+        let x = self.state[0];
         assert!(x != field::ZERO, "cannot compute INV of {} at step {}", field::ZERO, self.step);
-        self.registers[0][self.step] = field::inv(x);
+        self.next[0] = field::inv(x);
         self.copy_state(1);
     }
 
     fn op_neg(&mut self) {
         assert!(self.depth >= 1, "stack underflow at step {}", self.step);
-        let x = self.registers[0][self.step - 1];
-        self.registers[0][self.step] = field::neg(x);
+        let x = self.state[0];
+        self.next[0] = field::neg(x);
         self.copy_state(1);
     }
 
     fn op_not(&mut self) {
         assert!(self.depth >= 1, "stack underflow at step {}", self.step);
-        let x = self.registers[0][self.step - 1];
+        let x = self.state[0];
         assert!(is_binary(x), "cannot compute NOT of a non-binary value at step {}", self.step);
-        self.registers[0][self.step] = field::sub(field::ONE, x);
+        self.next[0] = field::sub(field::ONE, x);
         self.copy_state(1);
     }
 
     fn op_and(&mut self) {
         assert!(self.depth >= 2, "stack underflow at step {}", self.step);
-        let x = self.registers[0][self.step - 1];
-        let y = self.registers[1][self.step - 1];
+        let x = self.state[0];
+        let y = self.state[1];
         assert!(is_binary(x), "cannot compute AND for a non-binary value at step {}", self.step);
         assert!(is_binary(y), "cannot compute AND for a non-binary value at step {}", self.step);
 
-        self.registers[0][self.step] = if x == field::ONE && y == field::ONE { field::ONE } else { field::ZERO };
+        self.next[0] = if x == field::ONE && y == field::ONE { field::ONE } else { field::ZERO };
         self.shift_left(2, 1);
     }
 
     fn op_or(&mut self) {
         assert!(self.depth >= 2, "stack underflow at step {}", self.step);
-        let x = self.registers[0][self.step - 1];
-        let y = self.registers[1][self.step - 1];
+        let x = self.state[0];
+        let y = self.state[1];
         assert!(is_binary(x), "cannot compute OR for a non-binary value at step {}", self.step);
         assert!(is_binary(y), "cannot compute OR for a non-binary value at step {}", self.step);
 
-        self.registers[0][self.step] = if x == field::ONE || y == field::ONE { field::ONE } else { field::ZERO };
+        self.next[0] = if x == field::ONE || y == field::ONE { field::ONE } else { field::ZERO };
         self.shift_left(2, 1);
     }
 
+    fn op_expacc(&mut self, hint: OpHint) {
+        // process execution hint
+        match hint {
+            OpHint::ExpStart(n) => {
+                // if we are about to start exponentiation, push binary decomposition of the
+                // exponent onto tape A
+                assert!(self.depth >= 7, "stack underflow at step {}", self.step);
+                let exponent = self.state[6];
+                for i in 0..n {
+                    // most significant bit is pushed first
+                    self.tape_a.push((exponent >> (n - i - 1)) & 1);
+                }
+            },
+            OpHint::None => {
+                assert!(self.depth >= 6, "stack underflow at step {}", self.step);
+                assert!(self.tape_a.len() > 0, "attempt to read from empty tape A at step {}", self.step);
+            },
+            _ => panic!("execution hint {:?} is not valid for EXPACC operation", hint)
+        }
+
+        // get the next bit of the exponent from tape A
+        let bit = self.tape_a.pop().unwrap();
+        assert!(bit == field::ZERO || bit == field::ONE,
+            "expected binary input at step {} but received: {}", self.step, bit);
+
+        let multiplier   = self.state[1];
+        let power_of_two = self.state[2];
+        let result       = self.state[3];
+        let acc          = self.state[4];
+        let base_power   = self.state[5];
+
+        // update the next state of the computation; the multiplier for the current bit is
+        // applied to the result on the next step
+        self.next[0] = bit;
+        self.next[1] = if bit == field::ONE { base_power } else { field::ONE };
+        self.next[2] = field::mul(power_of_two, 2);
+        self.next[3] = field::mul(result, multiplier);
+        self.next[4] = field::add(acc, field::mul(bit, power_of_two));
+        self.next[5] = field::mul(base_power, base_power);
+
+        self.copy_state(6);
+    }
+
 
     // COMPARISON OPERATIONS
     // --------------------------------------------------------------------------------------------
     fn op_eq(&mut self) {
         assert!(self.depth >= 3, "stack underflow at step {}", self.step);
-        let aux = self.registers[0][self.step - 1];
-        let x = self.registers[1][self.step - 1];
-        let y = self.registers[2][self.step - 1];
+        let aux = self.state[0];
+        let x = self.state[1];
+        let y = self.state[2];
         if x == y {
-            self.registers[0][self.step] = field::ONE;
+            self.next[0] = field::ONE;
         } else {
             let diff = field::sub(x, y);
             assert!(aux == field::inv(diff), "invalid AUX value for EQ operation at step {}", self.step);
-            self.registers[0][self.step] = field::ZERO;
+            self.next[0] = field::ZERO;
         }
         self.shift_left(3, 2);
     }
@@ -478,8 +750,8 @@ impl Stack {
                 // if we are about to start comparison sequence, push binary decompositions
                 // of a and b values onto the tapes
                 assert!(self.depth >= 10, "stack underflow at step {}", self.step);
-                let a_val = self.registers[8][self.step - 1];
-                let b_val = self.registers[9][self.step - 1];
+                let a_val = self.state[8];
+                let b_val = self.state[9];
                 for i in 0..n {
                     self.tape_a.push((a_val >> i) & 1);
                     self.tape_b.push((b_val >> i) & 1);
@@ -506,7 +778,7 @@ impl Stack {
         let bit_lt = field::mul(b_bit, field::sub(field::ONE, a_bit));
 
         // compute current power of 2 for binary decomposition
-        let power_of_two = self.registers[0][self.step - 1];
+        let power_of_two = self.state[0];
         assert!(power_of_two.is_power_of_two(),
             "expected top of the stack at step {} to be a power of 2, but received {}", self.step, power_of_two);
         let next_power_of_two = if power_of_two == 1 {
@@ -517,19 +789,19 @@ impl Stack {
         };
 
         // determine if the result of comparison is already known
-        let gt = self.registers[4][self.step - 1];
-        let lt = self.registers[5][self.step - 1];
+        let gt = self.state[4];
+        let lt = self.state[5];
         let not_set = field::mul(field::sub(field::ONE, gt), field::sub(field::ONE, lt));
 
         // update the next state of the computation
-        self.registers[0][self.step] = next_power_of_two;
-        self.registers[1][self.step] = a_bit;
-        self.registers[2][self.step] = b_bit;
-        self.registers[3][self.step] = not_set;
-        self.registers[4][self.step] = field::add(gt, field::mul(bit_gt, not_set));
-        self.registers[5][self.step] = field::add(lt, field::mul(bit_lt, not_set));
-        self.registers[6][self.step] = field::add(self.registers[6][self.step - 1], field::mul(b_bit, power_of_two));
-        self.registers[7][self.step] = field::add(self.registers[7][self.step - 1], field::mul(a_bit, power_of_two));
+        self.next[0] = next_power_of_two;
+        self.next[1] = a_bit;
+        self.next[2] = b_bit;
+        self.next[3] = not_set;
+        self.next[4] = field::add(gt, field::mul(bit_gt, not_set));
+        self.next[5] = field::add(lt, field::mul(bit_lt, not_set));
+        self.next[6] = field::add(self.state[6], field::mul(b_bit, power_of_two));
+        self.next[7] = field::add(self.state[7], field::mul(a_bit, power_of_two));
 
         self.copy_state(8);
     }
@@ -541,12 +813,33 @@ impl Stack {
                 // if we are about to start range check sequence, push binary decompositions
                 // of the value onto tape A
                 assert!(self.depth >= 5, "stack underflow at step {}", self.step);
-                let val = self.registers[4][self.step - 1];
+                let val = self.state[4];
                 for i in 0..n {
                     // most significant bit is pushed first
                     self.tape_a.push((val >> (n - i - 1)) & 1);
                 }
             },
+            OpHint::MrupdateStart(n) => {
+                assert!(self.depth >= 9, "stack underflow at step {}", self.step);
+
+                let n = (n - 1) as usize;
+                self.fill_tape(0, n);
+                self.fill_tape(1, n);
+                if self.error.is_some() { return; }
+                assert!(self.tape_a.len() >= n, "too few items on tape A for mrupdate macro");
+                assert!(self.tape_b.len() >= n, "too few items on tape B for mrupdate macro");
+
+                let idx = self.state[8];
+
+                // interlace binary decomposition of the index with node values already present
+                // on tape A; unlike pmpath, each bit must be read before its node
+                let v_a = self.tape_a.split_off(self.tape_a.len() - n);
+                for i in 0..n {
+                    // most significant bit is pushed first
+                    self.tape_a.push(v_a[i]);
+                    self.tape_a.push((idx >> (n - i - 1)) & 1);
+                }
+            },
             OpHint::None => {
                 assert!(self.depth >= 4, "stack underflow at step {}", self.step);
                 assert!(self.tape_a.len() > 0, "attempt to read from empty tape A at step {}", self.step);
@@ -560,19 +853,19 @@ impl Stack {
             "expected binary input at step {} but received: {}", self.step, bit);
 
         // compute current power of 2 for binary decomposition
-        let power_of_two = self.registers[2][self.step - 1];
+        let power_of_two = self.state[2];
         assert!(power_of_two.is_power_of_two(),
             "expected 3rd value from the top of the stack at step {} to be a power of 2, but received {}",
             self.step, power_of_two);
         let next_power_of_two = field::mul(power_of_two, 2);
 
-        let acc = self.registers[3][self.step - 1];
+        let acc = self.state[3];
 
         // update the next state of the computation
-        self.registers[0][self.step] = bit;
-        self.registers[1][self.step] = 0;
-        self.registers[2][self.step] = next_power_of_two;
-        self.registers[3][self.step] = field::add(acc, field::mul(bit, power_of_two));
+        self.next[0] = bit;
+        self.next[1] = 0;
+        self.next[2] = next_power_of_two;
+        self.next[3] = field::add(acc, field::mul(bit, power_of_two));
 
         self.copy_state(4);
     }
@@ -582,22 +875,22 @@ impl Stack {
     fn op_rescr(&mut self) {
         assert!(self.depth >= HASH_STATE_WIDTH, "stack underflow at step {}", self.step);
         let mut state = [
-            self.registers[0][self.step - 1],
-            self.registers[1][self.step - 1],
-            self.registers[2][self.step - 1],
-            self.registers[3][self.step - 1],
-            self.registers[4][self.step - 1],
-            self.registers[5][self.step - 1],
+            self.state[0],
+            self.state[1],
+            self.state[2],
+            self.state[3],
+            self.state[4],
+            self.state[5],
         ];
 
-        hasher::apply_round(&mut state, self.step - 1);
+        hasher::permute(&mut state);
 
-        self.registers[0][self.step] = state[0];
-        self.registers[1][self.step] = state[1];
-        self.registers[2][self.step] = state[2];
-        self.registers[3][self.step] = state[3];
-        self.registers[4][self.step] = state[4];
-        self.registers[5][self.step] = state[5];
+        self.next[0] = state[0];
+        self.next[1] = state[1];
+        self.next[2] = state[2];
+        self.next[3] = state[3];
+        self.next[4] = state[4];
+        self.next[5] = state[5];
 
         self.copy_state(HASH_STATE_WIDTH);
     }
@@ -607,7 +900,7 @@ impl Stack {
 
     fn copy_state(&mut self, start: usize) {
         for i in start..self.depth {
-            self.registers[i][self.step] = self.registers[i][self.step - 1];
+            self.next[i] = self.state[i];
         }
     }
 
@@ -616,12 +909,12 @@ impl Stack {
         
         // shift all values by pos_count to the left
         for i in start..self.depth {
-            self.registers[i - pos_count][self.step] = self.registers[i][self.step - 1];
+            self.next[i - pos_count] = self.state[i];
         }
 
         // set all "shifted-in" slots to 0
         for i in (self.depth - pos_count)..self.depth {
-            self.registers[i][self.step] = field::ZERO;
+            self.next[i] = field::ZERO;
         }
 
         // stack depth has been reduced by pos_count
@@ -629,43 +922,83 @@ impl Stack {
     }
 
     fn shift_right(&mut self, start: usize, pos_count: usize) {
-        
+
+        // an overflow is reported once the operation has been executed so that the error can
+        // describe the operation; values shifted into `next` by then are discarded
+        if self.depth + pos_count > MAX_STACK_DEPTH {
+            self.overflowed = true;
+            return;
+        }
         self.depth += pos_count;
-        assert!(self.depth <= MAX_STACK_DEPTH, "stack overflow at step {}", self.step);
 
         if self.depth > self.max_depth {
             self.max_depth += pos_count;
-            if self.max_depth > self.registers.len() {
-                self.add_registers(self.max_depth - self.registers.len());
+            if self.max_depth > self.state.len() {
+                self.add_registers(self.max_depth - self.state.len());
             }
         }
 
         for i in start..(self.depth - pos_count) {
-            self.registers[i + pos_count][self.step] = self.registers[i][self.step - 1];
+            self.next[i + pos_count] = self.state[i];
         }
     }
 
-    /// Extends the stack by the specified number of registers.
+    /// Extends the stack by at least the specified number of registers; to avoid rebuilding the
+    /// trace table every time the stack grows, the number of registers is at least doubled
+    /// (up to MAX_STACK_DEPTH). Registers which end up unused are dropped from the trace.
     fn add_registers(&mut self, num_registers: usize) {
-        for _ in 0..num_registers {
-            self.registers.push(vec![field::ZERO; self.trace_length()]);
+        let old_width = self.state.len();
+        let new_width = std::cmp::max(old_width + num_registers, std::cmp::min(old_width * 2, MAX_STACK_DEPTH));
+
+        let mut trace = vec![field::ZERO; self.trace_length() * new_width];
+        for (new_row, row) in trace.chunks_mut(new_width).zip(self.trace.chunks(old_width)) {
+            new_row[..old_width].copy_from_slice(row);
         }
+        self.trace = trace;
+        self.state.resize(new_width, field::ZERO);
+        self.next.resize(new_width, field::ZERO);
     }
 
     fn advance_step(&mut self) {
         // increment step by 1
         self.step += 1;
 
-        // make sure there is enough memory allocated for register traces
+        // make sure there is enough memory allocated for the trace table
         if self.step >= self.trace_length() {
-            let new_length = self.trace_length() * 2;
-            for register in self.registers.iter_mut() { register.resize(new_length, field::ZERO); }
+            let new_length = self.trace.len() * 2;
+            self.trace.resize(new_length, field::ZERO);
+        }
+
+        // registers which are not set by the operation remain zeros
+        for value in self.next.iter_mut() {
+            *value = field::ZERO;
         }
     }
 }
 
+/// Values of stack registers and remaining values of the input tapes are derived from secret
+/// inputs, and so, they are zeroized when the stack is dropped.
+impl<'a> Drop for Stack<'a> {
+    fn drop(&mut self) {
+        zeroize(&mut self.state);
+        zeroize(&mut self.next);
+        zeroize(&mut self.trace);
+        zeroize(&mut self.tape_a);
+        zeroize(&mut self.tape_b);
+    }
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
 fn is_binary(value: u128) -> bool {
     return value == field::ZERO || value == field::ONE;
+}
+
+fn to_u256(limbs: &[u128], step: usize) -> [u64; 4] {
+    let mut result = [0u64; 4];
+    for (i, &limb) in limbs.iter().enumerate() {
+        assert!(limb <= u64::MAX as u128, "limb {} does not fit into 64 bits at step {}", limb, step);
+        result[i] = limb as u64;
+    }
+    return result;
 }
\ No newline at end of file
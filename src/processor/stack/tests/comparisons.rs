@@ -264,6 +264,24 @@ fn isodd_128() {
     assert_eq!(vec![is_odd, 7, 11, 0, 0, 0, 0, 0], state);
 }
 
+#[test]
+fn binacc_with_hint() {
+    // binary decomposition of the value is pushed onto tape A by the hint
+    let mut stack = init_stack(&[0, 0, 1, 0, 5, 7, 11], &[], &[], TRACE_LENGTH);
+    stack.execute(OpCode::BinAcc, OpHint::RcStart(4));
+    for _ in 0..3 { stack.execute(OpCode::BinAcc, OpHint::None); }
+
+    let state = get_stack_state(&stack, 4);
+    assert_eq!(vec![0, 0, 16, 5, 5, 7, 11, 0], state);
+}
+
+#[test]
+#[should_panic(expected = "expected binary input at step 1 but received: 2")]
+fn binacc_non_binary_input() {
+    let mut stack = init_stack(&[0, 0, 1, 0, 5, 7, 11], &[2], &[], TRACE_LENGTH);
+    stack.execute(OpCode::BinAcc, OpHint::None);
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
 fn build_inputs_for_cmp(a: u128, b: u128, size: usize) -> (Vec<u128>, Vec<u128>) {
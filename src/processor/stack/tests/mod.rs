@@ -1,7 +1,7 @@
 use crate::math::{ field };
 use crate::utils::{ hasher };
 use super::{ Stack, super::ProgramInputs, OpHint, OpCode };
-use crate::{ HASH_STATE_WIDTH };
+use crate::{ HASH_STATE_WIDTH, MIN_STACK_DEPTH };
 
 mod comparisons;
 mod conditional;
@@ -55,6 +55,23 @@ fn asserteq_fail() {
     stack.execute(OpCode::AssertEq, OpHint::None);
 }
 
+#[test]
+fn asserteqw() {
+    let mut stack = init_stack(&[1, 2, 3, 4, 1, 2, 3, 4], &[], &[], TRACE_LENGTH);
+    stack.execute(OpCode::AssertEqW, OpHint::None);
+    assert_eq!(vec![0, 0, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
+
+    assert_eq!(0, stack.depth);
+    assert_eq!(8, stack.max_depth);
+}
+
+#[test]
+#[should_panic(expected = "ASSERTEQW failed at step 1")]
+fn asserteqw_fail() {
+    let mut stack = init_stack(&[1, 2, 3, 4, 1, 2, 4, 3], &[], &[], TRACE_LENGTH);
+    stack.execute(OpCode::AssertEqW, OpHint::None);
+}
+
 // INPUT OPERATIONS
 // ================================================================================================
 
@@ -102,6 +119,35 @@ fn read2() {
     assert_eq!(5, stack.max_depth);
 }
 
+#[test]
+fn clk() {
+    let mut stack = init_stack(&[1], &[], &[], TRACE_LENGTH);
+
+    stack.execute(OpCode::Clk, OpHint::OpCount(5));
+    assert_eq!(vec![5, 1, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
+
+    assert_eq!(2, stack.depth);
+    assert_eq!(2, stack.max_depth);
+}
+
+#[test]
+#[should_panic]
+fn clk_no_hint() {
+    let mut stack = init_stack(&[1], &[], &[], TRACE_LENGTH);
+    stack.execute(OpCode::Clk, OpHint::None);
+}
+
+#[test]
+fn write() {
+    let mut stack = init_stack(&[1, 2], &[], &[], TRACE_LENGTH);
+
+    stack.execute(OpCode::Write, OpHint::None);
+    assert_eq!(vec![2, 0, 0, 0, 0, 0, 0, 0], get_stack_state(&stack, 1));
+
+    assert_eq!(1, stack.depth);
+    assert_eq!(2, stack.max_depth);
+}
+
 // STACK MANIPULATION OPERATIONS
 // ================================================================================================
 
@@ -334,6 +380,30 @@ fn and_or() {
     stack.execute(OpCode::Or, OpHint::None);
 }
 
+#[test]
+fn expacc() {
+    // compute 3^5 = 3^(0b101); the first bit is read using a hint
+    let mut stack = init_stack(&[1, 1, 1, 1, 0, 3, 5, 7], &[], &[], TRACE_LENGTH);
+    stack.execute(OpCode::ExpAcc, OpHint::ExpStart(3));
+    assert_eq!(vec![1, 3, 2, 1, 1, 9, 5, 7], get_stack_state(&stack, 1));
+
+    stack.execute(OpCode::ExpAcc, OpHint::None);
+    assert_eq!(vec![0, 1, 4, 3, 1, 81, 5, 7], get_stack_state(&stack, 2));
+
+    stack.execute(OpCode::ExpAcc, OpHint::None);
+    assert_eq!(vec![1, 81, 8, 3, 5, 6561, 5, 7], get_stack_state(&stack, 3));
+
+    assert_eq!(8, stack.depth);
+    assert_eq!(8, stack.max_depth);
+}
+
+#[test]
+#[should_panic(expected = "attempt to read from empty tape A at step 1")]
+fn expacc_no_bits() {
+    let mut stack = init_stack(&[1, 1, 1, 1, 0, 3, 5, 7], &[], &[], TRACE_LENGTH);
+    stack.execute(OpCode::ExpAcc, OpHint::None);
+}
+
 // CRYPTOGRAPHIC OPERATIONS
 // ================================================================================================
 
@@ -342,30 +412,59 @@ fn rescr() {
     let mut stack = init_stack(&[0, 0, 1, 2, 3, 4], &[], &[], TRACE_LENGTH);
     let mut expected = vec![0, 0, 1, 2, 3, 4, 0, 0];
 
+    // every RESCR operation applies a full permutation to the top 6 items
     stack.execute(OpCode::RescR, OpHint::None);
-    hasher::apply_round(&mut expected[..HASH_STATE_WIDTH], 0);
+    hasher::permute(&mut expected[..HASH_STATE_WIDTH]);
     assert_eq!(expected, get_stack_state(&stack, 1));
 
     stack.execute(OpCode::RescR, OpHint::None);
-    hasher::apply_round(&mut expected[..HASH_STATE_WIDTH], 1);
+    hasher::permute(&mut expected[..HASH_STATE_WIDTH]);
     assert_eq!(expected, get_stack_state(&stack, 2));
 
     assert_eq!(6, stack.depth);
     assert_eq!(6, stack.max_depth);
 }
 
+// TRACE TABLE
+// ================================================================================================
+
+#[test]
+fn trace_table_width() {
+    // the trace table is only as wide as the stack has been deep so far
+    let mut stack = init_stack(&[1, 2], &[], &[], TRACE_LENGTH);
+    assert_eq!(2, stack.state.len());
+
+    stack.execute(OpCode::Pad2, OpHint::None);
+    assert_eq!(4, stack.state.len());
+
+    // the width is at least doubled whenever the stack outgrows the table
+    stack.execute(OpCode::Dup, OpHint::None);
+    assert_eq!(8, stack.state.len());
+    stack.execute(OpCode::Drop, OpHint::None);
+
+    // registers which were never used are not a part of the trace
+    stack.finalize_trace();
+    let registers = stack.into_register_traces();
+    assert_eq!(5, registers.len());
+    assert_eq!(vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], registers[0]);
+    assert_eq!(vec![0, 2, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2], registers[3]);
+    assert_eq!(vec![0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], registers[4]);
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
 
-fn init_stack(public_inputs: &[u128], secret_inputs_a: &[u128], secret_inputs_b: &[u128], trace_length: usize) -> Stack {
+fn init_stack(public_inputs: &[u128], secret_inputs_a: &[u128], secret_inputs_b: &[u128], trace_length: usize) -> Stack<'static> {
     let inputs = ProgramInputs::new(public_inputs, secret_inputs_a, secret_inputs_b);
     return Stack::new(&inputs, trace_length);
 }
 
 fn get_stack_state(stack: &Stack, step: usize) -> Vec<u128> {
-    let mut state = Vec::with_capacity(stack.registers.len());
-    for i in 0..stack.registers.len() {
-        state.push(stack.registers[i][step]);
-    }
+    // the trace table grows together with the stack, and so, it may be narrower or wider than
+    // the expected state; missing registers are zeros
+    let width = stack.state.len();
+    let depth = std::cmp::max(stack.max_depth, MIN_STACK_DEPTH);
+    let mut state = stack.trace[(step * width)..((step + 1) * width)].to_vec();
+    state.resize(depth, field::ZERO);
     return state;
 }
\ No newline at end of file
@@ -0,0 +1,137 @@
+use serde::{ Serialize, Deserialize };
+use bincode::Options;
+use crate::{ crypto::hash, FormatHeader, MAX_PUBLIC_INPUTS, MAX_OUTPUTS };
+use super::ProgramInputs;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Describes the interface of a program: the number of public inputs with which the stack is
+/// initialized, the number of values the program reads from each of the secret input tapes,
+/// and the number of outputs the program produces. The ABI is not a part of the program hash;
+/// it is distributed together with the hash so that provers and verifiers can check that they
+/// pass inputs and outputs in the shape the program expects.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProgramAbi {
+    version     : u32,
+    num_inputs  : usize,
+    tape_lengths: [usize; 2],
+    num_outputs : usize,
+}
+
+// PROGRAM ABI IMPLEMENTATION
+// ================================================================================================
+impl ProgramAbi {
+
+    /// Returns an ABI of a program with the specified interface `version` which expects
+    /// `num_inputs` public inputs, `tape_lengths` values on secret input tapes A and B, and
+    /// produces `num_outputs` outputs.
+    pub fn new(version: u32, num_inputs: usize, tape_lengths: [usize; 2], num_outputs: usize) -> ProgramAbi {
+        assert!(num_inputs <= MAX_PUBLIC_INPUTS,
+            "expected no more than {} public inputs, but received {}", MAX_PUBLIC_INPUTS, num_inputs);
+        assert!(num_outputs <= MAX_OUTPUTS,
+            "cannot produce more than {} outputs, but requested {}", MAX_OUTPUTS, num_outputs);
+        assert!(tape_lengths[0] >= tape_lengths[1],
+            "number of primary secret inputs cannot be smaller than the number of secondary secret inputs");
+        return ProgramAbi { version, num_inputs, tape_lengths, num_outputs };
+    }
+
+    /// Returns the version of the program interface.
+    pub fn version(&self) -> u32 {
+        return self.version;
+    }
+
+    /// Returns the number of public inputs expected by the program.
+    pub fn num_inputs(&self) -> usize {
+        return self.num_inputs;
+    }
+
+    /// Returns the number of values expected on secret input tapes A and B.
+    pub fn tape_lengths(&self) -> [usize; 2] {
+        return self.tape_lengths;
+    }
+
+    /// Returns the number of outputs produced by the program.
+    pub fn num_outputs(&self) -> usize {
+        return self.num_outputs;
+    }
+
+    // VALIDATION
+    // -------------------------------------------------------------------------------------------
+
+    /// Checks that the `inputs` and the requested `num_outputs` match the ABI; this is done by
+    /// the prover before a program is executed.
+    pub fn check_execution(&self, inputs: &ProgramInputs, num_outputs: usize) -> Result<(), String> {
+        self.check_public_inputs(inputs.get_public_inputs())?;
+        let secret = inputs.get_secret_inputs();
+        for (i, (tape, name)) in secret.iter().zip(["A", "B"]).enumerate() {
+            if tape.len() != self.tape_lengths[i] {
+                return Err(format!("expected {} values on input tape {}, but received {}",
+                    self.tape_lengths[i], name, tape.len()));
+            }
+        }
+        return self.check_num_outputs(num_outputs);
+    }
+
+    /// Checks that `public_inputs` and `outputs` match the ABI; this is done by the verifier
+    /// before a proof is verified.
+    pub fn check_verification(&self, public_inputs: &[u128], outputs: &[u128]) -> Result<(), String> {
+        self.check_public_inputs(public_inputs)?;
+        return self.check_num_outputs(outputs.len());
+    }
+
+    fn check_public_inputs(&self, public_inputs: &[u128]) -> Result<(), String> {
+        if public_inputs.len() != self.num_inputs {
+            return Err(format!("expected {} public inputs, but received {}",
+                self.num_inputs, public_inputs.len()));
+        }
+        return Ok(());
+    }
+
+    fn check_num_outputs(&self, num_outputs: usize) -> Result<(), String> {
+        if num_outputs != self.num_outputs {
+            return Err(format!("expected {} outputs, but received {}", self.num_outputs, num_outputs));
+        }
+        return Ok(());
+    }
+
+    // BINARY SERIALIZATION
+    // -------------------------------------------------------------------------------------------
+
+    /// Serializes the ABI into bytes; the ABI is preceded by a format header which refers to
+    /// Rescue, the hash function with which program hashes are computed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        return bincode::serialize(&(FormatHeader::new(hash::rescue), self)).unwrap();
+    }
+
+    /// Parses an ABI from bytes produced by `to_bytes()`; unsupported format headers, trailing
+    /// bytes, and ABIs which could not have been built via `ProgramAbi::new()` result in an error.
+    pub fn from_bytes(bytes: &[u8]) -> Result<ProgramAbi, String> {
+        let header: FormatHeader = match bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .deserialize(bytes)
+        {
+            Ok(header) => header,
+            Err(err) => return Err(format!("invalid program ABI bytes: {}", err))
+        };
+        header.check(hash::rescue)?;
+
+        let (_, abi): (FormatHeader, ProgramAbi) = match bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .with_limit(bytes.len() as u64)
+            .reject_trailing_bytes()
+            .deserialize(bytes)
+        {
+            Ok(abi) => abi,
+            Err(err) => return Err(format!("invalid program ABI bytes: {}", err))
+        };
+
+        if abi.num_inputs > MAX_PUBLIC_INPUTS || abi.num_outputs > MAX_OUTPUTS
+            || abi.tape_lengths[0] < abi.tape_lengths[1]
+        {
+            return Err(String::from("program ABI describes an invalid program interface"));
+        }
+        return Ok(abi);
+    }
+}
@@ -157,6 +157,62 @@ impl AssemblyError {
         };
     }
 
+    pub fn unmatched_proc(step: usize, op: &[&str]) -> AssemblyError {
+        return AssemblyError {
+            message : format!("proc without matching end"),
+            step    : step,
+            op      : op.join("."),
+        };
+    }
+
+    pub fn undefined_procedure(op: &[&str], step: usize) -> AssemblyError {
+        return AssemblyError {
+            message : format!("procedure {} is not defined", op[1]),
+            step    : step,
+            op      : op.join("."),
+        };
+    }
+
+    pub fn duplicate_procedure(op: &[&str], step: usize) -> AssemblyError {
+        return AssemblyError {
+            message : format!("procedure {} is defined more than once", op[1]),
+            step    : step,
+            op      : op.join("."),
+        };
+    }
+
+    pub fn invalid_module_item(op: &[&str], step: usize) -> AssemblyError {
+        return AssemblyError {
+            message : format!("a module can contain only procedure definitions, but found '{}'", op.join(".")),
+            step    : step,
+            op      : op.join("."),
+        };
+    }
+
+    pub fn empty_module(name: &str) -> AssemblyError {
+        return AssemblyError {
+            message : format!("module {} must contain at least one procedure", name),
+            step    : 0,
+            op      : String::new(),
+        };
+    }
+
+    pub fn invalid_module_name(name: &str) -> AssemblyError {
+        return AssemblyError {
+            message : format!("module name '{}' is invalid", name),
+            step    : 0,
+            op      : String::new(),
+        };
+    }
+
+    pub fn duplicate_module(name: &str) -> AssemblyError {
+        return AssemblyError {
+            message : format!("module {} has already been added to the linker", name),
+            step    : 0,
+            op      : String::new(),
+        };
+    }
+
     // PUBLIC ACCESSORS
     // --------------------------------------------------------------------------------------------
     pub fn message(&self) -> &String {
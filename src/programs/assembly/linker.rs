@@ -0,0 +1,211 @@
+use super::{ Program, ProgramBlock, Group, Call, AssemblyError, parse_branch, compile_program, hash_group, tokenize, stdlib };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Combines separately compiled modules with a program which invokes procedures from these
+/// modules. A module may invoke procedures of modules added to the linker before it was compiled;
+/// thus, modules must be compiled and added to the linker in the order of their dependencies.
+pub struct Linker {
+    modules : Vec<Module>,
+}
+
+/// A named set of compiled procedures. Every procedure is fully resolved at the time the module
+/// is compiled, and thus, hashes of procedures do not change when the module is linked into
+/// different programs.
+#[derive(Clone, Debug)]
+pub struct Module {
+    name        : String,
+    procedures  : Vec<Procedure>,
+}
+
+/// A compiled procedure; procedure body is a sequence of program blocks which is inserted into
+/// a program as a Group block wherever the procedure is invoked.
+#[derive(Clone, Debug)]
+pub struct Procedure {
+    name    : String,
+    body    : Vec<ProgramBlock>,
+}
+
+/// Procedures which can be invoked via `exec` and `call` instructions while a program or a module
+/// is being compiled: procedures of modules added to the linker, and procedures defined earlier
+/// in the module which is being compiled.
+pub struct ProcedureScope<'a> {
+    linker  : Option<&'a Linker>,
+    local   : &'a [Procedure],
+}
+
+// LINKER IMPLEMENTATION
+// ================================================================================================
+impl Linker {
+
+    /// Returns a linker without any modules.
+    pub fn new() -> Linker {
+        return Linker { modules: Vec::new() };
+    }
+
+    /// Returns a linker with all modules of the standard library added to it; procedures of the
+    /// standard library can be invoked as `exec.<module>::<name>` (e.g. `exec.u256::add`).
+    pub fn with_stdlib() -> Linker {
+        let mut linker = Linker::new();
+        for &(name, source) in stdlib::MODULES.iter() {
+            let module = linker.compile_module(name, source)
+                .unwrap_or_else(|err| panic!("failed to compile stdlib module {}: {}", name, err));
+            linker.add_module(module).unwrap();
+        }
+        return linker;
+    }
+
+    /// Returns modules added to this linker in the order in which they were added.
+    pub fn modules(&self) -> &[Module] {
+        return &self.modules;
+    }
+
+    /// Adds the `module` to the linker; returns an error if a module with the same name has
+    /// already been added.
+    pub fn add_module(&mut self, module: Module) -> Result<(), AssemblyError> {
+        if self.get_module(&module.name).is_some() {
+            return Err(AssemblyError::duplicate_module(&module.name));
+        }
+        self.modules.push(module);
+        return Ok(());
+    }
+
+    /// Compiles provided assembly code into a module with the specified name. The source must
+    /// consist of procedure definitions of the form `proc.<name> ... end`; a procedure can invoke
+    /// procedures defined before it in the same module via `exec.<name>` instruction, and
+    /// procedures of modules added to this linker via `exec.<module>::<name>` instruction.
+    pub fn compile_module(&self, name: &str, source: &str) -> Result<Module, AssemblyError> {
+        if !is_valid_name(name) {
+            return Err(AssemblyError::invalid_module_name(name));
+        }
+
+        // break assembly string into tokens
+        let (tokens, locations) = tokenize(source);
+        if tokens.len() == 0 {
+            return Err(AssemblyError::empty_module(name));
+        }
+
+        // read procedures one by one; every procedure can see the procedures parsed before it
+        let mut procedures: Vec<Procedure> = Vec::new();
+        let mut i = 0;
+        while i < tokens.len() {
+            let head: Vec<&str> = tokens[i].split(".").collect();
+            if head[0] != "proc" {
+                return Err(AssemblyError::invalid_module_item(&head, i));
+            }
+            else if head.len() != 2 || !is_valid_name(head[1]) {
+                return Err(AssemblyError::invalid_block_head(&head, i));
+            }
+            else if procedures.iter().any(|procedure| procedure.name == head[1]) {
+                return Err(AssemblyError::duplicate_procedure(&head, i));
+            }
+
+            let scope = ProcedureScope { linker: Some(self), local: &procedures };
+            let mut body = Vec::new();
+            let end = parse_branch(&mut body, &tokens, &locations, i, &scope)?;
+            procedures.push(Procedure { name: String::from(head[1]), body });
+            i = end + 1;
+        }
+
+        return Ok(Module { name: String::from(name), procedures });
+    }
+
+    /// Compiles provided assembly code into a program in the same way as `assembly::compile()`
+    /// does, but also resolves `exec.<module>::<name>` instructions against modules added to
+    /// this linker. Bodies of invoked procedures become part of the program, and thus, the hash
+    /// of the linked program commits to all procedures it invokes.
+    pub fn link(&self, source: &str) -> Result<Program, AssemblyError> {
+        let scope = ProcedureScope { linker: Some(self), local: &[] };
+        return compile_program(source, &scope);
+    }
+
+    fn get_module(&self, name: &str) -> Option<&Module> {
+        return self.modules.iter().find(|module| module.name == name);
+    }
+}
+
+// MODULE IMPLEMENTATION
+// ================================================================================================
+impl Module {
+
+    /// Returns the name of this module.
+    pub fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Returns procedures of this module in the order in which they were defined.
+    pub fn procedures(&self) -> &[Procedure] {
+        return &self.procedures;
+    }
+
+    /// Returns a procedure with the specified name, if this module defines such a procedure.
+    pub fn get_procedure(&self, name: &str) -> Option<&Procedure> {
+        return self.procedures.iter().find(|procedure| procedure.name == name);
+    }
+}
+
+// PROCEDURE IMPLEMENTATION
+// ================================================================================================
+impl Procedure {
+
+    /// Returns the name of this procedure.
+    pub fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Returns the sequence of blocks executed by this procedure.
+    pub fn body(&self) -> &[ProgramBlock] {
+        return &self.body;
+    }
+
+    /// Returns hash of this procedure; the hash is computed in the same way as the hash of a
+    /// program with the same body, and can be used to identify audited procedures.
+    pub fn hash(&self) -> [u8; 32] {
+        return hash_group(&Group::new(self.body.clone()));
+    }
+
+    /// Returns hash of the body of this procedure; a program can call this procedure via
+    /// `dyncall` instruction when this value is at the top of the stack.
+    pub fn code_hash(&self) -> u128 {
+        return Call::new(self.body.clone()).body_hash();
+    }
+}
+
+// PROCEDURE SCOPE IMPLEMENTATION
+// ================================================================================================
+impl <'a> ProcedureScope<'a> {
+
+    /// Returns a scope which contains no procedures.
+    pub fn empty() -> ProcedureScope<'static> {
+        return ProcedureScope { linker: None, local: &[] };
+    }
+
+    /// Returns a procedure invoked by the specified `exec` or `call` instruction.
+    pub fn resolve(&self, op: &[&str], step: usize) -> Result<&Procedure, AssemblyError> {
+        if op.len() == 1 {
+            return Err(AssemblyError::missing_param(op, step));
+        }
+        else if op.len() > 2 {
+            return Err(AssemblyError::extra_param(op, step));
+        }
+
+        let procedure = match op[1].split_once("::") {
+            Some((module, name)) => self.linker
+                .and_then(|linker| linker.get_module(module))
+                .and_then(|module| module.get_procedure(name)),
+            None => self.local.iter().find(|procedure| procedure.name == op[1]),
+        };
+
+        return match procedure {
+            Some(procedure) => Ok(procedure),
+            None => Err(AssemblyError::undefined_procedure(op, step)),
+        };
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+fn is_valid_name(name: &str) -> bool {
+    return name.len() > 0 && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+}
@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use super::{ Program, ProgramBlock, Span, Group, Switch, Loop, OpCode, OpHint, BASE_CYCLE_LENGTH };
+use super::{ Program, ProgramBlock, Span, Group, Switch, Loop, Call, DynCall, SourceLocation, OpCode, OpHint, BASE_CYCLE_LENGTH, hash_group };
 
 mod parsers;
 use parsers::*;
@@ -7,6 +7,12 @@ use parsers::*;
 mod errors;
 use errors::{ AssemblyError };
 
+mod linker;
+pub use linker::{ Linker, Module, Procedure };
+use linker::{ ProcedureScope };
+
+mod stdlib;
+
 #[cfg(test)]
 mod tests;
 
@@ -15,11 +21,30 @@ type HintMap = HashMap<usize, OpHint>;
 // ASSEMBLY COMPILER
 // ================================================================================================
 
-/// Compiles provided assembly code into a program.
+/// Compiles provided assembly code into a program; to compile a program which invokes procedures
+/// of separately compiled modules, use `Linker::link()`.
 pub fn compile(source: &str) -> Result<Program, AssemblyError> {
+    return compile_program(source, &ProcedureScope::empty());
+}
+
+/// Returns the number of VM cycles needed to execute the specified assembly `instruction` when
+/// it is placed at position `offset` of a Span block; the cost includes NOOPs which the assembler
+/// inserts to align PUSH operations, and thus, may depend on the `offset`. Control flow
+/// instructions are not supported since their cost depends on the blocks they delimit.
+pub fn instruction_cycles(instruction: &str, offset: usize) -> Result<usize, AssemblyError> {
+    let op: Vec<&str> = instruction.split(".").collect();
+    let mut op_codes = vec![OpCode::Noop; offset];
+    let mut op_hints: HintMap = HashMap::new();
+    parse_op_token(op, &mut op_codes, &mut op_hints, 0)?;
+    return Ok(op_codes.len() - offset);
+}
+
+/// Compiles provided assembly code into a program; `exec` instructions are resolved against
+/// procedures in the `scope`.
+fn compile_program(source: &str, scope: &ProcedureScope) -> Result<Program, AssemblyError> {
 
     // break assembly string into tokens
-    let tokens: Vec<&str> = source.split_whitespace().collect();
+    let (tokens, locations) = tokenize(source);
 
     // perform basic validation
     if tokens.len() == 0 {
@@ -34,7 +59,7 @@ pub fn compile(source: &str) -> Result<Program, AssemblyError> {
 
     // read the program from the token stream
     let mut root_blocks = Vec::new();
-    let i = parse_branch(&mut root_blocks, &tokens, 0)?;
+    let i = parse_branch(&mut root_blocks, &tokens, &locations, 0, scope)?;
     let root = Group::new(root_blocks);
 
     // make sure there is nothing left after the last token
@@ -51,7 +76,7 @@ pub fn compile(source: &str) -> Result<Program, AssemblyError> {
 
 /// Parses a single program block from the `token` stream, and appends this block to the `parent`
 /// list of blocks.
-fn parse_block(parent: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) -> Result<usize, AssemblyError> {
+fn parse_block(parent: &mut Vec<ProgramBlock>, tokens: &[&str], locations: &[SourceLocation], mut i: usize, scope: &ProcedureScope) -> Result<usize, AssemblyError> {
 
     // read the block header
     let head: Vec<&str> = tokens[i].split(".").collect();
@@ -65,7 +90,7 @@ fn parse_block(parent: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
             }
             // then parse the body of the block, add the new block to the parent, and return
             let mut body = Vec::new();
-            i = parse_branch(&mut body, tokens, i)?;
+            i = parse_branch(&mut body, tokens, locations, i, scope)?;
             parent.push(Group::new_block(body));
             return Ok(i + 1);
         },
@@ -77,13 +102,13 @@ fn parse_block(parent: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
 
             // parse the body of the true branch
             let mut t_branch = Vec::new();
-            i = parse_branch(&mut t_branch, tokens, i)?;
+            i = parse_branch(&mut t_branch, tokens, locations, i, scope)?;
 
             // if the false branch is present, parse it as well; otherwise
             // create an empty false branch
             let mut f_branch = Vec::new();
             if tokens[i] == "else" {
-                i = parse_branch(&mut f_branch, tokens, i)?;
+                i = parse_branch(&mut f_branch, tokens, locations, i, scope)?;
             }
             else {
                 f_branch.push(Span::new_block(vec![
@@ -107,7 +132,7 @@ fn parse_block(parent: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
 
             // parse loop body
             let mut body_template = Vec::new();
-            i = parse_branch(&mut body_template, tokens, i)?;
+            i = parse_branch(&mut body_template, tokens, locations, i, scope)?;
 
             // duplicate loop body as many times as needed
             let body = repeat_block_sequence(body_template, num_iterations);
@@ -123,7 +148,7 @@ fn parse_block(parent: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
             }
             // then parse the body of the block, add the new block to the parent, and return
             let mut body = Vec::new();
-            i = parse_branch(&mut body, tokens, i)?;
+            i = parse_branch(&mut body, tokens, locations, i, scope)?;
             parent.push(Loop::new_block(body));
             return Ok(i + 1);
         },
@@ -133,7 +158,7 @@ fn parse_block(parent: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
 
 /// Builds a body of a program block by parsing tokens from the stream and transforming
 /// them into program blocks.
-fn parse_branch(body: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) -> Result<usize, AssemblyError> {
+fn parse_branch(body: &mut Vec<ProgramBlock>, tokens: &[&str], locations: &[SourceLocation], mut i: usize, scope: &ProcedureScope) -> Result<usize, AssemblyError> {
 
     // determine starting instructions of the branch based on branch head
     let mut head: Vec<&str> = tokens[i].split(".").collect();
@@ -144,6 +169,7 @@ fn parse_branch(body: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
             vec![OpCode::Begin]
         },
         "block"  => vec![],
+        "proc"   => vec![],
         "if"     => vec![OpCode::Assert],
         "else"   => vec![OpCode::Not, OpCode::Assert],
         "repeat" => vec![],
@@ -152,6 +178,9 @@ fn parse_branch(body: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
     };
     let mut op_hints: HintMap = HashMap::new();
 
+    // operations implied by the branch head are attributed to the head instruction
+    let mut op_sources = vec![Some(locations[i]); op_codes.len()];
+
     // save first step to check for empty branches
     let first_step = i;
     i += 1;
@@ -163,8 +192,36 @@ fn parse_branch(body: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
         i = match op[0] {
             "block" | "if" | "repeat" | "while" => {
                 let force_span = body.len() == 0;
-                add_span(body, &mut op_codes, &mut op_hints, force_span);
-                parse_block(body, tokens, i)?
+                add_span(body, &mut op_codes, &mut op_hints, &mut op_sources, force_span);
+                parse_block(body, tokens, locations, i, scope)?
+            },
+            "exec" => {
+                // body of the invoked procedure is inserted as a separate block
+                let procedure = scope.resolve(&op, i)?;
+                let force_span = body.len() == 0;
+                add_span(body, &mut op_codes, &mut op_hints, &mut op_sources, force_span);
+                body.push(Group::new_block(procedure.body().to_vec()));
+                i + 1
+            },
+            "call" => {
+                // body of the called procedure is executed with a fresh sponge state, and the
+                // state of the caller is restored from the call stack when the procedure returns
+                let procedure = scope.resolve(&op, i)?;
+                let force_span = body.len() == 0;
+                add_span(body, &mut op_codes, &mut op_hints, &mut op_sources, force_span);
+                body.push(Call::new_block(procedure.body().to_vec()));
+                i + 1
+            },
+            "dyncall" => {
+                // procedure to call is determined at execution time by the hash at the top of
+                // the stack, and thus, the procedure does not need to be known to the linker
+                if op.len() > 1 {
+                    return Err(AssemblyError::extra_param(&op, i));
+                }
+                let force_span = body.len() == 0;
+                add_span(body, &mut op_codes, &mut op_hints, &mut op_sources, force_span);
+                body.push(DynCall::new_block());
+                i + 1
             },
             "else" => {
                 if head[0] != "if" {
@@ -173,23 +230,30 @@ fn parse_branch(body: &mut Vec<ProgramBlock>, tokens: &[&str], mut i: usize) ->
                 else if i - first_step < 2 {
                     return Err(AssemblyError::empty_block(&head, first_step));
                 }
-                add_span(body, &mut op_codes, &mut op_hints, false);
+                add_span(body, &mut op_codes, &mut op_hints, &mut op_sources, false);
                 return Ok(i);
             },
             "end" => {
                 if i - first_step < 2 {
                     return Err(AssemblyError::empty_block(&head, first_step));
                 }
-                add_span(body, &mut op_codes, &mut op_hints, false);
+                add_span(body, &mut op_codes, &mut op_hints, &mut op_sources, false);
                 return Ok(i);
             },
-            _ => parse_op_token(op, &mut op_codes, &mut op_hints, i)?
+            _ => {
+                // all operations into which the instruction was transformed (including NOOPs
+                // inserted for alignment) are attributed to the instruction
+                let next = parse_op_token(op, &mut op_codes, &mut op_hints, i)?;
+                op_sources.resize(op_codes.len(), Some(locations[i]));
+                next
+            }
         };
     }
 
     // if all tokens were consumed by block end was not found, return an error
     return match head[0] {
         "block"  => Err(AssemblyError::unmatched_block(first_step)),
+        "proc"   => Err(AssemblyError::unmatched_proc(first_step, &head)),
         "if"     => Err(AssemblyError::unmatched_if(first_step)),
         "else"   => Err(AssemblyError::unmatched_else(first_step)),
         "repeat" => Err(AssemblyError::unmatched_repeat(first_step, &head)),
@@ -207,7 +271,11 @@ fn parse_op_token(op: Vec<&str>, op_codes: &mut Vec<OpCode>, op_hints: &mut Hint
         "assert" => parse_assert(op_codes, &op, step),
 
         "push"   => parse_push(op_codes, op_hints, &op, step),
-        "read"   => parse_read(op_codes, &op, step),
+        "pushw"  => parse_pushw(op_codes, op_hints, &op, step),
+        "read"   => parse_read(op_codes, op_hints, &op, step),
+        "clk"    => parse_clk(op_codes, &op, step),
+        "write"  => parse_write(op_codes, &op, step),
+        "emit"   => parse_emit(op_codes, op_hints, &op, step),
 
         "dup"    => parse_dup(op_codes, &op, step),
         "pad"    => parse_pad(op_codes, &op, step),
@@ -220,6 +288,7 @@ fn parse_op_token(op: Vec<&str>, op_codes: &mut Vec<OpCode>, op_hints: &mut Hint
         "sub"    => parse_sub(op_codes, &op, step),
         "mul"    => parse_mul(op_codes, &op, step),
         "div"    => parse_div(op_codes, &op, step),
+        "exp"    => parse_exp(op_codes, op_hints, &op, step),
         "neg"    => parse_neg(op_codes, &op, step),
         "inv"    => parse_inv(op_codes, &op, step),
         "not"    => parse_not(op_codes, &op, step),
@@ -232,12 +301,18 @@ fn parse_op_token(op: Vec<&str>, op_codes: &mut Vec<OpCode>, op_hints: &mut Hint
         "lt"     => parse_lt(op_codes, op_hints, &op, step),
         "rc"     => parse_rc(op_codes, op_hints, &op, step),
         "isodd"  => parse_isodd(op_codes, op_hints, &op, step),
+        "split"  => parse_split(op_codes, op_hints, &op, step),
 
         "choose" => parse_choose(op_codes, &op, step),
 
         "hash"   => parse_hash(op_codes, &op, step),
+        "hashr"  => parse_hashr(op_codes, &op, step),
+        "absorb" => parse_absorb(op_codes, &op, step),
+        "squeeze" => parse_squeeze(op_codes, &op, step),
+        "random" => parse_random(op_codes, &op, step),
         "smpath" => parse_smpath(op_codes, &op, step),
         "pmpath" => parse_pmpath(op_codes, op_hints, &op, step),
+        "mrupdate" => parse_mrupdate(op_codes, op_hints, &op, step),
 
         _ => return Err(AssemblyError::invalid_op(&op, step))
     }?;
@@ -250,7 +325,9 @@ fn parse_op_token(op: Vec<&str>, op_codes: &mut Vec<OpCode>, op_hints: &mut Hint
 // ================================================================================================
 
 /// Adds a new Span block to a program block body based on currently parsed instructions.
-fn add_span(body: &mut Vec<ProgramBlock>, op_codes: &mut Vec<OpCode>, op_hints: &mut HintMap, force: bool) {
+fn add_span(body: &mut Vec<ProgramBlock>, op_codes: &mut Vec<OpCode>, op_hints: &mut HintMap,
+    op_sources: &mut Vec<Option<SourceLocation>>, force: bool)
+{
 
     // if there were no instructions in the current span, don't do anything
     if op_codes.len() == 0 && !force { return };
@@ -260,12 +337,33 @@ fn add_span(body: &mut Vec<ProgramBlock>, op_codes: &mut Vec<OpCode>, op_hints:
     let pad_length = BASE_CYCLE_LENGTH - (span_op_codes.len() % BASE_CYCLE_LENGTH) - 1;
     span_op_codes.resize(span_op_codes.len() + pad_length, OpCode::Noop);
 
+    // padding NOOPs do not come from any instruction
+    let mut span_sources = op_sources.clone();
+    span_sources.resize(span_op_codes.len(), None);
+
     // add a new Span block to the body
-    body.push(ProgramBlock::Span(Span::new(span_op_codes, op_hints.clone())));
+    body.push(ProgramBlock::Span(Span::new(span_op_codes, op_hints.clone()).with_sources(span_sources)));
 
-    // clear op_codes and op_hints for the next Span block
+    // clear op_codes, op_hints, and op_sources for the next Span block
     op_codes.clear();
     op_hints.clear();
+    op_sources.clear();
+}
+
+/// Breaks assembly source into whitespace-separated tokens, and returns the tokens together with
+/// their locations in the source.
+fn tokenize(source: &str) -> (Vec<&str>, Vec<SourceLocation>) {
+    let mut tokens = Vec::new();
+    let mut locations = Vec::new();
+    for (i, line) in source.lines().enumerate() {
+        for token in line.split_whitespace() {
+            // tokens are slices of the line, and so, their offsets within the line are known
+            let offset = token.as_ptr() as usize - line.as_ptr() as usize;
+            tokens.push(token);
+            locations.push(SourceLocation { line: i + 1, column: line[..offset].chars().count() + 1 });
+        }
+    }
+    return (tokens, locations);
 }
 
 fn repeat_block_sequence(template: Vec<ProgramBlock>, num_iterations: usize) -> Vec<ProgramBlock> {
@@ -4,7 +4,7 @@ use super::{ AssemblyError, HintMap, OpCode, OpHint };
 // CONSTANTS
 // ================================================================================================
 const PUSH_OP_ALIGNMENT: usize = 8;
-const HASH_OP_ALIGNMENT: usize = 16;
+const MAX_PUSH_SEQUENCE: usize = 4;
 
 // CONTROL FLOW OPERATIONS
 // ================================================================================================
@@ -18,7 +18,7 @@ pub fn parse_noop(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result
     return Ok(true);
 }
 
-/// Appends either ASSERT or ASSERTEQ operations to the program.
+/// Appends either ASSERT, ASSERTEQ, or ASSERTEQW operations to the program.
 pub fn parse_assert(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
     if op.len() > 2 {
         return Err(AssemblyError::extra_param(op, step));
@@ -29,9 +29,12 @@ pub fn parse_assert(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Resu
     else if op[1] == "eq" {
         program.push(OpCode::AssertEq);
     }
+    else if op[1] == "eqw" {
+        program.push(OpCode::AssertEqW);
+    }
     else {
         return Err(AssemblyError::invalid_param_reason(op, step,
-            format!("parameter {} is invalid; allowed values are: [eq]", op[1])));
+            format!("parameter {} is invalid; allowed values are: [eq, eqw]", op[1])));
     }
     
     return Ok(true);
@@ -47,12 +50,33 @@ pub fn parse_push(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], s
     return Ok(true);
 }
 
-/// Makes sure PUSH operation alignment is correct and appends PUSH opcode to the program.
-fn append_push_op(program: &mut Vec<OpCode>, hints: &mut HintMap, value: u128) {
-    // pad the program with NOOPs to make sure PUSH happens on steps which are multiples of 8
+/// Appends a sequence of 4 PUSH operations to the program; values are pushed onto the stack
+/// in the order in which they are specified, so the last value ends up at the top of the stack.
+pub fn parse_pushw(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    let word = read_word(op, step)?;
+
+    // pad the program with NOOPs to make sure the entire sequence starts on a multiple of 8
     let alignment = program.len() % PUSH_OP_ALIGNMENT;
     let pad_length = (PUSH_OP_ALIGNMENT - alignment) % PUSH_OP_ALIGNMENT;
     program.resize(program.len() + pad_length, OpCode::Noop);
+
+    for &value in word.iter() {
+        append_push_op(program, hints, value);
+    }
+    return Ok(true);
+}
+
+/// Makes sure PUSH operation alignment is correct and appends PUSH opcode to the program.
+fn append_push_op(program: &mut Vec<OpCode>, hints: &mut HintMap, value: u128) {
+    // pad the program with NOOPs to make sure PUSH happens on steps which are multiples of 8,
+    // unless this PUSH can continue a sequence of PUSH operations
+    let alignment = program.len() % PUSH_OP_ALIGNMENT;
+    let is_sequence = alignment > 0 && alignment < MAX_PUSH_SEQUENCE
+        && program[program.len() - 1] == OpCode::Push;
+    if !is_sequence {
+        let pad_length = (PUSH_OP_ALIGNMENT - alignment) % PUSH_OP_ALIGNMENT;
+        program.resize(program.len() + pad_length, OpCode::Noop);
+    }
     
     // read the value to be pushed onto the stack
     hints.insert(program.len(), OpHint::PushValue(value));
@@ -61,9 +85,57 @@ fn append_push_op(program: &mut Vec<OpCode>, hints: &mut HintMap, value: u128) {
     program.push(OpCode::Push);
 }
 
-/// Appends either READ or READ2 operation to the program.
-pub fn parse_read(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
-    if op.len() > 2 {
+/// Appends CLK operation to the program.
+pub fn parse_clk(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    if op.len() > 1 { return Err(AssemblyError::extra_param(op, step)); }
+    program.push(OpCode::Clk);
+    return Ok(true);
+}
+
+/// Appends WRITE operation to the program.
+pub fn parse_write(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    if op.len() > 1 { return Err(AssemblyError::extra_param(op, step)); }
+    program.push(OpCode::Write);
+    return Ok(true);
+}
+
+/// Appends a sequence of operations which writes an event record to the output tape. The record
+/// consists of the id of the event followed by the value at the top of the stack (the payload of
+/// the event); the payload is removed from the stack.
+pub fn parse_emit(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    if op.len() == 1 {
+        return Err(AssemblyError::missing_param(op, step));
+    }
+    else if op.len() > 2 {
+        return Err(AssemblyError::extra_param(op, step));
+    }
+
+    let id = match op[1].parse::<u32>() {
+        Ok(id) => id,
+        Err(_) => return Err(AssemblyError::invalid_param(op, step))
+    };
+    append_push_op(program, hints, id as u128);
+    hints.insert(program.len(), OpHint::Event(id));
+    program.push(OpCode::Write);
+    program.push(OpCode::Write);
+    return Ok(true);
+}
+
+/// Appends either READ or READ2 operation to the program. READ operation can also be used to
+/// request advice from the host, in which case the id of the host call is specified as well.
+pub fn parse_read(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    if op.len() == 2 && op[1] == "host" {
+        return Err(AssemblyError::missing_param(op, step));
+    }
+    else if op.len() == 3 && op[1] == "host" {
+        let id = match op[2].parse::<u32>() {
+            Ok(id) => id,
+            Err(_) => return Err(AssemblyError::invalid_param(op, step))
+        };
+        hints.insert(program.len(), OpHint::HostCall(id));
+        program.push(OpCode::Read);
+    }
+    else if op.len() > 2 {
         return Err(AssemblyError::extra_param(op, step));
     }
     else if op.len() == 1 || op[1] == "a" {
@@ -72,9 +144,17 @@ pub fn parse_read(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result
     else if op[1] == "ab" {
         program.push(OpCode::Read2);
     }
+    else if op[1] == "div" {
+        hints.insert(program.len(), OpHint::DivStart);
+        program.push(OpCode::Read);
+    }
+    else if op[1] == "divw" {
+        hints.insert(program.len(), OpHint::DivWStart);
+        program.push(OpCode::Read);
+    }
     else {
         return Err(AssemblyError::invalid_param_reason(op, step,
-            format!("parameter {} is invalid; allowed values are: [a, ab]", op[1])));
+            format!("parameter {} is invalid; allowed values are: [a, ab, div, divw, host]", op[1])));
     }
 
     return Ok(true);
@@ -211,6 +291,35 @@ pub fn parse_div(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result<
     return Ok(true);
 }
 
+/// Appends a sequence of operations to the program to raise the 2nd value on the stack to the
+/// power of the 1st value on the stack. Exponentiation is done via square-and-multiply over the
+/// binary decomposition of the exponent; the exponent must fit into n bits, or the operation fails.
+pub fn parse_exp(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    let n = read_param(op, step)?;
+    if n > 128 {
+        return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("parameter {} is invalid; value must be between 1 and 128", n)))
+    }
+
+    // prepare the stack: [1, 1, 1, 1, 0, base, exponent]; this sets the multiplier, the power
+    // of two, and the result to 1, and the accumulated exponent to 0
+    program.extend_from_slice(&[OpCode::Swap, OpCode::Pad2, OpCode::Not, OpCode::Dup, OpCode::Dup2]);
+
+    // add a hint indicating that binary decomposition of the exponent is about to start,
+    // and process all bits of the exponent one bit per cycle
+    hints.insert(program.len(), OpHint::ExpStart(n));
+    program.resize(program.len() + (n as usize), OpCode::ExpAcc);
+
+    // make sure binary decomposition of the exponent is equal to the exponent itself, and
+    // apply the multiplier for the last bit to the result
+    program.extend_from_slice(&[
+        OpCode::Dup2,  OpCode::Roll8, OpCode::Drop4, OpCode::Swap,
+        OpCode::Drop,  OpCode::Swap2, OpCode::AssertEq, OpCode::Mul,
+    ]);
+
+    return Ok(true);
+}
+
 /// Appends NEG operation to the program.
 pub fn parse_neg(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
     if op.len() > 1 { return Err(AssemblyError::extra_param(op, step)); }
@@ -392,6 +501,51 @@ pub fn parse_isodd(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str],
     return Ok(true);
 }
 
+/// Appends a sequence of operations to the program to split the top value on the stack into its
+/// lower n bits and the remaining upper m bits; the lower part is pushed onto the stack last. If
+/// the value cannot be represented with n + m bits, the operation will fail.
+pub fn parse_split(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    if op.len() < 3 {
+        return Err(AssemblyError::missing_param(op, step));
+    }
+    else if op.len() > 3 {
+        return Err(AssemblyError::extra_param(op, step));
+    }
+
+    // n + m must be less than 128 bits so that a value can be composed from its two parts
+    // without wrapping around the field modulus
+    let n = read_param(&op[..2], step)?;
+    let m = read_param(&[op[0], op[2]], step)?;
+    if n + m > 127 {
+        return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("parameters {} and {} are invalid; their sum must not exceed 127", n, m)))
+    }
+
+    // prepare the stack in the same way as for range checks
+    program.push(OpCode::Pad2);
+    append_push_op(program, hints, field::ONE);
+    program.extend_from_slice(&[OpCode::Swap, OpCode::Dup]);
+
+    // aggregate the lower n bits of the value, save the result, and then continue aggregating
+    // the upper m bits on top of the saved copy
+    hints.insert(program.len(), OpHint::RcStart(n + m));
+    program.resize(program.len() + (n as usize), OpCode::BinAcc);
+    program.push(OpCode::Dup4);
+    program.resize(program.len() + (m as usize), OpCode::BinAcc);
+
+    // make sure the aggregated value is equal to the original value, and then compute the upper
+    // part from the lower part: hi = (value - lo) / 2^n
+    program.extend_from_slice(&[
+        OpCode::Drop,  OpCode::Drop,  OpCode::Drop, OpCode::Swap2, OpCode::Drop, OpCode::Drop,
+        OpCode::Swap,  OpCode::Drop,  OpCode::Dup,  OpCode::Roll4, OpCode::AssertEq,
+        OpCode::Dup2,  OpCode::Swap,  OpCode::Neg,  OpCode::Add,
+    ]);
+    append_push_op(program, hints, field::inv(field::exp(2, n as u128)));
+    program.extend_from_slice(&[OpCode::Mul, OpCode::Swap, OpCode::Drop, OpCode::Swap]);
+
+    return Ok(true);
+}
+
 // SELECTOR OPERATIONS
 // ================================================================================================
 
@@ -422,19 +576,84 @@ pub fn parse_hash(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result
             format!("parameter {} is invalid; allowed values are: [1, 2, 3, 4]", n)))
     }
 
-    // pad with NOOPs to make sure hashing starts on a step which is a multiple of 16
-    let alignment = program.len() % HASH_OP_ALIGNMENT;
-    let pad_length = (HASH_OP_ALIGNMENT - alignment) % HASH_OP_ALIGNMENT;
-    program.resize(program.len() + pad_length, OpCode::Noop);
+    // apply Rescue permutation and truncate the state
+    append_permutation(program);
+    program.push(OpCode::Drop4);
+
+    return Ok(true);
+}
+
+/// Appends a sequence of operations to the program to apply n Rescue permutations to the top 6
+/// items of the stack.
+pub fn parse_hashr(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    let n = read_param(op, step)?;
+    if n > 256 {
+        return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("parameter {} is invalid; value must be between 1 and 256", n)))
+    }
+
+    for _ in 0..n {
+        append_permutation(program);
+    }
+
+    return Ok(true);
+}
+
+/// Appends a sequence of operations to the program to absorb the top 4 items of the stack into
+/// the sponge state formed by the next 6 items. The first 4 items of the state are the rate and
+/// the remaining 2 items are the capacity; the absorbed items are added to the rate, and then
+/// the state is permuted.
+pub fn parse_absorb(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    if op.len() > 1 {
+        return Err(AssemblyError::extra_param(op, step));
+    }
+    append_absorb(program);
+    return Ok(true);
+}
+
+/// Appends a sequence of operations to the program to absorb the remaining n items at the top of
+/// the stack into the sponge state formed by the next 6 items, and to replace the state with the
+/// first 2 items of its rate. The items are padded with a 1 followed by as many 0's as needed to
+/// form a full block; so, when the number of hashed items is a multiple of 4, `squeeze` absorbs
+/// a block consisting of padding only.
+pub fn parse_squeeze(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    let n = if op.len() == 1 { 0 } else { read_param(op, step)? };
+    match n {
+        0 => program.extend_from_slice(&[OpCode::Pad2, OpCode::Not, OpCode::Swap, OpCode::Pad2]),
+        1 => program.extend_from_slice(&[OpCode::Pad2, OpCode::Drop, OpCode::Not, OpCode::Pad2]),
+        2 => program.extend_from_slice(&[OpCode::Pad2, OpCode::Not, OpCode::Swap]),
+        3 => program.extend_from_slice(&[OpCode::Pad2, OpCode::Drop, OpCode::Not]),
+        _ => return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("parameter {} is invalid; allowed values are: [1, 2, 3]", n)))
+    }
 
-    // append operations to execute 10 rounds of Rescue
+    // absorb the padded block, and then drop everything except for the first 2 items of the rate
+    append_absorb(program);
     program.extend_from_slice(&[
-        OpCode::RescR, OpCode::RescR, OpCode::RescR, OpCode::RescR, OpCode::RescR,
-        OpCode::RescR, OpCode::RescR, OpCode::RescR, OpCode::RescR, OpCode::RescR
+        OpCode::Swap2, OpCode::Drop, OpCode::Drop, OpCode::Swap2, OpCode::Drop, OpCode::Drop
     ]);
 
-    // truncate the state
-    program.push(OpCode::Drop4);
+    return Ok(true);
+}
+
+/// Appends a sequence of operations to the program to draw n pseudo-random values from the
+/// sponge state formed by the top 6 items of the stack. The state is permuted, and then copies
+/// of n items of its rate are pushed onto the stack; the state remains on the stack beneath
+/// them so that more values can be drawn from it later.
+pub fn parse_random(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    let n = read_param(op, step)?;
+    if n > 4 {
+        return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("parameter {} is invalid; allowed values are: [1, 2, 3, 4]", n)))
+    }
+
+    append_permutation(program);
+    match n {
+        1 => program.push(OpCode::Dup),
+        2 => program.push(OpCode::Dup2),
+        3 => program.extend_from_slice(&[OpCode::Dup4, OpCode::Drop]),
+        _ => program.push(OpCode::Dup4),
+    }
 
     return Ok(true);
 }
@@ -454,22 +673,15 @@ pub fn parse_smpath(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Resu
         OpCode::Read2, OpCode::Swap2, OpCode::Read2, OpCode::CSwap2, OpCode::Pad2
     ]);
 
-    // pad with NOOPs to make sure hashing starts on a step which is a multiple of 16
-    let alignment = program.len() % HASH_OP_ALIGNMENT;
-    let pad_length = (HASH_OP_ALIGNMENT - alignment) % HASH_OP_ALIGNMENT;
-    program.resize(program.len() + pad_length, OpCode::Noop);
-
     // repeat the following cycle of operations once for each remaining node:
     // 1. compute hash of the 2 nodes on the stack
     // 2. read the index of the next node in the authentication path
     // 3. read the next node in the authentication path
     // 4. base on position index bit = 1, swaps the nodes on the stack (using cswap2 instruction)
     // 5. pad the stack to prepare it for the next round of hashing
-    const SUB_CYCLE: [OpCode; 16] = [
-        OpCode::RescR, OpCode::RescR, OpCode::RescR,  OpCode::RescR,
-        OpCode::RescR, OpCode::RescR, OpCode::RescR,  OpCode::RescR,
-        OpCode::RescR, OpCode::RescR, OpCode::Drop4,  OpCode::Read2,
-        OpCode::Swap2, OpCode::Read2, OpCode::CSwap2, OpCode::Pad2,
+    const SUB_CYCLE: [OpCode; 7] = [
+        OpCode::RescR, OpCode::Drop4,  OpCode::Read2, OpCode::Swap2,
+        OpCode::Read2, OpCode::CSwap2, OpCode::Pad2,
     ];
 
     for _ in 0..(n - 2) {
@@ -478,7 +690,7 @@ pub fn parse_smpath(program: &mut Vec<OpCode>, op: &[&str], step: usize) -> Resu
 
     // at the end, use the same cycle except for the last 5 operations
     // since there is no need to read in any additional nodes
-    program.extend_from_slice(&SUB_CYCLE[..11]);
+    program.extend_from_slice(&SUB_CYCLE[..2]);
 
     return Ok(true);
 }
@@ -503,43 +715,111 @@ pub fn parse_pmpath(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str],
         OpCode::Swap, OpCode::Dup, OpCode::BinAcc, OpCode::Swap4, OpCode::CSwap2, OpCode::Pad2
     ]);
 
-    // pad with NOOPs to make sure hashing starts on a step which is a multiple of 16
-    let alignment = program.len() % HASH_OP_ALIGNMENT;
-    let pad_length = (HASH_OP_ALIGNMENT - alignment) % HASH_OP_ALIGNMENT;
-    program.resize(program.len() + pad_length, OpCode::Noop);
-
     // repeat the following cycle of operations once for each remaining node:
     // 1. compute hash of the 2 nodes on the stack
     // 2. read the index of the next node in the authentication path (using binacc instruction)
     // 3. read the next node in the authentication path
     // 4. base on position index bit = 1, swap the nodes on the stack (using cswap2 instruction)
     // 5. pad the stack to prepare it for the next round of hashing
-    const SUB_CYCLE: [OpCode; 32] = [
-        OpCode::RescR, OpCode::RescR,  OpCode::RescR, OpCode::RescR,
-        OpCode::RescR, OpCode::RescR,  OpCode::RescR, OpCode::RescR,
-        OpCode::RescR, OpCode::RescR,  OpCode::Drop4, OpCode::Pad2,
-        OpCode::Swap2, OpCode::Read2,  OpCode::Swap4, OpCode::BinAcc,
-        OpCode::Swap4, OpCode::CSwap2, OpCode::Pad2,  OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,   OpCode::Noop,  OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,   OpCode::Noop,  OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,   OpCode::Noop,  OpCode::Noop,
+    const SUB_CYCLE: [OpCode; 10] = [
+        OpCode::RescR, OpCode::Drop4,  OpCode::Pad2,  OpCode::Swap2,
+        OpCode::Read2, OpCode::Swap4,  OpCode::BinAcc, OpCode::Swap4,
+        OpCode::CSwap2, OpCode::Pad2,
     ];
 
     for _ in 0..(n - 2) {
         program.extend_from_slice(&SUB_CYCLE);
     }
 
-    // at the end, use the first 11 operations from the cycle since there is nothing else to read;
+    // at the end, use the first 2 operations from the cycle since there is nothing else to read;
     // then make sure the accumulated value of index is indeed equal to the leaf index
-    program.extend_from_slice(&SUB_CYCLE[..11]);
+    program.extend_from_slice(&SUB_CYCLE[..2]);
     program.extend_from_slice(&[OpCode::Swap2, OpCode::Drop, OpCode::Roll4, OpCode::AssertEq]);
 
     return Ok(true);
 }
 
+/// Appends a sequence of operations to the program to update a leaf of a Merkle tree of depth n.
+/// The stack is expected to contain the new value of the leaf, followed by the old value of the
+/// leaf, followed by the leaf index. Nodes of the authentication path are read from input tapes
+/// A and B only once, and are used to compute both the old and the new roots of the tree.
+pub fn parse_mrupdate(program: &mut Vec<OpCode>, hints: &mut HintMap, op: &[&str], step: usize) -> Result<bool, AssemblyError> {
+    let n = read_param(op, step)?;
+    if n < 2 || n > 128 {
+        return Err(AssemblyError::invalid_param_reason(op, step,
+            format!("parameter {} is invalid; value must be between 2 and 128", n)))
+    }
+
+    // set initial values of binary multiplier and index accumulator to 1 and 0 respectively,
+    // and arrange the stack as [pad, pad, 1, 0, old, new, index]
+    program.extend_from_slice(&[OpCode::Swap2, OpCode::Pad2, OpCode::Not, OpCode::Pad2]);
+
+    // add a hint indicating that mrupdate macro is about to begin
+    hints.insert(program.len(), OpHint::MrupdateStart(n));
+
+    // read the first bit of the index and the first node of the path; then make copies of both
+    // so that each can be used in hashing of the old and the new leaves
+    const PATH_READ: [OpCode; 13] = [
+        OpCode::BinAcc, OpCode::Swap2, OpCode::Swap4, OpCode::Roll8, OpCode::Roll8,
+        OpCode::Read2,  OpCode::Dup2,  OpCode::Swap4, OpCode::Dup2,  OpCode::Swap4,
+        OpCode::Swap2,  OpCode::CSwap2, OpCode::Pad2,
+    ];
+    program.extend_from_slice(&PATH_READ);
+
+    // repeat the following cycle of operations once for each remaining node:
+    // 1. compute hash of the old node and the node from the authentication path
+    // 2. move the copies of the path node and the index bit to the top of the stack
+    // 3. compute hash of the new node and the node from the authentication path
+    // 4. move binary accumulator to the top of the stack and read the next bit of the index
+    // 5. read the next node of the authentication path, make copies of the node and the index
+    //    bit, and prepare the stack for hashing of the old node
+    const SUB_CYCLE: [OpCode; 26] = [
+        OpCode::RescR,  OpCode::Drop4,  OpCode::Swap2,  OpCode::Swap4,
+        OpCode::CSwap2, OpCode::Pad2,   OpCode::RescR,  OpCode::Drop4,
+        OpCode::Swap2,  OpCode::Pad2,   OpCode::Roll8,  OpCode::Roll8,
+        OpCode::Swap2,  OpCode::BinAcc, OpCode::Swap2,  OpCode::Swap4,
+        OpCode::Roll8,  OpCode::Roll8,  OpCode::Read2,  OpCode::Dup2,
+        OpCode::Swap4,  OpCode::Dup2,   OpCode::Swap4,  OpCode::Swap2,
+        OpCode::CSwap2, OpCode::Pad2,
+    ];
+
+    for _ in 0..(n - 2) {
+        program.extend_from_slice(&SUB_CYCLE);
+    }
+
+    // at the end, use the first 8 operations from the cycle since there is nothing else to read;
+    // then make sure the accumulated value of index is indeed equal to the leaf index, and drop
+    // everything except for the new and the old roots
+    program.extend_from_slice(&SUB_CYCLE[..8]);
+    program.extend_from_slice(&[
+        OpCode::Dup, OpCode::Roll8, OpCode::Roll8, OpCode::Roll8, OpCode::Drop, OpCode::AssertEq,
+        OpCode::Drop
+    ]);
+
+    return Ok(true);
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
 
+/// Appends operations to add the top 4 items of the stack to the rate of the sponge state formed
+/// by the next 6 items, followed by a full Rescue permutation of the state.
+fn append_absorb(program: &mut Vec<OpCode>) {
+    // [x0, x1, x2, x3, r0, r1, r2, r3, c0, c1] becomes [r0+x0, r1+x1, r2+x2, r3+x3, c0, c1]
+    program.extend_from_slice(&[
+        OpCode::Swap,  OpCode::Roll4, OpCode::Roll8, OpCode::Add,   OpCode::Roll8,
+        OpCode::Swap,  OpCode::Swap2, OpCode::Swap4, OpCode::Roll4, OpCode::Add,
+        OpCode::Swap2, OpCode::Add,   OpCode::Swap2, OpCode::Add,
+    ]);
+    append_permutation(program);
+}
+
+/// Appends a full Rescue permutation to the program; the permutation is computed by the hash
+/// co-processor in a single RESCR operation.
+fn append_permutation(program: &mut Vec<OpCode>) {
+    program.push(OpCode::RescR);
+}
+
 fn read_param(op: &[&str], step: usize) -> Result<u32, AssemblyError> {
     if op.len() == 1 {
         // if no parameters were provided, assume parameter value 1
@@ -572,18 +852,40 @@ fn read_value(op: &[&str], step: usize) -> Result<u128, AssemblyError> {
         return Err(AssemblyError::extra_param(op, step));
     }
 
-    let result = if op[1].starts_with("0x") {
+    return parse_value(op, op[1], step);
+}
+
+fn read_word(op: &[&str], step: usize) -> Result<[u128; 4], AssemblyError> {
+    // make sure exactly 4 parameters were supplied
+    if op.len() < 5 {
+        return Err(AssemblyError::missing_param(op, step));
+    }
+    else if op.len() > 5 {
+        return Err(AssemblyError::extra_param(op, step));
+    }
+
+    let mut result = [field::ZERO; 4];
+    for i in 0..4 {
+        result[i] = parse_value(op, op[i + 1], step)?;
+    }
+    return Ok(result);
+}
+
+fn parse_value(op: &[&str], param: &str, step: usize) -> Result<u128, AssemblyError> {
+    let result = if param.starts_with("0x") {
         // parse hexadecimal number
-        match u128::from_str_radix(&op[1][2..], 16) {
+        match u128::from_str_radix(&param[2..], 16) {
             Ok(i) => i,
-            Err(_) => return Err(AssemblyError::invalid_param(op, step))
+            Err(_) => return Err(AssemblyError::invalid_param_reason(op, step,
+                format!("parameter '{}' is invalid", param)))
         }
     }
     else {
         // parse decimal number
-        match u128::from_str_radix(&op[1], 10) {
+        match u128::from_str_radix(param, 10) {
             Ok(i) => i,
-            Err(_) => return Err(AssemblyError::invalid_param(op, step))
+            Err(_) => return Err(AssemblyError::invalid_param_reason(op, step,
+                format!("parameter '{}' is invalid", param)))
         }
     };
 
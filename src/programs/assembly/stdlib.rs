@@ -0,0 +1,139 @@
+// STANDARD LIBRARY MODULES
+// ================================================================================================
+
+/// Modules of the standard library in the order of their dependencies; every module is compiled
+/// and added to the linker by `Linker::with_stdlib()`.
+pub const MODULES: [(&str, &str); 2] = [
+    ("u256", U256),
+    ("fixed", FIXED),
+];
+
+/// Arithmetic over unsigned 256-bit integers. An integer is represented by 4 stack items holding
+/// 64-bit limbs, with the least significant limb at the top of the stack; `add`, `sub`, `mul`,
+/// `mul_exact` and `mod` check that all limbs of their operands fit into 64 bits, and fail if
+/// they do not. Unless stated otherwise, operations wrap around 2^256.
+///
+/// * `check` - fails if any limb of the integer at the top of the stack does not fit into 64
+///   bits; the integer is left on the stack.
+/// * `add` - pops integers a and b from the stack and pushes a + b onto the stack.
+/// * `sub` - pops integers a and b from the stack and pushes a - b onto the stack.
+/// * `mul` - pops integers a and b from the stack and pushes a * b onto the stack.
+/// * `mul_exact` - same as `mul`, but fails if a * b does not fit into 256 bits.
+/// * `mod` - pops integers a and b from the stack and pushes a mod b onto the stack; fails if b
+///   is 0.
+/// * `add_unchecked` - same as `add`, but limbs of the operands are not checked; limbs of b may
+///   be as large as 2^64.
+/// * `mul_limbs` - pops 64-bit limbs x and y from the stack and pushes the low and the high
+///   64 bits of x * y onto the stack, with the low bits at the top; limbs are not checked.
+///
+/// A product of two limbs is below the field modulus, but does not fit into `split.n.m`, so
+/// `mul_limbs` reads its high half from tape A using `read.div`, and range-checks both halves.
+/// `mod` reads the quotient a / b using `read.divw`, checks that the quotient times b fits into
+/// 256 bits, and that the remainder is less than b; b and a are packed into 3 items each, split
+/// at 32-bit boundaries, so that all intermediate values remain within reach of the stack.
+const U256: &str = "
+    proc.check
+        dup rc.64 assert roll.4 dup rc.64 assert roll.4
+        dup rc.64 assert roll.4 dup rc.64 assert roll.4
+    end
+    proc.add_unchecked
+        swap swap.2 swap swap.4 roll.8 add split.64.1 roll.4 swap.4 roll.4 roll.8 roll.8
+        add add split.64.1 swap.2 add swap.2 add roll.4 add split.64.1 swap.2 roll.4
+        add split.64.1 swap.2 roll.4 drop roll.4
+    end
+    proc.add
+        exec.check swap.4 exec.check swap.4
+        exec.add_unchecked
+    end
+    proc.sub
+        exec.check swap.4 exec.check
+        neg push.0x10000000000000000 add roll.4
+        neg push.0xffffffffffffffff add roll.4
+        neg push.0xffffffffffffffff add roll.4
+        neg push.0xffffffffffffffff add roll.4
+        swap.4
+        exec.add_unchecked
+    end
+    proc.mul_limbs
+        mul push.0x10000000000000000 read.div swap drop read dup rc.64 assert
+        push.0xffffffffffffffff pick.2 lt.64 assert
+        pick.1 push.0x10000000000000000 mul pick.1 add roll.4 assert.eq
+    end
+    proc.mul
+        exec.check swap.4 exec.check roll.8 pick.1 swap exec.mul_limbs swap drop swap.4 pick.1
+        exec.mul_limbs swap drop swap swap.2 swap.4 roll.8 add swap.2 pad roll.8 swap.2 roll.8
+        dup.2 exec.mul_limbs swap drop swap roll.8 roll.8 roll.4 add swap.2 roll.4 swap pick.1
+        swap exec.mul_limbs swap.2 swap.4 roll.8 add swap roll.8 dup.2 exec.mul_limbs swap drop
+        roll.4 swap add swap.4 roll.8 roll.8 pick.1 exec.mul_limbs swap roll.8 swap add roll.8
+        roll.8 roll.4 add pad roll.8 roll.4 dup.2 exec.mul_limbs roll.8 roll.8 roll.4 add
+        swap.4 roll.8 roll.4 roll.8 add swap roll.8 swap pick.1 swap exec.mul_limbs swap roll.4
+        swap add swap.4 roll.4 roll.8 pick.1 exec.mul_limbs roll.8 roll.8 roll.4 add swap.2
+        roll.4 swap add pad roll.8 roll.4 exec.mul_limbs swap roll.4 swap add split.64.2 swap.2
+        swap.4 roll.8 add split.64.3 swap.2 swap.4 roll.8 add split.64.3 swap drop swap.2 drop
+        roll.4 drop roll.4 drop roll.4 swap roll.4
+    end
+    proc.mul_exact
+        exec.check swap.4 exec.check roll.4 roll.8 dup.2 mul push.0 assert.eq swap swap.2
+        swap.4 roll.8 dup.2 mul push.0 assert.eq swap roll.8 roll.8 roll.4 dup.2 mul push.0
+        assert.eq roll.8 swap pick.1 swap exec.mul_limbs swap push.0 assert.eq roll.4 roll.8
+        dup.2 mul push.0 assert.eq swap roll.8 dup.2 mul push.0 assert.eq swap roll.8 swap
+        pick.1 exec.mul_limbs swap push.0 assert.eq swap swap.2 swap.4 roll.8 add swap.2 pad
+        roll.8 roll.4 roll.8 dup.2 mul push.0 assert.eq swap swap.2 swap.4 roll.8 dup.2
+        exec.mul_limbs swap push.0 assert.eq roll.4 swap add swap roll.4 swap pick.1 swap
+        exec.mul_limbs swap roll.4 swap add swap.4 roll.4 swap dup.2 exec.mul_limbs swap push.0
+        assert.eq swap.4 drop roll.4 add pad swap.4 swap.2 roll.4 pick.1 exec.mul_limbs roll.8
+        roll.8 roll.4 add swap.2 roll.4 swap add swap pad swap.2 swap.4 swap dup.2
+        exec.mul_limbs swap roll.8 swap add roll.8 roll.8 roll.4 add swap.4 swap.2 roll.8
+        pick.1 swap exec.mul_limbs roll.8 roll.8 roll.4 add swap.4 swap pick.1 exec.mul_limbs
+        swap.2 swap.4 swap roll.8 add swap.4 roll.8 roll.4 add swap roll.4 swap exec.mul_limbs
+        swap.2 roll.4 add split.64.2 swap.2 drop swap.2 add split.64.3 pad pad swap.4 swap.2
+        roll.8 add dup rc.64 assert swap drop roll.4 drop roll.4 drop swap.2 roll.4
+    end
+    proc.mod
+        exec.check swap.4 exec.check read.divw swap.2 split.32.32 push.0x10000000000000000 mul
+        swap swap.2 swap.4 roll.8 add swap.4 push.0x100000000 mul swap swap.2 roll.4 add swap.4
+        swap.2 split.32.32 push.0x10000000000000000 mul swap swap.2 swap.4 roll.8 add swap.2
+        push.0x100000000 mul swap roll.8 roll.8 roll.4 add pad swap swap.2 swap.4 roll.4 dup
+        roll.8 roll.4 swap.4 dup roll.8 roll.4 swap.4 dup swap swap.4 swap.2 swap roll.8 roll.8
+        swap.2 roll.8 split.64.32 swap.2 split.32.64 push.0x100000000 mul swap swap.2 swap.4
+        roll.8 add swap.2 swap.4 swap roll.8 roll.4 roll.8 swap.2 read read read exec.mul_exact
+        swap.4 swap.2 roll.8 roll.4 swap.4 split.64.32 roll.4 swap roll.4 neg add
+        push.0x10000000000000000 add split.64.1 swap swap.2 swap.4 roll.4 split.32.64
+        push.0x100000000 mul swap roll.8 roll.8 roll.4 add swap.4 roll.4 swap.4 roll.8 neg add
+        add push.0xffffffffffffffff add split.64.1 swap swap.2 swap neg add add
+        push.0xffffffffffffffff add split.64.1 swap.2 swap.4 swap roll.4 roll.8 swap.2 neg add
+        add push.0xffffffffffffffff add split.64.1 swap assert roll.8 drop swap.2 split.64.32
+        swap.4 swap dup swap.4 swap.2 swap.4 roll.8 neg add push.0x10000000000000000 add
+        split.64.1 drop swap.2 split.32.64 push.0x100000000 mul roll.8 roll.8 roll.8 roll.4 add
+        swap dup roll.8 swap swap.2 roll.8 swap.2 neg add add push.0xffffffffffffffff add
+        split.64.1 drop pad swap.4 swap.2 dup swap.4 swap drop roll.4 swap neg add add
+        push.0xffffffffffffffff add split.64.1 drop roll.4 dup swap roll.4 pad swap.4 roll.8
+        roll.4 neg add add push.0xffffffffffffffff add split.64.1 drop not assert swap.2 drop
+        swap.2 swap swap.2 roll.4
+    end";
+
+/// Arithmetic over unsigned fixed-point numbers with 32 fractional bits. A number x is
+/// represented by a single stack item holding x * 2^32, and this value must be less than 2^63;
+/// every procedure checks that its operands and its result satisfy this condition, and fails if
+/// they do not. Results are rounded to the nearest representable number, with ties rounded up.
+///
+/// * `check` - fails if the number at the top of the stack is not a valid fixed-point number;
+///   the number is left on the stack.
+/// * `mul` - pops numbers a and b from the stack and pushes a * b onto the stack.
+/// * `div` - pops numbers a and b from the stack and pushes b / a onto the stack; fails if a
+///   is 0.
+const FIXED: &str = "
+    proc.check
+        dup rc.63 assert
+    end
+    proc.mul
+        exec.check swap exec.check
+        mul push.0x80000000 add split.32.63 drop
+    end
+    proc.div
+        exec.check swap exec.check
+        push.0x100000000 mul pick.1 split.1.62 drop add swap
+        read.div exec.check read
+        pick.2 pick.1 lt.63 assert
+        pick.2 pick.2 mul add roll.4 assert.eq swap drop
+    end";
@@ -7,9 +7,7 @@ fn single_block() {
 
     let expected = "\
         begin noop noop noop noop noop noop noop \
-        push(1) noop noop noop noop noop noop noop \
-        push(2) add noop noop noop noop noop noop \
-        noop noop noop noop noop noop noop end";
+        push(1) push(2) add noop noop noop noop end";
 
     assert_eq!(expected, format!("{:?}", program));
 }
@@ -22,10 +20,10 @@ fn sequence_of_blocks() {
     let expected = "\
         begin noop noop noop noop noop noop noop \
         noop noop noop noop noop noop noop block \
-        push(1) noop noop noop noop noop noop noop \
-        push(2) add noop noop noop noop noop end \
-        block push(3) noop noop noop noop noop noop \
-        noop push(4) add noop noop noop noop noop \
+        push(1) push(2) add noop noop noop noop noop \
+        noop noop noop noop noop noop noop end \
+        block push(3) push(4) add noop noop noop noop \
+        noop noop noop noop noop noop noop noop \
         end end";
 
     assert_eq!(expected, format!("{:?}", program));
@@ -39,10 +37,10 @@ fn sequence_of_blocks_with_prefix() {
     let expected = "\
         begin read read add noop noop noop noop \
         noop noop noop noop noop noop noop block \
-        push(1) noop noop noop noop noop noop noop \
-        push(2) add noop noop noop noop noop end \
-        block push(3) noop noop noop noop noop noop \
-        noop push(4) neg add noop noop noop noop \
+        push(1) push(2) add noop noop noop noop noop \
+        noop noop noop noop noop noop noop end \
+        block push(3) push(4) neg add noop noop noop \
+        noop noop noop noop noop noop noop noop \
         end end";
 
     assert_eq!(expected, format!("{:?}", program));
@@ -56,19 +54,157 @@ fn sequence_of_blocks_with_prefix_and_suffix() {
     let expected = "\
         begin read read add noop noop noop noop \
         noop noop noop noop noop noop noop block \
-        push(1) noop noop noop noop noop noop noop \
-        push(2) add noop noop noop noop noop end \
-        block push(3) noop noop noop noop noop noop \
-        noop push(4) neg add noop noop noop noop \
-        end pad2 pad2 noop noop noop noop noop \
+        push(1) push(2) add noop noop noop noop noop \
+        noop noop noop noop noop noop noop end \
+        block push(3) push(4) neg add noop noop noop \
+        noop noop noop noop noop noop noop noop \
+        end pad2 pad2 rescr drop4 noop noop noop \
         noop noop noop noop noop noop noop noop \
-        noop rescr rescr rescr rescr rescr rescr rescr \
-        rescr rescr rescr drop4 noop noop noop noop \
         end";
 
     assert_eq!(expected, format!("{:?}", program));
 }
 
+#[test]
+fn push_word() {
+    let source = "begin push.1 pushw.2.3.4.0x5 push.6 end";
+    let program = super::compile(source).unwrap();
+
+    let expected = "\
+        begin noop noop noop noop noop noop noop \
+        push(1) noop noop noop noop noop noop noop \
+        push(2) push(3) push(4) push(5) noop noop noop noop \
+        push(6) noop noop noop noop noop noop end";
+
+    assert_eq!(expected, format!("{:?}", program));
+
+    // a word must consist of exactly 4 values
+    let error = super::compile("begin pushw.1.2.3 end").err().unwrap();
+    assert_eq!("malformed instruction pushw: parameter is missing", error.message());
+
+    let error = super::compile("begin pushw.1.2.3.4.5 end").err().unwrap();
+    assert_eq!("malformed instruction pushw: too many parameters provided", error.message());
+
+    let error = super::compile("begin pushw.1.2.x.4 end").err().unwrap();
+    assert_eq!("malformed instruction pushw: parameter 'x' is invalid", error.message());
+}
+
+#[test]
+fn emit_events() {
+    let source = "begin push.7 emit.3 end";
+    let program = super::compile(source).unwrap();
+
+    let expected = "\
+        begin noop noop noop noop noop noop noop \
+        push(7) push(3) write.event.3 write noop noop noop end";
+
+    assert_eq!(expected, format!("{:?}", program));
+
+    let error = super::compile("begin emit end").err().unwrap();
+    assert_eq!("malformed instruction emit: parameter is missing", error.message());
+
+    let error = super::compile("begin emit.x end").err().unwrap();
+    assert_eq!("malformed instruction emit: parameter 'x' is invalid", error.message());
+}
+
+#[test]
+fn split_value() {
+    let source = "begin split.4.4 end";
+    let program = super::compile(source).unwrap();
+
+    let expected = "\
+        begin pad2 noop noop noop noop noop noop \
+        push(1) swap dup binacc.8 binacc binacc binacc dup4 \
+        binacc binacc binacc binacc drop drop drop swap2 \
+        drop drop swap drop dup roll4 asserteq dup2 \
+        swap neg add noop noop noop noop noop \
+        push(319014718988379809496913648081635901441) mul swap drop swap noop noop end";
+
+    assert_eq!(expected, format!("{:?}", program));
+
+    let error = super::compile("begin split.4 end").err().unwrap();
+    assert_eq!("malformed instruction split: parameter is missing", error.message());
+
+    let error = super::compile("begin split.64.64 end").err().unwrap();
+    assert_eq!("malformed instruction split: parameters 64 and 64 are invalid; their sum must not exceed 127", error.message());
+}
+
+#[test]
+fn hash_permutations() {
+    let source = "begin push.1 hashr.2 push.2 end";
+    let program = super::compile(source).unwrap();
+
+    // every permutation is a single operation which does not need to be aligned
+    let expected = "\
+        begin noop noop noop noop noop noop noop \
+        push(1) rescr rescr noop noop noop noop noop \
+        push(2) noop noop noop noop noop noop noop \
+        noop noop noop noop noop noop noop end";
+
+    assert_eq!(expected, format!("{:?}", program));
+
+    let error = super::compile("begin hashr.257 end").err().unwrap();
+    assert_eq!("malformed instruction hashr: parameter 257 is invalid; value must be between 1 and 256", error.message());
+}
+
+#[test]
+fn sponge_operations() {
+    let source = "begin absorb squeeze.3 end";
+    let program = super::compile(source).unwrap();
+
+    // absorbed items are added to the rate, and then the state is permuted
+    let expected = "\
+        begin swap roll4 roll8 add roll8 swap swap2 \
+        swap4 roll4 add swap2 add swap2 add rescr \
+        pad2 drop not swap roll4 roll8 add roll8 \
+        swap swap2 swap4 roll4 add swap2 add swap2 \
+        add rescr swap2 drop drop swap2 drop drop \
+        noop noop noop noop noop noop noop end";
+
+    assert_eq!(expected, format!("{:?}", program));
+
+    let error = super::compile("begin absorb.2 end").err().unwrap();
+    assert_eq!("malformed instruction absorb: too many parameters provided", error.message());
+
+    let error = super::compile("begin squeeze.4 end").err().unwrap();
+    assert_eq!("malformed instruction squeeze: parameter 4 is invalid; allowed values are: [1, 2, 3]", error.message());
+}
+
+#[test]
+fn random_values() {
+    let source = "begin random.3 end";
+    let program = super::compile(source).unwrap();
+
+    let expected = "\
+        begin rescr dup4 drop noop noop noop noop \
+        noop noop noop noop noop noop noop end";
+
+    assert_eq!(expected, format!("{:?}", program));
+
+    let error = super::compile("begin random.5 end").err().unwrap();
+    assert_eq!("malformed instruction random: parameter 5 is invalid; allowed values are: [1, 2, 3, 4]", error.message());
+}
+
+#[test]
+fn instruction_cycles() {
+    // simple instructions always take the same number of cycles
+    assert_eq!(1, super::instruction_cycles("add", 3).unwrap());
+    assert_eq!(2, super::instruction_cycles("sub", 3).unwrap());
+
+    // cost of instructions which need to be aligned depends on where they are placed
+    assert_eq!(1, super::instruction_cycles("push.1", 0).unwrap());
+    assert_eq!(6, super::instruction_cycles("push.1", 3).unwrap());
+
+    // hash permutations are not aligned, and so, their cost does not depend on placement
+    assert_eq!(4, super::instruction_cycles("hash.2", 0).unwrap());
+    assert_eq!(4, super::instruction_cycles("hash.2", 14).unwrap());
+    assert_eq!(2, super::instruction_cycles("hashr.2", 0).unwrap());
+
+
+    let error = super::instruction_cycles("while.true", 0).err().unwrap();
+    assert_eq!("instruction while.true is invalid", error.message());
+}
+
 // SWITCH BLOCKS
 // ================================================================================================
 
@@ -89,9 +225,7 @@ fn single_if_else() {
 
     let expected = "\
         begin noop noop noop noop noop noop noop \
-        push(3) noop noop noop noop noop noop noop \
-        push(5) read noop noop noop noop noop noop \
-        noop noop noop noop noop noop noop if \
+        push(3) push(5) read noop noop noop noop if \
         assert add dup mul noop noop noop noop \
         noop noop noop noop noop noop noop else \
         not assert mul dup add noop noop noop \
@@ -120,9 +254,7 @@ fn single_if_else_with_suffix() {
     let expected = "\
         begin \
             noop noop noop noop noop noop noop \
-            push(3) noop noop noop noop noop noop noop \
-            push(5) read noop noop noop noop noop noop \
-            noop noop noop noop noop noop noop \
+            push(3) push(5) read noop noop noop noop \
             if \
                 assert add dup mul noop noop noop noop \
                 noop noop noop noop noop noop noop \
@@ -159,9 +291,7 @@ fn nested_if_else() {
 
     let expected = "\
     begin noop noop noop noop noop noop noop \
-        push(3) noop noop noop noop noop noop noop \
-        push(5) read noop noop noop noop noop noop \
-        noop noop noop noop noop noop noop \
+        push(3) push(5) read noop noop noop noop \
         if \
             assert add dup mul read::eq eq noop noop \
             noop noop noop noop noop noop noop \
@@ -198,9 +328,7 @@ fn single_loop() {
 
     let expected = "\
     begin noop noop noop noop noop noop noop \
-        push(3) noop noop noop noop noop noop noop \
-        push(5) read noop noop noop noop noop noop \
-        noop noop noop noop noop noop noop \
+        push(3) push(5) read noop noop noop noop \
         while \
             assert add dup mul read2 noop noop noop \
             noop noop noop noop noop noop noop \
@@ -229,9 +357,7 @@ fn loop_with_suffix_and_nested_if_else() {
 
     let expected = "\
     begin noop noop noop noop noop noop noop \
-        push(3) noop noop noop noop noop noop noop \
-        push(5) read noop noop noop noop noop noop \
-        noop noop noop noop noop noop noop \
+        push(3) push(5) read noop noop noop noop \
         while \
             assert add dup mul read2 noop noop noop \
             noop noop noop noop noop noop noop \
@@ -400,4 +526,111 @@ fn repeat_2_blocks_with_suffix() {
     end";
 
     assert_eq!(expected, format!("{:?}", program));
-}
\ No newline at end of file
+}
+// LINKED PROCEDURES
+// ================================================================================================
+#[test]
+fn link_procedures() {
+    let mut linker = super::Linker::new();
+    let math = linker.compile_module("math", "proc.square dup mul end proc.quad exec.square exec.square end").unwrap();
+    linker.add_module(math).unwrap();
+    let lib = linker.compile_module("lib", "proc.inc_quad push.1 add exec.math::quad end").unwrap();
+    linker.add_module(lib).unwrap();
+
+    // invoked procedures are inserted into the program in the same way as explicit blocks
+    let program = linker.link("begin push.2 exec.lib::inc_quad push.5 add end").unwrap();
+    let expected = super::compile("\
+        begin push.2 \
+            block push.1 add \
+                block block dup mul end block dup mul end end \
+            end \
+        push.5 add end").unwrap();
+
+    assert_eq!(expected.hash(), program.hash());
+    assert_eq!(format!("{:?}", expected), format!("{:?}", program));
+
+    // procedure hash does not depend on the program into which the procedure is linked
+    let procedure = linker.modules()[0].get_procedure("square").unwrap();
+    let module = super::Linker::new().compile_module("math", "proc.square dup mul end").unwrap();
+    assert_eq!(procedure.hash(), module.procedures()[0].hash());
+}
+
+#[test]
+fn call_procedures() {
+    let mut linker = super::Linker::new();
+    let math = linker.compile_module("math", "proc.square dup mul end proc.quad call.square exec.square end").unwrap();
+    linker.add_module(math).unwrap();
+
+    // called procedures are hashed in the same way as procedures inserted via exec
+    let program = linker.link("begin push.2 call.math::quad push.5 add end").unwrap();
+    let expected = linker.link("begin push.2 exec.math::quad push.5 add end").unwrap();
+    assert_eq!(expected.hash(), program.hash());
+
+    let expected = "\
+        begin noop noop noop noop noop noop noop push(2) noop noop noop noop noop noop \
+            call \
+                noop noop noop noop noop noop noop noop noop noop noop noop noop noop noop \
+                call dup mul noop noop noop noop noop noop noop noop noop noop noop noop noop end \
+                block dup mul noop noop noop noop noop noop noop noop noop noop noop noop noop end \
+            end \
+        push(5) add noop noop noop noop noop noop noop noop noop noop noop noop noop end";
+    assert_eq!(expected, format!("{:?}", program));
+
+    let err = linker.link("begin push.2 call.math::cube end").unwrap_err();
+    assert_eq!("procedure math::cube is not defined", err.message());
+}
+
+#[test]
+fn dynamic_calls() {
+    // dynamic calls do not require a linker because the called procedure is not a part of the program
+    let program = super::compile("begin push.2 dyncall push.5 add end").unwrap();
+    let expected = "\
+        begin noop noop noop noop noop noop noop push(2) noop noop noop noop noop noop \
+            dyncall \
+        push(5) add noop noop noop noop noop noop noop noop noop noop noop noop noop end";
+    assert_eq!(expected, format!("{:?}", program));
+
+    // procedures can be called dynamically by their code hash
+    let module = super::Linker::new().compile_module("math", "proc.square dup mul end proc.cube dup dup mul mul end").unwrap();
+    let square = module.get_procedure("square").unwrap();
+    let cube = module.get_procedure("cube").unwrap();
+    assert_ne!(square.code_hash(), cube.code_hash());
+
+    let err = super::compile("begin push.2 dyncall.math::square end").unwrap_err();
+    assert_eq!("malformed instruction dyncall: too many parameters provided", err.message());
+}
+
+#[test]
+fn link_procedures_fail() {
+    let mut linker = super::Linker::new();
+    let math = linker.compile_module("math", "proc.square dup mul end").unwrap();
+    linker.add_module(math.clone()).unwrap();
+
+    let err = linker.add_module(math).unwrap_err();
+    assert_eq!("module math has already been added to the linker", err.message());
+
+    let err = linker.link("begin push.2 exec.math::cube end").unwrap_err();
+    assert_eq!("procedure math::cube is not defined", err.message());
+    assert_eq!(2, err.step());
+
+    // procedures of other modules must be qualified with the module name
+    let err = linker.link("begin push.2 exec.square end").unwrap_err();
+    assert_eq!("procedure square is not defined", err.message());
+
+    // programs compiled without a linker cannot invoke procedures
+    let err = super::compile("begin push.2 exec.math::square end").unwrap_err();
+    assert_eq!("procedure math::square is not defined", err.message());
+
+    let err = linker.compile_module("lib", "proc.a push.1 end proc.a push.2 end").unwrap_err();
+    assert_eq!("procedure a is defined more than once", err.message());
+
+    // a procedure can invoke only procedures defined before it
+    let err = linker.compile_module("lib", "proc.a exec.b end proc.b push.2 end").unwrap_err();
+    assert_eq!("procedure b is not defined", err.message());
+
+    let err = linker.compile_module("lib", "push.1 proc.a push.2 end").unwrap_err();
+    assert_eq!("a module can contain only procedure definitions, but found 'push.1'", err.message());
+
+    let err = linker.compile_module("lib", "proc.a push.2").unwrap_err();
+    assert_eq!("proc without matching end", err.message());
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
-use super::{ OpCode, OpHint, hash_seq, hash_op, BASE_CYCLE_LENGTH };
+use serde::{ Serialize };
+use super::{ OpCode, OpHint, hash_seq, hash_op, BASE_CYCLE_LENGTH, PUSH_OP_ALIGNMENT, MAX_PUSH_SEQUENCE };
 
 #[cfg(test)]
 mod tests;
@@ -32,12 +33,15 @@ pub enum ProgramBlock {
     Group(Group),
     Switch(Switch),
     Loop(Loop),
+    Call(Call),
+    DynCall(DynCall),
 }
 
 #[derive(Clone)]
 pub struct Span {
     op_codes    : Vec<OpCode>,
     op_hints    : HashMap<usize, OpHint>,
+    sources     : Vec<Option<SourceLocation>>,  // empty if the span was not assembled from source
 }
 
 #[derive(Clone)]
@@ -57,6 +61,31 @@ pub struct Loop {
     skip        : Vec<ProgramBlock>,
 }
 
+#[derive(Clone)]
+pub struct Call {
+    body        : Vec<ProgramBlock>,
+}
+
+#[derive(Clone)]
+pub struct DynCall {
+}
+
+/// Location of an assembly instruction in the source from which it was compiled; lines and
+/// columns are counted from 1.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub struct SourceLocation {
+    pub line    : usize,
+    pub column  : usize,
+}
+
+/// A single instruction of a span. Operations which carry an immediate value (i.e. PUSH) are
+/// paired with the value, and all other operations are paired with their hints.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Instruction {
+    Push(u128),
+    Op(OpCode, OpHint),
+}
+
 // PROGRAM BLOCK IMPLEMENTATION
 // ================================================================================================
 
@@ -78,6 +107,8 @@ impl std::fmt::Debug for ProgramBlock {
             ProgramBlock::Group(block)  => write!(f, "{:?}", block)?,
             ProgramBlock::Switch(block) => write!(f, "{:?}", block)?,
             ProgramBlock::Loop(block)   => write!(f, "{:?}", block)?,
+            ProgramBlock::Call(block)   => write!(f, "{:?}", block)?,
+            ProgramBlock::DynCall(block)=> write!(f, "{:?}", block)?,
         }
         return Ok(());
     }
@@ -96,13 +127,18 @@ impl Span {
         // make sure all instructions are valid
         for i in 0..instructions.len() {
             let op_code = instructions[i];
-            if op_code == OpCode::Push {
-                assert!(i % 8 == 0, "PUSH is not allowed on step {}, must be on step which is a multiple of 8", i);
+            if op_code.has_immediate() {
+                // an operation with an immediate value must either be on a step which is a
+                // multiple of 8 or continue a short sequence of such operations
+                let offset = i % PUSH_OP_ALIGNMENT;
+                assert!(offset == 0 || (offset < MAX_PUSH_SEQUENCE && instructions[i - 1].has_immediate()),
+                    "{} is not allowed on step {}, must be on step which is a multiple of {} or follow a sequence of less than {} operations with immediate values",
+                    op_code, i, PUSH_OP_ALIGNMENT, MAX_PUSH_SEQUENCE);
                 let hint = hints.get(&i);
-                assert!(hint.is_some(), "invalid PUSH operation on step {}: operation value is missing", i);
+                assert!(hint.is_some(), "invalid {} operation on step {}: operation value is missing", op_code, i);
                 match hint.unwrap() {
                     OpHint::PushValue(_) => (),
-                    _ => panic!("invalid PUSH operation on step {}: operation value is of wrong type", i)
+                    _ => panic!("invalid {} operation on step {}: operation value is of wrong type", op_code, i)
                 }
             }
         }
@@ -115,10 +151,21 @@ impl Span {
 
         return Span {
             op_codes: instructions,
-            op_hints: hints
+            op_hints: hints,
+            sources : Vec::new(),
         };
     }
 
+    /// Returns the span with the specified locations of the instructions from which its
+    /// operations were compiled; operations which were not compiled from an instruction (e.g.
+    /// NOOPs which pad the span) have no location.
+    pub fn with_sources(mut self, sources: Vec<Option<SourceLocation>>) -> Span {
+        assert!(sources.len() == self.length(),
+            "expected {} source locations, but received {}", self.length(), sources.len());
+        self.sources = sources;
+        return self;
+    }
+
     pub fn new_block(instructions: Vec<OpCode>) -> ProgramBlock {
         return ProgramBlock::Span(Span::new(instructions, HashMap::new()));
     }
@@ -146,16 +193,35 @@ impl Span {
         };
     }
 
+    /// Returns the location of the instruction from which the operation at the specified `step`
+    /// of the span was compiled, if it is known.
+    pub fn get_source(&self, step: usize) -> Option<SourceLocation> {
+        return self.sources.get(step).cloned().flatten();
+    }
+
+    /// Returns the instruction at the specified `step` of the span.
+    pub fn get_instruction(&self, step: usize) -> Instruction {
+        let (op_code, op_hint) = self.get_op(step);
+        if op_code.has_immediate() {
+            return match op_hint {
+                OpHint::PushValue(op_value) => Instruction::Push(op_value),
+                _ => panic!("value for {} operation is missing", op_code)
+            };
+        }
+        return Instruction::Op(op_code, op_hint);
+    }
+
+    /// Returns an iterator over (step, instruction) pairs of the span.
+    pub fn instructions(&self) -> impl Iterator<Item = (usize, Instruction)> + '_ {
+        return (0..self.length()).map(move |step| (step, self.get_instruction(step)));
+    }
+
     pub fn hash(&self, mut state: [u128; 4]) -> [u128; 4] {
-        for (i, &op_code) in self.op_codes.iter().enumerate() {
-            let op_value = if op_code == OpCode::Push {
-                match self.get_hint(i) {
-                    OpHint::PushValue(op_value) => op_value,
-                    _ => panic!("value for PUSH operation is missing")
-                }
+        for (i, instruction) in self.instructions() {
+            match instruction {
+                Instruction::Push(op_value) => hash_op(&mut state, OpCode::Push as u8, op_value, i),
+                Instruction::Op(op_code, _) => hash_op(&mut state, op_code as u8, 0, i),
             }
-            else { 0 };
-            hash_op(&mut state, op_code as u8, op_value, i)
         }
         return state;
     }
@@ -173,8 +239,18 @@ impl Span {
             new_hints.insert(step + offset, hint);
         }
 
-        // build and return a new Span
-        return Span::new(new_op_codes, new_hints);
+        // merge source locations; spans which were not assembled from source have no locations
+        let mut span = Span::new(new_op_codes, new_hints);
+        if span1.sources.len() > 0 || span2.sources.len() > 0 {
+            let mut new_sources = span1.sources.clone();
+            new_sources.resize(span1.length() + 1, None);
+            new_sources.extend_from_slice(&span2.sources);
+            new_sources.resize(span.length(), None);
+            span = span.with_sources(new_sources);
+        }
+
+        // return the new Span
+        return span;
     }
 }
 
@@ -191,6 +267,12 @@ impl std::fmt::Debug for Span {
     }
 }
 
+impl std::fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "line {}, column {}", self.line, self.column);
+    }
+}
+
 // GROUP IMPLEMENTATION
 // ================================================================================================
 impl Group {
@@ -336,6 +418,73 @@ impl std::fmt::Debug for Loop {
     }
 }
 
+// CALL IMPLEMENTATION
+// ================================================================================================
+impl Call {
+
+    /// Returns a block which executes the `body` as a procedure call; a Call block is hashed in
+    /// the same way as a Group block with the same body, and thus, calling a procedure instead of
+    /// inlining it does not change the hash of a program.
+    pub fn new(body: Vec<ProgramBlock>) -> Call {
+        validate_block_list(&body, &[]);
+        return Call { body };
+    }
+
+    pub fn new_block(body: Vec<ProgramBlock>) -> ProgramBlock {
+        return ProgramBlock::Call(Call::new(body));
+    }
+
+    pub fn body(&self) -> &[ProgramBlock] {
+        return &self.body;
+    }
+
+    pub fn body_hash(&self) -> u128 {
+        return hash_seq(&self.body, &BLOCK_SUFFIX, BLOCK_SUFFIX_OFFSET);
+    }
+
+    pub fn get_hash(&self) -> (u128, u128) {
+        let v0 = self.body_hash();
+        return (v0, 0);
+    }
+}
+
+impl std::fmt::Debug for Call {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "call ")?;
+        for block in self.body.iter() {
+            write!(f, "{:?} ", block)?;
+        }
+        write!(f, "end")
+    }
+}
+
+// DYNAMIC CALL IMPLEMENTATION
+// ================================================================================================
+impl DynCall {
+
+    /// Returns a block which calls a procedure whose body hash is at the top of the stack; the
+    /// body of the procedure is supplied by the host at execution time. Hash of a DynCall block
+    /// does not depend on the called procedure, and thus, the same program can call different
+    /// procedures.
+    pub fn new() -> DynCall {
+        return DynCall { };
+    }
+
+    pub fn new_block() -> ProgramBlock {
+        return ProgramBlock::DynCall(DynCall::new());
+    }
+
+    pub fn get_hash(&self) -> (u128, u128) {
+        return (0, 0);
+    }
+}
+
+impl std::fmt::Debug for DynCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "dyncall")
+    }
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
 fn validate_block_list(blocks: &Vec<ProgramBlock>, starts_with: &[OpCode]) {
@@ -47,4 +47,45 @@ fn span_hash() {
         238085520613464573032580920836572617149,  98362585914038709664139524327351111560,
         159064915881679512167348007665307977960, 152057468867502483682425300737565245134,
     ], hash);
-}
\ No newline at end of file
+}
+#[test]
+fn span_push_sequence() {
+    // a sequence of 4 PUSH operations starting on a multiple of 8 is valid
+    let block = Span::new(vec![
+        OpCode::Noop, OpCode::Noop, OpCode::Noop, OpCode::Noop,
+        OpCode::Noop, OpCode::Noop, OpCode::Noop, OpCode::Noop,
+        OpCode::Push, OpCode::Push, OpCode::Push, OpCode::Push,
+        OpCode::Noop, OpCode::Noop, OpCode::Noop
+    ], build_push_hints(&[8, 9, 10, 11]));
+    assert_eq!(15, block.length());
+}
+
+#[test]
+#[should_panic]
+fn span_push_sequence_too_long() {
+    Span::new(vec![
+        OpCode::Push, OpCode::Push, OpCode::Push, OpCode::Push,
+        OpCode::Push, OpCode::Noop, OpCode::Noop, OpCode::Noop,
+        OpCode::Noop, OpCode::Noop, OpCode::Noop, OpCode::Noop,
+        OpCode::Noop, OpCode::Noop, OpCode::Noop
+    ], build_push_hints(&[0, 1, 2, 3, 4]));
+}
+
+#[test]
+#[should_panic]
+fn span_push_sequence_misaligned() {
+    Span::new(vec![
+        OpCode::Noop, OpCode::Push, OpCode::Push, OpCode::Noop,
+        OpCode::Noop, OpCode::Noop, OpCode::Noop, OpCode::Noop,
+        OpCode::Noop, OpCode::Noop, OpCode::Noop, OpCode::Noop,
+        OpCode::Noop, OpCode::Noop, OpCode::Noop
+    ], build_push_hints(&[1, 2]));
+}
+
+fn build_push_hints(steps: &[usize]) -> HashMap<usize, OpHint> {
+    let mut hints = HashMap::new();
+    for &step in steps.iter() {
+        hints.insert(step, OpHint::PushValue(step as u128));
+    }
+    return hints;
+}
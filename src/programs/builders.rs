@@ -0,0 +1,65 @@
+use super::{ Program, ProgramInputs, assembly };
+
+// PROGRAM BUILDERS
+// ================================================================================================
+
+/// Returns a program which computes the `n`-th term of Fibonacci sequence together with the
+/// inputs for it; the term is the single output of the program.
+pub fn fibonacci(n: usize) -> (Program, ProgramInputs) {
+    assert!(n >= 3, "sequence length must be at least 3, but was {}", n);
+
+    // every step moves the 2nd stack item to the top, duplicates the top 2 items, drops the
+    // top item, and adds the remaining 2 items together
+    let source = format!("begin repeat.{} swap dup.2 drop add end end", n - 1);
+
+    // initialize the stack with the first 2 terms; 1 will be at the top
+    return (compile(&source), ProgramInputs::from_public(&[1, 0]));
+}
+
+/// Returns a program which hashes a pair of values `n` times, each time hashing the result of
+/// the previous hash, together with the inputs for it; the last digest (2 elements) is the
+/// output of the program.
+pub fn hash_chain(n: usize) -> (Program, ProgramInputs) {
+    assert!(n >= 2, "hash chain length must be at least 2, but was {}", n);
+    let source = format!("begin repeat.{} hash.2 end end", n);
+    return (compile(&source), ProgramInputs::from_public(&[1, 0]));
+}
+
+/// Returns a program which proves that a secret `value` is smaller than 2^`bits` together with
+/// the inputs for it; the value is passed via secret input tape A, and so, it is not revealed
+/// by the proof. The program has no outputs, and its execution fails if the value does not fit
+/// into the specified number of bits.
+pub fn range_proof(value: u128, bits: u32) -> (Program, ProgramInputs) {
+    assert!(bits >= 4 && bits <= 128, "number of bits must be between 4 and 128, but was {}", bits);
+    let source = format!("begin read rc.{} assert end", bits);
+    return (compile(&source), ProgramInputs::new(&[], &[value], &[]));
+}
+
+/// Returns a program which computes the Collatz sequence starting with a secret `value`
+/// together with the inputs for it; the number of steps needed to reach 1 is the single output
+/// of the program. Unlike other programs, this program executes an unbounded loop, and so, the
+/// length of its execution depends on the value.
+pub fn collatz(value: u128) -> (Program, ProgramInputs) {
+    assert!(value > 0, "starting value of a Collatz sequence must be greater than 0");
+    let source = "
+    begin
+        pad read dup push.1 ne
+        while.true
+            swap push.1 add swap dup isodd.128
+            if.true
+                push.3 mul push.1 add
+            else
+                push.2 div
+            end
+            dup push.1 ne
+        end
+        swap
+    end";
+    return (compile(source), ProgramInputs::new(&[], &[value], &[]));
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+fn compile(source: &str) -> Program {
+    return assembly::compile(source).expect("generated program should be valid");
+}
@@ -0,0 +1,57 @@
+use serde::{ Serialize };
+use crate::{ StarkProof, crypto::hash, HASH_DIGEST_SIZE };
+use super::{ hash_public_inputs, hash_output_tape };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Succinct summary of the statement proven by a proof: which program was executed, with which
+/// public inputs, what it produced, and under which proof options. Computing the digest does not
+/// verify the proof, and so, is cheap; this allows indexers to catalog proofs by their digests
+/// and verify them later (or never). Stack outputs and public inputs are not a part of a proof,
+/// and must be provided together with it, in the same way as they are provided to `verify()`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ExecutionDigest {
+    pub program_hash        : [u8; 32],
+    pub input_commitment    : [u128; HASH_DIGEST_SIZE], // see hash_public_inputs(); binds the number of inputs
+    pub output_commitment   : [u128; HASH_DIGEST_SIZE], // computed from outputs in the same way as input commitment
+    pub tape_commitment     : [u128; HASH_DIGEST_SIZE], // see hash_output_tape()
+    pub options_digest      : [u8; 32],                 // BLAKE3 hash of the format header and proof options
+}
+
+// EXECUTION DIGEST IMPLEMENTATION
+// ================================================================================================
+impl ExecutionDigest {
+
+    /// Returns a digest of the statement that a program with the specified `program_hash`,
+    /// executed with the specified `public_inputs`, produced `outputs`, as claimed by the `proof`;
+    /// the proof is not verified.
+    pub fn new(program_hash: &[u8; 32], public_inputs: &[u128], outputs: &[u128], proof: &StarkProof) -> ExecutionDigest {
+        let options_bytes = bincode::serialize(&(proof.format(), proof.options())).unwrap();
+        let mut options_digest = [0u8; 32];
+        hash::blake3(&options_bytes, &mut options_digest);
+
+        return ExecutionDigest {
+            program_hash        : *program_hash,
+            input_commitment    : hash_public_inputs(public_inputs),
+            output_commitment   : hash_public_inputs(outputs),
+            tape_commitment     : hash_output_tape(proof.output_tape()),
+            options_digest      : options_digest,
+        };
+    }
+
+    /// Returns a BLAKE3 hash of all components of the digest; two proofs have the same id only
+    /// if they claim the same statement under the same proof options. Commitments bind the
+    /// number of inputs and outputs too, and so, statements which differ only by trailing ZEROs
+    /// have different ids.
+    pub fn id(&self) -> [u8; 32] {
+        let mut result = [0u8; 32];
+        hash::blake3(&bincode::serialize(self).unwrap(), &mut result);
+        return result;
+    }
+
+    /// Serializes the digest into JSON.
+    pub fn to_json(&self) -> String {
+        return serde_json::to_string(self).unwrap();
+    }
+}
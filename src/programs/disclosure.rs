@@ -0,0 +1,158 @@
+use serde::{ Serialize, Deserialize };
+use crate::{ math::field, HASH_DIGEST_SIZE };
+use super::hashing::hash_stack_items;
+
+// CONSTANTS
+// ================================================================================================
+
+/// Maximum number of outputs which can be committed to; the number must be a power of two.
+pub const MAX_COMMITTED_OUTPUTS: usize = 8;
+
+// instructions which replace the top value of the stack with its leaf, and the top 2 values of
+// the stack with the root of a subtree of 2 leaves; salts of the leaves are read from tape A
+const COMMIT_LEAF: &str = "read.a hash.2";
+const COMMIT_PAIR: &str = "pad swap read.a hash.2 swap.2 drop read.a hash.2 swap.2 hash.4";
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// A Merkle tree built over the outputs of a program which makes it possible to reveal some of
+/// the outputs without revealing the others. Every leaf is a hash of an output together with a
+/// secret salt, and every internal node is a hash of its children; hashes are computed in the
+/// same way as `hash.2` and `hash.4` instructions compute them. A program commits to its outputs
+/// by executing instructions returned by `commit_outputs()` at the end, after which the root of
+/// the tree is the only output of the program.
+#[derive(Clone, Debug)]
+pub struct OutputCommitment {
+    outputs : Vec<u128>,
+    salts   : Vec<u128>,
+    levels  : Vec<Vec<[u128; HASH_DIGEST_SIZE]>>,   // levels[0] are the leaves; the last level is the root
+}
+
+/// An output revealed against an output commitment: the value and the salt of the output
+/// together with the Merkle authentication path from its leaf to the root.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OutputOpening {
+    pub index   : usize,
+    pub value   : u128,
+    pub salt    : u128,
+    pub path    : Vec<[u128; HASH_DIGEST_SIZE]>,
+}
+
+// OUTPUT COMMITMENT IMPLEMENTATION
+// ================================================================================================
+impl OutputCommitment {
+
+    /// Builds a commitment to the specified `outputs` with the specified `salts`; the salts must
+    /// be the values supplied to instructions returned by `commit_outputs()` via tape A, and
+    /// should be random (e.g. generated via `math::field::rand_vector()`) for the outputs which
+    /// are not revealed to stay hidden.
+    pub fn new(outputs: &[u128], salts: &[u128]) -> OutputCommitment {
+        check_num_outputs(outputs.len());
+        assert!(outputs.len() == salts.len(),
+            "expected {} salts, but received {}", outputs.len(), salts.len());
+
+        let mut levels = vec![outputs.iter().zip(salts.iter())
+            .map(|(&value, &salt)| hash_leaf(value, salt))
+            .collect::<Vec<_>>()];
+        while levels.last().unwrap().len() > 1 {
+            let level = levels.last().unwrap().chunks(2)
+                .map(|pair| hash_node(&pair[0], &pair[1]))
+                .collect();
+            levels.push(level);
+        }
+
+        return OutputCommitment { outputs: outputs.to_vec(), salts: salts.to_vec(), levels };
+    }
+
+    /// Returns the root of the tree in the order in which it is arranged on the stack at the end
+    /// of execution; these are the outputs of a program which commits to its outputs.
+    pub fn root(&self) -> [u128; HASH_DIGEST_SIZE] {
+        return self.levels.last().unwrap()[0];
+    }
+
+    /// Returns the number of outputs committed to.
+    pub fn num_outputs(&self) -> usize {
+        return self.outputs.len();
+    }
+
+    /// Reveals the output at the specified `index`.
+    pub fn open(&self, index: usize) -> OutputOpening {
+        assert!(index < self.outputs.len(),
+            "output index {} is out of bounds for {} outputs", index, self.outputs.len());
+        let path = (0..(self.levels.len() - 1))
+            .map(|depth| self.levels[depth][(index >> depth) ^ 1])
+            .collect();
+        return OutputOpening { index, value: self.outputs[index], salt: self.salts[index], path };
+    }
+}
+
+// OUTPUT OPENING IMPLEMENTATION
+// ================================================================================================
+impl OutputOpening {
+
+    /// Checks that this opening reveals an output of a program with `num_outputs` outputs which
+    /// committed to its outputs with the specified `root`.
+    pub fn verify(&self, root: &[u128; HASH_DIGEST_SIZE], num_outputs: usize) -> Result<(), String> {
+        if !num_outputs.is_power_of_two() || num_outputs > MAX_COMMITTED_OUTPUTS {
+            return Err(format!("cannot commit to {} outputs; the number of outputs must be a power of two not greater than {}",
+                num_outputs, MAX_COMMITTED_OUTPUTS));
+        }
+        if self.index >= num_outputs {
+            return Err(format!("output index {} is out of bounds for {} outputs", self.index, num_outputs));
+        }
+        if self.path.len() != num_outputs.trailing_zeros() as usize {
+            return Err(format!("expected authentication path of length {}, but received {}",
+                num_outputs.trailing_zeros(), self.path.len()));
+        }
+        let all_valid = self.path.iter().flatten().chain([self.value, self.salt].iter())
+            .all(|&value| value < field::MODULUS);
+        if !all_valid {
+            return Err(String::from("opening contains values which are not valid field elements"));
+        }
+
+        let mut node = hash_leaf(self.value, self.salt);
+        for (depth, sibling) in self.path.iter().enumerate() {
+            node = if (self.index >> depth) & 1 == 0 { hash_node(&node, sibling) } else { hash_node(sibling, &node) };
+        }
+        if node != *root {
+            return Err(format!("opening of output {} does not match output commitment", self.index));
+        }
+        return Ok(());
+    }
+}
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
+/// Returns instructions which replace the top `num_outputs` values of the stack with the root
+/// of an output commitment to them (see `OutputCommitment`); `num_outputs` must be a power of
+/// two not greater than 8. The instructions read one salt per output from tape A, and so, the
+/// salts must be placed on tape A right after the values read by the program itself.
+pub fn commit_outputs(num_outputs: usize) -> String {
+    check_num_outputs(num_outputs);
+    let quad = format!("{} swap.2 {} swap.2 hash.4", COMMIT_PAIR, COMMIT_PAIR);
+    return match num_outputs {
+        1 => String::from(COMMIT_LEAF),
+        2 => String::from(COMMIT_PAIR),
+        4 => quad,
+        _ => format!("{} pad.2 swap.4 {} swap.2 drop.2 swap.2 hash.4", quad, quad),
+    };
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+fn check_num_outputs(num_outputs: usize) {
+    assert!(num_outputs.is_power_of_two() && num_outputs <= MAX_COMMITTED_OUTPUTS,
+        "cannot commit to {} outputs; the number of outputs must be a power of two not greater than {}",
+        num_outputs, MAX_COMMITTED_OUTPUTS);
+}
+
+fn hash_leaf(value: u128, salt: u128) -> [u128; HASH_DIGEST_SIZE] {
+    return hash_stack_items(&[salt, value]);
+}
+
+fn hash_node(left: &[u128; HASH_DIGEST_SIZE], right: &[u128; HASH_DIGEST_SIZE]) -> [u128; HASH_DIGEST_SIZE] {
+    return hash_stack_items(&[left[0], left[1], right[0], right[1]]);
+}
@@ -0,0 +1,25 @@
+use crate::{ HASH_DIGEST_SIZE };
+use super::{ hash_public_inputs };
+
+// EVENT LOG
+// ================================================================================================
+
+/// Computes a digest of an event log. Every event emitted by a program via `emit.<id>`
+/// instruction is recorded on the output tape as a pair of values: the id of the event followed
+/// by its payload. Thus, the digest of the log is equal to the digest of the output tape of a
+/// program which writes to the tape only via `emit.<id>` instructions (see `hash_output_tape()`).
+pub fn hash_event_log(events: &[(u32, u128)]) -> [u128; HASH_DIGEST_SIZE] {
+    let mut tape = Vec::with_capacity(events.len() * 2);
+    for &(id, payload) in events.iter() {
+        tape.push(id as u128);
+        tape.push(payload);
+    }
+    return hash_output_tape(&tape);
+}
+
+/// Computes a digest of values written to the output tape; values are absorbed two at a time in
+/// the same way as public inputs are absorbed by `hash_public_inputs()` (followed by the number
+/// of values), and so, every event record on the tape is absorbed in a single round.
+pub fn hash_output_tape(tape: &[u128]) -> [u128; HASH_DIGEST_SIZE] {
+    return hash_public_inputs(tape);
+}
@@ -1,4 +1,4 @@
-use crate::{ utils::sponge };
+use crate::{ utils::{ sponge, hasher }, HASH_DIGEST_SIZE };
 use super::{
     ProgramBlock, OpCode, BASE_CYCLE_LENGTH, SPONGE_WIDTH as STATE_WIDTH, HACC_NUM_ROUNDS,
 };
@@ -40,6 +40,8 @@ pub fn hash_seq(blocks: &Vec<ProgramBlock>, suffix: &[u8], suffix_offset: usize)
                     ProgramBlock::Group(block)  => block.get_hash(),
                     ProgramBlock::Switch(block) => block.get_hash(),
                     ProgramBlock::Loop(block)   => block.get_hash(),
+                    ProgramBlock::Call(block)   => block.get_hash(),
+                    ProgramBlock::DynCall(block)=> block.get_hash(),
                     ProgramBlock::Span(_)       => (0, 0),  // can't happen
                 };
 
@@ -72,4 +74,13 @@ pub fn hash_acc(parent_hash: u128, v0: u128, v1: u128) -> [u128; STATE_WIDTH] {
         hash_op(&mut state, NOOP_VALUE, 0, i);
     }
     return state;
-}
\ No newline at end of file
+}
+
+/// Computes a hash of the specified stack items (top first) in the same way as `hash.n`
+/// instruction does, and returns it in the order in which it is arranged on the stack.
+pub fn hash_stack_items(items: &[u128]) -> [u128; HASH_DIGEST_SIZE] {
+    let mut values = items.to_vec();
+    values.reverse();
+    let digest = hasher::digest(&values);
+    return [digest[1], digest[0]];
+}
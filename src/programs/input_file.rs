@@ -0,0 +1,166 @@
+use std::{ fs, path::Path };
+use rand::prelude::*;
+use bincode::Options;
+use crate::{ math::field, utils::zeroize, MAX_PUBLIC_INPUTS };
+use super::ProgramInputs;
+
+// CONSTANTS
+// ================================================================================================
+
+/// Bytes with which every input file starts.
+const MAGIC: &[u8; 8] = b"DSTFINPT";
+
+/// Version of the input file format.
+const INPUT_FILE_VERSION: u8 = 1;
+
+// ids of the ways in which the payload of an input file can be encrypted
+const PLAINTEXT: u8 = 0;
+const BLAKE3_ENCRYPTED: u8 = 1;
+
+const HEADER_SIZE: usize = MAGIC.len() + 2;
+const NONCE_SIZE: usize = 32;
+const TAG_SIZE: usize = 32;
+
+// contexts from which per-file encryption and authentication keys are derived
+const ENCRYPTION_CONTEXT: &str = "distaff input file v1 encryption key";
+const AUTHENTICATION_CONTEXT: &str = "distaff input file v1 authentication key";
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
+/// Serializes the `inputs` into the input file format. The file consists of a header (magic
+/// bytes, format version, and encryption id) followed by the payload: public inputs and both
+/// secret input tapes. If a `key` is provided, the payload is encrypted with a keystream
+/// produced by BLAKE3 in keyed mode, and the header, a random nonce, and the ciphertext are
+/// authenticated with a BLAKE3 MAC; encryption and MAC keys are derived from the `key` and the
+/// nonce, and so, every file is encrypted with different keys.
+pub fn to_bytes(inputs: &ProgramInputs, key: Option<&[u8; 32]>) -> Vec<u8> {
+    let secret = inputs.get_secret_inputs();
+    let mut payload = bincode::serialize(&(inputs.get_public_inputs(), &secret[0], &secret[1])).unwrap();
+
+    let mut bytes = MAGIC.to_vec();
+    bytes.push(INPUT_FILE_VERSION);
+    match key {
+        None => {
+            bytes.push(PLAINTEXT);
+            bytes.extend_from_slice(&payload);
+        },
+        Some(key) => {
+            bytes.push(BLAKE3_ENCRYPTED);
+            let mut nonce = [0u8; NONCE_SIZE];
+            thread_rng().fill(&mut nonce);
+            bytes.extend_from_slice(&nonce);
+            let (encryption_key, authentication_key) = derive_keys(key, &nonce);
+            apply_keystream(&encryption_key, &payload, &mut bytes);
+            let tag = blake3::keyed_hash(&authentication_key, &bytes);
+            bytes.extend_from_slice(tag.as_bytes());
+        }
+    }
+
+    zeroize(&mut payload);
+    return bytes;
+}
+
+/// Parses inputs from bytes produced by `to_bytes()`; encrypted files are decrypted with the
+/// provided `key`. Files which were encrypted with a different key or modified after they were
+/// encrypted, invalid payloads, and encrypted files read without a key (and vice versa) result
+/// in an error.
+pub fn from_bytes(bytes: &[u8], key: Option<&[u8; 32]>) -> Result<ProgramInputs, String> {
+    if bytes.len() < HEADER_SIZE || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(String::from("not an input file"));
+    }
+    if bytes[MAGIC.len()] != INPUT_FILE_VERSION {
+        return Err(format!("unsupported input file version {}; only version {} is supported",
+            bytes[MAGIC.len()], INPUT_FILE_VERSION));
+    }
+
+    let mut payload = match (bytes[MAGIC.len() + 1], key) {
+        (PLAINTEXT, None) => bytes[HEADER_SIZE..].to_vec(),
+        (BLAKE3_ENCRYPTED, Some(key)) => {
+            if bytes.len() < HEADER_SIZE + NONCE_SIZE + TAG_SIZE {
+                return Err(String::from("encrypted input file is truncated"));
+            }
+            let (body, tag) = bytes.split_at(bytes.len() - TAG_SIZE);
+            let mut nonce = [0u8; NONCE_SIZE];
+            nonce.copy_from_slice(&body[HEADER_SIZE..(HEADER_SIZE + NONCE_SIZE)]);
+            let (encryption_key, authentication_key) = derive_keys(key, &nonce);
+
+            // the hash comparison is done in constant time
+            let mut expected_tag = [0u8; TAG_SIZE];
+            expected_tag.copy_from_slice(tag);
+            if blake3::keyed_hash(&authentication_key, body) != expected_tag {
+                return Err(String::from("input file could not be decrypted: wrong key or corrupted file"));
+            }
+
+            let mut payload = Vec::with_capacity(body.len() - HEADER_SIZE - NONCE_SIZE);
+            apply_keystream(&encryption_key, &body[(HEADER_SIZE + NONCE_SIZE)..], &mut payload);
+            payload
+        },
+        (PLAINTEXT, Some(_)) => return Err(String::from("input file is not encrypted, but a key was provided")),
+        (BLAKE3_ENCRYPTED, None) => return Err(String::from("input file is encrypted, but no key was provided")),
+        (encryption, _) => return Err(format!("unsupported input file encryption id {}", encryption)),
+    };
+
+    let result = parse_payload(&payload);
+    zeroize(&mut payload);
+    return result;
+}
+
+/// Writes the `inputs` to the file at the specified `path`; see `to_bytes()` for details.
+pub fn write(inputs: &ProgramInputs, path: &Path, key: Option<&[u8; 32]>) -> Result<(), String> {
+    return fs::write(path, to_bytes(inputs, key))
+        .map_err(|err| format!("could not write input file {}: {}", path.display(), err));
+}
+
+/// Reads inputs from the file at the specified `path`; see `from_bytes()` for details.
+pub fn read(path: &Path, key: Option<&[u8; 32]>) -> Result<ProgramInputs, String> {
+    let bytes = fs::read(path)
+        .map_err(|err| format!("could not read input file {}: {}", path.display(), err))?;
+    return from_bytes(&bytes, key);
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+fn derive_keys(key: &[u8; 32], nonce: &[u8; NONCE_SIZE]) -> ([u8; 32], [u8; 32]) {
+    let key_material = [&key[..], &nonce[..]].concat();
+    let mut encryption_key = [0u8; 32];
+    blake3::derive_key(ENCRYPTION_CONTEXT, &key_material, &mut encryption_key);
+    let mut authentication_key = [0u8; 32];
+    blake3::derive_key(AUTHENTICATION_CONTEXT, &key_material, &mut authentication_key);
+    return (encryption_key, authentication_key);
+}
+
+/// XORs the `data` with a keystream derived from the `key`, and appends the result to `output`.
+fn apply_keystream(key: &[u8; 32], data: &[u8], output: &mut Vec<u8>) {
+    let mut keystream = vec![0u8; data.len()];
+    blake3::Hasher::new_keyed(key).finalize_xof().fill(&mut keystream);
+    output.extend(data.iter().zip(keystream.iter()).map(|(a, b)| a ^ b));
+    zeroize(&mut keystream);
+}
+
+fn parse_payload(payload: &[u8]) -> Result<ProgramInputs, String> {
+    let (public, mut secret_a, mut secret_b): (Vec<u128>, Vec<u128>, Vec<u128>) = bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .with_limit(payload.len() as u64)
+        .reject_trailing_bytes()
+        .deserialize(payload)
+        .map_err(|err| format!("invalid input file payload: {}", err))?;
+
+    let result = if public.len() > MAX_PUBLIC_INPUTS {
+        Err(format!("expected no more than {} public inputs, but received {}", MAX_PUBLIC_INPUTS, public.len()))
+    }
+    else if secret_a.len() < secret_b.len() {
+        Err(String::from("number of primary secret inputs cannot be smaller than the number of secondary secret inputs"))
+    }
+    else if !public.iter().chain(secret_a.iter()).chain(secret_b.iter()).all(|&value| value < field::MODULUS) {
+        Err(String::from("input file contains values which are not valid field elements"))
+    }
+    else {
+        Ok(ProgramInputs::new(&public, &secret_a, &secret_b))
+    };
+
+    zeroize(&mut secret_a);
+    zeroize(&mut secret_b);
+    return result;
+}
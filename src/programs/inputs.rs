@@ -1,4 +1,6 @@
-use crate::{ MAX_PUBLIC_INPUTS };
+use std::path::Path;
+use crate::{ math::field, utils::{ hasher, zeroize }, MAX_PUBLIC_INPUTS, HASH_DIGEST_SIZE, ENVIRONMENT_WIDTH };
+use super::input_file;
 
 #[derive(Clone, Debug)]
 pub struct ProgramInputs {
@@ -24,6 +26,26 @@ impl ProgramInputs {
         };
     }
 
+    /// Returns `ProgramInputs` in which the stack is initialized with a digest of the provided
+    /// `public` inputs (see `hash_public_inputs()`) rather than with the inputs themselves. The
+    /// inputs are placed at the front of input tape `A`, and the program is expected to read
+    /// them from the tape, hash them, and compare the result with the digest on the stack. This
+    /// makes it possible to supply an unlimited number of public inputs to a program.
+    pub fn with_public_digest(public: &[u128], secret_a: &[u128], secret_b: &[u128]) -> ProgramInputs {
+        let mut tape_a = public.to_vec();
+        tape_a.extend_from_slice(secret_a);
+        return ProgramInputs::new(&hash_public_inputs(public), &tape_a, secret_b);
+    }
+
+    /// Returns `ProgramInputs` in which the stack is initialized with the `environment` word
+    /// followed by the provided `public` inputs (see `environment_inputs()`). The environment
+    /// describes the context of a specific execution (e.g. a block number or a domain separator)
+    /// and, like public inputs, is bound to the proof; thus, the same program can be verified
+    /// against different environments without changing its hash.
+    pub fn with_environment(environment: &[u128; ENVIRONMENT_WIDTH], public: &[u128], secret_a: &[u128], secret_b: &[u128]) -> ProgramInputs {
+        return ProgramInputs::new(&environment_inputs(environment, public), secret_a, secret_b);
+    }
+
     /// Returns `ProgramInputs` with public and secret input tapes set to empty vectors.
     pub fn none() -> ProgramInputs {
         return ProgramInputs {
@@ -41,6 +63,33 @@ impl ProgramInputs {
         };
     }
 
+    /// Reads inputs from an input file at the specified `path`; if the file is encrypted, the
+    /// `key` with which it was encrypted must be provided. Invalid files, files encrypted with a
+    /// different key, and files modified after they were encrypted result in an error.
+    pub fn from_file(path: &Path, key: Option<&[u8; 32]>) -> Result<ProgramInputs, String> {
+        return input_file::read(path, key);
+    }
+
+    /// Parses inputs from the contents of an input file; see `from_file()` for details.
+    pub fn from_file_bytes(bytes: &[u8], key: Option<&[u8; 32]>) -> Result<ProgramInputs, String> {
+        return input_file::from_bytes(bytes, key);
+    }
+
+    /// Writes the inputs to an input file at the specified `path`. If a `key` is provided, the
+    /// inputs are encrypted and authenticated with it (see `to_file_bytes()`), and so, the
+    /// secret inputs are never written to the file in plaintext.
+    pub fn to_file(&self, path: &Path, key: Option<&[u8; 32]>) -> Result<(), String> {
+        return input_file::write(self, path, key);
+    }
+
+    /// Serializes the inputs into the input file format. The file starts with a header which
+    /// identifies the format and the way in which the inputs are encrypted; if a `key` is
+    /// provided, the inputs are encrypted with a BLAKE3 keystream and authenticated with a BLAKE3
+    /// MAC under keys derived from the `key` and a random nonce.
+    pub fn to_file_bytes(&self, key: Option<&[u8; 32]>) -> Vec<u8> {
+        return input_file::to_bytes(self, key);
+    }
+
     pub fn get_public_inputs(&self) -> &[u128] {
         return &self.public;
     }
@@ -48,4 +97,50 @@ impl ProgramInputs {
     pub fn get_secret_inputs(&self) -> &[Vec<u128>; 2] {
         return &self.secret;
     }
-}
\ No newline at end of file
+}
+
+/// Secret inputs are zeroized when the inputs are dropped so that they do not remain in memory
+/// of a long-running prover after the proof is generated.
+impl Drop for ProgramInputs {
+    fn drop(&mut self) {
+        zeroize(&mut self.secret[0]);
+        zeroize(&mut self.secret[1]);
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Returns a list of values with which the stack is initialized when a program is executed in
+/// the specified `environment`: the environment word is at the top of the stack (with the first
+/// element of the word in the first position), and the `public` inputs are right below it. At
+/// most `MAX_PUBLIC_INPUTS - ENVIRONMENT_WIDTH` public inputs can be provided together with an
+/// environment.
+pub fn environment_inputs(environment: &[u128; ENVIRONMENT_WIDTH], public: &[u128]) -> Vec<u128> {
+    assert!(public.len() <= MAX_PUBLIC_INPUTS - ENVIRONMENT_WIDTH,
+        "expected no more than {} public inputs together with an environment, but received {}",
+        MAX_PUBLIC_INPUTS - ENVIRONMENT_WIDTH,
+        public.len());
+    let mut inputs = environment.to_vec();
+    inputs.extend_from_slice(public);
+    return inputs;
+}
+
+/// Computes a digest of the provided public inputs. The inputs are absorbed two at a time (the
+/// last input is padded with a ZERO if the number of inputs is odd), and the number of inputs is
+/// absorbed last, such that the digest can be recomputed by a program which starts with `pad.2`,
+/// executes `read.a read.a hash.4` for every pair of inputs, and then executes
+/// `push.<n> push.0 hash.4` where n is the number of inputs; the digest is returned in the order
+/// in which it is arranged on the stack. Since the number of inputs is absorbed, inputs which
+/// differ only by trailing ZEROs have different digests.
+pub fn hash_public_inputs(public: &[u128]) -> [u128; HASH_DIGEST_SIZE] {
+    let mut result = [field::ZERO; HASH_DIGEST_SIZE];
+    let length = [public.len() as u128, field::ZERO];
+    for chunk in public.chunks(2).chain(std::iter::once(&length[..])) {
+        let second = if chunk.len() == 2 { chunk[1] } else { field::ZERO };
+        let mut digest = hasher::digest(&[result[1], result[0], chunk[0], second]);
+        digest.reverse();
+        result.copy_from_slice(&digest);
+    }
+    return result;
+}
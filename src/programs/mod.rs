@@ -1,17 +1,38 @@
+use crossbeam_utils::thread;
 use crate::{
     math::field,
     processor::{ OpCode, OpHint },
     utils::{ as_bytes },
     SPONGE_WIDTH, PROGRAM_DIGEST_SIZE, BASE_CYCLE_LENGTH, HACC_NUM_ROUNDS,
+    PUSH_OP_ALIGNMENT, MAX_PUSH_SEQUENCE,
 };
 
 pub mod assembly;
+pub mod builders;
 
 pub mod blocks;
-use blocks::{ ProgramBlock, Span, Group, Switch, Loop };
+use blocks::{ ProgramBlock, Span, Group, Switch, Loop, Call, DynCall, Instruction };
+pub use blocks::{ SourceLocation };
+
+mod abi;
+pub use abi::{ ProgramAbi };
 
 mod inputs;
-pub use inputs::{ ProgramInputs };
+pub use inputs::{ ProgramInputs, hash_public_inputs, environment_inputs };
+
+mod input_file;
+
+mod events;
+pub use events::{ hash_event_log, hash_output_tape };
+
+mod disclosure;
+pub use disclosure::{ OutputCommitment, OutputOpening, commit_outputs, MAX_COMMITTED_OUTPUTS };
+
+mod nullifier;
+pub use nullifier::{ derive_nullifier, hash_nullifier, NULLIFIER_TAG };
+
+mod digest;
+pub use digest::{ ExecutionDigest };
 
 mod hashing;
 use hashing::{ hash_op, hash_acc, hash_seq };
@@ -25,6 +46,7 @@ mod tests;
 pub struct Program {
     root    : Group,
     hash    : [u8; 32],
+    abi     : Option<ProgramAbi>,
 }
 
 // PROGRAM IMPLEMENTATION
@@ -44,13 +66,17 @@ impl Program {
         }
 
         // compute program hash
-        let (v0, v1) = root.get_hash();
-        let hash = hash_acc(field::ZERO, v0, v1);
-        let mut hash_bytes = [0u8; 32];
-        hash_bytes.copy_from_slice(as_bytes(&hash[..PROGRAM_DIGEST_SIZE]));
+        let hash = hash_group(&root);
+        return Program { root, hash, abi: None };
+    }
 
-        return Program { root, hash: hash_bytes };
+    /// Returns the program with the specified ABI attached to it; `execute()` checks inputs of
+    /// a program against its ABI. The ABI does not affect the hash of the program.
+    pub fn with_abi(mut self, abi: ProgramAbi) -> Program {
+        self.abi = Some(abi);
+        return self;
     }
+
     /// Returns the root block of the program.
     pub fn root(&self) -> &Group {
         return &self.root;
@@ -60,6 +86,31 @@ impl Program {
     pub fn hash(&self) -> &[u8; 32] {
         return &self.hash;
     }
+
+    /// Returns true if the hash of the program is equal to the specified `hash`. This can be used
+    /// to make sure that a program is the one a proof is expected to be about before the proof is
+    /// verified. The hash is computed by the VM itself (the same way as the decoder computes it
+    /// during execution), and so, it does not depend on proof options: the same program has the
+    /// same hash regardless of the hash function or other parameters used to generate proofs.
+    /// Neither source locations nor the ABI of the program affect the hash.
+    pub fn matches_hash(&self, hash: &[u8; 32]) -> bool {
+        return self.hash == *hash;
+    }
+
+    /// Returns the ABI attached to the program, if any.
+    pub fn abi(&self) -> Option<&ProgramAbi> {
+        return self.abi.as_ref();
+    }
+
+    /// Returns an iterator over (step, instruction) pairs of all spans of the program, in the
+    /// order in which the spans appear in the program: the true branch of a switch precedes the
+    /// false branch, and the body of a loop precedes the block which skips the loop. Steps are
+    /// counted from the start of every span, and PUSH instructions carry their values.
+    pub fn iter_instructions(&self) -> impl Iterator<Item = (usize, Instruction)> + '_ {
+        let mut spans = Vec::new();
+        collect_spans(self.root.body(), &mut spans);
+        return spans.into_iter().flat_map(|span| span.instructions());
+    }
 }
 
 impl std::fmt::Debug for Program {
@@ -72,4 +123,62 @@ impl std::fmt::Debug for Program {
 
         return Ok(());
     }
-}
\ No newline at end of file
+}
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
+/// Computes hashes of programs with the specified root blocks; the hashes are returned in the
+/// same order as the blocks, and every hash is the same as the hash of `Program::new(root)`.
+/// Hashes of different programs are computed in parallel, and so, this is faster than building
+/// the programs one by one when many programs need to be hashed (e.g. to validate a registry of
+/// program hashes).
+pub fn hash_many(roots: &[Group]) -> Vec<[u8; 32]> {
+    let num_threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let batch_size = std::cmp::max(1, (roots.len() + num_threads - 1) / num_threads);
+
+    let mut result = vec![[0u8; 32]; roots.len()];
+    thread::scope(|s| {
+        for (hashes, roots) in result.chunks_mut(batch_size).zip(roots.chunks(batch_size)) {
+            s.spawn(move |_| {
+                for (hash, root) in hashes.iter_mut().zip(roots) {
+                    *hash = hash_group(root);
+                }
+            });
+        }
+    }).unwrap();
+
+    return result;
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Appends all spans of the specified `blocks` to `spans` in the order of their appearance.
+fn collect_spans<'a>(blocks: &'a [ProgramBlock], spans: &mut Vec<&'a Span>) {
+    for block in blocks.iter() {
+        match block {
+            ProgramBlock::Span(block) => spans.push(block),
+            ProgramBlock::Group(block) => collect_spans(block.body(), spans),
+            ProgramBlock::Switch(block) => {
+                collect_spans(block.true_branch(), spans);
+                collect_spans(block.false_branch(), spans);
+            },
+            ProgramBlock::Loop(block) => {
+                collect_spans(block.body(), spans);
+                collect_spans(block.skip(), spans);
+            },
+            ProgramBlock::Call(block) => collect_spans(block.body(), spans),
+            ProgramBlock::DynCall(_) => (),
+        }
+    }
+}
+
+/// Computes a hash of a program with the specified root block.
+fn hash_group(root: &Group) -> [u8; 32] {
+    let (v0, v1) = root.get_hash();
+    let hash = hash_acc(field::ZERO, v0, v1);
+    let mut hash_bytes = [0u8; 32];
+    hash_bytes.copy_from_slice(as_bytes(&hash[..PROGRAM_DIGEST_SIZE]));
+    return hash_bytes;
+}
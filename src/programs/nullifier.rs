@@ -0,0 +1,31 @@
+use crate::{ HASH_DIGEST_SIZE };
+use super::hashing::hash_stack_items;
+
+// CONSTANTS
+// ================================================================================================
+
+/// Domain separation tag hashed together with the secret and the domain of every nullifier;
+/// the value is the ASCII encoding of "nullifier".
+pub const NULLIFIER_TAG: u128 = 0x6e756c6c6966696572;
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
+/// Returns instructions which replace the top 2 values of the stack, a secret followed by a
+/// domain, with a nullifier derived from them (see `hash_nullifier()`). A nullifier is unique
+/// for every secret within a domain, but does not reveal the secret, and so, recording the
+/// nullifiers of verified proofs makes it possible to reject proofs which use the same secret
+/// twice (e.g. spend the same note). For example, a program which receives the domain as a
+/// public input and the secret via tape A would start with `read.a` followed by these
+/// instructions; if the nullifier is left at the top of the stack, it becomes the first 2
+/// outputs of the program, and can be checked via `verify_with_nullifier()`.
+pub fn derive_nullifier() -> String {
+    return format!("push.{} swap hash.3", NULLIFIER_TAG);
+}
+
+/// Computes the nullifier of the specified `secret` within the specified `domain` as a hash of
+/// the secret, `NULLIFIER_TAG`, and the domain; the nullifier is returned in the order in which
+/// it is arranged on the stack by instructions returned from `derive_nullifier()`.
+pub fn hash_nullifier(secret: u128, domain: u128) -> [u128; HASH_DIGEST_SIZE] {
+    return hash_stack_items(&[secret, NULLIFIER_TAG, domain]);
+}
@@ -1,6 +1,6 @@
 use crate::utils::{ as_bytes };
-use crate::processor::{ OpCode };
-use super::{ Program, ProgramBlock, Span, Group, Switch, Loop };
+use crate::processor::{ OpCode, OpHint };
+use super::{ Program, ProgramBlock, Span, Group, Switch, Loop, Instruction, assembly, PUSH_OP_ALIGNMENT };
 
 mod utils;
 use utils::{ traverse, close_block };
@@ -145,6 +145,55 @@ fn simple_loop() {
     assert_eq!(111, step);
 }
 
+#[test]
+fn iter_instructions() {
+    let program = assembly::compile("begin push.5 if.true push.7 push.8 else add end end").unwrap();
+
+    // PUSH instructions carry their values, and are placed on aligned steps of their spans
+    let pushes: Vec<(usize, u128)> = program.iter_instructions().filter_map(|(step, instruction)| match instruction {
+        Instruction::Push(value) => Some((step, value)),
+        _ => None,
+    }).collect();
+    assert_eq!(vec![5, 7, 8], pushes.iter().map(|&(_, value)| value).collect::<Vec<_>>());
+    assert!(pushes.iter().all(|&(step, _)| step % PUSH_OP_ALIGNMENT == 0 || pushes.iter().any(|&(other, _)| other + 1 == step)));
+
+    // all other instructions carry their op codes; the program starts with BEGIN
+    let instructions: Vec<(usize, Instruction)> = program.iter_instructions().collect();
+    assert_eq!((0, Instruction::Op(OpCode::Begin, OpHint::None)), instructions[0]);
+    assert!(instructions.iter().any(|&(_, instruction)| instruction == Instruction::Op(OpCode::Add, OpHint::None)));
+}
+
+#[test]
+fn matches_hash() {
+    let program = assembly::compile("begin push.3 push.5 add end").unwrap();
+    assert!(program.matches_hash(program.hash()));
+
+    // formatting of the source code does not affect the hash
+    let formatted = assembly::compile("begin\n    push.3\n    push.5\n    add\nend\n").unwrap();
+    assert!(formatted.matches_hash(program.hash()));
+
+    // but any change to the instructions does
+    let changed = assembly::compile("begin push.3 push.5 mul end").unwrap();
+    assert!(!changed.matches_hash(program.hash()));
+}
+
+#[test]
+fn hash_many() {
+    let programs: Vec<Program> = (0..37)
+        .map(|i| assembly::compile(&format!("begin push.{} if.true push.{} else repeat.{} dup end end end", i, i + 1, i % 5 + 2)).unwrap())
+        .collect();
+    let roots: Vec<Group> = programs.iter().map(|program| program.root().clone()).collect();
+
+    // hashes are the same as hashes of individual programs, and are in the same order
+    let hashes = super::hash_many(&roots);
+    assert_eq!(programs.len(), hashes.len());
+    for (program, hash) in programs.iter().zip(hashes.iter()) {
+        assert!(program.matches_hash(hash));
+    }
+
+    assert_eq!(Vec::<[u8; 32]>::new(), super::hash_many(&[]));
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
 fn build_first_block(op_code: OpCode, length: usize) -> ProgramBlock {
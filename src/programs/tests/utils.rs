@@ -26,6 +26,15 @@ pub fn traverse(blocks: &[ProgramBlock], stack: &mut Vec<u128>, hash: &mut [u128
                 hash.copy_from_slice(&state);
                 step
             },
+            ProgramBlock::Call(block) => {
+                step += 1; // CALL
+                let mut state = [0, 0, 0, 0];
+                step = traverse(block.body(), stack, &mut state, step);
+                step = close_block(&mut state, hash[0], 0, true, step);
+                hash.copy_from_slice(&state);
+                step
+            },
+            ProgramBlock::DynCall(_) => panic!("dynamic calls cannot be traversed"),
             ProgramBlock::Switch(block) => {
                 step += 1; // BEGIN
                 let mut state = [0, 0, 0, 0];
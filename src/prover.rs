@@ -0,0 +1,196 @@
+use std::time::Instant;
+use crate::{
+    Program, ProgramInputs, ProofOptions, StarkProof, HashTranscript, ExecutionError,
+    processor, stark::ProverSetup, prove_execution, MAX_OUTPUTS, DEFAULT_MAX_CYCLES, AUX_WIDTH,
+    math::{ field, polynom }, crypto::HashFunction,
+};
+
+// CONSTANTS
+// ================================================================================================
+
+// proof options considered by auto-tuning; FRI always folds layers by a factor of 4, and so,
+// the remainder length is the only FRI parameter which is tuned
+const TUNED_EXTENSION_FACTORS   : [usize; 5] = [16, 32, 64, 128, 256];
+const TUNED_GRINDING_FACTORS    : [u32; 5] = [0, 8, 16, 20, 24];
+const TUNED_REMAINDER_LENGTHS   : [usize; 7] = [16, 32, 64, 128, 256, 512, 1024];
+const MAX_NUM_QUERIES           : usize = 128;
+
+// sizes of micro-benchmarks used to estimate proving time
+const BENCH_FFT_SIZE            : usize = 1 << 12;
+const BENCH_NUM_HASHES          : usize = 1 << 10;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Generates proofs of execution of a single program for different inputs. Values which depend
+/// only on the shape of the execution trace (twiddles, constraint evaluators, and buffers used
+/// by the prover) are computed when the first proof is generated, and are re-used for all
+/// subsequent proofs for as long as the shape of the execution trace stays the same.
+pub struct Prover {
+    program     : Program,
+    options     : ProofOptions,
+    setup       : Option<ProverSetup>,
+}
+
+/// Goal of proof options tuning; see `Prover::auto_tune()`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TuningTarget {
+    /// minimize the time needed to generate a proof
+    Speed,
+    /// minimize the size of the proof
+    Size,
+}
+
+// PROVER IMPLEMENTATION
+// ================================================================================================
+impl Prover {
+
+    /// Returns a prover for the specified `program` which generates proofs using the specified
+    /// proof `options`.
+    pub fn new(program: &Program, options: &ProofOptions) -> Prover {
+        return Prover {
+            program : program.clone(),
+            options : options.clone(),
+            setup   : None,
+        };
+    }
+
+    /// Returns a prover for the specified `program` with proof options tuned for proofs of its
+    /// execution against `inputs` which provide at least `target_security` bits of conjectured
+    /// security (see `ProofOptions::conjectured_security()`). The program is executed to find
+    /// out the length and the width of its execution trace; then every combination of extension
+    /// factor, grinding factor, and FRI remainder length is considered with the smallest number
+    /// of queries which reaches the target. For `TuningTarget::Size`, the options with the
+    /// smallest estimated proof size are picked (see `ProofOptions::estimate()`); for
+    /// `TuningTarget::Speed`, proving time is estimated from the amount of FFT and hashing work
+    /// together with the costs of these measured by micro-benchmarks. The tuned options are
+    /// available via `options()`, and are recorded in every proof generated by the prover.
+    ///
+    /// The FRI folding factor is not tuned: FRI always folds layers by a factor of 4 (layers are
+    /// committed to as rows of 4 values), and so, it is not a part of proof options.
+    ///
+    /// Returns an error if the program cannot be executed, or if no options reach the target.
+    pub fn auto_tune(program: &Program, inputs: &ProgramInputs, target_security: u32, target: TuningTarget) -> Result<Prover, String> {
+        let (trace, _, _, _) = processor::execute(program, inputs, DEFAULT_MAX_CYCLES).map_err(|err| err.to_string())?;
+        let trace_length = trace[0].len();
+        let trace_width = trace.len();
+        let hash_fn = ProofOptions::default().hash_fn();
+        let (fft_cost, hash_cost) = match target {
+            TuningTarget::Speed => measure_costs(hash_fn),
+            TuningTarget::Size  => (0.0, 0.0),
+        };
+
+        let mut best: Option<(f64, ProofOptions)> = None;
+        for &extension_factor in TUNED_EXTENSION_FACTORS.iter() {
+            for &grinding_factor in TUNED_GRINDING_FACTORS.iter() {
+                let num_queries = (1..=MAX_NUM_QUERIES).find(|&num_queries| {
+                    let options = ProofOptions::new(extension_factor, num_queries, grinding_factor, hash_fn);
+                    options.conjectured_security(trace_length) >= target_security
+                });
+                let num_queries = match num_queries {
+                    Some(num_queries) => num_queries,
+                    None => continue,
+                };
+
+                for &remainder_length in TUNED_REMAINDER_LENGTHS.iter() {
+                    let options = ProofOptions::new(extension_factor, num_queries, grinding_factor, hash_fn)
+                        .with_remainder_length(remainder_length);
+                    let cost = match target {
+                        TuningTarget::Size  => options.estimate(trace_length, trace_width).proof_size as f64,
+                        TuningTarget::Speed => estimate_proving_time(&options, trace_length, trace_width, fft_cost, hash_cost),
+                    };
+                    if best.as_ref().map_or(true, |(best_cost, _)| cost < *best_cost) {
+                        best = Some((cost, options));
+                    }
+                }
+            }
+        }
+
+        return match best {
+            Some((_, options)) => Ok(Prover::new(program, &options)),
+            None => Err(format!("no proof options provide {} bits of security for a trace of {} steps",
+                target_security, trace_length)),
+        };
+    }
+
+    /// Returns the program for which the proofs are generated.
+    pub fn program(&self) -> &Program {
+        return &self.program;
+    }
+
+    /// Returns the options used to generate the proofs.
+    pub fn options(&self) -> &ProofOptions {
+        return &self.options;
+    }
+
+    /// Executes the program with the specified `inputs` and returns the result together with a
+    /// STARK-based proof of execution; the proof is the same as the one generated by `execute()`
+    /// function for the same program, inputs, and options.
+    ///
+    /// Panics if the program does not complete within `DEFAULT_MAX_CYCLES` cycles.
+    pub fn prove(&mut self, inputs: &ProgramInputs, num_outputs: usize) -> (Vec<u128>, StarkProof) {
+        return match self.prove_with_limit(inputs, num_outputs, DEFAULT_MAX_CYCLES) {
+            Ok(result) => result,
+            Err(err) => panic!("{}", err)
+        };
+    }
+
+    /// Executes the program and generates a proof of execution in the same way as `prove()`, but
+    /// returns `ExecutionError::ResourceExceeded` error if the program does not complete within
+    /// `max_cycles` cycles.
+    pub fn prove_with_limit(&mut self, inputs: &ProgramInputs, num_outputs: usize, max_cycles: usize)
+        -> Result<(Vec<u128>, StarkProof), ExecutionError>
+    {
+        assert!(num_outputs <= MAX_OUTPUTS,
+            "cannot produce more than {} outputs, but requested {}", MAX_OUTPUTS, num_outputs);
+
+        let now = Instant::now();
+        let (trace, ctx_depth, loop_depth, call_depth) = processor::execute(&self.program, inputs, max_cycles)?;
+        let mut transcript = HashTranscript::new(self.options.hash_fn());
+        return Ok(prove_execution(&self.program, inputs, num_outputs, &self.options, &mut transcript,
+            trace, ctx_depth, loop_depth, call_depth, &mut self.setup, now));
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Measures the time (in nanoseconds) of a single FFT butterfly, and of a single invocation of
+/// the `hash_fn` on 64 bytes.
+fn measure_costs(hash_fn: HashFunction) -> (f64, f64) {
+    let mut values = field::prng_vector([0u8; 32], BENCH_FFT_SIZE);
+    let now = Instant::now();
+    polynom::eval_fft(&mut values, true);
+    let num_butterflies = BENCH_FFT_SIZE * BENCH_FFT_SIZE.trailing_zeros() as usize / 2;
+    let fft_cost = now.elapsed().as_nanos() as f64 / num_butterflies as f64;
+
+    let mut buf = [0u8; 64];
+    let now = Instant::now();
+    for i in 0..BENCH_NUM_HASHES {
+        buf[0] = i as u8;
+        let mut result = [0u8; 32];
+        hash_fn(&buf, &mut result);
+        buf[32..].copy_from_slice(&result);
+    }
+    let hash_cost = now.elapsed().as_nanos() as f64 / BENCH_NUM_HASHES as f64;
+
+    return (fft_cost, hash_cost);
+}
+
+/// Estimates the time (in nanoseconds) needed to generate a proof with the specified `options`
+/// for an execution trace of `trace_length` steps and `trace_width` registers: all trace
+/// registers, the constraint polynomial and the composition polynomial are extended over the
+/// evaluation domain, the extended trace, constraint evaluations and FRI layers are committed
+/// to, and the proof-of-work nonce is searched for.
+fn estimate_proving_time(options: &ProofOptions, trace_length: usize, trace_width: usize, fft_cost: f64, hash_cost: f64) -> f64 {
+    let domain_size = (trace_length * options.extension_factor()) as f64;
+    let num_butterflies = domain_size * domain_size.log2() / 2.0;
+    let fft_work = (trace_width + AUX_WIDTH + 2) as f64 * num_butterflies;
+
+    // every Merkle tree has about as many internal nodes as leaves; FRI layers add up to a third
+    // of the evaluation domain
+    let num_hashes = 3.0 * 2.0 * domain_size + domain_size / 3.0
+        + f64::powi(2.0, options.grinding_factor() as i32);
+
+    return fft_work * fft_cost + num_hashes * hash_cost;
+}
@@ -0,0 +1,287 @@
+use std::{ fs, io::{ self, BufRead, Write } };
+use distaff::{ self, assembly, math::field, ProgramInputs, AuditReport, CheckKind, DEFAULT_MAX_CYCLES, MAX_PUBLIC_INPUTS };
+
+// CONSTANTS
+// ================================================================================================
+
+const HELP: &str = "\
+Type assembly instructions to execute them; the stack is printed after every line. Blocks (e.g.
+`if.true ... end`) can span several lines and are executed once they are closed. Commands:
+    :inputs <values>        set public inputs, i.e. the initial state of the stack (top first)
+    :advice <a|b> <values>  append values to secret input tape A or B
+    :undo                   remove the last line (or block) of instructions
+    :show                   print the program entered so far
+    :save <path>            save the program entered so far as an assembly file
+    :help                   print this message
+    :quit                   exit the REPL";
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// State of an interactive session: the program is made up of all lines of instructions entered
+/// so far, and it is re-executed against the session inputs every time a new line is entered.
+/// Lines which cannot be executed are rejected, and so, the program always executes cleanly.
+pub struct Session {
+    lines       : Vec<String>,  // lines of a block which spans several lines are kept together
+    pending     : Vec<String>,  // lines of a block which has not been closed yet
+    public      : Vec<u128>,
+    tape_a      : Vec<u128>,
+    tape_b      : Vec<u128>,
+    stack       : Vec<u128>,
+}
+
+/// Result of evaluating a single line of input.
+pub enum Response {
+    Output(String),
+    Quit,
+}
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
+/// Runs the REPL against standard input and output until the input is closed or `:quit` command
+/// is entered.
+pub fn run() {
+    println!("Distaff REPL; type :help for the list of commands");
+    let mut session = Session::new();
+    let stdin = io::stdin();
+    loop {
+        print!("{}", if session.is_pending() { ".. " } else { ">> " });
+        io::stdout().flush().unwrap();
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+        match session.eval(&line) {
+            Response::Output(output) => if output.len() > 0 { println!("{}", output) },
+            Response::Quit => break,
+        }
+    }
+}
+
+// SESSION IMPLEMENTATION
+// ================================================================================================
+impl Session {
+
+    pub fn new() -> Session {
+        return Session {
+            lines   : Vec::new(),
+            pending : Vec::new(),
+            public  : Vec::new(),
+            tape_a  : Vec::new(),
+            tape_b  : Vec::new(),
+            stack   : Vec::new(),
+        };
+    }
+
+    /// Returns true if the session is waiting for the end of a block.
+    pub fn is_pending(&self) -> bool {
+        return self.pending.len() > 0;
+    }
+
+    /// Returns the source of the program entered so far.
+    pub fn source(&self) -> String {
+        return build_source(&self.lines);
+    }
+
+    /// Evaluates a single line of input, which is either a command or a line of instructions.
+    pub fn eval(&mut self, line: &str) -> Response {
+        let line = line.trim();
+        if line.starts_with(":") {
+            return self.eval_command(line);
+        }
+        else if line.len() == 0 {
+            return Response::Output(String::new());
+        }
+
+        // wait until all blocks opened by the entered lines are closed
+        self.pending.push(String::from(line));
+        let depth = block_depth(&self.pending);
+        if depth > 0 {
+            return Response::Output(String::new());
+        }
+        let pending = self.pending.split_off(0);
+        if depth < 0 {
+            return Response::Output(String::from("error: `end` does not close any block"));
+        }
+
+        let mut lines = self.lines.clone();
+        lines.push(pending.join("\n"));
+        return Response::Output(match self.update(lines, self.public.clone(), self.tape_a.clone(), self.tape_b.clone()) {
+            Ok(()) => self.print_stack(),
+            Err(err) => format!("error: {}", err),
+        });
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+
+    fn eval_command(&mut self, line: &str) -> Response {
+        let args: Vec<&str> = line.split_whitespace().collect();
+        let result = match args[0] {
+            ":inputs" => parse_values(&args[1..]).and_then(|public| {
+                self.update(self.lines.clone(), public, self.tape_a.clone(), self.tape_b.clone())
+            }).map(|_| self.print_stack()),
+            ":advice" => match args.get(1) {
+                Some(&"a") | Some(&"b") => parse_values(&args[2..]).and_then(|values| {
+                    let (mut tape_a, mut tape_b) = (self.tape_a.clone(), self.tape_b.clone());
+                    if args[1] == "a" { tape_a.extend(values) } else { tape_b.extend(values) };
+                    self.update(self.lines.clone(), self.public.clone(), tape_a, tape_b)
+                }).map(|_| format!("tape A: {:?}, tape B: {:?}", self.tape_a, self.tape_b)),
+                _ => Err(String::from("expected tape name (a or b) followed by values")),
+            },
+            ":undo" => if self.is_pending() {
+                self.pending.clear();
+                Ok(String::from("discarded the unfinished block"))
+            }
+            else if self.lines.len() > 0 {
+                let mut lines = self.lines.clone();
+                lines.pop();
+                self.update(lines, self.public.clone(), self.tape_a.clone(), self.tape_b.clone())
+                    .map(|_| self.print_stack())
+            }
+            else {
+                Err(String::from("there is nothing to undo"))
+            },
+            ":show" => Ok(self.source()),
+            ":save" => match args.get(1) {
+                Some(path) => fs::write(path, self.source())
+                    .map(|_| format!("saved {} lines to {}", self.lines.len(), path))
+                    .map_err(|err| format!("could not save the program to {}: {}", path, err)),
+                None => Err(String::from("expected a path to save the program to")),
+            },
+            ":help" => Ok(String::from(HELP)),
+            ":quit" => return Response::Quit,
+            _ => Err(format!("unknown command {}; type :help for the list of commands", args[0])),
+        };
+
+        return Response::Output(match result {
+            Ok(output) => output,
+            Err(err) => format!("error: {}", err),
+        });
+    }
+
+    /// Executes the program made up of the specified `lines` against the specified inputs, and
+    /// updates the session if the program executes cleanly; otherwise, the session is left
+    /// unchanged and the first issue of the program is returned as an error.
+    fn update(&mut self, lines: Vec<String>, public: Vec<u128>, tape_a: Vec<u128>, tape_b: Vec<u128>) -> Result<(), String> {
+        if public.len() > MAX_PUBLIC_INPUTS {
+            return Err(format!("expected no more than {} public inputs, but received {}", MAX_PUBLIC_INPUTS, public.len()));
+        }
+        else if tape_b.len() > tape_a.len() {
+            return Err(String::from("tape B cannot hold more values than tape A; add values to tape A first"));
+        }
+
+        let stack = if lines.len() == 0 {
+            public.clone()
+        }
+        else {
+            let program = assembly::compile(&build_source(&lines)).map_err(|err| err.to_string())?;
+            let inputs = ProgramInputs::new(&public, &tape_a, &tape_b);
+            let report = distaff::audit(&program, &inputs, DEFAULT_MAX_CYCLES);
+            check_report(&report)?;
+            report.stack
+        };
+
+        self.lines = lines;
+        self.public = public;
+        self.tape_a = tape_a;
+        self.tape_b = tape_b;
+        self.stack = stack;
+        return Ok(());
+    }
+
+    fn print_stack(&self) -> String {
+        return format!("stack: {:?}", self.stack);
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Builds the source of a program made up of the specified lines of instructions.
+fn build_source(lines: &[String]) -> String {
+    let mut source = String::from("begin\n");
+    for line in lines.iter().flat_map(|line| line.lines()) {
+        source.push_str(&format!("    {}\n", line));
+    }
+    source.push_str("end\n");
+    return source;
+}
+
+/// Returns the number of blocks opened by the specified lines which have not been closed.
+fn block_depth(lines: &[String]) -> isize {
+    let mut depth = 0;
+    for line in lines.iter() {
+        for token in line.split_whitespace() {
+            match token.split(".").next().unwrap() {
+                "block" | "if" | "while" | "repeat" => depth += 1,
+                "end" => depth -= 1,
+                _ => (),
+            }
+        }
+    }
+    return depth;
+}
+
+fn parse_values(args: &[&str]) -> Result<Vec<u128>, String> {
+    return args.iter()
+        .map(|arg| match arg.parse::<u128>() {
+            Ok(value) if value < field::MODULUS => Ok(value),
+            _ => Err(format!("{} is not a valid field element", arg)),
+        })
+        .collect();
+}
+
+/// Converts the first issue recorded in the `report` into an error; reads from empty input tapes
+/// are reported together with a hint on how to supply the missing values.
+fn check_report(report: &AuditReport) -> Result<(), String> {
+    if let Some(failure) = report.failures.first() {
+        return match failure.kind {
+            CheckKind::Advice => Err(format!("{}; supply values with :advice command", failure.message)),
+            _ => Err(failure.message.clone()),
+        };
+    }
+    return match &report.error {
+        Some(err) => Err(err.clone()),
+        None => Ok(()),
+    };
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use super::{ Session, Response };
+
+    fn eval(session: &mut Session, line: &str) -> String {
+        return match session.eval(line) {
+            Response::Output(output) => output,
+            Response::Quit => String::from("quit"),
+        };
+    }
+
+    #[test]
+    fn session() {
+        let mut session = Session::new();
+        assert_eq!("stack: [3, 2]", eval(&mut session, ":inputs 3 2"));
+        assert_eq!("stack: [5]", eval(&mut session, "add"));
+
+        // lines with issues are rejected
+        assert!(eval(&mut session, "read").ends_with("supply values with :advice command"));
+        assert_eq!("tape A: [7], tape B: []", eval(&mut session, ":advice a 7"));
+        assert_eq!("stack: [7, 5]", eval(&mut session, "read"));
+        assert!(eval(&mut session, "drop drop drop").starts_with("error: stack underflow"));
+
+        // blocks are executed once they are closed
+        assert_eq!("", eval(&mut session, "push.1 if.true"));
+        assert!(session.is_pending());
+        assert_eq!("stack: [12]", eval(&mut session, "add end"));
+        assert_eq!("begin\n    add\n    read\n    push.1 if.true\n    add end\nend\n", session.source());
+
+        assert_eq!("stack: [7, 5]", eval(&mut session, ":undo"));
+        assert_eq!("quit", eval(&mut session, ":quit"));
+    }
+}
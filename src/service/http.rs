@@ -0,0 +1,347 @@
+use std::io::{ self, BufRead, BufReader, Read, Write };
+use std::net::{ TcpListener, TcpStream };
+use std::sync::{ Arc, atomic::{ AtomicUsize, Ordering } };
+use std::thread;
+use std::time::{ Duration, Instant };
+use serde::{ Deserialize };
+use serde_json::{ json, Value };
+use crate::{ math::field, verify_with_policy, ProgramInputs, StarkProof, MAX_PUBLIC_INPUTS };
+use super::{ ProvingService, ProveRequest, JobStatus };
+
+// CONSTANTS
+// ================================================================================================
+
+/// Requests with larger bodies are rejected without being read.
+const MAX_BODY_SIZE: usize = 16 << 20;
+
+/// Requests with a larger request line and headers (taken together), or with more headers, are
+/// rejected without reading the rest of the request.
+const MAX_HEADER_SIZE: usize = 16 << 10;
+const MAX_HEADERS: usize = 64;
+
+/// A request must be received in full within this time; otherwise, the connection is closed.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Connections received while this many connections are being handled are rejected.
+const MAX_CONNECTIONS: usize = 64;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Body of a POST /prove request; field elements are encoded as decimal strings so that they
+/// survive JSON parsers which read numbers as 64-bit floats.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonProveRequest {
+    program             : String,
+    #[serde(default)]
+    public_inputs       : Vec<String>,
+    #[serde(default)]
+    secret_a            : Vec<String>,
+    #[serde(default)]
+    secret_b            : Vec<String>,
+    num_outputs         : usize,
+    #[serde(default)]
+    max_cycles          : Option<usize>,
+}
+
+/// Body of a POST /verify request; the program hash and the proof are encoded as hex strings.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonVerifyRequest {
+    program_hash        : String,
+    #[serde(default)]
+    public_inputs       : Vec<String>,
+    outputs             : Vec<String>,
+    proof               : String,
+}
+
+struct Response {
+    code    : u16,
+    body    : Value,
+}
+
+/// Reads from a connection until the deadline, after which all reads fail; this bounds the
+/// time a client can take to send a request regardless of how slowly it sends the bytes.
+struct DeadlineReader {
+    stream      : TcpStream,
+    deadline    : Instant,
+}
+
+/// Decrements the number of active connections when the connection is closed.
+struct ConnectionGuard {
+    active      : Arc<AtomicUsize>,
+}
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
+/// Serves HTTP requests received by the `listener` using the `service`; every connection is
+/// handled by a separate thread and carries a single request. No more than 64 connections are
+/// handled at the same time (other connections are rejected with status 503), a request must
+/// be received within 30 seconds, and the request line and headers together must not exceed
+/// 16 KB. The following endpoints are supported:
+///
+/// * `POST /prove` - submits a proving job and responds with `{"job": <id>}`;
+/// * `GET /jobs/<id>` - responds with the status of a job; the status of a completed job
+///   includes the outputs of the program and the proof bytes encoded as a hex string. Once the
+///   status of a finished job is returned, the job is removed from the service;
+/// * `POST /verify` - verifies a proof and responds with `{"valid": <bool>}`, together with an
+///   error message if the proof is not valid. Proofs generated with options weaker than the
+///   options of the service are not valid (see `ServiceConfig::verification_policy()`).
+pub fn serve(listener: TcpListener, service: ProvingService) -> io::Result<()> {
+    let active = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let stream = stream?;
+        if active.load(Ordering::SeqCst) >= MAX_CONNECTIONS {
+            let _ = reject_connection(stream);
+            continue;
+        }
+
+        active.fetch_add(1, Ordering::SeqCst);
+        let guard = ConnectionGuard { active: active.clone() };
+        let service = service.clone();
+        thread::spawn(move || {
+            // a connection closed by the client is not an error of the service
+            let _ = handle_connection(stream, &service);
+            drop(guard);
+        });
+    }
+    return Ok(());
+}
+
+// DEADLINE READER AND CONNECTION GUARD IMPLEMENTATIONS
+// ================================================================================================
+impl Read for DeadlineReader {
+
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let now = Instant::now();
+        if now >= self.deadline {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "request was not received in time"));
+        }
+        self.stream.set_read_timeout(Some(self.deadline - now))?;
+        return self.stream.read(buf);
+    }
+}
+
+impl Drop for ConnectionGuard {
+
+    fn drop(&mut self) {
+        self.active.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+fn handle_connection(stream: TcpStream, service: &ProvingService) -> io::Result<()> {
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let deadline = Instant::now() + REQUEST_TIMEOUT;
+    let reader = DeadlineReader { stream: stream.try_clone()?, deadline };
+    let mut reader = BufReader::new(reader.take(MAX_HEADER_SIZE as u64));
+
+    let request_line = match read_header_line(&mut reader)? {
+        Some(line) => line,
+        None => return write_response(stream, error(431, "request line and headers are too large")),
+    };
+    let parts: Vec<&str> = request_line.split_whitespace().collect();
+    if parts.len() != 3 {
+        return write_response(stream, error(400, "malformed request line"));
+    }
+
+    let mut content_length = 0;
+    let mut num_headers = 0;
+    loop {
+        let header = match read_header_line(&mut reader)? {
+            Some(header) => header,
+            None => return write_response(stream, error(431, "request line and headers are too large")),
+        };
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        num_headers += 1;
+        if num_headers > MAX_HEADERS {
+            return write_response(stream, error(431, "request has too many headers"));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = match value.trim().parse() {
+                    Ok(length) => length,
+                    Err(_) => return write_response(stream, error(400, "invalid content length")),
+                };
+            }
+        }
+    }
+    if content_length > MAX_BODY_SIZE {
+        return write_response(stream, error(413, "request body is too large"));
+    }
+
+    // the body may have been partially read into the buffer together with the headers
+    let buffered = reader.buffer().len();
+    reader.get_mut().set_limit(content_length.saturating_sub(buffered) as u64);
+    let mut body = vec![0u8; content_length];
+    reader.read_exact(&mut body)?;
+
+    let response = route(service, parts[0], parts[1], &body);
+    return write_response(stream, response);
+}
+
+/// Reads a single line of the request line and headers; returns None if the line does not fit
+/// into the space left for the request line and headers.
+fn read_header_line(reader: &mut BufReader<io::Take<DeadlineReader>>) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if !line.ends_with('\n') {
+        if reader.get_ref().limit() == 0 {
+            return Ok(None);
+        }
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "connection closed before the request was received"));
+    }
+    return Ok(Some(line));
+}
+
+/// Responds to a connection which cannot be handled because too many connections are open.
+fn reject_connection(stream: TcpStream) -> io::Result<()> {
+    stream.set_write_timeout(Some(Duration::from_secs(1)))?;
+    return write_response(stream, error(503, "too many connections"));
+}
+
+fn route(service: &ProvingService, method: &str, path: &str, body: &[u8]) -> Response {
+    return match (method, path) {
+        ("POST", "/prove") => handle_prove(service, body),
+        ("POST", "/verify") => handle_verify(service, body),
+        ("GET", path) if path.starts_with("/jobs/") => handle_status(service, &path[6..]),
+        _ => error(404, "not found"),
+    };
+}
+
+fn handle_prove(service: &ProvingService, body: &[u8]) -> Response {
+    let request: JsonProveRequest = match serde_json::from_slice(body) {
+        Ok(request) => request,
+        Err(err) => return error(400, &format!("invalid request: {}", err)),
+    };
+
+    let public_inputs = match decode_elements(&request.public_inputs) {
+        Ok(values) => values,
+        Err(err) => return error(400, &err),
+    };
+    let secret_a = match decode_elements(&request.secret_a) {
+        Ok(values) => values,
+        Err(err) => return error(400, &err),
+    };
+    let secret_b = match decode_elements(&request.secret_b) {
+        Ok(values) => values,
+        Err(err) => return error(400, &err),
+    };
+    if public_inputs.len() > MAX_PUBLIC_INPUTS {
+        return error(400, &format!("expected no more than {} public inputs, but received {}",
+            MAX_PUBLIC_INPUTS, public_inputs.len()));
+    }
+    if secret_a.len() < secret_b.len() {
+        return error(400, "number of primary secret inputs cannot be smaller than the number of secondary secret inputs");
+    }
+
+    let result = service.submit(ProveRequest {
+        source      : request.program,
+        inputs      : ProgramInputs::new(&public_inputs, &secret_a, &secret_b),
+        num_outputs : request.num_outputs,
+        max_cycles  : request.max_cycles,
+    });
+    return match result {
+        Ok(id) => Response { code: 202, body: json!({ "job": id }) },
+        Err(err) => error(503, &err),
+    };
+}
+
+fn handle_status(service: &ProvingService, id: &str) -> Response {
+    let status = match id.parse::<u64>().ok().and_then(|id| service.fetch(id)) {
+        Some(status) => status,
+        None => return error(404, "job not found"),
+    };
+
+    let body = match status {
+        JobStatus::Queued => json!({ "status": "queued" }),
+        JobStatus::Running => json!({ "status": "running" }),
+        JobStatus::Completed { outputs, proof } => json!({
+            "status"    : "completed",
+            "outputs"   : encode_elements(&outputs),
+            "proof"     : hex::encode(proof),
+        }),
+        JobStatus::Failed(err) => json!({ "status": "failed", "error": err }),
+    };
+    return Response { code: 200, body };
+}
+
+fn handle_verify(service: &ProvingService, body: &[u8]) -> Response {
+    let request: JsonVerifyRequest = match serde_json::from_slice(body) {
+        Ok(request) => request,
+        Err(err) => return error(400, &format!("invalid request: {}", err)),
+    };
+
+    let mut program_hash = [0u8; 32];
+    match hex::decode(&request.program_hash) {
+        Ok(bytes) if bytes.len() == 32 => program_hash.copy_from_slice(&bytes),
+        _ => return error(400, "program hash must be a hex string of 32 bytes"),
+    };
+    let public_inputs = match decode_elements(&request.public_inputs) {
+        Ok(values) => values,
+        Err(err) => return error(400, &err),
+    };
+    let outputs = match decode_elements(&request.outputs) {
+        Ok(values) => values,
+        Err(err) => return error(400, &err),
+    };
+    let proof = match hex::decode(&request.proof) {
+        Ok(bytes) => bytes,
+        Err(_) => return error(400, "proof must be a hex string"),
+    };
+
+    // proof options are chosen by the prover; proofs weaker than those generated by the service
+    // itself are rejected
+    let policy = service.config().verification_policy();
+    let result = StarkProof::from_bytes(&proof)
+        .and_then(|proof| verify_with_policy(&program_hash, &public_inputs, &outputs, &proof, &policy));
+    let body = match result {
+        Ok(valid) => json!({ "valid": valid }),
+        Err(err) => json!({ "valid": false, "error": err }),
+    };
+    return Response { code: 200, body };
+}
+
+fn write_response(mut stream: TcpStream, response: Response) -> io::Result<()> {
+    let reason = match response.code {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        404 => "Not Found",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    };
+    let body = response.body.to_string();
+    write!(stream, "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.code, reason, body.len(), body)?;
+    return stream.flush();
+}
+
+fn error(code: u16, message: &str) -> Response {
+    return Response { code, body: json!({ "error": message }) };
+}
+
+fn decode_elements(values: &[String]) -> Result<Vec<u128>, String> {
+    let mut result = Vec::with_capacity(values.len());
+    for value in values.iter() {
+        match value.parse::<u128>() {
+            Ok(element) if element < field::MODULUS => result.push(element),
+            _ => return Err(format!("value '{}' is not a valid field element", value)),
+        }
+    }
+    return Ok(result);
+}
+
+fn encode_elements(values: &[u128]) -> Vec<String> {
+    return values.iter().map(|value| value.to_string()).collect();
+}
@@ -0,0 +1,344 @@
+use std::collections::{ HashMap, VecDeque };
+use std::panic::{ self, AssertUnwindSafe };
+use std::sync::{ Arc, Mutex, Condvar };
+use std::thread::{ self, JoinHandle };
+use std::time::{ Duration, Instant };
+use crate::{
+    assembly, processor, prove_execution, ProgramInputs, ProofOptions, MinimumAcceptableOptions,
+    HashTranscript, math::field, MAX_OUTPUTS, MIN_TRACE_LENGTH, MAX_REGISTER_COUNT,
+};
+
+mod http;
+pub use http::{ serve };
+
+#[cfg(test)]
+mod tests;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Configuration of a proving service.
+#[derive(Clone)]
+pub struct ServiceConfig {
+    pub num_workers     : usize,        // number of threads which execute and prove programs
+    pub max_memory      : usize,        // memory which all running jobs can use together, in bytes
+    pub max_cycles      : usize,        // maximum number of cycles a single program can execute
+    pub max_queued      : usize,        // maximum number of jobs waiting in the queue
+    pub job_ttl         : Duration,     // time for which results of finished jobs are kept
+    pub options         : ProofOptions, // options with which all proofs are generated and
+                                        // the weakest options accepted by the verifier
+}
+
+/// A request to execute a program written in Distaff assembly and to prove its execution; the
+/// program is linked against the standard library. If `max_cycles` is not set, the program can
+/// execute for as many cycles as the service allows.
+#[derive(Clone)]
+pub struct ProveRequest {
+    pub source          : String,
+    pub inputs          : ProgramInputs,
+    pub num_outputs     : usize,
+    pub max_cycles      : Option<usize>,
+}
+
+/// Status of a proving job.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed { outputs: Vec<u128>, proof: Vec<u8> },
+    Failed(String),
+}
+
+/// A pool of workers which prove programs submitted as jobs. Jobs are taken from the queue in
+/// the order in which they were submitted. Before a job is executed, it reserves the memory
+/// which the prover would need (as estimated by `ProofOptions::estimate()`) for the longest and
+/// widest execution trace the job can produce within its cycle limit, and waits until this
+/// memory fits into the memory left unused by other running jobs; once the program is executed,
+/// the reservation is reduced to the memory needed to prove the actual trace. Thus, `max_memory`
+/// bounds the memory used by concurrently running jobs, including the memory of their traces.
+///
+/// Submissions are rejected while `max_queued` jobs are waiting in the queue. Results of a
+/// finished job are dropped once they are fetched via `fetch()`, or `job_ttl` after the job
+/// finished.
+///
+/// The workers run until `shutdown()` is called on the service or on any of its clones.
+#[derive(Clone)]
+pub struct ProvingService {
+    shared  : Arc<Shared>,
+}
+
+struct Shared {
+    config  : ServiceConfig,
+    state   : Mutex<State>,
+    changed : Condvar,
+    workers : Mutex<Vec<JoinHandle<()>>>,
+}
+
+struct State {
+    next_id         : u64,
+    queue           : VecDeque<(u64, ProveRequest)>,
+    jobs            : HashMap<u64, JobStatus>,
+    finished        : VecDeque<(Instant, u64)>,
+    memory_in_use   : usize,
+    closed          : bool,
+}
+
+// SERVICE CONFIG IMPLEMENTATION
+// ================================================================================================
+impl Default for ServiceConfig {
+
+    /// Returns a configuration with a single worker per available CPU which can use up to 4 GB
+    /// of memory; programs can execute up to 2^15 cycles, so that a proof for the largest trace
+    /// fits into this memory with default proof options. Up to 1024 jobs can wait in the queue,
+    /// and results of finished jobs are kept for 1 hour.
+    fn default() -> ServiceConfig {
+        return ServiceConfig {
+            num_workers : thread::available_parallelism().map_or(1, |n| n.get()),
+            max_memory  : 4 << 30,
+            max_cycles  : 1 << 15,
+            max_queued  : 1024,
+            job_ttl     : Duration::from_secs(3600),
+            options     : ProofOptions::default(),
+        };
+    }
+}
+
+impl ServiceConfig {
+
+    /// Returns a policy which accepts only proofs generated with options at least as strong as
+    /// the options of this service; proofs generated with any hash function other than the one
+    /// used by the service are rejected.
+    pub fn verification_policy(&self) -> MinimumAcceptableOptions {
+        return MinimumAcceptableOptions {
+            field_modulus   : field::MODULUS,
+            hash_fns        : vec![self.options.hash_fn()],
+            num_queries     : self.options.num_queries(),
+            extension_factor: self.options.extension_factor(),
+            grinding_factor : self.options.grinding_factor(),
+        };
+    }
+}
+
+// PROVING SERVICE IMPLEMENTATION
+// ================================================================================================
+impl ProvingService {
+
+    /// Starts a proving service with the specified configuration; the workers run until the
+    /// service is shut down via `shutdown()`.
+    pub fn start(config: ServiceConfig) -> ProvingService {
+        assert!(config.num_workers > 0, "number of workers must be greater than 0");
+
+        let shared = Arc::new(Shared {
+            config  : config,
+            state   : Mutex::new(State {
+                next_id         : 1,
+                queue           : VecDeque::new(),
+                jobs            : HashMap::new(),
+                finished        : VecDeque::new(),
+                memory_in_use   : 0,
+                closed          : false,
+            }),
+            changed : Condvar::new(),
+            workers : Mutex::new(Vec::new()),
+        });
+
+        let workers = (0..shared.config.num_workers).map(|_| {
+            let shared = shared.clone();
+            return thread::spawn(move || run_worker(&shared));
+        }).collect();
+        *shared.workers.lock().unwrap() = workers;
+
+        return ProvingService { shared };
+    }
+
+    /// Returns the configuration of this service.
+    pub fn config(&self) -> &ServiceConfig {
+        return &self.shared.config;
+    }
+
+    /// Adds the `request` to the queue and returns the id of the job which will process it;
+    /// returns an error if the queue is full or the service has been shut down.
+    pub fn submit(&self, request: ProveRequest) -> Result<u64, String> {
+        let mut state = self.shared.state.lock().unwrap();
+        remove_expired(&mut state, self.shared.config.job_ttl);
+        if state.closed {
+            return Err(String::from("service has been shut down"));
+        }
+        if state.queue.len() >= self.shared.config.max_queued {
+            return Err(format!("cannot queue more than {} jobs", self.shared.config.max_queued));
+        }
+        let id = state.next_id;
+        state.next_id += 1;
+        state.jobs.insert(id, JobStatus::Queued);
+        state.queue.push_back((id, request));
+        self.shared.changed.notify_all();
+        return Ok(id);
+    }
+
+    /// Returns the status of the job with the specified `id`, or None if there is no such job.
+    pub fn status(&self, id: u64) -> Option<JobStatus> {
+        let mut state = self.shared.state.lock().unwrap();
+        remove_expired(&mut state, self.shared.config.job_ttl);
+        return state.jobs.get(&id).cloned();
+    }
+
+    /// Returns the status of the job with the specified `id` in the same way as `status()`
+    /// does, but if the job is finished, the job is removed from the service.
+    pub fn fetch(&self, id: u64) -> Option<JobStatus> {
+        let mut state = self.shared.state.lock().unwrap();
+        remove_expired(&mut state, self.shared.config.job_ttl);
+        return match state.jobs.get(&id) {
+            Some(JobStatus::Queued) | Some(JobStatus::Running) => state.jobs.get(&id).cloned(),
+            _ => state.jobs.remove(&id),
+        };
+    }
+
+    /// Blocks until the job with the specified `id` is completed or fails, and returns its
+    /// status; returns None if there is no such job.
+    pub fn wait(&self, id: u64) -> Option<JobStatus> {
+        let mut state = self.shared.state.lock().unwrap();
+        loop {
+            match state.jobs.get(&id) {
+                None => return None,
+                Some(JobStatus::Queued) | Some(JobStatus::Running) => (),
+                Some(status) => return Some(status.clone()),
+            }
+            state = self.shared.changed.wait(state).unwrap();
+        }
+    }
+
+    /// Closes the queue of this service and blocks until all workers have stopped. Jobs which
+    /// are already in the queue are processed before the workers stop, and so, once this
+    /// function returns, every submitted job is either completed or failed; new submissions are
+    /// rejected. Calling this function again has no effect.
+    pub fn shutdown(&self) {
+        self.shared.state.lock().unwrap().closed = true;
+        self.shared.changed.notify_all();
+
+        let workers = std::mem::take(&mut *self.shared.workers.lock().unwrap());
+        for worker in workers {
+            // failed jobs are caught by the workers, and so a worker cannot panic
+            worker.join().unwrap();
+        }
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Takes jobs from the queue and processes them one by one; returns once the queue is closed
+/// and empty.
+fn run_worker(shared: &Shared) {
+    loop {
+        let (id, request) = {
+            let mut state = shared.state.lock().unwrap();
+            loop {
+                if let Some(job) = state.queue.pop_front() {
+                    break job;
+                }
+                if state.closed {
+                    return;
+                }
+                state = shared.changed.wait(state).unwrap();
+            }
+        };
+        set_status(shared, id, JobStatus::Running);
+
+        // the VM reports invalid programs and inputs by panicking; such panics fail the job
+        // rather than the worker
+        let status = match panic::catch_unwind(AssertUnwindSafe(|| prove(shared, &request))) {
+            Ok(Ok((outputs, proof))) => JobStatus::Completed { outputs, proof },
+            Ok(Err(err)) => JobStatus::Failed(err),
+            Err(err) => JobStatus::Failed(panic_message(err)),
+        };
+        set_status(shared, id, status);
+        shared.state.lock().unwrap().finished.push_back((Instant::now(), id));
+    }
+}
+
+/// Waits until there is enough memory to prove the longest execution of the program described
+/// by the `request`, executes the program, and returns the outputs of the program together with
+/// the proof bytes.
+fn prove(shared: &Shared, request: &ProveRequest) -> Result<(Vec<u128>, Vec<u8>), String> {
+    let config = &shared.config;
+    if request.num_outputs > MAX_OUTPUTS {
+        return Err(format!("cannot produce more than {} outputs, but requested {}", MAX_OUTPUTS, request.num_outputs));
+    }
+    let max_cycles = request.max_cycles.unwrap_or(config.max_cycles);
+    if max_cycles > config.max_cycles {
+        return Err(format!("cannot execute more than {} cycles, but requested {}", config.max_cycles, max_cycles));
+    }
+    let program = match assembly::Linker::with_stdlib().link(&request.source) {
+        Ok(program) => program,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    // reserve memory for the largest trace the program can produce before executing it; the
+    // reservation is released even if execution or proving fails
+    let max_trace_length = std::cmp::max(max_cycles, MIN_TRACE_LENGTH).next_power_of_two();
+    let mut memory = config.options.estimate(max_trace_length, MAX_REGISTER_COUNT).prover_memory;
+    if memory > config.max_memory {
+        return Err(format!("proving the program requires up to {} bytes of memory, but the service is limited to {} bytes",
+            memory, config.max_memory));
+    }
+    {
+        let mut state = shared.state.lock().unwrap();
+        while state.memory_in_use + memory > config.max_memory {
+            state = shared.changed.wait(state).unwrap();
+        }
+        state.memory_in_use += memory;
+    }
+
+    let now = Instant::now();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let (trace, ctx_depth, loop_depth, call_depth) = processor::execute(&program, &request.inputs, max_cycles)
+            .map_err(|err| err.to_string())?;
+
+        // the actual trace needs no more memory than the largest one; release the difference
+        let needed = config.options.estimate(trace[0].len(), trace.len()).prover_memory;
+        if needed < memory {
+            shared.state.lock().unwrap().memory_in_use -= memory - needed;
+            shared.changed.notify_all();
+            memory = needed;
+        }
+
+        let mut transcript = HashTranscript::new(config.options.hash_fn());
+        return Ok(prove_execution(&program, &request.inputs, request.num_outputs, &config.options, &mut transcript,
+            trace, ctx_depth, loop_depth, call_depth, &mut None, now));
+    }));
+
+    shared.state.lock().unwrap().memory_in_use -= memory;
+    shared.changed.notify_all();
+
+    return match result {
+        Ok(Ok((outputs, proof))) => Ok((outputs, proof.to_bytes())),
+        Ok(Err(err)) => Err(err),
+        Err(err) => Err(panic_message(err)),
+    };
+}
+
+/// Removes jobs which finished more than `ttl` ago.
+fn remove_expired(state: &mut State, ttl: Duration) {
+    while let Some(&(finished_at, id)) = state.finished.front() {
+        if finished_at.elapsed() < ttl {
+            break;
+        }
+        state.jobs.remove(&id);
+        state.finished.pop_front();
+    }
+}
+
+fn set_status(shared: &Shared, id: u64, status: JobStatus) {
+    shared.state.lock().unwrap().jobs.insert(id, status);
+    shared.changed.notify_all();
+}
+
+fn panic_message(err: Box<dyn std::any::Any + Send>) -> String {
+    return match err.downcast::<String>() {
+        Ok(message) => *message,
+        Err(err) => match err.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => String::from("proving failed"),
+        },
+    };
+}
@@ -0,0 +1,212 @@
+use std::io::{ Read, Write };
+use std::net::{ TcpListener, TcpStream };
+use std::thread;
+use std::time::Duration;
+use serde_json::Value;
+use crate::{ assembly, execute, verify, ProgramInputs, ProofOptions, StarkProof, crypto::hash };
+use super::{ ProvingService, ServiceConfig, ProveRequest, JobStatus, serve };
+
+#[test]
+fn prove_jobs() {
+    let service = ProvingService::start(ServiceConfig { num_workers: 2, ..ServiceConfig::default() });
+    let source = "begin push.3 mul end";
+
+    let ids: Vec<u64> = (1..4).map(|i| service.submit(ProveRequest {
+        source      : String::from(source),
+        inputs      : ProgramInputs::from_public(&[i]),
+        num_outputs : 1,
+        max_cycles  : Some(1 << 10),
+    }).unwrap()).collect();
+
+    let program = assembly::compile(source).unwrap();
+    for (i, &id) in ids.iter().enumerate() {
+        let public_inputs = [i as u128 + 1];
+        match service.wait(id) {
+            Some(JobStatus::Completed { outputs, proof }) => {
+                assert_eq!(vec![3 * public_inputs[0]], outputs);
+                let proof = StarkProof::from_bytes(&proof).unwrap();
+                assert_eq!(Ok(true), verify(program.hash(), &public_inputs, &outputs, &proof));
+            },
+            status => panic!("unexpected job status {:?}", status),
+        }
+    }
+
+    // invalid programs fail their jobs without stopping the workers
+    let id = service.submit(ProveRequest {
+        source      : String::from("begin push.3 mul"),
+        inputs      : ProgramInputs::none(),
+        num_outputs : 1,
+        max_cycles  : Some(1 << 10),
+    }).unwrap();
+    let status = service.wait(id);
+    assert!(matches!(status, Some(JobStatus::Failed(_))), "unexpected job status {:?}", status);
+    assert_eq!(None, service.wait(100));
+}
+
+#[test]
+fn shutdown() {
+    let service = ProvingService::start(ServiceConfig { num_workers: 1, ..ServiceConfig::default() });
+    let request = ProveRequest {
+        source      : String::from("begin push.3 mul end"),
+        inputs      : ProgramInputs::from_public(&[1]),
+        num_outputs : 1,
+        max_cycles  : Some(1 << 10),
+    };
+
+    // jobs which are queued when the service is shut down are finished before workers stop
+    let ids: Vec<u64> = (0..3).map(|_| service.submit(request.clone()).unwrap()).collect();
+    service.clone().shutdown();
+    for &id in ids.iter() {
+        let status = service.status(id);
+        assert!(matches!(status, Some(JobStatus::Completed { .. })), "unexpected job status {:?}", status);
+    }
+
+    assert_eq!(Err(String::from("service has been shut down")), service.submit(request));
+    service.shutdown();
+}
+
+#[test]
+fn memory_limit() {
+    // a job which may need more memory than the service can provide fails before it is executed
+    let service = ProvingService::start(ServiceConfig { num_workers: 1, max_memory: 1 << 10, ..ServiceConfig::default() });
+    let id = service.submit(ProveRequest {
+        source      : String::from("begin push.3 mul end"),
+        inputs      : ProgramInputs::from_public(&[1]),
+        num_outputs : 1,
+        max_cycles  : Some(1 << 10),
+    }).unwrap();
+    match service.wait(id) {
+        Some(JobStatus::Failed(err)) => assert!(err.ends_with("but the service is limited to 1024 bytes"), "{}", err),
+        status => panic!("unexpected job status {:?}", status),
+    }
+
+    // a job cannot execute more cycles than the service allows
+    let service = ProvingService::start(ServiceConfig { num_workers: 1, max_cycles: 1 << 10, ..ServiceConfig::default() });
+    let id = service.submit(ProveRequest {
+        source      : String::from("begin push.3 mul end"),
+        inputs      : ProgramInputs::from_public(&[1]),
+        num_outputs : 1,
+        max_cycles  : Some(1 << 11),
+    }).unwrap();
+    let status = service.wait(id);
+    assert_eq!(Some(JobStatus::Failed(String::from("cannot execute more than 1024 cycles, but requested 2048"))), status);
+}
+
+#[test]
+fn job_limits() {
+    let request = ProveRequest {
+        source      : String::from("begin push.3 mul end"),
+        inputs      : ProgramInputs::from_public(&[1]),
+        num_outputs : 1,
+        max_cycles  : Some(1 << 10),
+    };
+
+    // submissions are rejected when the queue is full
+    let service = ProvingService::start(ServiceConfig { num_workers: 1, max_queued: 0, ..ServiceConfig::default() });
+    assert_eq!(Err(String::from("cannot queue more than 0 jobs")), service.submit(request.clone()));
+
+    // a finished job is removed once it is fetched
+    let service = ProvingService::start(ServiceConfig { num_workers: 1, ..ServiceConfig::default() });
+    let id = service.submit(request.clone()).unwrap();
+    assert!(matches!(service.wait(id), Some(JobStatus::Completed { .. })));
+    assert!(matches!(service.status(id), Some(JobStatus::Completed { .. })));
+    assert!(matches!(service.fetch(id), Some(JobStatus::Completed { .. })));
+    assert_eq!(None, service.fetch(id));
+
+    // a finished job is removed once its results expire
+    let service = ProvingService::start(ServiceConfig { num_workers: 1, job_ttl: Duration::from_millis(10), ..ServiceConfig::default() });
+    let id = service.submit(request).unwrap();
+    assert!(matches!(service.wait(id), Some(JobStatus::Completed { .. })));
+    thread::sleep(Duration::from_millis(20));
+    assert_eq!(None, service.status(id));
+}
+
+#[test]
+fn http_endpoints() {
+    let service = ProvingService::start(ServiceConfig { num_workers: 1, ..ServiceConfig::default() });
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let server = service.clone();
+    thread::spawn(move || serve(listener, server));
+
+    let (code, body) = send(&address, "POST", "/prove",
+        r#"{"program":"begin push.3 mul end","public_inputs":["5"],"num_outputs":1,"max_cycles":1024}"#);
+    assert_eq!(202, code);
+    let id = body["job"].as_u64().unwrap();
+    service.wait(id);
+
+    let (code, body) = send(&address, "GET", &format!("/jobs/{}", id), "");
+    assert_eq!(200, code);
+    assert_eq!("completed", body["status"]);
+    assert_eq!("15", body["outputs"][0]);
+
+    // a finished job is removed once its status is returned
+    let (code, _) = send(&address, "GET", &format!("/jobs/{}", id), "");
+    assert_eq!(404, code);
+
+    let program = assembly::compile("begin push.3 mul end").unwrap();
+    let request = format!(r#"{{"program_hash":"{}","public_inputs":["5"],"outputs":["15"],"proof":"{}"}}"#,
+        hex::encode(program.hash()), body["proof"].as_str().unwrap());
+    let (code, body) = send(&address, "POST", "/verify", &request);
+    assert_eq!(200, code);
+    assert_eq!(true, body["valid"]);
+
+    let request = request.replace(r#""outputs":["15"]"#, r#""outputs":["16"]"#);
+    let (_, body) = send(&address, "POST", "/verify", &request);
+    assert_eq!(false, body["valid"]);
+
+    // proofs generated with options weaker than those of the service are rejected
+    let options = ProofOptions::new(16, 1, 0, hash::blake3);
+    let (_, proof) = execute(&program, &ProgramInputs::from_public(&[5]), 1, &options);
+    assert_eq!(Ok(true), verify(program.hash(), &[5], &[15], &proof));
+    let request = format!(r#"{{"program_hash":"{}","public_inputs":["5"],"outputs":["15"],"proof":"{}"}}"#,
+        hex::encode(program.hash()), hex::encode(proof.to_bytes()));
+    let (code, body) = send(&address, "POST", "/verify", &request);
+    assert_eq!(200, code);
+    assert_eq!(false, body["valid"]);
+    assert_eq!("proof uses 1 queries, but at least 50 are required", body["error"]);
+
+    // malformed requests are rejected
+    let (code, _) = send(&address, "POST", "/prove", r#"{"program":"begin end","public_inputs":[5],"num_outputs":1}"#);
+    assert_eq!(400, code);
+    let (code, _) = send(&address, "GET", "/jobs/100", "");
+    assert_eq!(404, code);
+}
+
+#[test]
+fn http_limits() {
+    let service = ProvingService::start(ServiceConfig { num_workers: 1, ..ServiceConfig::default() });
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || serve(listener, service));
+
+    // a request line which does not fit into 16 KB is rejected
+    let request = format!("GET /{}", "a".repeat((16 << 10) - 5));
+    let (code, _) = send_raw(&address, &request);
+    assert_eq!(431, code);
+
+    // a request with more than 64 headers is rejected
+    let request = format!("GET /jobs/1 HTTP/1.1\r\n{}", "Accept: */*\r\n".repeat(65));
+    let (code, _) = send_raw(&address, &request);
+    assert_eq!(431, code);
+
+    // a request is still served if its body was read together with the headers
+    let (code, _) = send(&address, "GET", "/jobs/100", "{}");
+    assert_eq!(404, code);
+}
+
+fn send(address: &str, method: &str, path: &str, body: &str) -> (u16, Value) {
+    let request = format!("{} {} HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", method, path, body.len(), body);
+    return send_raw(address, &request);
+}
+
+fn send_raw(address: &str, request: &str) -> (u16, Value) {
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream.write_all(request.as_bytes()).unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let code = response[9..12].parse().unwrap();
+    let body = &response[(response.find("\r\n\r\n").unwrap() + 4)..];
+    return (code, serde_json::from_str(body).unwrap());
+}
@@ -0,0 +1,120 @@
+use crate::{ math::field, stark::{ TraceTable, TraceState }, AUX_WIDTH, BUS_REGISTER_IDX, OUTPUT_REGISTER_IDX };
+use super::{
+    bus::{ Bus, BusMessages, BUS_CONSTRAINT_DEGREE },
+    output::{ OutputTape, OUTPUT_CONSTRAINT_DEGREE },
+};
+
+// CONSTANTS
+// ================================================================================================
+
+/// Degrees of transition constraints of auxiliary registers; every auxiliary register has a
+/// single transition constraint, and the constraints are listed in the order of the registers.
+pub const AUX_CONSTRAINT_DEGREES: [usize; AUX_WIDTH] = build_constraint_degrees();
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Auxiliary registers of the execution trace. Values of these registers depend on randomness
+/// drawn by the verifier after the main trace has been committed to, and so, the registers are
+/// built from the extended main trace and are committed to separately. Every auxiliary register
+/// has a single transition constraint, and a pair of boundary constraints which fix its values
+/// at the first and at the last steps of the trace.
+///
+/// A new auxiliary register is added by increasing `AUX_WIDTH`, assigning an index to the
+/// register in the auxiliary layout, declaring the degree of its transition constraint in
+/// `build_constraint_degrees()`, and then building, evaluating, and bounding the register in the
+/// methods below; the rest of the prover and the verifier pick it up from there.
+pub struct AuxRegisters {
+    bus         : Bus,
+    output_tape : OutputTape,
+    tape_value  : u128,
+}
+
+// AUXILIARY REGISTERS IMPLEMENTATION
+// ================================================================================================
+impl AuxRegisters {
+
+    /// Returns auxiliary registers with randomness derived from the specified `seed` for an
+    /// execution which wrote `tape_values` to the output tape; the seed should be the commitment
+    /// to the main execution trace.
+    pub fn new(seed: [u8; 32], tape_values: &[u128]) -> AuxRegisters {
+        let output_tape = OutputTape::new(seed);
+        let tape_value = output_tape.reduce(tape_values);
+        return AuxRegisters {
+            bus         : Bus::new(seed),
+            output_tape : output_tape,
+            tape_value  : tape_value,
+        };
+    }
+
+    /// Builds all auxiliary registers from the specified `trace` using randomness derived from
+    /// the `seed`; the trace must be extended. Returns the registers in the order of the
+    /// auxiliary layout together with the values written to the output tape.
+    pub fn build_trace_columns(seed: [u8; 32], trace: &TraceTable) -> (Vec<Vec<u128>>, Vec<u128>) {
+        let bus_column = Bus::new(seed).build_trace_column(trace);
+        assert!(Bus::is_balanced(&bus_column), "not all messages sent to the bus were received from it");
+        let (output_column, tape_values) = OutputTape::new(seed).build_trace_column(trace);
+
+        let mut columns = vec![Vec::new(); AUX_WIDTH];
+        columns[BUS_REGISTER_IDX] = bus_column;
+        columns[OUTPUT_REGISTER_IDX] = output_column;
+        return (columns, tape_values);
+    }
+
+    /// Returns values of auxiliary registers at the first step of the trace: the running
+    /// product of the bus and the output tape register both start with 1.
+    pub fn initial_values(&self) -> [u128; AUX_WIDTH] {
+        let mut values = [field::ZERO; AUX_WIDTH];
+        values[BUS_REGISTER_IDX] = field::ONE;
+        values[OUTPUT_REGISTER_IDX] = field::ONE;
+        return values;
+    }
+
+    /// Returns values of auxiliary registers at the last step of the trace: the running product
+    /// of the bus must end with 1 (all messages sent to the bus were received from it), and the
+    /// output tape register must hold reduced values of the output tape.
+    pub fn final_values(&self) -> [u128; AUX_WIDTH] {
+        let mut values = [field::ZERO; AUX_WIDTH];
+        values[BUS_REGISTER_IDX] = field::ONE;
+        values[OUTPUT_REGISTER_IDX] = self.tape_value;
+        return values;
+    }
+
+    /// Evaluates transition constraints of all auxiliary registers given `current` and `next`
+    /// states of the trace and bus `messages` exchanged at this transition; the evaluations are
+    /// written into the `result` in the order of the auxiliary layout.
+    pub fn evaluate(&self, current: &TraceState, next: &TraceState, messages: &BusMessages, result: &mut [u128]) {
+        result[BUS_REGISTER_IDX] = self.bus.evaluate(current.bus(), next.bus(), messages);
+        result[OUTPUT_REGISTER_IDX] = self.output_tape.evaluate(current, next);
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+const fn build_constraint_degrees() -> [usize; AUX_WIDTH] {
+    let mut degrees = [0; AUX_WIDTH];
+    degrees[BUS_REGISTER_IDX] = BUS_CONSTRAINT_DEGREE;
+    degrees[OUTPUT_REGISTER_IDX] = OUTPUT_CONSTRAINT_DEGREE;
+    return degrees;
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ math::field, AUX_WIDTH };
+    use super::{ AuxRegisters, AUX_CONSTRAINT_DEGREES };
+
+    #[test]
+    fn boundary_values() {
+        let aux = AuxRegisters::new([1; 32], &[3, 5]);
+        assert_eq!(AUX_WIDTH, AUX_CONSTRAINT_DEGREES.len());
+        assert_eq!([field::ONE; AUX_WIDTH], aux.initial_values());
+
+        // the bus must be balanced and the output register must hold the reduced tape
+        let final_values = aux.final_values();
+        assert_eq!(field::ONE, final_values[0]);
+        assert_eq!(aux.output_tape.reduce(&[3, 5]), final_values[1]);
+    }
+}
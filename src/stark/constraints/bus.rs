@@ -0,0 +1,199 @@
+use crate::{ math::field, stark::TraceTable };
+use super::hasher;
+
+// CONSTANTS
+// ================================================================================================
+pub const NUM_BUS_CONSTRAINTS: usize = 1;
+
+/// each side of the bus has a single flag * message term of degree 3 (degree 2 flag and degree 1
+/// message); the running product adds one more degree
+pub const BUS_CONSTRAINT_DEGREE: usize = 4;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// A virtual bus which connects co-processors (e.g. hasher, memory, range checker) to the main
+/// execution trace. The bus is represented by a single running product column p such that for
+/// every transition of the trace:
+///
+///   p_{i+1} * (product of received messages at step i) = p_i * (product of sent messages at step i)
+///
+/// Every message is a list of field elements which is reduced to a single element as
+/// alpha + v_0 + beta * v_1 + beta^2 * v_2 + ..., where alpha and beta are random values drawn by
+/// the verifier after the main trace has been committed to. If p_0 = 1, then p_{n-1} = 1 if and
+/// only if (with high probability) every message sent to the bus was also received from it.
+pub struct Bus {
+    alpha   : u128,
+    beta    : u128,
+}
+
+/// Messages exchanged over the bus at a single transition of the execution trace. Every message
+/// is accompanied by a binary flag which specifies whether the message is actually sent (or
+/// received) at this transition; messages with a flag set to 0 do not affect the bus.
+pub struct BusMessages {
+    sent    : Vec<(u128, Vec<u128>)>,
+    received: Vec<(u128, Vec<u128>)>,
+}
+
+// BUS IMPLEMENTATION
+// ================================================================================================
+impl Bus {
+
+    /// Returns a new bus with randomness derived from the specified `seed`; the seed should be
+    /// the commitment to the main execution trace.
+    pub fn new(seed: [u8; 32]) -> Bus {
+        let randomness = field::prng_vector(seed, 2);
+        return Bus { alpha: randomness[0], beta: randomness[1] };
+    }
+
+    /// Reduces a list of values to a single field element using bus randomness.
+    pub fn reduce(&self, values: &[u128]) -> u128 {
+        let mut result = self.alpha;
+        let mut power = field::ONE;
+        for &value in values.iter() {
+            result = field::add(result, field::mul(value, power));
+            power = field::mul(power, self.beta);
+        }
+        return result;
+    }
+
+    /// Builds the running product column for the bus from the messages exchanged at every
+    /// transition of the trace; `messages[i]` must contain messages exchanged at the transition
+    /// from step i to step i + 1. The first value of the column is always 1.
+    pub fn build_column(&self, messages: &[BusMessages]) -> Vec<u128> {
+
+        // compute products of sent and received messages for all transitions, and invert
+        // products of received messages in a single batch
+        let mut sent = Vec::with_capacity(messages.len());
+        let mut received = Vec::with_capacity(messages.len());
+        for step_messages in messages.iter() {
+            sent.push(self.combine(&step_messages.sent));
+            received.push(self.combine(&step_messages.received));
+        }
+        let received = field::inv_many(&received);
+
+        let mut column = Vec::with_capacity(messages.len() + 1);
+        column.push(field::ONE);
+        for i in 0..messages.len() {
+            let next = field::mul(field::mul(column[i], sent[i]), received[i]);
+            column.push(next);
+        }
+        return column;
+    }
+
+    /// Builds the running product column for the bus from messages exchanged by the stack and
+    /// co-processors at every step of the specified `trace`; the trace must be extended.
+    pub fn build_trace_column(&self, trace: &TraceTable) -> Vec<u128> {
+        let mut current = trace.get_state(0);
+        let mut next = trace.get_state(0);
+
+        let mut messages = Vec::with_capacity(trace.unextended_length() - 1);
+        for i in 0..(trace.unextended_length() - 1) {
+            trace.fill_trace_state(&mut current, i);
+            trace.fill_trace_state(&mut next, i + 1);
+            messages.push(hasher::get_trace_bus_messages(&current, &next, i));
+        }
+
+        return self.build_column(&messages);
+    }
+
+    /// Returns true if every message sent to the bus was also received from it; that is, if the
+    /// last value of the running product `column` is 1.
+    pub fn is_balanced(column: &[u128]) -> bool {
+        return column[column.len() - 1] == field::ONE;
+    }
+
+    /// Evaluates the bus transition constraint given values of the running product column at the
+    /// current and the next steps, and the messages exchanged at this transition. The degree of
+    /// the constraint is 1 + sum of degrees of all flag * message terms on the larger side.
+    pub fn evaluate(&self, p_current: u128, p_next: u128, messages: &BusMessages) -> u128 {
+        let sent = self.combine(&messages.sent);
+        let received = self.combine(&messages.received);
+        return field::sub(field::mul(p_next, received), field::mul(p_current, sent));
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+
+    /// Computes a product of (flag * (reduce(message) - 1) + 1) for all messages; this evaluates
+    /// to reduce(message) when the flag is 1, and to 1 when the flag is 0.
+    fn combine(&self, messages: &[(u128, Vec<u128>)]) -> u128 {
+        let mut result = field::ONE;
+        for (flag, values) in messages.iter() {
+            let value = field::sub(self.reduce(values), field::ONE);
+            result = field::mul(result, field::add(field::mul(*flag, value), field::ONE));
+        }
+        return result;
+    }
+}
+
+// BUS MESSAGES IMPLEMENTATION
+// ================================================================================================
+impl BusMessages {
+
+    pub fn new() -> BusMessages {
+        return BusMessages { sent: Vec::new(), received: Vec::new() };
+    }
+
+    /// Registers a message sent to the bus; the message affects the bus only if `flag` is 1.
+    pub fn send(&mut self, flag: u128, values: &[u128]) {
+        self.sent.push((flag, values.to_vec()));
+    }
+
+    /// Registers a message received from the bus; the message affects the bus only if `flag` is 1.
+    pub fn receive(&mut self, flag: u128, values: &[u128]) {
+        self.received.push((flag, values.to_vec()));
+    }
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::math::field;
+    use super::{ Bus, BusMessages };
+
+    #[test]
+    fn balanced_bus() {
+        let bus = Bus::new([1; 32]);
+
+        // messages are sent at steps 0 and 1, and received in a different order at steps 2 and 3
+        let mut messages = build_messages(4);
+        messages[0].send(field::ONE, &[1, 2, 3]);
+        messages[1].send(field::ONE, &[4, 5, 6]);
+        messages[1].receive(field::ZERO, &[7, 8, 9]);
+        messages[2].receive(field::ONE, &[4, 5, 6]);
+        messages[3].receive(field::ONE, &[1, 2, 3]);
+
+        let column = bus.build_column(&messages);
+        assert_eq!(5, column.len());
+        assert_eq!(field::ONE, column[0]);
+        assert!(Bus::is_balanced(&column));
+
+        // transition constraints should be satisfied at every step
+        for i in 0..messages.len() {
+            assert_eq!(field::ZERO, bus.evaluate(column[i], column[i + 1], &messages[i]));
+        }
+    }
+
+    #[test]
+    fn unbalanced_bus() {
+        let bus = Bus::new([1; 32]);
+
+        // the received message differs from the sent message in a single value
+        let mut messages = build_messages(2);
+        messages[0].send(field::ONE, &[1, 2, 3]);
+        messages[1].receive(field::ONE, &[1, 2, 4]);
+
+        let column = bus.build_column(&messages);
+        assert!(!Bus::is_balanced(&column));
+
+        // a column which was not built from the messages should not satisfy the constraints
+        assert_ne!(field::ZERO, bus.evaluate(field::ONE, field::ONE, &messages[0]));
+    }
+
+    fn build_messages(length: usize) -> Vec<BusMessages> {
+        return (0..length).map(|_| BusMessages::new()).collect();
+    }
+}
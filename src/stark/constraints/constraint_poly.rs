@@ -1,8 +1,9 @@
 use crate::math::{ field, polynom, parallel };
-use crate::stark::{ MAX_CONSTRAINT_DEGREE, utils::CompositionCoefficients };
+use crate::stark::{ MAX_CONSTRAINT_DEGREE, BufferPool, utils::CompositionCoefficients };
 
 // TYPES AND INTERFACES
 // ================================================================================================
+#[derive(Clone)]
 pub struct ConstraintPoly {
     poly: Vec<u128>
 }
@@ -25,17 +26,27 @@ impl ConstraintPoly {
         return get_expected_degree(&self.poly);
     }
 
-    pub fn eval(&self, twiddles: &[u128]) -> Vec<u128> {
+    /// Evaluates the polynomial over the domain defined by `twiddles` shifted by `domain_offset`;
+    /// the buffer for the evaluations is taken from the `pool`.
+    pub fn eval(&self, twiddles: &[u128], domain_offset: u128, pool: &mut BufferPool) -> Vec<u128> {
         let domain_size = twiddles.len() * 2;
         assert!(domain_size > self.poly.len(), "domain size must be greater than poly length");
 
-        let mut evaluations = vec![field::ZERO; domain_size];
+        let mut evaluations = pool.take(domain_size);
         evaluations[..self.poly.len()].copy_from_slice(&self.poly);
+        polynom::shift_in_place(&mut evaluations[..self.poly.len()], domain_offset);
         polynom::eval_fft_twiddles(&mut evaluations, twiddles, true);
 
         return evaluations;
     }
 
+    /// Adds `other` polynomial into this polynomial; both polynomials must have been built for
+    /// execution traces of the same length.
+    pub fn add(&mut self, other: &ConstraintPoly) {
+        assert!(self.poly.len() == other.poly.len(), "constraint polynomials must be of the same length");
+        parallel::add_in_place(&mut self.poly, &other.poly, 1);
+    }
+
     pub fn merge_into(mut self, result: &mut Vec<u128>, z: u128, cc: &CompositionCoefficients) -> u128 {
 
         // evaluate the polynomial at point z
@@ -1,7 +1,6 @@
-use crate::math::{ field, parallel, fft, polynom };
-use crate::stark::{ TraceTable, TraceState };
-use crate::utils::{ uninit_vector };
-use super::{ ConstraintEvaluator, ConstraintPoly };
+use crate::math::{ field, parallel, polynom };
+use crate::stark::{ TraceTable, TraceState, BufferPool };
+use super::{ ConstraintEvaluator, ConstraintSetup, ConstraintPoly };
 
 // TYPES AND INTERFACES
 // ================================================================================================
@@ -15,14 +14,16 @@ pub struct ConstraintTable {
 // CONSTRAINT TABLE IMPLEMENTATION
 // ================================================================================================
 impl ConstraintTable {
-    pub fn new(trace: &TraceTable, trace_root: &[u8; 32], inputs: &[u128], outputs: &[u128]) -> ConstraintTable {
-        let evaluator = ConstraintEvaluator::from_trace(trace, trace_root, inputs, outputs);
+    /// Returns a constraint table for the specified `trace`; constraint evaluators are built from
+    /// the `setup`, and buffers for constraint evaluations are taken from the `pool`.
+    pub fn new(setup: &ConstraintSetup, trace: &TraceTable, aux_seed: [u8; 32], coefficients_seed: [u8; 32], inputs: &[u128], outputs: &[u128], tape_values: &[u128], pool: &mut BufferPool) -> ConstraintTable {
+        let evaluator = ConstraintEvaluator::from_setup(setup, trace, aux_seed, coefficients_seed, inputs, outputs, tape_values);
         let evaluation_domain_size = evaluator.domain_size();
         return ConstraintTable {
             evaluator       : evaluator,
-            i_evaluations   : uninit_vector(evaluation_domain_size),
-            f_evaluations   : uninit_vector(evaluation_domain_size),
-            t_evaluations   : uninit_vector(evaluation_domain_size),
+            i_evaluations   : pool.take_uninit(evaluation_domain_size),
+            f_evaluations   : pool.take_uninit(evaluation_domain_size),
+            t_evaluations   : pool.take_uninit(evaluation_domain_size),
         };
     }
 
@@ -50,28 +51,34 @@ impl ConstraintTable {
     }
 
     /// Interpolates all constraint evaluations into polynomials and combines all these 
-    /// polynomials into a single polynomial using pseudo-random linear combination.
-    pub fn combine_polys(mut self) -> ConstraintPoly
+    /// polynomials into a single polynomial using pseudo-random linear combination; `inv_twiddles`
+    /// are used to interpolate evaluations over the constraint evaluation domain, and the shift
+    /// of the domain is removed from the interpolated polynomials. The buffer
+    /// for the combined polynomial is taken from the `pool`, and buffers of constraint
+    /// evaluations are returned to it.
+    pub fn combine_polys(mut self, inv_twiddles: &[u128], pool: &mut BufferPool) -> ConstraintPoly
     {
-        let combination_root = field::get_root_of_unity(self.evaluation_domain_size());
-        let inv_twiddles = fft::get_inv_twiddles(combination_root, self.evaluation_domain_size());
+        assert!(inv_twiddles.len() * 2 == self.evaluation_domain_size(), "invalid number of inverse twiddles");
      
         #[cfg(debug_assertions)]
         self.validate_transition_degrees();
         
-        let mut combined_poly = uninit_vector(self.evaluation_domain_size());
+        let mut combined_poly = pool.take_uninit(self.evaluation_domain_size());
+        let inv_offset = field::inv(self.evaluator.domain_offset());
         
         // 1 ----- boundary constraints for the initial step --------------------------------------
         // interpolate initial step boundary constraint combination into a polynomial, divide the 
         // polynomial by Z(x) = (x - 1), and add it to the result
-        polynom::interpolate_fft_twiddles(&mut self.i_evaluations, &inv_twiddles, true);
+        polynom::interpolate_fft_twiddles(&mut self.i_evaluations, inv_twiddles, true);
+        polynom::shift_in_place(&mut self.i_evaluations, inv_offset);
         polynom::syn_div_in_place(&mut self.i_evaluations, field::ONE);
         combined_poly.copy_from_slice(&self.i_evaluations);
 
         // 2 ----- boundary constraints for the final step ----------------------------------------
         // interpolate final step boundary constraint combination into a polynomial, divide the 
         // polynomial by Z(x) = (x - x_at_last_step), and add it to the result
-        polynom::interpolate_fft_twiddles(&mut self.f_evaluations, &inv_twiddles, true);
+        polynom::interpolate_fft_twiddles(&mut self.f_evaluations, inv_twiddles, true);
+        polynom::shift_in_place(&mut self.f_evaluations, inv_offset);
         let x_at_last_step = self.evaluator.get_x_at_last_step();
         polynom::syn_div_in_place(&mut self.f_evaluations, x_at_last_step);
         parallel::add_in_place(&mut combined_poly, &self.f_evaluations, 1);
@@ -80,10 +87,15 @@ impl ConstraintTable {
         // interpolate transition constraint combination into a polynomial, divide the polynomial
         // by Z(x) = (x^steps - 1) / (x - x_at_last_step), and add it to the result
         let trace_length = self.trace_length();
-        polynom::interpolate_fft_twiddles(&mut self.t_evaluations, &inv_twiddles, true);
+        polynom::interpolate_fft_twiddles(&mut self.t_evaluations, inv_twiddles, true);
+        polynom::shift_in_place(&mut self.t_evaluations, inv_offset);
         polynom::syn_div_expanded_in_place(&mut self.t_evaluations, trace_length, &[x_at_last_step]);
         parallel::add_in_place(&mut combined_poly, &self.t_evaluations, 1);
 
+        pool.release(self.i_evaluations);
+        pool.release(self.f_evaluations);
+        pool.release(self.t_evaluations);
+
         return ConstraintPoly::new(combined_poly);
     }
 
@@ -1,6 +1,6 @@
 use super::{
-    TraceState, SPONGE_WIDTH,
-    are_equal, is_zero, EvaluationResult,
+    field, TraceState, SPONGE_WIDTH, NUM_CALL_FLAG_CONSTRAINTS,
+    are_equal, is_zero, is_binary, binary_not, EvaluationResult,
     enforce_left_shift, enforce_right_shift, enforce_stack_copy
 };
 
@@ -164,12 +164,160 @@ pub fn enforce_void(result: &mut [u128], current: &TraceState, next: &TraceState
     enforce_stack_copy(loop_result, current.loop_stack(), next.loop_stack(), 0, op_flag);
 }
 
+/// Enforces constraints on the call flag and on the call stack which must hold for all
+/// operations; `begin_flag` and `tend_flag` are flags of BEGIN and TEND operations regardless
+/// of the value of the call flag.
+pub fn enforce_call_flag(result: &mut [u128], current: &TraceState, next: &TraceState, begin_flag: u128, tend_flag: u128)
+{
+    let call_flag = current.call_flag();
+    let dyn_flag = current.dyn_flag();
+    let call_flag_start = SPONGE_WIDTH + 1 + current.ctx_stack().len() + current.loop_stack().len();
+
+    // make sure call flags are binary, call flag is set only for BEGIN and TEND operations, and
+    // dynamic flag is set only for procedure calls; dynamic calls need at least two call stack
+    // slots, and thus, for shorter call stacks dynamic flag must be 0
+    result[call_flag_start] = is_binary(call_flag);
+    result[call_flag_start + 1] = is_binary(dyn_flag);
+    result[call_flag_start + 2] = field::mul(call_flag, binary_not(field::add(begin_flag, tend_flag)));
+    result[call_flag_start + 3] = if current.call_stack().len() > 1 {
+        field::mul(dyn_flag, binary_not(call_flag))
+    } else {
+        dyn_flag
+    };
+
+    // make sure call stack didn't change unless a procedure call was started or ended
+    let call_stack_start = call_flag_start + NUM_CALL_FLAG_CONSTRAINTS;
+    let call_result = &mut result[call_stack_start..call_stack_start + current.call_stack().len()];
+    enforce_stack_copy(call_result, current.call_stack(), next.call_stack(), 0, binary_not(call_flag));
+}
+
+pub fn enforce_call(result: &mut [u128], current: &TraceState, next: &TraceState, op_flag: u128)
+{
+    // make sure sponge state has been cleared
+    let new_sponge = next.sponge();
+    result.agg_constraint(0, op_flag, is_zero(new_sponge[0]));
+    result.agg_constraint(1, op_flag, is_zero(new_sponge[1]));
+    result.agg_constraint(2, op_flag, is_zero(new_sponge[2]));
+    result.agg_constraint(3, op_flag, is_zero(new_sponge[3]));
+
+    // make sure context stack didn't change
+    let ctx_stack_start = SPONGE_WIDTH + 1; // 1 is for loop image constraint
+    let ctx_stack_end = ctx_stack_start + current.ctx_stack().len();
+    let ctx_result = &mut result[ctx_stack_start..ctx_stack_end];
+    enforce_stack_copy(ctx_result, current.ctx_stack(), next.ctx_stack(), 0, op_flag);
+
+    // make sure loop stack didn't change
+    let loop_stack_end = ctx_stack_end + current.loop_stack().len();
+    let loop_result = &mut result[ctx_stack_end..loop_stack_end];
+    enforce_stack_copy(loop_result, current.loop_stack(), next.loop_stack(), 0, op_flag);
+
+    // make sure hash of the calling block was pushed onto the call stack
+    let parent_hash = current.sponge()[0];
+    let call_stack_start = loop_stack_end + NUM_CALL_FLAG_CONSTRAINTS;
+    let call_result = &mut result[call_stack_start..call_stack_start + current.call_stack().len()];
+    call_result.agg_constraint(0, op_flag, are_equal(parent_hash, next.call_stack()[0]));
+    enforce_right_shift(call_result, current.call_stack(), next.call_stack(), 1, op_flag);
+}
+
+pub fn enforce_ret(result: &mut [u128], current: &TraceState, next: &TraceState, op_flag: u128)
+{
+    let parent_hash = current.call_stack()[0];
+    let block_hash = current.sponge()[0];
+
+    let new_sponge = next.sponge();
+    result.agg_constraint(0, op_flag, are_equal(parent_hash, new_sponge[0]));
+    result.agg_constraint(1, op_flag, are_equal(block_hash, new_sponge[1]));
+    result.agg_constraint(2, op_flag, is_zero(new_sponge[2]));
+    result.agg_constraint(3, op_flag, is_zero(new_sponge[3]));
+
+    // make sure context stack didn't change
+    let ctx_stack_start = SPONGE_WIDTH + 1; // 1 is for loop image constraint
+    let ctx_stack_end = ctx_stack_start + current.ctx_stack().len();
+    let ctx_result = &mut result[ctx_stack_start..ctx_stack_end];
+    enforce_stack_copy(ctx_result, current.ctx_stack(), next.ctx_stack(), 0, op_flag);
+
+    // make sure loop stack didn't change
+    let loop_stack_end = ctx_stack_end + current.loop_stack().len();
+    let loop_result = &mut result[ctx_stack_end..loop_stack_end];
+    enforce_stack_copy(loop_result, current.loop_stack(), next.loop_stack(), 0, op_flag);
+
+    // make sure hash of the calling block was popped from the call stack
+    let call_stack_start = loop_stack_end + NUM_CALL_FLAG_CONSTRAINTS;
+    let call_result = &mut result[call_stack_start..call_stack_start + current.call_stack().len()];
+    enforce_left_shift(call_result, current.call_stack(), next.call_stack(), 1, 1, op_flag);
+}
+
+pub fn enforce_dyncall(result: &mut [u128], current: &TraceState, next: &TraceState, op_flag: u128)
+{
+    // make sure sponge state has been cleared
+    let new_sponge = next.sponge();
+    result.agg_constraint(0, op_flag, is_zero(new_sponge[0]));
+    result.agg_constraint(1, op_flag, is_zero(new_sponge[1]));
+    result.agg_constraint(2, op_flag, is_zero(new_sponge[2]));
+    result.agg_constraint(3, op_flag, is_zero(new_sponge[3]));
+
+    // make sure context stack didn't change
+    let ctx_stack_start = SPONGE_WIDTH + 1; // 1 is for loop image constraint
+    let ctx_stack_end = ctx_stack_start + current.ctx_stack().len();
+    let ctx_result = &mut result[ctx_stack_start..ctx_stack_end];
+    enforce_stack_copy(ctx_result, current.ctx_stack(), next.ctx_stack(), 0, op_flag);
+
+    // make sure loop stack didn't change
+    let loop_stack_end = ctx_stack_end + current.loop_stack().len();
+    let loop_result = &mut result[ctx_stack_end..loop_stack_end];
+    enforce_stack_copy(loop_result, current.loop_stack(), next.loop_stack(), 0, op_flag);
+
+    // make sure hash of the called procedure (taken from the top of the user stack) and hash of
+    // the calling block were pushed onto the call stack
+    let callee_hash = current.user_stack()[0];
+    let parent_hash = current.sponge()[0];
+    let call_stack_start = loop_stack_end + NUM_CALL_FLAG_CONSTRAINTS;
+    let call_result = &mut result[call_stack_start..call_stack_start + current.call_stack().len()];
+    call_result.agg_constraint(0, op_flag, are_equal(callee_hash, next.call_stack()[0]));
+    call_result.agg_constraint(1, op_flag, are_equal(parent_hash, next.call_stack()[1]));
+    enforce_right_shift(call_result, current.call_stack(), next.call_stack(), 2, op_flag);
+}
+
+pub fn enforce_dynret(result: &mut [u128], current: &TraceState, next: &TraceState, op_flag: u128)
+{
+    // hash of the called procedure is not merged into the hash of the calling block; thus, the
+    // sponge is set to the state left by a block with hash (0, 0)
+    let callee_hash = current.call_stack()[0];
+    let parent_hash = current.call_stack()[1];
+
+    let new_sponge = next.sponge();
+    result.agg_constraint(0, op_flag, are_equal(parent_hash, new_sponge[0]));
+    result.agg_constraint(1, op_flag, is_zero(new_sponge[1]));
+    result.agg_constraint(2, op_flag, is_zero(new_sponge[2]));
+    result.agg_constraint(3, op_flag, is_zero(new_sponge[3]));
+
+    // make sure context stack didn't change
+    let ctx_stack_start = SPONGE_WIDTH + 1; // 1 is for loop image constraint
+    let ctx_stack_end = ctx_stack_start + current.ctx_stack().len();
+    let ctx_result = &mut result[ctx_stack_start..ctx_stack_end];
+    enforce_stack_copy(ctx_result, current.ctx_stack(), next.ctx_stack(), 0, op_flag);
+
+    // make sure loop stack didn't change
+    let loop_stack_end = ctx_stack_end + current.loop_stack().len();
+    let loop_result = &mut result[ctx_stack_end..loop_stack_end];
+    enforce_stack_copy(loop_result, current.loop_stack(), next.loop_stack(), 0, op_flag);
+
+    // make sure the executed procedure is the one which was called
+    let call_flag_start = loop_stack_end;
+    result.agg_constraint(call_flag_start + 4, op_flag, are_equal(callee_hash, current.sponge()[0]));
+
+    // make sure both hashes were popped from the call stack
+    let call_stack_start = loop_stack_end + NUM_CALL_FLAG_CONSTRAINTS;
+    let call_result = &mut result[call_stack_start..call_stack_start + current.call_stack().len()];
+    enforce_left_shift(call_result, current.call_stack(), next.call_stack(), 2, 2, op_flag);
+}
+
 // TESTS
 // ================================================================================================
 #[cfg(test)]
 mod tests {
     
-    use crate::processor::opcodes::{ FlowOps, UserOps };
+    use crate::{ processor::opcodes::{ FlowOps, UserOps }, HASHER_WIDTH, AUX_WIDTH };
     use super::{ TraceState, are_equal };
 
     #[test]
@@ -437,6 +585,139 @@ mod tests {
         assert_eq!(vec![1, 1, 1, 1, 0, 1, 1], evaluations);
     }
 
+    #[test]
+    fn op_call() {
+
+        // correct transition, context depth = 1, call depth = 2
+        let state1 = new_call_state(15, FlowOps::Begin, &[3, 5, 7, 9], &[8], [1, 0], &[4, 0]);
+        let state2 = new_call_state(16, FlowOps::Void,  &[0, 0, 0, 0], &[8], [0, 0], &[3, 4]);
+
+        let mut evaluations = vec![0; 14];
+        super::enforce_call(&mut evaluations, &state1, &state2, 1);
+        assert_eq!(vec![0; 14], evaluations);
+
+        // incorrect transition (context stack changed, call stack not shifted)
+        let state1 = new_call_state(15, FlowOps::Begin, &[3, 5, 7, 9], &[8], [1, 0], &[4, 0]);
+        let state2 = new_call_state(16, FlowOps::Void,  &[1, 0, 0, 0], &[3], [0, 0], &[4, 0]);
+
+        let mut evaluations = vec![0; 14];
+        super::enforce_call(&mut evaluations, &state1, &state2, 1);
+        assert_eq!(vec![1, 0, 0, 0, 0, are_equal(8, 3), 0, 0, 0, 0, 0, 0, are_equal(3, 4), are_equal(4, 0)], evaluations);
+    }
+
+    #[test]
+    fn op_ret() {
+
+        // correct transition, context depth = 1, call depth = 2
+        let state1 = new_call_state(15, FlowOps::Tend, &[3, 5, 7, 9], &[8], [1, 0], &[6, 4]);
+        let state2 = new_call_state(16, FlowOps::Void, &[6, 3, 0, 0], &[8], [0, 0], &[4, 0]);
+
+        let mut evaluations = vec![0; 14];
+        super::enforce_ret(&mut evaluations, &state1, &state2, 1);
+        assert_eq!(vec![0; 14], evaluations);
+
+        // incorrect transition (sponge not restored, call stack not popped)
+        let state1 = new_call_state(15, FlowOps::Tend, &[3, 5, 7, 9], &[8], [1, 0], &[6, 4]);
+        let state2 = new_call_state(16, FlowOps::Void, &[8, 3, 0, 0], &[8], [0, 0], &[6, 4]);
+
+        let mut evaluations = vec![0; 14];
+        super::enforce_ret(&mut evaluations, &state1, &state2, 1);
+        assert_eq!(vec![are_equal(6, 8), 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, are_equal(4, 6), 4], evaluations);
+    }
+
+    #[test]
+    fn op_dyncall() {
+
+        // correct transition, context depth = 1, call depth = 3; callee hash is at the top of
+        // the user stack
+        let state1 = new_call_state(15, FlowOps::Begin, &[3, 5, 7, 9], &[8], [1, 1], &[4, 0, 0]);
+        let state2 = new_call_state(16, FlowOps::Void,  &[0, 0, 0, 0], &[8], [0, 0], &[101, 3, 4]);
+
+        let mut evaluations = vec![0; 15];
+        super::enforce_dyncall(&mut evaluations, &state1, &state2, 1);
+        assert_eq!(vec![0; 15], evaluations);
+
+        // incorrect transition (callee hash not saved, call stack not shifted)
+        let state2 = new_call_state(16, FlowOps::Void,  &[0, 0, 0, 0], &[8], [0, 0], &[3, 4, 0]);
+
+        let mut evaluations = vec![0; 15];
+        super::enforce_dyncall(&mut evaluations, &state1, &state2, 1);
+        assert_eq!(vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, are_equal(101, 3), are_equal(3, 4), are_equal(4, 0)], evaluations);
+    }
+
+    #[test]
+    fn op_dynret() {
+
+        // correct transition, context depth = 1, call depth = 3
+        let state1 = new_call_state(15, FlowOps::Tend, &[3, 5, 7, 9], &[8], [1, 1], &[3, 6, 4]);
+        let state2 = new_call_state(16, FlowOps::Void, &[6, 0, 0, 0], &[8], [0, 0], &[4, 0, 0]);
+
+        let mut evaluations = vec![0; 15];
+        super::enforce_dynret(&mut evaluations, &state1, &state2, 1);
+        assert_eq!(vec![0; 15], evaluations);
+
+        // incorrect transition (executed procedure does not match the called one, hash of the
+        // procedure merged into the parent hash)
+        let state1 = new_call_state(15, FlowOps::Tend, &[5, 5, 7, 9], &[8], [1, 1], &[3, 6, 4]);
+        let state2 = new_call_state(16, FlowOps::Void, &[6, 5, 0, 0], &[8], [0, 0], &[4, 0, 0]);
+
+        let mut evaluations = vec![0; 15];
+        super::enforce_dynret(&mut evaluations, &state1, &state2, 1);
+        assert_eq!(vec![0, 5, 0, 0, 0, 0, 0, 0, 0, 0, 0, are_equal(3, 5), 0, 0, 0], evaluations);
+    }
+
+    #[test]
+    fn call_flags() {
+
+        // call stack does not change when call flag is not set
+        let state1 = new_call_state(15, FlowOps::Void, &[3, 5, 7, 9], &[8], [0, 0], &[6, 4]);
+        let state2 = new_call_state(16, FlowOps::Void, &[3, 5, 7, 9], &[8], [0, 0], &[6, 4]);
+
+        let mut evaluations = vec![0; 14];
+        super::enforce_call_flag(&mut evaluations, &state1, &state2, 0, 0);
+        assert_eq!(vec![0; 14], evaluations);
+
+        let state2 = new_call_state(16, FlowOps::Void, &[3, 5, 7, 9], &[8], [0, 0], &[6, 5]);
+        let mut evaluations = vec![0; 14];
+        super::enforce_call_flag(&mut evaluations, &state1, &state2, 0, 0);
+        assert_eq!(vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, are_equal(4, 5)], evaluations);
+
+        // call flag can be set only for BEGIN and TEND operations
+        let state1 = new_call_state(15, FlowOps::Void, &[3, 5, 7, 9], &[8], [1, 0], &[6, 4]);
+        let mut evaluations = vec![0; 14];
+        super::enforce_call_flag(&mut evaluations, &state1, &state2, 0, 0);
+        assert_eq!(vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0], evaluations);
+
+        let mut evaluations = vec![0; 14];
+        super::enforce_call_flag(&mut evaluations, &state1, &state2, 1, 0);
+        assert_eq!(vec![0; 14], evaluations);
+
+        // dynamic flag can be set only together with call flag
+        let state1 = new_call_state(15, FlowOps::Begin, &[3, 5, 7, 9], &[8], [0, 1], &[6, 4]);
+        let mut evaluations = vec![0; 14];
+        super::enforce_call_flag(&mut evaluations, &state1, &state2, 1, 0);
+        assert_eq!(1, evaluations[10]);
+
+        // dynamic flag cannot be set when the call stack is too short for a dynamic call
+        let state1 = new_call_state(15, FlowOps::Begin, &[3, 5, 7, 9], &[8], [1, 1], &[6]);
+        let state2 = new_call_state(16, FlowOps::Void,  &[0, 0, 0, 0], &[8], [0, 0], &[6]);
+        let mut evaluations = vec![0; 13];
+        super::enforce_call_flag(&mut evaluations, &state1, &state2, 1, 0);
+        assert_eq!(1, evaluations[10]);
+
+        // call flags must be binary
+        let state1 = new_call_state(15, FlowOps::Begin, &[3, 5, 7, 9], &[8], [2, 0], &[6, 4]);
+        let state2 = new_call_state(16, FlowOps::Void,  &[0, 0, 0, 0], &[8], [0, 0], &[6, 4]);
+        let mut evaluations = vec![0; 14];
+        super::enforce_call_flag(&mut evaluations, &state1, &state2, 1, 0);
+        assert_eq!(2, evaluations[7]);
+
+        let state1 = new_call_state(15, FlowOps::Begin, &[3, 5, 7, 9], &[8], [1, 2], &[6, 4]);
+        let mut evaluations = vec![0; 14];
+        super::enforce_call_flag(&mut evaluations, &state1, &state2, 1, 0);
+        assert_eq!(2, evaluations[8]);
+    }
+
     // HELPER FUNCTIONS
     // --------------------------------------------------------------------------------------------
     fn new_state(step: usize, flow_op: FlowOps, sponge: &[u128; 4], ctx_stack: &[u128], loop_stack: &[u128]) -> TraceState 
@@ -457,7 +738,19 @@ mod tests {
         state.extend_from_slice(ctx_stack);
         state.extend_from_slice(loop_stack);
         state.push(101); // single value for user stack
+        state.resize(state.len() + HASHER_WIDTH + AUX_WIDTH, 0);
+
+        return TraceState::from_vec(ctx_depth, loop_depth, 0, 1, &state);
+    }
+
+    fn new_call_state(step: usize, flow_op: FlowOps, sponge: &[u128; 4], ctx_stack: &[u128], call_flags: [u128; 2], call_stack: &[u128]) -> TraceState
+    {
+        let mut state = new_state(step, flow_op, sponge, ctx_stack, &[]).to_vec();
+        let call_start = state.len() - 1 - HASHER_WIDTH - AUX_WIDTH;
+        for (i, &value) in call_flags.iter().chain(call_stack.iter()).enumerate() {
+            state.insert(call_start + i, value);
+        }
 
-        return TraceState::from_vec(ctx_depth, loop_depth, 1, &state);
+        return TraceState::from_vec(ctx_stack.len(), 0, call_stack.len(), 1, &state);
     }
 }
\ No newline at end of file
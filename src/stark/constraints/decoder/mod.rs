@@ -20,6 +20,7 @@ mod flow_ops;
 use flow_ops::{
     enforce_begin, enforce_tend, enforce_fend, enforce_void,
     enforce_loop, enforce_wrap, enforce_break,
+    enforce_call_flag, enforce_call, enforce_ret, enforce_dyncall, enforce_dynret,
 };
 
 #[cfg(test)]
@@ -27,7 +28,7 @@ mod tests;
 
 // CONSTANTS
 // ================================================================================================
-const NUM_OP_CONSTRAINTS: usize = 15;
+const NUM_OP_CONSTRAINTS: usize = 16;
 const OP_CONSTRAINT_DEGREES: [usize; NUM_OP_CONSTRAINTS] = [
     2, 2, 2, 2, 2, 2, 2, 2, 2, 2,   // all op bits are binary
     3,                              // op_counter should be incremented for HACC operations
@@ -35,6 +36,7 @@ const OP_CONSTRAINT_DEGREES: [usize; NUM_OP_CONSTRAINTS] = [
     8,                              // when cf_ops are not all 0s, ld_ops and hd_ops must be all 1s
     6,                              // VOID can be followed only by VOID
     4,                              // operations happen on allowed step multiples
+    7,                              // PUSH sequences start on multiples of 8
 ];
 
 const NUM_SPONGE_CONSTRAINTS: usize = 4;
@@ -45,9 +47,25 @@ const SPONGE_CONSTRAINT_DEGREES: [usize; NUM_SPONGE_CONSTRAINTS] = [
 const LOOP_IMAGE_CONSTRAINT_DEGREE: usize = 4;
 const STACK_CONSTRAINT_DEGREE: usize = 4;
 
+pub const NUM_CALL_FLAG_CONSTRAINTS: usize = 5;
+const CALL_FLAG_CONSTRAINT_DEGREES: [usize; NUM_CALL_FLAG_CONSTRAINTS] = [
+    2,                              // call flag is binary
+    2,                              // dynamic flag is binary
+    4,                              // call flag can be set only for BEGIN and TEND
+    2,                              // dynamic flag can be set only together with call flag
+    6,                              // dynamic call returns from the procedure it called
+];
+
+// when a trace contains procedure calls, flags of BEGIN and TEND operations are multiplied
+// by the call flag and by the dynamic flag, and this increases the degree of stack
+// constraints by 2
+const CALL_STACK_CONSTRAINT_DEGREE: usize = 6;
+
 const CYCLE_MASK_IDX : usize = 0;
 const PREFIX_MASK_IDX: usize = 1;
 const PUSH_MASK_IDX  : usize = 2;
+const PUSH_SEQ_MASK_IDX: usize = 3;
+const NUM_MASKS      : usize = 4;
 
 pub const NUM_STATIC_DECODER_CONSTRAINTS: usize =
     NUM_OP_CONSTRAINTS
@@ -56,14 +74,16 @@ pub const NUM_STATIC_DECODER_CONSTRAINTS: usize =
 
 // TYPES AND INTERFACES
 // ================================================================================================
+#[derive(Clone)]
 pub struct Decoder {
     ctx_depth           : usize,
     loop_depth          : usize,
+    call_depth          : usize,
     trace_length        : usize,
     cycle_length        : usize,
     ark_values          : Vec<[u128; 2 * SPONGE_WIDTH]>,
     ark_polys           : Vec<Vec<u128>>,
-    mask_values         : Vec<[u128; 3]>,
+    mask_values         : Vec<[u128; NUM_MASKS]>,
     mask_polys          : Vec<Vec<u128>>,
     constraint_degrees  : Vec<usize>,
 }
@@ -72,30 +92,39 @@ pub struct Decoder {
 // ================================================================================================
 impl Decoder {
 
-    pub fn new(trace_length: usize, extension_factor: usize, ctx_depth: usize, loop_depth: usize) -> Decoder 
+    pub fn new(trace_length: usize, extension_factor: usize, domain_offset: u128, ctx_depth: usize, loop_depth: usize, call_depth: usize) -> Decoder 
     {
         // build an array of constraint degrees for the decoder
         let mut degrees = Vec::from(&OP_CONSTRAINT_DEGREES[..]);
         degrees.extend_from_slice(&SPONGE_CONSTRAINT_DEGREES[..]);
         degrees.push(LOOP_IMAGE_CONSTRAINT_DEGREE);
+
+        // call flag and call stack constraints are present only when the call stack is not empty
+        let stack_degree = if call_depth > 0 { CALL_STACK_CONSTRAINT_DEGREE } else { STACK_CONSTRAINT_DEGREE };
         degrees.resize(degrees.len()
             + cmp::max(ctx_depth, MIN_CONTEXT_DEPTH)
             + cmp::max(loop_depth, MIN_LOOP_DEPTH),
-            STACK_CONSTRAINT_DEGREE);
+            stack_degree);
+        if call_depth > 0 {
+            degrees.extend_from_slice(&CALL_FLAG_CONSTRAINT_DEGREES[..]);
+            degrees.resize(degrees.len() + call_depth, CALL_STACK_CONSTRAINT_DEGREE);
+        }
 
-        // determine extended cycle length
+        // determine extended cycle length; constants at x are evaluated at x^num_cycles, and so,
+        // the cycle domain is shifted by domain_offset^num_cycles
         let cycle_length = BASE_CYCLE_LENGTH * extension_factor;
+        let cycle_offset = field::exp(domain_offset, (trace_length / BASE_CYCLE_LENGTH) as u128);
 
         // extend rounds constants by the specified extension factor
-        let (ark_polys, ark_evaluations) = extend_constants(&ARK, extension_factor);
+        let (ark_polys, ark_evaluations) = extend_constants(&ARK, extension_factor, cycle_offset);
         let ark_values = transpose_ark_constants(ark_evaluations, cycle_length);
 
         // extend mask constants by the specified extension factor
-        let (mask_polys, mask_evaluations) = extend_constants(&MASKS, extension_factor);
+        let (mask_polys, mask_evaluations) = extend_constants(&MASKS, extension_factor, cycle_offset);
         let mask_values = transpose_mask_constants(mask_evaluations, cycle_length);
 
         return Decoder {
-            ctx_depth, loop_depth,
+            ctx_depth, loop_depth, call_depth,
             trace_length, cycle_length,
             ark_values, ark_polys,
             mask_values, mask_polys,
@@ -113,6 +142,11 @@ impl Decoder {
         return self.loop_depth;
     }
 
+    #[cfg(test)]
+    pub fn call_depth(&self) -> usize {
+        return self.call_depth;
+    }
+
     pub fn constraint_count(&self) -> usize {
         return self.constraint_degrees.len();
     }
@@ -136,17 +170,7 @@ impl Decoder {
         enforce_op_bits(&mut result[..NUM_OP_CONSTRAINTS], current, next, &masks);
 
         // evaluate constraints for flow control operations
-        let result = &mut result[NUM_OP_CONSTRAINTS..];
-        let op_flags = current.cf_op_flags();
-
-        enforce_hacc (result, current, next, &ark, op_flags[FlowOps::Hacc.op_index() ]);
-        enforce_begin(result, current, next,       op_flags[FlowOps::Begin.op_index()]);
-        enforce_tend (result, current, next,       op_flags[FlowOps::Tend.op_index() ]);
-        enforce_fend (result, current, next,       op_flags[FlowOps::Fend.op_index() ]);
-        enforce_loop (result, current, next,       op_flags[FlowOps::Loop.op_index() ]);
-        enforce_wrap (result, current, next,       op_flags[FlowOps::Wrap.op_index() ]);
-        enforce_break(result, current, next,       op_flags[FlowOps::Break.op_index()]);
-        enforce_void (result, current, next,       op_flags[FlowOps::Void.op_index() ]);
+        self.enforce_flow_ops(&mut result[NUM_OP_CONSTRAINTS..], current, next, &ark);
     }
 
     /// Evaluates decoder transition constraints at the specified x coordinate and saves the
@@ -165,7 +189,7 @@ impl Decoder {
         }
 
         // determine mask constants at the specified x coordinate
-        let mut masks = [field::ZERO; 3];
+        let mut masks = [field::ZERO; NUM_MASKS];
         for i in 0..masks.len() {
             masks[i] = polynom::eval(&self.mask_polys[i], x);
         }
@@ -174,17 +198,45 @@ impl Decoder {
         enforce_op_bits(&mut result[..NUM_OP_CONSTRAINTS], current, next, &masks);
 
         // evaluate constraints for flow control operations
-        let result = &mut result[NUM_OP_CONSTRAINTS..];
+        self.enforce_flow_ops(&mut result[NUM_OP_CONSTRAINTS..], current, next, &ark);
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+    fn enforce_flow_ops(&self, result: &mut [u128], current: &TraceState, next: &TraceState, ark: &[u128; 2 * SPONGE_WIDTH])
+    {
         let op_flags = current.cf_op_flags();
 
-        enforce_hacc (result, current, next, &ark, op_flags[FlowOps::Hacc as usize]);
-        enforce_begin(result, current, next, op_flags[FlowOps::Begin as usize]);
-        enforce_tend (result, current, next, op_flags[FlowOps::Tend as usize]);
-        enforce_fend (result, current, next, op_flags[FlowOps::Fend as usize]);
-        enforce_loop (result, current, next, op_flags[FlowOps::Loop as usize]);
-        enforce_wrap (result, current, next, op_flags[FlowOps::Wrap as usize]);
-        enforce_break(result, current, next, op_flags[FlowOps::Break as usize]);
-        enforce_void (result, current, next, op_flags[FlowOps::Void as usize]);
+        // BEGIN and TEND operations start and end procedure calls when the call flag is set
+        let call_flag = current.call_flag();
+        let begin_flag = op_flags[FlowOps::Begin.op_index()];
+        let tend_flag = op_flags[FlowOps::Tend.op_index()];
+
+        enforce_hacc (result, current, next, ark, op_flags[FlowOps::Hacc.op_index() ]);
+        enforce_begin(result, current, next,      field::mul(begin_flag, binary_not(call_flag)));
+        enforce_tend (result, current, next,      field::mul(tend_flag, binary_not(call_flag)));
+        enforce_fend (result, current, next,      op_flags[FlowOps::Fend.op_index() ]);
+        enforce_loop (result, current, next,      op_flags[FlowOps::Loop.op_index() ]);
+        enforce_wrap (result, current, next,      op_flags[FlowOps::Wrap.op_index() ]);
+        enforce_break(result, current, next,      op_flags[FlowOps::Break.op_index()]);
+        enforce_void (result, current, next,      op_flags[FlowOps::Void.op_index() ]);
+
+        // calls are dynamic when the dynamic flag is set; dynamic calls use two call stack slots
+        // and the dynamic flag is forced to 0 when the call stack is shorter than that
+        let dyn_flag = current.dyn_flag();
+        let begin_call = field::mul(begin_flag, call_flag);
+        let tend_call = field::mul(tend_flag, call_flag);
+
+        if self.call_depth > 0 {
+            enforce_call_flag(result, current, next, begin_flag, tend_flag);
+            enforce_call(result, current, next, field::mul(begin_call, binary_not(dyn_flag)));
+            enforce_ret (result, current, next, field::mul(tend_call, binary_not(dyn_flag)));
+        }
+
+        if self.call_depth > 1 {
+            enforce_dyncall(result, current, next, field::mul(begin_call, dyn_flag));
+            enforce_dynret (result, current, next, field::mul(tend_call, dyn_flag));
+        }
     }
 }
 
@@ -202,12 +254,12 @@ fn transpose_ark_constants(constants: Vec<Vec<u128>>, cycle_length: usize) -> Ve
     return values;
 }
 
-fn transpose_mask_constants(constants: Vec<Vec<u128>>, cycle_length: usize) -> Vec<[u128; 3]>
+fn transpose_mask_constants(constants: Vec<Vec<u128>>, cycle_length: usize) -> Vec<[u128; NUM_MASKS]>
 {
     let mut values = Vec::new();
     for i in 0..cycle_length {
-        values.push([field::ZERO; 3]);
-        for j in 0..3 {
+        values.push([field::ZERO; NUM_MASKS]);
+        for j in 0..NUM_MASKS {
             values[i][j] = constants[j][i];
         }
     }
@@ -216,8 +268,9 @@ fn transpose_mask_constants(constants: Vec<Vec<u128>>, cycle_length: usize) -> V
 
 // CYCLE MASKS
 // ================================================================================================
-const MASKS: [[u128; BASE_CYCLE_LENGTH]; 3] = [
+const MASKS: [[u128; BASE_CYCLE_LENGTH]; NUM_MASKS] = [
     [0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],   // multiples of 16
     [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0],   // one less than multiple of 16
-    [0, 1, 1, 1, 1, 1, 1, 1, 0, 1, 1, 1, 1, 1, 1, 1],   // multiples of 8
+    [0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0, 1, 1, 1, 1],   // first 4 steps after multiples of 8
+    [1, 1, 1, 0, 0, 0, 0, 0, 1, 1, 1, 0, 0, 0, 0, 0],   // first 3 steps after multiples of 8
 ];
\ No newline at end of file
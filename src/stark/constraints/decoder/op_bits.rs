@@ -1,13 +1,13 @@
 use super::{
     field::{ self, mul, add },
     TraceState, FlowOps, UserOps, is_binary, binary_not, are_equal, EvaluationResult,
-    CYCLE_MASK_IDX, PREFIX_MASK_IDX, PUSH_MASK_IDX,
+    CYCLE_MASK_IDX, PREFIX_MASK_IDX, PUSH_MASK_IDX, PUSH_SEQ_MASK_IDX, NUM_MASKS,
 };
 
 // CONSTRAINT EVALUATOR
 // ================================================================================================
 
-pub fn enforce_op_bits(result: &mut [u128], current: &TraceState, next: &TraceState, masks: &[u128; 3])
+pub fn enforce_op_bits(result: &mut [u128], current: &TraceState, next: &TraceState, masks: &[u128; NUM_MASKS])
 {
     let mut i = 0;
 
@@ -73,9 +73,17 @@ pub fn enforce_op_bits(result: &mut [u128], current: &TraceState, next: &TraceSt
     result.agg_constraint(i, cf_op_flags[FlowOps::Tend.op_index()], base_cycle_mask);
     result.agg_constraint(i, cf_op_flags[FlowOps::Fend.op_index()], base_cycle_mask);
 
-    // PUSH is allowed only on multiples of 8
+    // PUSH is allowed only on the first 4 steps after a multiple of 8
+    let push_flag = hd_op_flags[UserOps::Push.hd_index()];
     let push_cycle_mask = masks[PUSH_MASK_IDX];
-    result.agg_constraint(i, hd_op_flags[UserOps::Push.hd_index()], push_cycle_mask);
+    result.agg_constraint(i, push_flag, push_cycle_mask);
+    i += 1;
+
+    // PUSH which is not on a multiple of 8 must be preceded by another PUSH; the mask is
+    // applied to the current step, so it selects steps right before such PUSH operations
+    let next_push_flag = next.hd_op_flags()[UserOps::Push.hd_index()];
+    let push_seq_mask = masks[PUSH_SEQ_MASK_IDX];
+    result[i] = mul(push_seq_mask, mul(next_push_flag, binary_not(push_flag)));
 }
 
 // TESTS
@@ -92,7 +100,7 @@ mod tests {
 
         // all bits are 1s: success
         let state = new_state(FlowOps::Void as u8, UserOps::Noop as u8, 1);
-        assert_eq!(success_result, evaluate_state(&state, [0, 0, 0], false));
+        assert_eq!(success_result, evaluate_state(&state, [0, 0, 0, 0], false));
 
         // control flow bits are not binary
         for i in 0..3 {
@@ -102,7 +110,7 @@ mod tests {
             expected_evaluations[i] = 3 * 3 - 3;
 
             let state = new_state_from_bits(op_bits, [1, 1, 1, 1, 1, 1, 1]);
-            assert_eq!(expected_evaluations, &evaluate_state(&state, [0, 0, 0], false)[..10]);
+            assert_eq!(expected_evaluations, &evaluate_state(&state, [0, 0, 0, 0], false)[..10]);
         }
 
         // user bits are not binary
@@ -113,7 +121,7 @@ mod tests {
             expected_evaluations[i + 3] = 3 * 3 - 3;
 
             let state = new_state_from_bits([0, 0, 0], op_bits);
-            assert_eq!(expected_evaluations, &evaluate_state(&state, [0, 0, 0], false)[..10]);
+            assert_eq!(expected_evaluations, &evaluate_state(&state, [0, 0, 0, 0], false)[..10]);
         }
     }
 
@@ -125,18 +133,18 @@ mod tests {
         // user op bits cannot be all 0s
         for cf_op in 0..8 {
             let state = new_state(cf_op, 0, 1);
-            assert_ne!(success_result, evaluate_state(&state, [0, 0, 0], false));
+            assert_ne!(success_result, evaluate_state(&state, [0, 0, 0, 0], false));
         }
 
         // when cf_ops are not all 0s, user_ops must be all 1s
         for cf_op in 1..8 {
             for user_op in 0..127 {
                 let state = new_state(cf_op as u8, user_op as u8, 1);
-                assert_ne!(success_result, evaluate_state(&state, [0, 0, 0], false));
+                assert_ne!(success_result, evaluate_state(&state, [0, 0, 0, 0], false));
             }
 
             let state = new_state(cf_op as u8, UserOps::Noop as u8, 1);
-            assert_eq!(success_result, evaluate_state(&state, [0, 0, 0], false));
+            assert_eq!(success_result, evaluate_state(&state, [0, 0, 0, 0], false));
         }
     }
 
@@ -147,34 +155,57 @@ mod tests {
         
         // TEND and FEND are allowed only on multiples of 16
         let state = new_state(FlowOps::Tend as u8, UserOps::Noop as u8, 1);
-        assert_eq!(success_result, evaluate_state(&state, [0, 0, 0], false));
-        assert_ne!(success_result, evaluate_state(&state, [1, 0, 0], false));
+        assert_eq!(success_result, evaluate_state(&state, [0, 0, 0, 0], false));
+        assert_ne!(success_result, evaluate_state(&state, [1, 0, 0, 0], false));
 
         let state = new_state(FlowOps::Fend as u8, UserOps::Noop as u8, 1);
-        assert_eq!(success_result, evaluate_state(&state, [0, 0, 0], false));
-        assert_ne!(success_result, evaluate_state(&state, [1, 0, 0], false));
+        assert_eq!(success_result, evaluate_state(&state, [0, 0, 0, 0], false));
+        assert_ne!(success_result, evaluate_state(&state, [1, 0, 0, 0], false));
 
         // BEGIN, LOOP, WRAP, and BREAK are allowed only on one less than multiples of 16
         let state = new_state(FlowOps::Begin as u8, UserOps::Noop as u8, 1);
-        assert_eq!(success_result, evaluate_state(&state, [0, 0, 0], false));
-        assert_ne!(success_result, evaluate_state(&state, [0, 1, 0], false));
+        assert_eq!(success_result, evaluate_state(&state, [0, 0, 0, 0], false));
+        assert_ne!(success_result, evaluate_state(&state, [0, 1, 0, 0], false));
 
         let state = new_state(FlowOps::Loop as u8, UserOps::Noop as u8, 1);
-        assert_eq!(success_result, evaluate_state(&state, [0, 0, 0], false));
-        assert_ne!(success_result, evaluate_state(&state, [0, 1, 0], false));
+        assert_eq!(success_result, evaluate_state(&state, [0, 0, 0, 0], false));
+        assert_ne!(success_result, evaluate_state(&state, [0, 1, 0, 0], false));
 
         let state = new_state(FlowOps::Wrap as u8, UserOps::Noop as u8, 1);
-        assert_eq!(success_result, evaluate_state(&state, [0, 0, 0], false));
-        assert_ne!(success_result, evaluate_state(&state, [0, 1, 0], false));
+        assert_eq!(success_result, evaluate_state(&state, [0, 0, 0, 0], false));
+        assert_ne!(success_result, evaluate_state(&state, [0, 1, 0, 0], false));
 
         let state = new_state(FlowOps::Break as u8, UserOps::Noop as u8, 1);
-        assert_eq!(success_result, evaluate_state(&state, [0, 0, 0], false));
-        assert_ne!(success_result, evaluate_state(&state, [0, 1, 0], false));
+        assert_eq!(success_result, evaluate_state(&state, [0, 0, 0, 0], false));
+        assert_ne!(success_result, evaluate_state(&state, [0, 1, 0, 0], false));
 
-        // PUSH is allowed only on multiples of 8
+        // PUSH is allowed only on the first 4 steps after multiples of 8
         let state = new_state(FlowOps::Hacc as u8, UserOps::Push as u8, 1);
-        assert_eq!(success_result, evaluate_state(&state, [0, 0, 0], true));
-        assert_ne!(success_result, evaluate_state(&state, [0, 0, 1], true));
+        assert_eq!(success_result, evaluate_state(&state, [0, 0, 0, 0], true));
+        assert_ne!(success_result, evaluate_state(&state, [0, 0, 1, 0], true));
+    }
+
+    #[test]
+    fn invalid_push_sequence() {
+        let success_result = vec![0; NUM_OP_CONSTRAINTS];
+        let push_state = new_state(FlowOps::Hacc as u8, UserOps::Push as u8, 2);
+
+        // PUSH can follow PUSH
+        let state1 = new_state(FlowOps::Hacc as u8, UserOps::Push as u8, 1);
+        let mut evaluations = vec![0; NUM_OP_CONSTRAINTS];
+        super::enforce_op_bits(&mut evaluations, &state1, &push_state, &[0, 0, 0, 1]);
+        assert_eq!(success_result, evaluations);
+
+        // PUSH can follow any operation on a multiple of 8
+        let state1 = new_state(FlowOps::Hacc as u8, UserOps::Add as u8, 1);
+        let mut evaluations = vec![0; NUM_OP_CONSTRAINTS];
+        super::enforce_op_bits(&mut evaluations, &state1, &push_state, &[0, 0, 0, 0]);
+        assert_eq!(success_result, evaluations);
+
+        // PUSH cannot follow other operations on other steps
+        let mut evaluations = vec![0; NUM_OP_CONSTRAINTS];
+        super::enforce_op_bits(&mut evaluations, &state1, &push_state, &[0, 0, 0, 1]);
+        assert_ne!(success_result, evaluations);
     }
 
     #[test]
@@ -185,28 +216,28 @@ mod tests {
         let state1 = new_state(FlowOps::Hacc as u8, UserOps::Add as u8, 1);
         let state2 = new_state(FlowOps::Void as u8, UserOps::Noop as u8, 2);
         let mut evaluations = vec![0; NUM_OP_CONSTRAINTS];
-        super::enforce_op_bits(&mut evaluations, &state1, &state2, &[0, 0, 0]);
+        super::enforce_op_bits(&mut evaluations, &state1, &state2, &[0, 0, 0, 0]);
         assert_eq!(success_result, evaluations);
 
         // void can follow void
         let state1 = new_state(FlowOps::Void as u8, UserOps::Noop as u8, 1);
         let state2 = new_state(FlowOps::Void as u8, UserOps::Noop as u8, 1);
         let mut evaluations = vec![0; NUM_OP_CONSTRAINTS];
-        super::enforce_op_bits(&mut evaluations, &state1, &state2, &[0, 0, 0]);
+        super::enforce_op_bits(&mut evaluations, &state1, &state2, &[0, 0, 0, 0]);
         assert_eq!(success_result, evaluations);
 
         // non-void cannot follow void
         let state1 = new_state(FlowOps::Void as u8, UserOps::Noop as u8, 1);
         let state2 = new_state(FlowOps::Hacc as u8, UserOps::Add as u8, 1);
         let mut evaluations = vec![0; NUM_OP_CONSTRAINTS];
-        super::enforce_op_bits(&mut evaluations, &state1, &state2, &[0, 0, 0]);
+        super::enforce_op_bits(&mut evaluations, &state1, &state2, &[0, 0, 0, 0]);
         assert_ne!(success_result, evaluations);
     }
 
     // HELPER FUNCTIONS
     // --------------------------------------------------------------------------------------------
     fn new_state(flow_op: u8, user_op: u8, op_counter: u128) -> TraceState {
-        let mut state = TraceState::new(1, 0, 1);
+        let mut state = TraceState::new(1, 0, 0, 1);
     
         let mut op_bits = [0; 10];
         for i in 0..3 {
@@ -223,7 +254,7 @@ mod tests {
     }
 
     fn new_state_from_bits(cf_bits: [u128; 3], u_bits: [u128; 7]) -> TraceState {
-        let mut state = TraceState::new(1, 0, 1);
+        let mut state = TraceState::new(1, 0, 0, 1);
         state.set_op_bits([
             cf_bits[0], cf_bits[1], cf_bits[2],
             u_bits[0], u_bits[1], u_bits[2], u_bits[3], u_bits[4], u_bits[5], u_bits[6]
@@ -231,7 +262,7 @@ mod tests {
         return state;
     }
 
-    fn evaluate_state(state: &TraceState, masks: [u128; 3], inc_counter: bool) -> Vec<u128> {
+    fn evaluate_state(state: &TraceState, masks: [u128; 4], inc_counter: bool) -> Vec<u128> {
         let op_counter = if inc_counter { state.op_counter() + 1 } else { state.op_counter() };
         let next_state = new_state(FlowOps::Void as u8, UserOps::Noop as u8, op_counter);
         let mut evaluations = vec![0; NUM_OP_CONSTRAINTS];
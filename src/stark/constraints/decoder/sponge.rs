@@ -2,7 +2,7 @@ use crate::{
     math::field::{ self, add, sub, mul },
     utils::sponge::{ apply_sbox, apply_mds, apply_inv_mds },
 };
-use super::{ TraceState, UserOps, are_equal, EvaluationResult, SPONGE_WIDTH };
+use super::{ TraceState, UserOps, are_equal, enforce_stack_copy, EvaluationResult, SPONGE_WIDTH };
 
 // CONSTRAINT EVALUATOR
 // ================================================================================================
@@ -40,6 +40,15 @@ pub fn enforce_hacc(result: &mut [u128], current: &TraceState, next: &TraceState
     for i in 0..SPONGE_WIDTH {
         result.agg_constraint(i, op_flag, are_equal(old_sponge[i], new_sponge[i]));
     }
+
+    // make sure context and loop stacks didn't change
+    let ctx_stack_start = SPONGE_WIDTH + 1; // 1 is for loop image constraint
+    let ctx_stack_end = ctx_stack_start + current.ctx_stack().len();
+    let ctx_result = &mut result[ctx_stack_start..ctx_stack_end];
+    enforce_stack_copy(ctx_result, current.ctx_stack(), next.ctx_stack(), 0, op_flag);
+
+    let loop_result = &mut result[ctx_stack_end..ctx_stack_end + current.loop_stack().len()];
+    enforce_stack_copy(loop_result, current.loop_stack(), next.loop_stack(), 0, op_flag);
 }
 
 // TESTS
@@ -59,59 +68,59 @@ mod tests {
 
         // correct transition, push.7
         let push_value = 7;
-        let state1 = TraceState::from_vec(1, 0, 1, &vec![0,  1, 2, 3, 4,  0, 0, 0,  1, 1, 1, 1, 1,  0, 0,  0,  0]);
+        let state1 = TraceState::from_vec(1, 0, 0, 1, &vec![0,  1, 2, 3, 4,  0, 0, 0,  1, 1, 1, 1, 1,  0, 0,  0,  0,  0, 0, 0, 0, 0, 0, 0,  0, 0, 0, 0, 0, 0,  0, 0]);
 
         let mut sponge = [1, 2, 3, 4];
         apply_hacc_round(&mut sponge, state1.op_code(), push_value, 0);
         
         let state2 = build_state(&sponge, push_value);
 
-        let mut evaluations = vec![0; 4];
+        let mut evaluations = vec![0; 7];
         super::enforce_hacc(&mut evaluations, &state1, &state2, &ark[0], 1);
-        assert_eq!(vec![0, 0, 0, 0], evaluations);
+        assert_eq!(vec![0, 0, 0, 0, 0, 0, 0], evaluations);
 
         // correct transition, non-push op
-        let state1 = TraceState::from_vec(1, 0, 1, &vec![0,  1, 2, 3, 4,  0, 0, 0,  0, 0, 0, 0, 0,  1, 1,  0,  0]);
+        let state1 = TraceState::from_vec(1, 0, 0, 1, &vec![0,  1, 2, 3, 4,  0, 0, 0,  0, 0, 0, 0, 0,  1, 1,  0,  0,  0, 0, 0, 0, 0, 0, 0,  0, 0, 0, 0, 0, 0,  0, 0]);
 
         let mut sponge = [1, 2, 3, 4];
         apply_hacc_round(&mut sponge, state1.op_code(), 0, 0);
 
         let state2 = build_state(&sponge, 9);
-        let mut evaluations = vec![0; 4];
+        let mut evaluations = vec![0; 7];
         super::enforce_hacc(&mut evaluations, &state1, &state2, &ark[0], 1);
-        assert_eq!(vec![0, 0, 0, 0], evaluations);
+        assert_eq!(vec![0, 0, 0, 0, 0, 0, 0], evaluations);
 
         // incorrect transition, push.7
         let push_value = 7;
-        let state1 = TraceState::from_vec(1, 0, 1, &vec![0,  1, 2, 3, 4,  0, 0, 0,  1, 1, 1, 1, 1,  0, 0,  0,  0]);
+        let state1 = TraceState::from_vec(1, 0, 0, 1, &vec![0,  1, 2, 3, 4,  0, 0, 0,  1, 1, 1, 1, 1,  0, 0,  0,  0,  0, 0, 0, 0, 0, 0, 0,  0, 0, 0, 0, 0, 0,  0, 0]);
 
         let mut sponge = [1, 2, 3, 4];
         apply_hacc_round(&mut sponge, state1.op_code(), push_value, 0);
         
         let state2 = build_state(&sponge, push_value - 1);
 
-        let mut evaluations = vec![0; 4];
+        let mut evaluations = vec![0; 7];
         super::enforce_hacc(&mut evaluations, &state1, &state2, &ark[0], 1);
-        assert_eq!(vec![0, 340282366920938463463374557953744961536, 0, 0], evaluations);
+        assert_eq!(vec![0, 340282366920938463463374557953744961536, 0, 0, 0, 0, 0], evaluations);
 
         // incorrect transition, non-push op
-        let state1 = TraceState::from_vec(1, 0, 1, &vec![0,  1, 2, 3, 4,  0, 0, 0,  0, 0, 0, 0, 0,  1, 1,  0,  0]);
+        let state1 = TraceState::from_vec(1, 0, 0, 1, &vec![0,  1, 2, 3, 4,  0, 0, 0,  0, 0, 0, 0, 0,  1, 1,  0,  0,  0, 0, 0, 0, 0, 0, 0,  0, 0, 0, 0, 0, 0,  0, 0]);
 
         let mut sponge = [1, 2, 3, 4];
         apply_hacc_round(&mut sponge, state1.op_code(), 9, 0);
 
         let state2 = build_state(&sponge, 9);
-        let mut evaluations = vec![0; 4];
+        let mut evaluations = vec![0; 7];
         super::enforce_hacc(&mut evaluations, &state1, &state2, &ark[0], 1);
-        assert_eq!(vec![0, 340282366920938463463374557953744961528, 0, 0], evaluations);
+        assert_eq!(vec![0, 340282366920938463463374557953744961528, 0, 0, 0, 0, 0], evaluations);
     }
 
     // HELPER FUNCTIONS
     // --------------------------------------------------------------------------------------------
     fn build_state(sponge: &[u128; SPONGE_WIDTH], push_value: u128) -> TraceState {
         let state = vec![
-            0, sponge[0], sponge[1], sponge[2], sponge[3],  1, 1, 1,  1, 1, 1, 1, 1,  1, 1,  0,  push_value
+            0, sponge[0], sponge[1], sponge[2], sponge[3],  1, 1, 1,  1, 1, 1, 1, 1,  1, 1,  0,  push_value,  0, 0, 0, 0, 0, 0, 0,  0, 0, 0, 0, 0, 0,  0, 0
         ];
-        return TraceState::from_vec(1, 0, 1, &state);
+        return TraceState::from_vec(1, 0, 0, 1, &state);
     }
 }
\ No newline at end of file
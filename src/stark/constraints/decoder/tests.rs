@@ -1,4 +1,5 @@
 use crate::utils::sponge::{ apply_round as apply_hacc_round };
+use crate::{ math::field, HASHER_WIDTH, AUX_WIDTH };
 use super::{ Decoder, TraceState, UserOps };
 
 // CONSTANTS
@@ -11,7 +12,7 @@ const EXTENSION_FACTOR: usize = 8;
 #[test]
 fn enforce_begin() {
 
-    let decoder = new_decoder(1, 0);
+    let decoder = new_decoder(1, 0, 0);
     let step = 15 * EXTENSION_FACTOR;
     let success_result = vec![0; decoder.constraint_degrees().len()];
     
@@ -50,7 +51,7 @@ fn enforce_begin() {
 // ================================================================================================
 #[test]
 fn enforce_hacc() {
-    let decoder = new_decoder(1, 0);
+    let decoder = new_decoder(1, 0, 0);
     let success_result = vec![0; decoder.constraint_degrees().len()];
 
     // correct transition, push.9, step = 0
@@ -98,18 +99,29 @@ fn enforce_hacc() {
     apply_hacc_round(&mut state2[1..5], UserOps::Add as u128, 9, 0);
     let evaluations = evaluate_transition(&decoder, 0, state1, state2);
     assert_ne!(success_result, evaluations);
+
+    // incorrect transition (context stack changed), add, step = 0
+    let state1     = vec![1,  3, 5, 7, 9,  0, 0, 0,  0, 0, 0, 1, 0,  1, 1,  0,  0];
+    let mut state2 = vec![2,  3, 5, 7, 9,  0, 0, 0,  1, 1, 1, 1, 1,  1, 1,  5,  0];
+    apply_hacc_round(&mut state2[1..5], UserOps::Add as u128, 0, 0);
+    let evaluations = evaluate_transition(&decoder, 0, state1, state2);
+    assert_ne!(success_result, evaluations);
 }
 
 // HELPER FUNCTIONS
 // ================================================================================================
-fn new_decoder(ctx_depth: usize, loop_depth: usize) -> Decoder {
-    return Decoder::new(TRACE_LENGTH, EXTENSION_FACTOR, ctx_depth, loop_depth);
+fn new_decoder(ctx_depth: usize, loop_depth: usize, call_depth: usize) -> Decoder {
+    return Decoder::new(TRACE_LENGTH, EXTENSION_FACTOR, field::ONE, ctx_depth, loop_depth, call_depth);
 }
 
-fn evaluate_transition(decoder: &Decoder, step: usize, state1: Vec<u128>, state2: Vec<u128>) -> Vec<u128>
+fn evaluate_transition(decoder: &Decoder, step: usize, mut state1: Vec<u128>, mut state2: Vec<u128>) -> Vec<u128>
 {
-    let state1 = TraceState::from_vec(decoder.ctx_depth(), decoder.loop_depth(), 1, &state1);
-    let state2 = TraceState::from_vec(decoder.ctx_depth(), decoder.loop_depth(), 1, &state2);
+    // hasher registers do not affect decoder constraints, so they are set to all zeros
+    state1.resize(state1.len() + HASHER_WIDTH + AUX_WIDTH, 0);
+    state2.resize(state2.len() + HASHER_WIDTH + AUX_WIDTH, 0);
+
+    let state1 = TraceState::from_vec(decoder.ctx_depth(), decoder.loop_depth(), decoder.call_depth(), 1, &state1);
+    let state2 = TraceState::from_vec(decoder.ctx_depth(), decoder.loop_depth(), decoder.call_depth(), 1, &state2);
 
     let mut evaluations = vec![0; decoder.constraint_degrees().len()];
     decoder.evaluate(&state1, &state2, step, &mut evaluations);
@@ -1,20 +1,27 @@
 use crate::{
     math::field,
     utils::uninit_vector,
-    stark::{ StarkProof, TraceTable, TraceState, ConstraintCoefficients },
-    PROGRAM_DIGEST_SIZE,
+    stark::{ StarkProof, TraceInfo, TraceTable, TraceState, ConstraintCoefficients },
+    PROGRAM_DIGEST_SIZE, AUX_WIDTH,
+};
+use super::{
+    decoder::Decoder, stack::Stack, hasher::Hasher,
+    aux::{ AuxRegisters, AUX_CONSTRAINT_DEGREES },
+    super::MAX_CONSTRAINT_DEGREE
 };
-use super::{ decoder::Decoder, stack::Stack, super::MAX_CONSTRAINT_DEGREE };
 
 // TYPES AND INTERFACES
 // ================================================================================================
 pub struct Evaluator {
     decoder         : Decoder,
     stack           : Stack,
+    hasher          : Hasher,
+    aux             : AuxRegisters,
 
     coefficients    : ConstraintCoefficients,
     domain_size     : usize,
     extension_factor: usize,
+    domain_offset   : u128,
 
     t_constraint_num: usize,
     t_degree_groups : Vec<(u128, Vec<usize>)>,
@@ -28,33 +35,125 @@ pub struct Evaluator {
     b_degree_adj    : u128,
 }
 
+/// Constraint evaluators for the decoder, the stack, and the hasher, together with degrees of
+/// transition constraints grouped by degree adjustment factor. These depend only on the length
+/// of the execution trace and on the depths of its stacks, and thus, can be built once and
+/// re-used for all execution traces (or proofs) of the same shape.
+#[derive(Clone)]
+pub struct ConstraintSetup {
+    decoder         : Decoder,
+    stack           : Stack,
+    hasher          : Hasher,
+    t_constraint_num: usize,
+    t_degree_groups : Vec<(u128, Vec<usize>)>,
+    trace_length    : usize,
+    extension_factor: usize,
+    domain_offset   : u128,
+    ctx_depth       : usize,
+    loop_depth      : usize,
+    call_depth      : usize,
+    stack_depth     : usize,
+}
+
+// CONSTRAINT SETUP IMPLEMENTATION
+// ================================================================================================
+impl ConstraintSetup {
+
+    pub fn new(trace_length: usize, extension_factor: usize, domain_offset: u128, ctx_depth: usize, loop_depth: usize, call_depth: usize, stack_depth: usize) -> ConstraintSetup
+    {
+        let decoder = Decoder::new(trace_length, extension_factor, domain_offset, ctx_depth, loop_depth, call_depth);
+        let stack = Stack::new(stack_depth);
+        let hasher = Hasher::new(trace_length, extension_factor, domain_offset);
+
+        // build a list of transition constraint degrees
+        let t_constraint_degrees = [
+            decoder.constraint_degrees(), stack.constraint_degrees(), hasher.constraint_degrees(),
+            &AUX_CONSTRAINT_DEGREES
+        ].concat();
+
+        return ConstraintSetup {
+            decoder         : decoder,
+            stack           : stack,
+            hasher          : hasher,
+            t_constraint_num: t_constraint_degrees.len(),
+            t_degree_groups : group_transition_constraints(t_constraint_degrees, trace_length),
+            trace_length, extension_factor, domain_offset,
+            ctx_depth, loop_depth, call_depth, stack_depth,
+        };
+    }
+
+    /// Returns a setup for evaluating constraints of the specified `trace` over the constraint
+    /// evaluation domain; the domain is shifted by the same offset as the LDE domain of the trace.
+    pub fn from_trace(trace: &TraceTable) -> ConstraintSetup {
+        return ConstraintSetup::new(trace.unextended_length(), MAX_CONSTRAINT_DEGREE, trace.domain_offset(),
+            trace.ctx_depth(), trace.loop_depth(), trace.call_depth(), trace.stack_depth());
+    }
+
+    /// Returns true if this setup can be used to evaluate constraints of the specified `trace`.
+    pub fn matches(&self, trace: &TraceTable) -> bool {
+        return self.trace_length == trace.unextended_length()
+            && self.extension_factor == MAX_CONSTRAINT_DEGREE
+            && self.domain_offset == trace.domain_offset()
+            && self.ctx_depth == trace.ctx_depth()
+            && self.loop_depth == trace.loop_depth()
+            && self.call_depth == trace.call_depth()
+            && self.stack_depth == trace.stack_depth();
+    }
+
+    /// Returns a setup for evaluating constraints of the execution trace described by `info`
+    /// over the LDE domain defined by the `extension_factor`. The verifier evaluates periodic
+    /// constants from their polynomials at arbitrary points, and so, the setup does not depend
+    /// on the offset of the LDE domain.
+    pub fn from_trace_info(info: &TraceInfo, extension_factor: usize) -> ConstraintSetup {
+        return ConstraintSetup::new(info.trace_length(extension_factor), extension_factor, field::ONE,
+            info.ctx_depth as usize, info.loop_depth as usize, info.call_depth as usize, info.stack_depth as usize);
+    }
+
+    /// Returns true if this setup can be used to evaluate constraints of the execution trace
+    /// described by `info` over the LDE domain defined by the `extension_factor`.
+    pub fn matches_trace_info(&self, info: &TraceInfo, extension_factor: usize) -> bool {
+        return self.trace_length == info.trace_length(extension_factor)
+            && self.extension_factor == extension_factor
+            && self.ctx_depth == info.ctx_depth as usize
+            && self.loop_depth == info.loop_depth as usize
+            && self.call_depth == info.call_depth as usize
+            && self.stack_depth == info.stack_depth as usize;
+    }
+}
+
 // EVALUATOR IMPLEMENTATION
 // ================================================================================================
 impl Evaluator {
 
-    pub fn from_trace(trace: &TraceTable, trace_root: &[u8; 32], inputs: &[u128], outputs: &[u128]) -> Evaluator
+    pub fn from_trace(trace: &TraceTable, aux_seed: [u8; 32], coefficients_seed: [u8; 32], inputs: &[u128], outputs: &[u128], tape_values: &[u128]) -> Evaluator
+    {
+        let setup = ConstraintSetup::from_trace(trace);
+        return Evaluator::from_setup(&setup, trace, aux_seed, coefficients_seed, inputs, outputs, tape_values);
+    }
+
+    /// Builds an evaluator for the specified `trace` in the same way as from_trace() function,
+    /// but re-uses decoder, stack, and hasher constraint evaluators from the `setup`.
+    pub fn from_setup(setup: &ConstraintSetup, trace: &TraceTable, aux_seed: [u8; 32], coefficients_seed: [u8; 32], inputs: &[u128], outputs: &[u128], tape_values: &[u128]) -> Evaluator
     {
+        assert!(setup.matches(trace), "constraint setup does not match the shape of the execution trace");
         let last_state = trace.get_last_state();
         let ctx_depth = trace.ctx_depth();
         let loop_depth = trace.loop_depth();
+        let call_depth = trace.call_depth();
         let stack_depth = trace.stack_depth();
         let trace_length = trace.unextended_length();
         let extension_factor = MAX_CONSTRAINT_DEGREE;
 
-        // instantiate decoder and stack constraint evaluators 
-        let decoder = Decoder::new(trace_length, extension_factor, ctx_depth, loop_depth);
-        let stack = Stack::new(trace_length, extension_factor, stack_depth);
-
-        // build a list of transition constraint degrees
-        let t_constraint_degrees = [
-            decoder.constraint_degrees(), stack.constraint_degrees()
-        ].concat();
+        // decoder, stack, and hasher constraint evaluators are copied from the setup
+        let decoder = setup.decoder.clone();
+        let stack = setup.stack.clone();
+        let hasher = setup.hasher.clone();
 
         // if we are in debug mode, initialize vectors to hold individual evaluations
         // of transition constraints
         let domain_size = trace_length * extension_factor;
         let t_evaluations = if cfg!(debug_assertions) {
-            t_constraint_degrees.iter().map(|_| uninit_vector(domain_size)).collect()
+            (0..setup.t_constraint_num).map(|_| uninit_vector(domain_size)).collect()
         }
         else {
             Vec::new()
@@ -63,11 +162,14 @@ impl Evaluator {
         return Evaluator {
             decoder         : decoder,
             stack           : stack,
-            coefficients    : ConstraintCoefficients::new(*trace_root, ctx_depth, loop_depth, stack_depth),
+            hasher          : hasher,
+            aux             : AuxRegisters::new(aux_seed, tape_values),
+            coefficients    : ConstraintCoefficients::new(coefficients_seed, ctx_depth, loop_depth, call_depth, stack_depth),
             domain_size     : domain_size,
             extension_factor: extension_factor,
-            t_constraint_num: t_constraint_degrees.len(),
-            t_degree_groups : group_transition_constraints(t_constraint_degrees, trace_length),
+            domain_offset   : setup.domain_offset,
+            t_constraint_num: setup.t_constraint_num,
+            t_degree_groups : setup.t_degree_groups.clone(),
             t_evaluations   : t_evaluations,
             b_constraint_num: get_boundary_constraint_num(&inputs, &outputs),
             program_hash    : last_state.program_hash().to_vec(),
@@ -78,35 +180,38 @@ impl Evaluator {
         };
     }
 
-    pub fn from_proof(proof: &StarkProof, program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128]) -> Evaluator
+    /// Builds an evaluator for the execution trace committed to by the specified `proof`; decoder,
+    /// stack, and hasher constraint evaluators are re-used from the `setup`.
+    pub fn from_proof(setup: &ConstraintSetup, proof: &StarkProof, aux_seed: [u8; 32], coefficients_seed: [u8; 32], program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128]) -> Evaluator
     {
-        let ctx_depth = proof.ctx_depth();
-        let loop_depth = proof.loop_depth();
-        let stack_depth = proof.stack_depth();
-        let trace_length = proof.trace_length();
-        let extension_factor = proof.options().extension_factor();
-        
-        // instantiate decoder and stack constraint evaluators 
-        let decoder = Decoder::new(trace_length, extension_factor, ctx_depth, loop_depth);
-        let stack = Stack::new(trace_length, extension_factor, stack_depth);
+        return Evaluator::from_trace_info(setup, proof.trace_info(), proof.options().extension_factor(), proof.output_tape(),
+            aux_seed, coefficients_seed, program_hash, inputs, outputs);
+    }
 
-        // build a list of transition constraint degrees
-        let t_constraint_degrees = [
-            decoder.constraint_degrees(), stack.constraint_degrees()
-        ].concat();
+    /// Builds an evaluator for the execution trace described by `info` which wrote the specified
+    /// values to the output tape; this is used to evaluate constraints of individual executions
+    /// of a batch proof.
+    pub fn from_trace_info(setup: &ConstraintSetup, info: &TraceInfo, extension_factor: usize, output_values: &[u128], aux_seed: [u8; 32], coefficients_seed: [u8; 32], program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128]) -> Evaluator
+    {
+        assert!(setup.matches_trace_info(info, extension_factor), "constraint setup does not match the shape of the proof");
+        let trace_length = info.trace_length(extension_factor);
 
         return Evaluator {
-            decoder         : decoder,
-            stack           : stack,
-            coefficients    : ConstraintCoefficients::new(*proof.trace_root(), ctx_depth, loop_depth, stack_depth),
-            domain_size     : proof.domain_size(),
+            decoder         : setup.decoder.clone(),
+            stack           : setup.stack.clone(),
+            hasher          : setup.hasher.clone(),
+            aux             : AuxRegisters::new(aux_seed, output_values),
+            coefficients    : ConstraintCoefficients::new(coefficients_seed,
+                info.ctx_depth as usize, info.loop_depth as usize, info.call_depth as usize, info.stack_depth as usize),
+            domain_size     : info.domain_size(),
             extension_factor: extension_factor,
-            t_constraint_num: t_constraint_degrees.len(),
-            t_degree_groups : group_transition_constraints(t_constraint_degrees, trace_length),
+            domain_offset   : setup.domain_offset,
+            t_constraint_num: setup.t_constraint_num,
+            t_degree_groups : setup.t_degree_groups.clone(),
             t_evaluations   : Vec::new(),
             b_constraint_num: get_boundary_constraint_num(&inputs, &outputs),
             program_hash    : parse_program_hash(program_hash),
-            op_count        : proof.op_count(),
+            op_count        : info.op_count as u128,
             inputs          : inputs.to_vec(),
             outputs         : outputs.to_vec(),
             b_degree_adj    : get_boundary_constraint_adjustment_degree(trace_length),
@@ -125,6 +230,11 @@ impl Evaluator {
         return self.domain_size / self.extension_factor;
     }
 
+    /// Returns the offset by which the constraint evaluation domain is shifted.
+    pub fn domain_offset(&self) -> u128 {
+        return self.domain_offset;
+    }
+
     pub fn get_x_at_last_step(&self) -> u128 {
         let trace_root = field::get_root_of_unity(self.trace_length());
         return field::exp(trace_root, (self.trace_length() - 1) as u128);
@@ -139,9 +249,7 @@ impl Evaluator {
     pub fn evaluate_transition(&self, current: &TraceState, next: &TraceState, x: u128, step: usize) -> u128 {
         
         // evaluate transition constraints
-        let mut evaluations = vec![field::ZERO; self.t_constraint_num];
-        self.decoder.evaluate(&current, &next, step, &mut evaluations);
-        self.stack.evaluate(&current, &next, step, &mut evaluations[self.decoder.constraint_count()..]);
+        let evaluations = self.evaluate_transition_constraints(current, next, step);
 
         // when in debug mode, save transition evaluations before they are combined
         #[cfg(debug_assertions)]
@@ -161,14 +269,39 @@ impl Evaluator {
         return self.combine_transition_constraints(&evaluations, x);
     }
 
+    /// Evaluates all transition constraints at the specified `step` of the constraint evaluation
+    /// domain, and returns the evaluations without combining them; decoder constraints come first,
+    /// followed by stack, hasher, and auxiliary register constraints.
+    pub fn evaluate_transition_constraints(&self, current: &TraceState, next: &TraceState, step: usize) -> Vec<u128> {
+        let mut evaluations = vec![field::ZERO; self.t_constraint_num];
+        let (decoder_evaluations, evaluations_tail) = evaluations.split_at_mut(self.decoder.constraint_count());
+        let (stack_evaluations, evaluations_tail) = evaluations_tail.split_at_mut(self.stack.constraint_count());
+        let (hasher_evaluations, aux_evaluations) = evaluations_tail.split_at_mut(self.hasher.constraint_count());
+        self.decoder.evaluate(&current, &next, step, decoder_evaluations);
+        self.stack.evaluate(&current, &next, stack_evaluations);
+        self.hasher.evaluate(&current, &next, step, hasher_evaluations);
+
+        let messages = self.hasher.get_bus_messages(&current, &next, step);
+        self.aux.evaluate(&current, &next, &messages, aux_evaluations);
+
+        return evaluations;
+    }
+
     /// Computes pseudo-random liner combination of transition constraints at point x. This function
     /// is similar to the one above but it can also be used to evaluate constraints at any point
     /// in the filed (not just in the evaluation domain). However, it is also much slower.
     pub fn evaluate_transition_at(&self, current: &TraceState, next: &TraceState, x: u128) -> u128 {
         // evaluate transition constraints
         let mut evaluations = vec![field::ZERO; self.t_constraint_num];
-        self.decoder.evaluate_at(&current, &next, x, &mut evaluations);
-        self.stack.evaluate_at(&current, &next, x, &mut evaluations[self.decoder.constraint_count()..]);
+        let (decoder_evaluations, evaluations_tail) = evaluations.split_at_mut(self.decoder.constraint_count());
+        let (stack_evaluations, evaluations_tail) = evaluations_tail.split_at_mut(self.stack.constraint_count());
+        let (hasher_evaluations, aux_evaluations) = evaluations_tail.split_at_mut(self.hasher.constraint_count());
+        self.decoder.evaluate_at(&current, &next, x, decoder_evaluations);
+        self.stack.evaluate(&current, &next, stack_evaluations);
+        self.hasher.evaluate_at(&current, &next, x, hasher_evaluations);
+
+        let messages = self.hasher.get_bus_messages_at(&current, &next, x);
+        self.aux.evaluate(&current, &next, &messages, aux_evaluations);
 
         // compute a pseudo-random linear combination of all transition constraints
         return self.combine_transition_constraints(&evaluations, x);
@@ -240,6 +373,13 @@ impl Evaluator {
             result_adj = field::add(result_adj, field::mul(loop_stack[i], cc.loop_stack[i * 2 + 1]));
         }
 
+        // make sure all call stack registers are 0s
+        let call_stack = current.call_stack();
+        for i in 0..call_stack.len() {
+            i_result = field::add(i_result, field::mul(call_stack[i], cc.call_stack[i * 2]));
+            result_adj = field::add(result_adj, field::mul(call_stack[i], cc.call_stack[i * 2 + 1]));
+        }
+
         // make sure stack registers are set to inputs
         let user_stack = current.user_stack();
         for i in 0..self.inputs.len() {
@@ -248,6 +388,14 @@ impl Evaluator {
             result_adj = field::add(result_adj, field::mul(val, cc.user_stack[i * 2 + 1]));
         }
 
+        // make sure auxiliary registers start with their initial values
+        let aux = current.aux();
+        for (i, &value) in self.aux.initial_values().iter().enumerate() {
+            let val = field::sub(aux[i], value);
+            i_result = field::add(i_result, field::mul(val, cc.aux[i * 2]));
+            result_adj = field::add(result_adj, field::mul(val, cc.aux[i * 2 + 1]));
+        }
+
         // raise the degree of adjusted terms and sum all the terms together
         i_result = field::add(i_result, field::mul(result_adj, xp));
 
@@ -312,12 +460,29 @@ impl Evaluator {
             result_adj = field::add(result_adj, field::mul(loop_stack[i], cc.loop_stack[i * 2 + 1]));
         }
 
+        // make sure all call stack registers are 0s
+        let call_stack = current.call_stack();
+        for i in 0..call_stack.len() {
+            f_result = field::add(f_result, field::mul(call_stack[i], cc.call_stack[i * 2]));
+            result_adj = field::add(result_adj, field::mul(call_stack[i], cc.call_stack[i * 2 + 1]));
+        }
+
         // make sure user stack registers are set to outputs
         for i in 0..self.outputs.len() {
             let val = field::sub(user_stack[i], self.outputs[i]);
             f_result = field::add(f_result, field::mul(val, cc.user_stack[i * 2]));
             result_adj = field::add(result_adj, field::mul(val, cc.user_stack[i * 2 + 1]));
-        }        
+        }
+
+        // make sure auxiliary registers end with their final values; this means that all
+        // messages sent to the bus have been received from it, and that the output tape register
+        // holds the reduced values of the output tape
+        let aux = current.aux();
+        for (i, &value) in self.aux.final_values().iter().enumerate() {
+            let val = field::sub(aux[i], value);
+            f_result = field::add(f_result, field::mul(val, cc.aux[i * 2]));
+            result_adj = field::add(result_adj, field::mul(val, cc.aux[i * 2 + 1]));
+        }
 
         // raise the degree of adjusted terms and sum all the terms together
         f_result = field::add(f_result, field::mul(result_adj, xp));
@@ -328,7 +493,9 @@ impl Evaluator {
     // HELPER METHODS
     // -------------------------------------------------------------------------------------------
     fn should_evaluate_to_zero_at(&self, step: usize) -> bool {
-        return (step & (self.extension_factor - 1) == 0) // same as: step % extension_factor == 0
+        // a shifted evaluation domain does not pass through any steps of the execution trace
+        return (self.domain_offset == field::ONE)
+            && (step & (self.extension_factor - 1) == 0) // same as: step % extension_factor == 0
             && (step != self.domain_size - self.extension_factor);
     }
 
@@ -375,7 +542,8 @@ impl Evaluator {
     #[cfg(debug_assertions)]
     pub fn get_transition_degrees(&self) -> Vec<usize> {
         return [
-            self.decoder.constraint_degrees(), self.stack.constraint_degrees()
+            self.decoder.constraint_degrees(), self.stack.constraint_degrees(), self.hasher.constraint_degrees(),
+            &AUX_CONSTRAINT_DEGREES
         ].concat();
     }
 }
@@ -440,5 +608,6 @@ fn get_boundary_constraint_num(inputs: &[u128], outputs: &[u128]) -> usize {
     return
         PROGRAM_DIGEST_SIZE 
         + inputs.len() + outputs.len()
-        + 1 /* for op_count */;
+        + 1 /* for op_count */
+        + 2 * AUX_WIDTH /* initial and final values of auxiliary registers */;
 }
\ No newline at end of file
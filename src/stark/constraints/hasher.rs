@@ -0,0 +1,364 @@
+use crate::{
+    math::{ field, polynom },
+    stark::TraceState,
+    processor::OpCode,
+    utils::hasher::{ ARK, apply_sbox, apply_mds, apply_inv_mds },
+    BASE_CYCLE_LENGTH, HASH_STATE_WIDTH,
+};
+use super::{ BusMessages, utils::{ are_equal, is_binary, extend_constants, EvaluationResult } };
+
+// CONSTANTS
+// ================================================================================================
+pub const NUM_HASHER_CONSTRAINTS: usize = 3 * HASH_STATE_WIDTH + 2;
+const ROUND_CONSTRAINT_DEGREE: usize = 4;   // degree 3 Rescue round multiplied by cycle mask
+const COPY_CONSTRAINT_DEGREE: usize = 2;    // copying of permutation result multiplied by cycle mask
+const INPUT_CONSTRAINT_DEGREE: usize = 2;   // copying of permutation input multiplied by cycle masks
+const FLAG_CONSTRAINT_DEGREE: usize = 2;    // activity flag is binary and fixed within a cycle
+
+const NUM_MASKS     : usize = 5;
+const ROUND_MASK_IDX: usize = 0;
+const COPY_MASK_IDX : usize = 1;
+const FLAG_MASK_IDX : usize = 2;
+const INPUT_MASK_IDX: usize = 3;
+const OUTPUT_MASK_IDX: usize = 4;
+
+const PERMUTATION_LABEL: u128 = 1;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+#[derive(Clone)]
+pub struct Hasher {
+    trace_length        : usize,
+    cycle_length        : usize,
+    ark_values          : Vec<[u128; 2 * HASH_STATE_WIDTH]>,
+    ark_polys           : Vec<Vec<u128>>,
+    mask_values         : Vec<[u128; NUM_MASKS]>,
+    mask_polys          : Vec<Vec<u128>>,
+    constraint_degrees  : Vec<usize>,
+}
+
+// HASHER CONSTRAINT EVALUATOR IMPLEMENTATION
+// ================================================================================================
+impl Hasher {
+
+    pub fn new(trace_length: usize, extension_factor: usize, domain_offset: u128) -> Hasher
+    {
+        // build an array of constraint degrees for the hasher
+        let mut degrees = vec![ROUND_CONSTRAINT_DEGREE; HASH_STATE_WIDTH];
+        degrees.resize(2 * HASH_STATE_WIDTH, COPY_CONSTRAINT_DEGREE);
+        degrees.resize(3 * HASH_STATE_WIDTH, INPUT_CONSTRAINT_DEGREE);
+        degrees.resize(NUM_HASHER_CONSTRAINTS, FLAG_CONSTRAINT_DEGREE);
+
+        // determine extended cycle length; constants at x are evaluated at x^num_cycles, and so,
+        // the cycle domain is shifted by domain_offset^num_cycles
+        let cycle_length = BASE_CYCLE_LENGTH * extension_factor;
+        let cycle_offset = field::exp(domain_offset, (trace_length / BASE_CYCLE_LENGTH) as u128);
+
+        // extend rounds constants by the specified extension factor
+        let (ark_polys, ark_evaluations) = extend_constants(&ARK, extension_factor, cycle_offset);
+        let ark_values = transpose_ark_constants(ark_evaluations, cycle_length);
+
+        // extend mask constants by the specified extension factor
+        let (mask_polys, mask_evaluations) = extend_constants(&MASKS, extension_factor, cycle_offset);
+        let mask_values = transpose_mask_constants(mask_evaluations, cycle_length);
+
+        return Hasher {
+            trace_length, cycle_length,
+            ark_values, ark_polys,
+            mask_values, mask_polys,
+            constraint_degrees: degrees,
+        };
+    }
+
+    pub fn constraint_count(&self) -> usize {
+        return self.constraint_degrees.len();
+    }
+
+    pub fn constraint_degrees(&self) -> &[usize] {
+        return &self.constraint_degrees;
+    }
+
+    // EVALUATOR FUNCTIONS
+    // --------------------------------------------------------------------------------------------
+
+    /// Evaluates hasher transition constraints at the specified step of the evaluation domain and
+    /// saves the evaluations into `result`.
+    pub fn evaluate(&self, current: &TraceState, next: &TraceState, step: usize, result: &mut [u128])
+    {
+        // determine round and mask constants at the specified step
+        let ark = self.ark_values[step % self.cycle_length];
+        let masks = self.mask_values[step % self.cycle_length];
+
+        // evaluate transition constraints for the hasher
+        enforce_constraints(current, next, &ark, &masks, result);
+    }
+
+    /// Returns messages exchanged over the bus between the stack and the hasher at the
+    /// specified step of the evaluation domain.
+    pub fn get_bus_messages(&self, current: &TraceState, next: &TraceState, step: usize) -> BusMessages {
+        let masks = self.mask_values[step % self.cycle_length];
+        return build_bus_messages(current, next, &masks);
+    }
+
+    /// Evaluates hasher transition constraints at the specified x coordinate and saves the
+    /// evaluations into `result`. Unlike the function above, this function can evaluate constraints
+    /// at any out-of-domain point, but it is much slower than the previous function.
+    pub fn evaluate_at(&self, current: &TraceState, next: &TraceState, x: u128, result: &mut [u128])
+    {
+        // map x to the corresponding coordinate in constant cycles
+        let num_cycles = (self.trace_length / BASE_CYCLE_LENGTH) as u128;
+        let x = field::exp(x, num_cycles);
+
+        // determine round constants at the specified x coordinate
+        let mut ark = [field::ZERO; 2 * HASH_STATE_WIDTH];
+        for i in 0..ark.len() {
+            ark[i] = polynom::eval(&self.ark_polys[i], x);
+        }
+
+        // determine mask constants at the specified x coordinate
+        let masks = self.get_masks_at(x);
+
+        // evaluate transition constraints for the hasher
+        enforce_constraints(current, next, &ark, &masks, result);
+    }
+
+    /// Returns messages exchanged over the bus between the stack and the hasher at the
+    /// specified x coordinate.
+    pub fn get_bus_messages_at(&self, current: &TraceState, next: &TraceState, x: u128) -> BusMessages {
+        let num_cycles = (self.trace_length / BASE_CYCLE_LENGTH) as u128;
+        let masks = self.get_masks_at(field::exp(x, num_cycles));
+        return build_bus_messages(current, next, &masks);
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+
+    /// Evaluates mask constants at the specified x coordinate; x must already be mapped to the
+    /// corresponding coordinate in constant cycles.
+    fn get_masks_at(&self, x: u128) -> [u128; NUM_MASKS] {
+        let mut masks = [field::ZERO; NUM_MASKS];
+        for i in 0..masks.len() {
+            masks[i] = polynom::eval(&self.mask_polys[i], x);
+        }
+        return masks;
+    }
+}
+
+// BUS MESSAGES
+// ================================================================================================
+
+/// Returns messages exchanged over the bus between the stack and the hasher at a transition of
+/// the un-extended execution trace from the specified `step` to the next step.
+pub fn get_trace_bus_messages(current: &TraceState, next: &TraceState, step: usize) -> BusMessages {
+    let mut masks = [field::ZERO; NUM_MASKS];
+    for i in 0..masks.len() {
+        masks[i] = MASKS[i][step % BASE_CYCLE_LENGTH];
+    }
+    return build_bus_messages(current, next, &masks);
+}
+
+/// Every RESCR operation executed by the stack sends the input and the result of the permutation
+/// to the bus as a single message. An active hasher receives the same message at the 10th step
+/// of its cycle: at this step, the input registers of the hasher hold the input of the permutation,
+/// and the hasher state at the next step holds its result.
+fn build_bus_messages(current: &TraceState, next: &TraceState, masks: &[u128; NUM_MASKS]) -> BusMessages {
+    let mut messages = BusMessages::new();
+
+    // the stack sends permutation inputs and outputs
+    let stack_flag = current.hd_op_flags()[OpCode::RescR.hd_index()];
+    let stack_message = build_message(&current.user_stack()[..HASH_STATE_WIDTH], &next.user_stack()[..HASH_STATE_WIDTH]);
+    messages.send(stack_flag, &stack_message);
+
+    // the hasher receives permutation inputs and outputs
+    let hasher_flag = field::mul(current.hasher_flag(), masks[OUTPUT_MASK_IDX]);
+    let hasher_message = build_message(current.hasher_input(), next.hasher_state());
+    messages.receive(hasher_flag, &hasher_message);
+
+    return messages;
+}
+
+fn build_message(input: &[u128], output: &[u128]) -> Vec<u128> {
+    let mut result = Vec::with_capacity(1 + 2 * HASH_STATE_WIDTH);
+    result.push(PERMUTATION_LABEL);
+    result.extend_from_slice(input);
+    result.extend_from_slice(output);
+    return result;
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Enforces that during the first 10 steps of every cycle the hasher state is updated according
+/// to a single round of Rescue hash function, and that for the remaining steps of the cycle (except
+/// for the last one) the state does not change. The transition from the last step of a cycle to the
+/// first step of the next cycle is not constrained; this is where a new permutation is started.
+/// Also enforces that the input registers hold the state from the first step of a cycle until the
+/// end of the cycle, and that the activity flag is binary and does not change within a cycle.
+fn enforce_constraints(current: &TraceState, next: &TraceState, ark: &[u128], masks: &[u128; NUM_MASKS], result: &mut [u128])
+{
+    let old_state = current.hasher_state();
+    let new_state = next.hasher_state();
+
+    // evaluate the first half of Rescue round
+    let mut old_half = [field::ZERO; HASH_STATE_WIDTH];
+    old_half.copy_from_slice(&old_state[..HASH_STATE_WIDTH]);
+    for i in 0..HASH_STATE_WIDTH {
+        old_half[i] = field::add(old_half[i], ark[i]);
+    }
+    apply_sbox(&mut old_half);
+    apply_mds(&mut old_half);
+
+    // evaluate inverse of the second half of Rescue round
+    let mut new_half = [field::ZERO; HASH_STATE_WIDTH];
+    new_half.copy_from_slice(&new_state[..HASH_STATE_WIDTH]);
+    apply_inv_mds(&mut new_half);
+    apply_sbox(&mut new_half);
+    for i in 0..HASH_STATE_WIDTH {
+        new_half[i] = field::sub(new_half[i], ark[HASH_STATE_WIDTH + i]);
+    }
+
+    // compare the results of both halves of the round
+    let (round, result) = result.split_at_mut(HASH_STATE_WIDTH);
+    let (copy, result) = result.split_at_mut(HASH_STATE_WIDTH);
+    let (input, flag) = result.split_at_mut(HASH_STATE_WIDTH);
+    for i in 0..HASH_STATE_WIDTH {
+        round.agg_constraint(i, masks[ROUND_MASK_IDX], are_equal(new_half[i], old_half[i]));
+    }
+
+    // make sure the result of the permutation is preserved until the end of the cycle
+    for i in 0..HASH_STATE_WIDTH {
+        copy.agg_constraint(i, masks[COPY_MASK_IDX], are_equal(new_state[i], old_state[i]));
+    }
+
+    // make sure the input of the permutation is copied from the state at the first step of the
+    // cycle, and is preserved until the end of the cycle
+    let old_input = current.hasher_input();
+    let new_input = next.hasher_input();
+    let copy_mask = field::sub(masks[FLAG_MASK_IDX], masks[INPUT_MASK_IDX]);
+    for i in 0..HASH_STATE_WIDTH {
+        input.agg_constraint(i, masks[INPUT_MASK_IDX], are_equal(new_input[i], old_state[i]));
+        input.agg_constraint(i, copy_mask, are_equal(new_input[i], old_input[i]));
+    }
+
+    // make sure the activity flag is binary and is the same for all steps of a cycle
+    flag[0] = is_binary(current.hasher_flag());
+    flag.agg_constraint(1, masks[FLAG_MASK_IDX], are_equal(next.hasher_flag(), current.hasher_flag()));
+}
+
+fn transpose_ark_constants(constants: Vec<Vec<u128>>, cycle_length: usize) -> Vec<[u128; 2 * HASH_STATE_WIDTH]>
+{
+    let mut values = Vec::new();
+    for i in 0..cycle_length {
+        values.push([field::ZERO; 2 * HASH_STATE_WIDTH]);
+        for j in 0..(2 * HASH_STATE_WIDTH) {
+            values[i][j] = constants[j][i];
+        }
+    }
+    return values;
+}
+
+fn transpose_mask_constants(constants: Vec<Vec<u128>>, cycle_length: usize) -> Vec<[u128; NUM_MASKS]>
+{
+    let mut values = Vec::new();
+    for i in 0..cycle_length {
+        values.push([field::ZERO; NUM_MASKS]);
+        for j in 0..NUM_MASKS {
+            values[i][j] = constants[j][i];
+        }
+    }
+    return values;
+}
+
+// CYCLE MASKS
+// ================================================================================================
+const MASKS: [[u128; BASE_CYCLE_LENGTH]; NUM_MASKS] = [
+    [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0],   // Rescue rounds
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0],   // permutation result
+    [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0],   // activity flag
+    [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],   // permutation input
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0],   // permutation on the bus
+];
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ math::field, processor::Hasher as HashCoprocessor, stark::TraceState, HASH_STATE_WIDTH, HASHER_WIDTH, AUX_WIDTH };
+    use super::{ Hasher, NUM_HASHER_CONSTRAINTS };
+
+    const TRACE_LENGTH: usize = 32;
+    const EXTENSION_FACTOR: usize = 8;
+
+    #[test]
+    fn hasher_constraints() {
+        let hasher = Hasher::new(TRACE_LENGTH, EXTENSION_FACTOR, field::ONE);
+
+        // build hasher trace with a single permutation in the first cycle
+        let mut coprocessor = HashCoprocessor::new(TRACE_LENGTH);
+        coprocessor.permute([1, 2, 3, 4, 5, 6]);
+        coprocessor.finalize_trace(TRACE_LENGTH);
+        let registers = coprocessor.into_register_traces();
+
+        // all transitions within cycles should be valid
+        let success_result = vec![0; NUM_HASHER_CONSTRAINTS];
+        for step in 0..(TRACE_LENGTH - 1) {
+            let current = build_state(&registers, step);
+            let next = build_state(&registers, step + 1);
+            let evaluations = evaluate(&hasher, &current, &next, step);
+            assert_eq!(success_result, evaluations, "constraints failed at step {}", step);
+        }
+
+        // tampering with the state during Rescue rounds should be detected
+        let next = tamper_state(&build_state(&registers, 4), HASH_STATE_WIDTH);
+        let evaluations = evaluate(&hasher, &build_state(&registers, 3), &next, 3);
+        assert_ne!(success_result, evaluations);
+
+        // tampering with the permutation result should be detected
+        let next = tamper_state(&build_state(&registers, 12), HASH_STATE_WIDTH);
+        let evaluations = evaluate(&hasher, &build_state(&registers, 11), &next, 11);
+        assert_ne!(success_result, evaluations);
+
+        // tampering with the permutation input should be detected at the first and at the
+        // following steps of the cycle
+        let next = tamper_state(&build_state(&registers, 1), 0);
+        let evaluations = evaluate(&hasher, &build_state(&registers, 0), &next, 0);
+        assert_ne!(success_result, evaluations);
+
+        let next = tamper_state(&build_state(&registers, 10), 0);
+        let evaluations = evaluate(&hasher, &build_state(&registers, 9), &next, 9);
+        assert_ne!(success_result, evaluations);
+
+        // changing the activity flag within a cycle should be detected
+        let mut next = build_state(&registers, 10).to_vec();
+        next[16 + 1] = 0;
+        let next = TraceState::from_vec(1, 0, 0, 1, &next);
+        let evaluations = evaluate(&hasher, &build_state(&registers, 9), &next, 9);
+        assert_ne!(success_result, evaluations);
+    }
+
+    fn evaluate(hasher: &Hasher, current: &TraceState, next: &TraceState, step: usize) -> Vec<u128> {
+        let mut evaluations = vec![0; NUM_HASHER_CONSTRAINTS];
+        hasher.evaluate(current, next, step * EXTENSION_FACTOR, &mut evaluations);
+        return evaluations;
+    }
+
+    /// Increments the hasher register located `offset` registers before the last one; offset 0
+    /// points to the last input register, and offset 6 points to the last state register.
+    fn tamper_state(state: &TraceState, offset: usize) -> TraceState {
+        let mut values = state.to_vec();
+        let last = values.len() - AUX_WIDTH - 1 - offset;
+        values[last] = values[last] + 1;
+        return TraceState::from_vec(1, 0, 0, 1, &values);
+    }
+
+    fn build_state(registers: &[Vec<u128>], step: usize) -> TraceState {
+        // decoder with context depth 1 and a user stack with a single register
+        let mut state = vec![0; 16 + 1];
+        for i in 0..HASHER_WIDTH {
+            state.push(registers[i][step]);
+        }
+        state.resize(state.len() + AUX_WIDTH, 0);
+        return TraceState::from_vec(1, 0, 0, 1, &state);
+    }
+}
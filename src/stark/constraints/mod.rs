@@ -1,12 +1,22 @@
 mod evaluator;
 mod decoder;
 mod stack;
+mod hasher;
+mod bus;
+mod output;
+mod aux;
+#[cfg(test)]
+mod range;
 mod constraint_table;
 mod constraint_poly;
 mod utils;
 
-pub use decoder::{ NUM_STATIC_DECODER_CONSTRAINTS };
+pub use decoder::{ NUM_STATIC_DECODER_CONSTRAINTS, NUM_CALL_FLAG_CONSTRAINTS };
 pub use stack::{ NUM_AUX_CONSTRAINTS as NUM_AUX_STACK_CONSTRAINTS };
-pub use evaluator::{ Evaluator as ConstraintEvaluator};
+pub use hasher::{ NUM_HASHER_CONSTRAINTS };
+pub use bus::{ BusMessages, NUM_BUS_CONSTRAINTS };
+pub use output::{ NUM_OUTPUT_CONSTRAINTS };
+pub use aux::{ AuxRegisters };
+pub use evaluator::{ Evaluator as ConstraintEvaluator, ConstraintSetup };
 pub use constraint_table::{ ConstraintTable };
 pub use constraint_poly::{ ConstraintPoly };
\ No newline at end of file
@@ -0,0 +1,138 @@
+use crate::{ math::field, processor::OpCode, stark::{ TraceTable, TraceState } };
+use super::utils::are_equal;
+
+// CONSTANTS
+// ================================================================================================
+pub const NUM_OUTPUT_CONSTRAINTS: usize = 1;
+
+/// WRITE is a low-degree operation and so its flag has degree 5; the flag is multiplied by a
+/// degree 1 term
+pub const OUTPUT_CONSTRAINT_DEGREE: usize = 6;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Output tape of a program. Values written to the tape by WRITE operations are accumulated in
+/// a single register o such that o_0 = 1, and whenever a value v is written at step i:
+///
+///   o_{i+1} = o_i * gamma + v
+///
+/// At all other steps the register does not change. gamma is a random value drawn by the
+/// verifier after the main execution trace has been committed to. Thus, the last value of the
+/// register is an evaluation at gamma of a monic polynomial whose other coefficients are the
+/// values written to the tape, and it binds the proof to the entire sequence of written values
+/// (including their order and number) with high probability.
+pub struct OutputTape {
+    gamma   : u128,
+}
+
+// OUTPUT TAPE IMPLEMENTATION
+// ================================================================================================
+impl OutputTape {
+
+    /// Returns a new output tape with randomness derived from the specified `seed`; the seed
+    /// should be the commitment to the main execution trace.
+    pub fn new(seed: [u8; 32]) -> OutputTape {
+        // the first two values are drawn by the bus
+        let randomness = field::prng_vector(seed, 3);
+        return OutputTape { gamma: randomness[2] };
+    }
+
+    /// Reduces a sequence of values written to the tape to a single field element; this is the
+    /// value which the output tape register holds at the last step of the trace.
+    pub fn reduce(&self, values: &[u128]) -> u128 {
+        let mut result = field::ONE;
+        for &value in values.iter() {
+            result = field::add(field::mul(result, self.gamma), value);
+        }
+        return result;
+    }
+
+    /// Builds the output tape register from values written at every step of the specified
+    /// `trace`; the trace must be extended. Returns the register together with the list of
+    /// written values.
+    pub fn build_trace_column(&self, trace: &TraceTable) -> (Vec<u128>, Vec<u128>) {
+        let mut current = trace.get_state(0);
+
+        let mut values = Vec::new();
+        let mut column = Vec::with_capacity(trace.unextended_length());
+        column.push(field::ONE);
+        for i in 0..(trace.unextended_length() - 1) {
+            trace.fill_trace_state(&mut current, i);
+            let mut next = column[i];
+            if current.ld_op_flags()[OpCode::Write.ld_index()] == field::ONE {
+                let value = current.user_stack()[0];
+                next = field::add(field::mul(next, self.gamma), value);
+                values.push(value);
+            }
+            column.push(next);
+        }
+
+        return (column, values);
+    }
+
+    /// Evaluates the output tape transition constraint given `current` and `next` states of
+    /// the execution trace.
+    pub fn evaluate(&self, current: &TraceState, next: &TraceState) -> u128 {
+        let op_flag = current.ld_op_flags()[OpCode::Write.ld_index()];
+        let o_current = current.output_tape();
+        let written = field::add(field::mul(o_current, self.gamma), current.user_stack()[0]);
+        let expected = field::add(o_current, field::mul(op_flag, field::sub(written, o_current)));
+        return are_equal(next.output_tape(), expected);
+    }
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ math::field, stark::TraceState };
+    use super::OutputTape;
+
+    #[test]
+    fn reduce_values() {
+        let tape = OutputTape::new([1; 32]);
+        assert_eq!(field::ONE, tape.reduce(&[]));
+
+        let expected = field::add(field::mul(field::add(tape.gamma, 5), tape.gamma), 7);
+        assert_eq!(expected, tape.reduce(&[5, 7]));
+
+        // the order of values affects the result
+        assert_ne!(tape.reduce(&[1, 2, 3]), tape.reduce(&[1, 3, 2]));
+
+        // leading zeros affect the result as well
+        assert_ne!(tape.reduce(&[5, 7]), tape.reduce(&[0, 5, 7]));
+    }
+
+    #[test]
+    fn write_value() {
+        let tape = OutputTape::new([1; 32]);
+        let written = tape.reduce(&[5, 7]);
+
+        // WRITE operation appends the top of the stack to the tape
+        let current = build_state(&[1, 1, 1, 0, 1], 7, tape.reduce(&[5]));
+        let next = build_state(&[1, 1, 1, 1, 1], 0, written);
+        assert_eq!(field::ZERO, tape.evaluate(&current, &next));
+
+        let next = build_state(&[1, 1, 1, 1, 1], 0, tape.reduce(&[5, 6]));
+        assert_ne!(field::ZERO, tape.evaluate(&current, &next));
+
+        // other operations do not change the tape
+        let current = build_state(&[1, 1, 1, 1, 1], 7, written);
+        let next = build_state(&[1, 1, 1, 1, 1], 7, written);
+        assert_eq!(field::ZERO, tape.evaluate(&current, &next));
+
+        let next = build_state(&[1, 1, 1, 1, 1], 7, tape.reduce(&[5, 7, 7]));
+        assert_ne!(field::ZERO, tape.evaluate(&current, &next));
+    }
+
+    fn build_state(ld_op_bits: &[u128; 5], stack_top: u128, output_tape: u128) -> TraceState {
+        let b = ld_op_bits;
+        let state = vec![
+            0,  0, 0, 0, 0,  1, 1, 1,  b[0], b[1], b[2], b[3], b[4],  1, 1,  0,  stack_top,
+            0, 0, 0, 0, 0, 0, 0,  0, 0, 0, 0, 0, 0,  1, output_tape
+        ];
+        return TraceState::from_vec(1, 0, 0, 1, &state);
+    }
+}
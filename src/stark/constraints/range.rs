@@ -0,0 +1,124 @@
+use crate::{ math::field, processor::RANGE_TABLE_SIZE };
+use super::{ BusMessages, utils::{ are_equal, is_binary } };
+
+// CONSTANTS
+// ================================================================================================
+pub const NUM_RANGE_CONSTRAINTS: usize = 2;
+pub const RANGE_CONSTRAINT_DEGREES: [usize; NUM_RANGE_CONSTRAINTS] = [
+    2,  // value register either stays the same or is incremented by 1
+    2,  // lookup flag is binary
+];
+
+/// values of the value register at the first and at the last steps of the table
+pub const RANGE_TABLE_FIRST_VALUE: u128 = 0;
+pub const RANGE_TABLE_LAST_VALUE : u128 = (RANGE_TABLE_SIZE - 1) as u128;
+
+/// label of range check messages on the bus; distinct from labels of hash messages
+const RANGE_CHECK_LABEL: u128 = 3;
+
+const VALUE_IDX: usize = 0;
+const FLAG_IDX : usize = 1;
+
+// RANGE CHECK CONSTRAINTS
+// ================================================================================================
+
+/// Evaluates transition constraints of the range checker table (see processor::RangeChecker)
+/// given `current` and `next` states of the table registers, and saves the evaluations into
+/// `result`. Together with boundary constraints which fix the first and the last values of the
+/// table, these constraints guarantee that only 16-bit values can appear in the table.
+pub fn evaluate(current: &[u128], next: &[u128], result: &mut [u128]) {
+    let delta = are_equal(next[VALUE_IDX], current[VALUE_IDX]);
+    result[0] = field::mul(delta, field::sub(delta, field::ONE));
+    result[1] = is_binary(current[FLAG_IDX]);
+}
+
+/// Registers lookups satisfied by the range checker table at the current step; a row of the
+/// table satisfies a lookup only when its lookup flag is set to 1.
+pub fn receive_lookups(current: &[u128], messages: &mut BusMessages) {
+    messages.receive(current[FLAG_IDX], &[RANGE_CHECK_LABEL, current[VALUE_IDX]]);
+}
+
+/// Registers a request to check that `value` is a 16-bit value; the request is made only when
+/// `flag` is set to 1. Every such request must be satisfied by a distinct row of the range
+/// checker table for the bus to be balanced.
+pub fn send_lookup(flag: u128, value: u128, messages: &mut BusMessages) {
+    messages.send(flag, &[RANGE_CHECK_LABEL, value]);
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ math::field, processor::RangeChecker, stark::constraints::bus::{ Bus, BusMessages } };
+    use super::{ evaluate, receive_lookups, send_lookup, RANGE_TABLE_FIRST_VALUE, RANGE_TABLE_LAST_VALUE };
+
+    #[test]
+    fn range_check_table() {
+        let registers = build_table(&[5, 300, 300, 65535]);
+        let trace_length = registers[0].len();
+        assert_eq!(RANGE_TABLE_FIRST_VALUE, registers[0][0]);
+        assert_eq!(RANGE_TABLE_LAST_VALUE, registers[0][trace_length - 1]);
+
+        // all transitions of the table should be valid
+        let mut result = [field::ZERO; 2];
+        for i in 0..(trace_length - 1) {
+            evaluate(&get_row(&registers, i), &get_row(&registers, i + 1), &mut result);
+            assert_eq!([0, 0], result, "constraints failed at step {}", i);
+        }
+
+        // skipping a value in the table should be detected
+        evaluate(&[4, 0], &[6, 0], &mut result);
+        assert_ne!(0, result[0]);
+
+        // non-binary lookup flag should be detected
+        evaluate(&[4, 2], &[5, 0], &mut result);
+        assert_ne!(0, result[1]);
+    }
+
+    #[test]
+    fn range_check_lookups() {
+        let bus = Bus::new([3; 32]);
+        let registers = build_table(&[5, 300, 300, 65535]);
+
+        // lookups made by another component at the first steps of the trace should be
+        // balanced by the rows of the table
+        let mut messages = build_messages(&registers, &[5, 300, 300, 65535]);
+        assert!(Bus::is_balanced(&bus.build_column(&messages)));
+
+        // a lookup of a value which was not recorded should not be balanced
+        messages = build_messages(&registers, &[5, 300, 301, 65535]);
+        assert!(!Bus::is_balanced(&bus.build_column(&messages)));
+
+        // a value can be looked up only as many times as it was recorded
+        messages = build_messages(&registers, &[5, 300, 300, 300]);
+        assert!(!Bus::is_balanced(&bus.build_column(&messages)));
+    }
+
+    fn build_table(values: &[u16]) -> Vec<Vec<u128>> {
+        let mut checker = RangeChecker::new();
+        for &value in values.iter() {
+            checker.check_u16(value);
+        }
+        let trace_length = checker.trace_length();
+        return checker.into_register_traces(trace_length);
+    }
+
+    fn build_messages(registers: &[Vec<u128>], lookups: &[u128]) -> Vec<BusMessages> {
+        let trace_length = registers[0].len();
+        let mut messages = Vec::with_capacity(trace_length - 1);
+        for i in 0..(trace_length - 1) {
+            let mut step_messages = BusMessages::new();
+            receive_lookups(&get_row(registers, i), &mut step_messages);
+            if i < lookups.len() {
+                send_lookup(field::ONE, lookups[i], &mut step_messages);
+            }
+            messages.push(step_messages);
+        }
+        return messages;
+    }
+
+    fn get_row(registers: &[Vec<u128>], step: usize) -> [u128; 2] {
+        return [registers[0][step], registers[1][step]];
+    }
+}
@@ -116,4 +116,41 @@ pub fn enforce_or(result: &mut [u128], aux: &mut[u128], old_stack: &[u128], new_
     // ensure that both operands are binary values
     aux.agg_constraint(0, op_flag, is_binary(x));
     aux.agg_constraint(1, op_flag, is_binary(y));
-}
\ No newline at end of file
+}
+/// Enforces constraints for EXPACC operation. The operation performs a single step of
+/// square-and-multiply exponentiation using the next bit of the exponent (from the least
+/// significant one) supplied via input tape A.
+pub fn enforce_expacc(result: &mut [u128], old_stack: &[u128], new_stack: &[u128], op_flag: u128)
+{
+    // layout of first 6 registers:
+    // [exponent bit, multiplier, power of two, result, accumulated exponent, base power]
+    // exponent bit is located in the next state (not current state); the multiplier for the
+    // bit is applied to the result on the next step to keep the degree of constraints low
+    let bit = new_stack[0];
+    let multiplier = old_stack[1];
+    let power_of_two = old_stack[2];
+    let base_power = old_stack[5];
+
+    // the bit was a binary value
+    result.agg_constraint(0, op_flag, is_binary(bit));
+
+    // multiplier is set to the base power when the bit is 1, and to 1 otherwise
+    let next_multiplier = field::add(field::mul(bit, field::sub(base_power, field::ONE)), field::ONE);
+    result.agg_constraint(1, op_flag, are_equal(new_stack[1], next_multiplier));
+
+    // power of 2 register was updated correctly
+    result.agg_constraint(2, op_flag, are_equal(new_stack[2], field::mul(power_of_two, 2)));
+
+    // result was multiplied by the multiplier from the previous step
+    result.agg_constraint(3, op_flag, are_equal(new_stack[3], field::mul(old_stack[3], multiplier)));
+
+    // binary representation of the exponent was accumulated correctly
+    let acc = field::add(old_stack[4], field::mul(bit, power_of_two));
+    result.agg_constraint(4, op_flag, are_equal(new_stack[4], acc));
+
+    // base power was squared
+    result.agg_constraint(5, op_flag, are_equal(new_stack[5], field::mul(base_power, base_power)));
+
+    // registers beyond the 6th register remained the same
+    enforce_stack_copy(result, old_stack, new_stack, 6, op_flag);
+}
@@ -35,6 +35,17 @@ pub fn enforce_asserteq(result: &mut [u128], aux: &mut [u128], old_stack: &[u128
     aux.agg_constraint(0, op_flag, are_equal(old_stack[0], old_stack[1]));
 }
 
+/// Enforces constraints for ASSERTEQW operation. The stack is shifted by 8 registers the left
+/// and auxiliary constraints enforce that the first 4 elements of the stack are equal to the
+/// next 4 elements.
+pub fn enforce_asserteqw(result: &mut [u128], aux: &mut [u128], old_stack: &[u128], new_stack: &[u128], op_flag: u128)
+{
+    enforce_left_shift(result, old_stack, new_stack, 8, 8, op_flag);
+    for i in 0..4 {
+        aux.agg_constraint(i, op_flag, are_equal(old_stack[i], old_stack[i + 4]));
+    }
+}
+
 // EQUALITY
 // ================================================================================================
 
@@ -1,22 +1,21 @@
 use crate::{
-    math::{ field, polynom },
+    math::field,
     processor::OpCode,
     stark::TraceState,
-    utils::hasher::ARK,
-    BASE_CYCLE_LENGTH, HASH_STATE_WIDTH
+    HASH_STATE_WIDTH
 };
 use super::utils::{
-    are_equal, is_zero, is_binary, binary_not, extend_constants, EvaluationResult,
+    are_equal, is_zero, is_binary, binary_not, EvaluationResult,
     enforce_stack_copy, enforce_left_shift, enforce_right_shift,
 };
 
 mod input;
-use input::{ enforce_push, enforce_read, enforce_read2 };
+use input::{ enforce_push, enforce_read, enforce_read2, enforce_clk };
 
 mod arithmetic;
 use arithmetic::{
     enforce_add, enforce_mul, enforce_inv, enforce_neg,
-    enforce_not, enforce_and, enforce_or,
+    enforce_not, enforce_and, enforce_or, enforce_expacc,
 };
 
 mod manipulation;
@@ -26,7 +25,9 @@ use manipulation::{
 };
 
 mod comparison;
-use comparison::{ enforce_assert, enforce_asserteq, enforce_eq, enforce_cmp, enforce_binacc };
+use comparison::{
+    enforce_assert, enforce_asserteq, enforce_asserteqw, enforce_eq, enforce_cmp, enforce_binacc,
+};
 
 mod conditional;
 use conditional::{ enforce_choose, enforce_choose2, enforce_cswap2 };
@@ -36,17 +37,14 @@ use hash::{ enforce_rescr };
 
 // CONSTANTS
 // ================================================================================================
-pub const NUM_AUX_CONSTRAINTS: usize = 2;
-const AUX_CONSTRAINT_DEGREES: [usize; NUM_AUX_CONSTRAINTS] = [7, 7];
+pub const NUM_AUX_CONSTRAINTS: usize = 4;
+const AUX_CONSTRAINT_DEGREES: [usize; NUM_AUX_CONSTRAINTS] = [7, 7, 7, 7];
 const STACK_TRANSITION_DEGREE: usize = 7; // degree for all stack register transition constraints
 
 // TYPES AND INTERFACES
 // ================================================================================================
+#[derive(Clone)]
 pub struct Stack {
-    trace_length        : usize,
-    cycle_length        : usize,
-    ark_values          : Vec<[u128; 2 * HASH_STATE_WIDTH]>,
-    ark_polys           : Vec<Vec<u128>>,
     constraint_degrees  : Vec<usize>,
 }
 
@@ -54,24 +52,17 @@ pub struct Stack {
 // ================================================================================================
 impl Stack {
 
-    pub fn new(trace_length: usize, extension_factor: usize, stack_depth: usize) -> Stack 
+    pub fn new(stack_depth: usize) -> Stack 
     {
         // build an array of constraint degrees for the stack
         let mut degrees = Vec::from(&AUX_CONSTRAINT_DEGREES[..]);
         degrees.resize(stack_depth + NUM_AUX_CONSTRAINTS, STACK_TRANSITION_DEGREE);
 
-        // determine extended cycle length
-        let cycle_length = BASE_CYCLE_LENGTH * extension_factor;
-
-        // extend rounds constants by the specified extension factor
-        let (ark_polys, ark_evaluations) = extend_constants(&ARK, extension_factor);
-        let ark_values = transpose_ark_constants(ark_evaluations, cycle_length);
+        return Stack { constraint_degrees: degrees };
+    }
 
-        return Stack {
-            trace_length, cycle_length,
-            ark_values, ark_polys,
-            constraint_degrees: degrees,
-        };
+    pub fn constraint_count(&self) -> usize {
+        return self.constraint_degrees.len();
     }
 
     pub fn constraint_degrees(&self) -> &[usize] {
@@ -81,40 +72,18 @@ impl Stack {
     // EVALUATOR FUNCTIONS
     // --------------------------------------------------------------------------------------------
 
-    /// Evaluates stack transition constraints at the specified step of the evaluation domain and
-    /// saves the evaluations into `result`.
-    pub fn evaluate(&self, current: &TraceState, next: &TraceState, step: usize, result: &mut [u128])
-    {
-        // determine round constants at the specified step
-        let ark = self.ark_values[step % self.cycle_length];
-
-        // evaluate transition constraints for the stack
-        enforce_constraints(current, next, &ark, result);
-    }
-
-    /// Evaluates stack transition constraints at the specified x coordinate and saves the
-    /// evaluations into `result`. Unlike the function above, this function can evaluate constraints
-    /// at any out-of-domain point, but it is much slower than the previous function.
-    pub fn evaluate_at(&self, current: &TraceState, next: &TraceState, x: u128, result: &mut [u128])
+    /// Evaluates stack transition constraints and saves the evaluations into `result`. Stack
+    /// constraints do not depend on periodic values, and so, the same function is used for the
+    /// points of the evaluation domain and for out-of-domain points.
+    pub fn evaluate(&self, current: &TraceState, next: &TraceState, result: &mut [u128])
     {
-        // map x to the corresponding coordinate in constant cycles
-        let num_cycles = (self.trace_length / BASE_CYCLE_LENGTH) as u128;
-        let x = field::exp(x, num_cycles);
-
-        // determine round constants at the specified x coordinate
-        let mut ark = [field::ZERO; 2 * HASH_STATE_WIDTH];
-        for i in 0..ark.len() {
-            ark[i] = polynom::eval(&self.ark_polys[i], x);
-        }
-
-        // evaluate transition constraints for the stack
-        enforce_constraints(current, next, &ark, result);
+        enforce_constraints(current, next, result);
     }
 }
 
 // HELPER FUNCTIONS
 // ================================================================================================
-fn enforce_constraints(current: &TraceState, next: &TraceState, ark: &[u128], result: &mut [u128])
+fn enforce_constraints(current: &TraceState, next: &TraceState, result: &mut [u128])
 {
     // split constraint evaluation result into aux constraints and stack constraints
     let (aux, result) = result.split_at_mut(NUM_AUX_CONSTRAINTS);
@@ -134,11 +103,20 @@ fn enforce_constraints(current: &TraceState, next: &TraceState, ark: &[u128], re
     // assertion operations
     enforce_assert  (&mut evaluations, aux, old_stack, new_stack, ld_flags[OpCode::Assert.ld_index()]);
     enforce_asserteq(&mut evaluations, aux, old_stack, new_stack, ld_flags[OpCode::AssertEq.ld_index()]);
+    enforce_asserteqw(&mut evaluations, aux, old_stack, new_stack, ld_flags[OpCode::AssertEqW.ld_index()]);
 
     // input operations
     enforce_read    (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Read.ld_index()]);
     enforce_read2   (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Read2.ld_index()]);
 
+    // environment operations
+    let op_counter = current.op_counter();
+    enforce_clk     (&mut evaluations,      old_stack, new_stack, op_counter, ld_flags[OpCode::Clk.ld_index()]);
+
+    // output operations; values written to the output tape are accumulated in an auxiliary
+    // register, and so, the stack only needs to drop the top item
+    enforce_drop    (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Write.ld_index()]);
+
     // stack manipulation operations
     enforce_dup     (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Dup.ld_index()]);
     enforce_dup2    (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::Dup2.ld_index()]);
@@ -163,6 +141,7 @@ fn enforce_constraints(current: &TraceState, next: &TraceState, ark: &[u128], re
     enforce_not     (&mut evaluations, aux, old_stack, new_stack, ld_flags[OpCode::Not.ld_index()]);
     enforce_and     (&mut evaluations, aux, old_stack, new_stack, ld_flags[OpCode::And.ld_index()]);
     enforce_or      (&mut evaluations, aux, old_stack, new_stack, ld_flags[OpCode::Or.ld_index()]);
+    enforce_expacc  (&mut evaluations,      old_stack, new_stack, ld_flags[OpCode::ExpAcc.ld_index()]);
     
     // comparison operations
     enforce_eq      (&mut evaluations, aux, old_stack, new_stack, ld_flags[OpCode::Eq.ld_index()]);
@@ -178,7 +157,7 @@ fn enforce_constraints(current: &TraceState, next: &TraceState, ark: &[u128], re
 
     enforce_push    (&mut evaluations,      old_stack, new_stack,      hd_flags[OpCode::Push.hd_index() ]);
     enforce_cmp     (&mut evaluations,      old_stack, new_stack,      hd_flags[OpCode::Cmp.hd_index()  ]);
-    enforce_rescr   (&mut evaluations,      old_stack, new_stack, ark, hd_flags[OpCode::RescR.hd_index()]);
+    enforce_rescr   (&mut evaluations,      old_stack, new_stack,      hd_flags[OpCode::RescR.hd_index()]);
 
     // 3 ----- enforce constraints for composite operations ---------------------------------------
 
@@ -193,15 +172,3 @@ fn enforce_constraints(current: &TraceState, next: &TraceState, ark: &[u128], re
     // 4 ----- copy evaluations into the result ---------------------------------------------------
     result.copy_from_slice(&evaluations[..result.len()]);
 }
-
-fn transpose_ark_constants(constants: Vec<Vec<u128>>, cycle_length: usize) -> Vec<[u128; 2 * HASH_STATE_WIDTH]>
-{
-    let mut values = Vec::new();
-    for i in 0..cycle_length {
-        values.push([field::ZERO; 2 * HASH_STATE_WIDTH]);
-        for j in 0..(2 * HASH_STATE_WIDTH) {
-            values[i][j] = constants[j][i];
-        }
-    }
-    return values;
-}
\ No newline at end of file
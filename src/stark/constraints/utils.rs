@@ -84,7 +84,10 @@ impl EvaluationResult for Vec<u128> {
 
 // CONSTANT INTERPOLATION AND EXTENSIONS
 // ================================================================================================
-pub fn extend_constants(constants: &[[u128; BASE_CYCLE_LENGTH]], extension_factor: usize) -> (Vec<Vec<u128>>, Vec<Vec<u128>>)
+/// Interpolates every cycle of `constants` into a polynomial, and evaluates the polynomial over
+/// the cycle domain extended by `extension_factor` and shifted by `offset`; returns the
+/// polynomials together with their evaluations.
+pub fn extend_constants(constants: &[[u128; BASE_CYCLE_LENGTH]], extension_factor: usize, offset: u128) -> (Vec<Vec<u128>>, Vec<Vec<u128>>)
 {
     let root = field::get_root_of_unity(BASE_CYCLE_LENGTH);
     let inv_twiddles = fft::get_inv_twiddles(root, BASE_CYCLE_LENGTH);
@@ -103,6 +106,7 @@ pub fn extend_constants(constants: &[[u128; BASE_CYCLE_LENGTH]], extension_facto
         polynom::interpolate_fft_twiddles(&mut extended_constant, &inv_twiddles, true);
         polys.push(extended_constant.clone());
 
+        polynom::shift_in_place(&mut extended_constant, offset);
         unsafe { extended_constant.set_len(extended_constant.capacity()); }
         polynom::eval_fft_twiddles(&mut extended_constant, &twiddles, true);
 
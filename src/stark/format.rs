@@ -0,0 +1,152 @@
+use serde::{ Serialize, Deserialize };
+use crate::{
+    math::field,
+    crypto::{ HashFunction, hash },
+    MAX_CONTEXT_DEPTH, MAX_LOOP_DEPTH, MAX_CALL_DEPTH, MAX_STACK_DEPTH, MAX_PUBLIC_INPUTS,
+    MIN_TRACE_LENGTH, BASE_CYCLE_LENGTH, HASH_STATE_WIDTH, HASH_NUM_ROUNDS, SPONGE_WIDTH,
+    HACC_NUM_ROUNDS, NUM_CF_OPS, NUM_LD_OPS, NUM_HD_OPS, HASHER_WIDTH, AUX_WIDTH,
+};
+use super::MAX_CONSTRAINT_DEGREE;
+
+// CONSTANTS
+// ================================================================================================
+
+/// Version of the format in which proofs and program ABIs are serialized; the version must be
+/// incremented whenever a change makes previously serialized values unreadable or invalid.
+pub const FORMAT_VERSION: u8 = 2;
+
+/// Identifier of the field over which all computations are performed; 0 stands for the field
+/// with modulus 2^128 - 45 * 2^40 + 1.
+pub const FIELD_ID: u8 = 0;
+
+/// Hash functions which can be referenced by a format header, listed in the order of their ids.
+pub const HASH_FUNCTIONS: [(&str, HashFunction); 5] = [
+    ("blake3",   hash::blake3),
+    ("sha3",     hash::sha3),
+    ("rescue",   hash::rescue),
+    ("poseidon", hash::poseidon),
+    ("gmimc",    hash::gmimc),
+];
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Header of serialized proofs and program ABIs. The header identifies the version of the
+/// format, the field and the hash function used, and contains a digest of the parameters of the
+/// VM (e.g. widths of the traces and the number of hash rounds); the verifier rejects values
+/// with unsupported headers before doing anything else with them.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FormatHeader {
+    version         : u8,
+    field_id        : u8,
+    hash_id         : u8,
+    parameter_digest: [u8; 32],
+}
+
+// FORMAT HEADER IMPLEMENTATION
+// ================================================================================================
+impl FormatHeader {
+
+    /// Returns a header of the current format for values which rely on the specified `hash_fn`.
+    pub fn new(hash_fn: HashFunction) -> FormatHeader {
+        let hash_id = hash_id(hash_fn).expect("unsupported hash function");
+        return FormatHeader {
+            version         : FORMAT_VERSION,
+            field_id        : FIELD_ID,
+            hash_id         : hash_id,
+            parameter_digest: parameter_digest(),
+        };
+    }
+
+    /// Returns a header with the specified components; the header is not checked.
+    pub fn from_parts(version: u8, field_id: u8, hash_id: u8, parameter_digest: [u8; 32]) -> FormatHeader {
+        return FormatHeader { version, field_id, hash_id, parameter_digest };
+    }
+
+    pub fn version(&self) -> u8 {
+        return self.version;
+    }
+
+    pub fn field_id(&self) -> u8 {
+        return self.field_id;
+    }
+
+    pub fn hash_id(&self) -> u8 {
+        return self.hash_id;
+    }
+
+    pub fn parameter_digest(&self) -> &[u8; 32] {
+        return &self.parameter_digest;
+    }
+
+    /// Checks that this header describes the current format for values which rely on the
+    /// specified `hash_fn`; all error messages start with "unsupported".
+    pub fn check(&self, hash_fn: HashFunction) -> Result<(), String> {
+        self.check_version()?;
+        if Some(self.hash_id) != hash_id(hash_fn) {
+            return Err(format!("unsupported hash function id {}; expected hash function {}",
+                self.hash_id, hash_name(hash_fn)));
+        }
+        return Ok(());
+    }
+
+    /// Checks that this header describes the current format without checking which hash
+    /// function it refers to; this is done before the rest of a serialized value is parsed.
+    pub fn check_version(&self) -> Result<(), String> {
+        if self.version != FORMAT_VERSION {
+            return Err(format!("unsupported format version {}; only version {} is supported",
+                self.version, FORMAT_VERSION));
+        }
+        if self.field_id != FIELD_ID {
+            return Err(format!("unsupported field id {}; only field id {} is supported",
+                self.field_id, FIELD_ID));
+        }
+        if self.hash_id as usize >= HASH_FUNCTIONS.len() {
+            return Err(format!("unsupported hash function id {}", self.hash_id));
+        }
+        if self.parameter_digest != parameter_digest() {
+            return Err(format!("unsupported VM parameters with digest {}; expected digest {}",
+                hex::encode(self.parameter_digest), hex::encode(parameter_digest())));
+        }
+        return Ok(());
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Returns the id of the specified hash function, or None if the function is not listed in
+/// `HASH_FUNCTIONS`.
+pub fn hash_id(hash_fn: HashFunction) -> Option<u8> {
+    return HASH_FUNCTIONS.iter()
+        .position(|(_, f)| *f as usize == hash_fn as usize)
+        .map(|id| id as u8);
+}
+
+/// Returns the name of the specified hash function.
+pub fn hash_name(hash_fn: HashFunction) -> &'static str {
+    return match hash_id(hash_fn) {
+        Some(id) => HASH_FUNCTIONS[id as usize].0,
+        None => "unknown",
+    };
+}
+
+/// Computes a digest of the parameters of the VM which affect validity of proofs and program
+/// hashes; the digest changes whenever any of these parameters changes.
+pub fn parameter_digest() -> [u8; 32] {
+    let parameters = [
+        MAX_CONTEXT_DEPTH, MAX_LOOP_DEPTH, MAX_CALL_DEPTH, MAX_STACK_DEPTH, MAX_PUBLIC_INPUTS,
+        MIN_TRACE_LENGTH, BASE_CYCLE_LENGTH, HASH_STATE_WIDTH, HASH_NUM_ROUNDS, SPONGE_WIDTH,
+        HACC_NUM_ROUNDS, NUM_CF_OPS, NUM_LD_OPS, NUM_HD_OPS, HASHER_WIDTH, AUX_WIDTH,
+        MAX_CONSTRAINT_DEGREE,
+    ];
+
+    let mut bytes = field::MODULUS.to_le_bytes().to_vec();
+    for &parameter in parameters.iter() {
+        bytes.extend_from_slice(&(parameter as u64).to_le_bytes());
+    }
+
+    let mut result = [0u8; 32];
+    hash::blake3(&bytes, &mut result);
+    return result;
+}
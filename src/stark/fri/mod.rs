@@ -8,9 +8,7 @@ mod prover;
 pub use prover::{ reduce, build_proof };
 
 mod verifier;
-pub use verifier::{ verify };
-
-const MAX_REMAINDER_LENGTH: usize = 256;
+pub use verifier::{ verify, draw_special_xs };
 
 // TYPES AND INTERFACES
 // ================================================================================================
@@ -26,6 +24,7 @@ pub struct FriLayer {
     pub root    : [u8; 32],
     pub values  : Vec<[u128; 4]>,
     pub nodes   : Vec<Vec<[u8; 32]>>,
+    pub cap     : Vec<[u8; 32]>,
     pub depth   : u8,
 }
 
@@ -34,7 +33,7 @@ pub struct FriLayer {
 #[cfg(test)]
 mod tests {
     use crate::math::{ field, polynom };
-    use crate::stark::{ ProofOptions, utils::compute_query_positions };
+    use crate::stark::{ ProofOptions, HashTranscript, utils::compute_query_positions };
 
     #[test]
     fn prove_verify() {
@@ -47,13 +46,15 @@ mod tests {
         let evaluations = build_random_poly_evaluations(domain_size, degree);
 
         // generate proof
-        let (fri_trees, fri_values) = super::reduce(&evaluations, &domain, &options);
+        let mut transcript = HashTranscript::new(options.hash_fn());
+        let (fri_trees, fri_values) = super::reduce(&evaluations, &domain, &mut transcript, &options);
         let positions = compute_query_positions(fri_trees[fri_trees.len() - 1].root(), domain_size, &options);
-        let proof = super::build_proof(fri_trees, fri_values, &positions);
+        let proof = super::build_proof(fri_trees, fri_values, &positions, &options);
+        let special_xs = super::draw_special_xs(&proof, &mut HashTranscript::new(options.hash_fn()));
 
         // verify proof
         let sampled_evaluations = positions.iter().map(|&i| evaluations[i]).collect::<Vec<u128>>();
-        let result = super::verify(&proof, &sampled_evaluations, &positions, degree, &options);
+        let result = super::verify(&proof, &sampled_evaluations, &positions, &special_xs, domain_size, degree, &options);
         assert_eq!(Ok(true), result);
     }
 
@@ -67,33 +68,87 @@ mod tests {
 
         // degree too low 1
         let evaluations = build_random_poly_evaluations(domain_size, degree);
-        let (fri_trees, fri_values) = super::reduce(&evaluations, &domain, &options);
+        let mut transcript = HashTranscript::new(options.hash_fn());
+        let (fri_trees, fri_values) = super::reduce(&evaluations, &domain, &mut transcript, &options);
         let positions = compute_query_positions(fri_trees[fri_trees.len() - 1].root(), domain_size, &options);
-        let proof = super::build_proof(fri_trees, fri_values, &positions);
+        let proof = super::build_proof(fri_trees, fri_values, &positions, &options);
+        let special_xs = super::draw_special_xs(&proof, &mut HashTranscript::new(options.hash_fn()));
 
         let sampled_evaluations = positions.iter().map(|&i| evaluations[i]).collect::<Vec<u128>>();
-        let result = super::verify(&proof, &sampled_evaluations, &positions, degree - 1, &options);
+        let result = super::verify(&proof, &sampled_evaluations, &positions, &special_xs, domain_size, degree - 4, &options);
         let err_msg = format!("remainder is not a valid degree {} polynomial", 14);
         assert_eq!(Err(err_msg), result);
 
         // degree too low 2
         let evaluations = build_random_poly_evaluations(domain_size, degree + 1);
-        let (fri_trees, fri_values) = super::reduce(&evaluations, &domain, &options);
+        let mut transcript = HashTranscript::new(options.hash_fn());
+        let (fri_trees, fri_values) = super::reduce(&evaluations, &domain, &mut transcript, &options);
         let positions = compute_query_positions(fri_trees[fri_trees.len() - 1].root(), domain_size, &options);
-        let proof = super::build_proof(fri_trees, fri_values, &positions);
+        let proof = super::build_proof(fri_trees, fri_values, &positions, &options);
+        let special_xs = super::draw_special_xs(&proof, &mut HashTranscript::new(options.hash_fn()));
 
         let sampled_evaluations = positions.iter().map(|&i| evaluations[i]).collect::<Vec<u128>>();
-        let result = super::verify(&proof, &sampled_evaluations, &positions, degree, &options);
+        let result = super::verify(&proof, &sampled_evaluations, &positions, &special_xs, domain_size, degree, &options);
         let err_msg = format!("remainder is not a valid degree {} polynomial", 15);
         assert_eq!(Err(err_msg), result);
 
         // invalid evaluations
         let sampled_evaluations = sampled_evaluations[1..].to_vec();
-        let result = super::verify(&proof, &sampled_evaluations, &positions, degree, &options);
+        let result = super::verify(&proof, &sampled_evaluations, &positions, &special_xs, domain_size, degree, &options);
         let err_msg = format!("evaluations did not match column value at depth 0");
         assert_eq!(Err(err_msg), result);
     }
 
+    #[test]
+    fn verify_remainder() {
+        let degree: usize = 63;
+        let domain_size: usize = 512;
+        let root = field::get_root_of_unity(domain_size);
+        let domain = field::get_power_series(root, domain_size);
+        let evaluations = build_random_poly_evaluations(domain_size, degree);
+
+        // when the remainder is larger than the domain, the proof consists of the remainder only
+        let options = ProofOptions::default().with_remainder_length(1024);
+        let mut transcript = HashTranscript::new(options.hash_fn());
+        let (fri_trees, fri_values) = super::reduce(&evaluations, &domain, &mut transcript, &options);
+        let positions = compute_query_positions(fri_trees[fri_trees.len() - 1].root(), domain_size, &options);
+        let proof = super::build_proof(fri_trees, fri_values, &positions, &options);
+        let special_xs = super::draw_special_xs(&proof, &mut HashTranscript::new(options.hash_fn()));
+        assert_eq!(0, proof.layers.len());
+        assert_eq!(domain_size, proof.rem_values.len());
+
+        let sampled_evaluations = positions.iter().map(|&i| evaluations[i]).collect::<Vec<u128>>();
+        let result = super::verify(&proof, &sampled_evaluations, &positions, &special_xs, domain_size, degree, &options);
+        assert_eq!(Ok(true), result);
+
+        // remainder must match its commitment
+        let mut modified = proof.clone();
+        modified.rem_values[1] = field::add(modified.rem_values[1], field::ONE);
+        let result = super::verify(&modified, &sampled_evaluations, &positions, &special_xs, domain_size, degree, &options);
+        assert_eq!(Err(String::from("remainder values do not match remainder commitment")), result);
+
+        // number of layers must match the remainder length from the options
+        let default_options = ProofOptions::default();
+        let result = super::verify(&proof, &sampled_evaluations, &positions, &special_xs, domain_size, degree, &default_options);
+        let err_msg = String::from("FRI proof is missing layers: 512 values remain after the last layer");
+        assert_eq!(Err(err_msg), result);
+    }
+
+    #[test]
+    fn canonical_positions() {
+        let domain_size: usize = 512;
+        let options = ProofOptions::default();
+
+        // query positions are distinct and sorted in ascending order
+        let positions = compute_query_positions(&[7; 32], domain_size, &options);
+        assert_eq!(options.num_queries(), positions.len());
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+
+        // positions in FRI layers are de-duplicated and sorted as well
+        let positions = vec![301, 44, 172, 3, 44];
+        assert_eq!(vec![3, 44, 45], super::utils::get_augmented_positions(&positions, domain_size));
+    }
+
     // TODO: add more tests
 
     fn build_random_poly_evaluations(domain_size: usize, degree: usize) -> Vec<u128> {
@@ -1,39 +1,52 @@
 use std::mem;
+use crossbeam_utils::thread;
 use crate::math::{ field, quartic };
-use crate::crypto::{ MerkleTree };
-use crate::stark::{ ProofOptions };
+use crate::crypto::{ MerkleTree, HashFunction };
+use crate::stark::{ ProofOptions, Transcript, utils::get_num_threads };
+use crate::utils::{ uninit_vector, as_bytes };
 
-use super::{ FriProof, FriLayer, utils, MAX_REMAINDER_LENGTH};
+use super::{ FriProof, FriLayer, utils };
 
 // PROVER FUNCTIONS
 // ================================================================================================
 
-pub fn reduce(evaluations: &[u128], domain: &[u128], options: &ProofOptions) -> (Vec<MerkleTree>, Vec<Vec<[u128; 4]>>) {
+pub fn reduce(evaluations: &[u128], domain: &[u128], transcript: &mut dyn Transcript, options: &ProofOptions) -> (Vec<MerkleTree>, Vec<Vec<[u128; 4]>>) {
     let mut tree_results: Vec<MerkleTree> = Vec::new();
     let mut value_results: Vec<Vec<[u128; 4]>> = Vec::new();
 
     // transpose evaluations into a matrix with 4 columns and put its rows into a Merkle tree
     let mut p_values = quartic::transpose(evaluations, 1);
-    let hashed_values = utils::hash_values(&p_values, options.hash_fn());
+    let num_threads = get_num_threads(p_values.len());
+    let hashed_values = hash_rows(&p_values, options.hash_fn(), num_threads);
     let mut p_tree = MerkleTree::new(hashed_values, options.hash_fn());
 
+    // the domain may be a coset of a subgroup; then, the domain of layer d is the coset of the
+    // subgroup of the layer size shifted by offset^(4^d), and its x coordinates are obtained by
+    // multiplying every 4^d-th element of the domain by offset^(4^d - 1)
+    let domain_offset = domain[0];
+    let mut layer_offset = domain_offset;
+
     // reduce the degree by 4 at each iteration until the remaining polynomial is small enough
-    while p_tree.leaves().len() * 4 > MAX_REMAINDER_LENGTH {
+    while p_tree.leaves().len() * 4 > options.remainder_length() {
 
-        // build polynomials from each row of the polynomial value matrix
-        let depth = tree_results.len() as u32;
-        let xs = quartic::transpose(domain, usize::pow(4, depth));
-        let polys = quartic::interpolate_batch(&xs, &p_values);
+        // commit to the current layer and select a pseudo-random x coordinate
+        transcript.absorb(b"fri_layer_root", p_tree.root());
+        let special_x = transcript.draw_element(b"fri_special_x");
 
-        // select a pseudo-random x coordinate and evaluate each row polynomial at that x
-        let special_x = field::prng(*p_tree.root());
-        let column = quartic::evaluate_batch(&polys, special_x);
+        // interpolate each row of the current layer into a polynomial and evaluate the
+        // polynomials at x; rows are independent of each other, so they are folded in parallel
+        let depth = tree_results.len() as u32;
+        let num_threads = get_num_threads(p_values.len());
+        let x_offset = field::div(layer_offset, domain_offset);
+        let column = fold_layer(&p_values, domain, usize::pow(4, depth), x_offset, special_x, num_threads);
+        layer_offset = field::exp(layer_offset, 4);
 
         // break the column in a polynomial value matrix for the next layer
         let mut c_values = quartic::transpose(&column, 1);
 
         // put the resulting matrix into a Merkle tree
-        let hashed_values = utils::hash_values(&c_values, options.hash_fn());
+        let num_threads = get_num_threads(c_values.len());
+        let hashed_values = hash_rows(&c_values, options.hash_fn(), num_threads);
         let mut c_tree = MerkleTree::new(hashed_values, options.hash_fn());
 
         // set p_tree = c_tree and p_values = c_values for the next iteration of the loop
@@ -46,25 +59,26 @@ pub fn reduce(evaluations: &[u128], domain: &[u128], options: &ProofOptions) ->
     }
 
     // add the tree at the last layer (the remainder)
+    transcript.absorb(b"fri_remainder_root", p_tree.root());
     tree_results.push(p_tree);
     value_results.push(p_values);
 
     return (tree_results, value_results);
 }
 
-pub fn build_proof(trees: Vec<MerkleTree>, values: Vec<Vec<[u128; 4]>>, positions: &[usize]) -> FriProof {
+pub fn build_proof(trees: Vec<MerkleTree>, values: Vec<Vec<[u128; 4]>>, positions: &[usize], options: &ProofOptions) -> FriProof {
     let mut positions = positions.to_vec();
     let mut domain_size = trees[0].leaves().len() * 4;
 
     // for all trees, except the last one, record tree root, authentication paths
-    // to row evaluations, and values for row evaluations
+    // to row evaluations together with tree caps, and values for row evaluations
     let mut layers = Vec::with_capacity(trees.len());
     for i in 0..(trees.len() - 1) {
         
         positions = utils::get_augmented_positions(&positions, domain_size);
 
         let tree = &trees[i];
-        let proof = tree.prove_batch(&positions);
+        let proof = tree.prove_batch_with_cap(&positions, options.cap_height());
         
         let mut queried_values: Vec<[u128; 4]> = Vec::with_capacity(positions.len());
         for &position in positions.iter() {
@@ -75,6 +89,7 @@ pub fn build_proof(trees: Vec<MerkleTree>, values: Vec<Vec<[u128; 4]>>, position
             root    : *tree.root(),
             values  : queried_values,
             nodes   : proof.nodes,
+            cap     : proof.cap,
             depth   : proof.depth
         });
         domain_size = domain_size / 4;
@@ -93,4 +108,91 @@ pub fn build_proof(trees: Vec<MerkleTree>, values: Vec<Vec<[u128; 4]>>, position
     }
 
     return FriProof { layers, rem_root: *last_tree.root(), rem_values: remainder };
-}
\ No newline at end of file
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Interpolates every row of the layer `values` into a degree 3 polynomial and evaluates the
+/// polynomials at `x`. X coordinates of row i are domain[(i + k * n) * stride] * offset for k in
+/// 0..4, where n is the number of rows. Rows are split into `num_threads` batches of equal size which
+/// are folded in separate threads; the result does not depend on the number of threads.
+fn fold_layer(values: &[[u128; 4]], domain: &[u128], stride: usize, offset: u128, x: u128, num_threads: usize) -> Vec<u128> {
+    let n = values.len();
+    assert!(n % num_threads == 0, "number of rows must be divisible by number of threads");
+    let batch_size = n / num_threads;
+
+    let mut result = uninit_vector(n);
+    thread::scope(|s| {
+        for (i, batch) in result.chunks_mut(batch_size).enumerate() {
+            let start = i * batch_size;
+            let ys = &values[start..(start + batch_size)];
+            s.spawn(move |_| {
+                let xs = (start..(start + batch_size)).map(|j| [
+                    field::mul(domain[j * stride], offset),
+                    field::mul(domain[(j + n) * stride], offset),
+                    field::mul(domain[(j + 2 * n) * stride], offset),
+                    field::mul(domain[(j + 3 * n) * stride], offset),
+                ]).collect::<Vec<[u128; 4]>>();
+                let polys = quartic::interpolate_batch(&xs, ys);
+                batch.copy_from_slice(&quartic::evaluate_batch(&polys, x));
+            });
+        }
+    }).unwrap();
+
+    return result;
+}
+
+/// Hashes every row of the layer `values` into a leaf of the layer tree; rows are split into
+/// `num_threads` batches which are hashed in separate threads.
+fn hash_rows(values: &[[u128; 4]], hash: HashFunction, num_threads: usize) -> Vec<[u8; 32]> {
+    let n = values.len();
+    assert!(n % num_threads == 0, "number of rows must be divisible by number of threads");
+    let batch_size = n / num_threads;
+
+    let mut result: Vec<[u8; 32]> = uninit_vector(n);
+    thread::scope(|s| {
+        for (leaves, rows) in result.chunks_mut(batch_size).zip(values.chunks(batch_size)) {
+            s.spawn(move |_| {
+                for (leaf, row) in leaves.iter_mut().zip(rows) {
+                    hash(as_bytes(row), leaf);
+                }
+            });
+        }
+    }).unwrap();
+
+    return result;
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::math::{ field, quartic };
+    use crate::crypto::hash;
+    use crate::stark::fri::utils;
+
+    #[test]
+    fn fold_layer() {
+        let n: usize = 1 << 10;
+        let domain = field::get_power_series(field::get_root_of_unity(n * 16), n * 16);
+        let values = quartic::to_quartic_vec(field::rand_vector(n * 4));
+        let x = field::rand();
+
+        // sequential folding as it is done by the verifier
+        let xs = quartic::transpose(&domain, 4);
+        let expected = quartic::evaluate_batch(&quartic::interpolate_batch(&xs, &values), x);
+
+        assert_eq!(expected, super::fold_layer(&values, &domain, 4, field::ONE, x, 1));
+        assert_eq!(expected, super::fold_layer(&values, &domain, 4, field::ONE, x, 8));
+    }
+
+    #[test]
+    fn hash_rows() {
+        let values = quartic::to_quartic_vec(field::rand_vector(1 << 10));
+        let expected = utils::hash_values(&values, hash::blake3);
+        assert_eq!(expected, super::hash_rows(&values, hash::blake3, 1));
+        assert_eq!(expected, super::hash_rows(&values, hash::blake3, 4));
+    }
+}
@@ -1,15 +1,14 @@
 use crate::crypto::{ HashFunction };
 use crate::utils::{ uninit_vector, as_bytes };
 
+/// Maps positions in a column of the specified length to positions of rows in the FRI layer
+/// tree, where every row holds 4 values of the column; the result is de-duplicated and sorted
+/// in ascending order.
 pub fn get_augmented_positions(positions: &[usize], column_length: usize) -> Vec<usize> {
     let row_length = column_length / 4;
-    let mut result = Vec::new();
-    for i in 0..positions.len() {
-        let ap = positions[i] % row_length;
-        if !result.contains(&ap) {
-            result.push(ap);
-        }
-    }    
+    let mut result = positions.iter().map(|&position| position % row_length).collect::<Vec<usize>>();
+    result.sort_unstable();
+    result.dedup();
     return result;
 }
 
@@ -1,22 +1,61 @@
 use std::mem;
 use crate::math::{ field, polynom, quartic };
 use crate::crypto::{ MerkleTree, BatchMerkleProof };
-use crate::stark::{ ProofOptions };
+use crate::stark::{ ProofOptions, Transcript };
 
 use super::{ FriProof, FriLayer, utils };
 
 // VERIFIER
 // ================================================================================================
 
+/// Absorbs roots of all FRI layers into the `transcript` in the same order as the prover does, and
+/// returns pseudo-random x coordinates drawn for every layer (except for the remainder).
+pub fn draw_special_xs(proof: &FriProof, transcript: &mut dyn Transcript) -> Vec<u128> {
+    let mut result = Vec::with_capacity(proof.layers.len());
+    for layer in proof.layers.iter() {
+        transcript.absorb(b"fri_layer_root", &layer.root);
+        result.push(transcript.draw_element(b"fri_special_x"));
+    }
+    transcript.absorb(b"fri_remainder_root", &proof.rem_root);
+    return result;
+}
+
+/// Verifies that `evaluations` at the specified `positions` of a domain of `domain_size` points
+/// (shifted by `options.domain_offset()`) belong to a polynomial of degree at most `max_degree`. Layers are folded until no more than
+/// `options.remainder_length()` values remain; the remainder must match its commitment, and its
+/// degree is checked directly by interpolating a subset of its values.
 pub fn verify(
     proof       : &FriProof,
     evaluations : &[u128],
     positions   : &[usize],
+    special_xs  : &[u128],
+    domain_size : usize,
     max_degree  : usize,
     options     : &ProofOptions) -> Result<bool, String>
 {
+    if special_xs.len() != proof.layers.len() {
+        return Err(format!("expected {} pseudo-random x coordinates, but received {}",
+            proof.layers.len(), special_xs.len()));
+    }
+
+    // the shape of the proof is fully determined by the domain size and the remainder length
+    let mut layer_size = domain_size;
+    for (depth, layer) in proof.layers.iter().enumerate() {
+        if layer_size <= options.remainder_length() {
+            return Err(format!("expected {} FRI layers, but received {}", depth, proof.layers.len()));
+        }
+        if usize::pow(2, layer.depth as u32) != layer_size / 4 {
+            return Err(format!("invalid Merkle tree depth {} at layer {}", layer.depth, depth));
+        }
+        layer_size = layer_size / 4;
+    }
+    if layer_size > options.remainder_length() {
+        return Err(format!("FRI proof is missing layers: {} values remain after the last layer", layer_size));
+    }
+    if proof.rem_values.len() != layer_size {
+        return Err(format!("expected {} remainder values, but received {}", layer_size, proof.rem_values.len()));
+    }
 
-    let domain_size = usize::pow(2, proof.layers[0].depth as u32) * 4;
     let domain_root = field::get_root_of_unity(domain_size);
 
     // powers of the given root of unity 1, p, p^2, p^3 such that p^4 = 1
@@ -29,6 +68,7 @@ pub fn verify(
 
     // 1 ----- verify the recursive components of the FRI proof -----------------------------------
     let mut domain_root = domain_root;
+    let mut domain_offset = options.domain_offset();
     let mut domain_size = domain_size;
     let mut max_degree_plus_1 = max_degree + 1;
     let mut positions = positions.to_vec();
@@ -37,6 +77,9 @@ pub fn verify(
     for (depth, layer) in proof.layers.iter().enumerate() {
 
         let mut augmented_positions = utils::get_augmented_positions(&positions, domain_size);
+        if layer.values.len() != augmented_positions.len() {
+            return Err(format!("unexpected number of queried values at depth {}", depth));
+        }
         let column_values = get_column_values(&layer.values, &positions, &augmented_positions, domain_size);
         if evaluations != column_values {
             return Err(format!("evaluations did not match column value at depth {}", depth));
@@ -44,14 +87,14 @@ pub fn verify(
 
         // verify Merkle proof for the layer
         let merkle_proof = build_layer_merkle_proof(&layer, options);
-        if !MerkleTree::verify_batch(&layer.root, &augmented_positions, &merkle_proof, options.hash_fn()) {
+        if !MerkleTree::verify_batch_with_cap(&layer.root, &augmented_positions, &merkle_proof, options.cap_height(), options.hash_fn()) {
             return Err(format!("verification of Merkle proof failed at layer {}", depth));
         }
 
         // build a set of x for each row polynomial
         let mut xs = Vec::with_capacity(augmented_positions.len());
         for &i in augmented_positions.iter() {
-            let xe = field::exp(domain_root, i as u128);
+            let xe = field::mul(domain_offset, field::exp(domain_root, i as u128));
             xs.push([
                 field::mul(quartic_roots[0], xe),
                 field::mul(quartic_roots[1], xe),
@@ -63,21 +106,30 @@ pub fn verify(
         // interpolate x and y values into row polynomials
         let row_polys = quartic::interpolate_batch(&xs, &layer.values);
 
-        // calculate the pseudo-random x coordinate
-        let special_x = field::prng(layer.root);
+        // get the pseudo-random x coordinate for this layer
+        let special_x = special_xs[depth];
 
         // check that when the polynomials are evaluated at x, the result is equal to the corresponding column value
         evaluations = quartic::evaluate_batch(&row_polys, special_x);
 
         // update variables for the next iteration of the loop
         domain_root = field::exp(domain_root, 4);
-        max_degree_plus_1 = max_degree_plus_1 / 4;
+        domain_offset = field::exp(domain_offset, 4);
+        // a polynomial with n coefficients is folded into a polynomial with ceil(n / 4) coefficients
+        max_degree_plus_1 = (max_degree_plus_1 + 3) / 4;
         domain_size = domain_size / 4;
         mem::swap(&mut positions, &mut augmented_positions);
     }
 
     // 2 ----- verify the remainder of the FRI proof ----------------------------------------------
-    
+
+    // the remainder is committed to in the same way as the other layers
+    let rem_values = quartic::transpose(&proof.rem_values, 1);
+    let rem_tree = MerkleTree::new(utils::hash_values(&rem_values, options.hash_fn()), options.hash_fn());
+    if *rem_tree.root() != proof.rem_root {
+        return Err(String::from("remainder values do not match remainder commitment"));
+    }
+
     for (&position, evaluation) in positions.iter().zip(evaluations) {
         if proof.rem_values[position] != evaluation {
             return Err(String::from("remainder values are inconsistent with values of the last column"));
@@ -85,10 +137,10 @@ pub fn verify(
     }
 
     // make sure the remainder values satisfy the degree
-    return verify_remainder(&proof.rem_values, max_degree_plus_1, domain_root, options.extension_factor());
+    return verify_remainder(&proof.rem_values, max_degree_plus_1, domain_root, domain_offset, options.extension_factor());
 }
 
-fn verify_remainder(remainder: &[u128], max_degree_plus_1: usize, domain_root: u128, extension_factor: usize) -> Result<bool, String> {
+fn verify_remainder(remainder: &[u128], max_degree_plus_1: usize, domain_root: u128, domain_offset: u128, extension_factor: usize) -> Result<bool, String> {
     if max_degree_plus_1 > remainder.len() {
         return Err(String::from("remainder degree is greater than number of remainder values"));
     }
@@ -102,7 +154,9 @@ fn verify_remainder(remainder: &[u128], max_degree_plus_1: usize, domain_root: u
     }
 
     // pick a subset of points from the remainder and interpolate them into a polynomial
-    let domain = field::get_power_series(domain_root, remainder.len());
+    let domain = field::get_power_series(domain_root, remainder.len()).into_iter()
+        .map(|x| field::mul(x, domain_offset))
+        .collect::<Vec<u128>>();
     let mut xs = Vec::with_capacity(max_degree_plus_1);
     let mut ys = Vec::with_capacity(max_degree_plus_1);
     for i in 0..max_degree_plus_1 {
@@ -142,6 +196,7 @@ fn build_layer_merkle_proof(layer: &FriLayer, options: &ProofOptions) -> BatchMe
     return BatchMerkleProof {
         values  : utils::hash_values(&layer.values, options.hash_fn()),
         nodes   : layer.nodes.clone(),
+        cap     : layer.cap.clone(),
         depth   : layer.depth
     };
 }
@@ -164,16 +219,16 @@ mod tests {
         polynom::eval_fft(&mut remainder, true);
 
         // check against exact degree
-        let result = super::verify_remainder(&remainder, degree_plus_1, root, extension_factor);
+        let result = super::verify_remainder(&remainder, degree_plus_1, root, field::ONE, extension_factor);
         assert_eq!(Ok(true), result);
 
         // check against higher degree
-        let result = super::verify_remainder(&remainder, degree_plus_1 + 1, root, extension_factor);
+        let result = super::verify_remainder(&remainder, degree_plus_1 + 1, root, field::ONE, extension_factor);
         assert_eq!(Ok(true), result);
 
         // check against lower degree
         let degree_plus_1 = degree_plus_1 - 1;
-        let result = super::verify_remainder(&remainder, degree_plus_1, root, extension_factor);
+        let result = super::verify_remainder(&remainder, degree_plus_1, root, field::ONE, extension_factor);
         let err_msg = format!("remainder is not a valid degree {} polynomial", degree_plus_1 - 1);
         assert_eq!(Err(err_msg), result);
     }
@@ -4,23 +4,30 @@ mod options;
 mod prover;
 mod verifier;
 mod proof;
-mod fri;
+mod format;
+pub mod fri;
 mod utils;
+mod transcript;
+mod pool;
 
 pub use trace::{ TraceTable, TraceState };
 
 pub use constraints::{
     ConstraintEvaluator,
     ConstraintTable,
-    ConstraintPoly };
+    ConstraintPoly,
+    AuxRegisters };
 
 pub use utils::{
     ConstraintCoefficients,
     CompositionCoefficients };
 
-pub use options::ProofOptions;
-pub use proof::{ StarkProof, DeepValues };
-pub use prover::{ prove };
-pub use verifier::{ verify };
+pub use options::{ ProofOptions, ProofStats, MinimumAcceptableOptions };
+pub use format::{ FormatHeader, FORMAT_VERSION };
+pub use proof::{ StarkProof, BatchProof, ProofShape, ProofSizeStats, TraceInfo, DeepValues };
+pub use prover::{ prove, prove_batch, ProverSetup, ProverContext, ProverStats };
+pub use verifier::{ verify, verify_batch, VerifierSetup, BoundaryValues };
+pub use transcript::{ Transcript, HashTranscript, MerlinTranscript };
+pub use pool::{ BufferPool };
 
 const MAX_CONSTRAINT_DEGREE : usize = 8;
\ No newline at end of file
@@ -1,5 +1,5 @@
 use serde::{ Serialize, Deserialize };
-use crate::crypto::{ HashFunction, hash };
+use crate::{ math::field, crypto::{ HashFunction, hash }, AUX_WIDTH };
 use super::MAX_CONSTRAINT_DEGREE;
 
 // CONSTANTS
@@ -7,21 +7,62 @@ use super::MAX_CONSTRAINT_DEGREE;
 const DEFAULT_EXTENSION_FACTOR: u8 = (MAX_CONSTRAINT_DEGREE * 4) as u8;
 const DEFAULT_NUM_QUERIES     : u8 = 50;
 const DEFAULT_GRINDING_FACTOR : u8 = 20;
+const DEFAULT_REMAINDER_LENGTH: u8 = 8;    // 2^8 = 256 values
+const DEFAULT_DOMAIN_OFFSET   : u128 = field::ONE;
+
+// bounds (as powers of 2) on the number of values in the FRI remainder
+const MIN_REMAINDER_LENGTH    : u8 = 4;
+const MAX_REMAINDER_LENGTH    : u8 = 10;
+
+// bound on the height of the caps of Merkle trees
+const MAX_CAP_HEIGHT          : u8 = 8;
+
+// sizes (in bytes) of proof components used in proof size estimates
+const HASH_SIZE               : usize = 32;
+const ELEMENT_SIZE            : usize = 16;
+const VEC_LENGTH_SIZE         : usize = 8;
+
+// proximity parameter used in the proven security bound
+const JOHNSON_PROXIMITY_PARAM : f64 = 3.0;
 
 // TYPES AND INTERFACES
 // ================================================================================================
 
-// TODO: validate field values on de-serialization
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ProofOptions {
     extension_factor    : u8,   // stored as power of 2
     num_queries         : u8,
     grinding_factor     : u8,
+    remainder_length    : u8,   // stored as power of 2
+    domain_offset       : u128,
+    cap_height          : u8,
 
     #[serde(with = "hash_fn_serialization")]
     hash_fn: HashFunction,
 }
 
+/// Weakest proof options which a verifier is willing to accept; see `verify_with_policy()`.
+/// Proof options are chosen by the prover, and so, without such a policy a prover could submit
+/// a proof generated with options which provide little security.
+#[derive(Clone)]
+pub struct MinimumAcceptableOptions {
+    pub field_modulus       : u128,
+    pub hash_fns            : Vec<HashFunction>,
+    pub num_queries         : usize,
+    pub extension_factor    : usize,
+    pub grinding_factor     : u32,
+}
+
+/// Estimated properties of a proof generated for an execution trace of a given size; see
+/// `ProofOptions::estimate()`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ProofStats {
+    pub proof_size          : usize,    // size of the serialized proof in bytes
+    pub prover_memory       : usize,    // memory needed to hold extended trace, trees etc. in bytes
+    pub security_level      : u32,      // conjectured security level in bits
+    pub proven_security     : u32,      // provable security level in bits
+}
+
 // PROOF OPTIONS IMPLEMENTATION
 // ================================================================================================
 impl ProofOptions {
@@ -45,10 +86,71 @@ impl ProofOptions {
             extension_factor    : extension_factor.trailing_zeros() as u8,
             num_queries         : num_queries as u8,
             grinding_factor     : grinding_factor as u8,
+            remainder_length    : DEFAULT_REMAINDER_LENGTH,
+            domain_offset       : DEFAULT_DOMAIN_OFFSET,
+            cap_height          : 0,
             hash_fn
         };
     }
 
+    /// Returns these options with FRI folding stopped once a layer has no more than
+    /// `remainder_length` values; the remaining values are sent in the proof and their degree is
+    /// checked by the verifier directly. Larger remainders mean fewer FRI layers, but every
+    /// remainder value is included in the proof; `estimate()` can be used to pick the length
+    /// which results in the smallest proofs for a given trace length and number of queries.
+    /// The length must be a power of 2 between 16 and 1024; the default is 256.
+    pub fn with_remainder_length(mut self, remainder_length: usize) -> ProofOptions {
+        assert!(remainder_length.is_power_of_two(), "remainder_length must be a power of 2");
+        assert!(remainder_length >= 1 << MIN_REMAINDER_LENGTH,
+            "remainder_length cannot be smaller than {}", 1 << MIN_REMAINDER_LENGTH);
+        assert!(remainder_length <= 1 << MAX_REMAINDER_LENGTH,
+            "remainder_length cannot be greater than {}", 1 << MAX_REMAINDER_LENGTH);
+
+        self.remainder_length = remainder_length.trailing_zeros() as u8;
+        return self;
+    }
+
+    /// Returns these options with the LDE domain shifted by `domain_offset`: execution trace and
+    /// constraint polynomials are evaluated over the coset offset * H, where H is the subgroup
+    /// of the domain size. Any offset which is not in the subgroup of order 2^40 (the largest
+    /// power-of-2 subgroup of the field) places the evaluation domain outside of the trace
+    /// domain regardless of trace length; provers whose offsets do not differ by a factor from
+    /// this subgroup commit to evaluations over disjoint cosets. The default offset is 1, in which
+    /// case the evaluation domain is the subgroup itself.
+    pub fn with_domain_offset(mut self, domain_offset: u128) -> ProofOptions {
+        assert!(ProofOptions::is_valid_domain_offset(domain_offset),
+            "domain offset {} is not a valid coset shift", domain_offset);
+
+        self.domain_offset = domain_offset;
+        return self;
+    }
+
+    /// Returns these options with all Merkle trees committed to by their caps at `cap_height`:
+    /// the 2^`cap_height` nodes which are `cap_height` levels below the root are sent in the
+    /// proof, and authentication paths end at these nodes rather than at the root. Thus, every
+    /// path is shorter by `cap_height` nodes; as queried paths share the top levels of a tree,
+    /// this results in smaller proofs only for heights above log2(num_queries) or so, and
+    /// `estimate()` can be used to find out. The roots of the trees remain the commitments from
+    /// which queries are drawn. The height cannot be greater than 8; the default is 0, in which
+    /// case paths go all the way up to the root.
+    pub fn with_cap_height(mut self, cap_height: u32) -> ProofOptions {
+        assert!(cap_height <= MAX_CAP_HEIGHT as u32, "cap height cannot be greater than {}", MAX_CAP_HEIGHT);
+
+        self.cap_height = cap_height as u8;
+        return self;
+    }
+
+    /// Returns true if the LDE domain could be shifted by `domain_offset`: the offset must be
+    /// either 1 or a field element outside of the subgroup of order 2^40.
+    pub fn is_valid_domain_offset(domain_offset: u128) -> bool {
+        if domain_offset == field::ONE {
+            return true;
+        }
+        return domain_offset != field::ZERO
+            && domain_offset < field::MODULUS
+            && field::exp(domain_offset, 1 << field::TWO_ADICITY) != field::ONE;
+    }
+
     pub fn extension_factor(&self) -> usize {
         return 1 << (self.extension_factor as usize)
     }
@@ -61,10 +163,57 @@ impl ProofOptions {
         return self.grinding_factor as u32;
     }
 
+    /// Returns the maximum number of values in the last layer of FRI.
+    pub fn remainder_length(&self) -> usize {
+        return 1 << (self.remainder_length as usize);
+    }
+
+    /// Returns the offset by which the LDE domain is shifted from the subgroup of its size.
+    pub fn domain_offset(&self) -> u128 {
+        return self.domain_offset;
+    }
+
+    /// Returns the height of the caps of Merkle trees; trees which are not tall enough are
+    /// capped at the parents of their leaves.
+    pub fn cap_height(&self) -> u32 {
+        return self.cap_height as u32;
+    }
+
     pub fn hash_fn(&self) -> HashFunction {
         return self.hash_fn;
     }
 
+    /// Checks that these options could have been built by `ProofOptions::new()`,
+    /// `with_remainder_length()`, `with_domain_offset()`, and `with_cap_height()`; options which
+    /// are de-serialized from untrusted bytes should be validated before they are used.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.extension_factor < 4 || self.extension_factor > 8 {
+            return Err(format!("invalid extension factor 2^{}", self.extension_factor));
+        }
+
+        if self.num_queries == 0 || self.num_queries > 128 {
+            return Err(format!("invalid number of queries {}", self.num_queries));
+        }
+
+        if self.grinding_factor > 32 {
+            return Err(format!("invalid grinding factor {}", self.grinding_factor));
+        }
+
+        if self.remainder_length < MIN_REMAINDER_LENGTH || self.remainder_length > MAX_REMAINDER_LENGTH {
+            return Err(format!("invalid FRI remainder length 2^{}", self.remainder_length));
+        }
+
+        if !ProofOptions::is_valid_domain_offset(self.domain_offset) {
+            return Err(format!("invalid domain offset {}", self.domain_offset));
+        }
+
+        if self.cap_height > MAX_CAP_HEIGHT {
+            return Err(format!("invalid Merkle cap height {}", self.cap_height));
+        }
+
+        return Ok(());
+    }
+
     pub fn security_level(&self, optimistic: bool) -> u32 {
         let one_over_rho = (self.extension_factor() / MAX_CONSTRAINT_DEGREE) as u32;
         let security_factor = 31 - one_over_rho.leading_zeros(); // same as log2(one_over_rho)
@@ -77,6 +226,136 @@ impl ProofOptions {
 
         return result;
     }
+
+    /// Returns the rate of the code used for the composition polynomial; the degree of the
+    /// polynomial is roughly (MAX_CONSTRAINT_DEGREE - 1) * trace length, and it is evaluated
+    /// over a domain of extension_factor * trace length elements.
+    fn rate(&self) -> f64 {
+        return (MAX_CONSTRAINT_DEGREE - 1) as f64 / self.extension_factor() as f64;
+    }
+
+    /// Returns conjectured security level (in bits) of a proof generated with these options for
+    /// an execution trace of `trace_length` steps. This follows the conjecture used in ethSTARK
+    /// analysis: every FRI query contributes log2(1 / rho) bits of security (rho being the rate
+    /// of the code), and grinding adds `grinding_factor` bits. The result is capped by the bits
+    /// of security lost due to field size: log2(|F|) - log2(domain size); this accounts for the
+    /// probability that the DEEP point falls into the evaluation domain.
+    pub fn conjectured_security(&self, trace_length: usize) -> u32 {
+        let domain_size = (trace_length * self.extension_factor()) as f64;
+        let field_security = field_bits() - domain_size.log2();
+
+        let query_security = self.num_queries() as f64 * (1.0 / self.rate()).log2()
+            + self.grinding_factor() as f64;
+
+        return f64::min(field_security, query_security).floor() as u32;
+    }
+
+    /// Returns provable security level (in bits) of a proof generated with these options for an
+    /// execution trace of `trace_length` steps. This follows the analysis of FRI soundness in the
+    /// list-decoding regime (up to the Johnson bound) from ethSTARK documentation with proximity
+    /// parameter m = 3; the soundness error is bounded by:
+    ///
+    ///   (m + 1/2)^7 / (3 * rho^(3/2)) * n^2 / |F| + (sqrt(rho) * (1 + 1 / 2m))^q
+    ///
+    /// where n is the size of the evaluation domain and q is the number of queries. Grinding adds
+    /// `grinding_factor` bits to the second term only.
+    pub fn proven_security(&self, trace_length: usize) -> u32 {
+        let m = JOHNSON_PROXIMITY_PARAM;
+        let rho = self.rate();
+        let domain_size = (trace_length * self.extension_factor()) as f64;
+
+        let commit_error = (m + 0.5).powi(7) / (3.0 * rho.powf(1.5)) * domain_size.powi(2);
+        let field_security = field_bits() - commit_error.log2();
+
+        let query_error = rho.sqrt() * (1.0 + 1.0 / (2.0 * m));
+        let query_security = -(self.num_queries() as f64) * query_error.log2()
+            + self.grinding_factor() as f64;
+
+        return f64::max(0.0, f64::min(field_security, query_security)).floor() as u32;
+    }
+
+    /// Estimates size of a proof, amount of memory needed by the prover, and security level of
+    /// a proof generated with these options for an execution trace of `trace_length` steps and
+    /// `trace_width` registers (not counting auxiliary registers). This makes it possible to
+    /// compare different options without generating proofs. Proof size is an upper bound as it
+    /// assumes that authentication paths for queried positions share only the top nodes of
+    /// Merkle trees (or end at their caps); prover memory accounts only for the largest data
+    /// structures.
+    pub fn estimate(&self, trace_length: usize, trace_width: usize) -> ProofStats {
+        assert!(trace_length.is_power_of_two(), "trace length must be a power of 2");
+        assert!(trace_width > 0, "trace width must be greater than 0");
+
+        let domain_size = trace_length * self.extension_factor();
+        let domain_depth = domain_size.trailing_zeros() as usize;
+
+        // 1 ----- estimate proof size ------------------------------------------------------------
+
+        // trace and auxiliary trace states at queried positions, together with their
+        // authentication paths
+        let mut proof_size = self.estimate_queries_size(trace_width * ELEMENT_SIZE, domain_depth);
+        proof_size += self.estimate_queries_size(AUX_WIDTH * ELEMENT_SIZE, domain_depth);
+
+        // constraint evaluations at queried positions; 2 evaluations are stored per leaf
+        proof_size += self.estimate_queries_size(HASH_SIZE, domain_depth - 1);
+
+        // trace states at DEEP points z and z * g, and constraint evaluation at z
+        proof_size += 2 * (VEC_LENGTH_SIZE + (trace_width + AUX_WIDTH) * ELEMENT_SIZE) + ELEMENT_SIZE;
+
+        // FRI layers are reduced by a factor of 4 until the remainder is small enough; every
+        // layer holds 4 evaluations per leaf
+        let mut layer_size = domain_size;
+        while layer_size > self.remainder_length() {
+            let layer_depth = (layer_size / 4).trailing_zeros() as usize;
+            proof_size += HASH_SIZE + 1 + self.estimate_queries_size(4 * ELEMENT_SIZE, layer_depth);
+            layer_size = layer_size / 4;
+        }
+        proof_size += HASH_SIZE + VEC_LENGTH_SIZE + layer_size * ELEMENT_SIZE;
+
+        // trace, auxiliary, and constraint roots, trace info, nonce, and proof options
+        proof_size += 3 * HASH_SIZE + 8 + 8 + 4 + ELEMENT_SIZE;
+
+        // 2 ----- estimate prover memory ---------------------------------------------------------
+
+        // extended main and auxiliary traces, and their Merkle trees
+        let mut prover_memory = (trace_width + AUX_WIDTH) * domain_size * ELEMENT_SIZE;
+        prover_memory += 2 * (2 * domain_size * HASH_SIZE);
+
+        // constraint evaluations over the evaluation domain and the constraint Merkle tree
+        prover_memory += 3 * trace_length * MAX_CONSTRAINT_DEGREE * ELEMENT_SIZE;
+        prover_memory += domain_size * ELEMENT_SIZE + domain_size * HASH_SIZE;
+
+        // composition polynomial evaluations and FRI layers
+        prover_memory += domain_size * ELEMENT_SIZE;
+        prover_memory += (domain_size * ELEMENT_SIZE + domain_size / 2 * HASH_SIZE) * 4 / 3;
+
+        return ProofStats {
+            proof_size      : proof_size,
+            prover_memory   : prover_memory,
+            security_level  : self.conjectured_security(trace_length),
+            proven_security : self.proven_security(trace_length),
+        };
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+
+    /// Estimates the size of values at `num_queries` positions of a Merkle tree of the specified
+    /// `depth` together with a batch authentication path for these positions; values at each
+    /// position are assumed to take up `value_size` bytes.
+    fn estimate_queries_size(&self, value_size: usize, depth: usize) -> usize {
+        let num_queries = self.num_queries();
+
+        // the top log2(num_queries) levels of the tree are assumed to be shared by all paths,
+        // and paths end at the cap of the tree
+        let shared_depth = (usize::BITS - num_queries.leading_zeros()) as usize - 1;
+        let cap_height = std::cmp::min(self.cap_height(), depth as u32 - 1) as usize;
+        let path_length = depth.saturating_sub(std::cmp::max(shared_depth, cap_height));
+
+        let values_size = VEC_LENGTH_SIZE + num_queries * (VEC_LENGTH_SIZE + value_size);
+        let nodes_size = VEC_LENGTH_SIZE + num_queries * (VEC_LENGTH_SIZE + path_length * HASH_SIZE);
+        let cap_size = VEC_LENGTH_SIZE + if cap_height == 0 { 0 } else { (1 << cap_height) * HASH_SIZE };
+        return values_size + nodes_size + cap_size;
+    }
 }
 
 impl Default for ProofOptions {
@@ -86,12 +365,73 @@ impl Default for ProofOptions {
             extension_factor: DEFAULT_EXTENSION_FACTOR.trailing_zeros() as u8,
             num_queries     : DEFAULT_NUM_QUERIES,
             grinding_factor : DEFAULT_GRINDING_FACTOR,
+            remainder_length: DEFAULT_REMAINDER_LENGTH,
+            domain_offset   : DEFAULT_DOMAIN_OFFSET,
+            cap_height      : 0,
             hash_fn         : hash::blake3,
         };
     }
 
 }
 
+// MINIMUM ACCEPTABLE OPTIONS IMPLEMENTATION
+// ================================================================================================
+impl MinimumAcceptableOptions {
+
+    /// Checks that the specified proof `options` are at least as strong as the options defined
+    /// by this policy; returns an error describing the first violation otherwise. The proof
+    /// must also be generated over the field specified by the policy; currently, all proofs are
+    /// generated over a single 128-bit field.
+    pub fn check(&self, options: &ProofOptions) -> Result<(), String> {
+        if self.field_modulus != field::MODULUS {
+            return Err(format!("proof field modulus {} is not accepted", field::MODULUS));
+        }
+
+        if !self.hash_fns.iter().any(|&hash_fn| hash_fn as usize == options.hash_fn() as usize) {
+            return Err(String::from("proof hash function is not accepted"));
+        }
+
+        if options.num_queries() < self.num_queries {
+            return Err(format!("proof uses {} queries, but at least {} are required",
+                options.num_queries(), self.num_queries));
+        }
+
+        if options.extension_factor() < self.extension_factor {
+            return Err(format!("proof uses extension factor {}, but at least {} is required",
+                options.extension_factor(), self.extension_factor));
+        }
+
+        if options.grinding_factor() < self.grinding_factor {
+            return Err(format!("proof uses grinding factor {}, but at least {} is required",
+                options.grinding_factor(), self.grinding_factor));
+        }
+
+        return Ok(());
+    }
+}
+
+impl Default for MinimumAcceptableOptions {
+
+    /// Returns a policy which accepts proofs generated with default proof options or stronger.
+    fn default() -> MinimumAcceptableOptions {
+        return MinimumAcceptableOptions {
+            field_modulus   : field::MODULUS,
+            hash_fns        : vec![hash::blake3],
+            num_queries     : DEFAULT_NUM_QUERIES as usize,
+            extension_factor: DEFAULT_EXTENSION_FACTOR as usize,
+            grinding_factor : DEFAULT_GRINDING_FACTOR as u32,
+        };
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Returns log2 of the field modulus.
+fn field_bits() -> f64 {
+    return (field::MODULUS as f64).log2();
+}
+
 // HASH FUNCTION SERIALIZATION / DE-SERIALIZATION
 // ================================================================================================
 mod hash_fn_serialization {
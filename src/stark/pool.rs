@@ -0,0 +1,141 @@
+use crate::{ math::field, utils::zeroize, AUX_WIDTH };
+
+// CONSTANTS
+// ================================================================================================
+
+/// number of buffers needed by the prover in addition to the buffers for trace registers: 3 for
+/// constraint evaluations and 1 for the combined constraint polynomial; buffers for constraint
+/// evaluations are released once they are combined, and are then re-used for evaluations of the
+/// constraint polynomial and for the composition polynomial
+const NUM_CONSTRAINT_BUFFERS: usize = 4;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// A pool of equally-sized buffers used by the prover to hold extended trace registers, constraint
+/// evaluations, and other values defined over the LDE domain. All buffers needed to generate a
+/// proof are allocated up front when the pool is created; a buffer is allocated on demand only
+/// when the pool runs out of buffers.
+pub struct BufferPool {
+    buffers         : Vec<Vec<u128>>,
+    buffer_size     : usize,
+    num_allocated   : usize,
+}
+
+// BUFFER POOL IMPLEMENTATION
+// ================================================================================================
+impl BufferPool {
+
+    /// Returns a pool with enough buffers to generate a proof for an execution trace with the
+    /// specified `trace_length` and `trace_width` (number of main registers) when the trace is
+    /// extended by the specified `extension_factor`.
+    pub fn new(trace_length: usize, trace_width: usize, extension_factor: usize) -> BufferPool {
+        let buffer_size = trace_length * extension_factor;
+        let num_buffers = trace_width + AUX_WIDTH + NUM_CONSTRAINT_BUFFERS;
+        let buffers = (0..num_buffers).map(|_| Vec::with_capacity(buffer_size)).collect();
+        return BufferPool { buffers, buffer_size, num_allocated: num_buffers };
+    }
+
+    /// Returns the number of elements which can fit into a single buffer of the pool.
+    pub fn buffer_size(&self) -> usize {
+        return self.buffer_size;
+    }
+
+    /// Returns the number of buffers currently available in the pool.
+    #[cfg(test)]
+    pub fn available(&self) -> usize {
+        return self.buffers.len();
+    }
+
+    /// Returns the total number of buffers allocated by the pool, including buffers which were
+    /// allocated up front.
+    pub fn num_allocated(&self) -> usize {
+        return self.num_allocated;
+    }
+
+    /// Takes a buffer from the pool and resizes it to `length` elements all set to ZERO.
+    pub fn take(&mut self, length: usize) -> Vec<u128> {
+        let mut buffer = self.take_empty(length);
+        buffer.resize(length, field::ZERO);
+        return buffer;
+    }
+
+    /// Takes a buffer from the pool and sets its length to `length` elements without
+    /// initializing them; the buffer may contain values left over from its previous use.
+    pub fn take_uninit(&mut self, length: usize) -> Vec<u128> {
+        let mut buffer = self.take_empty(length);
+        unsafe { buffer.set_len(length); }
+        return buffer;
+    }
+
+    /// Returns the `buffer` to the pool; buffers which were not taken from a pool of the same
+    /// buffer size are dropped. Since buffers may hold values derived from secret inputs, the
+    /// buffer is zeroized before it is returned to the pool.
+    pub fn release(&mut self, mut buffer: Vec<u128>) {
+        zeroize(&mut buffer);
+        if buffer.capacity() == self.buffer_size {
+            self.buffers.push(buffer);
+        }
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+    fn take_empty(&mut self, length: usize) -> Vec<u128> {
+        assert!(length <= self.buffer_size,
+            "cannot take a buffer of {} elements from a pool of {}-element buffers", length, self.buffer_size);
+        return match self.buffers.pop() {
+            Some(buffer) => buffer,
+            None => {
+                self.num_allocated += 1;
+                Vec::with_capacity(self.buffer_size)
+            }
+        };
+    }
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::AUX_WIDTH;
+    use super::{ BufferPool, NUM_CONSTRAINT_BUFFERS };
+
+    #[test]
+    fn take_and_release() {
+        let mut pool = BufferPool::new(16, 2, 4);
+        let num_buffers = 2 + AUX_WIDTH + NUM_CONSTRAINT_BUFFERS;
+        assert_eq!(64, pool.buffer_size());
+        assert_eq!(num_buffers, pool.available());
+
+        // taken buffers are zeroed and have the requested length
+        let mut buffer = pool.take(32);
+        assert_eq!(vec![0; 32], buffer);
+        assert_eq!(64, buffer.capacity());
+        assert_eq!(num_buffers - 1, pool.available());
+
+        // released buffers are re-used
+        buffer[0] = 7;
+        pool.release(buffer);
+        assert_eq!(num_buffers, pool.available());
+        assert_eq!(vec![0; 64], pool.take(64));
+
+        // buffers of a different size are not accepted
+        pool.release(vec![0; 8]);
+        assert_eq!(num_buffers - 1, pool.available());
+
+        // once the pool is empty, new buffers are allocated on demand
+        let buffers: Vec<Vec<u128>> = (0..num_buffers).map(|_| pool.take_uninit(64)).collect();
+        assert_eq!(0, pool.available());
+        assert_eq!(num_buffers + 1, pool.num_allocated());
+        for buffer in buffers { pool.release(buffer); }
+        assert_eq!(num_buffers, pool.available());
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot take a buffer of 65 elements")]
+    fn take_too_long() {
+        let mut pool = BufferPool::new(16, 2, 4);
+        pool.take(65);
+    }
+}
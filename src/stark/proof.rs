@@ -1,7 +1,17 @@
+use std::io::{ Read, Cursor };
 use serde::{ Serialize, Deserialize };
+use bincode::Options;
+use crate::math::field;
 use crate::crypto::{ BatchMerkleProof };
-use crate::stark::{ fri::FriProof, TraceState, ProofOptions };
+use crate::stark::{ fri::FriProof, TraceState, ProofOptions, FormatHeader };
 use crate::utils::{ uninit_vector, as_bytes };
+use crate::HASHER_WIDTH;
+
+// CONSTANTS
+// ================================================================================================
+
+/// Size of a serialized format header: format version, field and hash ids, and parameter digest.
+const HEADER_SIZE: usize = 3 + 32;
 
 // TYPES AND INTERFACES
 // ================================================================================================
@@ -9,15 +19,23 @@ use crate::utils::{ uninit_vector, as_bytes };
 // TODO: custom serialization should reduce size by 5% - 10%
 #[derive(Clone, Serialize, Deserialize)]
 pub struct StarkProof {
+    format              : FormatHeader,
     trace_root          : [u8; 32],
     trace_info          : TraceInfo,
     trace_nodes         : Vec<Vec<[u8; 32]>>,
+    trace_cap           : Vec<[u8; 32]>,
     trace_evaluations   : Vec<Vec<u128>>,
+    aux_root            : [u8; 32],
+    aux_nodes           : Vec<Vec<[u8; 32]>>,
+    aux_cap             : Vec<[u8; 32]>,
+    aux_evaluations     : Vec<Vec<u128>>,
     constraint_root     : [u8; 32],
     constraint_proof    : BatchMerkleProof,
     deep_values         : DeepValues,
+    constraint_at_z     : u128,
     degree_proof        : FriProof,
     pow_nonce           : u64,
+    output_tape         : Vec<u128>,
     options             : ProofOptions
 }
 
@@ -27,15 +45,72 @@ pub struct DeepValues {
     pub trace_at_z2     : Vec<u128>,
 }
 
+/// A proof of execution of several programs generated together. Execution traces of all programs
+/// are padded to the same length and are placed next to each other in a single trace table, so
+/// that all programs share trace, auxiliary, and constraint commitments, a single low-degree
+/// proof, and the same set of queries. Values of trace registers and output tapes are recorded
+/// for each program separately in the order in which the programs were executed.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BatchProof {
+    format              : FormatHeader,
+    trace_root          : [u8; 32],
+    trace_info          : Vec<TraceInfo>,
+    trace_nodes         : Vec<Vec<[u8; 32]>>,
+    trace_cap           : Vec<[u8; 32]>,
+    trace_evaluations   : Vec<Vec<u128>>,
+    aux_root            : [u8; 32],
+    aux_nodes           : Vec<Vec<[u8; 32]>>,
+    aux_cap             : Vec<[u8; 32]>,
+    aux_evaluations     : Vec<Vec<u128>>,
+    constraint_root     : [u8; 32],
+    constraint_proof    : BatchMerkleProof,
+    deep_values         : Vec<DeepValues>,
+    constraint_at_z     : u128,
+    degree_proof        : FriProof,
+    pow_nonce           : u64,
+    output_tapes        : Vec<Vec<u128>>,
+    options             : ProofOptions
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TraceInfo {
     pub domain_depth    : u8,
     pub ctx_depth       : u8,
     pub loop_depth      : u8,
+    pub call_depth      : u8,
     pub stack_depth     : u8,
     pub op_count        : u32,
 }
 
+/// Shape of a proof: length of the execution trace and depths of the stacks of the VM. Values
+/// used by the verifier which depend only on the shape of a proof can be computed once and then
+/// re-used for all proofs of the same shape.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ProofShape {
+    pub trace_length    : usize,
+    pub ctx_depth       : usize,
+    pub loop_depth      : usize,
+    pub call_depth      : usize,
+    pub stack_depth     : usize,
+}
+
+/// Breakdown of the size of a serialized proof by component; all sizes are in bytes, and add up
+/// to the size of the proof returned by `StarkProof::to_bytes()`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ProofSizeStats {
+    pub total_size          : usize,
+    pub trace_openings      : usize,    // trace and auxiliary trace states at queried positions, their Merkle paths and caps
+    pub constraint_openings : usize,    // constraint evaluations at queried positions, their Merkle paths and cap
+    pub deep_values         : usize,    // trace states at z and z * g, and constraint evaluation at z
+    pub fri_layers          : usize,    // queried values of all FRI layers, their Merkle paths and caps
+    pub fri_remainder       : usize,    // values of the last FRI layer and their commitment
+    pub pow_nonce           : usize,
+    pub output_tape         : usize,
+    pub other               : usize,    // format header, commitments, trace info, and proof options
+    pub num_queries         : usize,
+    pub num_fri_layers      : usize,
+}
+
 // STARK PROOF IMPLEMENTATION
 // ================================================================================================
 impl StarkProof {
@@ -43,14 +118,20 @@ impl StarkProof {
         trace_root          : &[u8; 32],
         trace_proof         : BatchMerkleProof,
         trace_evaluations   : Vec<Vec<u128>>,
+        aux_root            : &[u8; 32],
+        aux_proof           : BatchMerkleProof,
+        aux_evaluations     : Vec<Vec<u128>>,
         constraint_root     : &[u8; 32],
         constraint_proof    : BatchMerkleProof,
         deep_values         : DeepValues,
+        constraint_at_z     : u128,
         degree_proof        : FriProof,
         pow_nonce           : u64,
         op_count            : u128,
+        output_tape         : Vec<u128>,
         ctx_depth           : usize,
         loop_depth          : usize,
+        call_depth          : usize,
         stack_depth         : usize,
         options             : &ProofOptions ) -> StarkProof
     {
@@ -58,20 +139,29 @@ impl StarkProof {
             domain_depth        : trace_proof.depth,
             ctx_depth           : ctx_depth as u8,
             loop_depth          : loop_depth as u8,
+            call_depth          : call_depth as u8,
             stack_depth         : stack_depth as u8,
             op_count            : op_count as u32,
         };
 
         return StarkProof {
+            format              : FormatHeader::new(options.hash_fn()),
             trace_root          : *trace_root,
             trace_info          : trace_info,
             trace_nodes         : trace_proof.nodes,
+            trace_cap           : trace_proof.cap,
             trace_evaluations   : trace_evaluations,
+            aux_root            : *aux_root,
+            aux_nodes           : aux_proof.nodes,
+            aux_cap             : aux_proof.cap,
+            aux_evaluations     : aux_evaluations,
             constraint_root     : *constraint_root,
             constraint_proof    : constraint_proof,
             deep_values         : deep_values,
+            constraint_at_z     : constraint_at_z,
             degree_proof        : degree_proof,
             pow_nonce           : pow_nonce,
+            output_tape         : output_tape,
             options             : options.clone()
         };
     }
@@ -84,23 +174,29 @@ impl StarkProof {
         return &self.options;
     }
 
+    /// Returns the header describing the format of this proof.
+    pub fn format(&self) -> &FormatHeader {
+        return &self.format;
+    }
+
     pub fn domain_size(&self) -> usize {
-        return usize::pow(2, self.trace_info.domain_depth as u32);
+        return self.trace_info.domain_size();
+    }
+
+    pub fn trace_info(&self) -> &TraceInfo {
+        return &self.trace_info;
     }
 
     pub fn trace_proof(&self) -> BatchMerkleProof {
+        return self.build_batch_proof(&self.trace_nodes, &self.trace_cap, &self.trace_evaluations);
+    }
 
-        let hash = self.options.hash_fn();
-        let mut hashed_states = uninit_vector::<[u8; 32]>(self.trace_evaluations.len());
-        for i in 0..self.trace_evaluations.len() {
-            hash(as_bytes(&self.trace_evaluations[i]), &mut hashed_states[i]);
-        }
+    pub fn aux_root(&self) -> &[u8; 32] {
+        return &self.aux_root;
+    }
 
-        return BatchMerkleProof {
-            nodes   : self.trace_nodes.clone(),
-            values  : hashed_states,
-            depth   : self.trace_info.domain_depth,
-         };
+    pub fn aux_proof(&self) -> BatchMerkleProof {
+        return self.build_batch_proof(&self.aux_nodes, &self.aux_cap, &self.aux_evaluations);
     }
 
     pub fn constraint_root(&self) -> &[u8; 32] {
@@ -111,6 +207,12 @@ impl StarkProof {
         return self.constraint_proof.clone();
     }
 
+    /// Returns the evaluation of the constraint polynomial at the DEEP point z; the verifier
+    /// checks that this value is consistent with trace states at z and z * g.
+    pub fn constraint_at_z(&self) -> u128 {
+        return self.constraint_at_z;
+    }
+
     pub fn degree_proof(&self) -> &FriProof {
         return &self.degree_proof;
     }
@@ -119,10 +221,136 @@ impl StarkProof {
         return &self.trace_evaluations;
     }
 
+    pub fn aux_evaluations(&self) -> &[Vec<u128>] {
+        return &self.aux_evaluations;
+    }
+
     pub fn pow_nonce(&self) -> u64 {
         return self.pow_nonce;
     }
 
+    /// Returns values written to the output tape by the program in the order in which they
+    /// were written.
+    pub fn output_tape(&self) -> &[u128] {
+        return &self.output_tape;
+    }
+
+    // JSON SERIALIZATION
+    // -------------------------------------------------------------------------------------------
+
+    /// Serializes the proof into canonical JSON: keys always appear in the same order, field
+    /// elements are encoded as 32-digit lowercase hex strings of their big-endian values, and
+    /// hashes are encoded as 64-digit lowercase hex strings of their bytes.
+    pub fn to_json(&self) -> String {
+        return json_serialization::to_json(self);
+    }
+
+    /// Parses a proof from JSON produced by `to_json()`; parsing is strict: unknown or missing
+    /// keys, non-canonical hex encodings, field elements outside of the field, and invalid proof
+    /// options all result in an error.
+    pub fn from_json(json: &str) -> Result<StarkProof, String> {
+        return json_serialization::from_json(json);
+    }
+
+    // BINARY SERIALIZATION
+    // -------------------------------------------------------------------------------------------
+
+    /// Serializes the proof into bytes; the encoding is the same as the one produced by
+    /// `bincode::serialize()`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        return bincode::serialize(self).unwrap();
+    }
+
+    /// Parses a proof from bytes produced by `to_bytes()`. Parsing is strict: trailing bytes,
+    /// field elements outside of the field, and invalid proof options all result in an error.
+    /// Thus, every proof has exactly one valid encoding, and proof bytes can be used to uniquely
+    /// identify a proof.
+    pub fn from_bytes(bytes: &[u8]) -> Result<StarkProof, String> {
+        // the header is parsed first so that proofs of other formats result in a clear error
+        let header: FormatHeader = match bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .deserialize(bytes)
+        {
+            Ok(header) => header,
+            Err(err) => return Err(format!("invalid proof bytes: {}", err))
+        };
+        header.check_version()?;
+
+        let proof: StarkProof = match bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .with_limit(bytes.len() as u64)
+            .reject_trailing_bytes()
+            .deserialize(bytes)
+        {
+            Ok(proof) => proof,
+            Err(err) => return Err(format!("invalid proof bytes: {}", err))
+        };
+
+        proof.check_values()?;
+        return Ok(proof);
+    }
+
+    /// Reads a proof in the encoding produced by `to_bytes()` from the `reader` without buffering
+    /// the encoded proof; parsing is as strict as in `from_bytes()`, except that bytes following
+    /// the proof are left in the reader. No more than `max_size` bytes are read, and so, memory
+    /// used for the proof is bounded by roughly `max_size` bytes regardless of the lengths the
+    /// encoding claims for the values it contains.
+    pub fn from_reader<R: Read>(mut reader: R, max_size: usize) -> Result<StarkProof, String> {
+        // the header has a fixed size, and so, it can be read and checked before the proof
+        let mut header_bytes = [0u8; HEADER_SIZE];
+        if HEADER_SIZE > max_size {
+            return Err(format!("invalid proof bytes: proof cannot take up more than {} bytes", max_size));
+        }
+        if let Err(err) = reader.read_exact(&mut header_bytes) {
+            return Err(format!("invalid proof bytes: {}", err));
+        }
+        let header: FormatHeader = match bincode::deserialize(&header_bytes) {
+            Ok(header) => header,
+            Err(err) => return Err(format!("invalid proof bytes: {}", err))
+        };
+        header.check_version()?;
+
+        let proof: StarkProof = match bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .with_limit(max_size as u64)
+            .allow_trailing_bytes()
+            .deserialize_from(Cursor::new(&header_bytes[..]).chain(reader))
+        {
+            Ok(proof) => proof,
+            Err(err) => return Err(format!("invalid proof bytes: {}", err))
+        };
+
+        proof.check_values()?;
+        return Ok(proof);
+    }
+
+    /// Checks that the values of a proof parsed from untrusted bytes could have been generated by
+    /// the prover: the proof options and the header are valid, and all values are field elements.
+    fn check_values(&self) -> Result<(), String> {
+        self.options.validate()?;
+        self.format.check(self.options.hash_fn())?;
+        if self.trace_info.domain_depth as u32 >= usize::BITS {
+            return Err(format!("domain depth {} is too large", self.trace_info.domain_depth));
+        }
+
+        let fri_values = self.degree_proof.layers.iter().flat_map(|layer| layer.values.iter().flatten());
+        let all_valid = self.trace_evaluations.iter().flatten()
+            .chain(self.aux_evaluations.iter().flatten())
+            .chain(self.deep_values.trace_at_z1.iter())
+            .chain(self.deep_values.trace_at_z2.iter())
+            .chain(std::iter::once(&self.constraint_at_z))
+            .chain(fri_values)
+            .chain(self.degree_proof.rem_values.iter())
+            .chain(self.output_tape.iter())
+            .all(|&value| value < field::MODULUS);
+        if !all_valid {
+            return Err(String::from("proof contains values which are not valid field elements"));
+        }
+
+        return Ok(());
+    }
+
     // TRACE INFO
     // -------------------------------------------------------------------------------------------
     pub fn trace_length(&self) -> usize {
@@ -137,6 +365,10 @@ impl StarkProof {
         return self.trace_info.loop_depth as usize;
     }
 
+    pub fn call_depth(&self) -> usize {
+        return self.trace_info.call_depth as usize;
+    }
+
     pub fn stack_depth(&self) -> usize {
         return self.trace_info.stack_depth as usize;
     }
@@ -145,21 +377,661 @@ impl StarkProof {
         return self.trace_info.op_count as u128;
     }
 
+    /// Returns the shape of this proof.
+    pub fn shape(&self) -> ProofShape {
+        return ProofShape {
+            trace_length    : self.trace_length(),
+            ctx_depth       : self.ctx_depth(),
+            loop_depth      : self.loop_depth(),
+            call_depth      : self.call_depth(),
+            stack_depth     : self.stack_depth(),
+        };
+    }
+
+    /// Returns the breakdown of the size of this proof by component, together with the number
+    /// of queries and FRI layers; this is useful for finding out where the bytes of a proof go.
+    pub fn stats(&self) -> ProofSizeStats {
+        let trace_openings = serialized_size(&self.trace_nodes) + serialized_size(&self.trace_cap)
+            + serialized_size(&self.trace_evaluations) + serialized_size(&self.aux_nodes)
+            + serialized_size(&self.aux_cap) + serialized_size(&self.aux_evaluations);
+        let constraint_openings = serialized_size(&self.constraint_proof);
+        let deep_values = serialized_size(&self.deep_values) + serialized_size(&self.constraint_at_z);
+        let fri_layers = serialized_size(&self.degree_proof.layers);
+        let fri_remainder = serialized_size(&self.degree_proof.rem_root) + serialized_size(&self.degree_proof.rem_values);
+        let pow_nonce = serialized_size(&self.pow_nonce);
+        let output_tape = serialized_size(&self.output_tape);
+
+        let total_size = serialized_size(self);
+        return ProofSizeStats {
+            total_size          : total_size,
+            trace_openings      : trace_openings,
+            constraint_openings : constraint_openings,
+            deep_values         : deep_values,
+            fri_layers          : fri_layers,
+            fri_remainder       : fri_remainder,
+            pow_nonce           : pow_nonce,
+            output_tape         : output_tape,
+            other               : total_size - trace_openings - constraint_openings - deep_values
+                - fri_layers - fri_remainder - pow_nonce - output_tape,
+            num_queries         : self.trace_evaluations.len(),
+            num_fri_layers      : self.degree_proof.layers.len(),
+        };
+    }
+
     // DEEP VALUES
     // -------------------------------------------------------------------------------------------
     pub fn get_state_at_z1(&self) -> TraceState {
-        return TraceState::from_vec(
-            self.ctx_depth(),
-            self.loop_depth(),
-            self.stack_depth(),
-            &self.deep_values.trace_at_z1);
+        return self.trace_info.build_state(&self.deep_values.trace_at_z1);
     }
 
     pub fn get_state_at_z2(&self) -> TraceState {
+        return self.trace_info.build_state(&self.deep_values.trace_at_z2);
+    }
+
+    // HELPER METHODS
+    // -------------------------------------------------------------------------------------------
+
+    /// Rebuilds a batch Merkle proof from the authentication `nodes`, the tree `cap`, and trace
+    /// `evaluations`; leaves of the proof are hashes of trace states at the queried positions.
+    fn build_batch_proof(&self, nodes: &Vec<Vec<[u8; 32]>>, cap: &Vec<[u8; 32]>, evaluations: &[Vec<u128>]) -> BatchMerkleProof {
+        return build_batch_proof(nodes, cap, evaluations, &self.options, self.trace_info.domain_depth);
+    }
+}
+
+// BATCH PROOF IMPLEMENTATION
+// ================================================================================================
+impl BatchProof {
+    pub fn new(
+        trace_root          : &[u8; 32],
+        trace_proof         : BatchMerkleProof,
+        trace_evaluations   : Vec<Vec<u128>>,
+        aux_root            : &[u8; 32],
+        aux_proof           : BatchMerkleProof,
+        aux_evaluations     : Vec<Vec<u128>>,
+        constraint_root     : &[u8; 32],
+        constraint_proof    : BatchMerkleProof,
+        deep_values         : Vec<DeepValues>,
+        constraint_at_z     : u128,
+        degree_proof        : FriProof,
+        pow_nonce           : u64,
+        trace_info          : Vec<TraceInfo>,
+        output_tapes        : Vec<Vec<u128>>,
+        options             : &ProofOptions ) -> BatchProof
+    {
+        return BatchProof {
+            format              : FormatHeader::new(options.hash_fn()),
+            trace_root          : *trace_root,
+            trace_info          : trace_info,
+            trace_nodes         : trace_proof.nodes,
+            trace_cap           : trace_proof.cap,
+            trace_evaluations   : trace_evaluations,
+            aux_root            : *aux_root,
+            aux_nodes           : aux_proof.nodes,
+            aux_cap             : aux_proof.cap,
+            aux_evaluations     : aux_evaluations,
+            constraint_root     : *constraint_root,
+            constraint_proof    : constraint_proof,
+            deep_values         : deep_values,
+            constraint_at_z     : constraint_at_z,
+            degree_proof        : degree_proof,
+            pow_nonce           : pow_nonce,
+            output_tapes        : output_tapes,
+            options             : options.clone()
+        };
+    }
+
+    /// Returns the number of program executions proven by this proof.
+    pub fn batch_size(&self) -> usize {
+        return self.trace_info.len();
+    }
+
+    pub fn trace_root(&self) -> &[u8; 32] {
+        return &self.trace_root;
+    }
+
+    pub fn options(&self) -> &ProofOptions {
+        return &self.options;
+    }
+
+    /// Returns the header describing the format of this proof.
+    pub fn format(&self) -> &FormatHeader {
+        return &self.format;
+    }
+
+    /// Returns a description of the execution trace of every program in the batch.
+    pub fn trace_info(&self) -> &[TraceInfo] {
+        return &self.trace_info;
+    }
+
+    pub fn trace_proof(&self) -> BatchMerkleProof {
+        return build_batch_proof(&self.trace_nodes, &self.trace_cap, &self.trace_evaluations, &self.options, self.domain_depth());
+    }
+
+    pub fn aux_root(&self) -> &[u8; 32] {
+        return &self.aux_root;
+    }
+
+    pub fn aux_proof(&self) -> BatchMerkleProof {
+        return build_batch_proof(&self.aux_nodes, &self.aux_cap, &self.aux_evaluations, &self.options, self.domain_depth());
+    }
+
+    pub fn constraint_root(&self) -> &[u8; 32] {
+        return &self.constraint_root;
+    }
+
+    pub fn constraint_proof(&self) -> BatchMerkleProof {
+        return self.constraint_proof.clone();
+    }
+
+    /// Returns the evaluation of the constraint polynomial at the DEEP point z; the verifier
+    /// checks that this value is consistent with trace states at z and z * g.
+    pub fn constraint_at_z(&self) -> u128 {
+        return self.constraint_at_z;
+    }
+
+    pub fn degree_proof(&self) -> &FriProof {
+        return &self.degree_proof;
+    }
+
+    /// Returns values of trace registers of all programs at the queried positions; for every
+    /// position, registers of the first program are followed by registers of the second program
+    /// etc.
+    pub fn trace_evaluations(&self) -> &[Vec<u128>] {
+        return &self.trace_evaluations;
+    }
+
+    /// Returns values of auxiliary registers of all programs at the queried positions, in the
+    /// same layout as `trace_evaluations()`.
+    pub fn aux_evaluations(&self) -> &[Vec<u128>] {
+        return &self.aux_evaluations;
+    }
+
+    /// Returns values of main and auxiliary trace registers of every program at the DEEP points.
+    pub fn deep_values(&self) -> &[DeepValues] {
+        return &self.deep_values;
+    }
+
+    pub fn pow_nonce(&self) -> u64 {
+        return self.pow_nonce;
+    }
+
+    /// Returns values written to the output tape by every program in the batch.
+    pub fn output_tapes(&self) -> &[Vec<u128>] {
+        return &self.output_tapes;
+    }
+
+    fn domain_depth(&self) -> u8 {
+        return match self.trace_info.first() {
+            Some(info) => info.domain_depth,
+            None => 0
+        };
+    }
+}
+
+// TRACE INFO IMPLEMENTATION
+// ================================================================================================
+impl TraceInfo {
+
+    pub fn domain_size(&self) -> usize {
+        return usize::pow(2, self.domain_depth as u32);
+    }
+
+    pub fn trace_length(&self, extension_factor: usize) -> usize {
+        return self.domain_size() / extension_factor;
+    }
+
+    /// Returns the number of main registers in the execution trace described by this info; this
+    /// excludes auxiliary registers.
+    pub fn register_count(&self) -> usize {
+        return TraceState::compute_decoder_width(self.ctx_depth as usize, self.loop_depth as usize, self.call_depth as usize)
+            + self.stack_depth as usize + HASHER_WIDTH;
+    }
+
+    /// Builds a trace state from `values` of main registers followed by auxiliary registers.
+    pub fn build_state(&self, values: &Vec<u128>) -> TraceState {
         return TraceState::from_vec(
-            self.ctx_depth(),
-            self.loop_depth(),
-            self.stack_depth(),
-            &self.deep_values.trace_at_z2);
+            self.ctx_depth as usize,
+            self.loop_depth as usize,
+            self.call_depth as usize,
+            self.stack_depth as usize,
+            values);
+    }
+}
+
+// PROOF SIZE STATS
+// ================================================================================================
+impl std::fmt::Display for ProofSizeStats {
+
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "proof size: {} bytes ({} queries, {} FRI layers)", self.total_size, self.num_queries, self.num_fri_layers)?;
+        let components = [
+            ("trace openings",      self.trace_openings),
+            ("constraint openings", self.constraint_openings),
+            ("DEEP values",         self.deep_values),
+            ("FRI layers",          self.fri_layers),
+            ("FRI remainder",       self.fri_remainder),
+            ("PoW nonce",           self.pow_nonce),
+            ("output tape",         self.output_tape),
+            ("other",               self.other),
+        ];
+        for (name, size) in components.iter() {
+            let share = *size as f64 * 100.0 / self.total_size as f64;
+            writeln!(f, "    {:<20}{:>10} bytes ({:.1}%)", name, size, share)?;
+        }
+        return Ok(());
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Returns the number of bytes the `value` takes up when serialized via `bincode::serialize()`.
+fn serialized_size<T: Serialize + ?Sized>(value: &T) -> usize {
+    return bincode::serialized_size(value).unwrap() as usize;
+}
+
+/// Rebuilds a batch Merkle proof from the authentication `nodes`, the tree `cap`, and trace
+/// `evaluations`; leaves of the proof are hashes of trace states at the queried positions.
+fn build_batch_proof(nodes: &Vec<Vec<[u8; 32]>>, cap: &Vec<[u8; 32]>, evaluations: &[Vec<u128>], options: &ProofOptions, depth: u8) -> BatchMerkleProof {
+    let hash = options.hash_fn();
+    let mut hashed_states = uninit_vector::<[u8; 32]>(evaluations.len());
+    for i in 0..evaluations.len() {
+        hash(as_bytes(&evaluations[i]), &mut hashed_states[i]);
+    }
+
+    return BatchMerkleProof {
+        nodes   : nodes.clone(),
+        values  : hashed_states,
+        cap     : cap.clone(),
+        depth   : depth,
+    };
+}
+
+// JSON SERIALIZATION / DE-SERIALIZATION
+// ================================================================================================
+mod json_serialization {
+
+    use serde::{ Serialize, Deserialize };
+    use crate::math::field;
+    use crate::crypto::{ BatchMerkleProof };
+    use crate::stark::{ ProofOptions, FormatHeader, fri::{ FriProof, FriLayer } };
+    use crate::stark::format::{ HASH_FUNCTIONS, FORMAT_VERSION };
+    use super::{ StarkProof, DeepValues, TraceInfo };
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct JsonProof {
+        format              : JsonFormat,
+        options             : JsonOptions,
+        trace_info          : JsonTraceInfo,
+        trace_root          : String,
+        trace_nodes         : Vec<Vec<String>>,
+        trace_cap           : Vec<String>,
+        trace_evaluations   : Vec<Vec<String>>,
+        aux_root            : String,
+        aux_nodes           : Vec<Vec<String>>,
+        aux_cap             : Vec<String>,
+        aux_evaluations     : Vec<Vec<String>>,
+        constraint_root     : String,
+        constraint_proof    : JsonMerkleProof,
+        deep_values         : JsonDeepValues,
+        constraint_at_z     : String,
+        degree_proof        : JsonFriProof,
+        pow_nonce           : u64,
+        output_tape         : Vec<String>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct JsonFormat {
+        version             : u8,
+        field_id            : u8,
+        hash_id             : u8,
+        parameter_digest    : String,
+    }
+
+    /// Only the version of the format is parsed from a proof before the proof itself, so that
+    /// proofs of other formats result in a clear error rather than in a parsing error.
+    #[derive(Deserialize)]
+    struct JsonVersion {
+        format              : Option<JsonFormatVersion>,
+    }
+
+    #[derive(Deserialize)]
+    struct JsonFormatVersion {
+        version             : u8,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct JsonOptions {
+        extension_factor    : usize,
+        num_queries         : usize,
+        grinding_factor     : u32,
+        #[serde(default = "default_remainder_length")]
+        remainder_length    : usize,
+        #[serde(default = "default_domain_offset")]
+        domain_offset       : String,
+        cap_height          : u32,
+        hash_fn             : String,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct JsonTraceInfo {
+        domain_depth        : u8,
+        ctx_depth           : u8,
+        loop_depth          : u8,
+        #[serde(default)]
+        call_depth          : u8,
+        stack_depth         : u8,
+        op_count            : u32,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct JsonMerkleProof {
+        values              : Vec<String>,
+        nodes               : Vec<Vec<String>>,
+        cap                 : Vec<String>,
+        depth               : u8,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct JsonDeepValues {
+        trace_at_z1         : Vec<String>,
+        trace_at_z2         : Vec<String>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct JsonFriProof {
+        layers              : Vec<JsonFriLayer>,
+        rem_root            : String,
+        rem_values          : Vec<String>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct JsonFriLayer {
+        root                : String,
+        values              : Vec<[String; 4]>,
+        nodes               : Vec<Vec<String>>,
+        cap                 : Vec<String>,
+        depth               : u8,
     }
-}
\ No newline at end of file
+
+    // SERIALIZATION
+    // --------------------------------------------------------------------------------------------
+    pub fn to_json(proof: &StarkProof) -> String {
+        let options = &proof.options;
+        let hash_fn = HASH_FUNCTIONS.iter()
+            .find(|(_, hash_fn)| *hash_fn as usize == options.hash_fn() as usize)
+            .map(|(name, _)| name.to_string())
+            .expect("unsupported hash function");
+
+        let json_proof = JsonProof {
+            format              : JsonFormat {
+                version         : proof.format.version(),
+                field_id        : proof.format.field_id(),
+                hash_id         : proof.format.hash_id(),
+                parameter_digest: encode_hash(proof.format.parameter_digest()),
+            },
+            options             : JsonOptions {
+                extension_factor: options.extension_factor(),
+                num_queries     : options.num_queries(),
+                grinding_factor : options.grinding_factor(),
+                remainder_length: options.remainder_length(),
+                domain_offset   : encode_element(options.domain_offset()),
+                cap_height      : options.cap_height(),
+                hash_fn         : hash_fn,
+            },
+            trace_info          : JsonTraceInfo {
+                domain_depth    : proof.trace_info.domain_depth,
+                ctx_depth       : proof.trace_info.ctx_depth,
+                loop_depth      : proof.trace_info.loop_depth,
+                call_depth      : proof.trace_info.call_depth,
+                stack_depth     : proof.trace_info.stack_depth,
+                op_count        : proof.trace_info.op_count,
+            },
+            trace_root          : encode_hash(&proof.trace_root),
+            trace_nodes         : encode_nodes(&proof.trace_nodes),
+            trace_cap           : encode_hashes(&proof.trace_cap),
+            trace_evaluations   : proof.trace_evaluations.iter().map(|v| encode_elements(v)).collect(),
+            aux_root            : encode_hash(&proof.aux_root),
+            aux_nodes           : encode_nodes(&proof.aux_nodes),
+            aux_cap             : encode_hashes(&proof.aux_cap),
+            aux_evaluations     : proof.aux_evaluations.iter().map(|v| encode_elements(v)).collect(),
+            constraint_root     : encode_hash(&proof.constraint_root),
+            constraint_proof    : JsonMerkleProof {
+                values          : encode_hashes(&proof.constraint_proof.values),
+                nodes           : encode_nodes(&proof.constraint_proof.nodes),
+                cap             : encode_hashes(&proof.constraint_proof.cap),
+                depth           : proof.constraint_proof.depth,
+            },
+            deep_values         : JsonDeepValues {
+                trace_at_z1     : encode_elements(&proof.deep_values.trace_at_z1),
+                trace_at_z2     : encode_elements(&proof.deep_values.trace_at_z2),
+            },
+            constraint_at_z     : encode_element(proof.constraint_at_z),
+            degree_proof        : JsonFriProof {
+                layers          : proof.degree_proof.layers.iter().map(|layer| JsonFriLayer {
+                    root        : encode_hash(&layer.root),
+                    values      : layer.values.iter().map(|v| [
+                        encode_element(v[0]), encode_element(v[1]), encode_element(v[2]), encode_element(v[3])
+                    ]).collect(),
+                    nodes       : encode_nodes(&layer.nodes),
+                    cap         : encode_hashes(&layer.cap),
+                    depth       : layer.depth,
+                }).collect(),
+                rem_root        : encode_hash(&proof.degree_proof.rem_root),
+                rem_values      : encode_elements(&proof.degree_proof.rem_values),
+            },
+            pow_nonce           : proof.pow_nonce,
+            output_tape         : encode_elements(&proof.output_tape),
+        };
+
+        return serde_json::to_string(&json_proof).unwrap();
+    }
+
+    // DE-SERIALIZATION
+    // --------------------------------------------------------------------------------------------
+    pub fn from_json(json: &str) -> Result<StarkProof, String> {
+        match serde_json::from_str::<JsonVersion>(json) {
+            Ok(JsonVersion { format: Some(format) }) if format.version != FORMAT_VERSION => {
+                return Err(format!("unsupported format version {}; only version {} is supported",
+                    format.version, FORMAT_VERSION));
+            },
+            Ok(JsonVersion { format: None }) => {
+                return Err(String::from("unsupported format: proof does not specify a format version"));
+            },
+            _ => ()
+        };
+
+        let proof: JsonProof = match serde_json::from_str(json) {
+            Ok(proof) => proof,
+            Err(err) => return Err(format!("invalid proof JSON: {}", err))
+        };
+
+        let trace_info = TraceInfo {
+            domain_depth        : proof.trace_info.domain_depth,
+            ctx_depth           : proof.trace_info.ctx_depth,
+            loop_depth          : proof.trace_info.loop_depth,
+            call_depth          : proof.trace_info.call_depth,
+            stack_depth         : proof.trace_info.stack_depth,
+            op_count            : proof.trace_info.op_count,
+        };
+        if trace_info.domain_depth as u32 >= usize::BITS {
+            return Err(format!("domain depth {} is too large", trace_info.domain_depth));
+        }
+
+        let mut layers = Vec::with_capacity(proof.degree_proof.layers.len());
+        for layer in proof.degree_proof.layers.iter() {
+            let mut values = Vec::with_capacity(layer.values.len());
+            for v in layer.values.iter() {
+                values.push([
+                    decode_element(&v[0])?, decode_element(&v[1])?, decode_element(&v[2])?, decode_element(&v[3])?
+                ]);
+            }
+            layers.push(FriLayer {
+                root    : decode_hash(&layer.root)?,
+                values  : values,
+                nodes   : decode_nodes(&layer.nodes)?,
+                cap     : decode_hashes(&layer.cap)?,
+                depth   : layer.depth,
+            });
+        }
+
+        let options = decode_options(&proof.options)?;
+        let format = FormatHeader::from_parts(proof.format.version, proof.format.field_id,
+            proof.format.hash_id, decode_hash(&proof.format.parameter_digest)?);
+        format.check(options.hash_fn())?;
+
+        return Ok(StarkProof {
+            format              : format,
+            trace_root          : decode_hash(&proof.trace_root)?,
+            trace_info          : trace_info,
+            trace_nodes         : decode_nodes(&proof.trace_nodes)?,
+            trace_cap           : decode_hashes(&proof.trace_cap)?,
+            trace_evaluations   : decode_evaluations(&proof.trace_evaluations)?,
+            aux_root            : decode_hash(&proof.aux_root)?,
+            aux_nodes           : decode_nodes(&proof.aux_nodes)?,
+            aux_cap             : decode_hashes(&proof.aux_cap)?,
+            aux_evaluations     : decode_evaluations(&proof.aux_evaluations)?,
+            constraint_root     : decode_hash(&proof.constraint_root)?,
+            constraint_proof    : BatchMerkleProof {
+                values          : decode_hashes(&proof.constraint_proof.values)?,
+                nodes           : decode_nodes(&proof.constraint_proof.nodes)?,
+                cap             : decode_hashes(&proof.constraint_proof.cap)?,
+                depth           : proof.constraint_proof.depth,
+            },
+            deep_values         : DeepValues {
+                trace_at_z1     : decode_elements(&proof.deep_values.trace_at_z1)?,
+                trace_at_z2     : decode_elements(&proof.deep_values.trace_at_z2)?,
+            },
+            constraint_at_z     : decode_element(&proof.constraint_at_z)?,
+            degree_proof        : FriProof {
+                layers          : layers,
+                rem_root        : decode_hash(&proof.degree_proof.rem_root)?,
+                rem_values      : decode_elements(&proof.degree_proof.rem_values)?,
+            },
+            pow_nonce           : proof.pow_nonce,
+            output_tape         : decode_elements(&proof.output_tape)?,
+            options             : options,
+        });
+    }
+
+    fn decode_options(options: &JsonOptions) -> Result<ProofOptions, String> {
+        let extension_factor = options.extension_factor;
+        if !extension_factor.is_power_of_two() || extension_factor < 16 || extension_factor > 256 {
+            return Err(format!("invalid extension factor {}", extension_factor));
+        }
+
+        if options.num_queries == 0 || options.num_queries > 128 {
+            return Err(format!("invalid number of queries {}", options.num_queries));
+        }
+
+        if options.grinding_factor > 32 {
+            return Err(format!("invalid grinding factor {}", options.grinding_factor));
+        }
+
+        let hash_fn = match HASH_FUNCTIONS.iter().find(|(name, _)| *name == options.hash_fn) {
+            Some((_, hash_fn)) => *hash_fn,
+            None => return Err(format!("unsupported hash function '{}'", options.hash_fn))
+        };
+
+        let remainder_length = options.remainder_length;
+        if !remainder_length.is_power_of_two() || remainder_length < 16 || remainder_length > 1024 {
+            return Err(format!("invalid FRI remainder length {}", remainder_length));
+        }
+
+        let domain_offset = decode_element(&options.domain_offset)?;
+        if !ProofOptions::is_valid_domain_offset(domain_offset) {
+            return Err(format!("invalid domain offset {}", domain_offset));
+        }
+
+        if options.cap_height > 8 {
+            return Err(format!("invalid Merkle cap height {}", options.cap_height));
+        }
+
+        return Ok(ProofOptions::new(extension_factor, options.num_queries, options.grinding_factor, hash_fn)
+            .with_remainder_length(remainder_length)
+            .with_domain_offset(domain_offset)
+            .with_cap_height(options.cap_height));
+    }
+
+    /// Proofs exported before the remainder length became configurable always used 256.
+    fn default_remainder_length() -> usize {
+        return ProofOptions::default().remainder_length();
+    }
+
+    /// Proofs exported before the domain offset became configurable were not shifted.
+    fn default_domain_offset() -> String {
+        return encode_element(ProofOptions::default().domain_offset());
+    }
+
+    // HELPER FUNCTIONS
+    // --------------------------------------------------------------------------------------------
+    fn encode_element(value: u128) -> String {
+        return format!("{:032x}", value);
+    }
+
+    fn encode_elements(values: &[u128]) -> Vec<String> {
+        return values.iter().map(|&v| encode_element(v)).collect();
+    }
+
+    fn encode_hash(value: &[u8; 32]) -> String {
+        return hex::encode(value);
+    }
+
+    fn encode_hashes(values: &[[u8; 32]]) -> Vec<String> {
+        return values.iter().map(|v| encode_hash(v)).collect();
+    }
+
+    fn encode_nodes(nodes: &[Vec<[u8; 32]>]) -> Vec<Vec<String>> {
+        return nodes.iter().map(|path| encode_hashes(path)).collect();
+    }
+
+    /// Parses a field element from exactly 32 lowercase hex digits.
+    fn decode_element(value: &str) -> Result<u128, String> {
+        if !is_canonical_hex(value, 32) {
+            return Err(format!("field element '{}' is not 32 lowercase hex digits", value));
+        }
+        let result = u128::from_str_radix(value, 16).unwrap();
+        if result >= field::MODULUS {
+            return Err(format!("value '{}' is not a valid field element", value));
+        }
+        return Ok(result);
+    }
+
+    fn decode_elements(values: &[String]) -> Result<Vec<u128>, String> {
+        return values.iter().map(|v| decode_element(v)).collect();
+    }
+
+    fn decode_evaluations(values: &[Vec<String>]) -> Result<Vec<Vec<u128>>, String> {
+        return values.iter().map(|v| decode_elements(v)).collect();
+    }
+
+    /// Parses a hash from exactly 64 lowercase hex digits.
+    fn decode_hash(value: &str) -> Result<[u8; 32], String> {
+        if !is_canonical_hex(value, 64) {
+            return Err(format!("hash '{}' is not 64 lowercase hex digits", value));
+        }
+        let mut result = [0u8; 32];
+        hex::decode_to_slice(value, &mut result).unwrap();
+        return Ok(result);
+    }
+
+    fn decode_hashes(values: &[String]) -> Result<Vec<[u8; 32]>, String> {
+        return values.iter().map(|v| decode_hash(v)).collect();
+    }
+
+    fn decode_nodes(nodes: &[Vec<String>]) -> Result<Vec<Vec<[u8; 32]>>, String> {
+        return nodes.iter().map(|path| decode_hashes(path)).collect();
+    }
+
+    fn is_canonical_hex(value: &str, length: usize) -> bool {
+        return value.len() == length && value.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'));
+    }
+}
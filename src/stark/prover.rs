@@ -1,171 +1,560 @@
 use std::time::Instant;
 use log::debug;
 use crate::{
-    math::{ field, polynom, fft },
-    crypto::MerkleTree,
+    math::{ field, polynom, parallel, fft },
+    crypto::{ MerkleTree, HashFunction, hash_columns },
+    utils::{ zeroize },
 };
 use super::{
-    ProofOptions, StarkProof, CompositionCoefficients, DeepValues, fri, utils,
+    ProofOptions, StarkProof, BatchProof, TraceInfo, CompositionCoefficients, DeepValues, Transcript,
+    BufferPool, fri, utils,
     trace::{ TraceTable, TraceState },
-    constraints::{ ConstraintTable, ConstraintPoly },
+    constraints::{ ConstraintTable, ConstraintSetup, ConstraintPoly, AuxRegisters },
     MAX_CONSTRAINT_DEGREE,
 };
 
-// PROVER FUNCTION
+// TYPES AND INTERFACES
 // ================================================================================================
 
-pub fn prove(trace: &mut TraceTable, inputs: &[u128], outputs: &[u128], options: &ProofOptions) -> StarkProof {
-    // 1 ----- extend execution trace -------------------------------------------------------------
-    let now = Instant::now();
+/// Values used by the prover which depend only on the shape of the execution trace (its length,
+/// width, and depths of its stacks) and on the extension factor and the offset of the LDE
+/// domain: the LDE domain, twiddles for
+/// FFT evaluation and interpolation, constraint evaluators, and a pool of buffers. A setup can
+/// be re-used to prove any number of execution traces of the same shape.
+pub struct ProverSetup {
+    trace_length        : usize,
+    register_count      : usize,
+    extension_factor    : usize,
+    domain_offset       : u128,
+    lde_domain          : Vec<u128>,
+    lde_twiddles        : Vec<u128>,
+    trace_inv_twiddles  : Vec<u128>,
+    constraint_inv_twiddles: Vec<u128>,
+    constraints         : ConstraintSetup,
+    pool                : BufferPool,
+}
 
-    // build LDE domain and LDE twiddles (for FFT evaluation over LDE domain)
-    let lde_root = field::get_root_of_unity(trace.domain_size());
-    let lde_domain = field::get_power_series(lde_root, trace.domain_size());
-    let lde_twiddles = twiddles_from_domain(&lde_domain);
+/// Commitments to an execution trace together with the data needed to complete a proof from
+/// them: the extended trace, Merkle trees of the trace, of its auxiliary registers, and of the
+/// constraint polynomial, and the constraint polynomial itself. A proof can be completed from
+/// the same context any number of times, e.g. to search for proof options which result in the
+/// smallest proof, without re-computing any of these values.
+pub struct ProverContext<'a> {
+    trace               : &'a TraceTable,
+    setup               : &'a mut ProverSetup,
+    trace_tree          : MerkleTree,
+    aux_tree            : MerkleTree,
+    constraint_tree     : MerkleTree,
+    constraint_poly     : ConstraintPoly,
+    tape_values         : Vec<u128>,
+    hash_fn             : HashFunction,
+}
 
-    // extend the execution trace registers to LDE domain
-    trace.extend(&lde_twiddles);
-    debug!("Extended execution trace from {} to {} steps in {} ms",
-        trace.unextended_length(),
-        trace.domain_size(), 
-        now.elapsed().as_millis());
+/// Memory used by the prover to generate a proof, in bytes, broken down by the phase of proof
+/// generation in which the memory was allocated. Like `ProofOptions::estimate()`, the stats
+/// account only for the largest data structures, but they are measured from the structures
+/// built for a specific proof rather than estimated.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ProverStats {
+    pub trace_memory        : usize,    // execution trace and its polynomials
+    pub lde_memory          : usize,    // main and auxiliary registers extended to the LDE domain
+    pub commitment_memory   : usize,    // trace, auxiliary, and constraint Merkle trees
+    pub constraint_memory   : usize,    // constraint evaluations and constraint polynomial
+    pub fri_memory          : usize,    // FRI layers and their Merkle trees
+    pub peak_memory         : usize,    // memory held at once while FRI layers are computed
+}
+
+// PROVER FUNCTION
+// ================================================================================================
 
-    // 2 ----- build Merkle tree from the extended execution trace ------------------------------------
+/// Generates a proof of the specified execution `trace`; all verifier challenges are derived from
+/// prover commitments using the provided `transcript`. Precomputed values, as well as buffers
+/// for values defined over the LDE domain, are taken from the `setup`; the setup must match the
+/// shape of the `trace`. Returns the proof together with the memory used to generate it.
+pub fn prove(trace: &mut TraceTable, inputs: &[u128], outputs: &[u128], options: &ProofOptions, transcript: &mut dyn Transcript, setup: &mut ProverSetup) -> (StarkProof, ProverStats) {
+    let mut context = ProverContext::new(trace, inputs, outputs, options, transcript, setup);
+    return context.prove(options, transcript);
+}
+
+/// Generates a single proof of several execution `traces`; the traces must be of the same length,
+/// and `inputs` and `outputs` must contain public inputs and outputs of every traced execution.
+/// The traces are committed to as a single table with registers of all traces placed next to
+/// each other: trace, auxiliary, and constraint commitments, as well as the low-degree proof and
+/// query positions are shared by all traces, while coefficients for constraint evaluation and
+/// DEEP composition are drawn for every trace separately.
+pub fn prove_batch(traces: &mut [TraceTable], inputs: &[Vec<u128>], outputs: &[Vec<u128>], options: &ProofOptions, transcript: &mut dyn Transcript) -> BatchProof {
+    assert!(!traces.is_empty(), "at least one execution trace must be provided");
+    assert!(traces.len() == inputs.len() && traces.len() == outputs.len(),
+        "public inputs and outputs must be provided for every execution trace");
+    let trace_length = traces[0].unextended_length();
+    for trace in traces.iter() {
+        assert!(trace.unextended_length() == trace_length && trace.extension_factor() == traces[0].extension_factor(),
+            "all execution traces must be of the same length");
+        assert!(trace.domain_offset() == options.domain_offset(),
+            "all execution traces must be extended over the domain defined by proof options");
+    }
+
+    // domains and twiddles depend only on the length of the traces; the pool is sized for the
+    // combined width of all traces
+    let mut setup = ProverSetup::new(&traces[0]);
+    let register_count = traces.iter().map(|trace| trace.register_count()).sum();
+    setup.pool = BufferPool::new(trace_length, register_count, traces[0].extension_factor());
+    let lde_domain = &setup.lde_domain;
+    let lde_twiddles = &setup.lde_twiddles;
+    let domain_offset = setup.domain_offset;
+    let pool = &mut setup.pool;
+
+    // 1 ----- extend execution traces and commit to them -----------------------------------------
     let now = Instant::now();
-    let trace_tree = trace.build_merkle_tree(options.hash_fn());
-    debug!("Built trace Merkle tree in {} ms", 
+    for trace in traces.iter_mut() {
+        trace.extend(lde_twiddles, &setup.trace_inv_twiddles, pool);
+    }
+    let trace_tree = build_batch_merkle_tree(traces, false, options.hash_fn());
+    debug!("Extended {} execution traces to {} steps and built trace Merkle tree in {} ms",
+        traces.len(),
+        lde_domain.len(),
         now.elapsed().as_millis());
 
-    // 3 ----- evaluate constraints ---------------------------------------------------------------
+    // 2 ----- build and commit to auxiliary trace registers --------------------------------------
     let now = Instant::now();
-    
-    // initialize constraint evaluation table
-    let mut constraints = ConstraintTable::new(&trace, trace_tree.root(), inputs, outputs);
-    
-    // allocate space to hold current and next states for constraint evaluations
-    let mut current = TraceState::new(trace.ctx_depth(), trace.loop_depth(), trace.stack_depth());
-    let mut next = TraceState::new(trace.ctx_depth(), trace.loop_depth(), trace.stack_depth());
-
-    // we don't need to evaluate constraints over the entire extended execution trace; we need
-    // to evaluate them over the domain extended to match max constraint degree - thus, we can
-    // skip most trace states for the purposes of constraint evaluation.
-    let stride = trace.extension_factor() / MAX_CONSTRAINT_DEGREE;
-    for i in (0..trace.domain_size()).step_by(stride) {
-        // TODO: this loop should be parallelized and also potentially optimized to avoid copying
-        // next state from the trace table twice
-
-        // copy current and next states from the trace table; next state may wrap around the
-        // execution trace (close to the end of the trace)
-        trace.fill_state(&mut current, i);
-        trace.fill_state(&mut next, (i + trace.extension_factor()) % trace.domain_size());
-
-        // evaluate the constraints
-        constraints.evaluate(&current, &next, lde_domain[i], i / stride);
-    }
 
-    debug!("Evaluated {} constraints over domain of {} elements in {} ms",
-        constraints.constraint_count(),
-        constraints.evaluation_domain_size(),
+    // the same randomness for auxiliary registers is used for all traces
+    transcript.absorb(b"trace_root", trace_tree.root());
+    let aux_seed = transcript.squeeze(b"aux_seed");
+    let mut output_tapes = Vec::with_capacity(traces.len());
+    for trace in traces.iter_mut() {
+        let (aux_columns, tape_values) = AuxRegisters::build_trace_columns(aux_seed, &trace);
+        trace.extend_aux(aux_columns, lde_twiddles, &setup.trace_inv_twiddles, pool);
+        output_tapes.push(tape_values);
+    }
+    let aux_tree = build_batch_merkle_tree(traces, true, options.hash_fn());
+    debug!("Built and committed to auxiliary registers of {} execution traces in {} ms",
+        traces.len(),
         now.elapsed().as_millis());
 
-    // 4 ----- convert constraint evaluations into a polynomial -----------------------------------
+    // 3 ----- evaluate constraints ---------------------------------------------------------------
     let now = Instant::now();
-    let constraint_poly = constraints.combine_polys();
-    debug!("Converted constraint evaluations into a single polynomial of degree {} in {} ms",
-        constraint_poly.degree(),
+
+    // constraints of every trace are evaluated with their own coefficients, and the resulting
+    // polynomials are added together into a single constraint polynomial
+    transcript.absorb(b"aux_root", aux_tree.root());
+    let stride = traces[0].extension_factor() / MAX_CONSTRAINT_DEGREE;
+    let mut constraint_poly: Option<ConstraintPoly> = None;
+    for (i, trace) in traces.iter().enumerate() {
+        let coefficients_seed = transcript.squeeze(b"constraint_coefficients");
+        let constraint_setup = ConstraintSetup::from_trace(trace);
+        let mut constraints = ConstraintTable::new(&constraint_setup, &trace, aux_seed, coefficients_seed,
+            &inputs[i], &outputs[i], &output_tapes[i], pool);
+
+        let mut current = TraceState::new(trace.ctx_depth(), trace.loop_depth(), trace.call_depth(), trace.stack_depth());
+        let mut next = TraceState::new(trace.ctx_depth(), trace.loop_depth(), trace.call_depth(), trace.stack_depth());
+        for j in (0..trace.domain_size()).step_by(stride) {
+            trace.fill_state(&mut current, j);
+            trace.fill_state(&mut next, (j + trace.extension_factor()) % trace.domain_size());
+            constraints.evaluate(&current, &next, lde_domain[j], j / stride);
+        }
+
+        let poly = constraints.combine_polys(&setup.constraint_inv_twiddles, pool);
+        match constraint_poly.as_mut() {
+            Some(combined_poly) => combined_poly.add(&poly),
+            None => constraint_poly = Some(poly),
+        }
+    }
+    let constraint_poly = constraint_poly.unwrap();
+    debug!("Evaluated constraints of {} execution traces in {} ms",
+        traces.len(),
         now.elapsed().as_millis());
 
-    // 5 ----- build Merkle tree from constraint polynomial evaluations ---------------------------
+    // 4 ----- build Merkle tree from constraint polynomial evaluations ---------------------------
     let now = Instant::now();
-    
-    // evaluate constraint polynomial over the evaluation domain
-    let constraint_evaluations = constraint_poly.eval(&lde_twiddles);
-
-    // put evaluations into a Merkle tree; 4 evaluations per leaf
+    let constraint_evaluations = constraint_poly.eval(lde_twiddles, domain_offset, pool);
     let constraint_evaluations = evaluations_to_leaves(constraint_evaluations);
     let constraint_tree = MerkleTree::new(constraint_evaluations, options.hash_fn());
     debug!("Evaluated constraint polynomial and built constraint Merkle tree in {} ms",
         now.elapsed().as_millis());
 
-    // 6 ----- build and evaluate deep composition polynomial -------------------------------------
+    // 5 ----- build and evaluate deep composition polynomial -------------------------------------
     let now = Instant::now();
 
-    // combine trace and constraint polynomials into the final deep composition polynomial
-    let seed = constraint_tree.root();
-    let (composition_poly, deep_values) = build_composition_poly(&trace, constraint_poly, seed);
+    // deep point z is shared by all traces and is derived from the first seed; coefficients for
+    // the composition are derived from a separate seed for every trace, with the first trace
+    // using the same seed as z
+    transcript.absorb(b"constraint_root", constraint_tree.root());
+    let seed = transcript.squeeze(b"deep_seed");
+    let z = field::prng(seed);
+    let mut deep_values = Vec::with_capacity(traces.len());
+    let mut composition_poly: Option<Vec<u128>> = None;
+    let mut constraint_coefficients = None;
+    for (i, trace) in traces.iter().enumerate() {
+        let seed = if i == 0 { seed } else { transcript.squeeze(b"deep_seed") };
+        let coefficients = CompositionCoefficients::new(seed);
+        let (poly, s1, s2) = trace.get_composition_poly(z, &coefficients, pool);
+        deep_values.push(DeepValues { trace_at_z1: s1, trace_at_z2: s2 });
+        match composition_poly.as_mut() {
+            Some(combined_poly) => {
+                parallel::add_in_place(combined_poly, &poly, 1);
+                pool.release(poly);
+            },
+            None => {
+                composition_poly = Some(poly);
+                constraint_coefficients = Some(coefficients);
+            }
+        }
+    }
+    let mut composed_evaluations = composition_poly.unwrap();
+    let constraint_at_z = constraint_poly.merge_into(&mut composed_evaluations, z, &constraint_coefficients.unwrap());
 
     // evaluate the composition polynomial over LDE domain
-    let mut composed_evaluations = composition_poly;
     debug_assert!(composed_evaluations.capacity() == lde_domain.len(), "invalid composition polynomial capacity");
     unsafe { composed_evaluations.set_len(composed_evaluations.capacity()); }
-    polynom::eval_fft_twiddles(&mut composed_evaluations, &lde_twiddles, true);
-
+    polynom::shift_in_place(&mut composed_evaluations, domain_offset);
+    polynom::eval_fft_twiddles(&mut composed_evaluations, lde_twiddles, true);
     debug!("Built composition polynomial and evaluated it over domain of {} elements in {} ms",
         composed_evaluations.len(),
         now.elapsed().as_millis());
 
-    // 7 ----- compute FRI layers for the composition polynomial ----------------------------------
+    // 6 ----- compute FRI layers for the composition polynomial ----------------------------------
     let now = Instant::now();
-    let composition_degree = utils::get_composition_degree(trace.unextended_length());
-    debug_assert!(composition_degree == polynom::infer_degree(&composed_evaluations));
-    let (fri_trees, fri_values) = fri::reduce(&composed_evaluations, &lde_domain, options);
+    let (fri_trees, fri_values) = fri::reduce(&composed_evaluations, lde_domain, transcript, options);
     debug!("Computed {} FRI layers from composition polynomial evaluations in {} ms",
-    fri_trees.len(),
-        now.elapsed().as_millis());
-
-    // 8 ----- determine query positions -----------------------------------------------------------
-    let now = Instant::now();
-
-    // combine all FRI layer roots into a single vector
-    let mut fri_roots: Vec<u8> = Vec::new();
-    for tree in fri_trees.iter() {
-        tree.root().iter().for_each(|&v| fri_roots.push(v));
-    }
-
-    // derive a seed from the combined roots
-    let mut seed = [0u8; 32];
-    options.hash_fn()(&fri_roots, &mut seed);
-
-    // apply proof-of-work to get a new seed
-    let (seed, pow_nonce) = utils::find_pow_nonce(seed, &options);
-
-    // generate pseudo-random query positions
-    let positions = utils::compute_query_positions(&seed, lde_domain.len(), options);
-    debug!("Determined {} query positions from seed {} in {} ms",
-        positions.len(),
-        hex::encode(seed),
+        fri_trees.len(),
         now.elapsed().as_millis());
-
-    // 9 ----- build proof object -----------------------------------------------------------------
-    let now = Instant::now();
-
-    // generate FRI proof
-    let fri_proof = fri::build_proof(fri_trees, fri_values, &positions);
-
-    // built a list of trace evaluations at queried positions
-    let trace_evaluations = trace.get_register_values_at(&positions);
-
-    // build a list of constraint positions
+    pool.release(composed_evaluations);
+
+    // 7 ----- determine query positions -----------------------------------------------------------
+    let seed = transcript.squeeze(b"pow_seed");
+    let (_, pow_nonce) = utils::find_pow_nonce(seed, &options);
+    transcript.absorb(b"pow_nonce", &pow_nonce.to_le_bytes());
+    let positions = transcript.draw_positions(b"query_positions", lde_domain.len(), options);
+
+    // 8 ----- build proof object -----------------------------------------------------------------
+    let fri_proof = fri::build_proof(fri_trees, fri_values, &positions, options);
+    let trace_evaluations = get_batch_values_at(traces, &positions, false);
+    let aux_evaluations = get_batch_values_at(traces, &positions, true);
     let constraint_positions = utils::map_trace_to_constraint_positions(&positions);
 
-    // build the proof object
-    let proof = StarkProof::new(
+    let trace_proof = trace_tree.prove_batch_with_cap(&positions, options.cap_height());
+    let trace_info = traces.iter().map(|trace| TraceInfo {
+        domain_depth    : trace_proof.depth,
+        ctx_depth       : trace.ctx_depth() as u8,
+        loop_depth      : trace.loop_depth() as u8,
+        call_depth      : trace.call_depth() as u8,
+        stack_depth     : trace.stack_depth() as u8,
+        op_count        : trace.get_last_state().op_counter() as u32,
+    }).collect();
+
+    return BatchProof::new(
         trace_tree.root(),
-        trace_tree.prove_batch(&positions),
+        trace_proof,
         trace_evaluations,
+        aux_tree.root(),
+        aux_tree.prove_batch_with_cap(&positions, options.cap_height()),
+        aux_evaluations,
         constraint_tree.root(),
-        constraint_tree.prove_batch(&constraint_positions),
+        constraint_tree.prove_batch_with_cap(&constraint_positions, options.cap_height()),
         deep_values,
+        constraint_at_z,
         fri_proof,
         pow_nonce,
-        trace.get_last_state().op_counter(),
-        trace.ctx_depth(),
-        trace.loop_depth(),
-        trace.stack_depth(),
+        trace_info,
+        output_tapes,
         &options);
+}
+
+// PROVER CONTEXT IMPLEMENTATION
+// ================================================================================================
+impl <'a> ProverContext<'a> {
+
+    /// Extends the execution `trace`, evaluates constraints over it, and commits to the extended
+    /// trace, its auxiliary registers, and the constraint polynomial; the commitments are
+    /// absorbed into the `transcript`, except for the constraint commitment which is absorbed
+    /// when a proof is generated. Precomputed values and buffers are taken from the `setup`; the
+    /// setup must match the shape of the `trace`.
+    pub fn new(trace: &'a mut TraceTable, inputs: &[u128], outputs: &[u128], options: &ProofOptions, transcript: &mut dyn Transcript, setup: &'a mut ProverSetup) -> ProverContext<'a> {
+        assert!(setup.matches(trace), "prover setup does not match the shape of the execution trace");
+        assert!(trace.domain_offset() == options.domain_offset(), "execution trace was extended over a different domain");
+        let lde_domain = &setup.lde_domain;
+        let lde_twiddles = &setup.lde_twiddles;
+        let domain_offset = setup.domain_offset;
+        let pool = &mut setup.pool;
+
+        // 1 ----- extend execution trace ----------------------------------------------------------
+        let now = Instant::now();
+
+        // extend the execution trace registers to LDE domain
+        trace.extend(lde_twiddles, &setup.trace_inv_twiddles, pool);
+        debug!("Extended execution trace from {} to {} steps in {} ms",
+            trace.unextended_length(),
+            trace.domain_size(), 
+            now.elapsed().as_millis());
+
+        // 2 ----- build Merkle tree from the extended execution trace -----------------------------
+        let now = Instant::now();
+        let trace_tree = trace.build_merkle_tree(options.hash_fn());
+        debug!("Built trace Merkle tree in {} ms", 
+            now.elapsed().as_millis());
+
+        // 3 ----- build and commit to auxiliary trace registers -----------------------------------
+        let now = Instant::now();
+
+        // draw randomness for auxiliary registers after committing to the trace tree, and build the
+        // registers (the running product of the bus and the output tape) from the main trace
+        transcript.absorb(b"trace_root", trace_tree.root());
+        let aux_seed = transcript.squeeze(b"aux_seed");
+        let (aux_columns, tape_values) = AuxRegisters::build_trace_columns(aux_seed, &trace);
+
+        // extend auxiliary registers and put them into a separate Merkle tree
+        trace.extend_aux(aux_columns, lde_twiddles, &setup.trace_inv_twiddles, pool);
+        let aux_tree = trace.build_aux_merkle_tree(options.hash_fn());
+        debug!("Built and committed to {} auxiliary registers in {} ms",
+            trace.aux_register_count(),
+            now.elapsed().as_millis());
+
+        // 4 ----- evaluate constraints ------------------------------------------------------------
+        let now = Instant::now();
+        
+        // initialize constraint evaluation table; coefficients for the constraints are drawn after
+        // committing to the auxiliary tree, which makes them dependent on both trace commitments
+        transcript.absorb(b"aux_root", aux_tree.root());
+        let coefficients_seed = transcript.squeeze(b"constraint_coefficients");
+        let mut constraints = ConstraintTable::new(&setup.constraints, &trace, aux_seed, coefficients_seed, inputs, outputs, &tape_values, pool);
+        
+        // allocate space to hold current and next states for constraint evaluations
+        let mut current = TraceState::new(trace.ctx_depth(), trace.loop_depth(), trace.call_depth(), trace.stack_depth());
+        let mut next = TraceState::new(trace.ctx_depth(), trace.loop_depth(), trace.call_depth(), trace.stack_depth());
+
+        // we don't need to evaluate constraints over the entire extended execution trace; we need
+        // to evaluate them over the domain extended to match max constraint degree - thus, we can
+        // skip most trace states for the purposes of constraint evaluation.
+        let stride = trace.extension_factor() / MAX_CONSTRAINT_DEGREE;
+        for i in (0..trace.domain_size()).step_by(stride) {
+            // TODO: this loop should be parallelized and also potentially optimized to avoid copying
+            // next state from the trace table twice
+
+            // copy current and next states from the trace table; next state may wrap around the
+            // execution trace (close to the end of the trace)
+            trace.fill_state(&mut current, i);
+            trace.fill_state(&mut next, (i + trace.extension_factor()) % trace.domain_size());
+
+            // evaluate the constraints
+            constraints.evaluate(&current, &next, lde_domain[i], i / stride);
+        }
+
+        debug!("Evaluated {} constraints over domain of {} elements in {} ms",
+            constraints.constraint_count(),
+            constraints.evaluation_domain_size(),
+            now.elapsed().as_millis());
+
+        // 5 ----- convert constraint evaluations into a polynomial --------------------------------
+        let now = Instant::now();
+        let constraint_poly = constraints.combine_polys(&setup.constraint_inv_twiddles, pool);
+        debug!("Converted constraint evaluations into a single polynomial of degree {} in {} ms",
+            constraint_poly.degree(),
+            now.elapsed().as_millis());
+
+        // 6 ----- build Merkle tree from constraint polynomial evaluations ------------------------
+        let now = Instant::now();
+        
+        // evaluate constraint polynomial over the evaluation domain
+        let constraint_evaluations = constraint_poly.eval(lde_twiddles, domain_offset, pool);
+
+        // put evaluations into a Merkle tree; 4 evaluations per leaf
+        let constraint_evaluations = evaluations_to_leaves(constraint_evaluations);
+        let constraint_tree = MerkleTree::new(constraint_evaluations, options.hash_fn());
+        debug!("Evaluated constraint polynomial and built constraint Merkle tree in {} ms",
+            now.elapsed().as_millis());
+
+        return ProverContext {
+            trace, setup, trace_tree, aux_tree, constraint_tree, constraint_poly, tape_values,
+            hash_fn         : options.hash_fn(),
+        };
+    }
+
+    /// Returns true if a proof with the specified `options` can be generated from this context;
+    /// such options must define the same LDE domain and the same hash function as the options
+    /// with which the context was created, but may differ in the number of queries, grinding
+    /// factor, and the length of FRI remainder.
+    pub fn matches(&self, options: &ProofOptions) -> bool {
+        return self.trace.extension_factor() == options.extension_factor()
+            && self.trace.domain_offset() == options.domain_offset()
+            && self.hash_fn as usize == options.hash_fn() as usize;
+    }
+
+    /// Completes a proof from the commitments of this context; the `transcript` must be in the
+    /// same state as the transcript passed to `ProverContext::new()` was when the context was
+    /// created. The proof can be completed any number of times: every time, the DEEP composition
+    /// polynomial, FRI layers, and query positions are derived from the provided transcript
+    /// without re-computing the trace LDE, constraint evaluations, or commitments. Returns the
+    /// proof together with the memory used to generate it.
+    pub fn prove(&mut self, options: &ProofOptions, transcript: &mut dyn Transcript) -> (StarkProof, ProverStats) {
+        assert!(self.matches(options), "proof options do not match the options with which the prover context was created");
+        let trace = self.trace;
+        let lde_domain = &self.setup.lde_domain;
+        let lde_twiddles = &self.setup.lde_twiddles;
+        let domain_offset = self.setup.domain_offset;
+        let pool = &mut self.setup.pool;
+
+        // 7 ----- build and evaluate deep composition polynomial ----------------------------------
+        let now = Instant::now();
+
+        // combine trace and constraint polynomials into the final deep composition polynomial;
+        // the constraint polynomial is consumed by the composition, and so, a copy of it is used
+        transcript.absorb(b"constraint_root", self.constraint_tree.root());
+        let seed = transcript.squeeze(b"deep_seed");
+        let (composition_poly, deep_values, constraint_at_z) = build_composition_poly(&trace, self.constraint_poly.clone(), &seed, pool);
+
+        // evaluate the composition polynomial over LDE domain
+        let mut composed_evaluations = composition_poly;
+        debug_assert!(composed_evaluations.capacity() == lde_domain.len(), "invalid composition polynomial capacity");
+        unsafe { composed_evaluations.set_len(composed_evaluations.capacity()); }
+        polynom::shift_in_place(&mut composed_evaluations, domain_offset);
+        polynom::eval_fft_twiddles(&mut composed_evaluations, lde_twiddles, true);
+
+        debug!("Built composition polynomial and evaluated it over domain of {} elements in {} ms",
+            composed_evaluations.len(),
+            now.elapsed().as_millis());
+
+        // 8 ----- compute FRI layers for the composition polynomial -------------------------------
+        let now = Instant::now();
+        let composition_degree = utils::get_composition_degree(trace.unextended_length());
+        debug_assert!(composition_degree == polynom::infer_degree(&composed_evaluations));
+        let (fri_trees, fri_values) = fri::reduce(&composed_evaluations, lde_domain, transcript, options);
+        debug!("Computed {} FRI layers from composition polynomial evaluations in {} ms",
+        fri_trees.len(),
+            now.elapsed().as_millis());
+
+        // all buffers of the pool, as well as all Merkle trees, are held at this point; register
+        // polynomials and an un-extended copy of the trace are kept by the trace table
+        let element_size = std::mem::size_of::<u128>();
+        let buffer_memory = pool.num_allocated() * pool.buffer_size() * element_size;
+        let mut stats = ProverStats::default();
+        stats.trace_memory = 2 * (trace.register_count() + trace.aux_register_count()) * trace.unextended_length() * element_size;
+        stats.lde_memory = (trace.register_count() + trace.aux_register_count()) * trace.domain_size() * element_size;
+        stats.commitment_memory = self.trace_tree.memory_size() + self.aux_tree.memory_size() + self.constraint_tree.memory_size();
+        stats.constraint_memory = buffer_memory - stats.lde_memory;
+        stats.fri_memory = fri_trees.iter().map(|tree| tree.memory_size()).sum::<usize>()
+            + fri_values.iter().map(|values| values.len() * 4 * element_size).sum::<usize>();
+        stats.peak_memory = stats.trace_memory + buffer_memory + stats.commitment_memory + stats.fri_memory;
+        pool.release(composed_evaluations);
+
+        // 9 ----- determine query positions -------------------------------------------------------
+        let now = Instant::now();
+
+        // all FRI layer roots have been absorbed into the transcript; draw a seed for proof-of-work
+        let seed = transcript.squeeze(b"pow_seed");
+
+        // apply proof-of-work to the seed, and absorb the nonce into the transcript
+        let (_, pow_nonce) = utils::find_pow_nonce(seed, &options);
+        transcript.absorb(b"pow_nonce", &pow_nonce.to_le_bytes());
+
+        // generate pseudo-random query positions
+        let positions = transcript.draw_positions(b"query_positions", lde_domain.len(), options);
+        debug!("Determined {} query positions in {} ms",
+            positions.len(),
+            now.elapsed().as_millis());
+
+        // 10 ---- build proof object --------------------------------------------------------------
+        let now = Instant::now();
+
+        // generate FRI proof
+        let fri_proof = fri::build_proof(fri_trees, fri_values, &positions, options);
+
+        // built a list of trace evaluations at queried positions
+        let trace_evaluations = trace.get_register_values_at(&positions);
+        let aux_evaluations = trace.get_aux_values_at(&positions);
+
+        // build a list of constraint positions
+        let constraint_positions = utils::map_trace_to_constraint_positions(&positions);
+
+        // build the proof object
+        let proof = StarkProof::new(
+            self.trace_tree.root(),
+            self.trace_tree.prove_batch_with_cap(&positions, options.cap_height()),
+            trace_evaluations,
+            self.aux_tree.root(),
+            self.aux_tree.prove_batch_with_cap(&positions, options.cap_height()),
+            aux_evaluations,
+            self.constraint_tree.root(),
+            self.constraint_tree.prove_batch_with_cap(&constraint_positions, options.cap_height()),
+            deep_values,
+            constraint_at_z,
+            fri_proof,
+            pow_nonce,
+            trace.get_last_state().op_counter(),
+            self.tape_values.clone(),
+            trace.ctx_depth(),
+            trace.loop_depth(),
+            trace.call_depth(),
+            trace.stack_depth(),
+            &options);
+
+        debug!("Built proof object in {} ms", now.elapsed().as_millis());
+        return (proof, stats);
+    }
+}
+
+/// Values of the input tapes are secret, and so, they are zeroized when the context is dropped.
+impl <'a> Drop for ProverContext<'a> {
+    fn drop(&mut self) {
+        zeroize(&mut self.tape_values);
+    }
+}
+
+// PROVER SETUP IMPLEMENTATION
+// ================================================================================================
+impl ProverSetup {
+
+    /// Returns a setup for proving execution traces of the same shape as the specified `trace`.
+    pub fn new(trace: &TraceTable) -> ProverSetup {
+        let trace_length = trace.unextended_length();
+        let domain_size = trace.domain_size();
+
+        // build LDE twiddles (for FFT evaluation over LDE domain) from the subgroup of the
+        // domain size, and then shift the subgroup by the offset to get the LDE domain
+        let domain_offset = trace.domain_offset();
+        let lde_root = field::get_root_of_unity(domain_size);
+        let mut lde_domain = field::get_power_series(lde_root, domain_size);
+        let lde_twiddles = twiddles_from_domain(&lde_domain);
+        if domain_offset != field::ONE {
+            lde_domain.iter_mut().for_each(|x| *x = field::mul(*x, domain_offset));
+        }
+
+        // build inverse twiddles for interpolating trace registers and constraint evaluations
+        let trace_root = field::get_root_of_unity(trace_length);
+        let trace_inv_twiddles = fft::get_inv_twiddles(trace_root, trace_length);
+        let constraint_domain_size = trace_length * MAX_CONSTRAINT_DEGREE;
+        let constraint_root = field::get_root_of_unity(constraint_domain_size);
+        let constraint_inv_twiddles = fft::get_inv_twiddles(constraint_root, constraint_domain_size);
+
+        return ProverSetup {
+            trace_length,
+            register_count  : trace.register_count(),
+            extension_factor: trace.extension_factor(),
+            domain_offset, lde_domain, lde_twiddles,
+            trace_inv_twiddles, constraint_inv_twiddles,
+            constraints     : ConstraintSetup::from_trace(trace),
+            pool            : BufferPool::new(trace_length, trace.register_count(), trace.extension_factor()),
+        };
+    }
+
+    /// Returns true if this setup can be used to prove the specified `trace`.
+    pub fn matches(&self, trace: &TraceTable) -> bool {
+        return self.trace_length == trace.unextended_length()
+            && self.register_count == trace.register_count()
+            && self.extension_factor == trace.extension_factor()
+            && self.domain_offset == trace.domain_offset()
+            && self.constraints.matches(trace);
+    }
 
-    debug!("Built proof object in {} ms", now.elapsed().as_millis());
-    return proof;
+    /// Returns the pool of buffers used by the prover.
+    pub fn pool(&self) -> &BufferPool {
+        return &self.pool;
+    }
+
+    /// Returns buffers of the extended `trace` to the pool of this setup.
+    pub fn release(&mut self, trace: TraceTable) {
+        trace.release(&mut self.pool);
+    }
 }
 
 // HELPER FUNCTIONS
@@ -186,16 +575,61 @@ fn evaluations_to_leaves(evaluations: Vec<u128>) -> Vec<[u8; 32]> {
     return unsafe { Vec::from_raw_parts(p as *mut [u8; 32], len, cap) };
 }
 
-fn build_composition_poly(trace: &TraceTable, constraint_poly: ConstraintPoly, seed: &[u8; 32]) -> (Vec<u128>, DeepValues) {
+/// Puts main (or auxiliary, if `aux` is true) registers of all `traces` into a single Merkle tree
+/// such that each leaf of the tree is a hash of the states of all traces at a given step.
+fn build_batch_merkle_tree(traces: &[TraceTable], aux: bool, hash: HashFunction) -> MerkleTree {
+    let columns = traces.iter()
+        .flat_map(|trace| if aux { trace.aux_register_columns() } else { trace.register_columns() })
+        .collect::<Vec<&[u128]>>();
+    let leaves = hash_columns(&columns, hash, utils::get_num_threads(traces[0].domain_size()));
+    return MerkleTree::new(leaves, hash);
+}
+
+/// Returns states of all `traces` at the specified `positions`; states of all traces at a given
+/// position are concatenated into a single row.
+fn get_batch_values_at(traces: &[TraceTable], positions: &[usize], aux: bool) -> Vec<Vec<u128>> {
+    let width = get_batch_width(traces, aux);
+    return positions.iter().map(|&position| {
+        let mut row = vec![field::ZERO; width];
+        read_batch_row_into(traces, position, aux, &mut row);
+        row
+    }).collect();
+}
+
+fn get_batch_width(traces: &[TraceTable], aux: bool) -> usize {
+    return traces.iter()
+        .map(|trace| if aux { trace.aux_register_count() } else { trace.register_count() })
+        .sum();
+}
+
+fn read_batch_row_into(traces: &[TraceTable], step: usize, aux: bool, row: &mut [u128]) {
+    let mut offset = 0;
+    for trace in traces.iter() {
+        if aux {
+            let width = trace.aux_register_count();
+            trace.read_aux_row_into(step, &mut row[offset..(offset + width)]);
+            offset += width;
+        }
+        else {
+            let width = trace.register_count();
+            trace.read_row_into(step, &mut row[offset..(offset + width)]);
+            offset += width;
+        }
+    }
+}
+
+/// Builds the DEEP composition polynomial from the trace and constraint polynomials; also
+/// returns trace states at z and z * g, and the evaluation of the constraint polynomial at z.
+fn build_composition_poly(trace: &TraceTable, constraint_poly: ConstraintPoly, seed: &[u8; 32], pool: &mut BufferPool) -> (Vec<u128>, DeepValues, u128) {
     // pseudo-randomly selection deep point z and coefficients for the composition
     let z = field::prng(*seed);
     let coefficients = CompositionCoefficients::new(*seed);
 
     // divide out deep point from trace polynomials and merge them into a single polynomial
-    let (mut result, s1, s2) = trace.get_composition_poly(z, &coefficients);
+    let (mut result, s1, s2) = trace.get_composition_poly(z, &coefficients, pool);
 
     // divide out deep point from constraint polynomial and merge it into the result
-    constraint_poly.merge_into(&mut result, z, &coefficients);
+    let constraint_at_z = constraint_poly.merge_into(&mut result, z, &coefficients);
 
-    return (result, DeepValues { trace_at_z1: s1, trace_at_z2: s2 });
+    return (result, DeepValues { trace_at_z1: s1, trace_at_z2: s2 }, constraint_at_z);
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use std::{ fmt, cmp };
+use std::{ fmt, cmp, convert::TryFrom };
 use crate::{
     math::field,
     OpCode,
@@ -8,6 +8,7 @@ use crate::{
     NUM_CF_OPS, NUM_LD_OPS, NUM_HD_OPS,
     NUM_CF_OP_BITS, NUM_LD_OP_BITS, NUM_HD_OP_BITS,
     CF_OP_BITS_RANGE, LD_OP_BITS_RANGE, HD_OP_BITS_RANGE,
+    HASH_STATE_WIDTH, HASHER_WIDTH, AUX_WIDTH, BUS_REGISTER_IDX, OUTPUT_REGISTER_IDX,
 };
 
 // CONSTANTS
@@ -26,10 +27,16 @@ pub struct TraceState {
     hd_op_bits  : [u128; NUM_HD_OP_BITS],
     ctx_stack   : Vec<u128>,
     loop_stack  : Vec<u128>,
+    call_flag   : u128,
+    dyn_flag    : u128,
+    call_stack  : Vec<u128>,
     user_stack  : Vec<u128>,
+    hasher      : [u128; HASHER_WIDTH],
+    aux         : [u128; AUX_WIDTH],
 
     ctx_depth   : usize,
     loop_depth  : usize,
+    call_depth  : usize,
     stack_depth : usize,
 
     cf_op_flags : [u128; NUM_CF_OPS],
@@ -47,7 +54,7 @@ impl TraceState {
     // CONSTRUCTORS
     // --------------------------------------------------------------------------------------------
 
-    pub fn new(ctx_depth: usize, loop_depth: usize, stack_depth: usize) -> TraceState {
+    pub fn new(ctx_depth: usize, loop_depth: usize, call_depth: usize, stack_depth: usize) -> TraceState {
         
         return TraceState {
             op_counter  : 0,
@@ -57,9 +64,15 @@ impl TraceState {
             hd_op_bits  : [0; NUM_HD_OP_BITS],
             ctx_stack   : vec![0; cmp::max(ctx_depth, MIN_CONTEXT_DEPTH)],
             loop_stack  : vec![0; cmp::max(loop_depth, MIN_LOOP_DEPTH)],
+            call_flag   : 0,
+            dyn_flag    : 0,
+            call_stack  : vec![0; call_depth],
             user_stack  : vec![0; cmp::max(stack_depth, MIN_STACK_DEPTH)],
+            hasher      : [0; HASHER_WIDTH],
+            aux         : [0; AUX_WIDTH],
             ctx_depth   : ctx_depth,
             loop_depth  : loop_depth,
+            call_depth  : call_depth,
             stack_depth : stack_depth,
             cf_op_flags : [0; NUM_CF_OPS],
             ld_op_flags : [0; NUM_LD_OPS],
@@ -70,7 +83,7 @@ impl TraceState {
         };
     }
 
-    pub fn from_vec(ctx_depth: usize, loop_depth: usize, stack_depth: usize, state: &Vec<u128>) -> TraceState {
+    pub fn from_vec(ctx_depth: usize, loop_depth: usize, call_depth: usize, stack_depth: usize, state: &Vec<u128>) -> TraceState {
 
         let op_counter = state[OP_COUNTER_IDX];
 
@@ -94,14 +107,28 @@ impl TraceState {
         let loop_stack_end = ctx_stack_end + loop_depth;
         loop_stack[..loop_depth].copy_from_slice(&state[ctx_stack_end..loop_stack_end]);
 
+        let call_stack_start = loop_stack_end + get_call_flag_width(call_depth);
+        let call_flag = if call_depth > 0 { state[loop_stack_end] } else { 0 };
+        let dyn_flag = if call_depth > 0 { state[loop_stack_end + 1] } else { 0 };
+        let call_stack_end = call_stack_start + call_depth;
+        let call_stack = state[call_stack_start..call_stack_end].to_vec();
+
         let mut user_stack = vec![0; cmp::max(stack_depth, MIN_STACK_DEPTH)];
-        user_stack[..stack_depth].copy_from_slice(&state[loop_stack_end..]);
+        let user_stack_end = call_stack_end + stack_depth;
+        user_stack[..stack_depth].copy_from_slice(&state[call_stack_end..user_stack_end]);
+
+        let mut hasher = [0; HASHER_WIDTH];
+        let hasher_end = user_stack_end + HASHER_WIDTH;
+        hasher.copy_from_slice(&state[user_stack_end..hasher_end]);
+
+        let mut aux = [0; AUX_WIDTH];
+        aux.copy_from_slice(&state[hasher_end..]);
 
         return TraceState {
             op_counter, sponge,
             cf_op_bits, ld_op_bits, hd_op_bits,
-            ctx_stack, loop_stack, user_stack,
-            ctx_depth, loop_depth, stack_depth,
+            ctx_stack, loop_stack, call_flag, dyn_flag, call_stack, user_stack, hasher, aux,
+            ctx_depth, loop_depth, call_depth, stack_depth,
             cf_op_flags : [0; NUM_CF_OPS],
             ld_op_flags : [0; NUM_LD_OPS],
             hd_op_flags : [0; NUM_HD_OPS],
@@ -113,14 +140,15 @@ impl TraceState {
 
     // STATIC FUNCTIONS
     // --------------------------------------------------------------------------------------------
-    pub fn compute_decoder_width(ctx_depth: usize, loop_depth: usize) -> usize {
-        return NUM_STATIC_DECODER_REGISTERS + ctx_depth + loop_depth;
+    pub fn compute_decoder_width(ctx_depth: usize, loop_depth: usize, call_depth: usize) -> usize {
+        return NUM_STATIC_DECODER_REGISTERS + ctx_depth + loop_depth + get_call_flag_width(call_depth) + call_depth;
     }
 
     // PUBLIC ACCESSORS
     // --------------------------------------------------------------------------------------------
     pub fn width(&self) -> usize {
-        return HD_OP_BITS_RANGE.end + self.ctx_depth + self.loop_depth + self.stack_depth;
+        return TraceState::compute_decoder_width(self.ctx_depth, self.loop_depth, self.call_depth)
+            + self.stack_depth + HASHER_WIDTH + AUX_WIDTH;
     }
 
     pub fn stack_depth(&self) -> usize {
@@ -173,6 +201,12 @@ impl TraceState {
         return result;
     }
 
+    /// Returns the user operation encoded by the op bits of this state; fails if the op bits
+    /// do not encode a valid operation.
+    pub fn user_op(&self) -> Result<OpCode, String> {
+        return OpCode::try_from(self.op_code());
+    }
+
     pub fn set_op_bits(&mut self, bits: [u128; NUM_OP_BITS]) {
         self.cf_op_bits.copy_from_slice(&bits[..3]);
         self.ld_op_bits.copy_from_slice(&bits[3..8]);
@@ -233,6 +267,52 @@ impl TraceState {
         return &self.user_stack;
     }
 
+    // PROCEDURE CALLS
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns 1 if BEGIN or TEND operation at this step starts or ends a procedure call; for
+    /// traces of programs which do not call procedures this is always 0.
+    pub fn call_flag(&self) -> u128 {
+        return self.call_flag;
+    }
+
+    /// Returns 1 if the procedure call started or ended at this step is a dynamic call.
+    pub fn dyn_flag(&self) -> u128 {
+        return self.dyn_flag;
+    }
+
+    pub fn call_stack(&self) -> &[u128] {
+        return &self.call_stack;
+    }
+
+    // HASHER
+    // --------------------------------------------------------------------------------------------
+    pub fn hasher_flag(&self) -> u128 {
+        return self.hasher[0];
+    }
+
+    pub fn hasher_state(&self) -> &[u128] {
+        return &self.hasher[1..(HASH_STATE_WIDTH + 1)];
+    }
+
+    pub fn hasher_input(&self) -> &[u128] {
+        return &self.hasher[(HASH_STATE_WIDTH + 1)..];
+    }
+
+    // AUXILIARY REGISTERS
+    // --------------------------------------------------------------------------------------------
+    pub fn aux(&self) -> &[u128] {
+        return &self.aux;
+    }
+
+    pub fn bus(&self) -> u128 {
+        return self.aux[BUS_REGISTER_IDX];
+    }
+
+    pub fn output_tape(&self) -> u128 {
+        return self.aux[OUTPUT_REGISTER_IDX];
+    }
+
     // RAW STATE
     // --------------------------------------------------------------------------------------------
     pub fn to_vec(&self) -> Vec<u128> {
@@ -244,7 +324,14 @@ impl TraceState {
         result.extend_from_slice(&self.hd_op_bits);
         result.extend_from_slice(&self.ctx_stack[..self.ctx_depth]);
         result.extend_from_slice(&self.loop_stack[..self.loop_depth]);
+        if self.call_depth > 0 {
+            result.push(self.call_flag);
+            result.push(self.dyn_flag);
+            result.extend_from_slice(&self.call_stack);
+        }
         result.extend_from_slice(&self.user_stack[..self.stack_depth]);
+        result.extend_from_slice(&self.hasher);
+        result.extend_from_slice(&self.aux);
         return result;
     }
 
@@ -268,14 +355,36 @@ impl TraceState {
             self.loop_stack[i] = trace[j][step];
         }
 
-        let user_stack_end = loop_stack_end + self.stack_depth;
-        for (i, j) in (loop_stack_end..user_stack_end).enumerate() {
+        let call_stack_start = loop_stack_end + get_call_flag_width(self.call_depth);
+        if self.call_depth > 0 {
+            self.call_flag = trace[loop_stack_end][step];
+            self.dyn_flag = trace[loop_stack_end + 1][step];
+        }
+
+        let call_stack_end = call_stack_start + self.call_depth;
+        for (i, j) in (call_stack_start..call_stack_end).enumerate() {
+            self.call_stack[i] = trace[j][step];
+        }
+
+        let user_stack_end = call_stack_end + self.stack_depth;
+        for (i, j) in (call_stack_end..user_stack_end).enumerate() {
             self.user_stack[i] = trace[j][step];
         }
+
+        let hasher_end = user_stack_end + HASHER_WIDTH;
+        for (i, j) in (user_stack_end..hasher_end).enumerate() {
+            self.hasher[i] = trace[j][step];
+        }
         
         self.op_flags_set = false;
     }
 
+    pub fn update_aux_from_trace(&mut self, aux_trace: &Vec<Vec<u128>>, step: usize) {
+        for (i, register) in aux_trace.iter().enumerate() {
+            self.aux[i] = register[step];
+        }
+    }
+
     // HELPER METHODS
     // --------------------------------------------------------------------------------------------
     fn set_op_flags(&mut self) {
@@ -298,7 +407,7 @@ impl TraceState {
         let not_1 = binary_not(self.ld_op_bits[1]);
         self.ld_op_flags[0] = field::mul(not_0, not_1);
         self.ld_op_flags[1] = field::mul(self.ld_op_bits[0], not_1);
-        self.ld_op_flags[2] = field::mul(not_0, self.cf_op_bits[1]);
+        self.ld_op_flags[2] = field::mul(not_0, self.ld_op_bits[1]);
         self.ld_op_flags[3] = field::mul(self.ld_op_bits[0], self.ld_op_bits[1]);
         self.ld_op_flags.copy_within(0..4, 4);
 
@@ -352,7 +461,7 @@ impl TraceState {
 
 impl fmt::Debug for TraceState {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "[{:>4}] {:>32X?} {:?} {:?} {:?} {:>32X?} {:>32X?} {:?}",
+        write!(f, "[{:>4}] {:>32X?} {:?} {:?} {:?} {:>32X?} {:>32X?} {} {} {:>32X?} {:?} {:>32X?} {:>32X?}",
             self.op_counter,
             self.sponge, 
             self.cf_op_bits,
@@ -360,14 +469,19 @@ impl fmt::Debug for TraceState {
             self.hd_op_bits,
             self.ctx_stack,
             self.loop_stack,
-            self.user_stack
+            self.call_flag,
+            self.dyn_flag,
+            self.call_stack,
+            self.user_stack,
+            self.hasher,
+            self.aux
         )
     }
 }
 
 impl fmt::Display for TraceState {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "[{:>4}] {:>16X?} {:?} {:?} {:?} {:>16X?} {:>16X?} {:?}",
+        write!(f, "[{:>4}] {:>16X?} {:?} {:?} {:?} {:>16X?} {:>16X?} {} {} {:>16X?} {:?} {:>16X?} {:>16X?}",
             self.op_counter,
             self.sponge.iter().map(|x| x >> 64).collect::<Vec<u128>>(),
             self.cf_op_bits,
@@ -375,7 +489,12 @@ impl fmt::Display for TraceState {
             self.hd_op_bits,
             self.ctx_stack.iter().map(|x| x >> 64).collect::<Vec<u128>>(),
             self.loop_stack.iter().map(|x| x >> 64).collect::<Vec<u128>>(),
-            &self.user_stack[..self.stack_depth]
+            self.call_flag,
+            self.dyn_flag,
+            self.call_stack.iter().map(|x| x >> 64).collect::<Vec<u128>>(),
+            &self.user_stack[..self.stack_depth],
+            self.hasher.iter().map(|x| x >> 64).collect::<Vec<u128>>(),
+            self.aux.iter().map(|x| x >> 64).collect::<Vec<u128>>()
         )
     }
 }
@@ -387,19 +506,27 @@ fn binary_not(v: u128) -> u128 {
     return field::sub(field::ONE, v);
 }
 
+/// Returns the number of call flag registers in a trace with the specified call stack depth;
+/// the call flag and the dynamic call flag registers are present only when the call stack is
+/// not empty.
+fn get_call_flag_width(call_depth: usize) -> usize {
+    return if call_depth > 0 { 2 } else { 0 };
+}
+
 // TESTS
 // ================================================================================================
 #[cfg(test)]
 mod tests {
 
-    use super::{ TraceState };
+    use super::{ TraceState, OpCode };
 
     #[test]
     fn from_vec() {
 
         // empty context and loop stacks
-        let state = TraceState::from_vec(0, 0, 2, &vec![
-            101,  1, 2, 3, 4,  5, 6, 7,  8, 9, 10, 11, 12,  13, 14,  15, 16
+        let state = TraceState::from_vec(0, 0, 0, 2, &vec![
+            101,  1, 2, 3, 4,  5, 6, 7,  8, 9, 10, 11, 12,  13, 14,  15, 16,  17, 18, 19, 20, 21, 22, 23,
+            24, 25, 26, 27, 28, 29,  30, 31
         ]);
 
         assert_eq!(101, state.op_counter());
@@ -410,15 +537,22 @@ mod tests {
         assert_eq!([0], state.ctx_stack());
         assert_eq!([0], state.loop_stack());
         assert_eq!([15, 16, 0, 0, 0, 0, 0, 0], state.user_stack());
-        assert_eq!(17, state.width());
+        assert_eq!(17, state.hasher_flag());
+        assert_eq!([18, 19, 20, 21, 22, 23], state.hasher_state());
+        assert_eq!([24, 25, 26, 27, 28, 29], state.hasher_input());
+        assert_eq!(30, state.bus());
+        assert_eq!(31, state.output_tape());
+        assert_eq!(32, state.width());
         assert_eq!(2, state.stack_depth());
         assert_eq!(vec![
-            101, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16
+            101, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
+            26, 27, 28, 29, 30, 31
         ], state.to_vec());
 
         // 1 item on context stack, empty loop stack
-        let state = TraceState::from_vec(1, 0, 2, &vec![
-            101,  1, 2, 3, 4,  5, 6, 7,  8, 9, 10, 11, 12,  13, 14,  15,  16, 17
+        let state = TraceState::from_vec(1, 0, 0, 2, &vec![
+            101,  1, 2, 3, 4,  5, 6, 7,  8, 9, 10, 11, 12,  13, 14,  15,  16, 17,  18, 19, 20, 21, 22, 23,
+            24,  25, 26, 27, 28, 29, 30,  31, 32
         ]);
 
         assert_eq!(101, state.op_counter());
@@ -429,16 +563,23 @@ mod tests {
         assert_eq!([15], state.ctx_stack());
         assert_eq!([0], state.loop_stack());
         assert_eq!([16, 17, 0, 0, 0, 0, 0, 0], state.user_stack());
-        assert_eq!(18, state.width());
+        assert_eq!(18, state.hasher_flag());
+        assert_eq!([19, 20, 21, 22, 23, 24], state.hasher_state());
+        assert_eq!([25, 26, 27, 28, 29, 30], state.hasher_input());
+        assert_eq!(31, state.bus());
+        assert_eq!(32, state.output_tape());
+        assert_eq!(33, state.width());
         assert_eq!(2, state.stack_depth());
         assert_eq!(vec![
-            101, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17
+            101, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26,
+            27, 28, 29, 30, 31, 32
         ], state.to_vec());
 
         // non-empty loop stack
-        let state = TraceState::from_vec(2, 1, 9, &vec![
+        let state = TraceState::from_vec(2, 1, 0, 9, &vec![
             101,  1, 2, 3, 4,  5, 6, 7,  8, 9, 10, 11, 12,  13, 14,  15, 16,  17,
-            18, 19, 20, 21, 22, 23, 24, 25, 26,
+            18, 19, 20, 21, 22, 23, 24, 25, 26,  27, 28, 29, 30, 31, 32, 33,  34, 35, 36, 37, 38, 39,
+            40, 41,
         ]);
 
         assert_eq!(101, state.op_counter());
@@ -449,18 +590,24 @@ mod tests {
         assert_eq!([15, 16], state.ctx_stack());
         assert_eq!([17], state.loop_stack());
         assert_eq!([18, 19, 20, 21, 22, 23, 24, 25, 26], state.user_stack());
-        assert_eq!(27, state.width());
+        assert_eq!(27, state.hasher_flag());
+        assert_eq!([28, 29, 30, 31, 32, 33], state.hasher_state());
+        assert_eq!([34, 35, 36, 37, 38, 39], state.hasher_input());
+        assert_eq!(40, state.bus());
+        assert_eq!(41, state.output_tape());
+        assert_eq!(42, state.width());
         assert_eq!(9, state.stack_depth());
         assert_eq!(vec![
             101, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17,
-            18, 19, 20, 21, 22, 23, 24, 25, 26,
+            18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41,
         ], state.to_vec());
     }
 
     #[test]
     fn update_from_trace() {
         let data = vec![
-            101,  1, 2, 3, 4,  5, 6, 7,  8, 9, 10, 11, 12,  13, 14,  15, 16,  17,  18, 19, 20
+            101,  1, 2, 3, 4,  5, 6, 7,  8, 9, 10, 11, 12,  13, 14,  15, 16,  17,  18, 19, 20,
+            21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33
         ];
         let mut trace = Vec::with_capacity(data.len());
         for i in 0..data.len() {
@@ -468,7 +615,7 @@ mod tests {
         }
 
         // first row
-        let mut state = TraceState::new(2, 1, 3);
+        let mut state = TraceState::new(2, 1, 0, 3);
         state.update_from_trace(&trace, 0);
 
         assert_eq!(0, state.op_counter());
@@ -479,7 +626,12 @@ mod tests {
         assert_eq!([0, 0], state.ctx_stack());
         assert_eq!([0], state.loop_stack());
         assert_eq!([0, 0, 0, 0, 0, 0, 0, 0], state.user_stack());
-        assert_eq!(21, state.width());
+        assert_eq!(0, state.hasher_flag());
+        assert_eq!([0, 0, 0, 0, 0, 0], state.hasher_state());
+        assert_eq!([0, 0, 0, 0, 0, 0], state.hasher_input());
+        assert_eq!(0, state.bus());
+        assert_eq!(0, state.output_tape());
+        assert_eq!(36, state.width());
         assert_eq!(3, state.stack_depth());
 
         // second row
@@ -493,16 +645,25 @@ mod tests {
         assert_eq!([15, 16], state.ctx_stack());
         assert_eq!([17], state.loop_stack());
         assert_eq!([18, 19, 20, 0, 0, 0, 0, 0], state.user_stack());
-        assert_eq!(21, state.width());
+        assert_eq!(21, state.hasher_flag());
+        assert_eq!([22, 23, 24, 25, 26, 27], state.hasher_state());
+        assert_eq!([28, 29, 30, 31, 32, 33], state.hasher_input());
+        assert_eq!(36, state.width());
         assert_eq!(3, state.stack_depth());
+
+        // auxiliary registers are updated separately
+        state.update_aux_from_trace(&vec![vec![0, 34, 0], vec![0, 35, 0]], 1);
+        assert_eq!(34, state.bus());
+        assert_eq!(35, state.output_tape());
+        assert_eq!(22, state.hasher_state()[0]);
     }
 
     #[test]
     fn op_flags() {
 
         // all zeros
-        let state = TraceState::from_vec(1, 0, 2, &vec![
-            101,  1, 2, 3, 4,  0, 0, 0,  0, 0, 0, 0, 0,  0, 0,  15, 16, 17
+        let state = TraceState::from_vec(1, 0, 0, 2, &vec![
+            101,  1, 2, 3, 4,  0, 0, 0,  0, 0, 0, 0, 0,  0, 0,  15, 16, 17,  0, 0, 0, 0, 0, 0, 0,  0, 0, 0, 0, 0, 0,  0, 0
         ]);
 
         assert_eq!([1, 0, 0, 0, 0, 0, 0, 0], state.cf_op_flags());
@@ -515,8 +676,8 @@ mod tests {
         assert_eq!(0, state.noop_flag());
 
         // all ones
-        let state = TraceState::from_vec(1, 0, 2, &vec![
-            101,  1, 2, 3, 4,  1, 1, 1,  1, 1, 1, 1, 1,  1, 1,  15, 16, 17
+        let state = TraceState::from_vec(1, 0, 0, 2, &vec![
+            101,  1, 2, 3, 4,  1, 1, 1,  1, 1, 1, 1, 1,  1, 1,  15, 16, 17,  0, 0, 0, 0, 0, 0, 0,  0, 0, 0, 0, 0, 0,  0, 0
         ]);
 
         assert_eq!([0, 0, 0, 0, 0, 0, 0, 1], state.cf_op_flags());
@@ -529,8 +690,8 @@ mod tests {
         assert_eq!(1, state.noop_flag());
 
         // mixed 1
-        let state = TraceState::from_vec(1, 0, 2, &vec![
-            101,  1, 2, 3, 4,  1, 0, 0,  1, 0, 0, 0, 0,  1, 0,  15, 16, 17
+        let state = TraceState::from_vec(1, 0, 0, 2, &vec![
+            101,  1, 2, 3, 4,  1, 0, 0,  1, 0, 0, 0, 0,  1, 0,  15, 16, 17,  0, 0, 0, 0, 0, 0, 0,  0, 0, 0, 0, 0, 0,  0, 0
         ]);
 
         assert_eq!([0, 1, 0, 0, 0, 0, 0, 0], state.cf_op_flags());
@@ -543,8 +704,8 @@ mod tests {
         assert_eq!(0, state.noop_flag());
 
         // mixed 2
-        let state = TraceState::from_vec(1, 0, 2, &vec![
-            101, 1, 2, 3, 4, 1, 1, 0, 1, 1, 0, 0, 0, 0, 1, 15, 16, 17
+        let state = TraceState::from_vec(1, 0, 0, 2, &vec![
+            101, 1, 2, 3, 4, 1, 1, 0, 1, 1, 0, 0, 0, 0, 1, 15, 16, 17,  0, 0, 0, 0, 0, 0, 0,  0, 0, 0, 0, 0, 0,  0, 0
         ]);
 
         assert_eq!([0, 0, 0, 1, 0, 0, 0, 0], state.cf_op_flags());
@@ -553,28 +714,50 @@ mod tests {
             0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
         ], state.ld_op_flags());
         assert_eq!([0, 0, 1, 0], state.hd_op_flags());
+
+        // mixed 3
+        let state = TraceState::from_vec(1, 0, 0, 2, &vec![
+            101, 1, 2, 3, 4, 0, 0, 0, 0, 1, 0, 0, 1, 1, 1, 15, 16, 17,  0, 0, 0, 0, 0, 0, 0,  0, 0, 0, 0, 0, 0,  0, 0
+        ]);
+
+        assert_eq!([1, 0, 0, 0, 0, 0, 0, 0], state.cf_op_flags());
+        assert_eq!([
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ], state.ld_op_flags());
+        assert_eq!([0, 0, 0, 1], state.hd_op_flags());
     }
 
     #[test]
     fn op_code() {
-        let state = TraceState::from_vec(1, 0, 2, &vec![
-            101,  1, 2, 3, 4,  1, 1, 1,  0, 0, 0, 0, 0,  0, 0,  15, 16, 17
+        let state = TraceState::from_vec(1, 0, 0, 2, &vec![
+            101,  1, 2, 3, 4,  1, 1, 1,  0, 0, 0, 0, 0,  0, 0,  15, 16, 17,  0, 0, 0, 0, 0, 0, 0,  0, 0, 0, 0, 0, 0,  0, 0
         ]);
         assert_eq!(0, state.op_code());
+        assert_eq!(Ok(OpCode::Begin), state.user_op());
 
-        let state = TraceState::from_vec(1, 0, 2, &vec![
-            101,  1, 2, 3, 4,  1, 1, 1,  1, 1, 1, 1, 1,  1, 1,  15, 16, 17
+        let state = TraceState::from_vec(1, 0, 0, 2, &vec![
+            101,  1, 2, 3, 4,  1, 1, 1,  1, 1, 1, 1, 1,  1, 1,  15, 16, 17,  0, 0, 0, 0, 0, 0, 0,  0, 0, 0, 0, 0, 0,  0, 0
         ]);
         assert_eq!(127, state.op_code());
+        assert_eq!(Ok(OpCode::Noop), state.user_op());
 
-        let state = TraceState::from_vec(1, 0, 2, &vec![
-            101,  1, 2, 3, 4,  1, 1, 1,  1, 1, 1, 1, 1,  1, 0,  15, 16, 17
+        let state = TraceState::from_vec(1, 0, 0, 2, &vec![
+            101,  1, 2, 3, 4,  1, 1, 1,  1, 1, 1, 1, 1,  1, 0,  15, 16, 17,  0, 0, 0, 0, 0, 0, 0,  0, 0, 0, 0, 0, 0,  0, 0
         ]);
         assert_eq!(63, state.op_code());
+        assert_eq!(Ok(OpCode::Cmp), state.user_op());
 
-        let state = TraceState::from_vec(1, 0, 2, &vec![
-            101,  1, 2, 3, 4,  1, 1, 1,  1, 0, 0, 0, 0,  1, 1,  15, 16, 17
+        let state = TraceState::from_vec(1, 0, 0, 2, &vec![
+            101,  1, 2, 3, 4,  1, 1, 1,  1, 0, 0, 0, 0,  1, 1,  15, 16, 17,  0, 0, 0, 0, 0, 0, 0,  0, 0, 0, 0, 0, 0,  0, 0
         ]);
         assert_eq!(97, state.op_code());
+        assert_eq!(Ok(OpCode::AssertEq), state.user_op());
+
+        // op bits which are not binary may not encode any operation
+        let state = TraceState::from_vec(1, 0, 0, 2, &vec![
+            101,  1, 2, 3, 4,  1, 1, 1,  1, 0, 0, 0, 0,  1, 2,  15, 16, 17,  0, 0, 0, 0, 0, 0, 0,  0, 0, 0, 0, 0, 0,  0, 0
+        ]);
+        assert!(state.user_op().is_err());
     }
 }
\ No newline at end of file
@@ -1,7 +1,7 @@
-use crate::math::{ field, fft, polynom, parallel };
-use crate::crypto::{ MerkleTree, HashFunction };
-use crate::stark::{ CompositionCoefficients, utils };
-use crate::utils::{ uninit_vector, filled_vector, as_bytes };
+use crate::math::{ field, polynom, parallel };
+use crate::crypto::{ MerkleTree, HashFunction, hash_columns };
+use crate::stark::{ CompositionCoefficients, BufferPool, utils };
+use crate::utils::{ zeroize };
 use super::{ TraceState };
 
 // TYPES AND INTERFACES
@@ -9,23 +9,31 @@ use super::{ TraceState };
 pub struct TraceTable {
     registers       : Vec<Vec<u128>>,
     polys           : Vec<Vec<u128>>,
+    aux_registers   : Vec<Vec<u128>>,
+    aux_polys       : Vec<Vec<u128>>,
+    trace           : Vec<Vec<u128>>,   // un-extended registers; kept once the table is extended
+    aux_trace       : Vec<Vec<u128>>,   // un-extended auxiliary registers
     ctx_depth       : usize,
     loop_depth      : usize,
+    call_depth      : usize,
     stack_depth     : usize,
     trace_length    : usize,
     extension_factor: usize,
+    domain_offset   : u128,
 }
 
 // TRACE TABLE IMPLEMENTATION
 // ================================================================================================
 impl TraceTable {
-    /// Returns a trace table constructed from the specified register traces.
-    pub fn new(registers: Vec<Vec<u128>>, ctx_depth: usize, loop_depth: usize, extension_factor: usize) -> TraceTable
+    /// Returns a trace table constructed from the specified register traces; once extended, the
+    /// registers are evaluated over the coset of the LDE domain shifted by `domain_offset`.
+    pub fn new(registers: Vec<Vec<u128>>, ctx_depth: usize, loop_depth: usize, call_depth: usize, extension_factor: usize, domain_offset: u128) -> TraceTable
     {
         // validate extension factor
         assert!(extension_factor.is_power_of_two(), "trace extension factor must be a power of 2");
         assert!(extension_factor >= crate::MIN_EXTENSION_FACTOR,
             "extension factor must be at least {}", crate::MIN_EXTENSION_FACTOR);
+        assert!(domain_offset != field::ZERO, "domain offset cannot be zero");
 
         // validate context depth
         assert!(ctx_depth <= crate::MAX_CONTEXT_DEPTH,
@@ -35,10 +43,15 @@ impl TraceTable {
         assert!(loop_depth <= crate::MAX_LOOP_DEPTH,
             "loop depth cannot be greater than {}", crate::MAX_LOOP_DEPTH);
 
-        // compute stack depth
-        let decoder_width = TraceState::compute_decoder_width(ctx_depth, loop_depth);
-        assert!(registers.len() > decoder_width, "user stack must consist of at least one register");
-        let stack_depth = registers.len() - decoder_width;
+        // validate call depth
+        assert!(call_depth <= crate::MAX_CALL_DEPTH,
+            "call depth cannot be greater than {}", crate::MAX_CALL_DEPTH);
+
+        // compute stack depth; hasher registers always follow the user stack
+        let decoder_width = TraceState::compute_decoder_width(ctx_depth, loop_depth, call_depth);
+        assert!(registers.len() > decoder_width + crate::HASHER_WIDTH,
+            "user stack must consist of at least one register");
+        let stack_depth = registers.len() - decoder_width - crate::HASHER_WIDTH;
 
         // validate register traces
         assert!(registers.len() < crate::MAX_REGISTER_COUNT,
@@ -52,32 +65,46 @@ impl TraceTable {
         let polys = Vec::with_capacity(registers.len());
         return TraceTable {
             registers, polys,
-            ctx_depth, loop_depth, stack_depth,
-            trace_length, extension_factor
+            aux_registers: Vec::new(), aux_polys: Vec::new(),
+            trace: Vec::new(), aux_trace: Vec::new(),
+            ctx_depth, loop_depth, call_depth, stack_depth,
+            trace_length, extension_factor, domain_offset
         };
     }
 
     /// Returns state of the trace table at the specified `step`.
     pub fn get_state(&self, step: usize) -> TraceState {
-        let mut result = TraceState::new(self.ctx_depth, self.loop_depth, self.stack_depth);
+        let mut result = TraceState::new(self.ctx_depth, self.loop_depth, self.call_depth, self.stack_depth);
         self.fill_state(&mut result, step);
         return result;
     }
 
-    /// Returns state of the trace table at the last step.
+    /// Returns state of the execution trace at the last step.
     pub fn get_last_state(&self) -> TraceState {
-        let last_step = if self.is_extended() {
-            self.domain_size() - self.extension_factor()
-        }
-        else {
-            self.unextended_length() - 1
-        };
-        return self.get_state(last_step);
+        let mut result = TraceState::new(self.ctx_depth, self.loop_depth, self.call_depth, self.stack_depth);
+        self.fill_trace_state(&mut result, self.unextended_length() - 1);
+        return result;
     }
 
-    /// Copies trace table state at the specified `step` to the passed in `state` object.
+    /// Copies trace table state at the specified `step` to the passed in `state` object; once
+    /// the table has been extended, steps refer to the points of the LDE domain.
     pub fn fill_state(&self, state: &mut TraceState, step: usize) {
         state.update_from_trace(&self.registers, step);
+        state.update_aux_from_trace(&self.aux_registers, step);
+    }
+
+    /// Copies state of the execution trace at the specified `step` to the passed in `state`
+    /// object; unlike fill_state(), steps always refer to the steps of the un-extended trace.
+    /// When the LDE domain is shifted, the extended registers do not pass through the trace
+    /// states, and so, the states are read from a copy of the un-extended registers.
+    pub fn fill_trace_state(&self, state: &mut TraceState, step: usize) {
+        if self.is_extended() {
+            state.update_from_trace(&self.trace, step);
+            state.update_aux_from_trace(&self.aux_trace, step);
+        }
+        else {
+            state.update_from_trace(&self.registers, step);
+        }
     }
 
     /// Returns the number of states in the un-extended trace table.
@@ -95,11 +122,21 @@ impl TraceTable {
         return self.extension_factor;
     }
 
-    /// Returns the number of registers in the trace table.
+    /// Returns the offset by which the LDE domain of the trace table is shifted.
+    pub fn domain_offset(&self) -> u128 {
+        return self.domain_offset;
+    }
+
+    /// Returns the number of registers in the main segment of the trace table.
     pub fn register_count(&self) -> usize {
         return self.registers.len();
     }
 
+    /// Returns the number of registers in the auxiliary segment of the trace table.
+    pub fn aux_register_count(&self) -> usize {
+        return self.aux_registers.len();
+    }
+
     /// Returns the number of registers used by context stack.
     pub fn ctx_depth(&self) -> usize {
         return self.ctx_depth;
@@ -110,6 +147,11 @@ impl TraceTable {
         return self.loop_depth;
     }
 
+    /// Returns the number of registers used by call stack.
+    pub fn call_depth(&self) -> usize {
+        return self.call_depth;
+    }
+
     /// Returns the number of registers used by user stack.
     pub fn stack_depth(&self) -> usize {
         return self.stack_depth;
@@ -133,77 +175,154 @@ impl TraceTable {
         return result;
     }
 
+    /// Returns values of all auxiliary registers at the specified `positions`.
+    pub fn get_aux_values_at(&self, positions: &[usize]) -> Vec<Vec<u128>> {
+        let mut result = Vec::with_capacity(positions.len());
+        for &i in positions.iter() {
+            let row = self.aux_registers.iter().map(|r| r[i]).collect();
+            result.push(row);
+        }
+        return result;
+    }
+
+    /// Copies values of all registers at the specified `step` into `row`.
+    pub fn read_row_into(&self, step: usize, row: &mut [u128]) {
+        for (value, register) in row.iter_mut().zip(self.registers.iter()) {
+            *value = register[step];
+        }
+    }
+
+    /// Returns values of all registers (extended, if the table has been extended) as a list of
+    /// columns, one column per register.
+    pub fn register_columns(&self) -> Vec<&[u128]> {
+        return self.registers.iter().map(|register| &register[..]).collect();
+    }
+
+    /// Returns values of all auxiliary registers as a list of columns in the same way as
+    /// register_columns() function does for the main registers.
+    pub fn aux_register_columns(&self) -> Vec<&[u128]> {
+        return self.aux_registers.iter().map(|register| &register[..]).collect();
+    }
+
+    /// Copies values of all auxiliary registers at the specified `step` into `row`.
+    pub fn read_aux_row_into(&self, step: usize, row: &mut [u128]) {
+        for (value, register) in row.iter_mut().zip(self.aux_registers.iter()) {
+            *value = register[step];
+        }
+    }
+
     /// Returns `true` if the trace table has been extended.
     pub fn is_extended(&self) -> bool {
         return self.registers[0].len() > self.trace_length;
     }
 
     /// Extends all registers of the trace table by the `extension_factor` specified during
-    /// trace table construction. A trace table can be extended only once.
-    pub fn extend(&mut self, twiddles: &[u128]) {
+    /// trace table construction; `twiddles` are used to evaluate register polynomials over the
+    /// LDE domain (shifted by the `domain_offset` of the table), and `inv_twiddles` to interpolate
+    /// register traces. Buffers for extended
+    /// registers are taken from the `pool`. A trace table can be extended only once.
+    pub fn extend(&mut self, twiddles: &[u128], inv_twiddles: &[u128], pool: &mut BufferPool) {
         assert!(!self.is_extended(), "trace table has already been extended");
         assert!(twiddles.len() * 2 == self.domain_size(), "invalid number of twiddles");
-
-        // build inverse twiddles needed for FFT interpolation
-        let root = field::get_root_of_unity(self.unextended_length());
-        let inv_twiddles = fft::get_inv_twiddles(root, self.unextended_length());
+        assert!(inv_twiddles.len() * 2 == self.unextended_length(), "invalid number of inverse twiddles");
         
-        // move register traces into polys
+        // move register traces into polys and keep a copy of the un-extended registers
         std::mem::swap(&mut self.registers, &mut self.polys);
+        self.trace = self.polys.clone();
 
         // extend all registers
         let domain_size = self.domain_size();
         for poly in self.polys.iter_mut() {
 
             // interpolate register trace into a polynomial
-            polynom::interpolate_fft_twiddles(poly, &inv_twiddles, true);
+            polynom::interpolate_fft_twiddles(poly, inv_twiddles, true);
             
             // allocate space to hold extended evaluations and copy the polynomial into it
-            let mut register = vec![field::ZERO; domain_size];
+            let mut register = pool.take(domain_size);
             register[..poly.len()].copy_from_slice(&poly);
             
-            // evaluate the polynomial over extended domain
+            // evaluate the polynomial over the extended domain shifted by the offset
+            polynom::shift_in_place(&mut register[..poly.len()], self.domain_offset);
             polynom::eval_fft_twiddles(&mut register, &twiddles, true);
             self.registers.push(register);
         }
     }
 
+    /// Interpolates the `aux_registers` and extends them to the LDE domain. Auxiliary registers
+    /// are built from the extended main trace after it has been committed to (e.g. using
+    /// randomness derived from the commitment), and thus, can be added only after the main
+    /// registers have been extended; auxiliary registers can be added only once.
+    pub fn extend_aux(&mut self, aux_registers: Vec<Vec<u128>>, twiddles: &[u128], inv_twiddles: &[u128], pool: &mut BufferPool) {
+        assert!(self.is_extended(), "trace table has not been extended yet");
+        assert!(self.aux_registers.len() == 0, "auxiliary registers have already been added");
+        assert!(twiddles.len() * 2 == self.domain_size(), "invalid number of twiddles");
+        assert!(inv_twiddles.len() * 2 == self.unextended_length(), "invalid number of inverse twiddles");
+        for register in aux_registers.iter() {
+            assert!(register.len() == self.trace_length, "all register traces must have the same length");
+        }
+
+        // extend all auxiliary registers
+        let domain_size = self.domain_size();
+        for mut poly in aux_registers.into_iter() {
+            self.aux_trace.push(poly.clone());
+            polynom::interpolate_fft_twiddles(&mut poly, inv_twiddles, true);
+
+            let mut register = pool.take(domain_size);
+            register[..poly.len()].copy_from_slice(&poly);
+            polynom::shift_in_place(&mut register[..poly.len()], self.domain_offset);
+            polynom::eval_fft_twiddles(&mut register, &twiddles, true);
+
+            self.aux_polys.push(poly);
+            self.aux_registers.push(register);
+        }
+    }
+
+    /// Returns buffers of extended registers (main and auxiliary) to the `pool` so that they
+    /// can be re-used for the next proof.
+    pub fn release(mut self, pool: &mut BufferPool) {
+        for register in self.registers.drain(..).chain(self.aux_registers.drain(..)) {
+            pool.release(register);
+        }
+    }
+
     /// Puts the trace table into a Merkle tree such that each state of the table becomes
     /// a distinct leaf in the tree; all registers at a given step are hashed together to
     /// form a single leaf value.
     pub fn build_merkle_tree(&self, hash: HashFunction) -> MerkleTree {
-        let mut trace_state = vec![field::ZERO; self.register_count()];
-        let mut hashed_states = uninit_vector::<[u8; 32]>(self.domain_size());
-        // TODO: this loop should be parallelized
-        for i in 0..self.domain_size() {
-            for j in 0..trace_state.len() {
-                trace_state[j] = self.registers[j][i];
-            }
-            hash(as_bytes(&trace_state), &mut hashed_states[i]);
-        }
-        return MerkleTree::new(hashed_states, hash);
+        let leaves = hash_columns(&self.register_columns(), hash, utils::get_num_threads(self.domain_size()));
+        return MerkleTree::new(leaves, hash);
     }
 
-    /// Evaluates trace polynomials at the specified point `z`; can be called only after
-    /// the trace table has been extended
+    /// Puts auxiliary registers of the trace table into a Merkle tree in the same way as
+    /// the main registers are put into a tree by build_merkle_tree() function.
+    pub fn build_aux_merkle_tree(&self, hash: HashFunction) -> MerkleTree {
+        let leaves = hash_columns(&self.aux_register_columns(), hash, utils::get_num_threads(self.domain_size()));
+        return MerkleTree::new(leaves, hash);
+    }
+
+    /// Evaluates trace polynomials (including polynomials of auxiliary registers) at the
+    /// specified point `z`; can be called only after the trace table has been extended
     pub fn eval_polys_at(&self, z: u128) -> Vec<u128> {
         assert!(self.is_extended(), "trace table has not been extended yet");
 
         let mut result = Vec::new();
-        for poly in self.polys.iter() {
+        for poly in self.polys.iter().chain(self.aux_polys.iter()) {
             result.push(polynom::eval(poly, z));
         }
         return result;
     }
 
-    /// Combines trace polynomials for all registers into a single composition polynomial.
+    /// Combines trace polynomials for all registers (main and auxiliary) into a single
+    /// composition polynomial.
     /// The combination is done as follows:
     /// 1. First, state of trace registers at deep points z and z * g are computed;
     /// 2. Then, polynomials T1_i(x) = (T_i(x) - T_i(z)) / (x - z) and 
     /// T2_i(x) = (T_i(x) - T_i(z * g)) / (x - z * g) are computed for all i and combined
     /// together into a single polynomial using a pseudo-random linear combination;
     /// 3. Then the degree of the polynomial is adjusted to match the specified degree
-    pub fn get_composition_poly(&self, z: u128, cc: &CompositionCoefficients) -> (Vec<u128>, Vec<u128>, Vec<u128>) {
+    ///
+    /// The buffer for the composition polynomial is taken from the `pool`.
+    pub fn get_composition_poly(&self, z: u128, cc: &CompositionCoefficients, pool: &mut BufferPool) -> (Vec<u128>, Vec<u128>, Vec<u128>) {
 
         let trace_length = self.unextended_length();
         assert!(self.is_extended(), "trace table has not been extended yet");
@@ -219,16 +338,16 @@ impl TraceTable {
         let mut t2_composition = vec![field::ZERO; trace_length];
 
         // combine trace polynomials into 2 composition polynomials T1(x) and T2(x)
-        for i in 0..self.polys.len() {
+        for (i, poly) in self.polys.iter().chain(self.aux_polys.iter()).enumerate() {
             // compute T1(x) = (T(x) - T(z)), multiply it by a pseudo-random coefficient,
             // and add the result into composition polynomial
-            parallel::mul_acc(&mut t1_composition, &self.polys[i], cc.trace1[i], 1);
+            parallel::mul_acc(&mut t1_composition, poly, cc.trace1[i], 1);
             let adjusted_tz = field::mul(trace_state1[i], cc.trace1[i]);
             t1_composition[0] = field::sub(t1_composition[0], adjusted_tz);
 
             // compute T2(x) = (T(x) - T(z * g)), multiply it by a pseudo-random
             // coefficient, and add the result into composition polynomial
-            parallel::mul_acc(&mut t2_composition, &self.polys[i], cc.trace2[i], 1);
+            parallel::mul_acc(&mut t2_composition, poly, cc.trace2[i], 1);
             let adjusted_tz = field::mul(trace_state2[i], cc.trace2[i]);
             t2_composition[0] = field::sub(t2_composition[0], adjusted_tz);
         }
@@ -242,7 +361,9 @@ impl TraceTable {
         // adjust the degree of the polynomial to match the degree parameter by computing
         // C(x) = T(x) * k_1 + T(x) * x^incremental_degree * k_2
         let poly_size = utils::get_composition_degree(trace_length).next_power_of_two();
-        let mut composition_poly = filled_vector(poly_size, self.domain_size(), field::ZERO);
+        // the entire buffer is zeroed because the prover extends the polynomial in place
+        let mut composition_poly = pool.take(self.domain_size());
+        composition_poly.truncate(poly_size);
         let incremental_degree = utils::get_incremental_trace_degree(trace_length);
         // this is equivalent to T(x) * k_1
         parallel::mul_acc(
@@ -261,6 +382,22 @@ impl TraceTable {
     }
 }
 
+/// Registers of the execution trace (and polynomials interpolated from them) are derived from
+/// secret inputs, and so, they are zeroized when the trace table is dropped.
+impl Drop for TraceTable {
+    fn drop(&mut self) {
+        let columns = self.registers.iter_mut()
+            .chain(self.polys.iter_mut())
+            .chain(self.aux_registers.iter_mut())
+            .chain(self.aux_polys.iter_mut())
+            .chain(self.trace.iter_mut())
+            .chain(self.aux_trace.iter_mut());
+        for column in columns {
+            zeroize(column);
+        }
+    }
+}
+
 // TESTS
 // ================================================================================================
 
@@ -271,9 +408,10 @@ mod tests {
     use crate::{
         math::{ field, polynom, parallel, fft },
         crypto::hash::blake3,
+        utils::as_bytes,
         programs::{ Program, ProgramInputs, blocks::{ ProgramBlock, Span, Group } },
         processor::{ execute, OpCode },
-        stark::{ TraceTable, CompositionCoefficients, utils::get_composition_degree }
+        stark::{ TraceTable, CompositionCoefficients, BufferPool, utils::get_composition_degree }
     };
     
     const EXT_FACTOR: usize = 32;
@@ -281,26 +419,90 @@ mod tests {
     #[test]
     fn eval_polys_at() {
         let mut trace = build_trace_table();
+        let mut pool = BufferPool::new(trace.unextended_length(), trace.register_count(), EXT_FACTOR);
         let lde_root = field::get_root_of_unity(trace.domain_size());
-        trace.extend(&fft::get_twiddles(lde_root, trace.domain_size()));
+        let inv_twiddles = get_inv_twiddles(&trace);
+        trace.extend(&fft::get_twiddles(lde_root, trace.domain_size()), &inv_twiddles, &mut pool);
 
         let g = field::get_root_of_unity(trace.unextended_length());
 
+        let bus_register = (0..trace.unextended_length() as u128).collect();
+        let output_register = (0..trace.unextended_length() as u128).rev().collect();
+        let aux_registers = vec![bus_register, output_register];
+        trace.extend_aux(aux_registers, &fft::get_twiddles(lde_root, trace.domain_size()), &inv_twiddles, &mut pool);
+
         let v1 = trace.eval_polys_at(g);
         let s1 = trace.get_state(1 * EXT_FACTOR);
         assert_eq!(v1, s1.to_vec());
+        assert_eq!(1, s1.bus());
+        assert_eq!(trace.unextended_length() as u128 - 2, s1.output_tape());
 
         let v2 = trace.eval_polys_at(field::exp(g, 2));
         let s2 = trace.get_state(2 * EXT_FACTOR);
         assert_eq!(v2, s2.to_vec());
+        assert_eq!(2, s2.bus());
+        assert_eq!(trace.unextended_length() as u128 - 3, s2.output_tape());
+    }
+
+    #[test]
+    fn extend_over_coset() {
+        let offset = 7;
+        let mut trace = build_trace_table();
+        let mut shifted = TraceTable::new(trace.registers.clone(), trace.ctx_depth(), trace.loop_depth(),
+            trace.call_depth(), EXT_FACTOR, offset);
+        let last_state = trace.get_last_state();
+
+        let mut pool = BufferPool::new(trace.unextended_length(), trace.register_count(), EXT_FACTOR);
+        let lde_root = field::get_root_of_unity(trace.domain_size());
+        let twiddles = fft::get_twiddles(lde_root, trace.domain_size());
+        let inv_twiddles = get_inv_twiddles(&trace);
+        trace.extend(&twiddles, &inv_twiddles, &mut pool);
+        shifted.extend(&twiddles, &inv_twiddles, &mut pool);
+
+        // both tables interpolate into the same polynomials, but the shifted table evaluates
+        // them over the coset offset * H
+        for &i in [0, 1, EXT_FACTOR, trace.domain_size() - 1].iter() {
+            let x = field::mul(offset, field::exp(lde_root, i as u128));
+            let row = shifted.registers.iter().map(|register| register[i]).collect::<Vec<u128>>();
+            assert_eq!(trace.eval_polys_at(x), row);
+            assert_ne!(trace.registers.iter().map(|register| register[i]).collect::<Vec<u128>>(), row);
+        }
+
+        // states of the execution trace are still available
+        assert_eq!(last_state.to_vec(), shifted.get_last_state().to_vec());
+        let mut state = shifted.get_state(0);
+        shifted.fill_trace_state(&mut state, 1);
+        assert_eq!(trace.get_state(EXT_FACTOR).to_vec(), state.to_vec());
+    }
+
+    #[test]
+    fn build_merkle_tree() {
+        let mut trace = build_trace_table();
+        let mut pool = BufferPool::new(trace.unextended_length(), trace.register_count(), EXT_FACTOR);
+        let lde_root = field::get_root_of_unity(trace.domain_size());
+        let inv_twiddles = get_inv_twiddles(&trace);
+        trace.extend(&fft::get_twiddles(lde_root, trace.domain_size()), &inv_twiddles, &mut pool);
+
+        // every leaf commits to all registers at a single step of the extended trace, and so, a
+        // query opens a single authentication path regardless of the number of registers
+        let tree = trace.build_merkle_tree(blake3);
+        assert_eq!(trace.domain_size(), tree.leaves().len());
+        for &i in [0, 1, trace.domain_size() - 1].iter() {
+            let row = trace.registers.iter().map(|register| register[i]).collect::<Vec<u128>>();
+            let mut expected = [0u8; 32];
+            blake3(as_bytes(&row), &mut expected);
+            assert_eq!(expected, tree.leaves()[i]);
+        }
     }
 
     #[test]
     fn get_composition_poly() {
 
         let mut trace = build_trace_table();
+        let mut pool = BufferPool::new(trace.unextended_length(), trace.register_count(), EXT_FACTOR);
         let lde_root = field::get_root_of_unity(trace.domain_size());
-        trace.extend(&fft::get_twiddles(lde_root, trace.domain_size()));
+        let inv_twiddles = get_inv_twiddles(&trace);
+        trace.extend(&fft::get_twiddles(lde_root, trace.domain_size()), &inv_twiddles, &mut pool);
 
         // compute trace composition polynomial
         let t_tree = trace.build_merkle_tree(blake3);
@@ -311,7 +513,7 @@ mod tests {
         let g = field::get_root_of_unity(trace.unextended_length());
         let zg = field::mul(z, g);
 
-        let (composition_poly, ..) = trace.get_composition_poly(z, &cc);
+        let (composition_poly, ..) = trace.get_composition_poly(z, &cc, &mut pool);
         let mut actual_evaluations = composition_poly.clone();
         polynom::eval_fft(&mut actual_evaluations, true);
         assert_eq!(target_degree, polynom::infer_degree(&actual_evaluations));
@@ -373,7 +575,12 @@ mod tests {
             ProgramBlock::Span(Span::new(instructions, HashMap::new()))
         ]));
         let inputs = ProgramInputs::from_public(&[1, 0]);
-        let (trace, ctx_depth, loop_depth) = execute(&program, &inputs);
-        return TraceTable::new(trace, ctx_depth, loop_depth, EXT_FACTOR);
+        let (trace, ctx_depth, loop_depth, call_depth) = execute(&program, &inputs, usize::MAX).unwrap();
+        return TraceTable::new(trace, ctx_depth, loop_depth, call_depth, EXT_FACTOR, field::ONE);
+    }
+
+    fn get_inv_twiddles(trace: &TraceTable) -> Vec<u128> {
+        let root = field::get_root_of_unity(trace.unextended_length());
+        return fft::get_inv_twiddles(root, trace.unextended_length());
     }
 }
\ No newline at end of file
@@ -0,0 +1,202 @@
+use crate::{ math::field, crypto::{ HashFunction, Strobe128 } };
+use super::{ ProofOptions, utils };
+
+// CONSTANTS
+// ================================================================================================
+const MERLIN_PROTOCOL_LABEL: &[u8] = b"Merlin v1.0";
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Fiat-Shamir transcript used to derive verifier challenges from prover commitments. The prover
+/// and the verifier interact with the transcript in exactly the same order: every commitment
+/// (e.g. a Merkle root) is absorbed into the transcript before challenges which depend on it are
+/// squeezed from the transcript. Every message is accompanied by a label which describes the role
+/// of the message in the protocol.
+pub trait Transcript {
+
+    /// Absorbs `data` (usually, a commitment) into the transcript.
+    fn absorb(&mut self, label: &'static [u8], data: &[u8]);
+
+    /// Squeezes a 32-byte challenge seed from the transcript; the seed depends on all data
+    /// absorbed into the transcript so far, as well as on all previously squeezed seeds.
+    fn squeeze(&mut self, label: &'static [u8]) -> [u8; 32];
+
+    /// Squeezes a pseudo-random field element from the transcript.
+    fn draw_element(&mut self, label: &'static [u8]) -> u128 {
+        return field::prng(self.squeeze(label));
+    }
+
+    /// Squeezes a set of distinct pseudo-random query positions in the evaluation domain of the
+    /// specified size; positions which fall onto the execution trace domain are skipped, and the
+    /// positions are returned in ascending order.
+    fn draw_positions(&mut self, label: &'static [u8], domain_size: usize, options: &ProofOptions) -> Vec<usize> {
+        return utils::compute_query_positions(&self.squeeze(label), domain_size, options);
+    }
+}
+
+/// Default transcript based on the hash function specified by proof options. The state of the
+/// transcript is a single 32-byte digest, and every hash invocation is made over exactly 64
+/// bytes of input; thus, the transcript can be instantiated with algebraic hash functions (e.g.
+/// Rescue) which makes it feasible to verify proofs inside the VM. Since the order of messages
+/// is fixed by the protocol, labels are not absorbed into the transcript.
+#[derive(Clone)]
+pub struct HashTranscript {
+    state   : [u8; 32],
+    counter : u64,
+    hash_fn : HashFunction,
+}
+
+/// Transcript compatible with transcripts of the [merlin](https://merlin.cool) crate: a message
+/// absorbed with a given label has the same effect as `append_message()` with the same label,
+/// and a seed squeezed with a given label is identical to the output of `challenge_bytes()` for
+/// a 32-byte buffer. Thus, a Distaff proof can be composed with other protocols which derive
+/// their challenges from a Merlin transcript.
+#[derive(Clone)]
+pub struct MerlinTranscript {
+    strobe  : Strobe128,
+}
+
+// HASH TRANSCRIPT IMPLEMENTATION
+// ================================================================================================
+impl HashTranscript {
+
+    pub fn new(hash_fn: HashFunction) -> HashTranscript {
+        return HashTranscript { state: [0; 32], counter: 0, hash_fn };
+    }
+}
+
+impl Transcript for HashTranscript {
+
+    /// Merges `data` into the state 32 bytes at a time as state = hash(state || chunk); the last
+    /// chunk is padded with zeros.
+    fn absorb(&mut self, _label: &'static [u8], data: &[u8]) {
+        let mut buffer = [0u8; 64];
+        for chunk in data.chunks(32) {
+            buffer[..32].copy_from_slice(&self.state);
+            buffer[32..].iter_mut().for_each(|b| *b = 0);
+            buffer[32..(32 + chunk.len())].copy_from_slice(chunk);
+            (self.hash_fn)(&buffer, &mut self.state);
+        }
+        self.counter = 0;
+    }
+
+    /// Computes the seed as hash(state || counter), and increments the counter; the counter is
+    /// reset every time new data is absorbed into the transcript.
+    fn squeeze(&mut self, _label: &'static [u8]) -> [u8; 32] {
+        let mut buffer = [0u8; 64];
+        buffer[..32].copy_from_slice(&self.state);
+        buffer[32..40].copy_from_slice(&self.counter.to_le_bytes());
+        self.counter += 1;
+
+        let mut result = [0u8; 32];
+        (self.hash_fn)(&buffer, &mut result);
+        return result;
+    }
+}
+
+// MERLIN TRANSCRIPT IMPLEMENTATION
+// ================================================================================================
+impl MerlinTranscript {
+
+    /// Returns a new transcript initialized with the specified application protocol `label`;
+    /// this is equivalent to `merlin::Transcript::new(label)`.
+    pub fn new(label: &'static [u8]) -> MerlinTranscript {
+        let mut transcript = MerlinTranscript { strobe: Strobe128::new(MERLIN_PROTOCOL_LABEL) };
+        transcript.append_message(b"dom-sep", label);
+        return transcript;
+    }
+
+    /// Appends a labeled `message` to the transcript.
+    pub fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        let data_len = (message.len() as u32).to_le_bytes();
+        self.strobe.meta_ad(label, false);
+        self.strobe.meta_ad(&data_len, true);
+        self.strobe.ad(message, false);
+    }
+
+    /// Fills the `dest` buffer with labeled challenge bytes derived from the transcript.
+    pub fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        let data_len = (dest.len() as u32).to_le_bytes();
+        self.strobe.meta_ad(label, false);
+        self.strobe.meta_ad(&data_len, true);
+        self.strobe.prf(dest, false);
+    }
+}
+
+impl Transcript for MerlinTranscript {
+
+    fn absorb(&mut self, label: &'static [u8], data: &[u8]) {
+        self.append_message(label, data);
+    }
+
+    fn squeeze(&mut self, label: &'static [u8]) -> [u8; 32] {
+        let mut result = [0u8; 32];
+        self.challenge_bytes(label, &mut result);
+        return result;
+    }
+}
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::crypto::hash;
+    use super::{ Transcript, HashTranscript, MerlinTranscript };
+
+    #[test]
+    fn hash_transcript() {
+        let mut t1 = HashTranscript::new(hash::rescue);
+        let mut t2 = HashTranscript::new(hash::rescue);
+
+        // the same messages result in the same challenges
+        t1.absorb(b"root", &[1; 32]);
+        t2.absorb(b"root", &[1; 32]);
+        let seed1 = t1.squeeze(b"seed");
+        assert_eq!(seed1, t2.squeeze(b"seed"));
+
+        // consecutive challenges are distinct
+        assert_ne!(seed1, t1.squeeze(b"seed"));
+        assert_ne!(seed1, t1.squeeze(b"seed"));
+
+        // challenges depend on all previously absorbed messages
+        let mut t3 = HashTranscript::new(hash::rescue);
+        t3.absorb(b"root", &[2; 32]);
+        let mut t4 = HashTranscript::new(hash::rescue);
+        t1.absorb(b"root", &[3; 32]);
+        t3.absorb(b"root", &[3; 32]);
+        t4.absorb(b"root", &[3; 32]);
+        let seed1 = t1.squeeze(b"seed");
+        assert_ne!(seed1, t3.squeeze(b"seed"));
+        assert_ne!(seed1, t4.squeeze(b"seed"));
+
+        // data longer than 32 bytes is absorbed in full
+        let mut t5 = HashTranscript::new(hash::rescue);
+        let mut t6 = HashTranscript::new(hash::rescue);
+        t5.absorb(b"data", &[1; 40]);
+        t6.absorb(b"data", &[1; 32]);
+        assert_ne!(t5.squeeze(b"seed"), t6.squeeze(b"seed"));
+    }
+
+    #[test]
+    fn merlin_transcript() {
+        // test vector from the merlin crate
+        let mut transcript = MerlinTranscript::new(b"test protocol");
+        transcript.append_message(b"some label", b"some data");
+        let challenge = transcript.squeeze(b"challenge");
+        assert_eq!("d5a21972d0d5fe320c0d263fac7fffb8145aa640af6e9bca177c03c7efcf0615", hex::encode(challenge));
+
+        // absorbing a message is the same as appending it
+        let mut t1 = MerlinTranscript::new(b"test protocol");
+        let mut t2 = MerlinTranscript::new(b"test protocol");
+        t1.absorb(b"root", &[1; 32]);
+        t2.append_message(b"root", &[1; 32]);
+        assert_eq!(t1.squeeze(b"seed"), t2.squeeze(b"seed"));
+
+        // labels affect the challenges
+        t1.absorb(b"root", &[2; 32]);
+        t2.absorb(b"other", &[2; 32]);
+        assert_ne!(t1.squeeze(b"seed"), t2.squeeze(b"seed"));
+    }
+}
@@ -4,10 +4,14 @@ use crate::{
     utils::RangeSlider,
     MAX_REGISTER_COUNT, MAX_PUBLIC_INPUTS,
     SPONGE_WIDTH,
-    MAX_CONTEXT_DEPTH, MAX_LOOP_DEPTH, MAX_STACK_DEPTH,
+    MAX_CONTEXT_DEPTH, MAX_LOOP_DEPTH, MAX_CALL_DEPTH, MAX_STACK_DEPTH,
     MIN_CONTEXT_DEPTH, MIN_LOOP_DEPTH, MIN_STACK_DEPTH,
     NUM_CF_OP_BITS, NUM_LD_OP_BITS, NUM_HD_OP_BITS,
-    stark::constraints::{ NUM_STATIC_DECODER_CONSTRAINTS, NUM_AUX_STACK_CONSTRAINTS },
+    AUX_WIDTH,
+    stark::constraints::{
+        NUM_STATIC_DECODER_CONSTRAINTS, NUM_CALL_FLAG_CONSTRAINTS, NUM_AUX_STACK_CONSTRAINTS, NUM_HASHER_CONSTRAINTS, NUM_BUS_CONSTRAINTS,
+        NUM_OUTPUT_CONSTRAINTS,
+    },
 };
 
 // CONSTANTS
@@ -20,15 +24,22 @@ const NUM_BOUNDARY_CONSTRAINTS: usize =
     + NUM_OP_BITS
     + MAX_CONTEXT_DEPTH
     + MAX_LOOP_DEPTH
-    + MAX_USER_STACK_IO_CONSTRAINTS;
+    + MAX_CALL_DEPTH
+    + MAX_USER_STACK_IO_CONSTRAINTS
+    + AUX_WIDTH;
 
 const NUM_TRANSITION_CONSTRAINTS: usize =
     0 
     + NUM_STATIC_DECODER_CONSTRAINTS
     + MAX_CONTEXT_DEPTH
     + MAX_LOOP_DEPTH
+    + NUM_CALL_FLAG_CONSTRAINTS
+    + MAX_CALL_DEPTH
     + MAX_STACK_DEPTH
-    + NUM_AUX_STACK_CONSTRAINTS;
+    + NUM_AUX_STACK_CONSTRAINTS
+    + NUM_HASHER_CONSTRAINTS
+    + NUM_BUS_CONSTRAINTS
+    + NUM_OUTPUT_CONSTRAINTS;
 
 const NUM_CONSTRAINTS: usize = NUM_TRANSITION_CONSTRAINTS + 2 * NUM_BOUNDARY_CONSTRAINTS;
 
@@ -46,7 +57,9 @@ pub struct BoundaryCoefficients {
     pub op_bits     : [u128; NUM_OP_BITS * 2],
     pub ctx_stack   : [u128; MAX_CONTEXT_DEPTH * 2],
     pub loop_stack  : [u128; MAX_LOOP_DEPTH * 2],
+    pub call_stack  : [u128; MAX_CALL_DEPTH * 2],
     pub user_stack  : [u128; MAX_USER_STACK_IO_CONSTRAINTS * 2],
+    pub aux         : [u128; AUX_WIDTH * 2],
 }
 
 pub struct CompositionCoefficients {
@@ -60,7 +73,7 @@ pub struct CompositionCoefficients {
 // IMPLEMENTATIONS
 // ================================================================================================
 impl ConstraintCoefficients {
-    pub fn new(seed: [u8; 32], ctx_depth: usize, loop_depth: usize, stack_depth: usize) -> ConstraintCoefficients {
+    pub fn new(seed: [u8; 32], ctx_depth: usize, loop_depth: usize, call_depth: usize, stack_depth: usize) -> ConstraintCoefficients {
 
         // generate a pseudo-random list of coefficients
         let coefficients = field::prng_vector(seed, 2 * NUM_CONSTRAINTS);
@@ -70,7 +83,7 @@ impl ConstraintCoefficients {
         let (f_boundary, i) = build_boundary_coefficients(&coefficients[i..]);
 
         // copy coefficients for transition constraints
-        let transition = build_transition_coefficients(&coefficients[i..], ctx_depth, loop_depth, stack_depth);
+        let transition = build_transition_coefficients(&coefficients[i..], ctx_depth, loop_depth, call_depth, stack_depth);
 
         return ConstraintCoefficients { i_boundary, f_boundary, transition };
     }
@@ -113,7 +126,9 @@ fn build_boundary_coefficients(coefficients: &[u128]) -> (BoundaryCoefficients,
         op_bits     : [0; NUM_OP_BITS * 2],
         ctx_stack   : [0; MAX_CONTEXT_DEPTH * 2],
         loop_stack  : [0; MAX_LOOP_DEPTH * 2],
+        call_stack  : [0; MAX_CALL_DEPTH * 2],
         user_stack  : [0; MAX_USER_STACK_IO_CONSTRAINTS * 2],
+        aux         : [0; AUX_WIDTH * 2],
     };
 
     let mut range: Range<usize> = Range { start: 0, end: 2 };
@@ -131,24 +146,36 @@ fn build_boundary_coefficients(coefficients: &[u128]) -> (BoundaryCoefficients,
     range = range.slide(MAX_LOOP_DEPTH * 2);
     result.loop_stack.copy_from_slice(&coefficients[range.clone()]);
 
+    range = range.slide(MAX_CALL_DEPTH * 2);
+    result.call_stack.copy_from_slice(&coefficients[range.clone()]);
+
     range = range.slide(MAX_USER_STACK_IO_CONSTRAINTS * 2);
     result.user_stack.copy_from_slice(&coefficients[range.clone()]);
 
+    range = range.slide(AUX_WIDTH * 2);
+    result.aux.copy_from_slice(&coefficients[range.clone()]);
+
     return (result, range.end);
 }
 
-fn build_transition_coefficients(coefficients: &[u128], ctx_depth: usize, loop_depth: usize, stack_depth: usize) -> Vec<u128>{
+fn build_transition_coefficients(coefficients: &[u128], ctx_depth: usize, loop_depth: usize, call_depth: usize, stack_depth: usize) -> Vec<u128>{
 
     let ctx_depth = std::cmp::max(ctx_depth, MIN_CONTEXT_DEPTH);
     let loop_depth = std::cmp::max(loop_depth, MIN_LOOP_DEPTH);
     let stack_depth = std::cmp::max(stack_depth, MIN_STACK_DEPTH);
+    let call_flag_constraints = if call_depth > 0 { NUM_CALL_FLAG_CONSTRAINTS } else { 0 };
 
     // compute number of used transition constraints
     let num_constraints = NUM_STATIC_DECODER_CONSTRAINTS
         + ctx_depth
         + loop_depth
+        + call_flag_constraints
+        + call_depth
         + stack_depth
-        + NUM_AUX_STACK_CONSTRAINTS;
+        + NUM_AUX_STACK_CONSTRAINTS
+        + NUM_HASHER_CONSTRAINTS
+        + NUM_BUS_CONSTRAINTS
+        + NUM_OUTPUT_CONSTRAINTS;
 
     // we need 2 coefficients per constraint
     let mut result = vec![0; num_constraints * 2];
@@ -158,8 +185,12 @@ fn build_transition_coefficients(coefficients: &[u128], ctx_depth: usize, loop_d
     // 1. static decoder constraints (e.g. op counter, op bit constraints, sponge constraints etc.)
     // 2. context stack constraints - the number depends on the actual context depth
     // 3. loop stack constraints - the number depends on the actual loop depth
-    // 4. aux stack constraints
-    // 5. user stack constraints - the number depends on the actual stack depth
+    // 4. call flag and call stack constraints - present only when the call stack is not empty
+    // 5. aux stack constraints
+    // 6. user stack constraints - the number depends on the actual stack depth
+    // 7. hasher constraints
+    // 8. bus constraints
+    // 9. output tape constraints
 
     let mut s_range = new_range(0, NUM_STATIC_DECODER_CONSTRAINTS * 2);
     let mut t_range = new_range(0, NUM_STATIC_DECODER_CONSTRAINTS * 2);
@@ -173,7 +204,15 @@ fn build_transition_coefficients(coefficients: &[u128], ctx_depth: usize, loop_d
     t_range = t_range.slide(loop_depth * 2);
     result[t_range.clone()].copy_from_slice(&coefficients[s_range.clone()]);
 
-    s_range = new_range(s_range.start + MAX_LOOP_DEPTH * 2, NUM_AUX_STACK_CONSTRAINTS * 2);
+    s_range = new_range(s_range.start + MAX_LOOP_DEPTH * 2, call_flag_constraints * 2);
+    t_range = t_range.slide(call_flag_constraints * 2);
+    result[t_range.clone()].copy_from_slice(&coefficients[s_range.clone()]);
+
+    s_range = new_range(s_range.start + NUM_CALL_FLAG_CONSTRAINTS * 2, call_depth * 2);
+    t_range = t_range.slide(call_depth * 2);
+    result[t_range.clone()].copy_from_slice(&coefficients[s_range.clone()]);
+
+    s_range = new_range(s_range.start + MAX_CALL_DEPTH * 2, NUM_AUX_STACK_CONSTRAINTS * 2);
     t_range = t_range.slide(NUM_AUX_STACK_CONSTRAINTS * 2);
     result[t_range.clone()].copy_from_slice(&coefficients[s_range.clone()]);
 
@@ -181,6 +220,18 @@ fn build_transition_coefficients(coefficients: &[u128], ctx_depth: usize, loop_d
     t_range = t_range.slide(stack_depth * 2);
     result[t_range.clone()].copy_from_slice(&coefficients[s_range.clone()]);
 
+    s_range = new_range(s_range.start + MAX_STACK_DEPTH * 2, NUM_HASHER_CONSTRAINTS * 2);
+    t_range = t_range.slide(NUM_HASHER_CONSTRAINTS * 2);
+    result[t_range.clone()].copy_from_slice(&coefficients[s_range.clone()]);
+
+    s_range = s_range.slide(NUM_BUS_CONSTRAINTS * 2);
+    t_range = t_range.slide(NUM_BUS_CONSTRAINTS * 2);
+    result[t_range.clone()].copy_from_slice(&coefficients[s_range.clone()]);
+
+    s_range = s_range.slide(NUM_OUTPUT_CONSTRAINTS * 2);
+    t_range = t_range.slide(NUM_OUTPUT_CONSTRAINTS * 2);
+    result[t_range.clone()].copy_from_slice(&coefficients[s_range.clone()]);
+
     return result;
 }
 
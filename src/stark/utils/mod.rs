@@ -10,6 +10,16 @@ pub use coefficients::{ ConstraintCoefficients, CompositionCoefficients };
 mod proof_of_work;
 pub use proof_of_work::{ find_pow_nonce, verify_pow_nonce };
 
+// CONSTANTS
+// ================================================================================================
+
+/// domains are processed in a single thread until every thread would get at least this many
+/// rows of the domain
+const MIN_ROWS_PER_THREAD: usize = 1024;
+
+// PUBLIC FUNCTIONS
+// ================================================================================================
+
 pub fn get_composition_degree(trace_length: usize) -> usize {
     return (MAX_CONSTRAINT_DEGREE - 1) * trace_length - 1;
 }
@@ -22,6 +32,8 @@ pub fn get_incremental_trace_degree(trace_length: usize) -> usize {
     return composition_degree - (trace_length - 2);
 }
 
+/// Draws `num_queries` distinct positions in the LDE domain from the `seed`; positions which
+/// fall onto the trace domain are skipped, and the positions are returned in ascending order.
 pub fn compute_query_positions(seed: &[u8; 32], domain_size: usize, options: &ProofOptions) -> Vec<usize> {
     let range = Uniform::from(0..domain_size);
     let mut index_iter = StdRng::from_seed(*seed).sample_iter(range);
@@ -40,14 +52,31 @@ pub fn compute_query_positions(seed: &[u8; 32], domain_size: usize, options: &Pr
         panic!("needed to generate {} query positions, but generated only {}", num_queries, result.len());
     }
 
+    // positions are put into canonical (ascending) order so that values opened at these
+    // positions are laid out in the proof in the same order regardless of how they were drawn
+    result.sort_unstable();
     return result;
 }
 
+/// Maps positions in the trace LDE domain to positions in the constraint Merkle tree, where
+/// every leaf holds 2 consecutive evaluations; the result is de-duplicated and sorted in
+/// ascending order.
 pub fn map_trace_to_constraint_positions(positions: &[usize]) -> Vec<usize> {
-    let mut result = Vec::with_capacity(positions.len());
-    for &position in positions.iter() {
-        let cp = position / 2;
-        if !result.contains(&cp) { result.push(cp); }
-    }
+    let mut result = positions.iter().map(|&position| position / 2).collect::<Vec<usize>>();
+    result.sort_unstable();
+    result.dedup();
     return result;
-}
\ No newline at end of file
+}
+
+/// Returns the number of threads for processing `num_rows` rows (e.g. of an FRI layer or of an
+/// extended trace): the largest power of 2 which does not exceed available parallelism and leaves
+/// at least MIN_ROWS_PER_THREAD rows to every thread; since `num_rows` is a power of 2, the rows
+/// can always be split evenly.
+pub fn get_num_threads(num_rows: usize) -> usize {
+    let available = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let mut num_threads = 1;
+    while num_threads * 2 <= available && num_rows / (num_threads * 2) >= MIN_ROWS_PER_THREAD {
+        num_threads *= 2;
+    }
+    return num_threads;
+}
@@ -1,34 +1,163 @@
 use crate::{
     math::field,
     crypto::{ MerkleTree },
-    MIN_TRACE_LENGTH
+    MIN_TRACE_LENGTH, AUX_WIDTH, PROGRAM_DIGEST_SIZE, MAX_PUBLIC_INPUTS, MAX_OUTPUTS
 };
-use super::{ StarkProof, TraceState, ConstraintEvaluator, CompositionCoefficients, fri, utils };
+use super::{
+    StarkProof, BatchProof, ProofShape, TraceInfo, TraceState, ConstraintEvaluator, CompositionCoefficients, constraints::ConstraintSetup,
+    Transcript, fri, utils
+};
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Values used by the verifier which depend only on the shape of the proof (trace length,
+/// extension factor, and stack depths): roots of unity of the trace and LDE domains, degrees
+/// used in the DEEP composition, and the setup for constraint evaluation. A setup built for one
+/// proof can be re-used to verify any other proof of the same shape.
+pub struct VerifierSetup {
+    lde_root            : u128,
+    trace_root          : u128,
+    incremental_degree  : u128,
+    composition_degree  : usize,
+    constraints         : ConstraintSetup,
+}
+
+/// Values which the execution trace committed to by a proof must have at its first and last
+/// steps, as derived by the verifier from the claimed program hash, public inputs, and outputs.
+/// Public inputs are the values of the user stack at the first step, and outputs are the values
+/// of the user stack at the last step; in both cases, the first value is the top of the stack,
+/// and stack registers beyond the provided values are not constrained.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoundaryValues {
+    pub initial_stack   : Vec<u128>,
+    pub final_stack     : Vec<u128>,
+    pub program_hash    : [u128; PROGRAM_DIGEST_SIZE],  // final state of the operation sponge
+    pub op_count        : u128,                         // final value of the op counter
+}
+
+// BOUNDARY VALUES IMPLEMENTATION
+// ================================================================================================
+impl BoundaryValues {
+
+    /// Returns the boundary values which the trace committed to by the `proof` must have for the
+    /// proof to be valid against the specified `program_hash`, `inputs`, and `outputs`. Returns
+    /// an error if there are more inputs or outputs than can be checked against the proof.
+    pub fn new(program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128], proof: &StarkProof) -> Result<BoundaryValues, String> {
+        check_public_values(inputs, outputs)?;
+        return Ok(BoundaryValues {
+            initial_stack   : inputs.to_vec(),
+            final_stack     : outputs.to_vec(),
+            program_hash    : [
+                field::from_bytes(&program_hash[..16]),
+                field::from_bytes(&program_hash[16..]),
+            ],
+            op_count        : proof.op_count(),
+        });
+    }
+
+    /// Compares these values, as expected by the verifier, against the `actual` values of an
+    /// execution (e.g. built via `BoundaryValues::new()` from the outputs returned by `execute()`),
+    /// and returns an error which names the first value that differs together with the expected
+    /// and the actual value. Only the values expected by the verifier are compared, in the same
+    /// way as the verifier checks them: the first value is the top of the stack, and registers
+    /// beyond the expected values are ignored.
+    pub fn compare(&self, actual: &BoundaryValues) -> Result<(), String> {
+        if self.program_hash != actual.program_hash {
+            return Err(format!("program hash differs: expected {:?}, but actual is {:?}",
+                self.program_hash, actual.program_hash));
+        }
+        compare_stack("public input", &self.initial_stack, &actual.initial_stack)?;
+        compare_stack("output", &self.final_stack, &actual.final_stack)?;
+        if self.op_count != actual.op_count {
+            return Err(format!("op count differs: expected {}, but actual is {}", self.op_count, actual.op_count));
+        }
+        return Ok(());
+    }
+}
+
+// VERIFIER SETUP IMPLEMENTATION
+// ================================================================================================
+impl VerifierSetup {
+
+    /// Returns a setup for verifying proofs of the same shape as the specified `proof`.
+    pub fn new(proof: &StarkProof) -> VerifierSetup {
+        return VerifierSetup::from_trace_info(proof.trace_info(), proof.options().extension_factor());
+    }
+
+    /// Returns a setup for verifying an execution trace described by `info` extended by the
+    /// specified `extension_factor`.
+    pub fn from_trace_info(info: &TraceInfo, extension_factor: usize) -> VerifierSetup {
+        let trace_length = info.trace_length(extension_factor);
+        return VerifierSetup {
+            lde_root            : field::get_root_of_unity(info.domain_size()),
+            trace_root          : field::get_root_of_unity(trace_length),
+            incremental_degree  : utils::get_incremental_trace_degree(trace_length) as u128,
+            composition_degree  : utils::get_composition_degree(trace_length),
+            constraints         : ConstraintSetup::from_trace_info(info, extension_factor),
+        };
+    }
+
+    /// Returns a setup for verifying proofs of the specified `shape` generated with the specified
+    /// `extension_factor`.
+    pub fn from_shape(shape: &ProofShape, extension_factor: usize) -> VerifierSetup {
+        assert!(shape.trace_length.is_power_of_two(), "trace length must be a power of 2");
+        let info = TraceInfo {
+            domain_depth    : (shape.trace_length * extension_factor).trailing_zeros() as u8,
+            ctx_depth       : shape.ctx_depth as u8,
+            loop_depth      : shape.loop_depth as u8,
+            call_depth      : shape.call_depth as u8,
+            stack_depth     : shape.stack_depth as u8,
+            op_count        : 0,
+        };
+        return VerifierSetup::from_trace_info(&info, extension_factor);
+    }
+
+    /// Returns true if this setup can be used to verify the specified `proof`.
+    pub fn matches(&self, proof: &StarkProof) -> bool {
+        return self.constraints.matches_trace_info(proof.trace_info(), proof.options().extension_factor());
+    }
+}
 
 // VERIFIER FUNCTION
 // ================================================================================================
 
-pub fn verify(program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128], proof: &StarkProof) -> Result<bool, String>
+/// Verifies the specified `proof`; all verifier challenges are derived from prover commitments
+/// using the provided `transcript`, which must be in the same state as the transcript used by
+/// the prover. The `setup` must have been built for a proof of the same shape.
+pub fn verify(program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128], proof: &StarkProof, transcript: &mut dyn Transcript, setup: &VerifierSetup) -> Result<bool, String>
 {
     let options = proof.options();
     let hash_fn = options.hash_fn();
+    let cap_height = options.cap_height();
+    proof.format().check(hash_fn)?;
 
-    // 1 ----- Verify proof of work and determine query positions ---------------------------------
-    let degree_proof = proof.degree_proof();
-    let mut fri_roots: Vec<u8> = Vec::new();
-    for layer in degree_proof.layers.iter() {
-        layer.root.iter().for_each(|&v| fri_roots.push(v));
+    if !setup.matches(proof) {
+        return Err(String::from("verifier setup does not match the shape of the proof"));
     }
-    degree_proof.rem_root.iter().for_each(|&v| fri_roots.push(v));
+    check_public_values(inputs, outputs)?;
+
+    // 1 ----- Replay the transcript to derive verifier challenges --------------------------------
+    transcript.absorb(b"trace_root", proof.trace_root());
+    let aux_seed = transcript.squeeze(b"aux_seed");
+
+    transcript.absorb(b"aux_root", proof.aux_root());
+    let coefficients_seed = transcript.squeeze(b"constraint_coefficients");
 
-    let mut seed = [0u8; 32];
-    hash_fn(&fri_roots, &mut seed);
-    let seed = match utils::verify_pow_nonce(seed, proof.pow_nonce(), &options) {
-        Ok(seed) => seed,
-        Err(msg) => return Err(msg)
-    };
+    transcript.absorb(b"constraint_root", proof.constraint_root());
+    let deep_seed = transcript.squeeze(b"deep_seed");
 
-    let t_positions = utils::compute_query_positions(&seed, proof.domain_size(), options);
+    let degree_proof = proof.degree_proof();
+    let special_xs = fri::draw_special_xs(&degree_proof, transcript);
+
+    // verify proof of work and determine query positions
+    let seed = transcript.squeeze(b"pow_seed");
+    if let Err(msg) = utils::verify_pow_nonce(seed, proof.pow_nonce(), &options) {
+        return Err(msg);
+    }
+    transcript.absorb(b"pow_nonce", &proof.pow_nonce().to_le_bytes());
+
+    let t_positions = transcript.draw_positions(b"query_positions", proof.domain_size(), options);
     let c_positions = utils::map_trace_to_constraint_positions(&t_positions);
 
     // 2 ----- Verify number of operations in the program -----------------------------------------
@@ -37,38 +166,209 @@ pub fn verify(program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128], proof:
     }
 
     // 3 ----- Verify trace and constraint Merkle proofs ------------------------------------------
-    if !MerkleTree::verify_batch(proof.trace_root(), &t_positions, &proof.trace_proof(), hash_fn) {
+    // query positions are re-derived in canonical order, and the proof must contain exactly one
+    // row of trace evaluations for every position, listed in the same order
+    if proof.trace_evaluations().len() != t_positions.len() || proof.aux_evaluations().len() != t_positions.len() {
+        return Err(String::from("proof contains an unexpected number of trace evaluations"));
+    }
+
+    if !MerkleTree::verify_batch_with_cap(proof.trace_root(), &t_positions, &proof.trace_proof(), cap_height, hash_fn) {
         return Err(String::from("verification of trace Merkle proof failed"));
     }
 
-    if !MerkleTree::verify_batch(proof.constraint_root(), &c_positions, &proof.constraint_proof(), hash_fn) {
+    if !MerkleTree::verify_batch_with_cap(proof.aux_root(), &t_positions, &proof.aux_proof(), cap_height, hash_fn) {
+        return Err(String::from("verification of auxiliary trace Merkle proof failed"));
+    }
+
+    if !MerkleTree::verify_batch_with_cap(proof.constraint_root(), &c_positions, &proof.constraint_proof(), cap_height, hash_fn) {
         return Err(String::from("verification of constraint Merkle proof failed"));
     }
 
     // 4 ----- Compute constraint evaluations at DEEP point z -------------------------------------
-    // derive DEEP point z from the root of the constraint tree
-    let z = field::prng(*proof.constraint_root());
+    // derive DEEP point z from the seed drawn after committing to the constraint tree
+    let z = field::prng(deep_seed);
 
     // evaluate constraints at z
     let constraint_evaluation_at_z = evaluate_constraints(
-        ConstraintEvaluator::from_proof(proof, program_hash, inputs, outputs),
+        ConstraintEvaluator::from_proof(&setup.constraints, proof, aux_seed, coefficients_seed, program_hash, inputs, outputs),
         proof.get_state_at_z1(),
         proof.get_state_at_z2(),
         z
     );
 
+    // the constraint polynomial committed to by the prover must evaluate at z to the value of
+    // constraints applied to trace states at z and z * g
+    if proof.constraint_at_z() != constraint_evaluation_at_z {
+        return Err(String::from("constraint evaluation at z is inconsistent with trace evaluations at z and z * g"));
+    }
+
     // 5 ----- Compute composition polynomial evaluations -----------------------------------------
-    // derive coefficient for linear combination from the root of constraint tree
-    let coefficients = CompositionCoefficients::new(*proof.constraint_root());
+    // derive coefficient for linear combination from the same seed as z
+    let coefficients = CompositionCoefficients::new(deep_seed);
 
     // compute composition values separately for trace and constraints, and then add them together
-    let t_composition = compose_registers(&proof, &t_positions, z, &coefficients);
-    let c_composition = compose_constraints(&proof, &t_positions, &c_positions, z, constraint_evaluation_at_z, &coefficients);
+    let registers = proof.trace_evaluations().iter().map(|row| &row[..]).collect::<Vec<&[u128]>>();
+    let aux_registers = proof.aux_evaluations().iter().map(|row| &row[..]).collect::<Vec<&[u128]>>();
+    let t_composition = compose_registers(&registers, &aux_registers, &proof.get_state_at_z1().to_vec(),
+        &proof.get_state_at_z2().to_vec(), setup, options.domain_offset(), &t_positions, z, &coefficients);
+    let c_composition = compose_constraints(&proof.constraint_proof().values, setup, options.domain_offset(), &t_positions, &c_positions, z, constraint_evaluation_at_z, &coefficients);
     let evaluations = t_composition.iter().zip(c_composition).map(|(&t, c)| field::add(t, c)).collect::<Vec<u128>>();
     
     // 6 ----- Verify low-degree proof -------------------------------------------------------------
-    let max_degree = utils::get_composition_degree(proof.trace_length());
-    return match fri::verify(&degree_proof, &evaluations, &t_positions, max_degree, options) {
+    return match fri::verify(&degree_proof, &evaluations, &t_positions, &special_xs, proof.domain_size(), setup.composition_degree, options) {
+        Ok(result) => Ok(result),
+        Err(msg) => Err(format!("verification of low-degree proof failed: {}", msg))
+    }
+}
+
+/// Verifies the specified batch `proof` of executions of programs with the specified hashes;
+/// `inputs` and `outputs` must contain public inputs and outputs of every execution in the same
+/// order in which the executions were proven. The `transcript` must be in the same state as the
+/// transcript used by the prover.
+pub fn verify_batch(program_hashes: &[[u8; 32]], inputs: &[Vec<u128>], outputs: &[Vec<u128>], proof: &BatchProof, transcript: &mut dyn Transcript) -> Result<bool, String>
+{
+    let options = proof.options();
+    let hash_fn = options.hash_fn();
+    let cap_height = options.cap_height();
+    proof.format().check(hash_fn)?;
+    let batch_size = proof.batch_size();
+
+    // 1 ----- Verify the shape of the proof ------------------------------------------------------
+    if batch_size == 0 {
+        return Err(String::from("batch proof must contain at least one execution"));
+    }
+    if program_hashes.len() != batch_size || inputs.len() != batch_size || outputs.len() != batch_size {
+        return Err(format!("batch proof contains {} executions, but {} program hashes, {} sets of inputs, and {} sets of outputs were provided",
+            batch_size, program_hashes.len(), inputs.len(), outputs.len()));
+    }
+    if proof.deep_values().len() != batch_size || proof.output_tapes().len() != batch_size {
+        return Err(String::from("batch proof must contain DEEP values and output tape of every execution"));
+    }
+
+    let trace_info = proof.trace_info();
+    let mut register_count = 0;
+    for (info, deep_values) in trace_info.iter().zip(proof.deep_values()) {
+        if info.domain_depth != trace_info[0].domain_depth {
+            return Err(String::from("all executions in a batch proof must have traces of the same length"));
+        }
+        if info.op_count < MIN_TRACE_LENGTH as u32 {
+            return Err(String::from("Verification of minimum operation count failed"));
+        }
+        let width = info.register_count() + AUX_WIDTH;
+        if deep_values.trace_at_z1.len() != width || deep_values.trace_at_z2.len() != width {
+            return Err(String::from("batch proof contains DEEP values of invalid width"));
+        }
+        register_count += info.register_count();
+    }
+    for i in 0..batch_size {
+        check_public_values(&inputs[i], &outputs[i])
+            .map_err(|msg| format!("execution {} of the batch: {}", i, msg))?;
+    }
+    if proof.trace_evaluations().iter().any(|row| row.len() != register_count)
+        || proof.aux_evaluations().iter().any(|row| row.len() != batch_size * AUX_WIDTH)
+    {
+        return Err(String::from("batch proof contains trace evaluations of invalid width"));
+    }
+
+    let setups = trace_info.iter()
+        .map(|info| VerifierSetup::from_trace_info(info, options.extension_factor()))
+        .collect::<Vec<VerifierSetup>>();
+
+    // 2 ----- Replay the transcript to derive verifier challenges --------------------------------
+    transcript.absorb(b"trace_root", proof.trace_root());
+    let aux_seed = transcript.squeeze(b"aux_seed");
+
+    transcript.absorb(b"aux_root", proof.aux_root());
+    let coefficients_seeds = (0..batch_size)
+        .map(|_| transcript.squeeze(b"constraint_coefficients"))
+        .collect::<Vec<[u8; 32]>>();
+
+    transcript.absorb(b"constraint_root", proof.constraint_root());
+    let deep_seeds = (0..batch_size)
+        .map(|_| transcript.squeeze(b"deep_seed"))
+        .collect::<Vec<[u8; 32]>>();
+
+    let degree_proof = proof.degree_proof();
+    let special_xs = fri::draw_special_xs(&degree_proof, transcript);
+
+    // verify proof of work and determine query positions
+    let seed = transcript.squeeze(b"pow_seed");
+    if let Err(msg) = utils::verify_pow_nonce(seed, proof.pow_nonce(), &options) {
+        return Err(msg);
+    }
+    transcript.absorb(b"pow_nonce", &proof.pow_nonce().to_le_bytes());
+
+    let t_positions = transcript.draw_positions(b"query_positions", trace_info[0].domain_size(), options);
+    let c_positions = utils::map_trace_to_constraint_positions(&t_positions);
+
+    // 3 ----- Verify trace and constraint Merkle proofs ------------------------------------------
+    // query positions are re-derived in canonical order, and the proof must contain exactly one
+    // row of trace evaluations for every position, listed in the same order
+    if proof.trace_evaluations().len() != t_positions.len() || proof.aux_evaluations().len() != t_positions.len() {
+        return Err(String::from("proof contains an unexpected number of trace evaluations"));
+    }
+
+    if !MerkleTree::verify_batch_with_cap(proof.trace_root(), &t_positions, &proof.trace_proof(), cap_height, hash_fn) {
+        return Err(String::from("verification of trace Merkle proof failed"));
+    }
+
+    if !MerkleTree::verify_batch_with_cap(proof.aux_root(), &t_positions, &proof.aux_proof(), cap_height, hash_fn) {
+        return Err(String::from("verification of auxiliary trace Merkle proof failed"));
+    }
+
+    if !MerkleTree::verify_batch_with_cap(proof.constraint_root(), &c_positions, &proof.constraint_proof(), cap_height, hash_fn) {
+        return Err(String::from("verification of constraint Merkle proof failed"));
+    }
+
+    // 4 ----- Compute constraint and trace compositions for every execution ----------------------
+    // DEEP point z is shared by all executions and is derived from the first seed
+    let z = field::prng(deep_seeds[0]);
+
+    let mut constraint_evaluation_at_z = field::ZERO;
+    let mut t_composition = vec![field::ZERO; t_positions.len()];
+    let mut offset = 0;
+    for (i, (info, setup)) in trace_info.iter().zip(setups.iter()).enumerate() {
+        let deep_values = &proof.deep_values()[i];
+
+        // constraint evaluations of all executions are added together in the same way as the
+        // prover adds together constraint polynomials
+        let evaluator = ConstraintEvaluator::from_trace_info(&setup.constraints, info, options.extension_factor(),
+            &proof.output_tapes()[i], aux_seed, coefficients_seeds[i], &program_hashes[i], &inputs[i], &outputs[i]);
+        let evaluation_at_z = evaluate_constraints(evaluator,
+            info.build_state(&deep_values.trace_at_z1),
+            info.build_state(&deep_values.trace_at_z2),
+            z);
+        constraint_evaluation_at_z = field::add(constraint_evaluation_at_z, evaluation_at_z);
+
+        // registers of this execution are located at the same offset in every queried row
+        let width = info.register_count();
+        let registers = proof.trace_evaluations().iter()
+            .map(|row| &row[offset..(offset + width)])
+            .collect::<Vec<&[u128]>>();
+        let aux_registers = proof.aux_evaluations().iter()
+            .map(|row| &row[(i * AUX_WIDTH)..((i + 1) * AUX_WIDTH)])
+            .collect::<Vec<&[u128]>>();
+        offset += width;
+
+        let coefficients = CompositionCoefficients::new(deep_seeds[i]);
+        let composition = compose_registers(&registers, &aux_registers, &deep_values.trace_at_z1,
+            &deep_values.trace_at_z2, setup, options.domain_offset(), &t_positions, z, &coefficients);
+        for (result, value) in t_composition.iter_mut().zip(composition) {
+            *result = field::add(*result, value);
+        }
+    }
+
+    if proof.constraint_at_z() != constraint_evaluation_at_z {
+        return Err(String::from("constraint evaluation at z is inconsistent with trace evaluations at z and z * g"));
+    }
+
+    // constraint polynomial is merged into the composition using coefficients of the first execution
+    let coefficients = CompositionCoefficients::new(deep_seeds[0]);
+    let c_composition = compose_constraints(&proof.constraint_proof().values, &setups[0], options.domain_offset(), &t_positions, &c_positions, z, constraint_evaluation_at_z, &coefficients);
+    let evaluations = t_composition.iter().zip(c_composition).map(|(&t, c)| field::add(t, c)).collect::<Vec<u128>>();
+
+    // 5 ----- Verify low-degree proof -------------------------------------------------------------
+    return match fri::verify(&degree_proof, &evaluations, &t_positions, &special_xs, trace_info[0].domain_size(), setups[0].composition_degree, options) {
         Ok(result) => Ok(result),
         Err(msg) => Err(format!("verification of low-degree proof failed: {}", msg))
     }
@@ -76,6 +376,35 @@ pub fn verify(program_hash: &[u8; 32], inputs: &[u128], outputs: &[u128], proof:
 
 // HELPER FUNCTIONS
 // ================================================================================================
+
+/// Makes sure that the number of public `inputs` and `outputs` does not exceed the number of
+/// stack registers which can be checked against the trace; stack of the trace always has at least
+/// as many registers as the maximum number of inputs and outputs.
+fn check_public_values(inputs: &[u128], outputs: &[u128]) -> Result<(), String> {
+    if inputs.len() > MAX_PUBLIC_INPUTS {
+        return Err(format!("expected no more than {} public inputs, but received {}", MAX_PUBLIC_INPUTS, inputs.len()));
+    }
+    if outputs.len() > MAX_OUTPUTS {
+        return Err(format!("expected no more than {} outputs, but received {}", MAX_OUTPUTS, outputs.len()));
+    }
+    return Ok(());
+}
+
+/// Compares `expected` values of the stack registers against the `actual` ones; values are
+/// named by their `kind` (e.g. "output") and their position from the top of the stack.
+fn compare_stack(kind: &str, expected: &[u128], actual: &[u128]) -> Result<(), String> {
+    for (i, &value) in expected.iter().enumerate() {
+        match actual.get(i) {
+            Some(&actual_value) if actual_value == value => (),
+            Some(&actual_value) => return Err(format!("{} {} differs: expected {}, but actual is {} (values are numbered from the top of the stack)",
+                kind, i, value, actual_value)),
+            None => return Err(format!("{} {} is expected to be {}, but only {} actual {}s are known",
+                kind, i, value, actual.len(), kind)),
+        }
+    }
+    return Ok(());
+}
+
 fn evaluate_constraints(evaluator: ConstraintEvaluator, state1: TraceState, state2: TraceState, x: u128) -> u128 {
     let (i_value, f_value) = evaluator.evaluate_boundaries(&state1, x);
     let t_value = evaluator.evaluate_transition_at(&state1, &state2, x);
@@ -95,24 +424,21 @@ fn evaluate_constraints(evaluator: ConstraintEvaluator, state1: TraceState, stat
     return result;
 }
 
-fn compose_registers(proof: &StarkProof, positions: &[usize], z: u128, cc: &CompositionCoefficients) -> Vec<u128>
+fn compose_registers(evaluations: &[&[u128]], aux_evaluations: &[&[u128]], trace_at_z1: &[u128], trace_at_z2: &[u128],
+    setup: &VerifierSetup, domain_offset: u128, positions: &[usize], z: u128, cc: &CompositionCoefficients) -> Vec<u128>
 {    
-    let lde_root = field::get_root_of_unity(proof.domain_size());
-    let trace_root = field::get_root_of_unity(proof.trace_length());
-    let next_z = field::mul(z, trace_root);
-
-    let trace_at_z1 = proof.get_state_at_z1().to_vec();
-    let trace_at_z2 = proof.get_state_at_z2().to_vec();
-    let evaluations = proof.trace_evaluations();
+    let lde_root = setup.lde_root;
+    let next_z = field::mul(z, setup.trace_root);
 
-    let incremental_degree = utils::get_incremental_trace_degree(proof.trace_length()) as u128;
+    let incremental_degree = setup.incremental_degree;
 
     let mut result = Vec::with_capacity(evaluations.len());
-    for (registers, &position) in evaluations.into_iter().zip(positions) {
-        let x = field::exp(lde_root, position as u128);
+    for ((registers, aux_registers), &position) in evaluations.iter().zip(aux_evaluations).zip(positions) {
+        let x = field::mul(domain_offset, field::exp(lde_root, position as u128));
         
+        // auxiliary registers follow the main registers in the deep composition
         let mut composition = field::ZERO;
-        for (i, &value) in registers.iter().enumerate() {
+        for (i, &value) in registers.iter().chain(aux_registers.iter()).enumerate() {
             // compute T1(x) = (T(x) - T(z)) / (x - z)
             let t1 = field::div(field::sub(value, trace_at_z1[i]), field::sub(x, z));
             // multiply it by a pseudo-random coefficient, and combine with result
@@ -135,10 +461,9 @@ fn compose_registers(proof: &StarkProof, positions: &[usize], z: u128, cc: &Comp
     return result;
 }
 
-fn compose_constraints(proof: &StarkProof, t_positions: &[usize], c_positions: &[usize], z: u128, evaluation_at_z: u128, cc: &CompositionCoefficients) -> Vec<u128> {
+fn compose_constraints(leaves: &[[u8; 32]], setup: &VerifierSetup, domain_offset: u128, t_positions: &[usize], c_positions: &[usize], z: u128, evaluation_at_z: u128, cc: &CompositionCoefficients) -> Vec<u128> {
     // build constraint evaluation values from the leaves of constraint Merkle proof
     let mut evaluations: Vec<u128> = Vec::with_capacity(t_positions.len());
-    let leaves = proof.constraint_proof().values;
     for &position in t_positions.iter() {
         let leaf_idx = c_positions.iter().position(|&v| v == position / 2).unwrap();
         let element_start = (position % 2) * 16;
@@ -146,12 +471,12 @@ fn compose_constraints(proof: &StarkProof, t_positions: &[usize], c_positions: &
         evaluations.push(field::from_bytes(element_bytes));
     }
 
-    let lde_root = field::get_root_of_unity(proof.domain_size());
+    let lde_root = setup.lde_root;
 
     // divide out deep point from the evaluations
     let mut result = Vec::with_capacity(evaluations.len());
     for (evaluation, &position) in evaluations.into_iter().zip(t_positions) {
-        let x = field::exp(lde_root, position as u128);
+        let x = field::mul(domain_offset, field::exp(lde_root, position as u128));
 
         // compute C(x) = (P(x) - P(z)) / (x - z)
         let composition = field::div(field::sub(evaluation, evaluation_at_z), field::sub(x, z));
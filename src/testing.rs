@@ -0,0 +1,669 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use rand::prelude::*;
+use rand_chacha::ChaCha20Rng;
+use serde::Serialize;
+use crate::{
+    Program, ProgramInputs, ProofOptions, StarkProof, Transcript, HashTranscript, OpCode, OpHint, assembly, processor,
+    blocks::{ ProgramBlock, Span, Group, SourceLocation },
+    math::{ field, fft },
+    crypto::hash::blake3,
+    stark::{ TraceTable, TraceState, ConstraintEvaluator, BufferPool, AuxRegisters, fri },
+    MAX_PUBLIC_INPUTS, MAX_OUTPUTS, MAX_STACK_DEPTH, HASHER_WIDTH, DEFAULT_MAX_CYCLES, MIN_EXTENSION_FACTOR,
+    LD_OP_BITS_RANGE, HD_OP_BITS_RANGE, BASE_CYCLE_LENGTH, PUSH_OP_ALIGNMENT,
+};
+
+// CONSTANTS
+// ================================================================================================
+const MIN_PROGRAM_LENGTH: usize = 8;
+const MAX_PROGRAM_LENGTH: usize = 48;
+const MAX_BLOCK_LENGTH  : usize = 8;
+
+/// stack depth of generated programs is kept well below MAX_STACK_DEPTH
+const MAX_GENERATED_DEPTH: usize = 16;
+
+/// control structures are nested at most this many levels deep
+const MAX_NESTING: usize = 2;
+
+/// while loops are executed at most this many times
+const MAX_LOOP_ITERATIONS: u32 = 2;
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// A deterministic program/input/proof fixture. Fixtures are fully determined by the seed and
+/// proof options from which they were generated: the seed drives generation of the program and
+/// of its inputs, and the prover does not use any randomness other than Fiat-Shamir challenges,
+/// which are derived from the default transcript. Thus, a fixture can be used to check that a
+/// verifier implemented elsewhere agrees with this implementation bit-for-bit.
+pub struct TestVector {
+    pub seed            : u64,
+    pub source          : String,
+    pub program         : Program,
+    pub inputs          : ProgramInputs,
+    pub outputs         : Vec<u128>,
+    pub proof           : StarkProof,
+}
+
+/// Execution trace of a program together with its auxiliary registers (the bus and the output
+/// tape), built in the same way as by the prover. Values of the trace can be modified to check
+/// that the modifications are caught by transition constraints; auxiliary registers are not
+/// rebuilt when main registers are modified.
+pub struct ExecutionTrace {
+    registers           : Vec<Vec<u128>>,
+    aux_registers       : Vec<Vec<u128>>,
+    ctx_depth           : usize,
+    loop_depth          : usize,
+    call_depth          : usize,
+    stack_depth         : usize,
+    evaluator           : ConstraintEvaluator,
+    sources             : Vec<Option<SourceLocation>>,  // source of the operation at every step
+}
+
+/// A test of one or more operations executed against a specified stack. The stack is set up by
+/// reading its values from secret tape A, after which the operations are executed together with
+/// NOOPs needed to keep them aligned. The resulting trace is built and checked in the same way as
+/// by the prover: every transition caused by the tested operations must satisfy all transition
+/// constraints. For example:
+/// ```
+/// # use distaff::{ OpCode, testing::VmTest };
+/// VmTest::new().stack(&[1, 2]).op(OpCode::Add).expect_stack(&[3]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct VmTest {
+    stack               : Vec<u128>,
+    tape_a              : Vec<u128>,
+    tape_b              : Vec<u128>,
+    ops                 : Vec<(OpCode, OpHint)>,
+}
+
+#[derive(Serialize)]
+struct JsonTestVector<'a> {
+    seed                : u64,
+    source              : &'a str,
+    program_hash        : String,
+    public_inputs       : Vec<String>,
+    secret_inputs       : [Vec<String>; 2],
+    outputs             : Vec<String>,
+    proof               : serde_json::Value,
+}
+
+/// A random well-formed program together with inputs for it. Every operation of the program has
+/// enough operands on the stack, both branches of every conditional and bodies of all loops
+/// leave the stack at the same depth, and secret input tapes contain exactly the values which
+/// are read by the program when it is executed. Conditions of all branches and loops are read
+/// from secret tape A, so different inputs exercise different execution paths.
+///
+/// A program can be generated from an arbitrary sequence of bytes; this makes the generator
+/// suitable for fuzzing. For example, a cargo-fuzz target can be defined as:
+/// ```ignore
+/// fuzz_target!(|data: &[u8]| {
+///     distaff::testing::round_trip(data, &distaff::ProofOptions::default());
+/// });
+/// ```
+#[derive(Clone, Debug)]
+pub struct GeneratedProgram {
+    pub source          : String,
+    pub inputs          : ProgramInputs,
+}
+
+/// Source of decisions made by the program generator.
+trait Choices {
+    fn next(&mut self) -> u32;
+
+    fn choose(&mut self, n: usize) -> usize {
+        return self.next() as usize % n;
+    }
+}
+
+/// Takes decisions from a sequence of bytes; once the bytes are exhausted, all decisions are 0,
+/// which guarantees that generation terminates for any input.
+struct ByteChoices<'a> {
+    data    : &'a [u8],
+    pos     : usize,
+}
+
+#[derive(Clone, Debug)]
+enum Instruction {
+    Op(String),
+    ReadA,
+    ReadAB,
+    If(Vec<Instruction>, Vec<Instruction>),
+    Repeat(usize, Vec<Instruction>),
+    While(Vec<Instruction>),
+}
+
+// TEST VECTOR GENERATION
+// ================================================================================================
+
+/// Generates a test vector from the specified `seed`; the program returns MAX_OUTPUTS values
+/// from the top of the stack.
+pub fn generate_test_vector(seed: u64, options: &ProofOptions) -> TestVector {
+    let generated = GeneratedProgram::from_seed(seed);
+
+    let program = generated.compile();
+    let (outputs, proof) = crate::execute(&program, &generated.inputs, MAX_OUTPUTS, options);
+
+    return TestVector { seed, source: generated.source, program, inputs: generated.inputs, outputs, proof };
+}
+
+/// Generates a program from `data`, executes it, and verifies the resulting proof; panics if
+/// any of these steps fails. This function is intended to be called from fuzz targets.
+pub fn round_trip(data: &[u8], options: &ProofOptions) {
+    let generated = GeneratedProgram::from_bytes(data);
+    let program = generated.compile();
+    let (outputs, proof) = crate::execute(&program, &generated.inputs, MAX_OUTPUTS, options);
+
+    let public_inputs = generated.inputs.get_public_inputs();
+    match crate::verify(program.hash(), public_inputs, &outputs, &proof) {
+        Ok(true) => (),
+        Ok(false) => panic!("verification of program {} failed", generated.source),
+        Err(msg) => panic!("verification of program {} failed: {}", generated.source, msg)
+    }
+}
+
+/// Executes the `program` using both the trace builder and the interpreter, and compares states
+/// of the stack at every step of execution; returns an error describing the first step at which
+/// the states diverge. Stack registers of the trace are padded with zeros beyond the current
+/// depth of the stack, and thus, interpreter states are padded in the same way before being
+/// compared. Programs which use host calls are not supported.
+pub fn check_interpreter(program: &Program, inputs: &ProgramInputs) -> Result<(), String> {
+    let (trace, ctx_depth, loop_depth, call_depth) = processor::execute(program, inputs, DEFAULT_MAX_CYCLES)
+        .map_err(|err| format!("trace builder failed: {}", err))?;
+    let states = processor::interpret_steps(program, inputs, DEFAULT_MAX_CYCLES)
+        .map_err(|err| format!("interpreter failed: {}", err))?;
+
+    let decoder_width = TraceState::compute_decoder_width(ctx_depth, loop_depth, call_depth);
+    let stack_registers = &trace[decoder_width..(trace.len() - HASHER_WIDTH)];
+    let trace_length = trace[0].len();
+    if states.len() > trace_length {
+        return Err(format!("interpreter executed {} steps, but trace length is only {}", states.len(), trace_length));
+    }
+
+    // once the program is executed, the last state of the stack is copied to all remaining steps
+    for step in 0..trace_length {
+        let state = &states[std::cmp::min(step, states.len() - 1)];
+        let width = std::cmp::max(state.len(), stack_registers.len());
+
+        let mut expected: Vec<u128> = stack_registers.iter().map(|register| register[step]).collect();
+        expected.resize(width, 0);
+        let mut actual = state.clone();
+        actual.resize(width, 0);
+
+        if expected != actual {
+            return Err(format!("stack states diverge at step {}: trace builder {:?}, interpreter {:?}",
+                step, expected, actual));
+        }
+    }
+
+    return Ok(());
+}
+
+// CONSTRAINT CHECKING
+// ================================================================================================
+
+/// Evaluates all transition constraints between the specified `step` and the next step of the
+/// `trace`, and returns individual evaluations of the constraints; for an honestly generated
+/// trace, all evaluations are zeros.
+pub fn evaluate_transition_at(trace: &ExecutionTrace, step: usize) -> Vec<u128> {
+    assert!(step < trace.length() - 1, "transition at step {} is outside of the trace", step);
+    let mut current = TraceState::new(trace.ctx_depth, trace.loop_depth, trace.call_depth, trace.stack_depth);
+    let mut next = TraceState::new(trace.ctx_depth, trace.loop_depth, trace.call_depth, trace.stack_depth);
+    current.update_from_trace(&trace.registers, step);
+    current.update_aux_from_trace(&trace.aux_registers, step);
+    next.update_from_trace(&trace.registers, step + 1);
+    next.update_aux_from_trace(&trace.aux_registers, step + 1);
+
+    // constraints are evaluated over a domain which is larger than the trace
+    let stride = trace.evaluator.domain_size() / trace.length();
+    return trace.evaluator.evaluate_transition_constraints(&current, &next, step * stride);
+}
+
+/// Evaluates transition constraints at every step of the `trace`; returns an error describing
+/// the first constraint which does not evaluate to zero, together with the location of the
+/// assembly instruction executed at that step, if it is known.
+pub fn check_transitions(trace: &ExecutionTrace) -> Result<(), String> {
+    return match find_failing_transition(trace) {
+        Some((step, i)) => {
+            let mut state = TraceState::new(trace.ctx_depth, trace.loop_depth, trace.call_depth, trace.stack_depth);
+            state.update_from_trace(&trace.registers, step);
+            let message = match state.user_op() {
+                Ok(op_code) => format!("transition constraint {} is not satisfied at step {} ({})", i, step, op_code),
+                Err(err) => format!("transition constraint {} is not satisfied at step {}: {}", i, step, err),
+            };
+            match trace.source(step) {
+                Some(location) => Err(format!("{} at {}", message, location)),
+                None => Err(message),
+            }
+        },
+        None => Ok(()),
+    };
+}
+
+/// Evaluates transition constraints at every step of the `trace`, and returns the step and the
+/// index of the first constraint which does not evaluate to zero, if any.
+pub fn find_failing_transition(trace: &ExecutionTrace) -> Option<(usize, usize)> {
+    for step in 0..(trace.length() - 1) {
+        let evaluations = evaluate_transition_at(trace, step);
+        if let Some(i) = evaluations.iter().position(|&value| value != field::ZERO) {
+            return Some((step, i));
+        }
+    }
+    return None;
+}
+
+/// Runs the commit phase of FRI over `evaluations` of a polynomial over the `domain`, in the same
+/// way as the prover does, and returns the root of the remainder layer; this makes it possible
+/// to benchmark the commit phase separately from the rest of the prover.
+pub fn commit_fri_layers(evaluations: &[u128], domain: &[u128], options: &ProofOptions) -> [u8; 32] {
+    assert!(evaluations.len() == domain.len(), "number of evaluations must be equal to the domain size");
+    let mut transcript = HashTranscript::new(options.hash_fn());
+    let (trees, _) = fri::reduce(evaluations, domain, &mut transcript, options);
+    return *trees[trees.len() - 1].root();
+}
+
+impl ExecutionTrace {
+
+    /// Executes the `program` and builds its execution trace; randomness for auxiliary registers
+    /// is derived from the hash of the program.
+    pub fn new(program: &Program, inputs: &ProgramInputs) -> ExecutionTrace {
+        let (registers, ctx_depth, loop_depth, call_depth) = processor::execute(program, inputs, DEFAULT_MAX_CYCLES)
+            .unwrap_or_else(|err| panic!("{}", err));
+
+        // auxiliary registers are built from the extended trace
+        let mut trace = TraceTable::new(registers.clone(), ctx_depth, loop_depth, call_depth, MIN_EXTENSION_FACTOR, field::ONE);
+        let lde_root = field::get_root_of_unity(trace.domain_size());
+        let mut twiddles = field::get_power_series(lde_root, trace.domain_size() / 2);
+        fft::permute(&mut twiddles);
+        let inv_twiddles = fft::get_inv_twiddles(field::get_root_of_unity(trace.unextended_length()), trace.unextended_length());
+        let mut pool = BufferPool::new(trace.unextended_length(), trace.register_count(), MIN_EXTENSION_FACTOR);
+        trace.extend(&twiddles, &inv_twiddles, &mut pool);
+
+        let mut transcript = HashTranscript::new(blake3);
+        transcript.absorb(b"program_hash", program.hash());
+        let aux_seed = transcript.squeeze(b"aux_seed");
+        let coefficients_seed = transcript.squeeze(b"constraint_coefficients");
+
+        let (aux_registers, tape_values) = AuxRegisters::build_trace_columns(aux_seed, &trace);
+
+        let last_state = trace.get_last_state();
+        let outputs = &last_state.user_stack()[..MAX_OUTPUTS];
+        let evaluator = ConstraintEvaluator::from_trace(&trace, aux_seed, coefficients_seed,
+            inputs.get_public_inputs(), outputs, &tape_values);
+
+        return ExecutionTrace {
+            registers,
+            aux_registers,
+            ctx_depth,
+            loop_depth,
+            call_depth,
+            stack_depth     : trace.stack_depth(),
+            evaluator,
+            sources         : processor::locate_steps(program, inputs, DEFAULT_MAX_CYCLES).unwrap_or_default(),
+        };
+    }
+
+    /// Returns the location of the assembly instruction executed at the specified `step`, if the
+    /// program was assembled from source and the step executes an operation compiled from an
+    /// instruction.
+    pub fn source(&self, step: usize) -> Option<SourceLocation> {
+        return self.sources.get(step).cloned().flatten();
+    }
+
+    /// Returns the number of steps in the trace.
+    pub fn length(&self) -> usize {
+        return self.registers[0].len();
+    }
+
+    /// Returns the number of registers in the trace; auxiliary registers follow main registers.
+    pub fn register_count(&self) -> usize {
+        return self.registers.len() + self.aux_registers.len();
+    }
+
+    /// Returns the range of registers which hold the user stack.
+    pub fn user_stack_registers(&self) -> Range<usize> {
+        let start = TraceState::compute_decoder_width(self.ctx_depth, self.loop_depth, self.call_depth);
+        return start..(start + self.stack_depth);
+    }
+
+    /// Returns the opcode of the user operation executed at the specified `step`.
+    pub fn op_code(&self, step: usize) -> u8 {
+        let mut op_code = 0;
+        for (i, register) in LD_OP_BITS_RANGE.chain(HD_OP_BITS_RANGE).enumerate() {
+            op_code |= (self.registers[register][step] as u8) << i;
+        }
+        return op_code;
+    }
+
+    /// Returns the value of the specified `register` at the specified `step`.
+    pub fn get(&self, register: usize, step: usize) -> u128 {
+        if register < self.registers.len() {
+            return self.registers[register][step];
+        }
+        return self.aux_registers[register - self.registers.len()][step];
+    }
+
+    /// Sets the value of the specified `register` at the specified `step`.
+    pub fn set(&mut self, register: usize, step: usize, value: u128) {
+        if register < self.registers.len() {
+            self.registers[register][step] = value;
+        }
+        else {
+            let num_registers = self.registers.len();
+            self.aux_registers[register - num_registers][step] = value;
+        }
+    }
+}
+
+impl TestVector {
+
+    /// Serializes the test vector into JSON; field elements are encoded in the same way as by
+    /// `StarkProof::to_json()`.
+    pub fn to_json(&self) -> String {
+        let [secret_a, secret_b] = self.inputs.get_secret_inputs();
+        let json_vector = JsonTestVector {
+            seed            : self.seed,
+            source          : &self.source,
+            program_hash    : hex::encode(self.program.hash()),
+            public_inputs   : encode_elements(self.inputs.get_public_inputs()),
+            secret_inputs   : [encode_elements(secret_a), encode_elements(secret_b)],
+            outputs         : encode_elements(&self.outputs),
+            proof           : serde_json::from_str(&self.proof.to_json()).unwrap(),
+        };
+        return serde_json::to_string(&json_vector).unwrap();
+    }
+}
+
+// OPERATION TESTS
+// ================================================================================================
+impl VmTest {
+
+    /// Returns a new test with an empty stack and no operations.
+    pub fn new() -> VmTest {
+        return VmTest::default();
+    }
+
+    /// Sets the stack against which the operations are executed; values are listed top first.
+    pub fn stack(mut self, values: &[u128]) -> VmTest {
+        assert!(values.len() <= MAX_STACK_DEPTH, "stack cannot contain more than {} values", MAX_STACK_DEPTH);
+        self.stack = values.to_vec();
+        return self;
+    }
+
+    /// Sets the values which the operations can read from secret tape A.
+    pub fn tape_a(mut self, values: &[u128]) -> VmTest {
+        self.tape_a = values.to_vec();
+        return self;
+    }
+
+    /// Sets the values which the operations can read from secret tape B.
+    pub fn tape_b(mut self, values: &[u128]) -> VmTest {
+        self.tape_b = values.to_vec();
+        return self;
+    }
+
+    /// Appends an operation without an execution hint to the operations under test.
+    pub fn op(self, op_code: OpCode) -> VmTest {
+        return self.op_with_hint(op_code, OpHint::None);
+    }
+
+    /// Appends an operation with the specified execution hint to the operations under test; for
+    /// PUSH operation, the hint must contain the pushed value.
+    pub fn op_with_hint(mut self, op_code: OpCode, op_hint: OpHint) -> VmTest {
+        self.ops.push((op_code, op_hint));
+        return self;
+    }
+
+    /// Executes the operations, checks transition constraints of every transition caused by
+    /// them, and returns the state of the stack after the last operation (top first); the
+    /// returned state is as wide as the deepest stack reached by the test, and slots beyond the
+    /// depth of the stack hold zeros.
+    pub fn run(&self) -> Result<Vec<u128>, String> {
+        assert!(self.ops.len() > 0, "at least one operation must be specified");
+
+        // the stack is set up by reading its values from tape A, deepest value first
+        let mut op_codes = vec![OpCode::Begin];
+        let mut tape_a: Vec<u128> = self.stack.iter().rev().cloned().collect();
+        tape_a.extend_from_slice(&self.tape_a);
+        op_codes.resize(self.stack.len() + 1, OpCode::Read);
+
+        // operations with immediate values must be aligned
+        let mut hints = HashMap::new();
+        let mut steps = Vec::with_capacity(self.ops.len());
+        for &(op_code, op_hint) in self.ops.iter() {
+            let mut step = op_codes.len();
+            if op_code.has_immediate() && step % PUSH_OP_ALIGNMENT != 0 {
+                step += PUSH_OP_ALIGNMENT - step % PUSH_OP_ALIGNMENT;
+            }
+            op_codes.resize(step, OpCode::Noop);
+            op_codes.push(op_code);
+            if op_hint != OpHint::None {
+                hints.insert(step, op_hint);
+            }
+            steps.push(step);
+        }
+        let length = op_codes.len() + (BASE_CYCLE_LENGTH - 1) - (op_codes.len() % BASE_CYCLE_LENGTH);
+        op_codes.resize(length, OpCode::Noop);
+
+        let program = Program::new(Group::new(vec![ProgramBlock::Span(Span::new(op_codes, hints))]));
+        let inputs = ProgramInputs::new(&[], &tape_a, &self.tape_b);
+        let trace = ExecutionTrace::new(&program, &inputs);
+
+        for (&step, &(op_code, _)) in steps.iter().zip(self.ops.iter()) {
+            let evaluations = evaluate_transition_at(&trace, step);
+            if let Some(i) = evaluations.iter().position(|&value| value != field::ZERO) {
+                return Err(format!("transition constraint {} is not satisfied by {} operation at step {}", i, op_code, step));
+            }
+        }
+
+        let step = steps[steps.len() - 1] + 1;
+        return Ok(trace.user_stack_registers().map(|register| trace.get(register, step)).collect());
+    }
+
+    /// Executes the operations in the same way as `run()` does, and panics if any transition
+    /// constraint is not satisfied, or if the top of the resulting stack is not equal to
+    /// `expected` values (top first); all slots below the expected values must be empty.
+    pub fn expect_stack(&self, expected: &[u128]) {
+        let mut actual = self.run().unwrap_or_else(|err| panic!("{}", err));
+        let width = std::cmp::max(actual.len(), expected.len());
+        actual.resize(width, field::ZERO);
+        let mut expected = expected.to_vec();
+        expected.resize(width, field::ZERO);
+        assert!(actual == expected, "expected stack {:?}, but was {:?}", expected, actual);
+    }
+}
+
+// PROGRAM GENERATION
+// ================================================================================================
+impl GeneratedProgram {
+
+    /// Generates a program using decisions taken from `data`; the same data always results in
+    /// the same program.
+    pub fn from_bytes(data: &[u8]) -> GeneratedProgram {
+        return generate_program(&mut ByteChoices { data, pos: 0 });
+    }
+
+    /// Generates a program using decisions drawn from a PRNG initialized with `seed`; the PRNG is
+    /// ChaCha20 (as implemented by `rand_chacha` crate), and so, the program generated from a
+    /// given seed does not change across versions of `rand` crate.
+    pub fn from_seed(seed: u64) -> GeneratedProgram {
+        return generate_program(&mut ChaCha20Rng::seed_from_u64(seed));
+    }
+
+    pub fn compile(&self) -> Program {
+        return assembly::compile(&self.source)
+            .unwrap_or_else(|err| panic!("generated program {} is invalid: {}", self.source, err));
+    }
+}
+
+fn generate_program(choices: &mut dyn Choices) -> GeneratedProgram {
+    let num_public_inputs = 1 + choices.choose(MAX_PUBLIC_INPUTS);
+    let public_inputs: Vec<u128> = (0..num_public_inputs).map(|_| choices.next() as u128).collect();
+
+    let length = MIN_PROGRAM_LENGTH + choices.choose(MAX_PROGRAM_LENGTH - MIN_PROGRAM_LENGTH + 1);
+    let (body, _) = generate_block(choices, num_public_inputs, length, 0);
+
+    // walk through the program in the order of execution to build secret input tapes
+    let mut tapes = [Vec::new(), Vec::new()];
+    build_tapes(&body, choices, &mut tapes);
+
+    let mut source = String::from("begin");
+    write_block(&body, &mut source);
+    source.push_str(" end");
+
+    return GeneratedProgram { source, inputs: ProgramInputs::new(&public_inputs, &tapes[0], &tapes[1]) };
+}
+
+/// Generates a sequence of `length` instructions starting at the specified stack `depth`;
+/// returns the instructions together with the stack depth at the end of the sequence.
+fn generate_block(choices: &mut dyn Choices, mut depth: usize, length: usize, nesting: usize) -> (Vec<Instruction>, usize) {
+    let mut result = Vec::with_capacity(length);
+    for _ in 0..length {
+        // build a list of instructions which can be executed at the current stack depth; the
+        // first option is always valid because stack depth never exceeds MAX_GENERATED_DEPTH
+        let can_push = depth < MAX_GENERATED_DEPTH - 1;
+        let can_nest = nesting < MAX_NESTING && can_push;
+        let mut options: Vec<(&str, isize)> = Vec::new();
+        if can_push                 { options.extend_from_slice(&[("push", 1), ("read.a", 1), ("read.ab", 2)]); }
+        if depth >= 1 && can_push   { options.push(("dup", 1)); }
+        if depth >= 1               { options.push(("neg", 0)); }
+        if depth >= 2               { options.extend_from_slice(&[
+            ("add", -1), ("sub", -1), ("mul", -1), ("swap", 0), ("drop", -1), ("eq", -1)
+        ]); }
+        if can_nest                 { options.extend_from_slice(&[("if", 0), ("repeat", 0), ("while", 0)]); }
+        if options.is_empty()       { options.push(("drop", -1)); }
+
+        let (name, delta) = options[choices.choose(options.len())];
+        let instruction = match name {
+            "push"      => Instruction::Op(format!("push.{}", choices.next() & 0xFFFF)),
+            "read.a"    => Instruction::ReadA,
+            "read.ab"   => Instruction::ReadAB,
+            "if"        => {
+                let t_branch = generate_balanced_block(choices, depth, nesting + 1);
+                let f_branch = generate_balanced_block(choices, depth, nesting + 1);
+                Instruction::If(t_branch, f_branch)
+            },
+            "repeat"    => {
+                let num_iterations = 2 + choices.choose(2);
+                Instruction::Repeat(num_iterations, generate_balanced_block(choices, depth, nesting + 1))
+            },
+            "while"     => Instruction::While(generate_balanced_block(choices, depth, nesting + 1)),
+            _           => Instruction::Op(name.to_string()),
+        };
+        depth = (depth as isize + delta) as usize;
+        result.push(instruction);
+    }
+
+    return (result, depth);
+}
+
+/// Generates a non-empty sequence of instructions which leaves the stack at the same `depth` at
+/// which it started; the depth is restored by dropping or pushing values at the end.
+fn generate_balanced_block(choices: &mut dyn Choices, depth: usize, nesting: usize) -> Vec<Instruction> {
+    let length = 1 + choices.choose(MAX_BLOCK_LENGTH);
+    let (mut result, mut end_depth) = generate_block(choices, depth, length, nesting);
+    while end_depth > depth {
+        result.push(Instruction::Op(String::from("drop")));
+        end_depth -= 1;
+    }
+    while end_depth < depth {
+        result.push(Instruction::Op(String::from("push.0")));
+        end_depth += 1;
+    }
+    return result;
+}
+
+/// Appends values read by the `instructions` to the secret input tapes in the order in which the
+/// values are read during execution; this also decides which branches are taken, and how many
+/// times every loop is executed.
+fn build_tapes(instructions: &[Instruction], choices: &mut dyn Choices, tapes: &mut [Vec<u128>; 2]) {
+    for instruction in instructions.iter() {
+        match instruction {
+            Instruction::Op(_) => (),
+            Instruction::ReadA => tapes[0].push(choices.next() as u128),
+            Instruction::ReadAB => {
+                tapes[0].push(choices.next() as u128);
+                tapes[1].push(choices.next() as u128);
+            },
+            Instruction::If(t_branch, f_branch) => {
+                if choices.choose(2) == 1 {
+                    tapes[0].push(1);
+                    build_tapes(t_branch, choices, tapes);
+                }
+                else {
+                    tapes[0].push(0);
+                    build_tapes(f_branch, choices, tapes);
+                }
+            },
+            Instruction::Repeat(num_iterations, body) => {
+                for _ in 0..*num_iterations {
+                    build_tapes(body, choices, tapes);
+                }
+            },
+            Instruction::While(body) => {
+                let num_iterations = choices.choose(MAX_LOOP_ITERATIONS as usize + 1);
+                for _ in 0..num_iterations {
+                    tapes[0].push(1);
+                    build_tapes(body, choices, tapes);
+                }
+                tapes[0].push(0);
+            },
+        }
+    }
+}
+
+/// Writes assembly source code for the `instructions`; conditions of branches and loops are
+/// read from secret tape A.
+fn write_block(instructions: &[Instruction], source: &mut String) {
+    for instruction in instructions.iter() {
+        match instruction {
+            Instruction::Op(op) => { source.push(' '); source.push_str(op); },
+            Instruction::ReadA => source.push_str(" read.a"),
+            Instruction::ReadAB => source.push_str(" read.ab"),
+            Instruction::If(t_branch, f_branch) => {
+                source.push_str(" read.a if.true");
+                write_block(t_branch, source);
+                source.push_str(" else");
+                write_block(f_branch, source);
+                source.push_str(" end");
+            },
+            Instruction::Repeat(num_iterations, body) => {
+                source.push_str(&format!(" repeat.{}", num_iterations));
+                write_block(body, source);
+                source.push_str(" end");
+            },
+            Instruction::While(body) => {
+                source.push_str(" read.a while.true");
+                write_block(body, source);
+                source.push_str(" read.a end");
+            },
+        }
+    }
+}
+
+// CHOICES IMPLEMENTATIONS
+// ================================================================================================
+impl Choices for ChaCha20Rng {
+    fn next(&mut self) -> u32 {
+        return self.gen();
+    }
+}
+
+impl <'a> Choices for ByteChoices<'a> {
+    fn next(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        for byte in bytes.iter_mut() {
+            if self.pos < self.data.len() {
+                *byte = self.data[self.pos];
+                self.pos += 1;
+            }
+        }
+        return u32::from_le_bytes(bytes);
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+fn encode_elements(values: &[u128]) -> Vec<String> {
+    return values.iter().map(|v| format!("{:032x}", v)).collect();
+}
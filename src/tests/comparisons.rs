@@ -1,6 +1,6 @@
 use crate::{ ProofOptions, math::field };
 use super::{
-    build_program, OpCode,
+    build_program, OpCode, assembly,
     super::{ execute, verify, ProgramInputs }
 };
 
@@ -27,6 +27,31 @@ fn eq_operations() {
     assert_eq!(Ok(true), result);
 }
 
+#[test]
+fn asserteqw_operation() {
+    let program = assembly::compile("begin pushw.1.2.3.4 assert.eqw end").unwrap();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[4, 3, 2, 1, 5]);
+    let num_outputs = 1;
+
+    let (outputs, proof) = execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(vec![5], outputs);
+
+    let result = verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+#[should_panic(expected = "ASSERTEQW failed")]
+fn asserteqw_operation_fail() {
+    let program = assembly::compile("begin pushw.1.2.3.4 assert.eqw end").unwrap();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[4, 3, 2, 5]);
+    execute(&program, &inputs, 1, &options);
+}
+
 #[test]
 fn cmp_operation() {
 
@@ -104,6 +129,42 @@ fn binacc_operation() {
     assert_eq!(Ok(true), result);
 }
 
+#[test]
+fn split_operation() {
+    let options = ProofOptions::default();
+    let num_outputs = 2;
+
+    // split a 65-bit value into the lower 64 bits and a carry bit
+    let program = assembly::compile("begin split.64.1 end").unwrap();
+    let inputs = ProgramInputs::from_public(&[(1 << 64) + 5]);
+
+    let (outputs, proof) = execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(vec![5, 1], outputs);
+
+    let result = verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+
+    // split a 64-bit value into two 32-bit halves
+    let program = assembly::compile("begin split.32.32 end").unwrap();
+    let inputs = ProgramInputs::from_public(&[(7 << 32) + 9]);
+
+    let (outputs, proof) = execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(vec![9, 7], outputs);
+
+    let result = verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+#[should_panic(expected = "ASSERTEQ failed")]
+fn split_operation_fail() {
+    let program = assembly::compile("begin split.32.4 end").unwrap();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[1 << 36]);
+    execute(&program, &inputs, 2, &options);
+}
+
 // HELPER FUNCTIONS
 // ================================================================================================
 fn build_inputs_for_cmp(a: u128, b: u128, size: usize) -> (Vec<u128>, Vec<u128>) {
@@ -1,8 +1,10 @@
 use std::collections::HashMap;
+use rand::prelude::*;
+use num_bigint::{ BigUint };
 use crate::{
-    ProofOptions, Program, ProgramInputs, OpCode, OpHint,
+    ProofOptions, StarkProof, BoundaryValues, FORMAT_VERSION, MinimumAcceptableOptions, testing, testing::VmTest, diagnostics, Transcript, HashTranscript, MerlinTranscript, Program, ProgramAbi, ExecutionDigest, OutputCommitment, ProgramInputs, OpCode, OpHint, CheckKind, SourceLocation, assembly,
     blocks::{ ProgramBlock, Span, Group },
-    math::field, utils::hasher
+    crypto::hash, math::field, utils::hasher
 };
 
 mod branches;
@@ -46,246 +48,2141 @@ fn execute_verify_fail() {
 
     // wrong inputs
     let result = super::verify(program.hash(), &[1, 1], &outputs, &proof);
-    let err_msg = format!("verification of low-degree proof failed: evaluations did not match column value at depth 0");
+    let err_msg = String::from("constraint evaluation at z is inconsistent with trace evaluations at z and z * g");
     assert_eq!(Err(err_msg), result);
 
     // wrong outputs
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &[5], &proof);
-    let err_msg = format!("verification of low-degree proof failed: evaluations did not match column value at depth 0");
+    let err_msg = String::from("constraint evaluation at z is inconsistent with trace evaluations at z and z * g");
     assert_eq!(Err(err_msg), result);
 
     // wrong program hash
     let mut program_hash2 = program.hash().clone();
     program_hash2[0] = 1;
     let result = super::verify(&program_hash2, inputs.get_public_inputs(), &outputs, &proof);
-    let err_msg = format!("verification of low-degree proof failed: evaluations did not match column value at depth 0");
+    let err_msg = String::from("constraint evaluation at z is inconsistent with trace evaluations at z and z * g");
     assert_eq!(Err(err_msg), result);
 }
 
+#[test]
+fn boundary_values() {
+    let program = assembly::compile("begin push.3 push.5 add end").unwrap();
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[1, 2]);
+    let (outputs, proof) = super::execute(&program, &inputs, 2, &options);
+    assert_eq!(vec![8, 1], outputs);
+
+    // outputs are checked against the stack at the last step, top first
+    let values = BoundaryValues::new(program.hash(), inputs.get_public_inputs(), &outputs, &proof).unwrap();
+    assert_eq!(vec![1, 2], values.initial_stack);
+    assert_eq!(vec![8, 1], values.final_stack);
+    assert_eq!(proof.op_count(), values.op_count);
+    assert_eq!(field::from_bytes(&program.hash()[..16]), values.program_hash[0]);
+
+    // mis-ordered or wrong outputs are reported against the actual values
+    assert_eq!(Ok(()), values.compare(&values));
+    let truncated = BoundaryValues::new(program.hash(), inputs.get_public_inputs(), &outputs[..1], &proof).unwrap();
+    assert_eq!(Ok(()), truncated.compare(&values));
+    let swapped = BoundaryValues::new(program.hash(), inputs.get_public_inputs(), &[1, 8], &proof).unwrap();
+    let err_msg = String::from("output 0 differs: expected 1, but actual is 8 (values are numbered from the top of the stack)");
+    assert_eq!(Err(err_msg), swapped.compare(&values));
+    let err_msg = String::from("output 1 is expected to be 1, but only 1 actual outputs are known");
+    assert_eq!(Err(err_msg), values.compare(&truncated));
+    let other_inputs = BoundaryValues::new(program.hash(), &[1, 3], &outputs, &proof).unwrap();
+    let err_msg = String::from("public input 1 differs: expected 3, but actual is 2 (values are numbered from the top of the stack)");
+    assert_eq!(Err(err_msg), other_inputs.compare(&values));
+    let other_program = BoundaryValues::new(&[0; 32], inputs.get_public_inputs(), &outputs, &proof).unwrap();
+    assert!(other_program.compare(&values).unwrap_err().starts_with("program hash differs"));
+
+    // inputs and outputs which cannot be checked against the proof are rejected up front
+    let err_msg = String::from("expected no more than 8 outputs, but received 9");
+    assert_eq!(Err(err_msg.clone()), BoundaryValues::new(program.hash(), &[], &[0; 9], &proof));
+    assert_eq!(Err(err_msg), super::verify(program.hash(), &[], &[0; 9], &proof));
+}
+
+#[test]
+fn proof_stats() {
+    let program = assembly::compile("begin push.3 push.5 add end").unwrap();
+    let options = ProofOptions::default();
+    let (_, proof) = super::execute(&program, &ProgramInputs::none(), 1, &options);
+
+    // sizes of all components add up to the size of the serialized proof
+    let stats = proof.stats();
+    assert_eq!(proof.to_bytes().len(), stats.total_size);
+    assert_eq!(stats.total_size, stats.trace_openings + stats.constraint_openings + stats.deep_values
+        + stats.fri_layers + stats.fri_remainder + stats.pow_nonce + stats.output_tape + stats.other);
+    assert_eq!(8, stats.pow_nonce);
+    assert_eq!(proof.trace_evaluations().len(), stats.num_queries);
+    assert_eq!(proof.degree_proof().layers.len(), stats.num_fri_layers);
+    assert!(stats.to_string().starts_with(&format!("proof size: {} bytes", stats.total_size)));
+}
+
+#[test]
+fn reusable_prover() {
+    // number of loop iterations depends on the secret inputs, and thus, so does trace length
+    let program = assembly::compile("begin read.a while.true push.3 mul read.a end end").unwrap();
+    let options = ProofOptions::default();
+    let mut prover = super::Prover::new(&program, &options);
+
+    for &iterations in [1, 2, 40, 3].iter() {
+        let mut secret_a = vec![1; iterations];
+        secret_a.push(0);
+        let inputs = ProgramInputs::new(&[1], &secret_a, &[]);
+
+        // proofs generated by the prover should be the same as proofs generated by execute()
+        let (outputs, proof) = prover.prove(&inputs, 1);
+        let (expected_outputs, expected_proof) = super::execute(&program, &inputs, 1, &options);
+        assert_eq!(expected_outputs, outputs);
+        assert_eq!(expected_proof.to_json(), proof.to_json());
+
+        let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+        assert_eq!(Ok(true), result);
+    }
+}
+
+#[test]
+fn auto_tuned_prover() {
+    let program = assembly::compile("begin read.a while.true push.3 mul read.a end end").unwrap();
+    let inputs = ProgramInputs::new(&[1], &[1, 1, 1, 0], &[]);
+    let (trace, _, _, _) = crate::processor::execute(&program, &inputs, super::DEFAULT_MAX_CYCLES).unwrap();
+    let (trace_length, trace_width) = (trace[0].len(), trace.len());
+
+    let size_prover = super::Prover::auto_tune(&program, &inputs, 100, super::TuningTarget::Size).unwrap();
+    let speed_prover = super::Prover::auto_tune(&program, &inputs, 100, super::TuningTarget::Speed).unwrap();
+    let size_options = size_prover.options().clone();
+    let speed_options = speed_prover.options().clone();
+    assert!(size_options.conjectured_security(trace_length) >= 100);
+    assert!(speed_options.conjectured_security(trace_length) >= 100);
+    assert!(size_options.estimate(trace_length, trace_width).proof_size <= speed_options.estimate(trace_length, trace_width).proof_size);
+
+    // tuned options are recorded in the proof
+    let mut prover = speed_prover;
+    let (outputs, proof) = prover.prove(&inputs, 1);
+    assert_eq!(speed_options.extension_factor(), proof.options().extension_factor());
+    assert_eq!(speed_options.num_queries(), proof.options().num_queries());
+    assert_eq!(speed_options.grinding_factor(), proof.options().grinding_factor());
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+
+    // security beyond what the field can provide cannot be reached
+    assert!(super::Prover::auto_tune(&program, &inputs, 200, super::TuningTarget::Speed).is_err());
+}
+
+#[test]
+fn reusable_verifier() {
+    let program = assembly::compile("begin read.a while.true push.3 mul read.a end end").unwrap();
+    let options = ProofOptions::default();
+    let mut verifier = super::Verifier::new(program.hash(), &options);
+
+    for &iterations in [1, 40, 2].iter() {
+        let mut secret_a = vec![1; iterations];
+        secret_a.push(0);
+        let inputs = ProgramInputs::new(&[1], &secret_a, &[]);
+        let (outputs, proof) = super::execute(&program, &inputs, 1, &options);
+
+        let result = verifier.verify(inputs.get_public_inputs(), &outputs, &proof);
+        assert_eq!(Ok(true), result);
+
+        // wrong outputs must be rejected by a verifier with a cached setup as well
+        let result = verifier.verify(inputs.get_public_inputs(), &[field::add(outputs[0], 1)], &proof);
+        assert_eq!(true, result.is_err());
+    }
+
+    // proofs generated with different options are rejected
+    let options2 = ProofOptions::new(options.extension_factor(), 80, options.grinding_factor(), options.hash_fn());
+    let inputs = ProgramInputs::new(&[1], &[1, 0], &[]);
+    let (outputs, proof) = super::execute(&program, &inputs, 1, &options2);
+    let result = verifier.verify(inputs.get_public_inputs(), &outputs, &proof);
+    let err_msg = format!("proof uses 80 queries, but {} are expected", options.num_queries());
+    assert_eq!(Err(err_msg), result);
+}
+
+#[test]
+fn prepared_verifier() {
+    let program = assembly::compile("begin read.a while.true push.3 mul read.a end end").unwrap();
+    let options = ProofOptions::default();
+
+    let mut proofs = Vec::new();
+    for &iterations in [1, 40].iter() {
+        let mut secret_a = vec![1; iterations];
+        secret_a.push(0);
+        let inputs = ProgramInputs::new(&[1], &secret_a, &[]);
+        proofs.push(super::execute(&program, &inputs, 1, &options));
+    }
+    assert_ne!(proofs[0].1.shape(), proofs[1].1.shape());
+
+    // setup for the shape of the first proof is computed ahead of time
+    let mut verifier = super::Verifier::new(program.hash(), &options);
+    let shape = super::ProofShape { trace_length: 128, ctx_depth: 1, loop_depth: 1, call_depth: 0, stack_depth: 2 };
+    assert_eq!(shape, proofs[0].1.shape());
+    verifier.prepare(&shape);
+    assert!(verifier.is_prepared(&shape));
+    assert!(!verifier.is_prepared(&proofs[1].1.shape()));
+
+    // setups for both shapes are retained when proofs of different shapes are interleaved
+    for (outputs, proof) in proofs.iter().chain(proofs.iter()) {
+        let result = verifier.verify(&[1], outputs, proof);
+        assert_eq!(Ok(true), result);
+        assert!(verifier.is_prepared(&proof.shape()));
+    }
+}
+
+#[test]
+fn batch_execute_verify() {
+    // programs of different length and shape are proven together
+    let programs = vec![
+        assembly::compile("begin push.3 push.5 add end").unwrap(),
+        assembly::compile("begin read.a while.true push.3 mul read.a end end").unwrap(),
+        assembly::compile("begin push.7 dup mul push.2 swap end").unwrap(),
+    ];
+    let inputs = vec![
+        ProgramInputs::none(),
+        ProgramInputs::new(&[1], &[1; 40].iter().chain(&[0]).cloned().collect::<Vec<u128>>(), &[]),
+        ProgramInputs::none(),
+    ];
+    let options = ProofOptions::default();
+    let (outputs, proof) = super::execute_batch(&programs, &inputs, 1, &options);
+    assert_eq!(3, proof.batch_size());
+    assert_eq!(vec![vec![8], vec![field::exp(3, 40)], vec![49]], outputs);
+
+    // each program produces the same outputs as when it is executed on its own
+    for (i, program) in programs.iter().enumerate() {
+        let (program_outputs, _) = super::execute(program, &inputs[i], 1, &options);
+        assert_eq!(program_outputs, outputs[i]);
+    }
+
+    let program_hashes = programs.iter().map(|program| *program.hash()).collect::<Vec<[u8; 32]>>();
+    let public_inputs = inputs.iter().map(|inputs| inputs.get_public_inputs().to_vec()).collect::<Vec<Vec<u128>>>();
+    let result = super::verify_batch(&program_hashes, &public_inputs, &outputs, &proof);
+    assert_eq!(Ok(true), result);
+
+    // wrong outputs of any of the programs must be rejected
+    let mut wrong_outputs = outputs.clone();
+    wrong_outputs[2][0] = field::add(wrong_outputs[2][0], 1);
+    let result = super::verify_batch(&program_hashes, &public_inputs, &wrong_outputs, &proof);
+    assert_eq!(true, result.is_err());
+
+    // executions must be verified in the same order as they were proven
+    let mut wrong_hashes = program_hashes.clone();
+    wrong_hashes.swap(0, 2);
+    let result = super::verify_batch(&wrong_hashes, &public_inputs, &outputs, &proof);
+    assert_eq!(true, result.is_err());
+
+    // missing executions are rejected
+    let result = super::verify_batch(&program_hashes[..2], &public_inputs[..2], &outputs[..2], &proof);
+    assert_eq!(true, result.is_err());
+}
+
+#[test]
+fn public_input_digest() {
+    // hash public inputs read from tape A and compare the result with the digest on the stack
+    let program = assembly::compile("
+        begin
+            pad.2 read.a read.a hash.4 read.a push.0 hash.4 push.3 push.0 hash.4
+            swap roll.4 assert.eq assert.eq
+        end").unwrap();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::with_public_digest(&[1, 2, 3], &[], &[]);
+    assert_eq!(inputs.get_public_inputs(), super::hash_public_inputs(&[1, 2, 3]));
+    let num_outputs = 1;
+
+    // the number of inputs is bound to the digest
+    assert_ne!(super::hash_public_inputs(&[1, 2, 3]), super::hash_public_inputs(&[1, 2, 3, 0]));
+    assert_ne!(super::hash_public_inputs(&[1, 2]), super::hash_public_inputs(&[1, 2, 0]));
+    assert_ne!([0, 0], super::hash_public_inputs(&[]));
+
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(outputs, [0]);
+
+    let result = super::verify(program.hash(), &super::hash_public_inputs(&[1, 2, 3]), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+
+    // wrong inputs
+    let result = super::verify(program.hash(), &super::hash_public_inputs(&[1, 2, 4]), &outputs, &proof);
+    let err_msg = String::from("constraint evaluation at z is inconsistent with trace evaluations at z and z * g");
+    assert_eq!(Err(err_msg), result);
+
+    // raw inputs can be supplied at verification time instead of their digest
+    let result = super::verify_with_committed_inputs(program.hash(), &[1, 2, 3], &outputs, &proof);
+    assert_eq!(Ok(true), result);
+    let result = super::verify_with_committed_inputs(program.hash(), &[1, 2, 4], &outputs, &proof);
+    assert!(result.is_err());
+
+    // inputs padded with a trailing ZERO are different inputs
+    let result = super::verify_with_committed_inputs(program.hash(), &[1, 2, 3, 0], &outputs, &proof);
+    assert!(result.is_err());
+}
+
+#[test]
+#[should_panic(expected = "ASSERTEQ failed")]
+fn public_input_digest_fail() {
+    let program = assembly::compile("
+        begin
+            pad.2 read.a read.a hash.4 read.a push.0 hash.4 push.3 push.0 hash.4
+            swap roll.4 assert.eq assert.eq
+        end").unwrap();
+
+    // the digest on the stack does not match the inputs on tape A
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::new(&super::hash_public_inputs(&[1, 2, 3]), &[1, 2, 4], &[]);
+    super::execute(&program, &inputs, 1, &options);
+}
+
+#[test]
+fn input_files() {
+    let inputs = ProgramInputs::new(&[1, 2], &[3, 4, 5], &[6]);
+    let key = [7u8; 32];
+
+    // plaintext and encrypted files should round-trip
+    for &file_key in [None, Some(&key)].iter() {
+        let bytes = inputs.to_file_bytes(file_key);
+        let parsed = ProgramInputs::from_file_bytes(&bytes, file_key).unwrap();
+        assert_eq!(inputs.get_public_inputs(), parsed.get_public_inputs());
+        assert_eq!(inputs.get_secret_inputs(), parsed.get_secret_inputs());
+    }
+
+    // secret inputs do not appear in encrypted files, and every file uses a different nonce
+    let bytes = inputs.to_file_bytes(Some(&key));
+    assert!(!bytes.windows(16).any(|window| window == &5u128.to_le_bytes()[..]));
+    assert_ne!(bytes, inputs.to_file_bytes(Some(&key)));
+
+    // wrong keys, modified files, and missing keys are rejected
+    let err_msg = String::from("input file could not be decrypted: wrong key or corrupted file");
+    assert_eq!(Err(err_msg.clone()), ProgramInputs::from_file_bytes(&bytes, Some(&[8u8; 32])).map(|_| ()));
+    let mut modified = bytes.clone();
+    modified[50] ^= 1;
+    assert_eq!(Err(err_msg), ProgramInputs::from_file_bytes(&modified, Some(&key)).map(|_| ()));
+    let err_msg = String::from("input file is encrypted, but no key was provided");
+    assert_eq!(Err(err_msg), ProgramInputs::from_file_bytes(&bytes, None).map(|_| ()));
+
+    // inputs read from a file can be used to execute a program
+    let path = std::env::temp_dir().join(format!("distaff_inputs_{}.bin", std::process::id()));
+    inputs.to_file(&path, Some(&key)).unwrap();
+    let parsed = ProgramInputs::from_file(&path, Some(&key)).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let program = assembly::compile("begin read.a add end").unwrap();
+    let (outputs, proof) = super::execute(&program, &parsed, 1, &ProofOptions::default());
+    assert_eq!(vec![4], outputs);
+    assert_eq!(Ok(true), super::verify(program.hash(), &[1, 2], &outputs, &proof));
+}
+
+#[test]
+fn proof_estimate() {
+    let program = assembly::compile("begin push.1 push.2 add pushw.3.4.5.6 push.7 end").unwrap();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[]);
+    let (_, proof) = super::execute(&program, &inputs, 1, &options);
+
+    let trace_width = proof.trace_evaluations()[0].len();
+    let stats = options.estimate(proof.trace_length(), trace_width);
+    assert_eq!(options.conjectured_security(proof.trace_length()), stats.security_level);
+    assert_eq!(options.proven_security(proof.trace_length()), stats.proven_security);
+
+    // the estimate is an upper bound but should not be far off
+    let proof_size = bincode::serialize(&proof).unwrap().len();
+    assert!(stats.proof_size >= proof_size, "estimated {} but was {}", stats.proof_size, proof_size);
+    assert!(stats.proof_size < proof_size * 3 / 2, "estimated {} but was {}", stats.proof_size, proof_size);
+
+    // increasing the number of queries should increase the size of the proof
+    let options2 = ProofOptions::new(options.extension_factor(), 80, options.grinding_factor(), options.hash_fn());
+    let stats2 = options2.estimate(proof.trace_length(), trace_width);
+    assert!(stats2.proof_size > stats.proof_size);
+    assert!(stats2.proven_security > stats.proven_security);
+    assert_eq!(stats.prover_memory, stats2.prover_memory);
+}
+
+#[test]
+fn security_level() {
+    let options = ProofOptions::default();
+
+    // with default options, security of short traces is limited by the number of queries and
+    // security of long traces is limited by the field size
+    assert_eq!(115, options.conjectured_security(256));
+    assert_eq!(103, options.conjectured_security(1 << 20));
+    assert_eq!(63, options.proven_security(256));
+    assert_eq!(63, options.proven_security(1 << 20));
+
+    // more queries increase the security level
+    let options2 = ProofOptions::new(options.extension_factor(), 80, options.grinding_factor(), options.hash_fn());
+    assert!(options2.proven_security(256) > options.proven_security(256));
+
+    // verification should fail if the proof does not provide the required security level
+    let program = assembly::compile("begin push.1 push.2 add end").unwrap();
+    let inputs = ProgramInputs::from_public(&[]);
+    let (outputs, proof) = super::execute(&program, &inputs, 1, &options);
+
+    let security = options.conjectured_security(proof.trace_length());
+    let result = super::verify_with_security(program.hash(), &[], &outputs, &proof, security);
+    assert_eq!(Ok(true), result);
+
+    let result = super::verify_with_security(program.hash(), &[], &outputs, &proof, security + 1);
+    let err_msg = format!("proof provides {} bits of security, but at least {} bits are required", security, security + 1);
+    assert_eq!(Err(err_msg), result);
+}
+
+#[test]
+fn proof_options_policy() {
+    let options = ProofOptions::default();
+    let program = assembly::compile("begin push.1 push.2 add end").unwrap();
+    let inputs = ProgramInputs::from_public(&[]);
+    let (outputs, proof) = super::execute(&program, &inputs, 1, &options);
+
+    // default policy accepts proofs generated with default options or stronger
+    let mut policy = MinimumAcceptableOptions::default();
+    let result = super::verify_with_policy(program.hash(), &[], &outputs, &proof, &policy);
+    assert_eq!(Ok(true), result);
+
+    // proofs which fall below any of the policy parameters should be rejected
+    policy.num_queries = 60;
+    let result = super::verify_with_policy(program.hash(), &[], &outputs, &proof, &policy);
+    assert_eq!(Err(String::from("proof uses 50 queries, but at least 60 are required")), result);
+
+    policy = MinimumAcceptableOptions::default();
+    policy.extension_factor = 64;
+    let result = super::verify_with_policy(program.hash(), &[], &outputs, &proof, &policy);
+    assert_eq!(Err(String::from("proof uses extension factor 32, but at least 64 is required")), result);
+
+    policy = MinimumAcceptableOptions::default();
+    policy.grinding_factor = 24;
+    let result = super::verify_with_policy(program.hash(), &[], &outputs, &proof, &policy);
+    assert_eq!(Err(String::from("proof uses grinding factor 20, but at least 24 is required")), result);
+
+    policy = MinimumAcceptableOptions::default();
+    policy.hash_fns = vec![hash::sha3];
+    let result = super::verify_with_policy(program.hash(), &[], &outputs, &proof, &policy);
+    assert_eq!(Err(String::from("proof hash function is not accepted")), result);
+
+    policy = MinimumAcceptableOptions::default();
+    policy.field_modulus = 7;
+    let result = super::verify_with_policy(program.hash(), &[], &outputs, &proof, &policy);
+    assert!(result.is_err());
+}
+
+#[test]
+fn custom_transcript() {
+    let options = ProofOptions::default();
+    let program = assembly::compile("begin push.1 push.2 add end").unwrap();
+    let inputs = ProgramInputs::from_public(&[]);
+
+    // the proof is generated against a transcript which already contains a message of some
+    // larger protocol
+    let mut transcript = HashTranscript::new(options.hash_fn());
+    transcript.absorb(b"protocol_message", &[7; 32]);
+    let (outputs, proof) = super::execute_with_transcript(&program, &inputs, 1, &options, &mut transcript);
+    assert_eq!(vec![3], outputs);
+
+    // verification succeeds only against a transcript in the same state
+    let mut transcript = HashTranscript::new(options.hash_fn());
+    transcript.absorb(b"protocol_message", &[7; 32]);
+    let result = super::verify_with_transcript(program.hash(), &[], &outputs, &proof, &mut transcript);
+    assert_eq!(Ok(true), result);
+
+    let result = super::verify(program.hash(), &[], &outputs, &proof);
+    assert!(result.is_err());
+
+    let mut transcript = HashTranscript::new(options.hash_fn());
+    transcript.absorb(b"protocol_message", &[8; 32]);
+    let result = super::verify_with_transcript(program.hash(), &[], &outputs, &proof, &mut transcript);
+    assert!(result.is_err());
+}
+
+#[test]
+fn merlin_transcript() {
+    let options = ProofOptions::default();
+    let program = assembly::compile("begin push.1 push.2 add end").unwrap();
+    let inputs = ProgramInputs::from_public(&[]);
+
+    let mut transcript = MerlinTranscript::new(b"test protocol");
+    transcript.append_message(b"statement", b"some statement");
+    let (outputs, proof) = super::execute_with_transcript(&program, &inputs, 1, &options, &mut transcript);
+
+    // the prover and the verifier transcripts should end up in the same state
+    let prover_challenge = transcript.squeeze(b"challenge");
+
+    let mut transcript = MerlinTranscript::new(b"test protocol");
+    transcript.append_message(b"statement", b"some statement");
+    let result = super::verify_with_transcript(program.hash(), &[], &outputs, &proof, &mut transcript);
+    assert_eq!(Ok(true), result);
+    assert_eq!(prover_challenge, transcript.squeeze(b"challenge"));
+
+    // a transcript with a different protocol label should not verify the proof
+    let mut transcript = MerlinTranscript::new(b"other protocol");
+    transcript.append_message(b"statement", b"some statement");
+    let result = super::verify_with_transcript(program.hash(), &[], &outputs, &proof, &mut transcript);
+    assert!(result.is_err());
+}
+
+#[test]
+fn proof_json() {
+    let options = ProofOptions::default();
+    let program = assembly::compile("begin push.3 push.5 add write end").unwrap();
+    let inputs = ProgramInputs::from_public(&[]);
+    let (outputs, proof) = super::execute(&program, &inputs, 1, &options);
+
+    // a proof imported from JSON should be identical to the original proof
+    let json = proof.to_json();
+    let imported = StarkProof::from_json(&json).unwrap();
+    assert_eq!(json, imported.to_json());
+    assert_eq!(bincode::serialize(&proof).unwrap(), bincode::serialize(&imported).unwrap());
+    assert_eq!(Ok(true), super::verify(program.hash(), &[], &outputs, &imported));
+
+    // values are encoded as fixed-width hex strings
+    assert!(json.contains("\"output_tape\":[\"00000000000000000000000000000008\"]"));
+    assert!(json.contains(&format!("\"trace_root\":\"{}\"", hex::encode(proof.trace_root()))));
+
+    // unknown keys should be rejected
+    let modified = json.replacen("\"pow_nonce\"", "\"extra\":1,\"pow_nonce\"", 1);
+    assert!(StarkProof::from_json(&modified).is_err());
+
+    // non-canonical encodings should be rejected
+    let modified = json.replacen("00000000000000000000000000000008", "0x000000000000000000000000000008", 1);
+    assert!(StarkProof::from_json(&modified).is_err());
+    let modified = json.replacen(&hex::encode(proof.trace_root()), &hex::encode(proof.trace_root()).to_uppercase(), 1);
+    assert!(StarkProof::from_json(&modified).is_err());
+
+    // values outside of the field should be rejected
+    let modified = json.replacen("00000000000000000000000000000008", "ffffffffffffffffffffffffffffffff", 1);
+    let err_msg = String::from("value 'ffffffffffffffffffffffffffffffff' is not a valid field element");
+    assert_eq!(Err(err_msg), StarkProof::from_json(&modified).map(|_| ()));
+
+    // invalid proof options should be rejected
+    let modified = json.replacen("\"hash_fn\":\"blake3\"", "\"hash_fn\":\"md5\"", 1);
+    let err_msg = String::from("unsupported hash function 'md5'");
+    assert_eq!(Err(err_msg), StarkProof::from_json(&modified).map(|_| ()));
+}
+
+#[test]
+fn proof_bytes() {
+    let options = ProofOptions::default();
+    let program = assembly::compile("begin push.3 push.5 add write end").unwrap();
+    let inputs = ProgramInputs::from_public(&[]);
+    let (outputs, proof) = super::execute(&program, &inputs, 1, &options);
+
+    // a proof parsed from bytes should be identical to the original proof
+    let bytes = proof.to_bytes();
+    let imported = StarkProof::from_bytes(&bytes).unwrap();
+    assert_eq!(bytes, imported.to_bytes());
+    assert_eq!(Ok(true), super::verify(program.hash(), &[], &outputs, &imported));
+
+    // trailing and missing bytes should be rejected
+    let mut modified = bytes.clone();
+    modified.push(0);
+    assert!(StarkProof::from_bytes(&modified).is_err());
+    assert!(StarkProof::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+    // proof bytes end with the output tape followed by 22 bytes of proof options
+    let options_offset = bytes.len() - 22;
+    let tape_offset = options_offset - 16;
+    assert_eq!(8u128.to_le_bytes(), bytes[tape_offset..(tape_offset + 16)]);
+
+    // values outside of the field should be rejected
+    let mut modified = bytes.clone();
+    modified[tape_offset..(tape_offset + 16)].copy_from_slice(&u128::MAX.to_le_bytes());
+    let err_msg = String::from("proof contains values which are not valid field elements");
+    assert_eq!(Err(err_msg), StarkProof::from_bytes(&modified).map(|_| ()));
+
+    // invalid proof options should be rejected
+    let mut modified = bytes.clone();
+    modified[options_offset] = 2;
+    let err_msg = String::from("invalid extension factor 2^2");
+    assert_eq!(Err(err_msg), StarkProof::from_bytes(&modified).map(|_| ()));
+
+    let mut modified = bytes.clone();
+    modified[options_offset + 1] = 0;
+    let err_msg = String::from("invalid number of queries 0");
+    assert_eq!(Err(err_msg), StarkProof::from_bytes(&modified).map(|_| ()));
+
+    let mut modified = bytes.clone();
+    modified[options_offset + 3] = 11;
+    let err_msg = String::from("invalid FRI remainder length 2^11");
+    assert_eq!(Err(err_msg), StarkProof::from_bytes(&modified).map(|_| ()));
+
+    let mut modified = bytes.clone();
+    modified[(options_offset + 4)..(options_offset + 20)].copy_from_slice(&0u128.to_le_bytes());
+    let err_msg = String::from("invalid domain offset 0");
+    assert_eq!(Err(err_msg), StarkProof::from_bytes(&modified).map(|_| ()));
+
+    let mut modified = bytes.clone();
+    modified[options_offset + 20] = 9;
+    let err_msg = String::from("invalid Merkle cap height 9");
+    assert_eq!(Err(err_msg), StarkProof::from_bytes(&modified).map(|_| ()));
+
+    let mut modified = bytes.clone();
+    modified[bytes.len() - 1] = 7;
+    assert!(StarkProof::from_bytes(&modified).is_err());
+}
+
+#[test]
+fn proof_from_reader() {
+    let options = ProofOptions::default();
+    let program = assembly::compile("begin push.3 push.5 add write end").unwrap();
+    let inputs = ProgramInputs::from_public(&[]);
+    let (outputs, proof) = super::execute(&program, &inputs, 1, &options);
+    let bytes = proof.to_bytes();
+
+    // a proof read from a reader should be the same as a proof parsed from bytes
+    let result = super::verify_from_reader(program.hash(), &[], &outputs, &bytes[..], bytes.len());
+    assert_eq!(Ok(true), result);
+    let result = super::verify_from_reader(program.hash(), &[], &[9], &bytes[..], bytes.len());
+    assert!(result.is_err());
+
+    // bytes following the proof are left in the reader
+    let mut stream = [&bytes[..], &bytes[..], &[1, 2, 3]].concat();
+    let mut reader = &stream[..];
+    for _ in 0..2 {
+        let imported = StarkProof::from_reader(&mut reader, bytes.len()).unwrap();
+        assert_eq!(bytes, imported.to_bytes());
+    }
+    assert_eq!(&[1, 2, 3], reader);
+
+    // proofs larger than the limit and truncated proofs should be rejected
+    let result = StarkProof::from_reader(&bytes[..], bytes.len() - 1).map(|_| ());
+    assert!(result.unwrap_err().starts_with("invalid proof bytes"));
+    let result = StarkProof::from_reader(&bytes[..(bytes.len() - 1)], bytes.len()).map(|_| ());
+    assert!(result.unwrap_err().starts_with("invalid proof bytes"));
+
+    // proofs read from a reader are validated in the same way as proofs parsed from bytes
+    stream[0] = FORMAT_VERSION + 1;
+    let result = StarkProof::from_reader(&stream[..], bytes.len()).map(|_| ());
+    assert!(result.unwrap_err().starts_with("unsupported format version"));
+    let tape_offset = bytes.len() - 22 - 16;
+    let mut modified = bytes.clone();
+    modified[tape_offset..(tape_offset + 16)].copy_from_slice(&u128::MAX.to_le_bytes());
+    let err_msg = String::from("proof contains values which are not valid field elements");
+    assert_eq!(Err(err_msg), StarkProof::from_reader(&modified[..], bytes.len()).map(|_| ()));
+}
+
+#[test]
+fn output_disclosure() {
+    let options = ProofOptions::default();
+    for &num_outputs in [1, 2, 4, 8].iter() {
+        // the program takes its outputs from the public inputs and commits to them
+        let values: Vec<u128> = (0..num_outputs).map(|i| 10 + i as u128).collect();
+        let salts = field::rand_vector(num_outputs);
+        let program = assembly::compile(&format!("begin {} end", super::commit_outputs(num_outputs))).unwrap();
+        let inputs = ProgramInputs::new(&values, &salts, &[]);
+        let (outputs, proof) = super::execute(&program, &inputs, 2, &options);
+
+        let commitment = OutputCommitment::new(&values, &salts);
+        assert_eq!(commitment.root().to_vec(), outputs);
+
+        // every output can be revealed on its own
+        let root = commitment.root();
+        for i in 0..num_outputs {
+            let opening = commitment.open(i);
+            let result = super::verify_with_disclosure(program.hash(), &values, &root, num_outputs, &[opening], &proof);
+            assert_eq!(Ok(true), result);
+        }
+
+        // openings of wrong values or of a different number of outputs are rejected
+        let mut opening = commitment.open(num_outputs - 1);
+        opening.value = 3;
+        let result = super::verify_with_disclosure(program.hash(), &values, &root, num_outputs, &[opening], &proof);
+        assert_eq!(Err(format!("opening of output {} does not match output commitment", num_outputs - 1)), result);
+        let result = commitment.open(0).verify(&root, num_outputs * 2);
+        assert!(result.is_err());
+    }
+}
+
+#[test]
+fn nullifier() {
+    // the domain is a public input, and the secret is read from tape A
+    let program = assembly::compile(&format!("begin read.a {} end", super::derive_nullifier())).unwrap();
+    let inputs = ProgramInputs::new(&[7], &[12345], &[]);
+    let (outputs, proof) = super::execute(&program, &inputs, 2, &ProofOptions::default());
+
+    let nullifier = super::hash_nullifier(12345, 7);
+    assert_eq!(nullifier.to_vec(), outputs);
+    let result = super::verify_with_nullifier(program.hash(), &[7], &outputs, &nullifier, &proof);
+    assert_eq!(Ok(true), result);
+
+    // nullifiers differ across secrets and domains
+    assert_ne!(nullifier, super::hash_nullifier(12346, 7));
+    assert_ne!(nullifier, super::hash_nullifier(12345, 8));
+    let result = super::verify_with_nullifier(program.hash(), &[7], &outputs, &super::hash_nullifier(12345, 8), &proof);
+    assert!(result.is_err());
+}
+
+#[test]
+fn execution_digest() {
+    let program = assembly::compile("begin add dup write end").unwrap();
+    let inputs = ProgramInputs::from_public(&[3, 5]);
+    let (outputs, proof) = super::execute(&program, &inputs, 1, &ProofOptions::default());
+    let (_, other_proof) = super::execute(&program, &inputs, 1, &ProofOptions::default().with_remainder_length(64));
+
+    let digest = ExecutionDigest::new(program.hash(), &[3, 5], &outputs, &proof);
+    assert_eq!(program.hash(), &digest.program_hash);
+    assert_eq!(super::hash_public_inputs(&[3, 5]), digest.input_commitment);
+    assert_eq!(super::hash_public_inputs(&[8]), digest.output_commitment);
+    assert_eq!(super::hash_output_tape(&[8]), digest.tape_commitment);
+
+    // the digest depends only on the statement and the proof options
+    let imported = StarkProof::from_bytes(&proof.to_bytes()).unwrap();
+    assert_eq!(digest.id(), ExecutionDigest::new(program.hash(), &[3, 5], &outputs, &imported).id());
+    assert_ne!(digest.id(), ExecutionDigest::new(program.hash(), &[3, 4], &outputs, &proof).id());
+    assert_ne!(digest.id(), ExecutionDigest::new(program.hash(), &[3, 5], &[9], &proof).id());
+    assert_ne!(digest.id(), ExecutionDigest::new(program.hash(), &[3, 5, 0], &outputs, &proof).id());
+    assert_ne!(digest.id(), ExecutionDigest::new(program.hash(), &[3, 5], &[8, 0], &proof).id());
+    assert_ne!(digest.id(), ExecutionDigest::new(&[0; 32], &[3, 5], &outputs, &proof).id());
+    let other_digest = ExecutionDigest::new(program.hash(), &[3, 5], &outputs, &other_proof);
+    assert_ne!(digest.options_digest, other_digest.options_digest);
+    assert_ne!(digest.id(), other_digest.id());
+}
+
+#[test]
+fn proof_format() {
+    let options = ProofOptions::default();
+    let program = assembly::compile("begin push.3 push.5 add end").unwrap();
+    let inputs = ProgramInputs::none();
+    let (outputs, proof) = super::execute(&program, &inputs, 1, &options);
+    assert_eq!(FORMAT_VERSION, proof.format().version());
+
+    // proof bytes start with the format header: version, field id, hash id, and parameter digest
+    let bytes = proof.to_bytes();
+    let version_err = format!("unsupported format version {}; only version {} is supported", FORMAT_VERSION + 1, FORMAT_VERSION);
+    let mut modified = bytes.clone();
+    modified[0] = FORMAT_VERSION + 1;
+    assert_eq!(Err(version_err.clone()), StarkProof::from_bytes(&modified).map(|_| ()));
+
+    let mut modified = bytes.clone();
+    modified[1] = 1;
+    let err_msg = String::from("unsupported field id 1; only field id 0 is supported");
+    assert_eq!(Err(err_msg), StarkProof::from_bytes(&modified).map(|_| ()));
+
+    let mut modified = bytes.clone();
+    modified[2] = 1;
+    let err_msg = String::from("unsupported hash function id 1; expected hash function blake3");
+    assert_eq!(Err(err_msg), StarkProof::from_bytes(&modified).map(|_| ()));
+
+    let mut modified = bytes.clone();
+    modified[3] ^= 1;
+    let result = StarkProof::from_bytes(&modified).map(|_| ());
+    assert!(result.unwrap_err().starts_with("unsupported VM parameters"));
+
+    // proofs exported to JSON carry the same header
+    let json = proof.to_json();
+    let version = format!("\"format\":{{\"version\":{},", FORMAT_VERSION);
+    let modified = json.replacen(&version, &format!("\"format\":{{\"version\":{},", FORMAT_VERSION + 1), 1);
+    assert_eq!(Err(version_err.clone()), StarkProof::from_json(&modified).map(|_| ()));
+
+    let modified = json.replacen("\"hash_id\":0", "\"hash_id\":2", 1);
+    let err_msg = String::from("unsupported hash function id 2; expected hash function blake3");
+    assert_eq!(Err(err_msg), StarkProof::from_json(&modified).map(|_| ()));
+
+    // a proof with a supported header verifies as before
+    let imported = StarkProof::from_json(&json).unwrap();
+    assert_eq!(Ok(true), super::verify(program.hash(), &[], &outputs, &imported));
+
+    // program ABIs are serialized with the same header
+    let mut abi_bytes = ProgramAbi::new(1, 0, [0, 0], 1).to_bytes();
+    abi_bytes[0] = FORMAT_VERSION + 1;
+    assert_eq!(Err(version_err), ProgramAbi::from_bytes(&abi_bytes));
+}
+
+#[test]
+fn constraint_evaluation_at_z() {
+    let options = ProofOptions::default();
+    let program = assembly::compile("begin push.3 push.5 add write end").unwrap();
+    let inputs = ProgramInputs::from_public(&[]);
+    let (outputs, proof) = super::execute(&program, &inputs, 1, &options);
+
+    // a proof with a modified constraint evaluation at z should be rejected before FRI is run
+    let value = proof.constraint_at_z();
+    let modified_value = field::add(value, field::ONE);
+    let json = proof.to_json().replacen(
+        &format!("\"constraint_at_z\":\"{:032x}\"", value),
+        &format!("\"constraint_at_z\":\"{:032x}\"", modified_value), 1);
+    let modified = StarkProof::from_json(&json).unwrap();
+    assert_eq!(modified_value, modified.constraint_at_z());
+
+    let err_msg = String::from("constraint evaluation at z is inconsistent with trace evaluations at z and z * g");
+    assert_eq!(Err(err_msg), super::verify(program.hash(), &[], &outputs, &modified));
+}
+
+#[test]
+fn fri_remainder_length() {
+    let program = assembly::compile("begin push.3 push.5 add write end").unwrap();
+    let inputs = ProgramInputs::from_public(&[]);
+
+    // a larger remainder replaces FRI layers with remainder values
+    let options = ProofOptions::default();
+    let (outputs, proof) = super::execute(&program, &inputs, 1, &options);
+    let large_options = ProofOptions::default().with_remainder_length(1024);
+    let (large_outputs, large_proof) = super::execute(&program, &inputs, 1, &large_options);
+    assert_eq!(outputs, large_outputs);
+    assert_eq!(0, large_proof.degree_proof().layers.len());
+    assert_eq!(proof.domain_size(), large_proof.degree_proof().rem_values.len());
+    assert_eq!(Ok(true), super::verify(program.hash(), &[], &large_outputs, &large_proof));
+
+    // the remainder length survives serialization
+    let imported = StarkProof::from_json(&large_proof.to_json()).unwrap();
+    assert_eq!(1024, imported.options().remainder_length());
+    let imported = StarkProof::from_bytes(&large_proof.to_bytes()).unwrap();
+    assert_eq!(Ok(true), super::verify(program.hash(), &[], &large_outputs, &imported));
+
+    // a smaller remainder adds FRI layers
+    let small_options = ProofOptions::default().with_remainder_length(16);
+    let (small_outputs, small_proof) = super::execute(&program, &inputs, 1, &small_options);
+    assert!(small_proof.degree_proof().layers.len() > proof.degree_proof().layers.len());
+    assert_eq!(Ok(true), super::verify(program.hash(), &[], &small_outputs, &small_proof));
+}
+
+#[test]
+fn domain_offset() {
+    let program = assembly::compile("begin push.3 push.5 add write end").unwrap();
+    let inputs = ProgramInputs::from_public(&[]);
+
+    // a proof over a shifted domain should be valid and should differ from a proof over the
+    // subgroup; proofs over different cosets should differ from each other as well
+    let options = ProofOptions::default();
+    let (outputs, proof) = super::execute(&program, &inputs, 1, &options);
+    let shifted_options = ProofOptions::default().with_domain_offset(7);
+    let (shifted_outputs, shifted_proof) = super::execute(&program, &inputs, 1, &shifted_options);
+    assert_eq!(outputs, shifted_outputs);
+    assert_ne!(proof.trace_root(), shifted_proof.trace_root());
+    assert_eq!(Ok(true), super::verify(program.hash(), &[], &shifted_outputs, &shifted_proof));
+
+    let other_options = ProofOptions::default().with_domain_offset(11);
+    let (other_outputs, other_proof) = super::execute(&program, &inputs, 1, &other_options);
+    assert_ne!(shifted_proof.trace_root(), other_proof.trace_root());
+    assert_eq!(Ok(true), super::verify(program.hash(), &[], &other_outputs, &other_proof));
+
+    // batch proofs are generated over the same domain as individual proofs
+    let programs = vec![program.clone(), assembly::compile("begin push.7 dup mul end").unwrap()];
+    let batch_inputs = vec![inputs.clone(), inputs.clone()];
+    let (batch_outputs, batch_proof) = super::execute_batch(&programs, &batch_inputs, 1, &shifted_options);
+    let program_hashes = programs.iter().map(|program| *program.hash()).collect::<Vec<[u8; 32]>>();
+    let result = super::verify_batch(&program_hashes, &[vec![], vec![]], &batch_outputs, &batch_proof);
+    assert_eq!(Ok(true), result);
+
+    // the offset survives serialization
+    let imported = StarkProof::from_bytes(&shifted_proof.to_bytes()).unwrap();
+    assert_eq!(7, imported.options().domain_offset());
+    let imported = StarkProof::from_json(&shifted_proof.to_json()).unwrap();
+    assert_eq!(Ok(true), super::verify(program.hash(), &[], &shifted_outputs, &imported));
+
+    // a proof verified against a different offset should be rejected
+    let offset = format!("\"domain_offset\":\"{:032x}\"", 7);
+    let json = shifted_proof.to_json().replacen(&offset, &format!("\"domain_offset\":\"{:032x}\"", 11), 1);
+    let modified = StarkProof::from_json(&json).unwrap();
+    assert!(super::verify(program.hash(), &[], &shifted_outputs, &modified).is_err());
+
+    // offsets which place the domain into the trace domain should be rejected
+    assert!(!ProofOptions::is_valid_domain_offset(field::ZERO));
+    assert!(!ProofOptions::is_valid_domain_offset(field::get_root_of_unity(1 << 20)));
+    let json = shifted_proof.to_json().replacen(&offset, &format!("\"domain_offset\":\"{:032x}\"", field::G), 1);
+    let err_msg = format!("invalid domain offset {}", field::G);
+    assert_eq!(Err(err_msg), StarkProof::from_json(&json).map(|_| ()));
+}
+
+#[test]
+fn merkle_caps() {
+    let program = assembly::compile("begin push.3 push.5 add write end").unwrap();
+    let inputs = ProgramInputs::from_public(&[]);
+
+    // capped trees have the same roots, but their authentication paths are shorter
+    let options = ProofOptions::default();
+    let (outputs, proof) = super::execute(&program, &inputs, 1, &options);
+    let capped_options = ProofOptions::default().with_cap_height(6);
+    let (capped_outputs, capped_proof) = super::execute(&program, &inputs, 1, &capped_options);
+    assert_eq!(outputs, capped_outputs);
+    assert_eq!(proof.trace_root(), capped_proof.trace_root());
+    assert_eq!(64, capped_proof.trace_proof().cap.len());
+    assert_eq!(64, capped_proof.degree_proof().layers[0].cap.len());
+    let count_nodes = |proof: &StarkProof| proof.trace_proof().nodes.iter().map(|path| path.len()).sum::<usize>();
+    assert!(count_nodes(&capped_proof) < count_nodes(&proof));
+    assert_eq!(Ok(true), super::verify(program.hash(), &[], &capped_outputs, &capped_proof));
+
+    // the estimate remains an upper bound
+    let trace_width = capped_proof.trace_evaluations()[0].len();
+    let stats = capped_options.estimate(capped_proof.trace_length(), trace_width);
+    assert!(stats.proof_size >= capped_proof.to_bytes().len());
+
+    // caps survive serialization
+    let imported = StarkProof::from_bytes(&capped_proof.to_bytes()).unwrap();
+    assert_eq!(6, imported.options().cap_height());
+    let imported = StarkProof::from_json(&capped_proof.to_json()).unwrap();
+    assert_eq!(Ok(true), super::verify(program.hash(), &[], &capped_outputs, &imported));
+
+    // a cap which does not match the root should be rejected
+    let cap = hex::encode(capped_proof.trace_proof().cap[0]);
+    let json = capped_proof.to_json().replacen(&cap, &hex::encode([0u8; 32]), 1);
+    let modified = StarkProof::from_json(&json).unwrap();
+    let result = super::verify(program.hash(), &[], &capped_outputs, &modified);
+    assert_eq!(Err(String::from("verification of trace Merkle proof failed")), result);
+
+    // batch proofs are capped in the same way
+    let programs = vec![program.clone(), assembly::compile("begin push.7 dup mul end").unwrap()];
+    let batch_inputs = vec![inputs.clone(), inputs.clone()];
+    let (batch_outputs, batch_proof) = super::execute_batch(&programs, &batch_inputs, 1, &capped_options);
+    assert_eq!(64, batch_proof.aux_proof().cap.len());
+    let program_hashes = programs.iter().map(|program| *program.hash()).collect::<Vec<[u8; 32]>>();
+    let result = super::verify_batch(&program_hashes, &[vec![], vec![]], &batch_outputs, &batch_proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+fn test_vectors() {
+    let options = ProofOptions::default();
+
+    // test vectors generated from the same seed should be identical
+    let vector = testing::generate_test_vector(7, &options);
+    let json = vector.to_json();
+    assert_eq!(json, testing::generate_test_vector(7, &options).to_json());
+    assert_ne!(json, testing::generate_test_vector(8, &options).to_json());
+
+    // the fixture for a given seed is pinned, since other implementations depend on it
+    let expected = "4544d8fd88f548beb3692e346b2c4eefe0d95905e548451d2bfda3c024095b96";
+    assert_eq!(expected, hex::encode(vector.program.hash()));
+
+    let public_inputs = vector.inputs.get_public_inputs();
+    let result = super::verify(vector.program.hash(), public_inputs, &vector.outputs, &vector.proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+fn generated_programs() {
+    // programs generated from the same data should be identical
+    let data: Vec<u8> = (0..255).collect();
+    let program = testing::GeneratedProgram::from_bytes(&data);
+    assert_eq!(program.source, testing::GeneratedProgram::from_bytes(&data).source);
+    assert_ne!(program.source, testing::GeneratedProgram::from_bytes(&data[1..]).source);
+
+    // any data should result in a valid program
+    let options = ProofOptions::default();
+    testing::round_trip(&[], &options);
+    testing::round_trip(&[0xFF; 64], &options);
+    testing::round_trip(&data, &options);
+    for seed in 0..4 {
+        let program = testing::GeneratedProgram::from_seed(seed);
+        testing::round_trip(program.source.as_bytes(), &options);
+    }
+}
+
+#[test]
+fn interpreter_consistency() {
+    // the interpreter and the trace builder should agree on all generated programs
+    for seed in 0..8 {
+        let generated = testing::GeneratedProgram::from_seed(seed);
+        assert_eq!(Ok(()), testing::check_interpreter(&generated.compile(), &generated.inputs));
+    }
+
+    // as well as on programs which use execution hints
+    let program = assembly::compile("begin push.3 push.5 gt.4 push.7 push.7 eq rc.8 push.2 exp.4 end").unwrap();
+    let inputs = ProgramInputs::from_public(&[1, 2]);
+    assert_eq!(Ok(()), testing::check_interpreter(&program, &inputs));
+
+    let outputs = super::run(&program, &inputs, 4, usize::MAX).unwrap();
+    let (expected, _) = super::execute(&program, &inputs, 4, &ProofOptions::default());
+    assert_eq!(expected, outputs);
+}
+
+#[test]
+fn audit_mode() {
+    // all failed checks are recorded in the order in which they occur
+    let program = assembly::compile("begin push.3 not push.5 push.6 assert.eq read drop drop drop end").unwrap();
+    let report = super::audit(&program, &ProgramInputs::none(), usize::MAX);
+    let kinds: Vec<CheckKind> = report.failures.iter().map(|failure| failure.kind).collect();
+    assert_eq!(vec![CheckKind::NonBinary, CheckKind::Assertion, CheckKind::Advice, CheckKind::Underflow], kinds);
+    assert_eq!("attempt to read from empty tape A at step 19", report.failures[2].message);
+    assert_eq!(Some(SourceLocation { line: 1, column: 14 }), report.failures[0].location);
+    assert_eq!(None, report.error);
+    assert!(!report.is_clean());
+    assert!(report.to_json().contains(r#""kind":"non_binary""#));
+
+    // programs without issues produce clean reports
+    let program = assembly::compile("begin push.3 push.5 add end").unwrap();
+    let report = super::audit(&program, &ProgramInputs::none(), usize::MAX);
+    assert!(report.is_clean());
+    assert!(report.cycles > 0);
+
+    // errors which abort execution are reported as well
+    let program = assembly::compile("begin push.1 while.true push.1 end end").unwrap();
+    let report = super::audit(&program, &ProgramInputs::none(), 64);
+    assert!(report.error.unwrap().starts_with("execution error: limit of 64 cycles exceeded"));
+}
+
+#[test]
+fn source_map() {
+    // every executed instruction is mapped onto the steps of the operations compiled from it;
+    // NOOPs which align PUSH operations are attributed to the PUSH instruction
+    let program = assembly::compile("begin\n    push.3 push.5\n    repeat.2\n        add dup\n    end\nend").unwrap();
+    let map = super::source_map(&program, &ProgramInputs::from_public(&[1]), usize::MAX).unwrap();
+    let expected = vec![
+        (SourceLocation { line: 1, column: 1 }, 0..1),
+        (SourceLocation { line: 2, column: 5 }, 1..9),
+        (SourceLocation { line: 2, column: 12 }, 9..10),
+        (SourceLocation { line: 4, column: 9 }, 16..17),
+        (SourceLocation { line: 4, column: 13 }, 17..18),
+        (SourceLocation { line: 4, column: 9 }, 32..33),
+        (SourceLocation { line: 4, column: 13 }, 33..34),
+    ];
+    assert_eq!(expected, map);
+
+    // transition constraint failures are reported together with the failing instruction
+    let mut trace = testing::ExecutionTrace::new(&program, &ProgramInputs::from_public(&[1]));
+    let register = trace.user_stack_registers().start;
+    trace.set(register, 17, 7);
+    let err = testing::check_transitions(&trace).unwrap_err();
+    assert!(err.ends_with("at line 4, column 9"), "{}", err);
+}
+
+#[test]
+#[should_panic(expected = "cannot compute INV of 0 at step 9 (line 2, column 8)")]
+fn source_location_panic() {
+    let program = assembly::compile("begin push.0\n       inv end").unwrap();
+    super::run(&program, &ProgramInputs::none(), 1, usize::MAX).unwrap();
+}
+
+#[test]
+fn constraint_consistency() {
+    let mut rng = StdRng::seed_from_u64(0);
+    for seed in 0..4 {
+        let generated = testing::GeneratedProgram::from_seed(seed);
+        let mut trace = testing::ExecutionTrace::new(&generated.compile(), &generated.inputs);
+
+        // all constraints should be satisfied by an honestly generated trace
+        assert_eq!(Ok(()), testing::check_transitions(&trace));
+
+        // and modifying any value of the trace should break at least one constraint; values which
+        // were just read from secret tapes are skipped because they can be dropped right away
+        let stack = trace.user_stack_registers();
+        for _ in 0..16 {
+            let register = rng.gen_range(0, trace.register_count());
+            let step = rng.gen_range(1, trace.length());
+            let op_code = trace.op_code(step - 1);
+            let is_read = op_code == OpCode::Read as u8 || op_code == OpCode::Read2 as u8;
+            if is_read && register >= stack.start && register < stack.start + 2 { continue; }
+
+            let value = trace.get(register, step);
+            trace.set(register, step, field::add(value, field::ONE));
+            assert!(testing::check_transitions(&trace).is_err(),
+                "modification of register {} at step {} was not detected", register, step);
+            trace.set(register, step, value);
+        }
+    }
+}
+
+#[test]
+fn comparison_placement() {
+    // CMP operations carry the current power of two on the stack, and so, comparisons produce
+    // correct results regardless of the step at which the comparison sequence starts
+    for offset in 0..32 {
+        let padding = "pad drop ".repeat(offset);
+        for &(op, x, y, expected) in [("gt", 7, 5, 1), ("gt", 5, 7, 0), ("lt", 5, 7, 1), ("lt", 7, 7, 0)].iter() {
+            let program = assembly::compile(&format!("begin {} {}.8 end", padding, op)).unwrap();
+            let inputs = ProgramInputs::from_public(&[x, y]);
+            assert_eq!(Ok(vec![expected]), super::run(&program, &inputs, 1, super::DEFAULT_MAX_CYCLES),
+                "{}.8 of {} and {} failed at offset {}", op, x, y, offset);
+
+            let trace = testing::ExecutionTrace::new(&program, &inputs);
+            assert_eq!(Ok(()), testing::check_transitions(&trace), "{}.8 failed at offset {}", op, offset);
+        }
+    }
+}
+
+#[test]
+fn trace_visualization() {
+    let program = assembly::compile("begin push.3 push.5 add end").unwrap();
+    let mut trace = testing::ExecutionTrace::new(&program, &ProgramInputs::none());
+
+    let html = diagnostics::render_html(&trace, 0..trace.length(), &[]);
+    assert_eq!(trace.length() + 2, html.matches("<tr").count());
+    assert!(html.contains("hacc / push") && html.contains("hacc / add"));
+    assert!(!html.contains("highlighted\""));
+    assert_eq!(None, diagnostics::render_failure_html(&trace, 2));
+
+    // rows of a transition which fails are highlighted
+    let register = trace.user_stack_registers().start;
+    trace.set(register, 4, 7);
+    let html = diagnostics::render_failure_html(&trace, 2).unwrap();
+    assert_eq!(2, html.matches("class=\"highlighted\"").count());
+    assert!(html.contains("is not satisfied between steps 3 and 4"), "{}", html);
+}
+
+#[test]
+fn vm_test() {
+    VmTest::new().stack(&[1, 2]).op(OpCode::Add).expect_stack(&[3]);
+    VmTest::new().stack(&[1, 2, 3]).op(OpCode::Swap).op(OpCode::Drop).expect_stack(&[1, 3]);
+    VmTest::new().op_with_hint(OpCode::Push, OpHint::PushValue(7)).op(OpCode::Dup).expect_stack(&[7, 7]);
+    VmTest::new().stack(&[5]).tape_a(&[9]).op(OpCode::Read).expect_stack(&[9, 5]);
+
+    // deep stacks and operations which must be aligned
+    let values: Vec<u128> = (1..21).collect();
+    VmTest::new().stack(&values).op(OpCode::Drop).expect_stack(&values[1..]);
+    let mut state = [3, 4, 5, 6, 7, 8];
+    hasher::permute(&mut state);
+    VmTest::new().stack(&[3, 4, 5, 6, 7, 8]).op(OpCode::Noop).op(OpCode::RescR).expect_stack(&state);
+}
+
+#[test]
+#[should_panic(expected = "but was [3, 0")]
+fn vm_test_mismatch() {
+    VmTest::new().stack(&[1, 2]).op(OpCode::Add).expect_stack(&[4]);
+}
+
 #[test]
 fn stack_manipulation() {
     let program = build_program(vec![
-        OpCode::Begin, OpCode::Noop,  OpCode::Noop,  OpCode::Noop,
+        OpCode::Begin, OpCode::Noop,  OpCode::Noop,  OpCode::Noop,
+        OpCode::Noop,  OpCode::Noop,  OpCode::Noop,  OpCode::Noop,
+        OpCode::Swap,  OpCode::Swap2, OpCode::Swap4, OpCode::Roll4,
+        OpCode::Roll8, OpCode::Dup,   OpCode::Add,   OpCode::Pad2,
+        OpCode::Push,  OpCode::Swap4, OpCode::Drop4, OpCode::Dup2,
+        OpCode::Swap4, OpCode::Add,   OpCode::Add,   OpCode::Dup4,
+        OpCode::Push,  OpCode::Add,   OpCode::Add,   OpCode::Add,
+        OpCode::Add,   OpCode::Noop,  OpCode::Noop,
+    ], &[11, 12]);
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[7, 6, 5, 4, 3, 2, 1, 0]);
+    let num_outputs = 8;
+
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(outputs, [46, 19, 4, 11, 0, 11, 0, 6]);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+fn push_operations() {
+    let program = assembly::compile("begin push.1 push.2 add pushw.3.4.5.6 push.7 end").unwrap();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[]);
+    let num_outputs = 6;
+
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(outputs, [7, 6, 5, 4, 3, 3]);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+fn clk_operation() {
+    let program = assembly::compile("begin clk push.1 add clk end").unwrap();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[]);
+    let num_outputs = 2;
+
+    // the first CLK is executed right after BEGIN; PUSH is aligned to step 8, so the second
+    // CLK is executed at step 10
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(outputs, [10, 2]);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+fn write_operations() {
+    let program = assembly::compile("begin push.3 write dup write add write push.7 end").unwrap();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[1, 2]);
+    let num_outputs = 1;
+
+    // values are written to the output tape in the order in which they are removed from the stack
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(outputs, [7]);
+    assert_eq!(proof.output_tape(), [3, 1, 3]);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+fn emit_operations() {
+    let program = assembly::compile("begin push.5 emit.1 dup mul emit.2 end").unwrap();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[3, 4]);
+    let mut host = EventHost { events: Vec::new() };
+
+    // every event is recorded on the output tape as its id followed by its payload
+    let (outputs, proof, _) = super::execute_with_host(&program, &inputs, &mut host, 1, &options);
+    assert_eq!(outputs, [4]);
+    assert_eq!(proof.output_tape(), [1, 5, 2, 9]);
+    assert_eq!(host.events, [(1, 5), (2, 9)]);
+
+    let event_log_hash = super::hash_event_log(&host.events);
+    let result = super::verify_with_events(program.hash(), inputs.get_public_inputs(), &outputs, &proof, &event_log_hash);
+    assert_eq!(Ok(true), result);
+
+    let other_hash = super::hash_event_log(&[(1, 5), (2, 8)]);
+    let result = super::verify_with_events(program.hash(), inputs.get_public_inputs(), &outputs, &proof, &other_hash);
+    assert!(result.is_err());
+
+    // events do not require a host interface
+    let (_, proof) = super::execute(&program, &inputs, 1, &options);
+    assert_eq!(proof.output_tape(), [1, 5, 2, 9]);
+}
+
+#[test]
+fn execute_verify_environment() {
+    // the program adds the first element of the environment word to the public input
+    let program = assembly::compile("begin swap.2 drop.2 swap drop add end").unwrap();
+    let options = ProofOptions::default();
+
+    for environment in [[7, 1, 2, 3], [9, 1, 2, 3]] {
+        let inputs = ProgramInputs::with_environment(&environment, &[5], &[], &[]);
+        let (outputs, proof) = super::execute(&program, &inputs, 1, &options);
+        assert_eq!(outputs, [environment[0] + 5]);
+
+        let result = super::verify_with_environment(program.hash(), &environment, &[5], &outputs, &proof);
+        assert_eq!(Ok(true), result);
+
+        // the proof is bound to the environment
+        let result = super::verify_with_environment(program.hash(), &[8, 1, 2, 3], &[5], &outputs, &proof);
+        assert!(result.is_err());
+    }
+
+    // at most 4 public inputs can be provided together with an environment
+    let inputs = ProgramInputs::with_environment(&[7, 1, 2, 3], &[5], &[], &[]);
+    let (outputs, proof) = super::execute(&program, &inputs, 1, &options);
+    let result = super::verify_with_environment(program.hash(), &[7, 1, 2, 3], &[5, 0, 0, 0, 0], &outputs, &proof);
+    assert_eq!(Err(String::from("expected no more than 4 public inputs together with an environment, but received 5")), result);
+}
+
+#[test]
+fn conditional_operations() {
+    // CHOOSE
+    let program = build_program(vec![
+        OpCode::Begin, OpCode::Choose,  OpCode::Choose, OpCode::Noop,
+        OpCode::Noop,  OpCode::Noop,    OpCode::Noop, OpCode::Noop,
+        OpCode::Noop,  OpCode::Noop,    OpCode::Noop, OpCode::Noop,
+        OpCode::Noop,  OpCode::Noop,    OpCode::Noop,
+    ], &[]);
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[3, 4, 1, 5, 0, 6, 7, 8]);
+    let num_outputs = 8;
+
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(outputs, [5, 6, 7, 8, 0, 0, 0, 0]);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+
+    // CHOOSE2
+    let program = build_program(vec![
+        OpCode::Begin, OpCode::Noop,    OpCode::Noop,    OpCode::Noop,
+        OpCode::Noop,  OpCode::Noop,    OpCode::Noop,    OpCode::Noop,
+        OpCode::Push,  OpCode::Noop,    OpCode::Noop,    OpCode::Noop,
+        OpCode::Noop,  OpCode::Noop,    OpCode::Noop,    OpCode::Noop,
+        OpCode::Push,  OpCode::Choose2, OpCode::Choose2, OpCode::Noop,
+        OpCode::Noop,  OpCode::Noop,    OpCode::Noop,    OpCode::Noop,
+        OpCode::Noop,  OpCode::Noop,    OpCode::Noop,    OpCode::Noop,
+        OpCode::Noop,  OpCode::Noop,    OpCode::Noop,
+    ], &[3, 4]);
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[5, 6, 1, 0, 7, 8, 0, 0]);
+    let num_outputs = 8;
+
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(outputs, [7, 8, 0, 0, 0, 0, 0, 0]);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+
+    // CSWAP2
+    let program = build_program(vec![
+        OpCode::Begin,  OpCode::CSwap2,  OpCode::Pad2,   OpCode::Swap4,
+        OpCode::CSwap2, OpCode::Noop,    OpCode::Noop,   OpCode::Noop,
+        OpCode::Noop,   OpCode::Noop,    OpCode::Noop,   OpCode::Noop,
+        OpCode::Noop,   OpCode::Noop,    OpCode::Noop,
+    ], &[]);
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[3, 4, 1, 2, 1, 0, 5, 6]);
+    let num_outputs = 8;
+
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(outputs, [3, 4, 5, 6, 1, 2, 0, 0]);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+#[should_panic(expected = "CHOOSE on a non-binary condition at step 2")]
+fn selection_operations_panic() {
+    let program = build_program(vec![
+        OpCode::Begin, OpCode::Choose, OpCode::Choose, OpCode::Noop,
+        OpCode::Noop,  OpCode::Noop,   OpCode::Noop,   OpCode::Noop,
+        OpCode::Noop,  OpCode::Noop,   OpCode::Noop,   OpCode::Noop,
+        OpCode::Noop,  OpCode::Noop,   OpCode::Noop,
+    ], &[]);
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[3, 4, 2, 5, 0, 6, 7, 8]);
+    let num_outputs = 8;
+
+    super::execute(&program, &inputs, num_outputs, &options);
+}
+
+#[test]
+fn math_operations() {
+    let program = build_program(vec![
+        OpCode::Begin, OpCode::Add,  OpCode::Mul,  OpCode::Inv,
+        OpCode::Neg,   OpCode::Swap, OpCode::Not,  OpCode::Noop, 
+        OpCode::Noop,  OpCode::Noop, OpCode::Noop, OpCode::Noop, 
+        OpCode::Noop,  OpCode::Noop, OpCode::Noop, 
+    ], &[]);
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[7, 6, 5, 0, 2, 3]);
+    let num_outputs = 2;
+
+    let expected_result = vec![field::ONE, field::neg(field::inv(65))];
+
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(expected_result, outputs);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+fn exp_operation() {
+    let program = assembly::compile("begin exp.32 end").unwrap();
+
+    let base = field::rand();
+    let exponent = field::rand() >> 96;
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[exponent, base, 7]);
+    let num_outputs = 2;
+
+    let expected_result = vec![field::exp(base, exponent), 7];
+
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(expected_result, outputs);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+#[should_panic]
+fn exp_operation_panic() {
+    // exponent does not fit into 8 bits
+    let program = assembly::compile("begin exp.8 end").unwrap();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[256, 3]);
+    let num_outputs = 1;
+
+    super::execute(&program, &inputs, num_outputs, &options);
+}
+
+#[test]
+fn bool_operations() {
+    let program = build_program(vec![
+        OpCode::Begin, OpCode::Not,  OpCode::Or,   OpCode::Or,
+        OpCode::And,   OpCode::And,  OpCode::Not,  OpCode::Noop,
+        OpCode::Noop,  OpCode::Noop, OpCode::Noop, OpCode::Noop,
+        OpCode::Noop,  OpCode::Noop, OpCode::Noop,
+    ], &[]);
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[1, 0, 1, 1, 0]);
+    let num_outputs = 1;
+
+    let expected_result = vec![field::ONE];
+
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(expected_result, outputs);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+fn hash_operations() {
+    // single hash
+    let program = build_program(vec![
+        OpCode::Begin, OpCode::RescR, OpCode::Drop,  OpCode::Drop,
+        OpCode::Drop,  OpCode::Drop,  OpCode::Noop,  OpCode::Noop,
         OpCode::Noop,  OpCode::Noop,  OpCode::Noop,  OpCode::Noop,
-        OpCode::Swap,  OpCode::Swap2, OpCode::Swap4, OpCode::Roll4,
-        OpCode::Roll8, OpCode::Dup,   OpCode::Add,   OpCode::Pad2,
-        OpCode::Push,  OpCode::Swap4, OpCode::Drop4, OpCode::Dup2,
-        OpCode::Swap4, OpCode::Add,   OpCode::Add,   OpCode::Dup4,
-        OpCode::Push,  OpCode::Add,   OpCode::Add,   OpCode::Add,
-        OpCode::Add,   OpCode::Noop,  OpCode::Noop,
-    ], &[11, 12]);
+        OpCode::Noop,  OpCode::Noop,  OpCode::Noop
+    ], &[]);
+
+    let value = [1, 2, 3, 4];
+    let mut expected_hash = hasher::digest(&value);
+    expected_hash.reverse();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[0, 0, 4, 3, 2, 1]);
+    let num_outputs = 2;
+
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(expected_hash, outputs);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+
+    // double hash
+    let program = build_program(vec![
+        OpCode::Begin, OpCode::RescR, OpCode::Drop4, OpCode::Pad2,
+        OpCode::Dup2,  OpCode::RescR, OpCode::Drop4, OpCode::Noop,
+        OpCode::Noop,  OpCode::Noop,  OpCode::Noop,  OpCode::Noop,
+        OpCode::Noop,  OpCode::Noop,  OpCode::Noop
+    ], &[]);
+
+    let value = [1, 2, 3, 4];
+    let mut expected_hash = hasher::digest(&value);
+    expected_hash = hasher::digest(&expected_hash);
+    expected_hash.reverse();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[0, 0, 4, 3, 2, 1]);
+    let num_outputs = 2;
+
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(expected_hash, outputs);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+fn hash_permutations() {
+    // each permutation is a single RESCR operation regardless of the preceding instructions
+    let program = assembly::compile("begin push.7 add hashr.2 end").unwrap();
+
+    let mut expected = vec![8, 2, 3, 4, 5, 6];
+    for _ in 0..2 {
+        for i in 0..10 { hasher::apply_round(&mut expected, i); }
+    }
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[1, 2, 3, 4, 5, 6]);
+    let num_outputs = 6;
+
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(expected, outputs);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+fn sponge_operations() {
+    // hash 6 values: one full block followed by a padded block with the remaining 2 values
+    let program = assembly::compile("begin pad.6 push.1 push.2 push.3 push.4 absorb push.5 push.6 squeeze.2 end").unwrap();
+
+    let mut state = vec![field::ZERO; 6];
+    for block in [[4, 3, 2, 1], [0, 1, 6, 5]].iter() {
+        for i in 0..4 { state[i] = field::add(state[i], block[i]); }
+        for i in 0..10 { hasher::apply_round(&mut state, i); }
+    }
+    let expected = state[..2].to_vec();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::none();
+    let num_outputs = 2;
+
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(expected, outputs);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+
+    // inputs of different lengths are padded to different blocks
+    let program1 = assembly::compile("begin pad.6 push.1 push.2 push.3 push.4 absorb squeeze end").unwrap();
+    let program2 = assembly::compile("begin pad.6 push.1 push.2 push.3 squeeze.3 end").unwrap();
+    let (outputs1, _) = super::execute(&program1, &inputs, num_outputs, &options);
+    let (outputs2, _) = super::execute(&program2, &inputs, num_outputs, &options);
+    assert_ne!(outputs1, outputs2);
+}
+
+#[test]
+fn random_operations() {
+    // the generator is seeded with 7 in the capacity; every draw permutes the state
+    let program = assembly::compile("begin push.7 pad.5 random.2 drop.2 random end").unwrap();
+
+    let mut state = vec![0, 0, 0, 0, 0, 7];
+    for _ in 0..2 {
+        for i in 0..10 { hasher::apply_round(&mut state, i); }
+    }
+    let expected = vec![state[0], state[0], state[1]];
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::none();
+    let num_outputs = 3;
+
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(expected, outputs);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+fn merkle_update_operation() {
+    let program = assembly::compile("begin mrupdate.4 end").unwrap();
+
+    // build a tree with 8 leaves and update the leaf at index 5
+    let mut leaves = Vec::new();
+    for i in 0..8 {
+        leaves.push([i * 2 + 1, i * 2 + 2]);
+    }
+    let index = 5;
+    let old_leaf = leaves[index];
+    let new_leaf = [field::rand(), field::rand()];
+
+    let (old_root, path) = compute_merkle_root(&leaves, index);
+    leaves[index] = new_leaf;
+    let (new_root, _) = compute_merkle_root(&leaves, index);
+
+    // nodes of the authentication path are split across tapes A and B
+    let tape_a: Vec<u128> = path.iter().map(|node| node[0]).collect();
+    let tape_b: Vec<u128> = path.iter().map(|node| node[1]).collect();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::new(
+        &[new_leaf[1], new_leaf[0], old_leaf[1], old_leaf[0], index as u128],
+        &tape_a, &tape_b);
+    let num_outputs = 4;
 
+    let expected_result = vec![new_root[1], new_root[0], old_root[1], old_root[0]];
+
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(expected_result, outputs);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+fn read_operations() {
+    let program = build_program(vec![
+        OpCode::Begin, OpCode::Read, OpCode::Read2, OpCode::Noop,
+        OpCode::Noop, OpCode::Noop,  OpCode::Noop,  OpCode::Noop,
+        OpCode::Push, OpCode::Noop,  OpCode::Noop,  OpCode::Noop,
+        OpCode::Noop, OpCode::Noop,  OpCode::Noop,
+    ], &[5]);
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::new(&[1], &[2, 3], &[4]);
+    let num_outputs = 5;
+
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(vec![5, 4, 3, 2, 1], outputs);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+}
+
+#[test]
+fn execute_with_limit() {
+    // the loop never terminates because the condition is always set to 1
+    let program = assembly::compile("begin push.1 while.true push.1 end end").unwrap();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::none();
+
+    let result = super::execute_with_limit(&program, &inputs, 1, &options, 1000);
+    match result {
+        Err(err) => {
+            assert!(matches!(err, super::ExecutionError::ResourceExceeded(1000, _)));
+            assert!(err.cycles() >= 1000);
+        },
+        Ok(_) => panic!("execution should have exceeded the limit")
+    }
+
+    let result = super::run(&program, &inputs, 1, 1000);
+    assert!(result.is_err());
+
+    // a program which terminates within the limit
+    let program = assembly::compile("begin push.3 push.5 add end").unwrap();
+    assert_eq!(Ok(vec![8, 0]), super::run(&program, &inputs, 2, 1000));
+    assert!(super::execute_with_limit(&program, &inputs, 2, &options, 1000).is_ok());
+}
+
+#[test]
+fn stack_overflow() {
+    // every DUP4 adds 4 items to the stack, and so, the 8th DUP4 overflows it
+    let program = assembly::compile("begin repeat.8 dup.4 end end").unwrap();
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[1, 2, 3, 4]);
+
+    let err = match super::execute_with_limit(&program, &inputs, 1, &options, 1000) {
+        Err(err) => err,
+        Ok(_) => panic!("execution should have overflowed the stack")
+    };
+    match &err {
+        super::ExecutionError::StackOverflow(step, op_code, history) => {
+            assert_eq!(OpCode::Dup4, *op_code);
+            let depths: Vec<usize> = history.iter().map(|&(_, depth)| depth).collect();
+            assert_eq!(vec![4, 8, 12, 16, 20, 24, 28, 32], depths);
+            assert!(history.windows(2).all(|pair| pair[0].0 < pair[1].0));
+            assert!(history[7].0 < *step);
+            assert_eq!(*step, err.cycles());
+        },
+        err => panic!("unexpected error: {}", err)
+    }
+    assert!(err.to_string().contains("stack overflow at step"), "{}", err);
+
+    // the interpreter reports the same error
+    assert_eq!(Err(err), super::run(&program, &inputs, 1, 1000));
+
+    // a program which stays within the limit is not affected
+    let program = assembly::compile("begin repeat.7 dup.4 end end").unwrap();
+    assert!(super::run(&program, &inputs, 1, 1000).is_ok());
+}
+
+#[test]
+fn program_builders() {
+    use crate::builders;
+
+    let (program, inputs) = builders::fibonacci(16);
+    assert_eq!(Ok(vec![987]), super::run(&program, &inputs, 1, super::DEFAULT_MAX_CYCLES));
+
+    let (program, inputs) = builders::collatz(27);
+    assert_eq!(Ok(vec![111]), super::run(&program, &inputs, 1, super::DEFAULT_MAX_CYCLES));
+
+    let (program, inputs) = builders::hash_chain(2);
+    let mut digest = hasher::digest(&[0, 1]);
+    digest.reverse();
+    digest = hasher::digest(&[digest[1], digest[0]]);
+    digest.reverse();
+    assert_eq!(Ok(digest), super::run(&program, &inputs, 2, super::DEFAULT_MAX_CYCLES));
+
+    // the value of a range proof is not a part of the public inputs
+    let options = ProofOptions::default();
+    let (program, inputs) = builders::range_proof(1 << 20, 21);
+    let (outputs, proof) = super::execute(&program, &inputs, 0, &options);
+    assert_eq!(Ok(true), super::verify(program.hash(), &[], &outputs, &proof));
+}
+
+#[test]
+#[should_panic(expected = "ASSERT failed")]
+fn program_builders_range_proof_fail() {
+    let (program, inputs) = crate::builders::range_proof(1 << 20, 20);
+    super::execute(&program, &inputs, 0, &ProofOptions::default());
+}
+
+#[test]
+fn bench_workloads() {
+    use crate::bench::{ self, Workload };
+
+    // expected outputs of the workloads match the outputs of the programs
+    let workloads = [Workload::fibonacci(4), Workload::merkle_path(5), Workload::hash_chain(3)];
+    for workload in workloads.iter() {
+        let outputs = super::run(&workload.program, &workload.inputs, workload.num_outputs, super::DEFAULT_MAX_CYCLES).unwrap();
+        assert_eq!(workload.expected_outputs, outputs, "{}", workload.name);
+    }
+    assert_eq!(vec![987], Workload::fibonacci(4).expected_outputs);
+
+    let options = ProofOptions::default();
+    let metrics = bench::measure(&workloads[1], &options);
+    assert_eq!("merkle_path_5", metrics.name);
+    assert!(metrics.trace_length.is_power_of_two());
+    assert!(metrics.proof_size > 0 && metrics.prover_memory > 0);
+}
+
+#[test]
+fn execute_with_stats() {
+    let program = assembly::compile("begin push.3 push.5 add end").unwrap();
     let options = ProofOptions::default();
-    let inputs = ProgramInputs::from_public(&[7, 6, 5, 4, 3, 2, 1, 0]);
-    let num_outputs = 8;
+    let inputs = ProgramInputs::none();
 
-    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
-    assert_eq!(outputs, [46, 19, 4, 11, 0, 11, 0, 6]);
+    let (outputs, proof, stats) = super::execute_with_stats(&program, &inputs, 1, &options);
+    assert_eq!(vec![8], outputs);
+    assert_eq!(Ok(true), super::verify(program.hash(), &[], &outputs, &proof));
 
-    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
-    assert_eq!(Ok(true), result);
+    // every phase allocates memory, and all of it is held at the peak
+    let phases = [stats.trace_memory, stats.lde_memory, stats.commitment_memory, stats.constraint_memory, stats.fri_memory];
+    assert!(phases.iter().all(|&memory| memory > 0), "{:?}", stats);
+    assert_eq!(stats.peak_memory, phases.iter().sum::<usize>());
+
+    // extending the trace to a larger domain requires more memory
+    let options = ProofOptions::new(64, 64, 0, hash::blake3);
+    let (_, _, larger) = super::execute_with_stats(&program, &inputs, 1, &options);
+    assert!(larger.lde_memory > stats.lde_memory);
+    assert!(larger.peak_memory > stats.peak_memory);
 }
 
 #[test]
-fn conditional_operations() {
-    // CHOOSE
-    let program = build_program(vec![
-        OpCode::Begin, OpCode::Choose,  OpCode::Choose, OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,    OpCode::Noop, OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,    OpCode::Noop, OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,    OpCode::Noop,
-    ], &[]);
+fn execute_with_options() {
+    let program = assembly::compile("begin push.3 push.5 add end").unwrap();
+    let inputs = ProgramInputs::none();
+    let options = [
+        ProofOptions::new(32, 48, 0, hash::blake3),
+        ProofOptions::new(32, 24, 8, hash::blake3),
+        ProofOptions::new(32, 32, 0, hash::blake3).with_remainder_length(16),
+    ];
+
+    // proofs completed from the same commitments are the same as proofs generated separately
+    let (outputs, proofs) = super::execute_with_options(&program, &inputs, 1, &options);
+    assert_eq!(vec![8], outputs);
+    for (options, proof) in options.iter().zip(proofs.iter()) {
+        let (_, expected) = super::execute(&program, &inputs, 1, options);
+        assert_eq!(expected.to_bytes(), proof.to_bytes());
+        assert_eq!(Ok(true), super::verify(program.hash(), &[], &outputs, proof));
+    }
+    assert_eq!(proofs[0].trace_root(), proofs[1].trace_root());
+}
+
+#[test]
+#[should_panic(expected = "all proof options must define the same extension factor")]
+fn execute_with_options_mismatch() {
+    let program = assembly::compile("begin push.3 push.5 add end").unwrap();
+    let options = [ProofOptions::new(32, 48, 0, hash::blake3), ProofOptions::new(64, 48, 0, hash::blake3)];
+    super::execute_with_options(&program, &ProgramInputs::none(), 1, &options);
+}
+
+#[test]
+fn checkpoint_resume() {
+    // value 4 supplied by the first host call is still on tape A when the checkpoint is captured,
+    // and the second host call is made after execution is resumed
+    let program = assembly::compile("
+        begin
+            read.host.2 push.1 add push.2 mul push.3 add push.4 mul push.5 add push.6 mul
+            read add read.host.1 mul
+        end").unwrap();
+    let mut host = |id: u32, stack: &[u128]| -> Vec<u128> {
+        return match id {
+            1 => vec![stack[0]],
+            _ => vec![3, 4],
+        };
+    };
 
     let options = ProofOptions::default();
-    let inputs = ProgramInputs::from_public(&[3, 4, 1, 5, 0, 6, 7, 8]);
-    let num_outputs = 8;
+    let inputs = ProgramInputs::from_public(&[5]);
+    let (expected_outputs, _, _) = super::execute_with_host(&program, &inputs, &mut host, 2, &options);
 
-    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
-    assert_eq!(outputs, [5, 6, 7, 8, 0, 0, 0, 0]);
+    let checkpoint = super::checkpoint(&program, &inputs, Some(&mut host), 20, usize::MAX).unwrap().unwrap();
+    assert_eq!(32, checkpoint.step());
+    assert_eq!(&[4], &checkpoint.stack_state().tape_a[..]);
+
+    // execution can be resumed from a de-serialized checkpoint
+    let checkpoint: super::Checkpoint = serde_json::from_str(&serde_json::to_string(&checkpoint).unwrap()).unwrap();
+    let outputs = super::resume(&program, &checkpoint, Some(&mut host), 2, usize::MAX).unwrap();
+    assert_eq!(expected_outputs, outputs);
+
+    // no checkpoint is captured if the program completes before the requested step
+    assert_eq!(None, super::checkpoint(&program, &inputs, Some(&mut host), 1000, usize::MAX).unwrap());
+}
+
+#[test]
+fn procedure_calls() {
+    let mut linker = assembly::Linker::new();
+    let math = linker.compile_module("math", "proc.square dup mul end proc.quad call.square call.square end").unwrap();
+    linker.add_module(math).unwrap();
+    let program = linker.link("
+        begin
+            push.2 call.math::quad push.1 add
+            push.1 while.true push.3 call.math::square add push.0 end
+        end").unwrap();
+
+    // calling procedures instead of inlining them does not change the program hash
+    let inlined = linker.link("
+        begin
+            push.2 exec.math::quad push.1 add
+            push.1 while.true push.3 exec.math::square add push.0 end
+        end").unwrap();
+    assert_eq!(inlined.hash(), program.hash());
+
+    // every call and return is checked by transition constraints
+    let inputs = ProgramInputs::none();
+    let trace = testing::ExecutionTrace::new(&program, &inputs);
+    assert_eq!(Ok(()), testing::check_transitions(&trace));
+
+    let options = ProofOptions::default();
+    let (outputs, proof) = super::execute(&program, &inputs, 1, &options);
+    assert_eq!(vec![26], outputs);
+    assert_eq!(2, proof.call_depth());
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
     assert_eq!(Ok(true), result);
 
-    // CHOOSE2
-    let program = build_program(vec![
-        OpCode::Begin, OpCode::Noop,    OpCode::Noop,    OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,    OpCode::Noop,    OpCode::Noop,
-        OpCode::Push,  OpCode::Noop,    OpCode::Noop,    OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,    OpCode::Noop,    OpCode::Noop,
-        OpCode::Push,  OpCode::Choose2, OpCode::Choose2, OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,    OpCode::Noop,    OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,    OpCode::Noop,    OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,    OpCode::Noop,
-    ], &[3, 4]);
+    // the inlined program still calls square from the body of quad
+    let (_, proof) = super::execute(&inlined, &inputs, 1, &options);
+    assert_eq!(1, proof.call_depth());
+
+    // programs which do not call procedures have no call stack registers
+    let program = assembly::compile("begin push.2 push.3 add end").unwrap();
+    let (_, proof) = super::execute(&program, &inputs, 1, &options);
+    assert_eq!(0, proof.call_depth());
+}
 
+#[test]
+fn stdlib_u256() {
+    let linker = assembly::Linker::with_stdlib();
     let options = ProofOptions::default();
-    let inputs = ProgramInputs::from_public(&[5, 6, 1, 0, 7, 8, 0, 0]);
-    let num_outputs = 8;
+    let inputs = ProgramInputs::none();
+    let num_outputs = 4;
 
+    let a = [u64::MAX, 3, u64::MAX, 1 << 63];
+    let b = [1, u64::MAX, 7, 1 << 63];
+
+    // push limbs of b and then limbs of a so that the least significant limb of a is at the top
+    let operands: Vec<String> = b.iter().rev().chain(a.iter().rev()).map(|limb| format!("push.{}", limb)).collect();
+    let operands = operands.join(" ");
+
+    let program = linker.link(&format!("begin {} exec.u256::add end", operands)).unwrap();
     let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
-    assert_eq!(outputs, [7, 8, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(add_u256(a, b), outputs);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
     assert_eq!(Ok(true), result);
 
-    // CSWAP2
-    let program = build_program(vec![
-        OpCode::Begin,  OpCode::CSwap2,  OpCode::Pad2,   OpCode::Swap4,
-        OpCode::CSwap2, OpCode::Noop,    OpCode::Noop,   OpCode::Noop,
-        OpCode::Noop,   OpCode::Noop,    OpCode::Noop,   OpCode::Noop,
-        OpCode::Noop,   OpCode::Noop,    OpCode::Noop,
-    ], &[]);
+    let program = linker.link(&format!("begin {} exec.u256::sub end", operands)).unwrap();
+    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(sub_u256(a, b), outputs);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+
+    // subtraction of a larger value wraps around, and addition wraps back
+    let program = linker.link("begin pushw.0.0.0.5 pushw.0.0.0.2 pushw.0.0.0.1 exec.u256::sub exec.u256::add end").unwrap();
+    let (outputs, _) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(vec![4, 0, 0, 0], outputs);
+}
 
+#[test]
+#[should_panic(expected = "ASSERT failed")]
+fn stdlib_u256_fail() {
+    // limbs which do not fit into 64 bits are rejected
+    let linker = assembly::Linker::with_stdlib();
+    let program = linker.link(&format!("begin pushw.0.0.0.1 pushw.0.0.1.{} exec.u256::add end", (1u128 << 64) + 1)).unwrap();
+    super::execute(&program, &ProgramInputs::none(), 4, &ProofOptions::default());
+}
+
+#[test]
+fn stdlib_u256_mul_mod() {
+    let linker = assembly::Linker::with_stdlib();
     let options = ProofOptions::default();
-    let inputs = ProgramInputs::from_public(&[3, 4, 1, 2, 1, 0, 5, 6]);
-    let num_outputs = 8;
+    let inputs = ProgramInputs::none();
+    let num_outputs = 4;
 
+    let a = [u64::MAX, 3, u64::MAX, 1 << 63];
+    let b = [1, u64::MAX, 7, 1 << 63];
+    let operands = |a: [u64; 4], b: [u64; 4]| -> String {
+        let operands: Vec<String> = b.iter().rev().chain(a.iter().rev()).map(|limb| format!("push.{}", limb)).collect();
+        return operands.join(" ");
+    };
+
+    // multiplication wraps around 2^256
+    let program = linker.link(&format!("begin {} exec.u256::mul end", operands(a, b))).unwrap();
     let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
-    assert_eq!(outputs, [3, 4, 5, 6, 1, 2, 0, 0]);
+    assert_eq!(mul_u256(a, b), outputs);
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
     assert_eq!(Ok(true), result);
+
+    // exact multiplication gives the same result when the product fits into 256 bits
+    let (c, d) = ([u64::MAX, u64::MAX, 5, 0], [u64::MAX, 1 << 40, 0, 0]);
+    let program = linker.link(&format!("begin {} exec.u256::mul_exact end", operands(c, d))).unwrap();
+    let (outputs, _) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(mul_u256(c, d), outputs);
+
+    // modular reduction by moduli of different sizes
+    for m in [b, [u64::MAX, u64::MAX, 0, 0], [3, 0, 0, 0], [0, 0, 0, 1], a] {
+        let program = linker.link(&format!("begin {} exec.u256::mod end", operands(a, m))).unwrap();
+        let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
+        assert_eq!(mod_u256(a, m), outputs);
+
+        let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+        assert_eq!(Ok(true), result);
+    }
+
+    // a dividend smaller than the modulus is left as is
+    let program = linker.link(&format!("begin {} exec.u256::mod end", operands(c, a))).unwrap();
+    let (outputs, _) = super::execute(&program, &inputs, num_outputs, &options);
+    assert_eq!(c.iter().map(|&limb| limb as u128).collect::<Vec<u128>>(), outputs);
+
+    // the interpreter computes quotients of 256-bit integers in the same way
+    assert_eq!(Ok(()), testing::check_interpreter(&program, &inputs));
 }
 
 #[test]
-#[should_panic(expected = "CHOOSE on a non-binary condition at step 2")]
-fn selection_operations_panic() {
-    let program = build_program(vec![
-        OpCode::Begin, OpCode::Choose, OpCode::Choose, OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,   OpCode::Noop,   OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,   OpCode::Noop,   OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,   OpCode::Noop,
-    ], &[]);
+#[should_panic(expected = "ASSERTEQ failed")]
+fn stdlib_u256_mul_overflow() {
+    let linker = assembly::Linker::with_stdlib();
+    let program = linker.link("begin pushw.0.0.1.0 pushw.1.0.0.0 exec.u256::mul_exact end").unwrap();
+    super::execute(&program, &ProgramInputs::none(), 4, &ProofOptions::default());
+}
+
+#[test]
+#[should_panic(expected = "division by zero")]
+fn stdlib_u256_mod_by_zero() {
+    let linker = assembly::Linker::with_stdlib();
+    let program = linker.link("begin pushw.0.0.0.0 pushw.0.0.0.5 exec.u256::mod end").unwrap();
+    super::execute(&program, &ProgramInputs::none(), 4, &ProofOptions::default());
+}
 
+#[test]
+fn stdlib_fixed() {
+    let linker = assembly::Linker::with_stdlib();
     let options = ProofOptions::default();
-    let inputs = ProgramInputs::from_public(&[3, 4, 2, 5, 0, 6, 7, 8]);
-    let num_outputs = 8;
+    let inputs = ProgramInputs::none();
+    let one = 1u128 << 32;
 
-    super::execute(&program, &inputs, num_outputs, &options);
+    // 1.5 * 2.25 = 3.375 and 3.375 / 1.5 = 2.25
+    let program = linker.link(&format!("begin push.{} push.{} exec.fixed::mul end", 3u128 << 31, 9u128 << 30)).unwrap();
+    let (outputs, proof) = super::execute(&program, &inputs, 1, &options);
+    assert_eq!(vec![27u128 << 29], outputs);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+
+    let program = linker.link(&format!("begin push.{} push.{} exec.fixed::div end", 27u128 << 29, 3u128 << 31)).unwrap();
+    let (outputs, proof) = super::execute(&program, &inputs, 1, &options);
+    assert_eq!(vec![9u128 << 30], outputs);
+
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
+
+    // results are rounded to the nearest number: 1 / 3 is rounded down, and 2 / 3 is rounded up
+    let program = linker.link(&format!("begin push.{} push.{} exec.fixed::div end", one, 3 * one)).unwrap();
+    let (outputs, _) = super::execute(&program, &inputs, 1, &options);
+    assert_eq!(vec![one / 3], outputs);
+
+    let program = linker.link(&format!("begin push.{} push.{} exec.fixed::div end", 2 * one, 3 * one)).unwrap();
+    let (outputs, _) = super::execute(&program, &inputs, 1, &options);
+    assert_eq!(vec![2 * one / 3 + 1], outputs);
+
+    // ties are rounded up
+    let program = linker.link(&format!("begin push.3 push.{} exec.fixed::mul end", one / 2)).unwrap();
+    let (outputs, _) = super::execute(&program, &inputs, 1, &options);
+    assert_eq!(vec![2], outputs);
 }
 
 #[test]
-fn math_operations() {
-    let program = build_program(vec![
-        OpCode::Begin, OpCode::Add,  OpCode::Mul,  OpCode::Inv,
-        OpCode::Neg,   OpCode::Swap, OpCode::Not,  OpCode::Noop, 
-        OpCode::Noop,  OpCode::Noop, OpCode::Noop, OpCode::Noop, 
-        OpCode::Noop,  OpCode::Noop, OpCode::Noop, 
-    ], &[]);
+#[should_panic(expected = "ASSERTEQ failed")]
+fn stdlib_fixed_overflow() {
+    let linker = assembly::Linker::with_stdlib();
+    let program = linker.link(&format!("begin push.{} push.{} exec.fixed::mul end", 1u128 << 62, 1u128 << 34)).unwrap();
+    super::execute(&program, &ProgramInputs::none(), 1, &ProofOptions::default());
+}
 
+#[test]
+fn program_abi() {
+    let abi = ProgramAbi::new(1, 2, [1, 0], 1);
+    let program = assembly::compile("begin read add mul end").unwrap().with_abi(abi.clone());
     let options = ProofOptions::default();
-    let inputs = ProgramInputs::from_public(&[7, 6, 5, 0, 2, 3]);
-    let num_outputs = 2;
-
-    let expected_result = vec![field::ONE, field::neg(field::inv(65))];
+    let inputs = ProgramInputs::new(&[3, 5], &[4], &[]);
 
-    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
-    assert_eq!(expected_result, outputs);
+    let (outputs, proof) = super::execute(&program, &inputs, 1, &options);
+    assert_eq!(vec![35], outputs);
 
-    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    let result = super::verify_with_abi(program.hash(), &abi, &[3, 5], &outputs, &proof);
     assert_eq!(Ok(true), result);
+
+    // public inputs and outputs of the wrong shape are rejected before the proof is verified
+    let result = super::verify_with_abi(program.hash(), &abi, &[3, 5, 0], &outputs, &proof);
+    assert_eq!(Err(String::from("expected 2 public inputs, but received 3")), result);
+
+    let result = super::verify_with_abi(program.hash(), &abi, &[3, 5], &[35, 0], &proof);
+    assert_eq!(Err(String::from("expected 1 outputs, but received 2")), result);
+
+    // the ABI can be serialized together with the program hash
+    let bytes = abi.to_bytes();
+    assert_eq!(Ok(abi), ProgramAbi::from_bytes(&bytes));
+    assert!(ProgramAbi::from_bytes(&bytes[1..]).is_err());
 }
 
 #[test]
-fn bool_operations() {
-    let program = build_program(vec![
-        OpCode::Begin, OpCode::Not,  OpCode::Or,   OpCode::Or,
-        OpCode::And,   OpCode::And,  OpCode::Not,  OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop, OpCode::Noop, OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop, OpCode::Noop,
-    ], &[]);
+#[should_panic(expected = "inputs do not match program ABI: expected 1 values on input tape A, but received 2")]
+fn program_abi_mismatch() {
+    let program = assembly::compile("begin read add mul end").unwrap().with_abi(ProgramAbi::new(1, 2, [1, 0], 1));
+    let inputs = ProgramInputs::new(&[3, 5], &[4, 6], &[]);
+    super::execute(&program, &inputs, 1, &ProofOptions::default());
+}
+
+#[test]
+fn dynamic_calls() {
+    let module = assembly::Linker::new().compile_module("math", "
+        proc.square drop dup mul end
+        proc.inc drop push.1 add end").unwrap();
+    let mut host = ProcedureHost { procedures: module.procedures().iter()
+        .map(|procedure| (procedure.code_hash(), procedure.body().to_vec())).collect() };
 
+    // the same program calls different procedures depending on the hash at the top of the stack
+    let program = assembly::compile("begin dyncall push.3 mul end").unwrap();
     let options = ProofOptions::default();
-    let inputs = ProgramInputs::from_public(&[1, 0, 1, 1, 0]);
-    let num_outputs = 1;
+    for (name, expected) in [("square", 75), ("inc", 18)] {
+        let hash = module.get_procedure(name).unwrap().code_hash();
+        let inputs = ProgramInputs::from_public(&[hash, 5]);
+        let (outputs, proof, _) = super::execute_with_host(&program, &inputs, &mut host, 1, &options);
+        assert_eq!(vec![expected], outputs);
+        assert_eq!(2, proof.call_depth());
 
-    let expected_result = vec![field::ONE];
+        let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+        assert_eq!(Ok(true), result);
 
-    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
-    assert_eq!(expected_result, outputs);
+        // proof is bound to the hash of the called procedure
+        let other_hash = field::add(hash, 1);
+        let result = super::verify(program.hash(), &[other_hash, 5], &outputs, &proof);
+        assert!(result.is_err());
+    }
 
-    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
-    assert_eq!(Ok(true), result);
+    // checkpoints are not captured inside dynamic calls
+    let inputs = ProgramInputs::from_public(&[module.procedures()[0].code_hash(), 5]);
+    let checkpoint = super::checkpoint(&program, &inputs, Some(&mut host), 16, usize::MAX).unwrap().unwrap();
+    assert_eq!(48, checkpoint.step());
+    let outputs = super::resume(&program, &checkpoint, Some(&mut host), 1, usize::MAX).unwrap();
+    assert_eq!(vec![75], outputs);
 }
 
 #[test]
-fn hash_operations() {
-    // single hash
-    let program = build_program(vec![
-        OpCode::Begin, OpCode::Noop,  OpCode::Noop,  OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,  OpCode::Noop,  OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,  OpCode::Noop,  OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,  OpCode::Noop,  OpCode::Noop,
-        OpCode::RescR, OpCode::RescR, OpCode::RescR, OpCode::RescR,
-        OpCode::RescR, OpCode::RescR, OpCode::RescR, OpCode::RescR,
-        OpCode::RescR, OpCode::RescR, OpCode::Drop,  OpCode::Drop,
-        OpCode::Drop,  OpCode::Drop,  OpCode::Noop
-    ], &[]);
+#[should_panic(expected = "was not supplied by the host")]
+fn dynamic_calls_panic() {
+    let program = assembly::compile("begin dyncall end").unwrap();
+    let mut host = ProcedureHost { procedures: HashMap::new() };
+    let inputs = ProgramInputs::from_public(&[7]);
+    super::execute_with_host(&program, &inputs, &mut host, 1, &ProofOptions::default());
+}
 
-    let value = [1, 2, 3, 4];
-    let mut expected_hash = hasher::digest(&value);
-    expected_hash.reverse();
+#[test]
+fn host_operations() {
+    let program = assembly::compile("begin read.host.1 mul read.host.2 read add end").unwrap();
+
+    // the host supplies an inverse of the top stack item for call 1, and two values for call 2
+    let mut host = |id: u32, stack: &[u128]| -> Vec<u128> {
+        return match id {
+            1 => vec![field::inv(stack[0])],
+            _ => vec![3, 4],
+        };
+    };
 
     let options = ProofOptions::default();
-    let inputs = ProgramInputs::from_public(&[0, 0, 4, 3, 2, 1]);
-    let num_outputs = 2;
+    let inputs = ProgramInputs::from_public(&[field::rand(), 5]);
+    let num_outputs = 3;
 
-    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
-    assert_eq!(expected_hash, outputs);
+    let (outputs, proof, advice_log) = super::execute_with_host(&program, &inputs, &mut host, num_outputs, &options);
+    assert_eq!(vec![7, 1, 5], outputs);
+    assert_eq!(2, advice_log.len());
+    assert_eq!((2, &[3, 4][..]), advice_log.get_entry(1));
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
     assert_eq!(Ok(true), result);
 
-    // double hash
-    let program = build_program(vec![
-        OpCode::Begin, OpCode::Noop,  OpCode::Noop,  OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,  OpCode::Noop,  OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,  OpCode::Noop,  OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,  OpCode::Noop,  OpCode::Noop,
-        OpCode::RescR, OpCode::RescR, OpCode::RescR, OpCode::RescR,
-        OpCode::RescR, OpCode::RescR, OpCode::RescR, OpCode::RescR,
-        OpCode::RescR, OpCode::RescR, OpCode::Drop4, OpCode::Noop,
-        OpCode::Pad2,  OpCode::Dup2,  OpCode::Noop,  OpCode::Noop,
-        OpCode::RescR, OpCode::RescR, OpCode::RescR, OpCode::RescR,
-        OpCode::RescR, OpCode::RescR, OpCode::RescR, OpCode::RescR,
-        OpCode::RescR, OpCode::RescR, OpCode::Drop4, OpCode::Noop,
-        OpCode::Noop,  OpCode::Noop,  OpCode::Noop
-    ], &[]);
-
-    let value = [1, 2, 3, 4];
-    let mut expected_hash = hasher::digest(&value);
-    expected_hash = hasher::digest(&expected_hash);
-    expected_hash.reverse();
+    // re-execute the program using the recorded advice
+    let (replay_outputs, proof) = super::execute_from_log(&program, &inputs, &advice_log, num_outputs, &options);
+    assert_eq!(outputs, replay_outputs);
 
-    let options = ProofOptions::default();
-    let inputs = ProgramInputs::from_public(&[0, 0, 4, 3, 2, 1]);
-    let num_outputs = 2;
+    let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
+    assert_eq!(Ok(true), result);
 
-    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
-    assert_eq!(expected_hash, outputs);
+    // re-execute the program using advice streamed from the serialized log
+    let mut bytes = Vec::new();
+    advice_log.write_to(&mut bytes).unwrap();
+    let mut stream = super::AdviceStream::new(&bytes[..]);
+    let (stream_outputs, proof) = super::execute_without_log(&program, &inputs, &mut stream, num_outputs, &options);
+    assert_eq!(outputs, stream_outputs);
+    assert_eq!(2, stream.records_read());
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
     assert_eq!(Ok(true), result);
 }
 
 #[test]
-fn read_operations() {
-    let program = build_program(vec![
-        OpCode::Begin, OpCode::Read, OpCode::Read2, OpCode::Noop,
-        OpCode::Noop, OpCode::Noop,  OpCode::Noop,  OpCode::Noop,
-        OpCode::Push, OpCode::Noop,  OpCode::Noop,  OpCode::Noop,
-        OpCode::Noop, OpCode::Noop,  OpCode::Noop,
-    ], &[5]);
+fn host_operations_invalid_advice() {
+    // values which are not valid field elements are rejected whether they are supplied by a host
+    // call or read from a streamed tape
+    let program = assembly::compile("begin push.1 read.host.1 add end").unwrap();
+    let mut host = |_: u32, _: &[u128]| -> Vec<u128> { vec![field::MODULUS] };
+    let result = super::checkpoint(&program, &ProgramInputs::none(), Some(&mut host), 1000, usize::MAX);
+    match result {
+        Err(super::ExecutionError::InvalidAdvice(_, value)) => assert_eq!(field::MODULUS, value),
+        _ => panic!("execution should have failed"),
+    }
+
+    struct TapeHost;
+    impl super::HostInterface for TapeHost {
+        fn get_advice(&mut self, _id: u32, _stack: &[u128]) -> Vec<u128> {
+            return Vec::new();
+        }
+
+        fn read_tape(&mut self, tape: usize) -> Option<u128> {
+            return Some(if tape == 0 { 2 } else { u128::MAX });
+        }
+    }
 
+    let program = assembly::compile("begin read.a read.ab add add end").unwrap();
+    let result = super::checkpoint(&program, &ProgramInputs::none(), Some(&mut TapeHost), 1000, usize::MAX);
+    match result {
+        Err(super::ExecutionError::InvalidAdvice(_, value)) => assert_eq!(u128::MAX, value),
+        _ => panic!("execution should have failed"),
+    }
+}
+
+#[test]
+fn host_operations_tape_stream() {
+    // tape values which are not supplied via inputs are read from the stream
+    let program = assembly::compile("begin read.a read.a read.ab add add add end").unwrap();
     let options = ProofOptions::default();
-    let inputs = ProgramInputs::new(&[1], &[2, 3], &[4]);
-    let num_outputs = 5;
+    let inputs = ProgramInputs::new(&[], &[1], &[]);
 
-    let (outputs, proof) = super::execute(&program, &inputs, num_outputs, &options);
-    assert_eq!(vec![5, 4, 3, 2, 1], outputs);
+    let tape_a: Vec<u8> = [2u128, 3].iter().flat_map(|v| v.to_le_bytes().to_vec()).collect();
+    let tape_b: Vec<u8> = 4u128.to_le_bytes().to_vec();
+    let mut stream = super::AdviceStream::with_tapes(&[][..], &tape_a[..], &tape_b[..]);
+    let (outputs, proof, advice_log) = super::execute_with_host(&program, &inputs, &mut stream, 1, &options);
+    assert_eq!(vec![10], outputs);
+    assert_eq!(&[2, 3], advice_log.get_tape(0));
+    assert_eq!(&[4], advice_log.get_tape(1));
 
     let result = super::verify(program.hash(), inputs.get_public_inputs(), &outputs, &proof);
     assert_eq!(Ok(true), result);
+
+    // recorded tape values are replayed from the log and can be written back into a stream
+    let (replay_outputs, _) = super::execute_from_log(&program, &inputs, &advice_log, 1, &options);
+    assert_eq!(outputs, replay_outputs);
+
+    let mut bytes = Vec::new();
+    advice_log.write_tape_to(0, &mut bytes).unwrap();
+    assert_eq!(tape_a, bytes);
+}
+
+#[test]
+#[should_panic(expected = "attempt to read from empty tape A")]
+fn host_operations_tape_stream_fail() {
+    // the stream ends before the program reads all values it needs
+    let program = assembly::compile("begin read.a read.a add end").unwrap();
+    let inputs = ProgramInputs::none();
+
+    let tape_a: Vec<u8> = 2u128.to_le_bytes().to_vec();
+    let mut stream = super::AdviceStream::with_tapes(&[][..], &tape_a[..], &[][..]);
+    super::execute_without_log(&program, &inputs, &mut stream, 1, &ProofOptions::default());
+}
+
+#[test]
+#[should_panic]
+fn host_operations_replay_panic() {
+    // recorded advice log does not have enough entries for the program
+    let program = assembly::compile("begin read.host.1 mul end").unwrap();
+    let mut host = |_id: u32, _stack: &[u128]| -> Vec<u128> { vec![1] };
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[3]);
+    let (_, _, advice_log) = super::execute_with_host(&program, &inputs, &mut host, 1, &options);
+
+    let program = assembly::compile("begin read.host.1 mul read.host.1 mul end").unwrap();
+    super::execute_from_log(&program, &inputs, &advice_log, 1, &options);
+}
+
+#[test]
+#[should_panic]
+fn host_operations_panic() {
+    // host calls cannot be made without a host interface
+    let program = assembly::compile("begin read.host.1 mul end").unwrap();
+
+    let options = ProofOptions::default();
+    let inputs = ProgramInputs::from_public(&[3]);
+    let num_outputs = 1;
+
+    super::execute(&program, &inputs, num_outputs, &options);
 }
 
 #[test]
@@ -314,6 +2211,76 @@ fn assert_operations() {
 
 // HELPER FUNCTIONS
 // ================================================================================================
+struct ProcedureHost {
+    procedures: HashMap<u128, Vec<ProgramBlock>>,
+}
+
+impl super::HostInterface for ProcedureHost {
+    fn get_advice(&mut self, id: u32, _stack: &[u128]) -> Vec<u128> {
+        panic!("unexpected host call {}", id);
+    }
+
+    fn get_procedure(&mut self, hash: u128) -> Option<Vec<ProgramBlock>> {
+        return self.procedures.get(&hash).cloned();
+    }
+}
+
+struct EventHost {
+    events: Vec<(u32, u128)>,
+}
+
+impl super::HostInterface for EventHost {
+    fn get_advice(&mut self, id: u32, _stack: &[u128]) -> Vec<u128> {
+        panic!("unexpected host call {}", id);
+    }
+
+    fn on_event(&mut self, id: u32, payload: u128) {
+        self.events.push((id, payload));
+    }
+}
+
+fn add_u256(a: [u64; 4], b: [u64; 4]) -> Vec<u128> {
+    let mut carry = 0u128;
+    let mut result = Vec::new();
+    for i in 0..4 {
+        let sum = a[i] as u128 + b[i] as u128 + carry;
+        result.push(sum & (u64::MAX as u128));
+        carry = sum >> 64;
+    }
+    return result;
+}
+
+fn sub_u256(a: [u64; 4], b: [u64; 4]) -> Vec<u128> {
+    let mut borrow = false;
+    let mut result = Vec::new();
+    for i in 0..4 {
+        let (diff, b1) = a[i].overflowing_sub(b[i]);
+        let (diff, b2) = diff.overflowing_sub(borrow as u64);
+        result.push(diff as u128);
+        borrow = b1 || b2;
+    }
+    return result;
+}
+
+fn mul_u256(a: [u64; 4], b: [u64; 4]) -> Vec<u128> {
+    let product = to_biguint(a) * to_biguint(b);
+    return from_biguint(product % (BigUint::from(1u8) << 256));
+}
+
+fn mod_u256(a: [u64; 4], b: [u64; 4]) -> Vec<u128> {
+    return from_biguint(to_biguint(a) % to_biguint(b));
+}
+
+fn to_biguint(value: [u64; 4]) -> BigUint {
+    return value.iter().rev().fold(BigUint::from(0u8), |acc, &limb| (acc << 64) + BigUint::from(limb));
+}
+
+fn from_biguint(value: BigUint) -> Vec<u128> {
+    let mut limbs = value.to_u64_digits();
+    limbs.resize(4, 0);
+    return limbs.into_iter().map(|limb| limb as u128).collect();
+}
+
 fn build_program(instructions: Vec<OpCode>, push_values: &[u128]) -> Program {
 
     // build hint map for PUSh operations
@@ -333,4 +2300,21 @@ fn build_program(instructions: Vec<OpCode>, push_values: &[u128]) -> Program {
 
     let root = vec![ProgramBlock::Span(Span::new(instructions, hints))];
     return Program::new(Group::new(root));
-}
\ No newline at end of file
+}
+
+/// Computes the root of a Merkle tree with the specified leaves, and returns it together with
+/// the authentication path for the leaf at the specified index.
+fn compute_merkle_root(leaves: &[[u128; 2]], index: usize) -> ([u128; 2], Vec<[u128; 2]>) {
+    let mut nodes = leaves.to_vec();
+    let mut path = Vec::new();
+    let mut index = index;
+    while nodes.len() > 1 {
+        path.push(nodes[index ^ 1]);
+        nodes = nodes.chunks(2).map(|pair| {
+            let hash = hasher::digest(&[pair[0][0], pair[0][1], pair[1][0], pair[1][1]]);
+            [hash[0], hash[1]]
+        }).collect();
+        index = index >> 1;
+    }
+    return (nodes[0], path);
+}
@@ -1,3 +1,4 @@
+use std::convert::TryInto;
 use crate::{
     math::field,
     HASH_STATE_WIDTH as STATE_WIDTH,
@@ -16,13 +17,16 @@ pub fn digest(values: &[u128]) -> Vec<u128> {
     let mut state = [field::ZERO; STATE_WIDTH];
     state[..values.len()].copy_from_slice(values);
     state.reverse();
+    permute(&mut state);
+    state.reverse();
+    return state[..DIGEST_SIZE].to_vec();
+}
 
+/// Applies a full Rescue permutation (all rounds of the hash function) to the `state`.
+pub fn permute(state: &mut [u128]) {
     for i in 0..NUM_ROUNDS {
-        apply_round(&mut state, i);
+        apply_round(state, i);
     }
-
-    state.reverse();
-    return state[..DIGEST_SIZE].to_vec();
 }
 
 pub fn apply_round(state: &mut [u128], step: usize) {
@@ -51,41 +55,36 @@ pub fn apply_sbox(state: &mut [u128]) {
     }
 }
 
+/// Computes x^INV_ALPHA for all elements of the state together (see `field::exp_many()`).
 pub fn apply_inv_sbox(state: &mut[u128]) {
-    // TODO: optimize
-    for i in 0..STATE_WIDTH {
-        state[i] = field::exp(state[i], INV_ALPHA);
-    }
+    let state: &mut [u128; STATE_WIDTH] = (&mut state[..STATE_WIDTH]).try_into().unwrap();
+    field::exp_many(state, INV_ALPHA);
 }
 
 pub fn apply_mds(state: &mut[u128]) {
-    let mut result = [field::ZERO; STATE_WIDTH];
-    let mut temp = [field::ZERO; STATE_WIDTH];
-    for i in 0..STATE_WIDTH {
-        for j in 0..STATE_WIDTH {
-            temp[j] = field::mul(MDS[i * STATE_WIDTH + j], state[j]);
-        }
-
-        for j in 0..STATE_WIDTH {
-            result[i] = field::add(result[i], temp[j]);
-        }
-    }
-    state.copy_from_slice(&result);
+    apply_matrix::<STATE_WIDTH>(&MDS, state);
 }
 
 pub fn apply_inv_mds(state: &mut[u128]) {
-    let mut result = [field::ZERO; STATE_WIDTH];
-    let mut temp = [field::ZERO; STATE_WIDTH];
-    for i in 0..STATE_WIDTH {
-        for j in 0..STATE_WIDTH {
-            temp[j] = field::mul(INV_MDS[i * STATE_WIDTH + j], state[j]);
-        }
+    apply_matrix::<STATE_WIDTH>(&INV_MDS, state);
+}
 
-        for j in 0..STATE_WIDTH {
-            result[i] = field::add(result[i], temp[j]);
+// MATRIX MULTIPLICATION
+// ------------------------------------------------------------------------------------------------
+
+/// Multiplies the first W elements of the state by the specified W x W matrix (in row-major
+/// order) one column at a time, so that every step performs W independent multiplications and
+/// additions; this is shared by Rescue permutations of all state widths.
+pub fn apply_matrix<const W: usize>(matrix: &[u128], state: &mut[u128]) {
+    debug_assert!(matrix.len() == W * W, "expected a {} x {} matrix", W, W);
+    let mut result = [field::ZERO; W];
+    for j in 0..W {
+        let s = state[j];
+        for i in 0..W {
+            result[i] = field::add(result[i], field::mul(matrix[i * W + j], s));
         }
     }
-    state.copy_from_slice(&result);
+    state[..W].copy_from_slice(&result);
 }
 
 // 128-BIT RESCUE CONSTANTS
@@ -125,4 +124,96 @@ pub const ARK: [[u128; CYCLE_LENGTH]; STATE_WIDTH * 2] = [
     [179443458614881887600494128053111694648, 108165142884901978856319583750672324489,  97063282200318501142854934314343169049, 261286087759526359216271155361018330507,  67833038363599207475373040930824843019,  56878992720628535103195481580617360771, 198852036109370286966576164360266278255, 174521831193496100673067735908873646985, 251654188127562510403516067236333482372,  48056343894932757577046683797067209079, 306942787541210815164178987028698818659, 156642260202818413362503062578539720517, 251616653853928459967283575542057535293, 188741644029927191719040650968720800409, 281428110117091114144446350524650424481,  64627937813848943279040280988334503406],
     [289278996656706117461857789813498821934, 274604860873273636237081114376077113475, 126000924558481152083098962591383883438, 129877116445533126989528570413807277693, 172066229584406173063202914726937339958, 298530663250990395227144225232608384365,  16989575615175240495557720305287640349, 102835474498154050313290986853294842906, 297928660776980173370496618733852490961,  96037481352786813748421760769380383926,   2818165229115014774032882127170013258, 293027053537479076557105009345927645442, 249369722351358137898587699909312963803, 300544292992993952360719000252205715076, 323117003802246814764810890058143344905, 243579355010018669877160932197352017974],
     [339223760157195739332845857285008200423, 208632865147351209340449219082125897333,  96675618862527967726114378655626650641, 162892536327655189685235410342890574896, 196910153233132861881308509456401645140, 281841826874183647567546019531929972702, 155276073049009029667373106803046514344, 152642017050116048509158960350000858013, 286456894851095755022390967246767421000, 215531716255970146473658338852472046173, 324452408864695917006896030536225525119, 314094406162389098987684450322979120529, 114910730596486251472791631840513265074,  81795345404219176616297063519210464031,  22603524397731600512825466576357638930,  63900149356112496372337283043133097338],
-];
\ No newline at end of file
+];
+
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ math::field, HASH_STATE_WIDTH as STATE_WIDTH };
+
+    #[test]
+    fn apply_inv_sbox() {
+        for _ in 0..32 {
+            let mut state = field::rand_vector(STATE_WIDTH);
+            let mut expected = state.clone();
+            apply_inv_sbox_scalar(&mut expected);
+            super::apply_inv_sbox(&mut state);
+            assert_eq!(expected, state);
+        }
+
+        // edge cases: 0, 1 and -1 are mapped to themselves
+        let edge_cases = vec![field::ZERO, field::ONE, field::neg(field::ONE), field::ZERO, field::ONE, field::ZERO];
+        let mut state = edge_cases.clone();
+        super::apply_inv_sbox(&mut state);
+        assert_eq!(edge_cases, state);
+
+        // inverse S-box undoes S-box
+        let original = field::rand_vector(STATE_WIDTH);
+        let mut state = original.clone();
+        super::apply_sbox(&mut state);
+        super::apply_inv_sbox(&mut state);
+        assert_eq!(original, state);
+    }
+
+    #[test]
+    fn apply_mds() {
+        for _ in 0..32 {
+            let mut state = field::rand_vector(STATE_WIDTH);
+            let mut expected = state.clone();
+            apply_mds_scalar(&mut expected);
+            super::apply_mds(&mut state);
+            assert_eq!(expected, state);
+        }
+
+        // inverse MDS undoes MDS
+        let original = field::rand_vector(STATE_WIDTH);
+        let mut state = original.clone();
+        super::apply_mds(&mut state);
+        super::apply_inv_mds(&mut state);
+        assert_eq!(original, state);
+    }
+
+    #[test]
+    fn apply_round() {
+        let original = field::rand_vector(STATE_WIDTH);
+        let mut expected = original.clone();
+        for step in 0..super::NUM_ROUNDS {
+            let ark_idx = step % super::CYCLE_LENGTH;
+            super::add_constants(&mut expected, ark_idx, 0);
+            super::apply_sbox(&mut expected);
+            apply_mds_scalar(&mut expected);
+            super::add_constants(&mut expected, ark_idx, STATE_WIDTH);
+            apply_inv_sbox_scalar(&mut expected);
+            apply_mds_scalar(&mut expected);
+        }
+
+        let mut state = original.clone();
+        for step in 0..super::NUM_ROUNDS {
+            super::apply_round(&mut state, step);
+        }
+        assert_eq!(expected, state);
+    }
+
+    // HELPER FUNCTIONS
+    // --------------------------------------------------------------------------------------------
+
+    /// Computes x^INV_ALPHA for every element of the state separately.
+    fn apply_inv_sbox_scalar(state: &mut[u128]) {
+        for i in 0..STATE_WIDTH {
+            state[i] = field::exp(state[i], super::INV_ALPHA);
+        }
+    }
+
+    /// Multiplies the state by the MDS matrix one row at a time.
+    fn apply_mds_scalar(state: &mut[u128]) {
+        let mut result = [field::ZERO; STATE_WIDTH];
+        for i in 0..STATE_WIDTH {
+            for j in 0..STATE_WIDTH {
+                result[i] = field::add(result[i], field::mul(super::MDS[i * STATE_WIDTH + j], state[j]));
+            }
+        }
+        state.copy_from_slice(&result);
+    }
+}
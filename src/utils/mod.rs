@@ -1,4 +1,4 @@
-use std::{ mem, slice, ops::Range };
+use std::{ mem, ptr, slice, ops::Range, sync::atomic };
 
 // RE-EXPORTS
 // ================================================================================================
@@ -19,6 +19,19 @@ pub fn filled_vector<T: Copy>(length: usize, capacity: usize, value: T) -> Vec<T
     return vector;
 }
 
+/// Overwrites all elements of the `vector` with zeros (default values) and clears it; elements
+/// beyond the length of the vector (but within its capacity) are overwritten as well, since they
+/// may still hold values which were removed from the vector. The writes are volatile so that
+/// they are not eliminated by the compiler when the vector is dropped right after being zeroized.
+pub fn zeroize<T: Copy + Default>(vector: &mut Vec<T>) {
+    let values = vector.as_mut_ptr();
+    for i in 0..vector.capacity() {
+        unsafe { ptr::write_volatile(values.add(i), T::default()); }
+    }
+    atomic::compiler_fence(atomic::Ordering::SeqCst);
+    vector.clear();
+}
+
 #[cfg(test)]
 pub fn remove_leading_zeros(values: &[u128]) -> Vec<u128> {
     for i in (0..values.len()).rev() {
@@ -40,6 +53,49 @@ pub fn as_bytes<T>(values: &[T]) -> &[u8] {
     return result;
 }
 
+// 256-BIT INTEGERS
+// ================================================================================================
+
+/// Divides `dividend` by `divisor` where both are 256-bit integers represented by four 64-bit
+/// limbs with the least significant limb first, and returns the quotient in the same form.
+pub fn div_u256(dividend: &[u64; 4], divisor: &[u64; 4]) -> [u64; 4] {
+    assert!(divisor.iter().any(|&limb| limb != 0), "division by zero");
+
+    // shift-subtract long division; the remainder is always smaller than the divisor, so after
+    // shifting it by one bit only the carry out of the top limb needs to be tracked
+    let mut quotient = [0u64; 4];
+    let mut remainder = [0u64; 4];
+    for i in (0..256).rev() {
+        let carry = remainder[3] >> 63;
+        for j in (1..4).rev() {
+            remainder[j] = (remainder[j] << 1) | (remainder[j - 1] >> 63);
+        }
+        remainder[0] = (remainder[0] << 1) | ((dividend[i / 64] >> (i % 64)) & 1);
+
+        if carry == 1 || !lt_u256(&remainder, divisor) {
+            let mut borrow = 0;
+            for j in 0..4 {
+                let (d, b1) = remainder[j].overflowing_sub(divisor[j]);
+                let (d, b2) = d.overflowing_sub(borrow);
+                remainder[j] = d;
+                borrow = (b1 || b2) as u64;
+            }
+            quotient[i / 64] |= 1 << (i % 64);
+        }
+    }
+
+    return quotient;
+}
+
+fn lt_u256(a: &[u64; 4], b: &[u64; 4]) -> bool {
+    for i in (0..4).rev() {
+        if a[i] != b[i] {
+            return a[i] < b[i];
+        }
+    }
+    return false;
+}
+
 // RANGE
 // ================================================================================================
 pub trait RangeSlider {
@@ -72,4 +128,35 @@ mod tests {
         ];
         assert_eq!(expected, bytes);
     }
+
+    #[test]
+    fn div_u256() {
+        // single-limb values
+        assert_eq!([3, 0, 0, 0], super::div_u256(&[10, 0, 0, 0], &[3, 0, 0, 0]));
+
+        // (2^256 - 1) / (2^64 + 1) = 2^192 - 2^128 + 2^64 - 1
+        let max = [u64::MAX; 4];
+        let expected = [u64::MAX, 0, u64::MAX, 0];
+        assert_eq!(expected, super::div_u256(&max, &[1, 1, 0, 0]));
+
+        // (2^256 - 1) / (2^255 + 1) = 1
+        assert_eq!([1, 0, 0, 0], super::div_u256(&max, &[1, 0, 0, 1 << 63]));
+
+        // dividend smaller than the divisor
+        assert_eq!([0; 4], super::div_u256(&[5, 0, 0, 0], &[0, 1, 0, 0]));
+    }
+
+    #[test]
+    fn zeroize() {
+        let mut vector: Vec<u128> = Vec::with_capacity(8);
+        vector.extend_from_slice(&[1, 2, 3, 4, 5]);
+        vector.truncate(2);
+
+        // values beyond the length of the vector are zeroized too
+        super::zeroize(&mut vector);
+        assert_eq!(0, vector.len());
+        assert_eq!(8, vector.capacity());
+        unsafe { vector.set_len(5); }
+        assert_eq!(vec![0; 5], vector);
+    }
 }
\ No newline at end of file
@@ -1,8 +1,10 @@
+use std::convert::TryInto;
 use crate::math::{ field };
 use crate::{
     BASE_CYCLE_LENGTH as NUM_ROUNDS,
     SPONGE_WIDTH as STATE_WIDTH,
 };
+use super::hasher;
 
 // ACCUMULATOR FUNCTIONS
 // ================================================================================================
@@ -41,41 +43,18 @@ pub fn apply_sbox(state: &mut [u128]) {
     }
 }
 
+/// Computes x^INV_ALPHA for all elements of the state together (see `field::exp_many()`).
 pub fn apply_inv_sbox(state: &mut[u128]) {
-    // TODO: optimize
-    for i in 0..STATE_WIDTH {
-        state[i] = field::exp(state[i], INV_ALPHA);
-    }
+    let state: &mut [u128; STATE_WIDTH] = (&mut state[..STATE_WIDTH]).try_into().unwrap();
+    field::exp_many(state, INV_ALPHA);
 }
 
 pub fn apply_mds(state: &mut[u128]) {
-    let mut result = [field::ZERO; STATE_WIDTH];
-    let mut temp = [field::ZERO; STATE_WIDTH];
-    for i in 0..STATE_WIDTH {
-        for j in 0..STATE_WIDTH {
-            temp[j] = field::mul(MDS[i * STATE_WIDTH + j], state[j]);
-        }
-
-        for j in 0..STATE_WIDTH {
-            result[i] = field::add(result[i], temp[j]);
-        }
-    }
-    state.copy_from_slice(&result);
+    hasher::apply_matrix::<STATE_WIDTH>(&MDS, state);
 }
 
 pub fn apply_inv_mds(state: &mut[u128]) {
-    let mut result = [field::ZERO; STATE_WIDTH];
-    let mut temp = [field::ZERO; STATE_WIDTH];
-    for i in 0..STATE_WIDTH {
-        for j in 0..STATE_WIDTH {
-            temp[j] = field::mul(INV_MDS[i * STATE_WIDTH + j], state[j]);
-        }
-
-        for j in 0..STATE_WIDTH {
-            result[i] = field::add(result[i], temp[j]);
-        }
-    }
-    state.copy_from_slice(&result);
+    hasher::apply_matrix::<STATE_WIDTH>(&INV_MDS, state);
 }
 
 // 128-BIT RESCUE CONSTANTS
@@ -106,4 +85,41 @@ pub const ARK: [[u128; NUM_ROUNDS]; STATE_WIDTH * 2] = [
     [326468515245013538774703881972225680443, 209040028248304238735923683513240525194, 234470815157983004947611441850027217492, 311182552853825261047305944842224924215,  25509259982013669682461356932775370545,  77086595049850596660690999278719011720,   7640791703119561504971867271087353186, 170024582242541755392979256646565617273, 153964862116746563988492365899737226989,  37163237225742447359704121711857363416, 108165142884901978856319583750672324489,  69476260396969790693146402021744933499,  45955200056324872841369110391855073949, 261286087759526359216271155361018330507, 321756280164272289841871040803703440350, 334905318181122708043147970432770442813],
     [310538827479436149892724250590698914519, 221096166077280180974764042888991644280, 274604860873273636237081114376077113475, 230609671293877243511889006223284127479,  59235259390239124162891762278360245334, 129877116445533126989528570413807277693, 250107916917535224528378129994943394294, 232074846252364869196809445831737773796, 298530663250990395227144225232608384365, 265168486075436613449458788630803272512, 166545598284411242433605578379265360252, 102835474498154050313290986853294842906, 189445283838085809052254029811407633258, 302719082300742526890675313445319567341,  96037481352786813748421760769380383926, 214406010671246827947835794343033790693],
     [230635877078223923040415038811686445073, 293027053537479076557105009345927645442, 118114082982223329826045602989947510129, 185089342855265166563915858025522983409, 300544292992993952360719000252205715076, 284751400376525550861233017183497639371,  62365388436267647533064120634464266870, 243579355010018669877160932197352017974,  93028746118909893246237533845189074002, 161426242690584918941198733450953748769, 208632865147351209340449219082125897333, 185941035889835671403097747661105595079, 182846621472767704751329603405195985261, 162892536327655189685235410342890574896, 101396399019525872501663112616210307683, 191090374127295994022314014407997806335],
-];
\ No newline at end of file
+];
+// TESTS
+// ================================================================================================
+#[cfg(test)]
+mod tests {
+
+    use crate::{ math::field, SPONGE_WIDTH as STATE_WIDTH };
+
+    #[test]
+    fn apply_inv_sbox() {
+        let original = field::rand_vector(STATE_WIDTH);
+        let mut state = original.clone();
+        super::apply_inv_sbox(&mut state);
+        let expected: Vec<u128> = original.iter().map(|&x| field::exp(x, super::INV_ALPHA)).collect();
+        assert_eq!(expected, state);
+
+        // inverse S-box undoes S-box
+        super::apply_sbox(&mut state);
+        assert_eq!(original, state);
+    }
+
+    #[test]
+    fn apply_mds() {
+        let original = field::rand_vector(STATE_WIDTH);
+        let mut state = original.clone();
+        super::apply_mds(&mut state);
+        let expected: Vec<u128> = (0..STATE_WIDTH)
+            .map(|i| (0..STATE_WIDTH).fold(field::ZERO, |acc, j| {
+                field::add(acc, field::mul(super::MDS[i * STATE_WIDTH + j], original[j]))
+            }))
+            .collect();
+        assert_eq!(expected, state);
+
+        // inverse MDS undoes MDS
+        super::apply_inv_mds(&mut state);
+        assert_eq!(original, state);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{ ProofOptions, ProofShape, StarkProof, HashTranscript, stark::{ self, VerifierSetup } };
+
+// TYPES AND INTERFACES
+// ================================================================================================
+
+/// Verifies proofs of execution of a single program generated with the same proof options.
+/// Values which depend only on the shape of the proof (roots of unity, composition degrees, and
+/// constraint evaluators) are computed when the first proof of a given shape is verified, and
+/// are re-used for all subsequent proofs of the same shape. Setups for expected shapes can also
+/// be computed ahead of time via `prepare()` method.
+pub struct Verifier {
+    program_hash    : [u8; 32],
+    options         : ProofOptions,
+    setups          : Vec<(ProofShape, VerifierSetup)>,
+}
+
+// VERIFIER IMPLEMENTATION
+// ================================================================================================
+impl Verifier {
+
+    /// Returns a verifier for proofs of execution of a program with the specified `program_hash`;
+    /// only proofs generated with the specified proof `options` are accepted.
+    pub fn new(program_hash: &[u8; 32], options: &ProofOptions) -> Verifier {
+        return Verifier {
+            program_hash: *program_hash,
+            options     : options.clone(),
+            setups      : Vec::new(),
+        };
+    }
+
+    /// Returns the hash of the program for which the proofs are verified.
+    pub fn program_hash(&self) -> &[u8; 32] {
+        return &self.program_hash;
+    }
+
+    /// Returns the options with which the proofs must be generated.
+    pub fn options(&self) -> &ProofOptions {
+        return &self.options;
+    }
+
+    /// Computes values needed to verify proofs of the specified `shape`, unless they have already
+    /// been computed.
+    pub fn prepare(&mut self, shape: &ProofShape) {
+        if !self.is_prepared(shape) {
+            let setup = VerifierSetup::from_shape(shape, self.options.extension_factor());
+            self.setups.push((*shape, setup));
+        }
+    }
+
+    /// Returns true if values needed to verify proofs of the specified `shape` have already
+    /// been computed.
+    pub fn is_prepared(&self, shape: &ProofShape) -> bool {
+        return self.setups.iter().any(|(prepared, _)| prepared == shape);
+    }
+
+    /// Verifies that if the program is executed with the provided `public_inputs` and some secret
+    /// inputs, the result is equal to the `outputs`; this is the same as calling `verify()`
+    /// function, but the proof is also rejected if it was generated with options different from
+    /// the options of this verifier.
+    pub fn verify(&mut self, public_inputs: &[u128], outputs: &[u128], proof: &StarkProof) -> Result<bool, String> {
+        check_options(&self.options, proof.options())?;
+        let shape = proof.shape();
+        self.prepare(&shape);
+        let (_, setup) = self.setups.iter().find(|(prepared, _)| *prepared == shape).unwrap();
+
+        let mut transcript = HashTranscript::new(self.options.hash_fn());
+        return stark::verify(&self.program_hash, public_inputs, outputs, proof, &mut transcript, setup);
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+fn check_options(expected: &ProofOptions, options: &ProofOptions) -> Result<(), String> {
+    if options.hash_fn() as usize != expected.hash_fn() as usize {
+        return Err(String::from("proof hash function does not match verifier hash function"));
+    }
+
+    if options.num_queries() != expected.num_queries() {
+        return Err(format!("proof uses {} queries, but {} are expected",
+            options.num_queries(), expected.num_queries()));
+    }
+
+    if options.extension_factor() != expected.extension_factor() {
+        return Err(format!("proof uses extension factor {}, but {} is expected",
+            options.extension_factor(), expected.extension_factor()));
+    }
+
+    if options.grinding_factor() != expected.grinding_factor() {
+        return Err(format!("proof uses grinding factor {}, but {} is expected",
+            options.grinding_factor(), expected.grinding_factor()));
+    }
+
+    if options.remainder_length() != expected.remainder_length() {
+        return Err(format!("proof uses FRI remainder length {}, but {} is expected",
+            options.remainder_length(), expected.remainder_length()));
+    }
+
+    if options.domain_offset() != expected.domain_offset() {
+        return Err(format!("proof uses domain offset {}, but {} is expected",
+            options.domain_offset(), expected.domain_offset()));
+    }
+
+    return Ok(());
+}